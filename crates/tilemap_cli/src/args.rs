@@ -0,0 +1,102 @@
+//! 极简参数解析：位置参数 + `--name value` / `--name=value`。
+
+use std::collections::HashMap;
+
+use crate::CliError;
+
+pub struct Args {
+    positional: Vec<String>,
+    options: HashMap<String, String>,
+}
+
+impl Args {
+    /// `value_opts` 为允许的选项名；其余 `--xxx` 一律报错。
+    pub fn parse(raw: impl IntoIterator<Item = String>, value_opts: &[&str]) -> Result<Self, CliError> {
+        let mut out = Args {
+            positional: Vec::new(),
+            options: HashMap::new(),
+        };
+        let mut it = raw.into_iter();
+        while let Some(arg) = it.next() {
+            let Some(name) = arg.strip_prefix("--") else {
+                out.positional.push(arg);
+                continue;
+            };
+            // 支持 `--name=value`。
+            let (name, inline) = match name.split_once('=') {
+                Some((n, v)) => (n, Some(v.to_string())),
+                None => (name, None),
+            };
+            if value_opts.contains(&name) {
+                let value = match inline {
+                    Some(v) => v,
+                    None => it
+                        .next()
+                        .ok_or_else(|| CliError::Usage(format!("选项 --{name} 缺少参数值")))?,
+                };
+                out.options.insert(name.to_string(), value);
+            } else {
+                return Err(CliError::Usage(format!("未知选项: --{name}")));
+            }
+        }
+        Ok(out)
+    }
+
+    /// 要求恰好 `names.len()` 个位置参数。
+    pub fn expect_positional(&self, names: &[&str]) -> Result<Vec<&str>, CliError> {
+        if self.positional.len() != names.len() {
+            let want: Vec<String> = names.iter().map(|n| format!("<{n}>")).collect();
+            return Err(CliError::Usage(format!(
+                "需要 {} 个参数 {}，实际 {} 个",
+                names.len(),
+                want.join(" "),
+                self.positional.len()
+            )));
+        }
+        Ok(self.positional.iter().map(String::as_str).collect())
+    }
+
+    pub fn option(&self, name: &str) -> Option<&str> {
+        self.options.get(name).map(String::as_str)
+    }
+}
+
+/// 解析 `WxH`（或单个数字表示正方形），两边都必须 > 0。
+pub fn parse_size(text: &str, what: &str) -> Result<(u32, u32), CliError> {
+    let bad = || CliError::Usage(format!("{what} 格式应为 WxH 或 N，实际为 {text:?}"));
+    let (w, h) = match text.split_once(['x', 'X']) {
+        Some((w, h)) => (w, h),
+        None => (text, text),
+    };
+    let w: u32 = w.trim().parse().map_err(|_| bad())?;
+    let h: u32 = h.trim().parse().map_err(|_| bad())?;
+    if w == 0 || h == 0 {
+        return Err(bad());
+    }
+    Ok((w, h))
+}
+
+/// 解析逗号分隔的图层下标列表。
+pub fn parse_layers(text: &str) -> Result<Vec<u32>, CliError> {
+    text.split(',')
+        .map(|s| {
+            s.trim()
+                .parse::<u32>()
+                .map_err(|_| CliError::Usage(format!("图层列表应为逗号分隔的数字，实际为 {text:?}")))
+        })
+        .collect()
+}
+
+/// 解析十六进制颜色 `RRGGBB` / `RRGGBBAA`（可带 `#` 前缀）。
+pub fn parse_color(text: &str) -> Result<image::Rgba<u8>, CliError> {
+    let bad = || CliError::Usage(format!("颜色格式应为 RRGGBB 或 RRGGBBAA，实际为 {text:?}"));
+    let hex = text.trim().trim_start_matches('#');
+    if !(hex.len() == 6 || hex.len() == 8) || !hex.is_ascii() {
+        return Err(bad());
+    }
+    let mut rgba = [0, 0, 0, 255];
+    for (i, c) in rgba.iter_mut().enumerate().take(hex.len() / 2) {
+        *c = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).map_err(|_| bad())?;
+    }
+    Ok(image::Rgba(rgba))
+}
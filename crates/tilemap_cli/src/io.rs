@@ -0,0 +1,245 @@
+//! 地图文件读写（按扩展名识别格式）与 tileset 图片定位。
+
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+use tilemap_core::{TileGrid, TileMapData};
+use tilemap_format::{
+    decode_map_binary, decode_map_ron, decode_map_tiled_json, encode_map_binary,
+    encode_map_ron_v3, encode_map_tiled_json, TiledTilesetInfo, TilesetEntry,
+};
+use tilemap_render::Atlas;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MapFormat {
+    Ron,
+    TiledJson,
+    Binary,
+}
+
+impl MapFormat {
+    pub fn name(self) -> &'static str {
+        match self {
+            MapFormat::Ron => "ron",
+            MapFormat::TiledJson => "tiled",
+            MapFormat::Binary => "bin",
+        }
+    }
+
+    /// `--format` 的取值。
+    pub fn parse(text: &str) -> Result<Self, String> {
+        match text.to_ascii_lowercase().as_str() {
+            "ron" => Ok(MapFormat::Ron),
+            "tiled" | "json" | "tmj" => Ok(MapFormat::TiledJson),
+            "bin" | "binary" | "tmb" => Ok(MapFormat::Binary),
+            _ => Err(format!("未知格式 {text:?}（可选 ron / tiled / bin）")),
+        }
+    }
+
+    /// 按扩展名识别：`.ron`、`.json`/`.tmj`、`.tmb`/`.bin`。
+    pub fn from_path(path: &Path) -> Result<Self, String> {
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match ext.as_str() {
+            "ron" => Ok(MapFormat::Ron),
+            "json" | "tmj" => Ok(MapFormat::TiledJson),
+            "tmb" | "bin" => Ok(MapFormat::Binary),
+            _ => Err(format!(
+                "无法从扩展名识别 {} 的格式，请用 --format 指定",
+                path.display()
+            )),
+        }
+    }
+}
+
+/// 读入的地图 + 文件里记录的 tileset 列表。
+pub struct MapFile {
+    pub map: TileMapData,
+    pub tilesets: Vec<TilesetEntry>,
+    pub format: MapFormat,
+}
+
+impl MapFile {
+    /// 补齐地图用到、但列表里没有的 tileset（占位条目，asset_path 为空），与编辑器保存时一致。
+    pub fn ensure_tileset_entries(&mut self) {
+        let mut used: Vec<String> = Vec::new();
+        for (_, _, _, t) in self.map.iter_tiles() {
+            if !t.tileset_id.is_empty() && !used.contains(t.tileset_id) {
+                used.push(t.tileset_id.clone());
+            }
+        }
+        used.sort();
+        for id in used {
+            if self.tilesets.iter().any(|t| t.id == id) {
+                continue;
+            }
+            self.tilesets.push(TilesetEntry {
+                id: id.clone(),
+                name: id,
+                category: "default".to_string(),
+                asset_path: String::new(),
+                ..Default::default()
+            });
+        }
+    }
+}
+
+pub fn load_map(path: &Path, format: Option<MapFormat>) -> Result<MapFile, String> {
+    let format = match format {
+        Some(f) => f,
+        None => MapFormat::from_path(path)?,
+    };
+    let read_err = |e: std::io::Error| format!("读取 {} 失败: {e}", path.display());
+    let parse_err = |e: String| format!("解析 {} 失败（{}）: {e}", path.display(), format.name());
+    let (map, tilesets) = match format {
+        MapFormat::Ron => {
+            let text = std::fs::read_to_string(path).map_err(read_err)?;
+            decode_map_ron::<TilesetEntry>(&text).map_err(parse_err)?
+        }
+        MapFormat::TiledJson => {
+            let text = std::fs::read_to_string(path).map_err(read_err)?;
+            decode_map_tiled_json(&text).map_err(parse_err)?
+        }
+        MapFormat::Binary => {
+            let bytes = std::fs::read(path).map_err(read_err)?;
+            decode_map_binary(&bytes).map_err(parse_err)?
+        }
+    };
+    Ok(MapFile { map, tilesets, format })
+}
+
+pub fn save_map(
+    path: &Path,
+    format: MapFormat,
+    file: &MapFile,
+    assets: &AssetContext,
+) -> Result<(), String> {
+    let bytes = match format {
+        MapFormat::Ron => {
+            let ids = file.tilesets.iter().map(|t| t.id.clone()).collect();
+            encode_map_ron_v3(&file.map, file.tilesets.clone(), ids)?.into_bytes()
+        }
+        MapFormat::Binary => encode_map_binary(&file.map, &file.tilesets)?,
+        MapFormat::TiledJson => {
+            let out_dir = path.parent().unwrap_or(Path::new("."));
+            let infos: Vec<TiledTilesetInfo> = file
+                .tilesets
+                .iter()
+                .map(|entry| {
+                    let image_path = assets.resolve(entry);
+                    let image_size = image_path
+                        .as_deref()
+                        .and_then(|p| image::image_dimensions(p).ok());
+                    let image = match &image_path {
+                        Some(p) => relative_to(out_dir, p),
+                        None => entry.asset_path.clone(),
+                    };
+                    TiledTilesetInfo {
+                        entry: entry.clone(),
+                        image,
+                        image_size,
+                    }
+                })
+                .collect();
+            encode_map_tiled_json(&file.map, assets.tile_size, &infos)?.into_bytes()
+        }
+    };
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).map_err(|e| format!("创建 {} 失败: {e}", parent.display()))?;
+    }
+    std::fs::write(path, bytes).map_err(|e| format!("写入 {} 失败: {e}", path.display()))
+}
+
+/// tileset 图片所在的 assets 根目录与网格尺寸（地图文件本身不记录 tile 尺寸）。
+pub struct AssetContext {
+    pub root: PathBuf,
+    pub tile_size: (u32, u32),
+}
+
+impl AssetContext {
+    /// tileset 图片的实际路径；占位条目（asset_path 为空）返回 None。
+    pub fn resolve(&self, entry: &TilesetEntry) -> Option<PathBuf> {
+        if entry.asset_path.is_empty() {
+            return None;
+        }
+        Some(self.root.join(&entry.asset_path))
+    }
+
+    /// 图集切分规则：条目未设置 tile 尺寸时使用 `--tile-size`。
+    pub fn grid(&self, entry: &TilesetEntry) -> TileGrid {
+        entry.grid(self.tile_size)
+    }
+
+    pub fn load_atlas(&self, entry: &TilesetEntry) -> Result<Atlas, String> {
+        let Some(path) = self.resolve(entry) else {
+            return Err(format!("tileset {} 没有 asset_path（占位条目）", entry.id));
+        };
+        if !path.exists() {
+            return Err(format!("tileset {} 的图片不存在: {}", entry.id, path.display()));
+        }
+        image::open(&path)
+            .map(|img| Atlas {
+                image: img.to_rgba8(),
+                grid: self.grid(entry),
+            })
+            .map_err(|e| format!("tileset {} 的图片 {} 无法读取: {e}", entry.id, path.display()))
+    }
+}
+
+/// 按 tileset_id 缓存已加载的图集（加载失败也缓存，避免重复报错）。
+pub struct AtlasCache<'a> {
+    assets: &'a AssetContext,
+    by_id: HashMap<String, Result<Atlas, String>>,
+}
+
+impl<'a> AtlasCache<'a> {
+    pub fn new(assets: &'a AssetContext) -> Self {
+        Self {
+            assets,
+            by_id: HashMap::new(),
+        }
+    }
+
+    pub fn get(&mut self, tilesets: &[TilesetEntry], id: &str) -> &Result<Atlas, String> {
+        let assets = self.assets;
+        self.by_id.entry(id.to_string()).or_insert_with(|| {
+            match tilesets.iter().find(|t| t.id == id) {
+                Some(entry) => assets.load_atlas(entry),
+                None => Err(format!("地图引用的 tileset {id} 不在 tileset 列表中")),
+            }
+        })
+    }
+}
+
+/// 生成从 `from_dir` 指向 `to` 的相对路径（`/` 分隔，供 Tiled 解析）；无法计算时返回绝对路径。
+fn relative_to(from_dir: &Path, to: &Path) -> String {
+    let (Ok(from), Ok(to_abs)) = (std::path::absolute(from_dir), std::path::absolute(to)) else {
+        return to.to_string_lossy().replace('\\', "/");
+    };
+    let normalize = |p: &Path| -> Vec<String> {
+        let mut out: Vec<String> = Vec::new();
+        for c in p.components() {
+            match c {
+                Component::ParentDir => {
+                    out.pop();
+                }
+                Component::CurDir => {}
+                other => out.push(other.as_os_str().to_string_lossy().into_owned()),
+            }
+        }
+        out
+    };
+    let from = normalize(&from);
+    let to_parts = normalize(&to_abs);
+    // 不同盘符/根：只能给绝对路径。
+    if from.first() != to_parts.first() {
+        return to_abs.to_string_lossy().replace('\\', "/");
+    }
+    let common = from.iter().zip(&to_parts).take_while(|(a, b)| a == b).count();
+    let mut parts: Vec<&str> = vec![".."; from.len() - common];
+    parts.extend(to_parts[common..].iter().map(String::as_str));
+    parts.join("/")
+}
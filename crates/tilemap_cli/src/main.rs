@@ -0,0 +1,390 @@
+//! tilemap-cli：无界面的地图批处理工具（构建脚本用）。
+//!
+//! 子命令：info / convert / validate / resize / merge-layers / render / colliders / bake-a2。
+//! 退出码：0 成功；1 输入有误或检查未通过；2 命令行用法错误。
+
+#![forbid(unsafe_code)]
+
+mod args;
+mod io;
+mod validate;
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use tilemap_core::{ops, ColliderMode, TileGrid};
+use tilemap_format::{encode_colliders_json, map_colliders};
+use tilemap_render::{bake_rm_a2_autotile, render_map, Atlas, RenderOptions};
+
+use args::{parse_color, parse_layers, parse_size, Args};
+use io::{load_map, save_map, AssetContext, AtlasCache, MapFile, MapFormat};
+
+const USAGE: &str = "\
+用法: tilemap-cli <命令> [参数] [选项]
+
+命令:
+  info <map>                        打印尺寸、图层、tileset 与格子统计
+  convert <in> <out>                格式互转（RON / Tiled JSON / 二进制）
+  validate <map>                    检查 tileset 图片与 hash、tile index、图层元数据
+  resize <in> <out> --size WxH      改为固定尺寸（裁掉越界格子）
+  merge-layers <in> <out>           把多层合并为一层（上层覆盖下层）
+  render <map> <out.png>            渲染为 PNG
+  colliders <map> <out.json>        按 tile 碰撞形状生成合并后的碰撞体（JSON，单位为格子）
+  bake-a2 <a2.png> <out.png>        把 RPG Maker A2 自动图块块烘焙成 47 块 blob 图集（8 列）
+
+选项:
+  --format <ron|tiled|bin>          输出格式（默认按扩展名：.ron / .json .tmj / .tmb .bin）
+  --input-format <ron|tiled|bin>    输入格式（默认按扩展名）
+  --assets <dir>                    tileset 图片的根目录（默认 ./assets）
+  --tile-size <WxH>                 网格尺寸，像素（默认 32x32；tileset 未设置 tile 尺寸时也用它切图）
+  --layers <i,j,..>                 merge-layers 要合并 / colliders 要参与的图层（默认全部）
+  --mode <rects|outlines>           colliders 整格实心的合并方式：矩形（默认）或轮廓
+  --scale <倍数>                    render 输出缩放（默认 1；小于 1 生成缩略图）
+  --grid <RRGGBB[AA]>               render 叠加网格线（十六进制颜色）
+  --block <n>                       bake-a2 的 2x3 块左上角 tile 序号（默认 0；切图尺寸取 --tile-size）
+";
+
+/// 命令失败的两类原因：用法错误（退出码 2）与执行失败（退出码 1）。
+pub enum CliError {
+    Usage(String),
+    Failed(String),
+}
+
+impl From<String> for CliError {
+    fn from(e: String) -> Self {
+        CliError::Failed(e)
+    }
+}
+
+const COMMON_OPTS: &[&str] = &["input-format", "assets", "tile-size"];
+
+fn main() -> ExitCode {
+    let mut raw = std::env::args().skip(1);
+    let Some(command) = raw.next() else {
+        eprint!("{USAGE}");
+        return ExitCode::from(2);
+    };
+    let result = match command.as_str() {
+        "info" => cmd_info(raw),
+        "convert" => cmd_convert(raw),
+        "validate" => cmd_validate(raw),
+        "resize" => cmd_resize(raw),
+        "merge-layers" => cmd_merge_layers(raw),
+        "render" => cmd_render(raw),
+        "colliders" => cmd_colliders(raw),
+        "bake-a2" => cmd_bake_a2(raw),
+        "help" | "-h" | "--help" => {
+            print!("{USAGE}");
+            Ok(())
+        }
+        other => Err(CliError::Usage(format!("未知命令: {other}"))),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(CliError::Usage(msg)) => {
+            eprintln!("错误: {msg}\n");
+            eprint!("{USAGE}");
+            ExitCode::from(2)
+        }
+        Err(CliError::Failed(msg)) => {
+            eprintln!("错误: {msg}");
+            ExitCode::from(1)
+        }
+    }
+}
+
+fn opts(extra: &[&'static str]) -> Vec<&'static str> {
+    COMMON_OPTS.iter().chain(extra).copied().collect()
+}
+
+fn asset_context(args: &Args) -> Result<AssetContext, CliError> {
+    let tile_size = match args.option("tile-size") {
+        Some(s) => parse_size(s, "--tile-size")?,
+        None => (32, 32),
+    };
+    Ok(AssetContext {
+        root: PathBuf::from(args.option("assets").unwrap_or("assets")),
+        tile_size,
+    })
+}
+
+fn format_opt(args: &Args, name: &str) -> Result<Option<MapFormat>, CliError> {
+    args.option(name)
+        .map(|s| MapFormat::parse(s).map_err(CliError::Usage))
+        .transpose()
+}
+
+fn load_input(args: &Args, path: &str) -> Result<MapFile, CliError> {
+    let format = format_opt(args, "input-format")?;
+    Ok(load_map(Path::new(path), format)?)
+}
+
+fn save_output(args: &Args, path: &str, file: &mut MapFile) -> Result<MapFormat, CliError> {
+    let path = Path::new(path);
+    let format = match format_opt(args, "format")? {
+        Some(f) => f,
+        None => MapFormat::from_path(path).map_err(CliError::Usage)?,
+    };
+    file.ensure_tileset_entries();
+    save_map(path, format, file, &asset_context(args)?)?;
+    Ok(format)
+}
+
+fn cmd_info(raw: impl Iterator<Item = String>) -> Result<(), CliError> {
+    let args = Args::parse(raw, &opts(&[]))?;
+    let [path] = args.expect_positional(&["map"])?[..] else {
+        unreachable!()
+    };
+    let file = load_input(&args, path)?;
+    let map = &file.map;
+
+    println!("文件: {path}（{}）", file.format.name());
+    match map.fixed_size() {
+        Some((w, h)) => println!("尺寸: {w}x{h}"),
+        None => match map.used_bounds() {
+            Some(b) => println!(
+                "尺寸: 无限（已用范围 x {}..={}，y {}..={}）",
+                b.min_x, b.max_x, b.min_y, b.max_y
+            ),
+            None => println!("尺寸: 无限（空）"),
+        },
+    }
+
+    let mut per_layer = vec![0usize; map.layers as usize];
+    let mut per_tileset: Vec<usize> = vec![0; file.tilesets.len()];
+    let mut unlisted = 0usize;
+    let mut total = 0usize;
+    for (layer, _, _, tile) in map.iter_tiles() {
+        total += 1;
+        if let Some(n) = per_layer.get_mut(layer as usize) {
+            *n += 1;
+        }
+        match file.tilesets.iter().position(|t| &t.id == tile.tileset_id) {
+            Some(i) => per_tileset[i] += 1,
+            None => unlisted += 1,
+        }
+    }
+
+    println!("图层: {}", map.layers);
+    for (i, count) in per_layer.iter().enumerate() {
+        let meta = map.layer_data.get(i).cloned().unwrap_or_default();
+        let mut flags = String::new();
+        if !meta.visible {
+            flags.push_str(" [隐藏]");
+        }
+        if meta.locked {
+            flags.push_str(" [锁定]");
+        }
+        println!("  [{i}] {}  格子 {count}{flags}", meta.name);
+    }
+
+    println!("tileset: {}", file.tilesets.len());
+    for (t, count) in file.tilesets.iter().zip(&per_tileset) {
+        let path = if t.asset_path.is_empty() { "<缺少 asset_path>" } else { &t.asset_path };
+        println!("  {}  {}  {path}  格子 {count}", t.id, t.name);
+    }
+    if unlisted > 0 {
+        println!("  （另有 {unlisted} 个格子引用了不在列表中的 tileset）");
+    }
+    println!("格子总数: {total}");
+    Ok(())
+}
+
+fn cmd_convert(raw: impl Iterator<Item = String>) -> Result<(), CliError> {
+    let args = Args::parse(raw, &opts(&["format"]))?;
+    let [input, output] = args.expect_positional(&["in", "out"])?[..] else {
+        unreachable!()
+    };
+    let mut file = load_input(&args, input)?;
+    let from = file.format;
+    let to = save_output(&args, output, &mut file)?;
+    println!("{input}（{}）-> {output}（{}）", from.name(), to.name());
+    Ok(())
+}
+
+fn cmd_validate(raw: impl Iterator<Item = String>) -> Result<(), CliError> {
+    let args = Args::parse(raw, &opts(&[]))?;
+    let [path] = args.expect_positional(&["map"])?[..] else {
+        unreachable!()
+    };
+    let file = load_input(&args, path)?;
+    let problems = validate::validate_map(&file.map, &file.tilesets, &asset_context(&args)?);
+    if problems.is_empty() {
+        println!("{path}: 检查通过");
+        return Ok(());
+    }
+    for p in &problems {
+        println!("{path}: {p}");
+    }
+    Err(CliError::Failed(format!("{path}: 发现 {} 个问题", problems.len())))
+}
+
+fn cmd_resize(raw: impl Iterator<Item = String>) -> Result<(), CliError> {
+    let args = Args::parse(raw, &opts(&["format", "size"]))?;
+    let [input, output] = args.expect_positional(&["in", "out"])?[..] else {
+        unreachable!()
+    };
+    let Some(size) = args.option("size") else {
+        return Err(CliError::Usage("resize 需要 --size WxH".to_string()));
+    };
+    let (w, h) = parse_size(size, "--size")?;
+    let mut file = load_input(&args, input)?;
+    let (cropped, cropped_meta) = ops::resize(&mut file.map, w, h);
+    save_output(&args, output, &mut file)?;
+    println!(
+        "已改为 {w}x{h}，裁掉 {} 个格子、{} 个格子的元数据",
+        cropped.len(),
+        cropped_meta.len()
+    );
+    Ok(())
+}
+
+fn cmd_merge_layers(raw: impl Iterator<Item = String>) -> Result<(), CliError> {
+    let args = Args::parse(raw, &opts(&["format", "layers"]))?;
+    let [input, output] = args.expect_positional(&["in", "out"])?[..] else {
+        unreachable!()
+    };
+    let mut file = load_input(&args, input)?;
+    let layers = match args.option("layers") {
+        Some(s) => parse_layers(s)?,
+        None => (0..file.map.layers).collect(),
+    };
+    if let Some(&bad) = layers.iter().find(|&&l| l >= file.map.layers) {
+        return Err(CliError::Failed(format!(
+            "图层 {bad} 不存在（地图共 {} 层）",
+            file.map.layers
+        )));
+    }
+    let mut sorted = layers.clone();
+    sorted.sort_unstable();
+    sorted.dedup();
+    if sorted.len() < 2 {
+        return Err(CliError::Usage("至少需要两个不同的图层才能合并".to_string()));
+    }
+
+    let changes = ops::merge_layers(&mut file.map, &sorted);
+    // 被合并掉的图层已清空，从上往下删除，保持下标有效。
+    for &l in sorted[1..].iter().rev() {
+        file.map.remove_layer(l);
+    }
+    save_output(&args, output, &mut file)?;
+    println!(
+        "已把图层 {:?} 合并到图层 {}（改动 {} 个格子），剩余 {} 层",
+        sorted,
+        sorted[0],
+        changes.len(),
+        file.map.layers
+    );
+    Ok(())
+}
+
+fn cmd_render(raw: impl Iterator<Item = String>) -> Result<(), CliError> {
+    let args = Args::parse(raw, &opts(&["scale", "grid"]))?;
+    let [input, output] = args.expect_positional(&["map", "out.png"])?[..] else {
+        unreachable!()
+    };
+    let scale = match args.option("scale") {
+        Some(s) => match s.trim().parse::<f32>() {
+            Ok(v) if v.is_finite() && v > 0.0 => v,
+            _ => return Err(CliError::Usage(format!("--scale 应为大于 0 的数字，实际为 {s:?}"))),
+        },
+        None => 1.0,
+    };
+    let grid = args.option("grid").map(parse_color).transpose()?;
+    let file = load_input(&args, input)?;
+    let assets = asset_context(&args)?;
+
+    // 只加载地图实际用到的图集；读取失败的在这里报告，渲染时留空。
+    let used: BTreeSet<&str> = file.map.iter_tiles().map(|(_, _, _, t)| t.tileset_id.as_str()).collect();
+    let mut cache = AtlasCache::new(&assets);
+    let mut atlases = HashMap::new();
+    for id in used {
+        match cache.get(&file.tilesets, id) {
+            Ok(atlas) => {
+                atlases.insert(id.to_string(), atlas.clone());
+            }
+            Err(e) => eprintln!("警告: {e}"),
+        }
+    }
+    let opts = RenderOptions {
+        tile_size: assets.tile_size,
+        scale,
+        grid,
+    };
+    let rendered = render_map(&file.map, &atlases, &opts)?;
+    for w in &rendered.warnings {
+        eprintln!("警告: {w}");
+    }
+    rendered
+        .image
+        .save_with_format(output, image::ImageFormat::Png)
+        .map_err(|e| format!("写入 {output} 失败: {e}"))?;
+    println!(
+        "已渲染 {}x{} 像素 -> {output}",
+        rendered.image.width(),
+        rendered.image.height()
+    );
+    Ok(())
+}
+
+fn cmd_colliders(raw: impl Iterator<Item = String>) -> Result<(), CliError> {
+    let args = Args::parse(raw, &opts(&["layers", "mode"]))?;
+    let [input, output] = args.expect_positional(&["map", "out.json"])?[..] else {
+        unreachable!()
+    };
+    let mode = match args.option("mode") {
+        None | Some("rects") => ColliderMode::Rects,
+        Some("outlines") => ColliderMode::Outlines,
+        Some(other) => return Err(CliError::Usage(format!("--mode 应为 rects 或 outlines，实际为 {other:?}"))),
+    };
+    let file = load_input(&args, input)?;
+    let layers = match args.option("layers") {
+        Some(s) => parse_layers(s)?,
+        None => (0..file.map.layers).collect(),
+    };
+    if let Some(&bad) = layers.iter().find(|&&l| l >= file.map.layers) {
+        return Err(CliError::Failed(format!(
+            "图层 {bad} 不存在（地图共 {} 层）",
+            file.map.layers
+        )));
+    }
+    let colliders = map_colliders(&file.map, &file.tilesets, &layers, mode);
+    if colliders.is_empty() {
+        eprintln!("警告: 所选图层没有带碰撞形状的 tile");
+    }
+    let text = encode_colliders_json(&colliders, mode, asset_context(&args)?.tile_size)?;
+    std::fs::write(output, text).map_err(|e| format!("写入 {output} 失败: {e}"))?;
+    println!(
+        "已生成 {} 个矩形、{} 个轮廓、{} 个多边形 -> {output}",
+        colliders.rects.len(),
+        colliders.outlines.len(),
+        colliders.polygons.len()
+    );
+    Ok(())
+}
+
+fn cmd_bake_a2(raw: impl Iterator<Item = String>) -> Result<(), CliError> {
+    let args = Args::parse(raw, &opts(&["block"]))?;
+    let [input, output] = args.expect_positional(&["a2.png", "out.png"])?[..] else {
+        unreachable!()
+    };
+    let block = match args.option("block") {
+        Some(s) => s
+            .trim()
+            .parse::<u32>()
+            .map_err(|_| CliError::Usage(format!("--block 应为非负整数，实际为 {s:?}")))?,
+        None => 0,
+    };
+    let (tw, th) = asset_context(&args)?.tile_size;
+    let image = image::open(input)
+        .map_err(|e| format!("读取 {input} 失败: {e}"))?
+        .to_rgba8();
+    let atlas = Atlas { image, grid: TileGrid::new(tw, th) };
+    let sheet = bake_rm_a2_autotile(&atlas, block)?;
+    sheet
+        .save_with_format(output, image::ImageFormat::Png)
+        .map_err(|e| format!("写入 {output} 失败: {e}"))?;
+    println!("已烘焙 47 块自动图块（{tw}x{th}）-> {output}");
+    Ok(())
+}
@@ -0,0 +1,47 @@
+//! `validate` 子命令：用 tilemap_format 的检查逻辑，图片状态直接读文件得到。
+
+use std::io::Cursor;
+
+use tilemap_core::TileMapData;
+use tilemap_format::{validate_map as check_map, AssetProbe, TilesetEntry};
+
+use crate::io::AssetContext;
+
+/// 返回发现的问题（空 = 通过）。
+pub fn validate_map(
+    map: &TileMapData,
+    tilesets: &[TilesetEntry],
+    assets: &AssetContext,
+) -> Vec<String> {
+    check_map(map, tilesets, |entry| probe(assets, entry))
+        .iter()
+        .map(ToString::to_string)
+        .collect()
+}
+
+fn probe(assets: &AssetContext, entry: &TilesetEntry) -> AssetProbe {
+    let Some(path) = assets.resolve(entry) else {
+        return AssetProbe::Missing { path: String::new() };
+    };
+    let shown = path.display().to_string();
+    if !path.exists() {
+        return AssetProbe::Missing { path: shown };
+    }
+    let unreadable = |error: String| AssetProbe::Unreadable { path: shown.clone(), error };
+    let bytes = match std::fs::read(&path) {
+        Ok(b) => b,
+        Err(e) => return unreadable(e.to_string()),
+    };
+    let dims = image::ImageReader::new(Cursor::new(&bytes))
+        .with_guessed_format()
+        .map_err(|e| e.to_string())
+        .and_then(|r| r.into_dimensions().map_err(|e| e.to_string()));
+    let (w, h) = match dims {
+        Ok(d) => d,
+        Err(e) => return unreadable(e),
+    };
+    AssetProbe::Ready {
+        capacity: assets.grid(entry).capacity(w, h),
+        hash: Some(blake3::hash(&bytes).to_hex().to_string()),
+    }
+}
@@ -0,0 +1,110 @@
+//! 以子进程方式运行 tilemap-cli，检查输出文件与退出码。
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+use tilemap_core::{TileMapData, TileRef};
+use tilemap_format::{decode_map_ron, encode_map_ron_v3, TilesetEntry};
+
+fn cli(args: &[&str], dir: &Path) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_tilemap-cli"))
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .expect("启动 tilemap-cli 失败")
+}
+
+/// 每个测试一个独立的工作目录：`assets/tiles.png`（两块 32x32）+ `map.ron`（4x3，两层）。
+fn workspace(name: &str) -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("assets")).unwrap();
+    let atlas = image::RgbaImage::from_fn(64, 32, |x, _| {
+        if x < 32 { image::Rgba([255, 0, 0, 255]) } else { image::Rgba([0, 0, 255, 255]) }
+    });
+    atlas.save(dir.join("assets/tiles.png")).unwrap();
+
+    let mut map = TileMapData::new_with_layers(4, 3, 2);
+    let tile = |index| Some(TileRef { tileset_id: "tiles".to_string(), index, rot: 0, flip_x: false, flip_y: false });
+    map.set(0, 0, 0, tile(0));
+    map.set(0, 3, 2, tile(1));
+    map.set(1, 1, 1, tile(1));
+    let entry = TilesetEntry {
+        id: "tiles".to_string(),
+        name: "tiles".to_string(),
+        category: "default".to_string(),
+        asset_path: "tiles.png".to_string(),
+        ..Default::default()
+    };
+    let text = encode_map_ron_v3(&map, vec![entry], vec!["tiles".to_string()]).unwrap();
+    std::fs::write(dir.join("map.ron"), text).unwrap();
+    dir
+}
+
+fn read_ron(path: &Path) -> TileMapData {
+    let text = std::fs::read_to_string(path).unwrap();
+    decode_map_ron::<TilesetEntry>(&text).unwrap().0
+}
+
+#[test]
+fn convert_round_trips_through_binary_and_tiled_json() {
+    let dir = workspace("convert");
+    for (out, back) in [("map.tmb", "from_bin.ron"), ("map.tmj", "from_tiled.ron")] {
+        let status = cli(&["convert", "map.ron", out], &dir).status;
+        assert!(status.success(), "convert map.ron {out}: {status}");
+        let status = cli(&["convert", out, back], &dir).status;
+        assert!(status.success(), "convert {out} {back}: {status}");
+
+        let original = read_ron(&dir.join("map.ron"));
+        let restored = read_ron(&dir.join(back));
+        assert_eq!(restored.fixed_size(), Some((4, 3)));
+        assert_eq!(restored.layers, 2);
+        let tiles = |m: &TileMapData| -> Vec<_> {
+            m.iter_tiles().map(|(l, x, y, t)| (l, x, y, t.tileset_id.clone(), t.index)).collect()
+        };
+        assert_eq!(tiles(&restored), tiles(&original), "{out}");
+    }
+
+    // --format 覆盖扩展名推断。
+    assert!(cli(&["convert", "map.ron", "map.out", "--format", "bin"], &dir).status.success());
+    let bytes = std::fs::read(dir.join("map.out")).unwrap();
+    assert!(bytes.starts_with(&tilemap_format::BINARY_MAGIC));
+}
+
+#[test]
+fn render_writes_a_png_of_the_map_size() {
+    let dir = workspace("render");
+    let out = cli(&["render", "map.ron", "map.png"], &dir);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let image = image::open(dir.join("map.png")).unwrap().to_rgba8();
+    assert_eq!(image.dimensions(), (4 * 32, 3 * 32));
+
+    let out = cli(&["render", "map.ron", "thumb.png", "--scale", "0.25"], &dir);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert_eq!(image::image_dimensions(dir.join("thumb.png")).unwrap(), (32, 24));
+}
+
+#[test]
+fn bad_input_exits_with_1_and_bad_usage_with_2() {
+    let dir = workspace("errors");
+    std::fs::write(dir.join("broken.ron"), "not a map").unwrap();
+
+    // 执行失败：文件不存在 / 内容解析失败。
+    for args in [&["convert", "missing.ron", "out.ron"][..], &["info", "broken.ron"], &["render", "broken.ron", "x.png"]] {
+        let out = cli(args, &dir);
+        assert_eq!(out.status.code(), Some(1), "{args:?}");
+        assert!(String::from_utf8_lossy(&out.stderr).starts_with("错误: "), "{args:?}");
+    }
+
+    // 用法错误：未知命令、缺少参数、无法识别的格式、非法选项值。
+    for args in [
+        &[][..],
+        &["frobnicate"],
+        &["convert", "map.ron"],
+        &["convert", "map.ron", "map.xyz"],
+        &["render", "map.ron", "x.png", "--scale", "0"],
+    ] {
+        assert_eq!(cli(args, &dir).status.code(), Some(2), "{args:?}");
+    }
+    assert!(!dir.join("map.xyz").exists());
+}
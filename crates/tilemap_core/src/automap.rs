@@ -0,0 +1,371 @@
+//! 规则自动映射（Tiled Automapping 子集）。
+//!
+//! 规则本身是小地图，图层按名字区分用途（`<层名>` 指目标地图中同名的图层）：
+//! - `input_<层名>`：该位置必须是这些 tile 之一（同名的多个 input 层互为“或”）；
+//! - `inputnot_<层名>`：该位置不能是这些 tile；
+//! - `output_<层名>`：匹配后写入的 tile（规则中空着的格子不改动）。
+//!
+//! 特殊 tile 使用 tileset id [`AUTOMAP_TILESET`]，index 与 Tiled 的 Automapping Rules tileset 一致：
+//! 空 / 忽略 / 非空 / 其它（非空且不是本规则该层输入中出现过的 tile）。输出中的“空”表示清除。
+//!
+//! 规则按顺序执行：同一条规则先在当前地图上找出全部匹配再统一写入，后面的规则能看到前面规则的输出。
+
+use std::collections::{HashMap, HashSet};
+
+use crate::ops::{ChangeSet, Recorder};
+use crate::{TileBounds, TileMapData, TileRef, TileView};
+
+/// 规则中特殊 tile 所属的 tileset id。
+pub const AUTOMAP_TILESET: &str = "automap";
+pub const AUTOMAP_EMPTY: u32 = 0;
+pub const AUTOMAP_IGNORE: u32 = 1;
+pub const AUTOMAP_NON_EMPTY: u32 = 2;
+pub const AUTOMAP_OTHER: u32 = 3;
+
+const INPUT: &str = "input_";
+const INPUT_NOT: &str = "inputnot_";
+const OUTPUT: &str = "output_";
+
+/// 一条规则：输入 / 输出图案所在的小地图（固定尺寸，图层名见模块说明）。
+#[derive(Clone, Debug)]
+pub struct AutomapRule {
+    pub pattern: TileMapData,
+}
+
+impl AutomapRule {
+    pub fn new(pattern: TileMapData) -> Self {
+        Self { pattern }
+    }
+
+    /// 把一张规则地图按 4 邻接连通的非空区域（所有规则图层合并计算）拆成多条规则，
+    /// 按从上到下、从左到右的顺序排列。
+    pub fn split(rules: &TileMapData) -> Vec<AutomapRule> {
+        let rule_layers: Vec<u32> = (0..rules.layers)
+            .filter(|&l| rules.layer_data.get(l as usize).is_some_and(|d| layer_role(&d.name).is_some()))
+            .collect();
+        let used: HashSet<(i32, i32)> = rules
+            .iter_cells()
+            .filter(|(l, ..)| rule_layers.contains(l))
+            .map(|(_, x, y, _)| (x, y))
+            .collect();
+
+        let mut seen = HashSet::new();
+        let mut parts: Vec<(TileBounds, Vec<(i32, i32)>)> = Vec::new();
+        let mut starts: Vec<(i32, i32)> = used.iter().copied().collect();
+        starts.sort_unstable_by_key(|&(x, y)| (-y, x));
+        for start in starts {
+            if !seen.insert(start) {
+                continue;
+            }
+            let mut bounds = TileBounds::from_corners(start.0, start.1, start.0, start.1);
+            let mut cells = vec![start];
+            let mut i = 0;
+            while i < cells.len() {
+                let (x, y) = cells[i];
+                i += 1;
+                for n in [(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)] {
+                    if used.contains(&n) && seen.insert(n) {
+                        bounds.include(n.0, n.1);
+                        cells.push(n);
+                    }
+                }
+            }
+            parts.push((bounds, cells));
+        }
+        parts.sort_by_key(|(b, _)| (-b.max_y, b.min_x));
+
+        parts
+            .into_iter()
+            .map(|(b, cells)| {
+                let mut pattern = TileMapData::new_with_layers(b.width(), b.height(), rules.layers);
+                pattern.layer_data = rules.layer_data.clone();
+                for &layer in &rule_layers {
+                    for &(x, y) in &cells {
+                        let tile = rules.get(layer, x, y);
+                        if tile.is_some() {
+                            pattern.set(layer, x - b.min_x, y - b.min_y, tile);
+                        }
+                    }
+                }
+                AutomapRule { pattern }
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Role {
+    Input,
+    InputNot,
+    Output,
+}
+
+/// 规则图层名 -> (用途, 目标层名)。
+fn layer_role(name: &str) -> Option<(Role, &str)> {
+    // `inputnot_` 也以 `input` 开头，先判断它。
+    [(INPUT_NOT, Role::InputNot), (INPUT, Role::Input), (OUTPUT, Role::Output)]
+        .into_iter()
+        .find_map(|(prefix, role)| name.strip_prefix(prefix).map(|target| (role, target)))
+        .filter(|(_, target)| !target.is_empty())
+}
+
+#[derive(Clone, PartialEq)]
+enum Cond {
+    Tile(TileRef),
+    Empty,
+    Ignore,
+    NonEmpty,
+    Other,
+}
+
+impl Cond {
+    fn from_tile(tile: TileRef) -> Self {
+        if tile.tileset_id != AUTOMAP_TILESET {
+            return Cond::Tile(tile);
+        }
+        match tile.index {
+            AUTOMAP_EMPTY => Cond::Empty,
+            AUTOMAP_NON_EMPTY => Cond::NonEmpty,
+            AUTOMAP_OTHER => Cond::Other,
+            _ => Cond::Ignore,
+        }
+    }
+
+    fn matches(&self, tile: Option<TileView<'_>>, known: &[TileRef]) -> bool {
+        match (self, tile) {
+            (Cond::Ignore, _) => true,
+            (Cond::Empty, t) => t.is_none(),
+            (Cond::NonEmpty, t) => t.is_some(),
+            (Cond::Tile(want), Some(t)) => t.to_tile_ref() == *want,
+            (Cond::Other, Some(t)) => !known.contains(&t.to_tile_ref()),
+            (_, None) => false,
+        }
+    }
+}
+
+#[derive(Default)]
+struct CellCond {
+    any: Vec<Cond>,
+    not: Vec<Cond>,
+}
+
+/// 针对某张地图解析好的规则（图层名已换成图层下标）。
+struct Compiled {
+    inputs: Vec<(u32, i32, i32, CellCond)>,
+    /// 每个目标层在本规则输入中出现过的 tile（“其它”据此判断）。
+    known: HashMap<u32, Vec<TileRef>>,
+    outputs: Vec<(u32, i32, i32, Option<TileRef>)>,
+    input_bounds: TileBounds,
+}
+
+impl Compiled {
+    /// 目标地图缺少规则用到的图层、或规则没有输入 / 输出时返回 `None`（跳过该规则）。
+    fn new(rule: &AutomapRule, map: &TileMapData) -> Option<Self> {
+        let pattern = &rule.pattern;
+        let mut inputs: HashMap<(u32, i32, i32), CellCond> = HashMap::new();
+        let mut known: HashMap<u32, Vec<TileRef>> = HashMap::new();
+        let mut outputs = Vec::new();
+        for layer in 0..pattern.layers {
+            let Some((role, target)) = pattern.layer_data.get(layer as usize).and_then(|d| layer_role(&d.name))
+            else {
+                continue;
+            };
+            let target = map.layer_data.iter().position(|d| d.name == target)? as u32;
+            for (l, x, y, tile) in pattern.iter_tiles() {
+                if l != layer {
+                    continue;
+                }
+                let tile = tile.to_tile_ref();
+                match role {
+                    Role::Output => {
+                        let out = match Cond::from_tile(tile.clone()) {
+                            Cond::Tile(_) => Some(tile),
+                            Cond::Empty => None,
+                            _ => continue,
+                        };
+                        outputs.push((target, x, y, out));
+                    }
+                    Role::Input | Role::InputNot => {
+                        let cond = Cond::from_tile(tile.clone());
+                        if let Cond::Tile(_) = cond {
+                            known.entry(target).or_default().push(tile);
+                        }
+                        let cell = inputs.entry((target, x, y)).or_default();
+                        match role {
+                            Role::Input => cell.any.push(cond),
+                            _ => cell.not.push(cond),
+                        }
+                    }
+                }
+            }
+        }
+        if outputs.is_empty() {
+            return None;
+        }
+        let mut input_bounds: Option<TileBounds> = None;
+        for &(_, x, y) in inputs.keys() {
+            match input_bounds.as_mut() {
+                Some(b) => b.include(x, y),
+                None => input_bounds = Some(TileBounds::from_corners(x, y, x, y)),
+            }
+        }
+        let mut inputs: Vec<(u32, i32, i32, CellCond)> =
+            inputs.into_iter().map(|((l, x, y), c)| (l, x, y, c)).collect();
+        inputs.sort_unstable_by_key(|&(l, x, y, _)| (l, y, x));
+        Some(Self {
+            inputs,
+            known,
+            outputs,
+            input_bounds: input_bounds?,
+        })
+    }
+
+    fn matches_at(&self, map: &TileMapData, ox: i32, oy: i32) -> bool {
+        self.inputs.iter().all(|(layer, x, y, cond)| {
+            let (mx, my) = (ox + x, oy + y);
+            let tile = if map.contains(mx, my) { map.view(map.cell(*layer, mx, my)) } else { None };
+            let known = self.known.get(layer).map_or(&[][..], |v| v.as_slice());
+            (cond.any.is_empty() || cond.any.iter().any(|c| c.matches(tile, known)))
+                && !cond.not.iter().any(|c| c.matches(tile, known))
+        })
+    }
+}
+
+/// 规则的最大影响范围（格）：某格改动后，只有距离不超过它的格子可能因此匹配。
+pub fn automap_reach(rules: &[AutomapRule]) -> i32 {
+    rules
+        .iter()
+        .map(|r| r.pattern.width.max(r.pattern.height) as i32)
+        .max()
+        .unwrap_or(0)
+}
+
+/// 规则默认作用范围：固定尺寸地图为整张图；无限地图为已绘制范围向外扩一个规则尺寸。
+pub fn automap_bounds(map: &TileMapData, rules: &[AutomapRule]) -> Option<TileBounds> {
+    if let Some((w, h)) = map.fixed_size() {
+        return (w > 0 && h > 0).then(|| TileBounds::from_corners(0, 0, w as i32 - 1, h as i32 - 1));
+    }
+    let used = map.used_bounds()?;
+    let reach = automap_reach(rules);
+    Some(TileBounds::from_corners(
+        used.min_x - reach,
+        used.min_y - reach,
+        used.max_x + reach,
+        used.max_y + reach,
+    ))
+}
+
+/// 依次执行 `rules`，只写入 `bounds`（`None` 为 [`automap_bounds`]）范围内的格子；返回一个净变更集。
+pub fn apply_automap(map: &mut TileMapData, rules: &[AutomapRule], bounds: Option<TileBounds>) -> ChangeSet {
+    let mut rec = Recorder::default();
+    let Some(area) = bounds.or_else(|| automap_bounds(map, rules)) else {
+        return rec.finish();
+    };
+    for rule in rules {
+        let Some(compiled) = Compiled::new(rule, map) else {
+            continue;
+        };
+        let ib = compiled.input_bounds;
+        let mut hits = Vec::new();
+        for oy in (area.min_y - ib.max_y)..=(area.max_y - ib.min_y) {
+            for ox in (area.min_x - ib.max_x)..=(area.max_x - ib.min_x) {
+                if compiled.matches_at(map, ox, oy) {
+                    hits.push((ox, oy));
+                }
+            }
+        }
+        for (ox, oy) in hits {
+            for (layer, x, y, tile) in &compiled.outputs {
+                let (mx, my) = (ox + x, oy + y);
+                if !area.contains(mx, my) {
+                    continue;
+                }
+                rec.set_tile(map, *layer, mx, my, tile.as_ref());
+            }
+        }
+    }
+    rec.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ops::revert;
+
+    fn tile(tileset: &str, index: u32) -> Option<TileRef> {
+        Some(TileRef {
+            tileset_id: tileset.to_string(),
+            index,
+            rot: 0,
+            flip_x: false,
+            flip_y: false,
+        })
+    }
+
+    fn named(mut map: TileMapData, names: &[&str]) -> TileMapData {
+        for (data, name) in map.layer_data.iter_mut().zip(names) {
+            data.name = name.to_string();
+        }
+        map
+    }
+
+    /// 墙（1 号）上方为空时，在 Overhead 层的上方一格放墙顶（2 号）。
+    fn wall_top_rule() -> AutomapRule {
+        let mut pattern = named(TileMapData::new_with_layers(1, 2, 2), &["input_Ground", "output_Overhead"]);
+        pattern.set(0, 0, 0, tile("t", 1));
+        pattern.set(0, 0, 1, tile(AUTOMAP_TILESET, AUTOMAP_EMPTY));
+        pattern.set(1, 0, 1, tile("t", 2));
+        AutomapRule::new(pattern)
+    }
+
+    #[test]
+    fn wall_with_empty_above_gets_a_top() {
+        let mut map = named(TileMapData::new_with_layers(4, 4, 2), &["Ground", "Overhead"]);
+        // 一列两格高的墙 + 一格单独的墙；只有各列最上面的墙上方会放墙顶。
+        map.set(0, 1, 0, tile("t", 1));
+        map.set(0, 1, 1, tile("t", 1));
+        map.set(0, 3, 3, tile("t", 1));
+        let rules = [wall_top_rule()];
+
+        let changes = apply_automap(&mut map, &rules, None);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(map.get(1, 1, 2), tile("t", 2));
+        // 最上一行的墙上方出了地图，视为空，但输出在图外被丢弃。
+        assert_eq!(map.get(1, 3, 3), None);
+
+        // 已满足时再跑一次没有变更；范围限制只写 bounds 内。
+        assert!(apply_automap(&mut map, &rules, None).is_empty());
+        revert(&mut map, &changes);
+        let outside = TileBounds::from_corners(2, 0, 3, 3);
+        assert!(apply_automap(&mut map, &rules, Some(outside)).is_empty());
+        assert_eq!(apply_automap(&mut map, &rules, Some(TileBounds::from_corners(1, 2, 1, 2))).len(), 1);
+    }
+
+    #[test]
+    fn split_rules_and_inputnot() {
+        // 规则地图里两块不相连的区域 = 两条规则：上面一条把“不是水(5)也不是空的格子”换成 6，下面一条是墙顶。
+        let mut rules = named(
+            TileMapData::new_with_layers(3, 4, 3),
+            &["input_Ground", "inputnot_Ground", "output_Ground"],
+        );
+        rules.set(0, 0, 3, tile(AUTOMAP_TILESET, AUTOMAP_NON_EMPTY));
+        rules.set(1, 0, 3, tile("t", 5));
+        rules.set(2, 0, 3, tile("t", 6));
+        rules.set(0, 2, 0, tile("t", 1));
+        rules.set(0, 2, 1, tile(AUTOMAP_TILESET, AUTOMAP_EMPTY));
+        rules.set(2, 2, 1, tile("t", 2));
+        let split = AutomapRule::split(&rules);
+        assert_eq!(split.len(), 2);
+        assert_eq!((split[0].pattern.width, split[0].pattern.height), (1, 1));
+        assert_eq!((split[1].pattern.width, split[1].pattern.height), (1, 2));
+
+        let mut map = named(TileMapData::new_with_layers(3, 1, 1), &["Ground"]);
+        map.set(0, 0, 0, tile("t", 5));
+        map.set(0, 1, 0, tile("t", 7));
+        let changes = apply_automap(&mut map, &split, None);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(map.get(0, 0, 0), tile("t", 5));
+        assert_eq!(map.get(0, 1, 0), tile("t", 6));
+        assert_eq!(map.get(0, 2, 0), None);
+    }
+}
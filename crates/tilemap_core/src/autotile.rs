@@ -0,0 +1,313 @@
+//! 自动图块（47 块 blob）：按 8 邻居选择变体，绘制时连同邻居一起更新。
+//!
+//! 邻居位采用常见的 blob 约定（地图 y 向上，北 = `(x, y + 1)`）：
+//! N=1, NE=2, E=4, SE=8, S=16, SW=32, W=64, NW=128；角只有在相邻两条边都相连时才计入，
+//! 化简后恰好 47 种组合，按掩码升序编号为变体 0..47，tile = `first + 变体`。
+//!
+//! RPG Maker A2 的 2x3 块（4x6 个半 tile）可用 `rm_a2_quarters` 拼出每个变体，
+//! 预先烘焙成 47 块的图集后按普通 blob 使用，地图里始终只保存普通 tile。
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::ops::{connected_region, ChangeSet, Recorder};
+use crate::{TileCell, TileMapData, TileRef};
+
+pub const AUTOTILE_N: u8 = 1;
+pub const AUTOTILE_NE: u8 = 2;
+pub const AUTOTILE_E: u8 = 4;
+pub const AUTOTILE_SE: u8 = 8;
+pub const AUTOTILE_S: u8 = 16;
+pub const AUTOTILE_SW: u8 = 32;
+pub const AUTOTILE_W: u8 = 64;
+pub const AUTOTILE_NW: u8 = 128;
+
+/// 变体数量。
+pub const AUTOTILE_VARIANTS: u32 = 47;
+
+/// 烘焙出的 blob 图集列数（47 块排成 8x6，最后一格空）。
+pub const AUTOTILE_SHEET_COLUMNS: u32 = 8;
+
+/// (位, dx, dy)，dy 为地图坐标（y 向上）。
+const NEIGHBORS: [(u8, i32, i32); 8] = [
+    (AUTOTILE_N, 0, 1),
+    (AUTOTILE_NE, 1, 1),
+    (AUTOTILE_E, 1, 0),
+    (AUTOTILE_SE, 1, -1),
+    (AUTOTILE_S, 0, -1),
+    (AUTOTILE_SW, -1, -1),
+    (AUTOTILE_W, -1, 0),
+    (AUTOTILE_NW, -1, 1),
+];
+
+/// 去掉“两条相邻边不都相连”的角。
+pub const fn reduce_mask(mask: u8) -> u8 {
+    let mut out = mask & (AUTOTILE_N | AUTOTILE_E | AUTOTILE_S | AUTOTILE_W);
+    let corners = [
+        (AUTOTILE_NE, AUTOTILE_N, AUTOTILE_E),
+        (AUTOTILE_SE, AUTOTILE_S, AUTOTILE_E),
+        (AUTOTILE_SW, AUTOTILE_S, AUTOTILE_W),
+        (AUTOTILE_NW, AUTOTILE_N, AUTOTILE_W),
+    ];
+    let mut i = 0;
+    while i < corners.len() {
+        let (corner, a, b) = corners[i];
+        if mask & corner != 0 && mask & a != 0 && mask & b != 0 {
+            out |= corner;
+        }
+        i += 1;
+    }
+    out
+}
+
+/// 47 个化简掩码（升序），下标即变体编号。
+const BLOB_MASKS: [u8; AUTOTILE_VARIANTS as usize] = {
+    let mut out = [0u8; AUTOTILE_VARIANTS as usize];
+    let mut n = 0;
+    let mut m = 0u16;
+    while m < 256 {
+        if reduce_mask(m as u8) == m as u8 {
+            out[n] = m as u8;
+            n += 1;
+        }
+        m += 1;
+    }
+    out
+};
+
+/// 邻居掩码对应的变体（先化简）。
+pub fn blob_variant(mask: u8) -> u32 {
+    BLOB_MASKS
+        .binary_search(&reduce_mask(mask))
+        .map(|i| i as u32)
+        .unwrap_or(0)
+}
+
+/// 变体对应的化简掩码。
+pub fn blob_mask(variant: u32) -> Option<u8> {
+    BLOB_MASKS.get(variant as usize).copied()
+}
+
+/// RPG Maker A2 块中拼出某个掩码所用的 4 个半 tile：顺序为左上、右上、左下、右下，
+/// 值为块内的 `[列, 行]`（半 tile 单位，图片坐标 y 向下，块宽 4、高 6）。
+pub fn rm_a2_quarters(mask: u8) -> [[u32; 2]; 4] {
+    let has = |bit: u8| mask & bit != 0;
+    if !has(AUTOTILE_N | AUTOTILE_E | AUTOTILE_S | AUTOTILE_W) {
+        // 四边都不相连：直接用左上角的预览 tile。
+        return [[0, 0], [1, 0], [0, 1], [1, 1]];
+    }
+    // (qx, qy, 水平边, 竖直边, 对角)
+    let quarters = [
+        (0, 0, AUTOTILE_W, AUTOTILE_N, AUTOTILE_NW),
+        (1, 0, AUTOTILE_E, AUTOTILE_N, AUTOTILE_NE),
+        (0, 1, AUTOTILE_W, AUTOTILE_S, AUTOTILE_SW),
+        (1, 1, AUTOTILE_E, AUTOTILE_S, AUTOTILE_SE),
+    ];
+    quarters.map(|(qx, qy, h, v, d)| {
+        let (h, v, d) = (has(h), has(v), has(d));
+        if h && v && !d {
+            // 右上角的 tile 是四个内角。
+            return [2 + qx, qy];
+        }
+        let col = match (h, qx) {
+            (true, 0) => 2,
+            (true, _) => 1,
+            (false, 0) => 0,
+            (false, _) => 3,
+        };
+        let row = match (v, qy) {
+            (true, 0) => 4,
+            (true, _) => 3,
+            (false, 0) => 2,
+            (false, _) => 5,
+        };
+        [col, row]
+    })
+}
+
+/// tileset 上的一组 47 块 blob 自动图块：index `first..first + 47` 按变体顺序排列。
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AutotileDef {
+    pub name: String,
+    pub first: u32,
+}
+
+impl AutotileDef {
+    pub fn contains(&self, index: u32) -> bool {
+        index >= self.first && index - self.first < AUTOTILE_VARIANTS
+    }
+
+    pub fn tile_index(&self, variant: u32) -> u32 {
+        self.first + variant.min(AUTOTILE_VARIANTS - 1)
+    }
+}
+
+/// 地图上某一层中属于同一组自动图块的格子。
+struct Member<'a> {
+    layer: u32,
+    tileset_id: &'a str,
+    def: &'a AutotileDef,
+}
+
+impl Member<'_> {
+    /// 固定尺寸地图的边界外视为相连（与 RPG Maker 一致，贴边不会出现描边）。
+    fn is_member(&self, map: &TileMapData, x: i32, y: i32) -> bool {
+        if !map.contains(x, y) {
+            return map.fixed_size().is_some();
+        }
+        map.view(map.cell(self.layer, x, y))
+            .is_some_and(|v| v.tileset_id == self.tileset_id && self.def.contains(v.index))
+    }
+
+    fn mask(&self, map: &TileMapData, x: i32, y: i32) -> u8 {
+        NEIGHBORS
+            .iter()
+            .filter(|(_, dx, dy)| self.is_member(map, x + dx, y + dy))
+            .fold(0, |m, (bit, _, _)| m | bit)
+    }
+
+    fn cell(&self, map: &mut TileMapData, variant: u32) -> TileCell {
+        map.pack(Some(&TileRef {
+            tileset_id: self.tileset_id.to_string(),
+            index: self.def.tile_index(variant),
+            rot: 0,
+            flip_x: false,
+            flip_y: false,
+        }))
+    }
+
+    /// 重新计算 `cells` 及其 8 邻居中属于本组的格子的变体。
+    fn fixup(&self, rec: &mut Recorder, map: &mut TileMapData, cells: &[(i32, i32)]) {
+        let mut touched: Vec<(i32, i32)> = cells
+            .iter()
+            .flat_map(|&(x, y)| {
+                std::iter::once((x, y)).chain(NEIGHBORS.iter().map(move |(_, dx, dy)| (x + dx, y + dy)))
+            })
+            .filter(|&(x, y)| map.contains(x, y))
+            .collect();
+        touched.sort_unstable();
+        touched.dedup();
+        for (x, y) in touched {
+            if !self.is_member(map, x, y) {
+                continue;
+            }
+            let variant = blob_variant(self.mask(map, x, y));
+            let cell = self.cell(map, variant);
+            rec.set(map, self.layer, x, y, cell);
+        }
+    }
+}
+
+/// 用自动图块绘制（或 `erase` 时清除）`cells`，并更新周围同组格子的变体。
+///
+/// 返回净变更集（含被连带修改的邻居），可作为一条撤销命令。
+pub fn paint_autotile(
+    map: &mut TileMapData,
+    layer: u32,
+    tileset_id: &str,
+    def: &AutotileDef,
+    cells: &[(i32, i32)],
+    erase: bool,
+) -> ChangeSet {
+    let mut rec = Recorder::default();
+    if layer >= map.layers {
+        return rec.finish();
+    }
+    let member = Member { layer, tileset_id, def };
+    // 先写入任意一个变体占位，邻居才能看到新格子。
+    let fill = if erase { TileCell::EMPTY } else { member.cell(map, 0) };
+    for &(x, y) in cells {
+        rec.set(map, layer, x, y, fill);
+    }
+    member.fixup(&mut rec, map, cells);
+    rec.finish()
+}
+
+/// 自动图块版油漆桶：把与 `(x, y)` 相同的 4 邻接连通区域改成自动图块（或清除）。
+pub fn fill_autotile(
+    map: &mut TileMapData,
+    layer: u32,
+    x: i32,
+    y: i32,
+    tileset_id: &str,
+    def: &AutotileDef,
+    erase: bool,
+) -> ChangeSet {
+    let region = connected_region(map, layer, x, y);
+    paint_autotile(map, layer, tileset_id, def, &region, erase)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ops::{reapply, revert};
+    use crate::TileBounds;
+
+    fn def() -> AutotileDef {
+        AutotileDef {
+            name: "grass".to_string(),
+            first: 10,
+        }
+    }
+
+    fn variant_at(map: &TileMapData, x: i32, y: i32) -> Option<u8> {
+        let t = map.get(0, x, y)?;
+        blob_mask(t.index - def().first)
+    }
+
+    #[test]
+    fn blob_has_47_variants_and_corners_need_both_edges() {
+        assert_eq!(BLOB_MASKS[0], 0);
+        assert_eq!(BLOB_MASKS[46], 255);
+        assert_eq!(blob_variant(AUTOTILE_NE), 0);
+        assert_eq!(blob_mask(blob_variant(AUTOTILE_N | AUTOTILE_E | AUTOTILE_NE)), Some(7));
+        assert_eq!(blob_mask(47), None);
+        // 孤立块用左上角的预览 tile；四周都相连、角不相连时用内角 tile。
+        assert_eq!(rm_a2_quarters(0), [[0, 0], [1, 0], [0, 1], [1, 1]]);
+        assert_eq!(rm_a2_quarters(AUTOTILE_E), [[0, 2], [1, 2], [0, 5], [1, 5]]);
+        let cross = AUTOTILE_N | AUTOTILE_E | AUTOTILE_S | AUTOTILE_W;
+        assert_eq!(rm_a2_quarters(cross), [[2, 0], [3, 0], [2, 1], [3, 1]]);
+        assert_eq!(rm_a2_quarters(255), [[2, 4], [1, 4], [2, 3], [1, 3]]);
+    }
+
+    #[test]
+    fn painting_updates_neighbors_and_undoes_in_one_step() {
+        let mut map = TileMapData::new_with_layers(5, 5, 1);
+        let first = paint_autotile(&mut map, 0, "t", &def(), &[(1, 2)], false);
+        assert_eq!(first.len(), 1);
+        assert_eq!(variant_at(&map, 1, 2), Some(0));
+
+        // 右侧再画一格：两格互相连接。
+        let second = paint_autotile(&mut map, 0, "t", &def(), &[(2, 2)], false);
+        assert_eq!(second.len(), 2);
+        assert_eq!(variant_at(&map, 1, 2), Some(AUTOTILE_E));
+        assert_eq!(variant_at(&map, 2, 2), Some(AUTOTILE_W));
+
+        revert(&mut map, &second);
+        assert_eq!(variant_at(&map, 1, 2), Some(0));
+        assert!(map.get(0, 2, 2).is_none());
+        reapply(&mut map, &second);
+
+        let erased = paint_autotile(&mut map, 0, "t", &def(), &[(2, 2)], true);
+        assert_eq!(erased.len(), 2);
+        assert_eq!(variant_at(&map, 1, 2), Some(0));
+    }
+
+    #[test]
+    fn fill_treats_fixed_map_border_as_connected() {
+        let mut map = TileMapData::new_with_layers(3, 3, 1);
+        let changes = fill_autotile(&mut map, 0, 1, 1, "t", &def(), false);
+        assert_eq!(changes.len(), 9);
+        let cells: Vec<(i32, i32)> =
+            map.tiles_in_rect(0, TileBounds::from_corners(0, 0, 2, 2)).into_iter().map(|(x, y, _)| (x, y)).collect();
+        assert_eq!(cells.len(), 9);
+        for (x, y) in cells {
+            assert_eq!(variant_at(&map, x, y), Some(255));
+        }
+        // 中间挖空：四周的格子都失去一个方向。
+        paint_autotile(&mut map, 0, "t", &def(), &[(1, 1)], true);
+        assert_eq!(variant_at(&map, 1, 2), Some(!(AUTOTILE_S | AUTOTILE_SE | AUTOTILE_SW)));
+        assert_eq!(variant_at(&map, 0, 0), Some(!AUTOTILE_NE));
+    }
+}
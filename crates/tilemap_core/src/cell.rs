@@ -0,0 +1,221 @@
+//! 紧凑格子存储：每张地图一张 tileset 表，格子里只存表下标。
+//!
+//! `TileCell` 把 tileset 下标、tile index、rot、flip 打包进一个 `u64`，
+//! 0 表示空格子。对外仍通过 `TileRef`（拥有）/ `TileView`（借用）访问。
+
+use std::collections::HashMap;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{Orientation, TileRef, TilesetId};
+
+const INDEX_BITS: u32 = 32;
+const SLOT_SHIFT: u32 = INDEX_BITS;
+const SLOT_MASK: u64 = 0xFFFF;
+const ROT_SHIFT: u32 = 48;
+const FLIP_X_BIT: u64 = 1 << 50;
+const FLIP_Y_BIT: u64 = 1 << 51;
+
+/// 打包后的格子：`index(32) | slot+1(16) | rot(2) | flip_x(1) | flip_y(1)`。
+///
+/// slot 是所在地图 `TilesetTable` 的下标，只在同一张地图内有意义。
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct TileCell(u64);
+
+impl TileCell {
+    pub const EMPTY: TileCell = TileCell(0);
+
+    /// 朝向按规范形式存储，同一朝向的不同 `rot/flip` 写法得到相同的格子。
+    pub fn new(slot: u16, index: u32, orientation: Orientation) -> Self {
+        let (rot, flip_x, flip_y) = orientation.to_rot_flip();
+        let mut bits = index as u64;
+        bits |= (slot as u64 + 1) << SLOT_SHIFT;
+        bits |= (rot as u64) << ROT_SHIFT;
+        if flip_x {
+            bits |= FLIP_X_BIT;
+        }
+        if flip_y {
+            bits |= FLIP_Y_BIT;
+        }
+        Self(bits)
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// tileset 表下标；空格子返回 None。
+    pub fn slot(self) -> Option<u16> {
+        let raw = (self.0 >> SLOT_SHIFT) & SLOT_MASK;
+        (raw != 0).then(|| (raw - 1) as u16)
+    }
+
+    pub fn index(self) -> u32 {
+        self.0 as u32
+    }
+
+    pub fn rot(self) -> u8 {
+        ((self.0 >> ROT_SHIFT) & 0b11) as u8
+    }
+
+    pub fn flip_x(self) -> bool {
+        self.0 & FLIP_X_BIT != 0
+    }
+
+    pub fn flip_y(self) -> bool {
+        self.0 & FLIP_Y_BIT != 0
+    }
+
+    pub fn orientation(self) -> Orientation {
+        Orientation::from_rot_flip(self.rot(), self.flip_x(), self.flip_y())
+    }
+}
+
+/// 地图内的 tileset 表（只追加，保证已有 slot 不失效）。
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "Vec<TilesetId>", into = "Vec<TilesetId>"))]
+#[derive(Clone, Debug, Default)]
+pub struct TilesetTable {
+    ids: Vec<TilesetId>,
+    lookup: HashMap<TilesetId, u16>,
+}
+
+impl TilesetTable {
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    pub fn id(&self, slot: u16) -> Option<&TilesetId> {
+        self.ids.get(slot as usize)
+    }
+
+    pub fn slot_of(&self, tileset_id: &str) -> Option<u16> {
+        self.lookup.get(tileset_id).copied()
+    }
+
+    /// 查找或登记 tileset；表满（超过 u16 容量）时返回 None。
+    pub fn intern(&mut self, tileset_id: &str) -> Option<u16> {
+        if let Some(slot) = self.slot_of(tileset_id) {
+            return Some(slot);
+        }
+        let slot = u16::try_from(self.ids.len()).ok().filter(|&s| s < u16::MAX)?;
+        self.ids.push(tileset_id.to_string());
+        self.lookup.insert(tileset_id.to_string(), slot);
+        Some(slot)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &TilesetId> + '_ {
+        self.ids.iter()
+    }
+}
+
+impl From<Vec<TilesetId>> for TilesetTable {
+    fn from(ids: Vec<TilesetId>) -> Self {
+        let mut table = Self::default();
+        for id in &ids {
+            table.intern(id);
+        }
+        table
+    }
+}
+
+impl From<TilesetTable> for Vec<TilesetId> {
+    fn from(table: TilesetTable) -> Self {
+        table.ids
+    }
+}
+
+/// 借用地图 tileset 表的格子视图（不分配）。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TileView<'a> {
+    pub tileset_id: &'a TilesetId,
+    pub index: u32,
+    pub rot: u8,
+    pub flip_x: bool,
+    pub flip_y: bool,
+}
+
+impl TileView<'_> {
+    pub fn orientation(&self) -> Orientation {
+        Orientation::from_rot_flip(self.rot, self.flip_x, self.flip_y)
+    }
+
+    pub fn to_tile_ref(&self) -> TileRef {
+        TileRef {
+            tileset_id: self.tileset_id.clone(),
+            index: self.index,
+            rot: self.rot,
+            flip_x: self.flip_x,
+            flip_y: self.flip_y,
+        }
+    }
+}
+
+impl PartialEq<TileRef> for TileView<'_> {
+    fn eq(&self, other: &TileRef) -> bool {
+        *self.tileset_id == other.tileset_id
+            && self.index == other.index
+            && self.orientation() == other.orientation()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tile_cell_packs_extremes_and_every_orientation() {
+        // slot 存为 slot+1，因此最大可用 slot 是 u16::MAX - 1。
+        let max_slot = u16::MAX - 1;
+        for slot in [0, max_slot] {
+            for index in [0, u32::MAX] {
+                for o in Orientation::ALL {
+                    let cell = TileCell::new(slot, index, o);
+                    assert!(!cell.is_empty());
+                    assert_eq!(cell.slot(), Some(slot));
+                    assert_eq!(cell.index(), index);
+                    assert_eq!(cell.orientation(), o);
+                }
+            }
+        }
+        assert_eq!(TileCell::EMPTY.slot(), None);
+        assert_eq!(TileCell::default(), TileCell::EMPTY);
+    }
+
+    #[test]
+    fn tileset_table_interns_ids_once() {
+        let mut table = TilesetTable::default();
+        assert_eq!(table.intern("grass"), Some(0));
+        assert_eq!(table.intern("water"), Some(1));
+        assert_eq!(table.intern("grass"), Some(0));
+        assert_eq!(table.len(), 2);
+        assert_eq!(table.id(1).map(String::as_str), Some("water"));
+        assert_eq!(table.slot_of("sand"), None);
+
+        // 从 Vec 还原时重复项合并，slot 按首次出现的顺序分配。
+        let table = TilesetTable::from(vec!["a".to_string(), "b".to_string(), "a".to_string()]);
+        assert_eq!(table.len(), 2);
+        assert_eq!(table.slot_of("b"), Some(1));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn tileset_table_serializes_as_id_list() {
+        let mut table = TilesetTable::default();
+        table.intern("grass");
+        table.intern("water");
+        let json = serde_json::to_string(&table).unwrap();
+        assert_eq!(json, r#"["grass","water"]"#);
+
+        let back: TilesetTable = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.slot_of("water"), Some(1));
+        assert_eq!(back.iter().collect::<Vec<_>>(), table.iter().collect::<Vec<_>>());
+    }
+}
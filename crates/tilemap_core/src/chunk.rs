@@ -0,0 +1,282 @@
+//! 无限地图的稀疏 chunk 存储。
+//!
+//! 格子按 `CHUNK_SIZE x CHUNK_SIZE` 分块，按 (layer, cx, cy) 存在有序表中；
+//! chunk 坐标可以为负。chunk 内全部为空时会被移除，因此空白区域不占内存。
+
+use std::collections::BTreeMap;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{TileBounds, TileCell};
+
+/// chunk 边长（格子数）。
+pub const CHUNK_SIZE: u32 = 32;
+
+/// chunk 键：图层 + chunk 坐标（可为负）。
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ChunkKey {
+    pub layer: u32,
+    pub cx: i32,
+    pub cy: i32,
+}
+
+impl ChunkKey {
+    /// 格子坐标 -> (chunk 键, chunk 内扁平下标)。
+    pub fn from_cell(layer: u32, x: i32, y: i32) -> (Self, usize) {
+        let size = CHUNK_SIZE as i32;
+        let key = Self {
+            layer,
+            cx: x.div_euclid(size),
+            cy: y.div_euclid(size),
+        };
+        let lx = x.rem_euclid(size) as usize;
+        let ly = y.rem_euclid(size) as usize;
+        (key, ly * CHUNK_SIZE as usize + lx)
+    }
+
+    /// chunk 左下角格子的坐标。
+    pub fn origin(&self) -> (i32, i32) {
+        let size = CHUNK_SIZE as i32;
+        (self.cx * size, self.cy * size)
+    }
+}
+
+/// 序列化为 `CHUNK_SIZE * CHUNK_SIZE` 个格子的列表；长度不符的数据拒绝加载。
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "Vec<TileCell>", into = "Vec<TileCell>"))]
+#[derive(Clone, Debug)]
+pub struct TileChunk {
+    tiles: Vec<TileCell>,
+}
+
+impl TileChunk {
+    fn new() -> Self {
+        Self {
+            tiles: vec![TileCell::EMPTY; (CHUNK_SIZE * CHUNK_SIZE) as usize],
+        }
+    }
+
+    pub fn get(&self, local: usize) -> TileCell {
+        self.tiles.get(local).copied().unwrap_or_default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tiles.iter().all(|t| t.is_empty())
+    }
+
+    /// 遍历 chunk 内非空格子：(lx, ly, cell)。
+    pub fn iter(&self) -> impl Iterator<Item = (u32, u32, TileCell)> + '_ {
+        self.tiles.iter().enumerate().filter_map(|(i, &t)| {
+            if t.is_empty() {
+                return None;
+            }
+            let i = i as u32;
+            Some((i % CHUNK_SIZE, i / CHUNK_SIZE, t))
+        })
+    }
+}
+
+impl TryFrom<Vec<TileCell>> for TileChunk {
+    type Error = String;
+
+    fn try_from(tiles: Vec<TileCell>) -> Result<Self, String> {
+        let expected = (CHUNK_SIZE * CHUNK_SIZE) as usize;
+        if tiles.len() != expected {
+            return Err(format!("chunk 格子数 {} 不等于 {expected}", tiles.len()));
+        }
+        Ok(Self { tiles })
+    }
+}
+
+impl From<TileChunk> for Vec<TileCell> {
+    fn from(chunk: TileChunk) -> Self {
+        chunk.tiles
+    }
+}
+
+/// 稀疏 chunk 集合。序列化为 `(键, chunk)` 列表（JSON 的 map 键只能是字符串）。
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(from = "Vec<(ChunkKey, TileChunk)>", into = "Vec<(ChunkKey, TileChunk)>")
+)]
+#[derive(Clone, Debug, Default)]
+pub struct TileChunks {
+    chunks: BTreeMap<ChunkKey, TileChunk>,
+}
+
+impl TileChunks {
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn chunk(&self, key: &ChunkKey) -> Option<&TileChunk> {
+        self.chunks.get(key)
+    }
+
+    pub fn get(&self, layer: u32, x: i32, y: i32) -> TileCell {
+        let (key, local) = ChunkKey::from_cell(layer, x, y);
+        self.chunks.get(&key).map(|c| c.get(local)).unwrap_or_default()
+    }
+
+    /// 写入格子，返回旧值。写入空格子后若 chunk 变空则移除。
+    pub fn set(&mut self, layer: u32, x: i32, y: i32, cell: TileCell) -> TileCell {
+        let (key, local) = ChunkKey::from_cell(layer, x, y);
+        if !cell.is_empty() {
+            let chunk = self.chunks.entry(key).or_insert_with(TileChunk::new);
+            return std::mem::replace(&mut chunk.tiles[local], cell);
+        }
+        let Some(chunk) = self.chunks.get_mut(&key) else {
+            return TileCell::EMPTY;
+        };
+        let before = std::mem::take(&mut chunk.tiles[local]);
+        if !before.is_empty() && chunk.is_empty() {
+            self.chunks.remove(&key);
+        }
+        before
+    }
+
+    /// 遍历全部非空格子：(layer, x, y, cell)。
+    pub fn iter(&self) -> impl Iterator<Item = (u32, i32, i32, TileCell)> + '_ {
+        self.chunks.iter().flat_map(|(key, chunk)| {
+            let (ox, oy) = key.origin();
+            chunk
+                .iter()
+                .map(move |(lx, ly, t)| (key.layer, ox + lx as i32, oy + ly as i32, t))
+        })
+    }
+
+    /// 遍历与 `rect` 相交的 chunk（只查已存在的 chunk）。
+    pub fn chunks_in_rect(
+        &self,
+        layer: u32,
+        rect: TileBounds,
+    ) -> impl Iterator<Item = (ChunkKey, &TileChunk)> + '_ {
+        let (min, _) = ChunkKey::from_cell(layer, rect.min_x, rect.min_y);
+        let (max, _) = ChunkKey::from_cell(layer, rect.max_x, rect.max_y);
+        (min.cy..=max.cy).flat_map(move |cy| {
+            (min.cx..=max.cx).filter_map(move |cx| {
+                let key = ChunkKey { layer, cx, cy };
+                self.chunks.get(&key).map(|c| (key, c))
+            })
+        })
+    }
+}
+
+/// 还原时丢弃全空的 chunk，保持“空白区域不占 chunk”的约定。
+impl From<Vec<(ChunkKey, TileChunk)>> for TileChunks {
+    fn from(chunks: Vec<(ChunkKey, TileChunk)>) -> Self {
+        Self {
+            chunks: chunks.into_iter().filter(|(_, c)| !c.is_empty()).collect(),
+        }
+    }
+}
+
+impl From<TileChunks> for Vec<(ChunkKey, TileChunk)> {
+    fn from(chunks: TileChunks) -> Self {
+        chunks.chunks.into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Orientation, TileMapData};
+
+    fn cell(index: u32) -> TileCell {
+        TileCell::new(0, index, Orientation::IDENTITY)
+    }
+
+    #[test]
+    fn chunk_key_uses_euclidean_division_for_negative_cells() {
+        let last = CHUNK_SIZE as usize - 1;
+        let (key, local) = ChunkKey::from_cell(0, -1, -1);
+        assert_eq!((key.cx, key.cy), (-1, -1));
+        assert_eq!(local, last * CHUNK_SIZE as usize + last);
+
+        // -32 正好是 chunk -1 的左下角，-33 已进入 chunk -2。
+        let (key, local) = ChunkKey::from_cell(0, -32, -32);
+        assert_eq!((key.cx, key.cy), (-1, -1));
+        assert_eq!(local, 0);
+        assert_eq!(key.origin(), (-32, -32));
+        let (key, _) = ChunkKey::from_cell(0, -33, 0);
+        assert_eq!((key.cx, key.cy), (-2, 0));
+    }
+
+    #[test]
+    fn negative_cells_across_chunk_edges_round_trip() {
+        let mut chunks = TileChunks::default();
+        let cells = [(-1, -1), (0, -1), (-1, 0), (0, 0), (-32, 32), (-33, 32)];
+        for (i, &(x, y)) in cells.iter().enumerate() {
+            assert!(chunks.set(0, x, y, cell(i as u32)).is_empty());
+        }
+        assert_eq!(chunks.chunk_count(), 6);
+        for (i, &(x, y)) in cells.iter().enumerate() {
+            assert_eq!(chunks.get(0, x, y), cell(i as u32));
+        }
+        // 相邻格子不受影响，其他图层也是空的。
+        assert!(chunks.get(0, -2, -1).is_empty());
+        assert!(chunks.get(1, -1, -1).is_empty());
+    }
+
+    #[test]
+    fn clearing_the_last_cell_removes_the_chunk() {
+        let mut chunks = TileChunks::default();
+        chunks.set(0, -5, 3, cell(1));
+        chunks.set(0, -6, 3, cell(2));
+        assert_eq!(chunks.chunk_count(), 1);
+
+        assert_eq!(chunks.set(0, -5, 3, TileCell::EMPTY), cell(1));
+        assert_eq!(chunks.chunk_count(), 1);
+        assert_eq!(chunks.set(0, -6, 3, TileCell::EMPTY), cell(2));
+        assert!(chunks.is_empty());
+
+        // 擦除不存在的格子不会凭空建出 chunk。
+        assert!(chunks.set(0, 100, 100, TileCell::EMPTY).is_empty());
+        assert!(chunks.is_empty());
+    }
+
+    #[test]
+    fn iter_walks_chunks_in_key_order_then_rows_bottom_up() {
+        let mut chunks = TileChunks::default();
+        for (i, &(layer, x, y)) in [(1, 0, 0), (0, 1, 1), (0, 0, 1), (0, 5, 0), (0, -1, 0), (0, 0, -1)]
+            .iter()
+            .enumerate()
+        {
+            chunks.set(layer, x, y, cell(i as u32));
+        }
+        let order: Vec<(u32, i32, i32)> = chunks.iter().map(|(l, x, y, _)| (l, x, y)).collect();
+        assert_eq!(
+            order,
+            vec![(0, -1, 0), (0, 0, -1), (0, 5, 0), (0, 0, 1), (0, 1, 1), (1, 0, 0)]
+        );
+
+        // 无限地图的 iter_cells 直接沿用 chunk 顺序。
+        let mut map = TileMapData::new_infinite(2);
+        map.chunks = chunks.clone();
+        assert!(map.iter_cells().map(|(l, x, y, _)| (l, x, y)).eq(order));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn infinite_map_serializes_to_json() {
+        let mut map = TileMapData::new_infinite(1);
+        let grass = map.tilesets.intern("grass").unwrap();
+        map.chunks.set(0, -40, 5, TileCell::new(grass, 3, Orientation::IDENTITY));
+        map.chunks.set(0, 7, -1, TileCell::new(grass, 4, Orientation::FLIP_X));
+        let json = serde_json::to_string(&map).unwrap();
+        let back: TileMapData = serde_json::from_str(&json).unwrap();
+        assert!(back.chunks.iter().eq(map.chunks.iter()));
+
+        // chunk 格子数不对时拒绝加载，而不是之后写入时越界 panic。
+        let mut doc: serde_json::Value = serde_json::from_str(&json).unwrap();
+        doc["chunks"][0][1] = serde_json::json!([0]);
+        assert!(serde_json::from_value::<TileMapData>(doc).is_err());
+    }
+}
@@ -0,0 +1,266 @@
+//! 由地图生成合并后的碰撞几何：整格实心合并为矩形或外轮廓，其它形状（半格、斜坡）原样保留。
+//!
+//! 坐标单位为格子（y 向上，与地图一致），游戏侧乘以格子像素尺寸即可。
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{CollisionShape, TileMapData, TilesetId};
+
+/// 整格实心的合并方式。
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColliderMode {
+    /// 贪心合并为尽量少的轴对齐矩形（适合只支持盒子的物理引擎）。
+    #[default]
+    Rects,
+    /// 描出每块实心区域的轮廓（外圈逆时针、洞顺时针；适合 chain / polyline 碰撞体）。
+    Outlines,
+}
+
+/// 以格子为单位的矩形，(x, y) 为左下角。
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ColliderRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MapColliders {
+    /// `ColliderMode::Rects` 的结果。
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Vec::is_empty"))]
+    pub rects: Vec<ColliderRect>,
+    /// `ColliderMode::Outlines` 的结果：闭合环，顶点为格子角点，已去掉共线点。
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Vec::is_empty"))]
+    pub outlines: Vec<Vec<[i32; 2]>>,
+    /// 非整格的形状（已按格子朝向变换并平移到格子位置，逆时针）；落在实心格里的会被去掉。
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Vec::is_empty"))]
+    pub polygons: Vec<Vec<[f32; 2]>>,
+}
+
+impl MapColliders {
+    pub fn is_empty(&self) -> bool {
+        self.rects.is_empty() && self.outlines.is_empty() && self.polygons.is_empty()
+    }
+}
+
+/// 合并 `layers` 中所有格子的碰撞形状。`shapes_of(tileset_id, index)` 返回 tile 的碰撞定义
+/// （tile 局部坐标，未变换）；不在地图范围内的图层下标忽略。
+pub fn build_colliders<'a>(
+    map: &TileMapData,
+    layers: &[u32],
+    mode: ColliderMode,
+    shapes_of: impl Fn(&TilesetId, u32) -> &'a [CollisionShape],
+) -> MapColliders {
+    let layers: HashSet<u32> = layers.iter().copied().filter(|&l| l < map.layers).collect();
+    // (y, x) 排序：贪心合并从下往上、从左往右扫描，结果稳定。
+    let mut solid: BTreeSet<(i32, i32)> = BTreeSet::new();
+    let mut partial: BTreeMap<(i32, i32), Vec<Vec<[f32; 2]>>> = BTreeMap::new();
+    for (layer, x, y, tile) in map.iter_tiles() {
+        if !layers.contains(&layer) {
+            continue;
+        }
+        for shape in shapes_of(tile.tileset_id, tile.index) {
+            if shape.is_degenerate() {
+                continue;
+            }
+            let shape = shape.transformed(tile.orientation());
+            if shape.is_full() {
+                solid.insert((y, x));
+            } else {
+                let points = shape.points().into_iter().map(|[px, py]| [px + x as f32, py + y as f32]).collect();
+                partial.entry((y, x)).or_default().push(points);
+            }
+        }
+    }
+
+    let polygons = partial
+        .into_iter()
+        .filter(|(cell, _)| !solid.contains(cell))
+        .flat_map(|(_, polys)| polys)
+        .collect();
+    let mut out = MapColliders {
+        polygons,
+        ..Default::default()
+    };
+    match mode {
+        ColliderMode::Rects => out.rects = greedy_rects(solid),
+        ColliderMode::Outlines => out.outlines = trace_outlines(&solid),
+    }
+    out
+}
+
+/// 先沿 x 取最长一段，再整段向上扩展。
+fn greedy_rects(mut remaining: BTreeSet<(i32, i32)>) -> Vec<ColliderRect> {
+    let mut rects = Vec::new();
+    while let Some(&(y, x)) = remaining.iter().next() {
+        let mut width = 1;
+        while remaining.contains(&(y, x + width)) {
+            width += 1;
+        }
+        let mut height = 1;
+        while (0..width).all(|dx| remaining.contains(&(y + height, x + dx))) {
+            height += 1;
+        }
+        for dy in 0..height {
+            for dx in 0..width {
+                remaining.remove(&(y + dy, x + dx));
+            }
+        }
+        rects.push(ColliderRect {
+            x,
+            y,
+            width: width as u32,
+            height: height as u32,
+        });
+    }
+    rects
+}
+
+/// 收集实心区域的边界边（实心在左侧），再按“优先左转”连成环：
+/// 只有对角相接的两格会被描成两个环（实心按 4 邻接划分区域）。
+fn trace_outlines(solid: &BTreeSet<(i32, i32)>) -> Vec<Vec<[i32; 2]>> {
+    let is_solid = |x: i32, y: i32| solid.contains(&(y, x));
+    let mut edges: BTreeMap<[i32; 2], Vec<[i32; 2]>> = BTreeMap::new();
+    let mut add = |a: [i32; 2], b: [i32; 2]| edges.entry(a).or_default().push(b);
+    for &(y, x) in solid {
+        if !is_solid(x, y - 1) {
+            add([x, y], [x + 1, y]);
+        }
+        if !is_solid(x + 1, y) {
+            add([x + 1, y], [x + 1, y + 1]);
+        }
+        if !is_solid(x, y + 1) {
+            add([x + 1, y + 1], [x, y + 1]);
+        }
+        if !is_solid(x - 1, y) {
+            add([x, y + 1], [x, y]);
+        }
+    }
+
+    let mut outlines = Vec::new();
+    while let Some((&start, _)) = edges.iter().next() {
+        let mut ring = vec![start];
+        let mut from = start;
+        let mut to = take_edge(&mut edges, start, None);
+        while to != start {
+            ring.push(to);
+            let dir = [to[0] - from[0], to[1] - from[1]];
+            from = to;
+            to = take_edge(&mut edges, from, Some(dir));
+        }
+        outlines.push(drop_collinear(ring));
+    }
+    outlines
+}
+
+/// 取出从 `at` 出发的一条边；有多条时按左转、直行、右转的顺序选。
+fn take_edge(edges: &mut BTreeMap<[i32; 2], Vec<[i32; 2]>>, at: [i32; 2], dir: Option<[i32; 2]>) -> [i32; 2] {
+    let outgoing = edges.get_mut(&at).expect("boundary edges form closed rings");
+    let i = match dir {
+        Some([dx, dy]) if outgoing.len() > 1 => [[-dy, dx], [dx, dy], [dy, -dx]]
+            .iter()
+            .find_map(|d| outgoing.iter().position(|e| [e[0] - at[0], e[1] - at[1]] == *d))
+            .unwrap_or(0),
+        _ => 0,
+    };
+    let to = outgoing.swap_remove(i);
+    if outgoing.is_empty() {
+        edges.remove(&at);
+    }
+    to
+}
+
+fn drop_collinear(ring: Vec<[i32; 2]>) -> Vec<[i32; 2]> {
+    let n = ring.len();
+    (0..n)
+        .filter(|&i| {
+            let [px, py] = ring[(i + n - 1) % n];
+            let [x, y] = ring[i];
+            let [nx, ny] = ring[(i + 1) % n];
+            (x - px) * (ny - y) - (y - py) * (nx - x) != 0
+        })
+        .map(|i| ring[i])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Orientation, TileRef};
+
+    fn put(map: &mut TileMapData, layer: u32, x: i32, y: i32, index: u32, orientation: Orientation) {
+        let mut tile = TileRef {
+            tileset_id: "t".to_string(),
+            index,
+            rot: 0,
+            flip_x: false,
+            flip_y: false,
+        };
+        tile.set_orientation(orientation);
+        map.set(layer, x, y, Some(tile));
+    }
+
+    /// tile 0 = 整格，tile 1 = 实心在右下的斜坡。
+    fn shapes() -> Vec<Vec<CollisionShape>> {
+        vec![
+            vec![CollisionShape::Full],
+            vec![CollisionShape::polygon(vec![[0.0, 0.0], [1.0, 0.0], [1.0, 1.0]])],
+        ]
+    }
+
+    fn area(ring: &[[i32; 2]]) -> i32 {
+        let n = ring.len();
+        (0..n)
+            .map(|i| ring[i][0] * ring[(i + 1) % n][1] - ring[(i + 1) % n][0] * ring[i][1])
+            .sum::<i32>()
+    }
+
+    #[test]
+    fn merges_solids_and_keeps_slopes() {
+        // 5x5：底部一整行 + 中间一个 3x3 带洞的环（洞在 (2,2)），层 1 上放一个斜坡。
+        let mut map = TileMapData::new_with_layers(5, 5, 2);
+        for x in 0..5 {
+            put(&mut map, 0, x, 0, 0, Orientation::IDENTITY);
+        }
+        for y in 1..4 {
+            for x in 1..4 {
+                if (x, y) != (2, 2) {
+                    put(&mut map, 0, x, y, 0, Orientation::IDENTITY);
+                }
+            }
+        }
+        put(&mut map, 1, 0, 1, 1, Orientation::FLIP_X);
+        // 被实心格覆盖的斜坡不单独输出。
+        put(&mut map, 1, 1, 1, 1, Orientation::IDENTITY);
+        // 只选层 0 时不参与。
+        put(&mut map, 1, 4, 4, 0, Orientation::IDENTITY);
+
+        let defs = shapes();
+        let lookup = |_: &TilesetId, i: u32| defs[i as usize].as_slice();
+
+        let rects = build_colliders(&map, &[0, 1], ColliderMode::Rects, lookup);
+        assert!(rects.outlines.is_empty());
+        let covered: u32 = rects.rects.iter().map(|r| r.width * r.height).sum();
+        assert_eq!(covered, 5 + 8 + 1);
+        assert_eq!(rects.rects[0], ColliderRect { x: 0, y: 0, width: 5, height: 1 });
+        assert_eq!(rects.polygons, vec![vec![[0.0, 2.0], [0.0, 1.0], [1.0, 1.0]]]);
+
+        let only_ground = build_colliders(&map, &[0], ColliderMode::Rects, lookup);
+        assert_eq!(only_ground.rects.len(), 5);
+        assert!(only_ground.polygons.is_empty());
+
+        let outlines = build_colliders(&map, &[0, 1], ColliderMode::Outlines, lookup);
+        assert_eq!(outlines.polygons, rects.polygons);
+        let mut rings: Vec<(usize, i32)> = outlines.outlines.iter().map(|r| (r.len(), area(r))).collect();
+        rings.sort();
+        // 地面 + 环合并成一个 8 顶点的外圈，洞是顺时针的 4 顶点环，(4,4) 单独一块。
+        assert_eq!(rings, vec![(4, -2), (4, 2), (8, 2 * 14)]);
+    }
+}
@@ -0,0 +1,125 @@
+//! tile 碰撞形状：tile 局部坐标下的整格 / 多边形，随图块朝向变换。
+//!
+//! 坐标约定：0..1 归一化，原点在 tile 左下角、y 向上（与地图格子坐标一致），
+//! 游戏侧乘以格子尺寸再加上格子原点即得到世界坐标。
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::Orientation;
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq)]
+pub enum CollisionShape {
+    /// 整格实心。
+    Full,
+    /// 简单多边形（顶点逆时针）。半格、斜坡等都用多边形表示。
+    Polygon(Vec<[f32; 2]>),
+}
+
+impl CollisionShape {
+    /// 构造多边形，顶点统一为逆时针。
+    pub fn polygon(mut points: Vec<[f32; 2]>) -> Self {
+        if signed_area(&points) < 0.0 {
+            points.reverse();
+        }
+        Self::Polygon(points)
+    }
+
+    /// 轮廓顶点（逆时针）；`Full` 返回整格的四个角。
+    pub fn points(&self) -> Vec<[f32; 2]> {
+        match self {
+            Self::Full => vec![[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]],
+            Self::Polygon(points) => points.clone(),
+        }
+    }
+
+    /// 是否覆盖整个格子（`Full`，或恰好为整格正方形的多边形）。
+    pub fn is_full(&self) -> bool {
+        match self {
+            Self::Full => true,
+            Self::Polygon(points) => {
+                points.iter().all(|&[x, y]| (0.0..=1.0).contains(&x) && (0.0..=1.0).contains(&y))
+                    && (signed_area(points).abs() - 1.0).abs() <= 1e-4
+            }
+        }
+    }
+
+    /// 少于 3 个顶点或面积为 0 的多边形不产生碰撞。
+    pub fn is_degenerate(&self) -> bool {
+        match self {
+            Self::Full => false,
+            Self::Polygon(points) => points.len() < 3 || signed_area(points).abs() <= f32::EPSILON,
+        }
+    }
+
+    /// 按地图格子上的朝向（`TileRef::orientation`）变换，绕 tile 中心旋转 / 翻转。
+    pub fn transformed(&self, orientation: Orientation) -> Self {
+        let Self::Polygon(points) = self else {
+            return Self::Full;
+        };
+        if orientation == Orientation::IDENTITY {
+            return self.clone();
+        }
+        // Orientation 定义在 y 向下的图像坐标里，换到 y 向上的 tile 坐标再作用。
+        let m = orientation.y_up().matrix();
+        let points = points
+            .iter()
+            .map(|&[x, y]| {
+                let (cx, cy) = (x - 0.5, y - 0.5);
+                [
+                    m[0][0] as f32 * cx + m[0][1] as f32 * cy + 0.5,
+                    m[1][0] as f32 * cx + m[1][1] as f32 * cy + 0.5,
+                ]
+            })
+            .collect();
+        Self::polygon(points)
+    }
+}
+
+/// 多边形有向面积（逆时针为正）。
+fn signed_area(points: &[[f32; 2]]) -> f32 {
+    let n = points.len();
+    let mut sum = 0.0;
+    for i in 0..n {
+        let [x0, y0] = points[i];
+        let [x1, y1] = points[(i + 1) % n];
+        sum += x0 * y1 - x1 * y0;
+    }
+    sum * 0.5
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(shape: &CollisionShape) -> Vec<(i32, i32)> {
+        let mut v: Vec<_> = shape
+            .points()
+            .iter()
+            .map(|p| ((p[0] * 2.0).round() as i32, (p[1] * 2.0).round() as i32))
+            .collect();
+        v.sort();
+        v
+    }
+
+    #[test]
+    fn slope_follows_rotation_and_flip() {
+        // 实心在右下的斜坡：左下 -> 右下 -> 右上。
+        let slope = CollisionShape::polygon(vec![[0.0, 0.0], [1.0, 0.0], [1.0, 1.0]]);
+        assert!(signed_area(&slope.points()) > 0.0);
+
+        let flipped = slope.transformed(Orientation::FLIP_X);
+        assert_eq!(sorted(&flipped), vec![(0, 0), (0, 2), (2, 0)]);
+        assert!(signed_area(&flipped.points()) > 0.0, "翻转后仍为逆时针");
+
+        // 顺时针转 90°：右上角转到右下角，实心到左下。
+        let rotated = slope.transformed(Orientation::ROT_CW);
+        assert_eq!(sorted(&rotated), vec![(0, 0), (0, 2), (2, 0)]);
+        let half = CollisionShape::polygon(vec![[0.0, 0.0], [1.0, 0.0], [1.0, 0.5], [0.0, 0.5]]);
+        assert_eq!(sorted(&half.transformed(Orientation::ROT_180)), vec![(0, 1), (0, 2), (2, 1), (2, 2)]);
+
+        assert_eq!(CollisionShape::Full.transformed(Orientation::ROT_CW), CollisionShape::Full);
+        assert!(CollisionShape::Polygon(vec![[0.0, 0.0], [1.0, 1.0]]).is_degenerate());
+    }
+}
@@ -0,0 +1,89 @@
+//! tileset 图集的切分规则（tile 尺寸 + 外边距 + 间距），与 Tiled 的 tileset 属性一致。
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// 图集切分：第 `i` 个 tile 的左上角在 `margin + col * (tile + spacing)`（图片坐标，y 向下）。
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TileGrid {
+    pub tile_width: u32,
+    pub tile_height: u32,
+    /// 图片四周的留白（像素）。
+    pub margin: u32,
+    /// 相邻 tile 之间的间隔（像素）。
+    pub spacing: u32,
+}
+
+impl TileGrid {
+    /// 紧密排列、无留白的图集。
+    pub fn new(tile_width: u32, tile_height: u32) -> Self {
+        Self {
+            tile_width,
+            tile_height,
+            margin: 0,
+            spacing: 0,
+        }
+    }
+
+    pub fn tile_size(&self) -> (u32, u32) {
+        (self.tile_width, self.tile_height)
+    }
+
+    /// 宽度为 `image_width` 的图片能切出的列数（不足一个 tile 的余量忽略）。
+    pub fn columns(&self, image_width: u32) -> u32 {
+        count(image_width, self.tile_width, self.margin, self.spacing)
+    }
+
+    pub fn rows(&self, image_height: u32) -> u32 {
+        count(image_height, self.tile_height, self.margin, self.spacing)
+    }
+
+    /// 图片可切出的 tile 总数（columns * rows）。
+    pub fn capacity(&self, image_width: u32, image_height: u32) -> u32 {
+        self.columns(image_width).saturating_mul(self.rows(image_height))
+    }
+
+    /// 第 `index` 个 tile 在图片中的左上角像素坐标（按行优先、`columns` 列）。
+    pub fn tile_origin(&self, index: u32, columns: u32) -> (u32, u32) {
+        let columns = columns.max(1);
+        let (col, row) = (index % columns, index / columns);
+        (
+            self.margin + col * (self.tile_width + self.spacing),
+            self.margin + row * (self.tile_height + self.spacing),
+        )
+    }
+}
+
+fn count(size: u32, tile: u32, margin: u32, spacing: u32) -> u32 {
+    if tile == 0 {
+        return 0;
+    }
+    // 与 Tiled 相同：最后一个 tile 之后不需要 spacing，右/下侧的 margin 也不强制。
+    (size.saturating_sub(margin) + spacing) / (tile + spacing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn margin_and_spacing_slice_like_tiled() {
+        // 2px 外边距、1px 间距的 3x2 个 16px tile：2 + 3*16 + 2*1 = 52，再加右侧 2px。
+        let grid = TileGrid {
+            tile_width: 16,
+            tile_height: 16,
+            margin: 2,
+            spacing: 1,
+        };
+        assert_eq!(grid.columns(54), 3);
+        assert_eq!(grid.rows(37), 2);
+        assert_eq!(grid.capacity(54, 37), 6);
+        assert_eq!(grid.tile_origin(0, 3), (2, 2));
+        assert_eq!(grid.tile_origin(4, 3), (19, 19));
+
+        assert_eq!(TileGrid::new(32, 32).columns(100), 3);
+        assert_eq!(TileGrid::new(32, 32).capacity(31, 64), 0);
+        assert_eq!(TileGrid::new(0, 32).columns(64), 0);
+    }
+}
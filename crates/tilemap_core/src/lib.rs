@@ -6,6 +6,47 @@ use bevy::prelude::Resource;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+mod automap;
+mod autotile;
+mod cell;
+mod chunk;
+mod collision;
+mod colliders;
+mod grid;
+mod meta;
+mod orientation;
+mod rng;
+mod shapes;
+mod variation;
+mod wang;
+pub mod ops;
+
+pub use automap::{
+    apply_automap, automap_bounds, automap_reach, AutomapRule, AUTOMAP_EMPTY, AUTOMAP_IGNORE, AUTOMAP_NON_EMPTY,
+    AUTOMAP_OTHER, AUTOMAP_TILESET,
+};
+pub use autotile::{
+    blob_mask, blob_variant, fill_autotile, paint_autotile, reduce_mask, rm_a2_quarters, AutotileDef, AUTOTILE_E,
+    AUTOTILE_N, AUTOTILE_NE, AUTOTILE_NW, AUTOTILE_S, AUTOTILE_SE, AUTOTILE_SHEET_COLUMNS, AUTOTILE_SW,
+    AUTOTILE_VARIANTS, AUTOTILE_W,
+};
+pub use cell::{TileCell, TileView, TilesetTable};
+pub use chunk::{ChunkKey, TileChunk, TileChunks, CHUNK_SIZE};
+pub use collision::CollisionShape;
+pub use colliders::{build_colliders, ColliderMode, ColliderRect, MapColliders};
+pub use grid::TileGrid;
+pub use meta::{
+    fill_meta_rect, flood_fill_meta, reapply_meta, revert_meta, CellMeta, MetaChange, MetaChangeSet, MetaField,
+    MetaGrid, MetaRecorder, PASS_BLOCK_ALL, PASS_BLOCK_DOWN, PASS_BLOCK_LEFT, PASS_BLOCK_RIGHT, PASS_BLOCK_UP,
+};
+pub use rng::TileRng;
+pub use shapes::{drag_bounds, ellipse_cells, line_cells, polyline_cells, rect_outline_cells, snap_line_45};
+pub use variation::{paint_variations, Variation, VariationSet};
+pub use wang::{fill_wang, paint_wang, WangColor, WangId, WangKind, WangSet, WangTile};
+pub use orientation::{
+    Orientation, TILED_FLIP_D, TILED_FLIP_H, TILED_FLIP_MASK, TILED_FLIP_V,
+};
+
 /// 稳定 tileset id。
 ///
 /// 约定：使用导入图片内容的 hash（或至少是文件名+hash）生成，保证跨机器/拷贝时一致。
@@ -16,7 +57,7 @@ pub type TilesetId = String;
 pub struct TileRef {
     pub tileset_id: TilesetId,
     pub index: u32,
-    /// 0,1,2,3 => 0/90/180/270 度顺时针。
+    /// 0,1,2,3 => 0/90/180/270 度顺时针；先旋转，再 flip_x/flip_y（见 `Orientation::from_rot_flip`）。
     #[cfg_attr(feature = "serde", serde(default))]
     pub rot: u8,
     #[cfg_attr(feature = "serde", serde(default))]
@@ -25,12 +66,36 @@ pub struct TileRef {
     pub flip_y: bool,
 }
 
+impl TileRef {
+    pub fn orientation(&self) -> Orientation {
+        Orientation::from_rot_flip(self.rot, self.flip_x, self.flip_y)
+    }
+
+    /// 写入朝向（规范形式：flip_y 恒为 false）。
+    pub fn set_orientation(&mut self, orientation: Orientation) {
+        (self.rot, self.flip_x, self.flip_y) = orientation.to_rot_flip();
+    }
+
+    /// 在当前朝向之后再施加 `op`。
+    pub fn transform(&mut self, op: Orientation) {
+        self.set_orientation(self.orientation().then(op));
+    }
+}
+
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, Debug)]
 pub struct LayerData {
     pub name: String,
     pub visible: bool,
     pub locked: bool,
+    /// 不透明度（0.0..=1.0）；旧存档缺省为 1.0。
+    #[cfg_attr(feature = "serde", serde(default = "default_layer_opacity"))]
+    pub opacity: f32,
+}
+
+#[cfg(feature = "serde")]
+fn default_layer_opacity() -> f32 {
+    1.0
 }
 
 impl Default for LayerData {
@@ -39,16 +104,69 @@ impl Default for LayerData {
             name: "Layer".to_string(),
             visible: true,
             locked: false,
+            opacity: 1.0,
         }
     }
 }
 
 pub const DEFAULT_LAYER_COUNT: u32 = 2;
 
+/// 格子坐标的包围盒（闭区间，坐标可为负）。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TileBounds {
+    pub min_x: i32,
+    pub min_y: i32,
+    pub max_x: i32,
+    pub max_y: i32,
+}
+
+impl TileBounds {
+    pub fn from_corners(ax: i32, ay: i32, bx: i32, by: i32) -> Self {
+        Self {
+            min_x: ax.min(bx),
+            min_y: ay.min(by),
+            max_x: ax.max(bx),
+            max_y: ay.max(by),
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        (self.max_x - self.min_x + 1) as u32
+    }
+
+    pub fn height(&self) -> u32 {
+        (self.max_y - self.min_y + 1) as u32
+    }
+
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.min_x && x <= self.max_x && y >= self.min_y && y <= self.max_y
+    }
+
+    /// 扩展包围盒使其包含 (x, y)。
+    pub fn include(&mut self, x: i32, y: i32) {
+        self.min_x = self.min_x.min(x);
+        self.min_y = self.min_y.min(y);
+        self.max_x = self.max_x.max(x);
+        self.max_y = self.max_y.max(y);
+    }
+
+    /// 与另一个包围盒求交；无交集时返回 None。
+    pub fn intersect(&self, other: &TileBounds) -> Option<TileBounds> {
+        let r = TileBounds {
+            min_x: self.min_x.max(other.min_x),
+            min_y: self.min_y.max(other.min_y),
+            max_x: self.max_x.min(other.max_x),
+            max_y: self.max_y.min(other.max_y),
+        };
+        (r.min_x <= r.max_x && r.min_y <= r.max_y).then_some(r)
+    }
+}
+
 #[cfg_attr(feature = "bevy", derive(Resource))]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, Debug)]
 pub struct TileMapData {
+    /// 固定尺寸地图的宽高；无限地图为 0。
     pub width: u32,
     pub height: u32,
     /// 图层数量。tiles 按 layer0..layerN 的顺序扁平存储。
@@ -56,13 +174,33 @@ pub struct TileMapData {
     pub layers: u32,
     #[cfg_attr(feature = "serde", serde(default))]
     pub layer_data: Vec<LayerData>,
-    pub tiles: Vec<Option<TileRef>>,
+    /// 本地图用到的 tileset；格子里只存这张表的下标。
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub tilesets: TilesetTable,
+    /// 固定尺寸地图的稠密存储（无限地图为空）。
+    pub tiles: Vec<TileCell>,
+    /// 无限地图：格子存放在 `chunks` 中，坐标不受 width/height 限制（可为负）。
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub infinite: bool,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "TileChunks::is_empty"))]
+    pub chunks: TileChunks,
+    /// 区域 / 通行 / 地形标记（与图层无关，稀疏存储）。
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "MetaGrid::is_empty"))]
+    pub meta: MetaGrid,
 }
 
 fn default_layers() -> u32 {
     1
 }
 
+/// 固定尺寸地图的格子总数；超出 usize 时 panic（调用方应先校验尺寸）。
+fn dense_len(width: u32, height: u32, layers: u32) -> usize {
+    (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|n| n.checked_mul(layers as usize))
+        .expect("地图尺寸溢出")
+}
+
 impl TileMapData {
     pub fn new(width: u32, height: u32) -> Self {
         Self::new_with_layers(width, height, DEFAULT_LAYER_COUNT)
@@ -76,6 +214,7 @@ impl TileMapData {
                 name: format!("Layer {}", i + 1),
                 visible: true,
                 locked: false,
+                opacity: 1.0,
             });
         }
         Self {
@@ -83,16 +222,247 @@ impl TileMapData {
             height,
             layers,
             layer_data,
-            tiles: vec![None; (width * height * layers) as usize],
+            tilesets: TilesetTable::default(),
+            tiles: vec![TileCell::EMPTY; dense_len(width, height, layers)],
+            infinite: false,
+            chunks: TileChunks::default(),
+            meta: MetaGrid::default(),
+        }
+    }
+
+    /// 创建无限地图（稀疏 chunk 存储）。
+    pub fn new_infinite(layers: u32) -> Self {
+        let mut map = Self::new_with_layers(0, 0, layers);
+        map.infinite = true;
+        map
+    }
+
+    /// 固定尺寸地图返回 (width, height)；无限地图返回 None。
+    pub fn fixed_size(&self) -> Option<(u32, u32)> {
+        (!self.infinite).then_some((self.width, self.height))
+    }
+
+    /// 坐标是否可写（无限地图恒为 true）。
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        if self.infinite {
+            return true;
+        }
+        x >= 0 && y >= 0 && (x as u32) < self.width && (y as u32) < self.height
+    }
+
+    /// `layer` 存在且坐标在图内。
+    pub fn writable(&self, layer: u32, x: i32, y: i32) -> bool {
+        layer < self.layers && self.contains(x, y)
+    }
+
+    /// 读取打包格子；越界/空格子返回 `TileCell::EMPTY`。
+    pub fn cell(&self, layer: u32, x: i32, y: i32) -> TileCell {
+        if layer >= self.layers {
+            return TileCell::EMPTY;
+        }
+        if self.infinite {
+            return self.chunks.get(layer, x, y);
+        }
+        if !self.contains(x, y) {
+            return TileCell::EMPTY;
+        }
+        let idx = self.idx_layer(layer, x as u32, y as u32);
+        self.tiles.get(idx).copied().unwrap_or_default()
+    }
+
+    /// 写入打包格子，返回旧值；越界写入会被忽略（返回 `TileCell::EMPTY`）。
+    ///
+    /// `cell` 必须来自本地图（`pack` / `cell`），否则 slot 对应的 tileset 不确定。
+    pub fn set_cell(&mut self, layer: u32, x: i32, y: i32, cell: TileCell) -> TileCell {
+        if layer >= self.layers {
+            return TileCell::EMPTY;
+        }
+        if self.infinite {
+            return self.chunks.set(layer, x, y, cell);
+        }
+        if !self.contains(x, y) {
+            return TileCell::EMPTY;
         }
+        let idx = self.idx_layer(layer, x as u32, y as u32);
+        match self.tiles.get_mut(idx) {
+            Some(slot) => std::mem::replace(slot, cell),
+            None => TileCell::EMPTY,
+        }
+    }
+
+    /// 把 `TileRef` 打包为本地图的格子（必要时登记 tileset）。
+    ///
+    /// tileset 表已满时返回 `TileCell::EMPTY`。
+    pub fn pack(&mut self, tile: Option<&TileRef>) -> TileCell {
+        let Some(tile) = tile else {
+            return TileCell::EMPTY;
+        };
+        let Some(slot) = self.tilesets.intern(&tile.tileset_id) else {
+            return TileCell::EMPTY;
+        };
+        TileCell::new(slot, tile.index, tile.orientation())
+    }
+
+    pub fn view(&self, cell: TileCell) -> Option<TileView<'_>> {
+        let tileset_id = self.tilesets.id(cell.slot()?)?;
+        Some(TileView {
+            tileset_id,
+            index: cell.index(),
+            rot: cell.rot(),
+            flip_x: cell.flip_x(),
+            flip_y: cell.flip_y(),
+        })
+    }
+
+    pub fn unpack(&self, cell: TileCell) -> Option<TileRef> {
+        self.view(cell).map(|v| v.to_tile_ref())
+    }
+
+    pub fn get(&self, layer: u32, x: i32, y: i32) -> Option<TileRef> {
+        self.unpack(self.cell(layer, x, y))
+    }
+
+    /// 写入格子，返回旧值；越界写入会被忽略（返回 None）。
+    pub fn set(&mut self, layer: u32, x: i32, y: i32, tile: Option<TileRef>) -> Option<TileRef> {
+        if !self.writable(layer, x, y) {
+            return None;
+        }
+        let cell = self.pack(tile.as_ref());
+        let before = self.set_cell(layer, x, y, cell);
+        self.unpack(before)
+    }
+
+    /// 遍历全部非空格子：(layer, x, y, cell)。
+    pub fn iter_cells(&self) -> Box<dyn Iterator<Item = (u32, i32, i32, TileCell)> + '_> {
+        if self.infinite {
+            return Box::new(self.chunks.iter());
+        }
+        let layer_len = self.layer_len().max(1);
+        let width = self.width.max(1) as usize;
+        Box::new(self.tiles.iter().enumerate().filter_map(move |(i, &t)| {
+            if t.is_empty() {
+                return None;
+            }
+            let local = i % layer_len;
+            Some((
+                (i / layer_len) as u32,
+                (local % width) as i32,
+                (local / width) as i32,
+                t,
+            ))
+        }))
+    }
+
+    /// 遍历全部非空格子：(layer, x, y, tile)。
+    pub fn iter_tiles(&self) -> impl Iterator<Item = (u32, i32, i32, TileView<'_>)> + '_ {
+        self.iter_cells()
+            .filter_map(|(layer, x, y, cell)| Some((layer, x, y, self.view(cell)?)))
+    }
+
+    /// 某层在 `rect` 范围内的非空格子：(x, y, tile)。
+    ///
+    /// 无限地图只访问与范围相交且存在的 chunk，空白区域不产生开销。
+    pub fn tiles_in_rect(&self, layer: u32, rect: TileBounds) -> Vec<(i32, i32, TileView<'_>)> {
+        let mut out = Vec::new();
+        if layer >= self.layers {
+            return out;
+        }
+        if self.infinite {
+            for (key, chunk) in self.chunks.chunks_in_rect(layer, rect) {
+                let (ox, oy) = key.origin();
+                for (lx, ly, cell) in chunk.iter() {
+                    let (x, y) = (ox + lx as i32, oy + ly as i32);
+                    if !rect.contains(x, y) {
+                        continue;
+                    }
+                    if let Some(t) = self.view(cell) {
+                        out.push((x, y, t));
+                    }
+                }
+            }
+            return out;
+        }
+        if self.width == 0 || self.height == 0 {
+            return out;
+        }
+        let full = TileBounds::from_corners(0, 0, self.width as i32 - 1, self.height as i32 - 1);
+        let Some(rect) = rect.intersect(&full) else {
+            return out;
+        };
+        for y in rect.min_y..=rect.max_y {
+            for x in rect.min_x..=rect.max_x {
+                if let Some(t) = self.view(self.cell(layer, x, y)) {
+                    out.push((x, y, t));
+                }
+            }
+        }
+        out
+    }
+
+    /// 已绘制格子的包围盒（所有图层）；地图为空时返回 None。
+    pub fn used_bounds(&self) -> Option<TileBounds> {
+        let mut bounds: Option<TileBounds> = None;
+        for (_, x, y, _) in self.iter_cells() {
+            match bounds.as_mut() {
+                Some(b) => b.include(x, y),
+                None => bounds = Some(TileBounds::from_corners(x, y, x, y)),
+            }
+        }
+        bounds
+    }
+
+    /// 转为无限地图（坐标保持不变）。
+    pub fn to_infinite(&self) -> Self {
+        if self.infinite {
+            return self.clone();
+        }
+        let mut out = Self::new_infinite(self.layers);
+        out.layer_data = self.layer_data.clone();
+        out.tilesets = self.tilesets.clone();
+        for (layer, x, y, cell) in self.iter_cells() {
+            out.set_cell(layer, x, y, cell);
+        }
+        out.meta = self.meta.clone();
+        out
+    }
+
+    /// 转为固定尺寸地图：尺寸覆盖全部已绘制格子与原点；若有负坐标，整体平移到非负区域。
+    ///
+    /// 返回新地图与施加的平移量 (dx, dy)。
+    pub fn to_fixed(&self, min_width: u32, min_height: u32) -> (Self, (i32, i32)) {
+        if !self.infinite {
+            return (self.clone(), (0, 0));
+        }
+        let mut bounds = TileBounds::from_corners(
+            0,
+            0,
+            min_width.max(1) as i32 - 1,
+            min_height.max(1) as i32 - 1,
+        );
+        if let Some(used) = self.used_bounds() {
+            bounds.include(used.min_x, used.min_y);
+            bounds.include(used.max_x, used.max_y);
+        }
+        for (x, y, _) in self.meta.iter() {
+            bounds.include(x, y);
+        }
+        let (dx, dy) = (-bounds.min_x, -bounds.min_y);
+        let mut out = Self::new_with_layers(bounds.width(), bounds.height(), self.layers);
+        out.layer_data = self.layer_data.clone();
+        out.tilesets = self.tilesets.clone();
+        for (layer, x, y, cell) in self.iter_cells() {
+            out.set_cell(layer, x + dx, y + dy, cell);
+        }
+        out.meta = self.meta.translated(dx, dy);
+        (out, (dx, dy))
     }
 
     pub fn layer_len(&self) -> usize {
-        (self.width * self.height) as usize
+        self.width as usize * self.height as usize
     }
 
     pub fn idx_layer(&self, layer: u32, x: u32, y: u32) -> usize {
-        (layer as usize) * self.layer_len() + (y * self.width + x) as usize
+        (layer as usize) * self.layer_len() + y as usize * self.width as usize + x as usize
     }
 
     /// 兼容旧调用：等价于 layer 0。
@@ -100,7 +470,7 @@ impl TileMapData {
         self.idx_layer(0, x, y)
     }
 
-    /// 将地图升级为至少 `layers` 层：旧数据保持在 layer0，新层填 None。
+    /// 将地图升级为至少 `layers` 层：旧数据保持在 layer0，新层填空格子。
     pub fn ensure_layers(&mut self, layers: u32) {
         let layers = layers.max(1);
         if self.layers >= layers {
@@ -112,38 +482,67 @@ impl TileMapData {
                         name: format!("Layer {}", i + 1),
                         visible: true,
                         locked: false,
+                        opacity: 1.0,
                     });
                 }
             }
             return;
         }
-        let len = self.layer_len();
-        self.tiles.resize(len * layers as usize, None);
+        if !self.infinite {
+            let len = self.layer_len();
+            self.tiles.resize(len * layers as usize, TileCell::EMPTY);
+        }
         for i in self.layers..layers {
              self.layer_data.push(LayerData {
                 name: format!("Layer {}", i + 1),
                 visible: true,
                 locked: false,
+                opacity: 1.0,
             });
         }
         self.layers = layers;
     }
 
-    pub fn topmost_layer_at(&self, x: u32, y: u32) -> Option<u32> {
-        if self.layers == 0 {
-            return None;
+    /// 删除一层（其上各层下移一位）；只剩一层或越界时返回 false。
+    pub fn remove_layer(&mut self, layer: u32) -> bool {
+        if layer >= self.layers || self.layers <= 1 {
+            return false;
         }
-        for layer in (0..self.layers).rev() {
-            let idx = self.idx_layer(layer, x, y);
-            if self.tiles.get(idx).and_then(|t| t.as_ref()).is_some() {
-                return Some(layer);
-            }
+        let kept: Vec<(u32, i32, i32, TileCell)> =
+            self.iter_cells().filter(|&(l, _, _, _)| l != layer).collect();
+        self.layers -= 1;
+        if self.infinite {
+            self.chunks = TileChunks::default();
+        } else {
+            self.tiles = vec![TileCell::EMPTY; self.layer_len() * self.layers as usize];
         }
-        None
+        if (layer as usize) < self.layer_data.len() {
+            self.layer_data.remove(layer as usize);
+        }
+        for (l, x, y, cell) in kept {
+            let l = if l > layer { l - 1 } else { l };
+            self.set_cell(l, x, y, cell);
+        }
+        true
+    }
+
+    pub fn topmost_layer_at(&self, x: i32, y: i32) -> Option<u32> {
+        (0..self.layers)
+            .rev()
+            .find(|&layer| !self.cell(layer, x, y).is_empty())
+    }
+
+    /// `layers`（从下到上排列）中该格非空的最上层；越界图层忽略。
+    pub fn topmost_layer_among(&self, layers: &[u32], x: i32, y: i32) -> Option<u32> {
+        layers
+            .iter()
+            .rev()
+            .copied()
+            .find(|&layer| !self.cell(layer, x, y).is_empty())
     }
 
-    pub fn topmost_idx_at(&self, x: u32, y: u32) -> Option<usize> {
+    pub fn topmost_tile_at(&self, x: i32, y: i32) -> Option<TileView<'_>> {
         let layer = self.topmost_layer_at(x, y)?;
-        Some(self.idx_layer(layer, x, y))
+        self.view(self.cell(layer, x, y))
     }
 }
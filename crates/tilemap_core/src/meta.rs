@@ -0,0 +1,312 @@
+//! 地图元数据网格（RPG Maker 的区域 / 通行 / 地形标记）：与图层无关，每个格子一份。
+//!
+//! 稀疏存储，全 0 的格子不保存；固定尺寸与无限地图共用同一结构。
+
+use std::collections::{BTreeMap, HashMap};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::ops::FillMode;
+use crate::TileBounds;
+
+/// 通行标记：置位表示该方向不可通行（全 0 = 四向可通行）。
+pub const PASS_BLOCK_DOWN: u8 = 1;
+pub const PASS_BLOCK_LEFT: u8 = 2;
+pub const PASS_BLOCK_RIGHT: u8 = 4;
+pub const PASS_BLOCK_UP: u8 = 8;
+pub const PASS_BLOCK_ALL: u8 = 0b1111;
+
+/// 单个格子的元数据。
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CellMeta {
+    /// 区域 id（0 = 无区域）。
+    pub region: u8,
+    /// `PASS_BLOCK_*` 位组合。
+    pub passage: u8,
+    /// 地形标记（0 = 无）。
+    pub terrain: u8,
+}
+
+impl CellMeta {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn get(&self, field: MetaField) -> u8 {
+        match field {
+            MetaField::Region => self.region,
+            MetaField::Passage => self.passage,
+            MetaField::Terrain => self.terrain,
+        }
+    }
+
+    /// 只改一个字段，其余保持不变。
+    pub fn with(mut self, field: MetaField, value: u8) -> Self {
+        match field {
+            MetaField::Region => self.region = value,
+            MetaField::Passage => self.passage = value & PASS_BLOCK_ALL,
+            MetaField::Terrain => self.terrain = value,
+        }
+        self
+    }
+}
+
+/// 元数据的三个字段（Meta 模式下工具写入的目标）。
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum MetaField {
+    #[default]
+    Region,
+    Passage,
+    Terrain,
+}
+
+/// 存档里的一条记录（只写非 0 字段）。
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug)]
+struct MetaRecord {
+    x: i32,
+    y: i32,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "is_zero"))]
+    region: u8,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "is_zero"))]
+    passage: u8,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "is_zero"))]
+    terrain: u8,
+}
+
+#[cfg(feature = "serde")]
+fn is_zero(v: &u8) -> bool {
+    *v == 0
+}
+
+/// 稀疏元数据网格：(x, y) -> 非空 `CellMeta`。
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "Vec<MetaRecord>", into = "Vec<MetaRecord>"))]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MetaGrid {
+    cells: BTreeMap<(i32, i32), CellMeta>,
+}
+
+impl MetaGrid {
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn get(&self, x: i32, y: i32) -> CellMeta {
+        self.cells.get(&(x, y)).copied().unwrap_or_default()
+    }
+
+    /// 写入并返回旧值；空值会删除记录。
+    pub fn set(&mut self, x: i32, y: i32, meta: CellMeta) -> CellMeta {
+        let before = if meta.is_empty() {
+            self.cells.remove(&(x, y))
+        } else {
+            self.cells.insert((x, y), meta)
+        };
+        before.unwrap_or_default()
+    }
+
+    /// 遍历非空格子：(x, y, meta)，按 (x, y) 排序。
+    pub fn iter(&self) -> impl Iterator<Item = (i32, i32, CellMeta)> + '_ {
+        self.cells.iter().map(|(&(x, y), &m)| (x, y, m))
+    }
+
+    /// `rect` 范围内的非空格子。
+    pub fn in_rect(&self, rect: TileBounds) -> impl Iterator<Item = (i32, i32, CellMeta)> + '_ {
+        self.cells
+            .range((rect.min_x, i32::MIN)..=(rect.max_x, i32::MAX))
+            .filter(move |((_, y), _)| *y >= rect.min_y && *y <= rect.max_y)
+            .map(|(&(x, y), &m)| (x, y, m))
+    }
+
+    /// 只保留满足条件的格子（改尺寸时裁掉越界元数据）。
+    pub fn retain(&mut self, mut keep: impl FnMut(i32, i32) -> bool) {
+        self.cells.retain(|&(x, y), _| keep(x, y));
+    }
+
+    /// 整体平移（无限地图转固定尺寸时使用）。
+    pub fn translated(&self, dx: i32, dy: i32) -> Self {
+        Self {
+            cells: self.cells.iter().map(|(&(x, y), &m)| ((x + dx, y + dy), m)).collect(),
+        }
+    }
+}
+
+impl From<Vec<MetaRecord>> for MetaGrid {
+    fn from(records: Vec<MetaRecord>) -> Self {
+        let mut grid = MetaGrid::default();
+        for r in records {
+            let meta = CellMeta {
+                region: r.region,
+                passage: r.passage & PASS_BLOCK_ALL,
+                terrain: r.terrain,
+            };
+            grid.set(r.x, r.y, meta);
+        }
+        grid
+    }
+}
+
+impl From<MetaGrid> for Vec<MetaRecord> {
+    fn from(grid: MetaGrid) -> Self {
+        grid.iter()
+            .map(|(x, y, m)| MetaRecord {
+                x,
+                y,
+                region: m.region,
+                passage: m.passage,
+                terrain: m.terrain,
+            })
+            .collect()
+    }
+}
+
+/// 单个格子的元数据变更（撤销栈用）。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MetaChange {
+    pub x: i32,
+    pub y: i32,
+    pub before: CellMeta,
+    pub after: CellMeta,
+}
+
+pub type MetaChangeSet = Vec<MetaChange>;
+
+/// 边写边记：同一格子只保留最早的 before 与最后的 after。
+#[derive(Default)]
+pub struct MetaRecorder {
+    index: HashMap<(i32, i32), usize>,
+    changes: MetaChangeSet,
+}
+
+impl MetaRecorder {
+    /// 把 (x, y) 的 `field` 改为 `value`（越界忽略）。
+    pub fn set(&mut self, map: &mut crate::TileMapData, x: i32, y: i32, field: MetaField, value: u8) {
+        if !map.contains(x, y) {
+            return;
+        }
+        let after = map.meta.get(x, y).with(field, value);
+        let before = map.meta.set(x, y, after);
+        match self.index.get(&(x, y)) {
+            Some(&i) => self.changes[i].after = after,
+            None => {
+                self.index.insert((x, y), self.changes.len());
+                self.changes.push(MetaChange { x, y, before, after });
+            }
+        }
+    }
+
+    pub fn finish(self) -> MetaChangeSet {
+        self.changes.into_iter().filter(|c| c.before != c.after).collect()
+    }
+}
+
+/// 撤销：按逆序写回 before。
+pub fn revert_meta(map: &mut crate::TileMapData, changes: &[MetaChange]) {
+    for ch in changes.iter().rev() {
+        map.meta.set(ch.x, ch.y, ch.before);
+    }
+}
+
+/// 重做：按顺序写入 after。
+pub fn reapply_meta(map: &mut crate::TileMapData, changes: &[MetaChange]) {
+    for ch in changes {
+        map.meta.set(ch.x, ch.y, ch.after);
+    }
+}
+
+/// 把矩形内每个格子的 `field` 设为 `value`。
+pub fn fill_meta_rect(
+    map: &mut crate::TileMapData,
+    bounds: TileBounds,
+    field: MetaField,
+    value: u8,
+) -> MetaChangeSet {
+    let mut rec = MetaRecorder::default();
+    for y in bounds.min_y..=bounds.max_y {
+        for x in bounds.min_x..=bounds.max_x {
+            rec.set(map, x, y, field, value);
+        }
+    }
+    rec.finish()
+}
+
+/// 按 `mode`（4 / 8 邻接连通或整层）填充 `field` 值与起点相同的格子（无限地图限制在 `ops::fill_limit` 范围内）。
+///
+/// 元数据与图层无关，只比较 `field` 本身，不看 tile。
+pub fn flood_fill_meta(
+    map: &mut crate::TileMapData,
+    x: i32,
+    y: i32,
+    field: MetaField,
+    value: u8,
+    mode: FillMode,
+) -> MetaChangeSet {
+    if !map.contains(x, y) {
+        return Vec::new();
+    }
+    let target = map.meta.get(x, y).get(field);
+    if target == map.meta.get(x, y).with(field, value).get(field) {
+        return Vec::new();
+    }
+    let region = crate::ops::region_where(map, x, y, mode, |cx, cy| map.meta.get(cx, cy).get(field) == target);
+    let mut rec = MetaRecorder::default();
+    for (cx, cy) in region {
+        rec.set(map, cx, cy, field, value);
+    }
+    rec.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TileMapData;
+
+    #[test]
+    fn meta_fill_and_undo_keep_other_fields() {
+        let mut map = TileMapData::new_with_layers(4, 3, 1);
+        let wall = fill_meta_rect(&mut map, TileBounds::from_corners(0, 0, 1, 2), MetaField::Passage, PASS_BLOCK_ALL);
+        assert_eq!(wall.len(), 6);
+
+        // 区域填充只看 region 字段：通行标记不影响连通性，也不会被覆盖。
+        let region = flood_fill_meta(&mut map, 3, 0, MetaField::Region, 7, FillMode::Contiguous);
+        assert_eq!(region.len(), 12);
+        assert_eq!(map.meta.get(0, 0), CellMeta { region: 7, passage: PASS_BLOCK_ALL, terrain: 0 });
+        assert!(flood_fill_meta(&mut map, 3, 0, MetaField::Region, 7, FillMode::Contiguous).is_empty());
+
+        // 越界写入忽略；写回 0 会删掉空记录。
+        assert!(fill_meta_rect(&mut map, TileBounds::from_corners(4, 0, 5, 0), MetaField::Terrain, 1).is_empty());
+        revert_meta(&mut map, &region);
+        assert_eq!(map.meta.len(), 6);
+        revert_meta(&mut map, &wall);
+        assert!(map.meta.is_empty());
+        reapply_meta(&mut map, &wall);
+        assert_eq!(map.meta.get(1, 2).passage, PASS_BLOCK_ALL);
+    }
+
+    #[test]
+    fn meta_fill_follows_fill_mode() {
+        let mut map = TileMapData::new_with_layers(4, 3, 1);
+        let mut seed = MetaRecorder::default();
+        for (x, y) in [(0, 0), (1, 1), (3, 0)] {
+            seed.set(&mut map, x, y, MetaField::Region, 1);
+        }
+        let seeded = seed.finish();
+
+        for (mode, filled) in [(FillMode::Contiguous, 1), (FillMode::Diagonal, 2), (FillMode::Global, 3)] {
+            let changes = flood_fill_meta(&mut map, 0, 0, MetaField::Region, 5, mode);
+            assert_eq!(changes.len(), filled, "{mode:?}");
+            revert_meta(&mut map, &changes);
+        }
+        // 比较的是元数据本身，空白格子（region 0）也能整层填充。
+        let blank = flood_fill_meta(&mut map, 2, 2, MetaField::Region, 2, FillMode::Global);
+        assert_eq!(blank.len(), 12 - seeded.len());
+    }
+}
@@ -0,0 +1,1267 @@
+//! 区域操作（纯逻辑，不依赖 Bevy）：填充、拷贝/盖章（含印章笔刷与图案填充）、平移、替换、清理、合并图层、改尺寸。
+//!
+//! 所有操作都直接修改地图，并返回“净变更集”（每个格子最多一条，且 before != after），
+//! 可原样放进撤销栈；`revert` / `reapply` 负责回放。
+//!
+//! 坐标约定：地图格子坐标 y 向上（与编辑器渲染一致），因此格子位置按 `Orientation::y_up` 变换，
+//! 图块自身朝向按图像坐标系复合。
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+#[cfg(feature = "bevy")]
+use bevy::prelude::Resource;
+
+use crate::{
+    reapply_meta, CellMeta, MetaChange, MetaChangeSet, Orientation, TileBounds, TileCell, TileMapData, TileRef, TileView,
+};
+
+/// 单个格子的变更（slot 指向所在地图的 tileset 表）。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CellChange {
+    pub layer: u32,
+    /// 格子坐标（无限地图可为负）。
+    pub x: i32,
+    pub y: i32,
+    pub before: TileCell,
+    pub after: TileCell,
+}
+
+/// 一次操作产生的变更集。
+pub type ChangeSet = Vec<CellChange>;
+
+/// 撤销：按逆序写回 before。
+pub fn revert(map: &mut TileMapData, changes: &[CellChange]) {
+    for ch in changes.iter().rev() {
+        map.set_cell(ch.layer, ch.x, ch.y, ch.before);
+    }
+}
+
+/// 重做：按顺序写入 after。
+pub fn reapply(map: &mut TileMapData, changes: &[CellChange]) {
+    for ch in changes {
+        map.set_cell(ch.layer, ch.x, ch.y, ch.after);
+    }
+}
+
+/// 边写边记：同一格子多次写入时只保留最早的 before 与最后的 after。
+#[derive(Default)]
+pub(crate) struct Recorder {
+    index: HashMap<(u32, i32, i32), usize>,
+    changes: ChangeSet,
+}
+
+impl Recorder {
+    pub(crate) fn set(&mut self, map: &mut TileMapData, layer: u32, x: i32, y: i32, after: TileCell) {
+        if !map.writable(layer, x, y) {
+            return;
+        }
+        let before = map.set_cell(layer, x, y, after);
+        match self.index.get(&(layer, x, y)) {
+            Some(&i) => self.changes[i].after = after,
+            None => {
+                self.index.insert((layer, x, y), self.changes.len());
+                self.changes.push(CellChange { layer, x, y, before, after });
+            }
+        }
+    }
+
+    /// 先检查边界再 pack：越界的格子不会把 tileset 登记进只增不减的 tileset 表。
+    pub(crate) fn set_tile(&mut self, map: &mut TileMapData, layer: u32, x: i32, y: i32, tile: Option<&TileRef>) {
+        if !map.writable(layer, x, y) {
+            return;
+        }
+        let cell = map.pack(tile);
+        self.set(map, layer, x, y, cell);
+    }
+
+    pub(crate) fn finish(self) -> ChangeSet {
+        self.changes.into_iter().filter(|c| c.before != c.after).collect()
+    }
+}
+
+/// 拷贝出来的矩形区域（与具体地图的 tileset 表无关，可跨地图粘贴）。
+#[cfg_attr(feature = "bevy", derive(Resource))]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TileRegion {
+    pub width: u32,
+    pub height: u32,
+    /// 行优先，`tiles[y * width + x]`。
+    pub tiles: Vec<Option<TileRef>>,
+}
+
+impl TileRegion {
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0 || self.tiles.is_empty()
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> Option<&TileRef> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        self.tiles.get((y * self.width + x) as usize)?.as_ref()
+    }
+}
+
+/// 可填充范围：固定尺寸地图为整张地图；无限地图为“已绘制内容 ∪ 起点”的包围盒，避免无限扩散。
+pub fn fill_limit(map: &TileMapData, x: i32, y: i32) -> TileBounds {
+    match map.fixed_size() {
+        Some((w, h)) => TileBounds::from_corners(0, 0, w as i32 - 1, h as i32 - 1),
+        None => {
+            let mut b = map.used_bounds().unwrap_or(TileBounds::from_corners(x, y, x, y));
+            b.include(x, y);
+            b
+        }
+    }
+}
+
+/// 先 4 邻接、后对角，保持 4 邻接时的 BFS 顺序不变。
+const NEIGHBORS_8: [(i32, i32); 8] = [(-1, 0), (1, 0), (0, -1), (0, 1), (-1, -1), (1, -1), (-1, 1), (1, 1)];
+
+/// 油漆桶：从 `(x, y)` 起按 4 邻接把与起点相同的连通格子改成 `fill`。
+pub fn flood_fill(map: &mut TileMapData, layer: u32, x: i32, y: i32, fill: TileCell) -> ChangeSet {
+    let mut changes = ChangeSet::new();
+    if layer >= map.layers || !map.contains(x, y) || map.cell(layer, x, y) == fill {
+        return changes;
+    }
+    for (x, y) in connected_region(map, layer, x, y) {
+        let before = map.set_cell(layer, x, y, fill);
+        changes.push(CellChange { layer, x, y, before, after: fill });
+    }
+    changes
+}
+
+/// 油漆桶选取格子的方式。
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FillMode {
+    /// 4 邻接连通区域。
+    #[default]
+    Contiguous,
+    /// 8 邻接（含对角）连通区域。
+    Diagonal,
+    /// 不要求连通：图层上所有与起点相同的格子。
+    Global,
+}
+
+impl FillMode {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Contiguous => Self::Diagonal,
+            Self::Diagonal => Self::Global,
+            Self::Global => Self::Contiguous,
+        }
+    }
+}
+
+/// 按 `mode` 选出与 `(x, y)` 内容相同的格子（范围见 `fill_limit`）。
+///
+/// 内容按 `sample` 图层合成比较：每格取其中最上层的非空格子（只有一层时即该层的格子），
+/// 例如取样地面 + 墙壁两层、写入另一层时，墙壁成为填充边界。
+pub fn fill_region(map: &TileMapData, sample: &[u32], x: i32, y: i32, mode: FillMode) -> Vec<(i32, i32)> {
+    if !samples_map(map, sample, x, y) {
+        return Vec::new();
+    }
+    let target = sampled_cell(map, sample, x, y);
+    region_where(map, x, y, mode, |cx, cy| sampled_cell(map, sample, cx, cy) == target)
+}
+
+/// 与 `(x, y)` 内容相同、4 邻接连通的格子（按 BFS 顺序，范围见 `fill_limit`）。
+pub fn connected_region(map: &TileMapData, layer: u32, x: i32, y: i32) -> Vec<(i32, i32)> {
+    fill_region(map, &[layer], x, y, FillMode::Contiguous)
+}
+
+fn samples_map(map: &TileMapData, sample: &[u32], x: i32, y: i32) -> bool {
+    map.contains(x, y) && sample.iter().any(|&l| l < map.layers)
+}
+
+fn sampled_cell(map: &TileMapData, sample: &[u32], x: i32, y: i32) -> TileCell {
+    map.topmost_layer_among(sample, x, y)
+        .map_or(TileCell::EMPTY, |l| map.cell(l, x, y))
+}
+
+/// 按 `mode` 选出满足 `same` 的格子：连通方式从 `(x, y)` 起 BFS，整层方式逐行扫描 `fill_limit`。
+///
+/// 调用方保证起点在地图内且满足 `same`。
+pub(crate) fn region_where(
+    map: &TileMapData,
+    x: i32,
+    y: i32,
+    mode: FillMode,
+    same: impl Fn(i32, i32) -> bool,
+) -> Vec<(i32, i32)> {
+    let limit = fill_limit(map, x, y);
+    let diagonal = match mode {
+        FillMode::Contiguous => false,
+        FillMode::Diagonal => true,
+        FillMode::Global => {
+            let mut region = Vec::new();
+            for cy in limit.min_y..=limit.max_y {
+                for cx in limit.min_x..=limit.max_x {
+                    if same(cx, cy) {
+                        region.push((cx, cy));
+                    }
+                }
+            }
+            return region;
+        }
+    };
+
+    let mut region = Vec::new();
+    let mut visited: HashSet<(i32, i32)> = HashSet::new();
+    let mut q = VecDeque::new();
+    visited.insert((x, y));
+    q.push_back((x, y));
+
+    while let Some((x, y)) = q.pop_front() {
+        if !same(x, y) {
+            continue;
+        }
+        region.push((x, y));
+
+        for (dx, dy) in NEIGHBORS_8 {
+            if !diagonal && dx != 0 && dy != 0 {
+                continue;
+            }
+            let (nx, ny) = (x + dx, y + dy);
+            if limit.contains(nx, ny) && visited.insert((nx, ny)) {
+                q.push_back((nx, ny));
+            }
+        }
+    }
+    region
+}
+
+/// 矩形填充（越界部分忽略）；`fill` 为空格子即清除。
+pub fn fill_rect(map: &mut TileMapData, layer: u32, bounds: TileBounds, fill: TileCell) -> ChangeSet {
+    let mut rec = Recorder::default();
+    for y in bounds.min_y..=bounds.max_y {
+        for x in bounds.min_x..=bounds.max_x {
+            rec.set(map, layer, x, y, fill);
+        }
+    }
+    rec.finish()
+}
+
+/// 把一组格子（直线、椭圆等形状）写成 `fill`；重复与越界的格子忽略。
+pub fn fill_cells(map: &mut TileMapData, layer: u32, cells: &[(i32, i32)], fill: TileCell) -> ChangeSet {
+    let mut rec = Recorder::default();
+    for &(x, y) in cells {
+        rec.set(map, layer, x, y, fill);
+    }
+    rec.finish()
+}
+
+/// 拷贝矩形区域（越界格子视为空）。
+pub fn extract_region(map: &TileMapData, layer: u32, bounds: TileBounds) -> TileRegion {
+    let (width, height) = (bounds.width(), bounds.height());
+    let mut tiles = Vec::with_capacity((width * height) as usize);
+    for y in bounds.min_y..=bounds.max_y {
+        for x in bounds.min_x..=bounds.max_x {
+            tiles.push(map.get(layer, x, y));
+        }
+    }
+    TileRegion { width, height, tiles }
+}
+
+/// 把区域按 `orientation` 变换后盖到 `(x, y)`（变换后区域的左下角）。
+///
+/// 区域里的空格子同样会覆盖地图；越界部分忽略。
+pub fn stamp_region(
+    map: &mut TileMapData,
+    layer: u32,
+    region: &TileRegion,
+    x: i32,
+    y: i32,
+    orientation: Orientation,
+) -> ChangeSet {
+    let mut rec = Recorder::default();
+    stamp_into(&mut rec, map, layer, region, x, y, orientation);
+    rec.finish()
+}
+
+fn stamp_into(
+    rec: &mut Recorder,
+    map: &mut TileMapData,
+    layer: u32,
+    region: &TileRegion,
+    x: i32,
+    y: i32,
+    orientation: Orientation,
+) {
+    for (cx, cy, tile) in oriented_tiles(region, orientation) {
+        rec.set_tile(map, layer, x + cx as i32, y + cy as i32, tile.as_ref());
+    }
+}
+
+/// 印章笔刷：与 `stamp_region` 相同的变换与定位，但只写非空格子（空格子保留地图原样）。
+pub fn stamp_tiles(
+    map: &mut TileMapData,
+    layer: u32,
+    region: &TileRegion,
+    x: i32,
+    y: i32,
+    orientation: Orientation,
+) -> ChangeSet {
+    let mut rec = Recorder::default();
+    for (cx, cy, tile) in oriented_tiles(region, orientation) {
+        if tile.is_some() {
+            rec.set_tile(map, layer, x + cx as i32, y + cy as i32, tile.as_ref());
+        }
+    }
+    rec.finish()
+}
+
+/// 按 Tiled 的做法平铺的印章图案：变换后的左上角对齐一组格子外接框的左上角，向四周重复。
+pub struct TilePattern {
+    width: u32,
+    height: u32,
+    tiles: Vec<Option<TileRef>>,
+    anchor: (i32, i32),
+}
+
+impl TilePattern {
+    /// `cells` 为空或印章为空尺寸时返回 `None`。
+    pub fn new(region: &TileRegion, orientation: Orientation, cells: &[(i32, i32)]) -> Option<Self> {
+        let (width, height) = orientation.dims(region.width, region.height);
+        let min_x = cells.iter().map(|c| c.0).min()?;
+        let max_y = cells.iter().map(|c| c.1).max()?;
+        if width == 0 || height == 0 {
+            return None;
+        }
+        let mut tiles = vec![None; (width * height) as usize];
+        for (cx, cy, tile) in oriented_tiles(region, orientation) {
+            tiles[(cy * width + cx) as usize] = tile;
+        }
+        // y 向上：图案顶行落在外接框的 max_y。
+        let anchor = (min_x, max_y + 1 - height as i32);
+        Some(Self { width, height, tiles, anchor })
+    }
+
+    /// 格子 `(x, y)` 上的图案 tile（图案的空格子为 `None`）。
+    pub fn tile_at(&self, x: i32, y: i32) -> Option<&TileRef> {
+        let lx = (x - self.anchor.0).rem_euclid(self.width as i32) as u32;
+        let ly = (y - self.anchor.1).rem_euclid(self.height as i32) as u32;
+        self.tiles[(ly * self.width + lx) as usize].as_ref()
+    }
+}
+
+/// 用印章图案填满 `cells`（矩形、油漆桶区域等），平铺方式见 `TilePattern`；图案里的空格子保留地图原样。
+pub fn fill_pattern(
+    map: &mut TileMapData,
+    layer: u32,
+    cells: &[(i32, i32)],
+    region: &TileRegion,
+    orientation: Orientation,
+) -> ChangeSet {
+    let mut rec = Recorder::default();
+    let Some(pattern) = TilePattern::new(region, orientation, cells) else {
+        return rec.finish();
+    };
+    for &(x, y) in cells {
+        if let Some(tile) = pattern.tile_at(x, y) {
+            rec.set_tile(map, layer, x, y, Some(tile));
+        }
+    }
+    rec.finish()
+}
+
+/// 区域按 `orientation` 变换后的格子：(变换后的局部坐标, 变换后的 tile)。
+fn oriented_tiles(region: &TileRegion, orientation: Orientation) -> impl Iterator<Item = (u32, u32, Option<TileRef>)> + '_ {
+    let cell_op = orientation.y_up();
+    (0..region.height)
+        .flat_map(move |sy| (0..region.width).map(move |sx| (sx, sy)))
+        .filter_map(move |(sx, sy)| {
+            let (cx, cy) = cell_op.transform_cell(sx, sy, region.width, region.height)?;
+            let tile = region.get(sx, sy).map(|t| {
+                let mut t = t.clone();
+                t.transform(orientation);
+                t
+            });
+            Some((cx, cy, tile))
+        })
+}
+
+/// 原地变换矩形区域：清空原区域，再把变换结果盖回（左下角不变）。
+///
+/// 旋转 90° 时宽高互换，结果可能超出原区域；越界部分忽略。
+pub fn transform_region(
+    map: &mut TileMapData,
+    layer: u32,
+    bounds: TileBounds,
+    orientation: Orientation,
+) -> ChangeSet {
+    relocate(map, layer, bounds, bounds.min_x, bounds.min_y, orientation)
+}
+
+/// 把矩形区域整体移动 `(dx, dy)`：原区域清空，目标区域写入（越界部分忽略）。
+pub fn move_region(map: &mut TileMapData, layer: u32, bounds: TileBounds, dx: i32, dy: i32) -> ChangeSet {
+    relocate(map, layer, bounds, bounds.min_x + dx, bounds.min_y + dy, Orientation::IDENTITY)
+}
+
+fn relocate(
+    map: &mut TileMapData,
+    layer: u32,
+    bounds: TileBounds,
+    x: i32,
+    y: i32,
+    orientation: Orientation,
+) -> ChangeSet {
+    let region = extract_region(map, layer, bounds);
+    let mut rec = Recorder::default();
+    for y in bounds.min_y..=bounds.max_y {
+        for x in bounds.min_x..=bounds.max_x {
+            rec.set(map, layer, x, y, TileCell::EMPTY);
+        }
+    }
+    stamp_into(&mut rec, map, layer, &region, x, y, orientation);
+    rec.finish()
+}
+
+/// 一组格子（任意形状的选区）的包围盒。
+pub fn cells_bounds(cells: &[(i32, i32)]) -> Option<TileBounds> {
+    let (&(x, y), rest) = cells.split_first()?;
+    let mut bounds = TileBounds::from_corners(x, y, x, y);
+    for &(x, y) in rest {
+        bounds.include(x, y);
+    }
+    Some(bounds)
+}
+
+/// 拷贝一组格子：区域为其包围盒，集合以外的位置为 None（越界格子视为空）。
+pub fn extract_cells(map: &TileMapData, layer: u32, cells: &[(i32, i32)]) -> TileRegion {
+    let Some(bounds) = cells_bounds(cells) else {
+        return TileRegion::default();
+    };
+    let (width, height) = (bounds.width(), bounds.height());
+    let mut tiles = vec![None; (width * height) as usize];
+    for &(x, y) in cells {
+        let i = (y - bounds.min_y) as u32 * width + (x - bounds.min_x) as u32;
+        tiles[i as usize] = map.get(layer, x, y);
+    }
+    TileRegion { width, height, tiles }
+}
+
+/// 一组格子按 `orientation` 变换后的位置（与 `transform_region` 一样保持包围盒左下角不变），顺序与 `cells` 对应。
+pub fn oriented_cells(cells: &[(i32, i32)], orientation: Orientation) -> Vec<(i32, i32)> {
+    let Some(bounds) = cells_bounds(cells) else {
+        return Vec::new();
+    };
+    let (w, h) = (bounds.width(), bounds.height());
+    let cell_op = orientation.y_up();
+    cells
+        .iter()
+        .map(|&(x, y)| {
+            let (sx, sy) = ((x - bounds.min_x) as u32, (y - bounds.min_y) as u32);
+            // 格子都在包围盒内，变换不会越界。
+            let (cx, cy) = cell_op.transform_cell(sx, sy, w, h).unwrap_or((sx, sy));
+            (bounds.min_x + cx as i32, bounds.min_y + cy as i32)
+        })
+        .collect()
+}
+
+/// 把一组格子移动 `(dx, dy)`：原格子清空，目标格子写入（含空格子）；集合以外的地图内容不受影响。
+pub fn move_cells(map: &mut TileMapData, layer: u32, cells: &[(i32, i32)], dx: i32, dy: i32) -> ChangeSet {
+    relocate_cells(map, layer, cells, dx, dy, Orientation::IDENTITY, true)
+}
+
+/// 与 `move_cells` 相同，但保留原格子。
+pub fn copy_cells(map: &mut TileMapData, layer: u32, cells: &[(i32, i32)], dx: i32, dy: i32) -> ChangeSet {
+    relocate_cells(map, layer, cells, dx, dy, Orientation::IDENTITY, false)
+}
+
+/// 原地变换一组格子（位置见 `oriented_cells`，tile 的朝向一并变换）；越界部分忽略。
+pub fn transform_cells(
+    map: &mut TileMapData,
+    layer: u32,
+    cells: &[(i32, i32)],
+    orientation: Orientation,
+) -> ChangeSet {
+    relocate_cells(map, layer, cells, 0, 0, orientation, true)
+}
+
+fn relocate_cells(
+    map: &mut TileMapData,
+    layer: u32,
+    cells: &[(i32, i32)],
+    dx: i32,
+    dy: i32,
+    orientation: Orientation,
+    clear_source: bool,
+) -> ChangeSet {
+    let tiles: Vec<Option<TileRef>> = cells.iter().map(|&(x, y)| map.get(layer, x, y)).collect();
+    let mut rec = Recorder::default();
+    if clear_source {
+        for &(x, y) in cells {
+            rec.set(map, layer, x, y, TileCell::EMPTY);
+        }
+    }
+    for ((x, y), tile) in oriented_cells(cells, orientation).into_iter().zip(tiles) {
+        let tile = tile.map(|mut t| {
+            t.transform(orientation);
+            t
+        });
+        rec.set_tile(map, layer, x + dx, y + dy, tile.as_ref());
+    }
+    rec.finish()
+}
+
+/// 整体平移全部图层与元数据，返回 tile 与元数据两份变更。
+///
+/// `wrap` 只对固定尺寸地图有意义：移出边界的格子从另一侧回来；否则直接丢弃。
+pub fn shift(map: &mut TileMapData, dx: i32, dy: i32, wrap: bool) -> (ChangeSet, MetaChangeSet) {
+    let fixed = map.fixed_size();
+    if matches!(fixed, Some((0, _)) | Some((_, 0))) {
+        return (ChangeSet::new(), MetaChangeSet::new());
+    }
+    let target = |x: i32, y: i32| match fixed {
+        Some((w, h)) if wrap => ((x + dx).rem_euclid(w as i32), (y + dy).rem_euclid(h as i32)),
+        _ => (x + dx, y + dy),
+    };
+
+    // 先计算平移后的内容，再与当前内容对比生成变更。
+    let mut moved: HashMap<(u32, i32, i32), TileCell> = HashMap::new();
+    for (layer, x, y, cell) in map.iter_cells() {
+        let (nx, ny) = target(x, y);
+        if map.contains(nx, ny) {
+            moved.insert((layer, nx, ny), cell);
+        }
+    }
+
+    let mut cells: Vec<(u32, i32, i32)> = map
+        .iter_cells()
+        .map(|(layer, x, y, _)| (layer, x, y))
+        .chain(moved.keys().copied())
+        .collect();
+    cells.sort();
+    cells.dedup();
+
+    let mut changes = ChangeSet::new();
+    for (layer, x, y) in cells {
+        let before = map.cell(layer, x, y);
+        let after = moved.get(&(layer, x, y)).copied().unwrap_or_default();
+        if before != after {
+            changes.push(CellChange { layer, x, y, before, after });
+        }
+    }
+    reapply(map, &changes);
+
+    // 元数据按同样的规则平移。
+    let mut moved_meta: HashMap<(i32, i32), CellMeta> = HashMap::new();
+    for (x, y, meta) in map.meta.iter() {
+        let (nx, ny) = target(x, y);
+        if map.contains(nx, ny) {
+            moved_meta.insert((nx, ny), meta);
+        }
+    }
+    let mut spots: Vec<(i32, i32)> = map
+        .meta
+        .iter()
+        .map(|(x, y, _)| (x, y))
+        .chain(moved_meta.keys().copied())
+        .collect();
+    spots.sort();
+    spots.dedup();
+
+    let mut meta_changes = MetaChangeSet::new();
+    for (x, y) in spots {
+        let before = map.meta.get(x, y);
+        let after = moved_meta.get(&(x, y)).copied().unwrap_or_default();
+        if before != after {
+            meta_changes.push(MetaChange { x, y, before, after });
+        }
+    }
+    reapply_meta(map, &meta_changes);
+    (changes, meta_changes)
+}
+
+/// 把 `from` 全部替换成 `to`；`layer` 为 None 时作用于所有图层。
+pub fn replace_all(map: &mut TileMapData, layer: Option<u32>, from: TileCell, to: TileCell) -> ChangeSet {
+    if from == to {
+        return ChangeSet::new();
+    }
+    let hits: Vec<(u32, i32, i32)> = map
+        .iter_cells()
+        .filter(|&(l, _, _, cell)| cell == from && layer.is_none_or(|want| want == l))
+        .map(|(l, x, y, _)| (l, x, y))
+        .collect();
+    let mut changes = ChangeSet::with_capacity(hits.len());
+    for (l, x, y) in hits {
+        let before = map.set_cell(l, x, y, to);
+        changes.push(CellChange { layer: l, x, y, before, after: to });
+    }
+    changes
+}
+
+/// 把 `layers` 中 `area` 为真的格子里等于 `from` 的换成 `to`（None 为清空）。
+///
+/// `ignore_orientation` 时只比较 tileset 与 index，替换后的 tile 沿用原格子的朝向。
+pub fn replace_tiles(
+    map: &mut TileMapData,
+    layers: &[u32],
+    area: impl Fn(i32, i32) -> bool,
+    from: &TileRef,
+    to: Option<&TileRef>,
+    ignore_orientation: bool,
+) -> ChangeSet {
+    let hits: Vec<(u32, i32, i32, Orientation)> = map
+        .iter_cells()
+        .filter(|&(l, x, y, _)| layers.contains(&l) && area(x, y))
+        .filter_map(|(l, x, y, cell)| {
+            let v = map.view(cell)?;
+            let same = if ignore_orientation {
+                *v.tileset_id == from.tileset_id && v.index == from.index
+            } else {
+                v == *from
+            };
+            same.then(|| (l, x, y, v.orientation()))
+        })
+        .collect();
+    let mut rec = Recorder::default();
+    for (l, x, y, orientation) in hits {
+        let after = match to {
+            Some(to) if ignore_orientation => {
+                let mut tile = to.clone();
+                tile.set_orientation(orientation);
+                map.pack(Some(&tile))
+            }
+            to => map.pack(to),
+        };
+        rec.set(map, l, x, y, after);
+    }
+    rec.finish()
+}
+
+/// 清空所有图层中 `pred` 为真的格子（例如 tile index 超出图集范围的格子）。
+pub fn clear_where(
+    map: &mut TileMapData,
+    mut pred: impl FnMut(u32, i32, i32, TileView<'_>) -> bool,
+) -> ChangeSet {
+    let hits: Vec<(u32, i32, i32)> = map
+        .iter_cells()
+        .filter(|&(l, x, y, cell)| map.view(cell).is_some_and(|v| pred(l, x, y, v)))
+        .map(|(l, x, y, _)| (l, x, y))
+        .collect();
+    let mut changes = ChangeSet::with_capacity(hits.len());
+    for (l, x, y) in hits {
+        let before = map.set_cell(l, x, y, TileCell::EMPTY);
+        changes.push(CellChange { layer: l, x, y, before, after: TileCell::EMPTY });
+    }
+    changes
+}
+
+/// 把引用 tileset `from` 的格子全部改为引用 `to`（index 与朝向不变），例如重新链接到内容不同的图片后。
+///
+/// `from` 不在地图里或 tileset 表已满时不做任何事。
+pub fn remap_tileset(map: &mut TileMapData, from: &str, to: &str) -> ChangeSet {
+    if from == to {
+        return ChangeSet::new();
+    }
+    let Some(from_slot) = map.tilesets.slot_of(from) else {
+        return ChangeSet::new();
+    };
+    let hits: Vec<(u32, i32, i32, TileCell)> = map
+        .iter_cells()
+        .filter(|&(_, _, _, cell)| cell.slot() == Some(from_slot))
+        .collect();
+    if hits.is_empty() {
+        return ChangeSet::new();
+    }
+    let Some(to_slot) = map.tilesets.intern(to) else {
+        return ChangeSet::new();
+    };
+    let mut changes = ChangeSet::with_capacity(hits.len());
+    for (l, x, y, before) in hits {
+        let after = TileCell::new(to_slot, before.index(), before.orientation());
+        map.set_cell(l, x, y, after);
+        changes.push(CellChange { layer: l, x, y, before, after });
+    }
+    changes
+}
+
+/// 合并图层：每个格子取 `layers` 中最上层的非空格子写入其中最低的一层，其余各层清空。
+///
+/// 越界图层忽略；有效图层少于两层时不做任何事。
+pub fn merge_layers(map: &mut TileMapData, layers: &[u32]) -> ChangeSet {
+    let mut layers: Vec<u32> = layers.iter().copied().filter(|&l| l < map.layers).collect();
+    layers.sort_unstable();
+    layers.dedup();
+    let Some((&dst, rest)) = layers.split_first() else {
+        return ChangeSet::new();
+    };
+    if rest.is_empty() {
+        return ChangeSet::new();
+    }
+    let mut top: HashMap<(i32, i32), (u32, TileCell)> = HashMap::new();
+    for (l, x, y, cell) in map.iter_cells() {
+        if layers.binary_search(&l).is_err() {
+            continue;
+        }
+        let entry = top.entry((x, y)).or_insert((l, cell));
+        if l > entry.0 {
+            *entry = (l, cell);
+        }
+    }
+    let mut rec = Recorder::default();
+    for ((x, y), (_, cell)) in top {
+        for &l in rest {
+            rec.set(map, l, x, y, TileCell::EMPTY);
+        }
+        rec.set(map, dst, x, y, cell);
+    }
+    rec.finish()
+}
+
+/// 改为 `width*height` 的固定尺寸地图（无限地图同样适用），保留图层与 tileset 表。
+///
+/// 返回被裁掉的格子与元数据（after 为空）。尺寸本身不在变更集里：撤销时需先恢复原尺寸再回放。
+pub fn resize(map: &mut TileMapData, width: u32, height: u32) -> (ChangeSet, MetaChangeSet) {
+    let mut resized = TileMapData::new_with_layers(width, height, map.layers.max(1));
+    resized.layer_data = map.layer_data.clone();
+    resized.tilesets = map.tilesets.clone();
+    resized.meta = map.meta.clone();
+
+    let meta_changes: MetaChangeSet = map
+        .meta
+        .iter()
+        .filter(|&(x, y, _)| !resized.contains(x, y))
+        .map(|(x, y, before)| MetaChange { x, y, before, after: CellMeta::default() })
+        .collect();
+    resized.meta.retain(|x, y| x >= 0 && y >= 0 && (x as u32) < width && (y as u32) < height);
+
+    let mut changes = ChangeSet::new();
+    for (layer, x, y, cell) in map.iter_cells() {
+        if resized.contains(x, y) {
+            resized.set_cell(layer, x, y, cell);
+        } else {
+            changes.push(CellChange { layer, x, y, before: cell, after: TileCell::EMPTY });
+        }
+    }
+    *map = resized;
+    (changes, meta_changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::revert_meta;
+
+    fn tile(id: &str, index: u32) -> TileRef {
+        TileRef {
+            tileset_id: id.to_string(),
+            index,
+            rot: 0,
+            flip_x: false,
+            flip_y: false,
+        }
+    }
+
+    fn snapshot(map: &TileMapData) -> Vec<(u32, i32, i32, Option<TileRef>)> {
+        let mut v: Vec<_> = map.iter_cells().map(|(l, x, y, c)| (l, x, y, map.unpack(c))).collect();
+        v.sort_by_key(|(l, x, y, _)| (*l, *x, *y));
+        v
+    }
+
+    /// 变更集必须是净变更，且 revert/reapply 能精确往返。
+    fn check_undo(map: &mut TileMapData, before: &[(u32, i32, i32, Option<TileRef>)], changes: &[CellChange]) {
+        let after = snapshot(map);
+        let mut seen = HashSet::new();
+        for ch in changes {
+            assert_ne!(ch.before, ch.after);
+            assert!(seen.insert((ch.layer, ch.x, ch.y)), "duplicate change {ch:?}");
+        }
+        revert(map, changes);
+        assert_eq!(snapshot(map), before);
+        reapply(map, changes);
+        assert_eq!(snapshot(map), after);
+    }
+
+    #[test]
+    fn flood_fill_stays_within_region() {
+        let mut map = TileMapData::new_with_layers(5, 5, 1);
+        // 竖墙 x=2 把地图分成左右两半。
+        for y in 0..5 {
+            map.set(0, 2, y, Some(tile("a", 1)));
+        }
+        let before = snapshot(&map);
+        let fill = map.pack(Some(&tile("a", 7)));
+        let changes = flood_fill(&mut map, 0, 0, 0, fill);
+        assert_eq!(changes.len(), 10);
+        assert_eq!(map.get(0, 1, 4), Some(tile("a", 7)));
+        assert_eq!(map.get(0, 3, 0), None);
+        assert_eq!(map.get(0, 2, 2), Some(tile("a", 1)));
+        check_undo(&mut map, &before, &changes);
+
+        // 与起点相同则无变更；越界起点同样无变更。
+        assert!(flood_fill(&mut map, 0, 0, 0, fill).is_empty());
+        assert!(flood_fill(&mut map, 0, 9, 9, fill).is_empty());
+    }
+
+    #[test]
+    fn flood_fill_infinite_is_bounded() {
+        let mut map = TileMapData::new_infinite(1);
+        map.set(0, -2, -2, Some(tile("a", 1)));
+        map.set(0, 2, 2, Some(tile("a", 1)));
+        let fill = map.pack(Some(&tile("b", 0)));
+        let changes = flood_fill(&mut map, 0, 0, 0, fill);
+        // 5x5 包围盒减去两个已有格子。
+        assert_eq!(changes.len(), 23);
+        assert_eq!(map.get(0, 3, 0), None);
+    }
+
+    #[test]
+    fn fill_region_diagonal_and_global_modes() {
+        let mut map = TileMapData::new_with_layers(4, 4, 1);
+        // 对角线上的 (0,0) (1,1) 与孤立的 (3,0)。
+        for (x, y) in [(0, 0), (1, 1), (3, 0)] {
+            map.set(0, x, y, Some(tile("a", 1)));
+        }
+        let sorted = |mut v: Vec<(i32, i32)>| {
+            v.sort_unstable();
+            v
+        };
+        assert_eq!(fill_region(&map, &[0], 0, 0, FillMode::Contiguous), vec![(0, 0)]);
+        assert_eq!(sorted(fill_region(&map, &[0], 0, 0, FillMode::Diagonal)), vec![(0, 0), (1, 1)]);
+        assert_eq!(sorted(fill_region(&map, &[0], 0, 0, FillMode::Global)), vec![(0, 0), (1, 1), (3, 0)]);
+        assert_eq!(fill_region(&map, &[0], 2, 2, FillMode::Global).len(), 13);
+        assert!(fill_region(&map, &[0], 9, 9, FillMode::Global).is_empty());
+    }
+
+    #[test]
+    fn fill_region_samples_composite_layers() {
+        let mut map = TileMapData::new_with_layers(4, 4, 3);
+        // 第 0 层满铺地面，第 1 层 x=2 一道竖墙，第 2 层（要写入的层）为空。
+        for y in 0..4 {
+            for x in 0..4 {
+                map.set(0, x, y, Some(tile("a", 1)));
+            }
+            map.set(1, 2, y, Some(tile("a", 9)));
+        }
+        assert_eq!(fill_region(&map, &[2], 0, 0, FillMode::Contiguous).len(), 16);
+        assert_eq!(fill_region(&map, &[0], 0, 0, FillMode::Contiguous).len(), 16);
+        let floor = fill_region(&map, &[0, 1], 0, 0, FillMode::Contiguous);
+        assert_eq!(floor.len(), 8);
+        assert!(floor.iter().all(|&(x, _)| x < 2));
+        // 越界的取样图层忽略；全部越界时没有可填充的格子。
+        assert_eq!(fill_region(&map, &[0, 1, 7], 2, 0, FillMode::Global).len(), 4);
+        assert!(fill_region(&map, &[7], 0, 0, FillMode::Contiguous).is_empty());
+    }
+
+    #[test]
+    fn replace_tiles_by_layer_area_and_orientation() {
+        let mut map = TileMapData::new_with_layers(4, 4, 2);
+        let mut turned = tile("a", 1);
+        turned.set_orientation(Orientation::ROT_CW);
+        map.set(0, 0, 0, Some(tile("a", 1)));
+        map.set(0, 3, 3, Some(turned.clone()));
+        map.set(1, 0, 0, Some(tile("a", 1)));
+        map.set(1, 1, 0, Some(tile("a", 2)));
+        let before = snapshot(&map);
+
+        // 精确匹配只换掉朝向相同的格子；其它图层不受影响。
+        let changes = replace_tiles(&mut map, &[0], |_, _| true, &tile("a", 1), Some(&tile("b", 5)), false);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(map.get(0, 0, 0), Some(tile("b", 5)));
+        assert_eq!(map.get(1, 0, 0), Some(tile("a", 1)));
+        check_undo(&mut map, &before, &changes);
+        revert(&mut map, &changes);
+
+        // 忽略朝向时沿用原格子的朝向；区域外的格子不动。
+        let changes = replace_tiles(&mut map, &[0, 1], |_, _| true, &tile("a", 1), Some(&tile("b", 5)), true);
+        assert_eq!(changes.len(), 3);
+        let mut expect = tile("b", 5);
+        expect.set_orientation(Orientation::ROT_CW);
+        assert_eq!(map.get(0, 3, 3), Some(expect));
+        revert(&mut map, &changes);
+        let area = TileBounds::from_corners(0, 0, 1, 1);
+        let changes = replace_tiles(&mut map, &[0, 1], |x, y| area.contains(x, y), &tile("a", 1), None, true);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(map.get(0, 3, 3), Some(turned));
+        assert_eq!(map.get(1, 1, 0), Some(tile("a", 2)));
+    }
+
+    #[test]
+    fn cell_sets_extract_move_and_transform() {
+        let mut map = TileMapData::new(5, 5);
+        // L 形选区：(1,1) (2,1) (1,2)；(2,2) 在包围盒内但不在选区里。
+        let cells = [(1, 1), (2, 1), (1, 2)];
+        map.set(0, 1, 1, Some(tile("a", 1)));
+        map.set(0, 2, 1, Some(tile("a", 2)));
+        map.set(0, 2, 2, Some(tile("b", 9)));
+        let before = snapshot(&map);
+
+        assert_eq!(cells_bounds(&cells), Some(TileBounds::from_corners(1, 1, 2, 2)));
+        assert_eq!(cells_bounds(&[]), None);
+        let region = extract_cells(&map, 0, &cells);
+        assert_eq!((region.width, region.height), (2, 2));
+        assert_eq!(region.tiles, vec![Some(tile("a", 1)), Some(tile("a", 2)), None, None]);
+
+        // 选区里的空格子同样随之移动，覆盖目标格子。
+        let changes = move_cells(&mut map, 0, &cells, 1, 0);
+        assert_eq!(map.get(0, 1, 1), None);
+        assert_eq!(map.get(0, 2, 1), Some(tile("a", 1)));
+        assert_eq!(map.get(0, 3, 1), Some(tile("a", 2)));
+        assert_eq!(map.get(0, 2, 2), None);
+        check_undo(&mut map, &before, &changes);
+        revert(&mut map, &changes);
+
+        // 复制保留原格子；包围盒里不在选区的格子不参与。
+        let changes = copy_cells(&mut map, 0, &cells, 0, 2);
+        assert_eq!(map.get(0, 1, 1), Some(tile("a", 1)));
+        assert_eq!(map.get(0, 2, 3), Some(tile("a", 2)));
+        assert_eq!(map.get(0, 2, 2), Some(tile("b", 9)));
+        revert(&mut map, &changes);
+
+        // 水平翻转：位置在包围盒内镜像，tile 本身也翻转。
+        assert_eq!(oriented_cells(&cells, Orientation::FLIP_X), vec![(2, 1), (1, 1), (2, 2)]);
+        let changes = transform_cells(&mut map, 0, &cells, Orientation::FLIP_X);
+        let mut flipped = tile("a", 1);
+        flipped.transform(Orientation::FLIP_X);
+        assert_eq!(map.get(0, 2, 1), Some(flipped));
+        assert_eq!(map.get(0, 2, 2), None);
+        check_undo(&mut map, &before, &changes);
+    }
+
+    #[test]
+    fn fill_rect_clips_and_skips_same() {
+        let mut map = TileMapData::new_with_layers(4, 4, 2);
+        let fill = map.pack(Some(&tile("a", 3)));
+        map.set_cell(1, 0, 0, fill);
+        let before = snapshot(&map);
+        let changes = fill_rect(&mut map, 1, TileBounds::from_corners(-1, -1, 1, 1), fill);
+        assert_eq!(changes.len(), 3);
+        assert_eq!(map.cell(1, 1, 1), fill);
+        assert!(map.cell(0, 1, 1).is_empty());
+        check_undo(&mut map, &before, &changes);
+
+        let cleared = fill_rect(&mut map, 1, TileBounds::from_corners(0, 0, 3, 3), TileCell::EMPTY);
+        assert_eq!(cleared.len(), 4);
+        assert!(map.iter_cells().next().is_none());
+    }
+
+    #[test]
+    fn extract_and_stamp_round_trip() {
+        let mut src = TileMapData::new_with_layers(4, 4, 1);
+        src.set(0, 1, 1, Some(tile("a", 1)));
+        src.set(0, 2, 1, Some(tile("b", 2)));
+        let region = extract_region(&src, 0, TileBounds::from_corners(1, 1, 2, 2));
+        assert_eq!((region.width, region.height), (2, 2));
+        assert_eq!(region.get(0, 0), Some(&tile("a", 1)));
+        assert_eq!(region.get(1, 1), None);
+        assert!(!region.is_empty());
+
+        // 粘贴到另一张地图：tileset 表按需登记。
+        let mut dst = TileMapData::new_infinite(1);
+        let before = snapshot(&dst);
+        let changes = stamp_region(&mut dst, 0, &region, -5, 3, Orientation::IDENTITY);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(dst.get(0, -5, 3), Some(tile("a", 1)));
+        assert_eq!(dst.get(0, -4, 3), Some(tile("b", 2)));
+        check_undo(&mut dst, &before, &changes);
+    }
+
+    #[test]
+    fn stamp_region_with_orientation() {
+        // 横条 [a b c]，顺时针转成竖条：y 向上时 a 在最上方。
+        let region = TileRegion {
+            width: 3,
+            height: 1,
+            tiles: vec![Some(tile("t", 0)), Some(tile("t", 1)), Some(tile("t", 2))],
+        };
+        let mut map = TileMapData::new_with_layers(5, 5, 1);
+        let changes = stamp_region(&mut map, 0, &region, 1, 1, Orientation::ROT_CW);
+        assert_eq!(changes.len(), 3);
+        let at = |map: &TileMapData, y| map.get(0, 1, y).map(|t| (t.index, t.orientation()));
+        assert_eq!(at(&map, 3), Some((0, Orientation::ROT_CW)));
+        assert_eq!(at(&map, 2), Some((1, Orientation::ROT_CW)));
+        assert_eq!(at(&map, 1), Some((2, Orientation::ROT_CW)));
+
+        // 图块自身朝向与粘贴朝向复合。
+        let mut flipped = tile("t", 9);
+        flipped.set_orientation(Orientation::FLIP_X);
+        let one = TileRegion { width: 1, height: 1, tiles: vec![Some(flipped)] };
+        stamp_region(&mut map, 0, &one, 0, 0, Orientation::FLIP_X);
+        assert_eq!(map.get(0, 0, 0).unwrap().orientation(), Orientation::IDENTITY);
+
+        // 越界部分忽略。
+        let changes = stamp_region(&mut map, 0, &region, 4, 4, Orientation::IDENTITY);
+        assert_eq!(changes.len(), 1);
+    }
+
+    #[test]
+    fn stamp_tiles_skips_empty_cells() {
+        // 2x2 印章缺右上角：该格保留地图原样。
+        let region = TileRegion {
+            width: 2,
+            height: 2,
+            tiles: vec![Some(tile("t", 0)), Some(tile("t", 1)), Some(tile("t", 2)), None],
+        };
+        let mut map = TileMapData::new_with_layers(4, 4, 1);
+        map.set(0, 2, 2, Some(tile("keep", 5)));
+        let changes = stamp_tiles(&mut map, 0, &region, 1, 1, Orientation::IDENTITY);
+        assert_eq!(changes.len(), 3);
+        assert_eq!(map.get(0, 2, 2).map(|t| t.index), Some(5));
+        assert_eq!(map.get(0, 1, 2).map(|t| t.index), Some(2));
+
+        // 水平翻转后空格子落在左上角。
+        let mut map = TileMapData::new_with_layers(4, 4, 1);
+        stamp_tiles(&mut map, 0, &region, 0, 0, Orientation::FLIP_X);
+        assert!(map.get(0, 0, 1).is_none());
+        assert_eq!(map.get(0, 1, 1).map(|t| t.index), Some(2));
+        assert_eq!(map.get(0, 1, 0).map(|t| t.index), Some(0));
+    }
+
+    #[test]
+    fn fill_pattern_aligns_to_top_left_of_area() {
+        // 2x2 图案：下行 [0 1]，上行 [2 3]。
+        let region = TileRegion {
+            width: 2,
+            height: 2,
+            tiles: vec![Some(tile("t", 0)), Some(tile("t", 1)), Some(tile("t", 2)), Some(tile("t", 3))],
+        };
+        let mut map = TileMapData::new_with_layers(6, 6, 1);
+        // 3x3 区域 (1..=3, 1..=3)：左上角 (1, 3) 是图案左上角的 tile 2。
+        let cells: Vec<(i32, i32)> = (1..=3).flat_map(|y| (1..=3).map(move |x| (x, y))).collect();
+        let changes = fill_pattern(&mut map, 0, &cells, &region, Orientation::IDENTITY);
+        assert_eq!(changes.len(), 9);
+        let at = |map: &TileMapData, x, y| map.get(0, x, y).map(|t| t.index);
+        assert_eq!(at(&map, 1, 3), Some(2));
+        assert_eq!(at(&map, 2, 3), Some(3));
+        assert_eq!(at(&map, 1, 2), Some(0));
+        assert_eq!(at(&map, 3, 1), Some(2));
+        assert_eq!(at(&map, 3, 2), Some(0));
+
+        // 旋转后的图案按变换后的尺寸平铺。
+        let strip = TileRegion { width: 2, height: 1, tiles: vec![Some(tile("t", 7)), Some(tile("t", 8))] };
+        let mut map = TileMapData::new_with_layers(6, 6, 1);
+        let column: Vec<(i32, i32)> = (0..4).map(|y| (0, y)).collect();
+        fill_pattern(&mut map, 0, &column, &strip, Orientation::ROT_CW);
+        let indices: Vec<_> = (0..4).map(|y| at(&map, 0, y)).collect();
+        assert_eq!(indices, vec![Some(8), Some(7), Some(8), Some(7)]);
+    }
+
+    #[test]
+    fn off_map_writes_do_not_register_tilesets() {
+        let region = TileRegion { width: 1, height: 1, tiles: vec![Some(tile("far", 0))] };
+        let mut map = TileMapData::new_with_layers(4, 4, 1);
+        assert!(stamp_tiles(&mut map, 0, &region, 10, 10, Orientation::IDENTITY).is_empty());
+        assert!(stamp_region(&mut map, 0, &region, -5, 0, Orientation::IDENTITY).is_empty());
+        assert!(stamp_tiles(&mut map, 3, &region, 0, 0, Orientation::IDENTITY).is_empty());
+        assert!(fill_pattern(&mut map, 0, &[(9, 9)], &region, Orientation::IDENTITY).is_empty());
+        assert_eq!(map.set(0, 4, 0, Some(tile("far", 0))), None);
+        assert_eq!(map.tilesets.slot_of("far"), None);
+    }
+
+    #[test]
+    fn transform_region_is_net_and_undoable() {
+        let mut map = TileMapData::new_with_layers(6, 6, 1);
+        map.set(0, 0, 0, Some(tile("a", 0)));
+        map.set(0, 1, 0, Some(tile("a", 1)));
+        map.set(0, 2, 0, Some(tile("a", 2)));
+        map.set(0, 3, 0, Some(tile("z", 0)));
+        let before = snapshot(&map);
+        let bounds = TileBounds::from_corners(0, 0, 2, 0);
+        let changes = transform_region(&mut map, 0, bounds, Orientation::ROT_CW);
+        // (0,0) 被重写成 index 2，(1,0)(2,0) 清空，(0,1)(0,2) 新增。
+        assert_eq!(changes.len(), 5);
+        assert_eq!(map.get(0, 0, 2).map(|t| t.index), Some(0));
+        assert_eq!(map.get(0, 0, 0).map(|t| t.index), Some(2));
+        assert_eq!(map.get(0, 3, 0), Some(tile("z", 0)));
+        check_undo(&mut map, &before, &changes);
+
+        // 转四次回到原样。
+        let mut map2 = TileMapData::new_with_layers(6, 6, 1);
+        map2.set(0, 1, 1, Some(tile("a", 0)));
+        map2.set(0, 2, 1, Some(tile("a", 1)));
+        let start = snapshot(&map2);
+        let mut b = TileBounds::from_corners(1, 1, 2, 1);
+        for _ in 0..4 {
+            transform_region(&mut map2, 0, b, Orientation::ROT_CW);
+            b = TileBounds::from_corners(b.min_x, b.min_y, b.min_x + b.height() as i32 - 1, b.min_y + b.width() as i32 - 1);
+        }
+        assert_eq!(snapshot(&map2), start);
+    }
+
+    #[test]
+    fn move_region_overlapping() {
+        let mut map = TileMapData::new_with_layers(5, 1, 1);
+        map.set(0, 0, 0, Some(tile("a", 0)));
+        map.set(0, 1, 0, Some(tile("a", 1)));
+        let before = snapshot(&map);
+        let changes = move_region(&mut map, 0, TileBounds::from_corners(0, 0, 1, 0), 1, 0);
+        assert_eq!(map.get(0, 0, 0), None);
+        assert_eq!(map.get(0, 1, 0), Some(tile("a", 0)));
+        assert_eq!(map.get(0, 2, 0), Some(tile("a", 1)));
+        assert_eq!(changes.len(), 3);
+        check_undo(&mut map, &before, &changes);
+    }
+
+    #[test]
+    fn shift_blank_wrap_and_infinite() {
+        let mut map = TileMapData::new_with_layers(3, 2, 2);
+        map.set(0, 2, 0, Some(tile("a", 1)));
+        map.set(1, 0, 1, Some(tile("a", 2)));
+        let before = snapshot(&map);
+
+        let (changes, _) = shift(&mut map, 1, 0, false);
+        assert_eq!(map.get(0, 2, 0), None);
+        assert_eq!(map.get(1, 1, 1), Some(tile("a", 2)));
+        assert_eq!(map.iter_cells().count(), 1);
+        check_undo(&mut map, &before, &changes);
+        revert(&mut map, &changes);
+
+        let (changes, _) = shift(&mut map, 1, 0, true);
+        assert_eq!(map.get(0, 0, 0), Some(tile("a", 1)));
+        assert_eq!(map.iter_cells().count(), 2);
+        check_undo(&mut map, &before, &changes);
+
+        let mut inf = TileMapData::new_infinite(1);
+        inf.set(0, 0, 0, Some(tile("a", 1)));
+        let (changes, _) = shift(&mut inf, -3, 2, true);
+        assert_eq!(inf.get(0, -3, 2), Some(tile("a", 1)));
+        assert_eq!(changes.len(), 2);
+    }
+
+    #[test]
+    fn shift_moves_meta_with_the_same_wrap_rule() {
+        let wall = CellMeta { passage: crate::PASS_BLOCK_ALL, ..Default::default() };
+        let mut map = TileMapData::new_with_layers(3, 2, 1);
+        map.meta.set(2, 0, wall);
+        map.meta.set(0, 1, CellMeta { region: 4, ..Default::default() });
+        let before = map.meta.clone();
+
+        // 不回绕：移出右边界的元数据丢弃。
+        let (tiles, meta) = shift(&mut map, 1, 0, false);
+        assert!(tiles.is_empty());
+        assert_eq!(map.meta.len(), 1);
+        assert_eq!(map.meta.get(1, 1).region, 4);
+        revert_meta(&mut map, &meta);
+        assert_eq!(map.meta, before);
+
+        // 回绕：从另一侧回来，撤销 / 重做精确往返。
+        let (_, meta) = shift(&mut map, 1, 0, true);
+        assert_eq!(map.meta.get(0, 0), wall);
+        let after = map.meta.clone();
+        revert_meta(&mut map, &meta);
+        assert_eq!(map.meta, before);
+        crate::reapply_meta(&mut map, &meta);
+        assert_eq!(map.meta, after);
+    }
+
+    #[test]
+    fn replace_all_by_layer() {
+        let mut map = TileMapData::new_with_layers(3, 3, 2);
+        let a = map.pack(Some(&tile("a", 1)));
+        let b = map.pack(Some(&tile("b", 2)));
+        map.set_cell(0, 0, 0, a);
+        map.set_cell(0, 1, 1, a);
+        map.set_cell(1, 2, 2, a);
+        map.set_cell(1, 0, 2, b);
+        let before = snapshot(&map);
+
+        let changes = replace_all(&mut map, Some(0), a, b);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(map.cell(1, 2, 2), a);
+        check_undo(&mut map, &before, &changes);
+        revert(&mut map, &changes);
+
+        let changes = replace_all(&mut map, None, a, TileCell::EMPTY);
+        assert_eq!(changes.len(), 3);
+        assert_eq!(map.iter_cells().count(), 1);
+        assert!(replace_all(&mut map, None, b, b).is_empty());
+    }
+
+    #[test]
+    fn clear_where_matches_views() {
+        let mut map = TileMapData::new_infinite(2);
+        map.set(0, -3, 4, Some(tile("a", 9)));
+        map.set(1, 0, 0, Some(tile("a", 1)));
+        map.set(1, 5, 5, Some(tile("b", 9)));
+        let before = snapshot(&map);
+        let changes = clear_where(&mut map, |_, _, _, t| t.tileset_id == "a" && t.index >= 4);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(map.get(0, -3, 4), None);
+        assert_eq!(map.iter_cells().count(), 2);
+        check_undo(&mut map, &before, &changes);
+    }
+
+    #[test]
+    fn remap_tileset_keeps_index_and_orientation() {
+        let mut map = TileMapData::new_with_layers(3, 1, 2);
+        let mut rotated = tile("old", 5);
+        rotated.set_orientation(Orientation::ROT_CW);
+        map.set(0, 0, 0, Some(rotated.clone()));
+        map.set(1, 2, 0, Some(tile("old", 1)));
+        map.set(0, 1, 0, Some(tile("other", 1)));
+        let before = snapshot(&map);
+        let changes = remap_tileset(&mut map, "old", "new");
+        assert_eq!(changes.len(), 2);
+        rotated.tileset_id = "new".to_string();
+        assert_eq!(map.get(0, 0, 0), Some(rotated));
+        assert_eq!(map.get(1, 2, 0), Some(tile("new", 1)));
+        assert_eq!(map.get(0, 1, 0), Some(tile("other", 1)));
+        check_undo(&mut map, &before, &changes);
+        assert!(remap_tileset(&mut map, "missing", "new").is_empty());
+    }
+
+    #[test]
+    fn merge_layers_keeps_topmost() {
+        let mut map = TileMapData::new_with_layers(3, 1, 3);
+        map.set(0, 0, 0, Some(tile("a", 1)));
+        map.set(1, 0, 0, Some(tile("a", 2)));
+        map.set(2, 1, 0, Some(tile("a", 3)));
+        map.set(0, 2, 0, Some(tile("a", 4)));
+        let before = snapshot(&map);
+        let changes = merge_layers(&mut map, &[2, 0, 1, 7]);
+        assert_eq!(map.get(0, 0, 0), Some(tile("a", 2)));
+        assert_eq!(map.get(0, 1, 0), Some(tile("a", 3)));
+        assert_eq!(map.get(0, 2, 0), Some(tile("a", 4)));
+        assert_eq!(map.iter_cells().count(), 3);
+        check_undo(&mut map, &before, &changes);
+
+        assert!(merge_layers(&mut map, &[1]).is_empty());
+    }
+
+    #[test]
+    fn resize_crops_and_keeps_layers() {
+        let mut map = TileMapData::new_with_layers(4, 4, 3);
+        map.layer_data[2].name = "top".to_string();
+        map.set(2, 3, 3, Some(tile("a", 1)));
+        map.set(0, 1, 1, Some(tile("b", 2)));
+        let (changes, _) = resize(&mut map, 2, 2);
+        assert_eq!(map.fixed_size(), Some((2, 2)));
+        assert_eq!(map.layers, 3);
+        assert_eq!(map.layer_data[2].name, "top");
+        assert_eq!(map.get(0, 1, 1), Some(tile("b", 2)));
+        assert_eq!(changes.len(), 1);
+        assert_eq!(map.unpack(changes[0].before), Some(tile("a", 1)));
+
+        let mut inf = TileMapData::new_infinite(1);
+        inf.set(0, -1, 0, Some(tile("a", 1)));
+        inf.set(0, 0, 0, Some(tile("a", 2)));
+        let (changes, _) = resize(&mut inf, 3, 3);
+        assert!(!inf.infinite);
+        assert_eq!(inf.get(0, 0, 0), Some(tile("a", 2)));
+        assert_eq!(changes.len(), 1);
+    }
+
+    #[test]
+    fn resize_reports_cropped_meta_for_undo() {
+        let mut map = TileMapData::new_with_layers(4, 4, 1);
+        map.set(0, 3, 3, Some(tile("a", 1)));
+        map.meta.set(1, 1, CellMeta { region: 2, ..Default::default() });
+        map.meta.set(3, 0, CellMeta { terrain: 5, ..Default::default() });
+        let tiles_before = snapshot(&map);
+        let meta_before = map.meta.clone();
+
+        let (changes, meta) = resize(&mut map, 2, 2);
+        assert_eq!(meta.len(), 1);
+        assert_eq!((meta[0].x, meta[0].y, meta[0].before.terrain), (3, 0, 5));
+        assert_eq!(map.meta.len(), 1);
+
+        // 撤销：先恢复原尺寸，再回放两份变更。
+        resize(&mut map, 4, 4);
+        revert(&mut map, &changes);
+        revert_meta(&mut map, &meta);
+        assert_eq!(snapshot(&map), tiles_before);
+        assert_eq!(map.meta, meta_before);
+    }
+}
@@ -0,0 +1,434 @@
+//! 图块朝向：二面体群 D4（4 种旋转 × 是否镜像，共 8 个元素）。
+//!
+//! 规范形式即 Tiled 的 H/V/D 三个翻转位（与 `bevy_ecs_tilemap::TileFlip` 的 x/y/d 一致）：
+//! 先沿主对角线翻转（D），再水平翻转（H），最后垂直翻转（V）。
+//!
+//! 矩阵/坐标均在“图像坐标系”下：x 向右，y 向下。顺时针指屏幕上看到的顺时针。
+
+use std::fmt;
+
+const H_BIT: u8 = 0b001;
+const V_BIT: u8 = 0b010;
+const D_BIT: u8 = 0b100;
+
+/// Tiled gid 高位的翻转标记。
+pub const TILED_FLIP_H: u32 = 0x8000_0000;
+pub const TILED_FLIP_V: u32 = 0x4000_0000;
+pub const TILED_FLIP_D: u32 = 0x2000_0000;
+pub const TILED_FLIP_MASK: u32 = TILED_FLIP_H | TILED_FLIP_V | TILED_FLIP_D;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Orientation(u8);
+
+impl Orientation {
+    pub const IDENTITY: Orientation = Orientation(0);
+    pub const ROT_CW: Orientation = Orientation(H_BIT | D_BIT);
+    pub const ROT_180: Orientation = Orientation(H_BIT | V_BIT);
+    pub const ROT_CCW: Orientation = Orientation(V_BIT | D_BIT);
+    pub const FLIP_X: Orientation = Orientation(H_BIT);
+    pub const FLIP_Y: Orientation = Orientation(V_BIT);
+    /// 沿主对角线翻转（交换 x/y）。
+    pub const TRANSPOSE: Orientation = Orientation(D_BIT);
+    /// 沿副对角线翻转。
+    pub const ANTI_TRANSPOSE: Orientation = Orientation(H_BIT | V_BIT | D_BIT);
+
+    /// 全部 8 个元素（按 H/V/D 位序）。
+    pub const ALL: [Orientation; 8] = [
+        Orientation(0),
+        Orientation(1),
+        Orientation(2),
+        Orientation(3),
+        Orientation(4),
+        Orientation(5),
+        Orientation(6),
+        Orientation(7),
+    ];
+
+    pub const fn from_flip_bits(h: bool, v: bool, d: bool) -> Self {
+        Self((h as u8) | ((v as u8) << 1) | ((d as u8) << 2))
+    }
+
+    pub fn flip_h(self) -> bool {
+        self.0 & H_BIT != 0
+    }
+
+    pub fn flip_v(self) -> bool {
+        self.0 & V_BIT != 0
+    }
+
+    pub fn flip_d(self) -> bool {
+        self.0 & D_BIT != 0
+    }
+
+    /// 从 Tiled 的原始 gid 读取翻转位（忽略低位的 tile id）。
+    pub fn from_tiled_gid(gid: u32) -> Self {
+        Self::from_flip_bits(
+            gid & TILED_FLIP_H != 0,
+            gid & TILED_FLIP_V != 0,
+            gid & TILED_FLIP_D != 0,
+        )
+    }
+
+    /// 转为 Tiled gid 的翻转位（与 tile id 按位或即可）。
+    pub fn tiled_flags(self) -> u32 {
+        let mut flags = 0;
+        if self.flip_h() {
+            flags |= TILED_FLIP_H;
+        }
+        if self.flip_v() {
+            flags |= TILED_FLIP_V;
+        }
+        if self.flip_d() {
+            flags |= TILED_FLIP_D;
+        }
+        flags
+    }
+
+    /// 作用在以图块中心为原点的坐标上（整数，y 向下）。
+    pub fn apply(self, x: i32, y: i32) -> (i32, i32) {
+        let (mut x, mut y) = if self.flip_d() { (y, x) } else { (x, y) };
+        if self.flip_h() {
+            x = -x;
+        }
+        if self.flip_v() {
+            y = -y;
+        }
+        (x, y)
+    }
+
+    /// 2x2 矩阵（作用于列向量）。
+    pub fn matrix(self) -> [[i32; 2]; 2] {
+        let (ax, ay) = self.apply(1, 0);
+        let (bx, by) = self.apply(0, 1);
+        [[ax, bx], [ay, by]]
+    }
+
+    pub fn from_matrix(m: [[i32; 2]; 2]) -> Option<Self> {
+        Self::ALL.into_iter().find(|o| o.matrix() == m)
+    }
+
+    fn from_fn(f: impl Fn(i32, i32) -> (i32, i32)) -> Self {
+        let a = f(1, 0);
+        let b = f(0, 1);
+        Self::ALL
+            .into_iter()
+            .find(|o| o.apply(1, 0) == a && o.apply(0, 1) == b)
+            .expect("D4 对复合封闭")
+    }
+
+    /// 先 `self` 再 `next`。
+    pub fn then(self, next: Orientation) -> Self {
+        Self::from_fn(|x, y| {
+            let (x, y) = self.apply(x, y);
+            next.apply(x, y)
+        })
+    }
+
+    pub fn inverse(self) -> Self {
+        // D4 中只有 ±90° 旋转不是自身的逆。
+        match self {
+            Self::ROT_CW => Self::ROT_CCW,
+            Self::ROT_CCW => Self::ROT_CW,
+            other => other,
+        }
+    }
+
+    /// 是否交换宽高（90°/270° 以及对角翻转）。
+    pub fn swaps_axes(self) -> bool {
+        self.flip_d()
+    }
+
+    pub fn dims(self, w: u32, h: u32) -> (u32, u32) {
+        if self.swaps_axes() { (h, w) } else { (w, h) }
+    }
+
+    /// 把 `w*h` 网格里的 `(x, y)` 映射到变换后网格（尺寸见 `dims`）。越界返回 None。
+    pub fn transform_cell(self, x: u32, y: u32, w: u32, h: u32) -> Option<(u32, u32)> {
+        if x >= w || y >= h {
+            return None;
+        }
+        // 用 2 倍坐标把原点放到网格中心，避免半格。
+        let cx = 2 * x as i64 - (w as i64 - 1);
+        let cy = 2 * y as i64 - (h as i64 - 1);
+        let (ax, ay) = self.apply(1, 0);
+        let (bx, by) = self.apply(0, 1);
+        let nx = ax as i64 * cx + bx as i64 * cy;
+        let ny = ay as i64 * cx + by as i64 * cy;
+        let (nw, nh) = self.dims(w, h);
+        Some((((nx + nw as i64 - 1) / 2) as u32, ((ny + nh as i64 - 1) / 2) as u32))
+    }
+
+    /// 同一个几何变换在 y 轴向上的坐标系（如地图格子坐标）中的表示。
+    ///
+    /// 翻转不变，旋转方向互换。
+    pub fn y_up(self) -> Self {
+        Self::from_fn(|x, y| {
+            let (x, y) = self.apply(x, -y);
+            (x, -y)
+        })
+    }
+
+    /// 由 `TileRef` 的 `rot/flip_x/flip_y` 构造：先顺时针旋转 `rot*90°`，再 flip_x，再 flip_y。
+    pub fn from_rot_flip(rot: u8, flip_x: bool, flip_y: bool) -> Self {
+        let mut o = Self::IDENTITY;
+        for _ in 0..rot % 4 {
+            o = o.then(Self::ROT_CW);
+        }
+        if flip_x {
+            o = o.then(Self::FLIP_X);
+        }
+        if flip_y {
+            o = o.then(Self::FLIP_Y);
+        }
+        o
+    }
+
+    /// 规范的 `(rot, flip_x, flip_y)`：flip_y 恒为 false。
+    pub fn to_rot_flip(self) -> (u8, bool, bool) {
+        for rot in 0..4 {
+            for flip_x in [false, true] {
+                if Self::from_rot_flip(rot, flip_x, false) == self {
+                    return (rot, flip_x, false);
+                }
+            }
+        }
+        unreachable!("每个 D4 元素都能写成 旋转+可选水平翻转")
+    }
+
+    /// Sprite 的表示：先水平翻转（`Sprite::flip_x`），再顺时针旋转 `rot*90°`（Transform）。
+    pub fn to_sprite_parts(self) -> (u8, bool) {
+        for rot in 0..4 {
+            for flip_x in [false, true] {
+                let mut o = if flip_x { Self::FLIP_X } else { Self::IDENTITY };
+                for _ in 0..rot {
+                    o = o.then(Self::ROT_CW);
+                }
+                if o == self {
+                    return (rot, flip_x);
+                }
+            }
+        }
+        unreachable!("每个 D4 元素都能写成 水平翻转+旋转")
+    }
+}
+
+/// 规范形式的简写，例如 `90°`、`270° flipX`。
+impl fmt::Display for Orientation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (rot, flip_x, _) = self.to_rot_flip();
+        write!(f, "{}°", rot as u32 * 90)?;
+        if flip_x {
+            write!(f, " flipX")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "bevy_ecs_tilemap")]
+impl From<Orientation> for bevy_ecs_tilemap::prelude::TileFlip {
+    fn from(o: Orientation) -> Self {
+        Self {
+            x: o.flip_h(),
+            y: o.flip_v(),
+            d: o.flip_d(),
+        }
+    }
+}
+
+#[cfg(feature = "bevy_ecs_tilemap")]
+impl From<bevy_ecs_tilemap::prelude::TileFlip> for Orientation {
+    fn from(flip: bevy_ecs_tilemap::prelude::TileFlip) -> Self {
+        Self::from_flip_bits(flip.x, flip.y, flip.d)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rot_cw_n(n: u8) -> Orientation {
+        (0..n).fold(Orientation::IDENTITY, |o, _| o.then(Orientation::ROT_CW))
+    }
+
+    #[test]
+    fn all_elements_are_distinct() {
+        for (i, a) in Orientation::ALL.iter().enumerate() {
+            for b in &Orientation::ALL[i + 1..] {
+                assert_ne!(a.matrix(), b.matrix());
+            }
+        }
+    }
+
+    #[test]
+    fn matrix_round_trip() {
+        for o in Orientation::ALL {
+            assert_eq!(Orientation::from_matrix(o.matrix()), Some(o));
+            let m = o.matrix();
+            let det = m[0][0] * m[1][1] - m[0][1] * m[1][0];
+            assert_eq!(det == -1, !is_rotation(o));
+        }
+        assert_eq!(Orientation::from_matrix([[2, 0], [0, 1]]), None);
+    }
+
+    fn is_rotation(o: Orientation) -> bool {
+        (0..4).any(|n| rot_cw_n(n) == o)
+    }
+
+    #[test]
+    fn composition_matches_matrix_product() {
+        for a in Orientation::ALL {
+            for b in Orientation::ALL {
+                let (ma, mb) = (a.matrix(), b.matrix());
+                let mut prod = [[0; 2]; 2];
+                for (r, row) in prod.iter_mut().enumerate() {
+                    for (c, cell) in row.iter_mut().enumerate() {
+                        *cell = mb[r][0] * ma[0][c] + mb[r][1] * ma[1][c];
+                    }
+                }
+                assert_eq!(a.then(b).matrix(), prod, "{a:?} then {b:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn group_axioms() {
+        for a in Orientation::ALL {
+            assert_eq!(a.then(Orientation::IDENTITY), a);
+            assert_eq!(Orientation::IDENTITY.then(a), a);
+            assert_eq!(a.then(a.inverse()), Orientation::IDENTITY);
+            assert_eq!(a.inverse().then(a), Orientation::IDENTITY);
+            assert_eq!(a.inverse().inverse(), a);
+            for b in Orientation::ALL {
+                for c in Orientation::ALL {
+                    assert_eq!(a.then(b).then(c), a.then(b.then(c)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn named_elements() {
+        assert_eq!(rot_cw_n(1), Orientation::ROT_CW);
+        assert_eq!(rot_cw_n(2), Orientation::ROT_180);
+        assert_eq!(rot_cw_n(3), Orientation::ROT_CCW);
+        assert_eq!(rot_cw_n(4), Orientation::IDENTITY);
+        assert_eq!(Orientation::FLIP_X.then(Orientation::FLIP_Y), Orientation::ROT_180);
+        assert_eq!(Orientation::TRANSPOSE.then(Orientation::ROT_180), Orientation::ANTI_TRANSPOSE);
+        // 顺时针：右 -> 下（y 向下）。
+        assert_eq!(Orientation::ROT_CW.apply(1, 0), (0, 1));
+        assert_eq!(Orientation::FLIP_X.apply(1, 1), (-1, 1));
+        assert_eq!(Orientation::TRANSPOSE.apply(1, 0), (0, 1));
+    }
+
+    #[test]
+    fn flip_bits_round_trip() {
+        for o in Orientation::ALL {
+            assert_eq!(Orientation::from_flip_bits(o.flip_h(), o.flip_v(), o.flip_d()), o);
+            assert_eq!(Orientation::from_tiled_gid(o.tiled_flags() | 42), o);
+            assert_eq!(o.tiled_flags() & !TILED_FLIP_MASK, 0);
+        }
+        // Tiled 文档：顺时针 90° = H + D。
+        assert_eq!(Orientation::ROT_CW.tiled_flags(), TILED_FLIP_H | TILED_FLIP_D);
+        assert_eq!(Orientation::ROT_CCW.tiled_flags(), TILED_FLIP_V | TILED_FLIP_D);
+        assert_eq!(Orientation::ROT_180.tiled_flags(), TILED_FLIP_H | TILED_FLIP_V);
+    }
+
+    #[test]
+    fn rot_flip_canonical_form() {
+        // 16 种 (rot, flip_x, flip_y) 组合恰好两两对应同一个元素。
+        let mut hits = [0; 8];
+        for rot in 0..4u8 {
+            for fx in [false, true] {
+                for fy in [false, true] {
+                    let o = Orientation::from_rot_flip(rot, fx, fy);
+                    hits[o.0 as usize] += 1;
+                    let (r, cfx, cfy) = o.to_rot_flip();
+                    assert!(r < 4 && !cfy);
+                    assert_eq!(Orientation::from_rot_flip(r, cfx, cfy), o);
+                    // rot 超出 0..4 时按模 4 处理。
+                    assert_eq!(Orientation::from_rot_flip(rot + 4, fx, fy), o);
+                }
+            }
+        }
+        assert_eq!(hits, [2; 8]);
+        for o in Orientation::ALL {
+            let (r, fx, fy) = o.to_rot_flip();
+            assert_eq!(Orientation::from_rot_flip(r, fx, fy).to_rot_flip(), (r, fx, fy));
+        }
+    }
+
+    #[test]
+    fn rot_flip_matches_legacy_tile_flip() {
+        // 与旧的 tile_flip_from_ref 逐一对照，保证已有地图渲染不变。
+        for rot in 0..4u8 {
+            for fx in [false, true] {
+                for fy in [false, true] {
+                    let (mut x, mut y, mut d) = (fx, fy, false);
+                    match rot {
+                        1 => {
+                            d = true;
+                            x = !x;
+                        }
+                        2 => {
+                            x = !x;
+                            y = !y;
+                        }
+                        3 => {
+                            d = true;
+                            y = !y;
+                        }
+                        _ => {}
+                    }
+                    let o = Orientation::from_rot_flip(rot, fx, fy);
+                    assert_eq!((o.flip_h(), o.flip_v(), o.flip_d()), (x, y, d));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sprite_parts_round_trip() {
+        for o in Orientation::ALL {
+            let (rot, flip_x) = o.to_sprite_parts();
+            let base = if flip_x { Orientation::FLIP_X } else { Orientation::IDENTITY };
+            assert_eq!(base.then(rot_cw_n(rot)), o);
+        }
+    }
+
+    #[test]
+    fn y_up_swaps_rotation_direction() {
+        assert_eq!(Orientation::ROT_CW.y_up(), Orientation::ROT_CCW);
+        assert_eq!(Orientation::ROT_CCW.y_up(), Orientation::ROT_CW);
+        assert_eq!(Orientation::FLIP_X.y_up(), Orientation::FLIP_X);
+        assert_eq!(Orientation::FLIP_Y.y_up(), Orientation::FLIP_Y);
+        for a in Orientation::ALL {
+            assert_eq!(a.y_up().y_up(), a);
+            for b in Orientation::ALL {
+                assert_eq!(a.then(b).y_up(), a.y_up().then(b.y_up()));
+            }
+        }
+    }
+
+    #[test]
+    fn transform_cell_is_a_bijection() {
+        for o in Orientation::ALL {
+            for (w, h) in [(1, 1), (3, 1), (2, 5), (4, 4)] {
+                let (nw, nh) = o.dims(w, h);
+                let mut seen = vec![false; (nw * nh) as usize];
+                for y in 0..h {
+                    for x in 0..w {
+                        let (nx, ny) = o.transform_cell(x, y, w, h).unwrap();
+                        assert!(nx < nw && ny < nh);
+                        assert!(!std::mem::replace(&mut seen[(ny * nw + nx) as usize], true));
+                        let back = o.inverse().transform_cell(nx, ny, nw, nh).unwrap();
+                        assert_eq!(back, (x, y));
+                    }
+                }
+                assert_eq!(o.transform_cell(w, 0, w, h), None);
+            }
+        }
+        // 3x1 横条顺时针转成 1x3 竖条（y 向下）：左端到上方。
+        assert_eq!(Orientation::ROT_CW.transform_cell(0, 0, 3, 1), Some((0, 0)));
+        assert_eq!(Orientation::ROT_CW.transform_cell(2, 0, 3, 1), Some((0, 2)));
+    }
+}
@@ -0,0 +1,54 @@
+//! 可设种子的小型随机数发生器（SplitMix64），供地形 / 随机笔刷在等价候选中挑选。
+//!
+//! 不追求密码学强度；同一种子得到同一序列，便于测试与复现。
+
+/// SplitMix64 随机数发生器。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TileRng {
+    state: u64,
+}
+
+impl TileRng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// `[0, 1)` 内的均匀浮点数。
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// `0..n` 内的均匀整数；`n == 0` 时返回 0。
+    pub fn below(&mut self, n: u32) -> u32 {
+        if n == 0 {
+            return 0;
+        }
+        (((self.next_u64() >> 32) * n as u64) >> 32) as u32
+    }
+
+    /// 按权重挑一个下标；权重全为 0（或为空）时返回 `None`。
+    pub fn pick_weighted(&mut self, weights: &[f32]) -> Option<usize> {
+        let total: f32 = weights.iter().map(|w| w.max(0.0)).sum();
+        if total <= 0.0 {
+            return None;
+        }
+        let mut at = self.next_f32() * total;
+        for (i, w) in weights.iter().enumerate() {
+            let w = w.max(0.0);
+            if at < w {
+                return Some(i);
+            }
+            at -= w;
+        }
+        // 浮点误差落到末尾时取最后一个正权重。
+        weights.iter().rposition(|w| *w > 0.0)
+    }
+}
@@ -0,0 +1,229 @@
+//! 形状栅格化（纯逻辑）：直线 / 折线、矩形边框、椭圆，返回要绘制的格子坐标。
+//!
+//! 坐标与地图一致（y 向上）；结果不做越界裁剪，由写入方（`ops::fill_cells` 等）忽略图外格子。
+
+use std::collections::{BTreeMap, HashSet};
+
+use crate::TileBounds;
+
+/// Bresenham 直线：含两个端点，按从 `from` 到 `to` 的顺序。
+pub fn line_cells(from: (i32, i32), to: (i32, i32)) -> Vec<(i32, i32)> {
+    let (mut x, mut y) = from;
+    let dx = (to.0 - x).abs();
+    let dy = -(to.1 - y).abs();
+    let sx = if to.0 >= x { 1 } else { -1 };
+    let sy = if to.1 >= y { 1 } else { -1 };
+    let mut err = dx + dy;
+    let mut cells = Vec::with_capacity((dx.max(-dy) + 1) as usize);
+    loop {
+        cells.push((x, y));
+        if (x, y) == to {
+            return cells;
+        }
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            y += sy;
+        }
+    }
+}
+
+/// 折线：依次连接各顶点，去掉重复格子（保持首次出现的顺序）。
+pub fn polyline_cells(points: &[(i32, i32)]) -> Vec<(i32, i32)> {
+    let mut seen = HashSet::new();
+    let mut cells = Vec::new();
+    match points {
+        [] => {}
+        [only] => cells.push(*only),
+        _ => {
+            for pair in points.windows(2) {
+                for c in line_cells(pair[0], pair[1]) {
+                    if seen.insert(c) {
+                        cells.push(c);
+                    }
+                }
+            }
+        }
+    }
+    cells
+}
+
+/// 把终点吸附到从起点出发的 45° 方向（水平 / 竖直 / 对角线），取离原终点最近的一条。
+pub fn snap_line_45(from: (i32, i32), to: (i32, i32)) -> (i32, i32) {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let (ax, ay) = (dx.abs(), dy.abs());
+    // tan(22.5°) ≈ 0.414：较短分量不足较长分量的 0.414 倍时贴到轴上，否则走对角线。
+    if ay * 1000 <= ax * 414 {
+        (to.0, from.1)
+    } else if ax * 1000 <= ay * 414 {
+        (from.0, to.1)
+    } else {
+        let d = (ax + ay) / 2;
+        (from.0 + d * dx.signum(), from.1 + d * dy.signum())
+    }
+}
+
+/// 拖拽形状的外接框：`square` 时取两轴中较长者（正方形 / 圆），`centered` 时以起点为中心。
+pub fn drag_bounds(start: (i32, i32), current: (i32, i32), square: bool, centered: bool) -> TileBounds {
+    let (mut dx, mut dy) = (current.0 - start.0, current.1 - start.1);
+    if square {
+        let d = dx.abs().max(dy.abs());
+        dx = d * if dx < 0 { -1 } else { 1 };
+        dy = d * if dy < 0 { -1 } else { 1 };
+    }
+    let (ax, ay) = if centered { (start.0 - dx, start.1 - dy) } else { start };
+    TileBounds::from_corners(ax, ay, start.0 + dx, start.1 + dy)
+}
+
+/// 矩形边框（一格宽）。
+pub fn rect_outline_cells(bounds: TileBounds) -> Vec<(i32, i32)> {
+    let mut cells = Vec::new();
+    for y in bounds.min_y..=bounds.max_y {
+        for x in bounds.min_x..=bounds.max_x {
+            let edge = x == bounds.min_x || x == bounds.max_x || y == bounds.min_y || y == bounds.max_y;
+            if edge {
+                cells.push((x, y));
+            }
+        }
+    }
+    cells
+}
+
+/// 内切于 `bounds` 的椭圆：`filled` 为实心，否则为一格宽的 8 连通轮廓。
+///
+/// 轮廓用 Zingl 的外接矩形椭圆算法（整数误差项，偶数尺寸也对称）；实心按行填满轮廓的左右端之间。
+pub fn ellipse_cells(bounds: TileBounds, filled: bool) -> Vec<(i32, i32)> {
+    let mut outline = HashSet::new();
+    let (mut x0, mut x1) = (bounds.min_x as i64, bounds.max_x as i64);
+    let a = x1 - x0;
+    let b = (bounds.max_y - bounds.min_y) as i64;
+    let b1 = b & 1;
+    let mut dx = 4 * (1 - a) * b * b;
+    let mut dy = 4 * (b1 + 1) * a * a;
+    let mut err = dx + dy + b1 * a * a;
+    let mut y0 = bounds.min_y as i64 + (b + 1) / 2;
+    let mut y1 = y0 - b1;
+    let (aa, bb) = (8 * a * a, 8 * b * b);
+    let mut plot = |x: i64, y: i64| {
+        outline.insert((x as i32, y as i32));
+    };
+    loop {
+        plot(x1, y0);
+        plot(x0, y0);
+        plot(x0, y1);
+        plot(x1, y1);
+        let e2 = 2 * err;
+        if e2 <= dy {
+            y0 += 1;
+            y1 -= 1;
+            dy += aa;
+            err += dy;
+        }
+        if e2 >= dx || 2 * err > dy {
+            x0 += 1;
+            x1 -= 1;
+            dx += bb;
+            err += dx;
+        }
+        if x0 > x1 {
+            break;
+        }
+    }
+    // 很扁的椭圆（宽 1~2）提前结束时补齐两端。
+    while y0 - y1 <= b {
+        plot(x0 - 1, y0);
+        plot(x1 + 1, y0);
+        plot(x0 - 1, y1);
+        plot(x1 + 1, y1);
+        y0 += 1;
+        y1 -= 1;
+    }
+
+    let mut rows: BTreeMap<i32, (i32, i32)> = BTreeMap::new();
+    for &(x, y) in &outline {
+        if bounds.contains(x, y) {
+            let span = rows.entry(y).or_insert((x, x));
+            *span = (span.0.min(x), span.1.max(x));
+        }
+    }
+    let mut cells = Vec::new();
+    for (y, (min_x, max_x)) in rows {
+        for x in min_x..=max_x {
+            if filled || outline.contains(&(x, y)) {
+                cells.push((x, y));
+            }
+        }
+    }
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_is_gapless_and_symmetric_in_length() {
+        assert_eq!(line_cells((0, 0), (0, 0)), vec![(0, 0)]);
+        assert_eq!(line_cells((0, 0), (3, 0)), vec![(0, 0), (1, 0), (2, 0), (3, 0)]);
+        assert_eq!(line_cells((2, 2), (0, 0)), vec![(2, 2), (1, 1), (0, 0)]);
+
+        let cells = line_cells((0, 0), (5, -2));
+        assert_eq!(cells.len(), 6);
+        assert_eq!((cells[0], cells[5]), ((0, 0), (5, -2)));
+        for pair in cells.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            assert!((a.0 - b.0).abs() <= 1 && (a.1 - b.1).abs() <= 1);
+        }
+        assert_eq!(line_cells((5, -2), (0, 0)).len(), 6);
+    }
+
+    #[test]
+    fn snap_and_polyline() {
+        assert_eq!(snap_line_45((0, 0), (5, 1)), (5, 0));
+        assert_eq!(snap_line_45((0, 0), (-1, -6)), (0, -6));
+        assert_eq!(snap_line_45((1, 1), (5, -3)), (5, -3));
+        assert_eq!(snap_line_45((0, 0), (4, -2)), (3, -3));
+
+        // 共享拐点只出现一次。
+        let cells = polyline_cells(&[(0, 0), (2, 0), (2, 2)]);
+        assert_eq!(cells, vec![(0, 0), (1, 0), (2, 0), (2, 1), (2, 2)]);
+        assert_eq!(polyline_cells(&[(4, 4)]), vec![(4, 4)]);
+    }
+
+    #[test]
+    fn drag_bounds_square_and_centered() {
+        assert_eq!(drag_bounds((2, 2), (5, 3), false, false), TileBounds::from_corners(2, 2, 5, 3));
+        assert_eq!(drag_bounds((2, 2), (5, 3), true, false), TileBounds::from_corners(2, 2, 5, 5));
+        assert_eq!(drag_bounds((2, 2), (0, 3), true, true), TileBounds::from_corners(0, 0, 4, 4));
+        assert_eq!(rect_outline_cells(TileBounds::from_corners(0, 0, 2, 2)).len(), 8);
+    }
+
+    #[test]
+    fn ellipse_is_symmetric_and_touches_its_box() {
+        for (w, h) in [(1, 1), (1, 4), (2, 2), (5, 5), (6, 3), (10, 2), (7, 12)] {
+            let bounds = TileBounds::from_corners(3, -2, 3 + w - 1, -2 + h - 1);
+            let filled = ellipse_cells(bounds, true);
+            let outline = ellipse_cells(bounds, false);
+            let set: HashSet<_> = filled.iter().copied().collect();
+            assert!(outline.iter().all(|c| set.contains(c)), "{w}x{h}");
+            assert!(filled.iter().all(|&(x, y)| bounds.contains(x, y)), "{w}x{h}");
+            for &(x, y) in &filled {
+                let mirrored = (bounds.min_x + bounds.max_x - x, bounds.min_y + bounds.max_y - y);
+                assert!(set.contains(&mirrored), "{w}x{h} missing mirror of {:?}", (x, y));
+            }
+            let (xs, ys): (HashSet<_>, HashSet<_>) = filled.iter().copied().unzip();
+            assert_eq!((xs.len(), ys.len()), (w as usize, h as usize), "{w}x{h}");
+        }
+
+        // 5x5 圆：每行 3/5/5/5/3，四角空出；轮廓不含圆心。
+        let bounds = TileBounds::from_corners(0, 0, 4, 4);
+        let filled = ellipse_cells(bounds, true);
+        assert_eq!(filled.len(), 21);
+        assert!(!filled.contains(&(0, 0)));
+        assert!(!ellipse_cells(bounds, false).contains(&(2, 2)));
+    }
+}
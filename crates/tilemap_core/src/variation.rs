@@ -0,0 +1,153 @@
+//! 随机变体笔刷：为每个格子从一组可互换的 tile 中按权重挑一个，可选随机旋转 / 翻转。
+//!
+//! 变体集随 tileset 保存；挑选用可设种子的 `TileRng`，同一种子得到同一结果。
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::ops::{ChangeSet, Recorder};
+use crate::{Orientation, TileMapData, TileRef, TileRng};
+
+/// 变体集里的一个 tile 及其相对权重。
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Variation {
+    pub index: u32,
+    #[cfg_attr(feature = "serde", serde(default = "default_weight"))]
+    pub weight: f32,
+}
+
+#[cfg(feature = "serde")]
+fn default_weight() -> f32 {
+    1.0
+}
+
+/// 一组可互换的 tile（几种草地、几种碎石……）。
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct VariationSet {
+    pub name: String,
+    pub tiles: Vec<Variation>,
+    /// 随机旋转 0 / 90 / 180 / 270 度。
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub random_rotation: bool,
+    /// 随机水平 / 垂直翻转。
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub random_flip: bool,
+}
+
+impl VariationSet {
+    pub fn contains(&self, index: u32) -> bool {
+        self.tiles.iter().any(|v| v.index == index)
+    }
+
+    pub fn weight(&self, index: u32) -> Option<f32> {
+        self.tiles.iter().find(|v| v.index == index).map(|v| v.weight)
+    }
+
+    /// 按权重挑一个 tile，并按开关随机朝向；权重全为 0 时返回 `None`。
+    pub fn pick(&self, tileset_id: &str, rng: &mut TileRng) -> Option<TileRef> {
+        let weights: Vec<f32> = self.tiles.iter().map(|v| v.weight).collect();
+        let picked = &self.tiles[rng.pick_weighted(&weights)?];
+        let rot = if self.random_rotation { rng.below(4) as u8 } else { 0 };
+        let (flip_x, flip_y) = if self.random_flip { (rng.below(2) == 1, rng.below(2) == 1) } else { (false, false) };
+        let mut tile = TileRef {
+            tileset_id: tileset_id.to_string(),
+            index: picked.index,
+            rot: 0,
+            flip_x: false,
+            flip_y: false,
+        };
+        tile.set_orientation(Orientation::from_rot_flip(rot, flip_x, flip_y));
+        Some(tile)
+    }
+}
+
+/// 为 `cells` 逐格随机挑选变体写入。
+///
+/// 已经是本组 tile 的格子保持不变（拖动经过时不会反复随机）。
+pub fn paint_variations(
+    map: &mut TileMapData,
+    layer: u32,
+    tileset_id: &str,
+    set: &VariationSet,
+    cells: &[(i32, i32)],
+    rng: &mut TileRng,
+) -> ChangeSet {
+    let mut rec = Recorder::default();
+    if layer >= map.layers {
+        return rec.finish();
+    }
+    for &(x, y) in cells {
+        if !map.contains(x, y) {
+            continue;
+        }
+        let done = map
+            .view(map.cell(layer, x, y))
+            .is_some_and(|v| v.tileset_id.as_str() == tileset_id && set.contains(v.index));
+        if done {
+            continue;
+        }
+        let Some(tile) = set.pick(tileset_id, rng) else {
+            return rec.finish();
+        };
+        rec.set_tile(map, layer, x, y, Some(&tile));
+    }
+    rec.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(weights: &[(u32, f32)]) -> VariationSet {
+        VariationSet {
+            name: "grass".into(),
+            tiles: weights.iter().map(|&(index, weight)| Variation { index, weight }).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn weighted_picks_are_reproducible_and_skip_members() {
+        let grass = set(&[(1, 3.0), (2, 1.0), (3, 0.0)]);
+        let cells: Vec<(i32, i32)> = (0..16).flat_map(|y| (0..16).map(move |x| (x, y))).collect();
+        let paint = |seed| {
+            let mut map = TileMapData::new_with_layers(16, 16, 1);
+            paint_variations(&mut map, 0, "t", &grass, &cells, &mut TileRng::new(seed));
+            map
+        };
+        let indices = |map: &TileMapData| {
+            let mut v: Vec<_> = map.iter_tiles().map(|(_, x, y, t)| (x, y, t.index)).collect();
+            v.sort_unstable();
+            v
+        };
+        let map = paint(42);
+        assert_eq!(indices(&map), indices(&paint(42)));
+
+        let count = |index| map.iter_tiles().filter(|(_, _, _, t)| t.index == index).count();
+        assert_eq!(count(1) + count(2), 256);
+        assert_eq!(count(3), 0);
+        // 3:1 的权重：期望 192 / 64。
+        assert!((150..230).contains(&count(1)), "{}", count(1));
+        assert!(map.iter_tiles().all(|(_, _, _, t)| t.orientation() == Orientation::IDENTITY));
+
+        // 已是本组 tile 的格子不再重抽。
+        let mut again = map.clone();
+        let changes = paint_variations(&mut again, 0, "t", &grass, &cells, &mut TileRng::new(7));
+        assert!(changes.is_empty());
+    }
+
+    #[test]
+    fn random_orientation_is_optional() {
+        let mut grass = set(&[(1, 1.0)]);
+        grass.random_rotation = true;
+        grass.random_flip = true;
+        let mut rng = TileRng::new(3);
+        let seen: std::collections::HashSet<_> =
+            (0..200).map(|_| grass.pick("t", &mut rng).unwrap().orientation()).collect();
+        assert_eq!(seen.len(), 8);
+
+        assert!(set(&[(1, 0.0)]).pick("t", &mut rng).is_none());
+    }
+}
@@ -0,0 +1,316 @@
+//! Wang 集（地形过渡），与 Tiled 的 corner / edge / mixed wangset 一致。
+//!
+//! 每个 tile 有 8 个槽位的 wang id（顺序同 Tiled：上、右上、右、右下、下、左下、左、左上），
+//! 值为颜色编号（1 起，0 = 未设置）。地图 y 向上，“上” = `(x, y + 1)` 方向。
+//!
+//! 地形笔刷把格子的角（corner）/ 边（edge）/ 两者（mixed）涂成某个颜色，再为被涂的格子和
+//! 共享这些角 / 边的邻居挑选最匹配的 tile；同样匹配的候选按颜色概率（权重）随机挑选。
+
+use std::collections::{HashMap, HashSet};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::ops::{connected_region, ChangeSet, Recorder};
+use crate::{TileMapData, TileRef, TileRng};
+
+/// wang id：8 个槽位的颜色编号。
+pub type WangId = [u8; 8];
+
+const CORNER_SLOTS: [usize; 4] = [1, 3, 5, 7];
+const EDGE_SLOTS: [usize; 4] = [0, 2, 4, 6];
+const ALL_SLOTS: [usize; 8] = [0, 1, 2, 3, 4, 5, 6, 7];
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum WangKind {
+    #[default]
+    Corner,
+    Edge,
+    Mixed,
+}
+
+impl WangKind {
+    /// 参与匹配的槽位。
+    pub fn slots(self) -> &'static [usize] {
+        match self {
+            WangKind::Corner => &CORNER_SLOTS,
+            WangKind::Edge => &EDGE_SLOTS,
+            WangKind::Mixed => &ALL_SLOTS,
+        }
+    }
+
+    /// Tiled JSON 中的 `type` 字段。
+    pub fn tiled_name(self) -> &'static str {
+        match self {
+            WangKind::Corner => "corner",
+            WangKind::Edge => "edge",
+            WangKind::Mixed => "mixed",
+        }
+    }
+
+    pub fn from_tiled_name(name: &str) -> Option<Self> {
+        match name {
+            "corner" => Some(WangKind::Corner),
+            "edge" => Some(WangKind::Edge),
+            "mixed" => Some(WangKind::Mixed),
+            _ => None,
+        }
+    }
+}
+
+/// 地形颜色（草地、沙地、水……）。
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct WangColor {
+    pub name: String,
+    /// `#rrggbb`，仅用于界面显示。
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub color: String,
+    /// 代表该颜色的 tile。
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub tile: Option<u32>,
+    /// 随机挑选候选时的相对权重。
+    #[cfg_attr(feature = "serde", serde(default = "default_probability"))]
+    pub probability: f32,
+}
+
+#[cfg(feature = "serde")]
+fn default_probability() -> f32 {
+    1.0
+}
+
+impl Default for WangColor {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            color: String::new(),
+            tile: None,
+            probability: 1.0,
+        }
+    }
+}
+
+/// tileset 中带 wang id 的 tile。
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WangTile {
+    pub index: u32,
+    pub wang_id: WangId,
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WangSet {
+    pub name: String,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub kind: WangKind,
+    /// 代表该集合的 tile。
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub tile: Option<u32>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub colors: Vec<WangColor>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub tiles: Vec<WangTile>,
+}
+
+impl WangSet {
+    pub fn wang_id(&self, index: u32) -> Option<WangId> {
+        self.tiles.iter().find(|t| t.index == index).map(|t| t.wang_id)
+    }
+
+    /// 颜色 `color`（1 起）的名字；越界返回 `None`。
+    pub fn color(&self, color: u8) -> Option<&WangColor> {
+        self.colors.get((color as usize).checked_sub(1)?)
+    }
+
+    /// 在期望的 wang id（0 = 不限）下的不匹配槽位数。
+    fn mismatch(&self, tile: &WangId, desired: &WangId) -> usize {
+        self.kind
+            .slots()
+            .iter()
+            .filter(|&&s| desired[s] != 0 && tile[s] != desired[s])
+            .count()
+    }
+
+    /// 候选 tile 的权重：各槽位颜色概率之积。
+    fn weight(&self, tile: &WangId) -> f32 {
+        self.kind
+            .slots()
+            .iter()
+            .filter_map(|&s| self.color(tile[s]))
+            .map(|c| c.probability)
+            .product()
+    }
+}
+
+/// 格子 `(x, y)` 第 `slot` 个槽位在“二倍网格”上的位置：相邻格子共享的角 / 边落在同一点。
+fn slot_pos(x: i32, y: i32, slot: usize) -> (i32, i32) {
+    let (cx, cy) = (2 * x + 1, 2 * y + 1);
+    // 上 = y + 1（地图 y 向上）。
+    const OFFSETS: [(i32, i32); 8] = [(0, 1), (1, 1), (1, 0), (1, -1), (0, -1), (-1, -1), (-1, 0), (-1, 1)];
+    let (dx, dy) = OFFSETS[slot];
+    (cx + dx, cy + dy)
+}
+
+/// 用地形颜色 `color`（1 起）涂 `cells`，并为它们及共享角 / 边的邻居重新挑选 tile。
+///
+/// 邻居只在已经是本集合的 tile 时才会被替换；已完全匹配的格子保持不变（拖动时不会反复随机）。
+pub fn paint_wang(
+    map: &mut TileMapData,
+    layer: u32,
+    tileset_id: &str,
+    set: &WangSet,
+    color: u8,
+    cells: &[(i32, i32)],
+    rng: &mut TileRng,
+) -> ChangeSet {
+    let mut rec = Recorder::default();
+    if layer >= map.layers || set.color(color).is_none() || set.tiles.is_empty() {
+        return rec.finish();
+    }
+    let slots = set.kind.slots();
+    let painted_cells: HashSet<(i32, i32)> = cells.iter().copied().filter(|&(x, y)| map.contains(x, y)).collect();
+    let mut painted: HashMap<(i32, i32), u8> = HashMap::new();
+    for &(x, y) in &painted_cells {
+        for &s in slots {
+            painted.insert(slot_pos(x, y, s), color);
+        }
+    }
+
+    let mut affected: Vec<(i32, i32)> = painted_cells
+        .iter()
+        .flat_map(|&(x, y)| (-1..=1).flat_map(move |dy| (-1..=1).map(move |dx| (x + dx, y + dy))))
+        .filter(|&(x, y)| map.contains(x, y))
+        .collect();
+    affected.sort_unstable();
+    affected.dedup();
+
+    // 先按改动前的地图算出每个格子的期望，再统一写入，结果与遍历顺序无关。
+    let mut plan = Vec::new();
+    for (x, y) in affected {
+        let current = map
+            .view(map.cell(layer, x, y))
+            .filter(|v| v.tileset_id == tileset_id)
+            .and_then(|v| set.wang_id(v.index));
+        if current.is_none() && !painted_cells.contains(&(x, y)) {
+            continue;
+        }
+        let mut desired = [0u8; 8];
+        for &s in slots {
+            desired[s] = painted
+                .get(&slot_pos(x, y, s))
+                .copied()
+                .unwrap_or_else(|| current.map(|c| c[s]).unwrap_or(0));
+        }
+        if current.is_some_and(|c| set.mismatch(&c, &desired) == 0) {
+            continue;
+        }
+        plan.push((x, y, desired));
+    }
+
+    for (x, y, desired) in plan {
+        let Some(best) = set.tiles.iter().map(|t| set.mismatch(&t.wang_id, &desired)).min() else {
+            continue;
+        };
+        let candidates: Vec<&WangTile> =
+            set.tiles.iter().filter(|t| set.mismatch(&t.wang_id, &desired) == best).collect();
+        let weights: Vec<f32> = candidates.iter().map(|t| set.weight(&t.wang_id)).collect();
+        let pick = rng
+            .pick_weighted(&weights)
+            .unwrap_or_else(|| rng.below(candidates.len() as u32) as usize);
+        let tile = TileRef {
+            tileset_id: tileset_id.to_string(),
+            index: candidates[pick].index,
+            rot: 0,
+            flip_x: false,
+            flip_y: false,
+        };
+        rec.set_tile(map, layer, x, y, Some(&tile));
+    }
+    rec.finish()
+}
+
+/// 地形版油漆桶：把与 `(x, y)` 相同的 4 邻接连通区域涂成 `color`。
+pub fn fill_wang(
+    map: &mut TileMapData,
+    layer: u32,
+    (x, y): (i32, i32),
+    tileset_id: &str,
+    set: &WangSet,
+    color: u8,
+    rng: &mut TileRng,
+) -> ChangeSet {
+    let region = connected_region(map, layer, x, y);
+    paint_wang(map, layer, tileset_id, set, color, &region, rng)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ops::{reapply, revert};
+
+    /// 草地(1) / 沙地(2) 的 corner 集：tile i 的第 k 个角（右上、右下、左下、左上）在 bit k 置位时为沙地；
+    /// 另有 16 号与 0 号同为全草地，用于验证等价候选的随机挑选。
+    fn grass_sand() -> WangSet {
+        let corner = |i: u32| {
+            let mut id = [0u8; 8];
+            for (k, &s) in CORNER_SLOTS.iter().enumerate() {
+                id[s] = if i & (1 << k) != 0 { 2 } else { 1 };
+            }
+            id
+        };
+        let mut tiles: Vec<WangTile> = (0..16).map(|i| WangTile { index: i, wang_id: corner(i) }).collect();
+        tiles.push(WangTile { index: 16, wang_id: corner(0) });
+        let color = |name: &str| WangColor { name: name.to_string(), ..Default::default() };
+        WangSet {
+            name: "terrain".to_string(),
+            kind: WangKind::Corner,
+            tile: None,
+            colors: vec![color("grass"), color("sand")],
+            tiles,
+        }
+    }
+
+    fn index_at(map: &TileMapData, x: i32, y: i32) -> Option<u32> {
+        map.get(0, x, y).map(|t| t.index)
+    }
+
+    #[test]
+    fn painting_a_corner_set_updates_shared_corners() {
+        let set = grass_sand();
+        let mut map = TileMapData::new_with_layers(5, 5, 1);
+        let mut rng = TileRng::new(7);
+        let cells: Vec<(i32, i32)> = (0..5).flat_map(|y| (0..5).map(move |x| (x, y))).collect();
+        paint_wang(&mut map, 0, "t", &set, 1, &cells, &mut rng);
+        assert!(map.iter_tiles().all(|(_, _, _, t)| t.index == 0 || t.index == 16));
+
+        let changes = paint_wang(&mut map, 0, "t", &set, 2, &[(2, 2)], &mut rng);
+        assert_eq!(changes.len(), 9);
+        assert_eq!(index_at(&map, 2, 2), Some(15));
+        // 东邻的左上、左下角；东北邻的左下角；北邻的右下、左下角。
+        assert_eq!(index_at(&map, 3, 2), Some(0b1100));
+        assert_eq!(index_at(&map, 3, 3), Some(0b0100));
+        assert_eq!(index_at(&map, 2, 3), Some(0b0110));
+
+        // 再涂一次不产生变更；撤销 / 重做往返。
+        assert!(paint_wang(&mut map, 0, "t", &set, 2, &[(2, 2)], &mut rng).is_empty());
+        revert(&mut map, &changes);
+        assert!(matches!(index_at(&map, 2, 2), Some(0 | 16)));
+        reapply(&mut map, &changes);
+        assert_eq!(index_at(&map, 3, 2), Some(0b1100));
+    }
+
+    #[test]
+    fn equal_candidates_are_picked_by_seeded_rng() {
+        let set = grass_sand();
+        let pick = |seed: u64| {
+            let mut map = TileMapData::new_with_layers(1, 1, 1);
+            paint_wang(&mut map, 0, "t", &set, 1, &[(0, 0)], &mut TileRng::new(seed));
+            index_at(&map, 0, 0).unwrap()
+        };
+        let picks: HashSet<u32> = (0..32).map(pick).collect();
+        assert_eq!(picks, HashSet::from([0, 16]));
+        assert_eq!(pick(5), pick(5));
+    }
+}
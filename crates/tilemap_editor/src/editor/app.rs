@@ -9,15 +9,129 @@ use super::{
 	tileset,
 	types::{
 		BrushSettings, Clipboard, ContextMenuCommand, ContextMenuState, EditorConfig, EditorState,
-			LayerState, MapSizeInput, MenuState, PanState, PastePreview, PasteState, SelectionState, ShiftMapSettings,
+			LayerState, MapSizeInput, MenuState, PanState, PastePreview, PasteState, LineToolState, ShapePreview, SelectionState, ShiftMapSettings,
 			TilesetLibrary, TilesetLoading, TilesetRuntime, ToolState, UiState, UndoStack,
-			PaletteSearchInput, LayerNameInput,
+			PaletteSearchInput, LayerNameInput, ProblemsState, RelinkDialogState, ReplaceTileDialogState, TilesetGridDialogState,
+			TileAnimationClock, TileAnimationEditorState, TileCollisionEditorState, MetaBrushState,
+			AutomapState, AutotileBrushState, VariationBrushState, WangBrushState,
 	},
 	ui,
 	world,
 	UI_BG,
 };
 
+/// 编辑器全局资源（正式运行与 headless 测试共用同一份注册表）。
+pub(super) struct EditorResourcesPlugin;
+
+impl Plugin for EditorResourcesPlugin {
+	fn build(&self, app: &mut App) {
+		app
+			.init_resource::<EditorConfig>()
+			.init_resource::<EditorState>()
+			.init_resource::<TilesetLibrary>()
+			.init_resource::<TilesetRuntime>()
+			.init_resource::<TilesetLoading>()
+			.init_resource::<PanState>()
+			.init_resource::<MapSizeInput>()
+			.init_resource::<UiState>()
+			.init_resource::<PaletteSearchInput>()
+			.init_resource::<LayerNameInput>()
+			.init_resource::<MenuState>()
+			.init_resource::<ToolState>()
+			.init_resource::<BrushSettings>()
+			.init_resource::<Clipboard>()
+			.init_resource::<ContextMenuState>()
+			.init_resource::<ContextMenuCommand>()
+			.init_resource::<PasteState>()
+			.init_resource::<PastePreview>()
+			.init_resource::<LineToolState>()
+			.init_resource::<ShapePreview>()
+			.init_resource::<LayerState>()
+			.init_resource::<SelectionState>()
+			.init_resource::<ShiftMapSettings>()
+			.init_resource::<ProblemsState>()
+			.init_resource::<RelinkDialogState>()
+			.init_resource::<TilesetGridDialogState>()
+			.init_resource::<ReplaceTileDialogState>()
+			.init_resource::<TileAnimationClock>()
+			.init_resource::<TileAnimationEditorState>()
+			.init_resource::<TileCollisionEditorState>()
+			.init_resource::<MetaBrushState>()
+			.init_resource::<AutotileBrushState>()
+			.init_resource::<WangBrushState>()
+			.init_resource::<VariationBrushState>()
+			.init_resource::<AutomapState>()
+			.init_resource::<UndoStack>();
+	}
+}
+
+/// 编辑逻辑：快捷键、鼠标工具与 tile 动画时钟（只读写资源与地图，不依赖窗口后端、渲染或资源加载）。
+///
+/// 正式运行与 headless 测试共用，保证测试里的系统集合与顺序和编辑器一致。
+pub(super) struct EditorToolsPlugin;
+
+impl Plugin for EditorToolsPlugin {
+	fn build(&self, app: &mut App) {
+		app
+			.add_systems(
+				Update,
+				(
+					// --- World: keyboard shortcuts ---
+					world::keyboard_shortcuts,
+					world::layer_shortcuts,
+					world::tool_shortcuts,
+					world::eyedropper_hold_shortcut,
+					world::copy_paste_shortcuts,
+					world::paste_transform_shortcuts,
+					world::context_menu_open_close,
+					world::context_menu_clear_consumption,
+					world::apply_context_menu_command,
+					world::shift_map_shortcuts,
+					world::move_selection_shortcuts,
+					world::selection_cut_delete_shortcuts,
+					world::selection_selectall_cancel_shortcuts,
+					world::undo_redo_shortcuts,
+				),
+			)
+			// --- Tile animation: clock + canvas ---
+			.add_systems(Update, (world::advance_tile_animation_clock, world::animate_map_tiles).chain())
+			// --- World: mouse tools ---
+			.add_systems(Update, world::update_paste_preview)
+			.add_systems(
+				Update,
+				world::update_shape_preview
+					.after(world::line_with_mouse)
+					.after(world::rect_with_mouse),
+			)
+			.add_systems(Update, world::selection_move_with_mouse)
+			.add_systems(Update, world::eyedropper_with_mouse)
+			.add_systems(Update, world::paint_with_mouse)
+			.add_systems(Update, world::rect_with_mouse)
+			.add_systems(Update, world::fill_with_mouse)
+			.add_systems(Update, world::line_with_mouse)
+			.add_systems(
+				Update,
+				world::automap_after_edit
+					.after(world::paint_with_mouse)
+					.after(world::rect_with_mouse)
+					.after(world::fill_with_mouse)
+					.after(world::line_with_mouse),
+			)
+			// 拖动移动选区先于框选：按下时若落在选区内由前者接管，框选看到 moving 后不再重置选区。
+			.add_systems(Update, world::select_with_mouse.after(world::selection_move_with_mouse))
+			.add_systems(Update, world::magic_wand_with_mouse)
+			.add_systems(
+				Update,
+				// 临时粘贴落地后会切回原工具，排在绘制工具之后，避免同一次点击又被原工具画一遍。
+				world::paste_with_mouse
+					.after(world::paint_with_mouse)
+					.after(world::rect_with_mouse)
+					.after(world::fill_with_mouse)
+					.after(world::line_with_mouse),
+			);
+	}
+}
+
 /// 运行编辑器。
 pub fn run() {
 	let assets_dir = workspace_assets_dir();
@@ -41,29 +155,8 @@ pub fn run() {
 				}),
 		)
 		.add_plugins(TilemapPlugin)
-		// --- Resources ---
-		.init_resource::<EditorConfig>()
-		.init_resource::<EditorState>()
-		.init_resource::<TilesetLibrary>()
-		.init_resource::<TilesetRuntime>()
-		.init_resource::<TilesetLoading>()
-		.init_resource::<PanState>()
-		.init_resource::<MapSizeInput>()
-		.init_resource::<UiState>()
-		.init_resource::<PaletteSearchInput>()
-		.init_resource::<LayerNameInput>()
-		.init_resource::<MenuState>()
-		.init_resource::<ToolState>()
-		.init_resource::<BrushSettings>()
-		.init_resource::<Clipboard>()
-		.init_resource::<ContextMenuState>()
-		.init_resource::<ContextMenuCommand>()
-		.init_resource::<PasteState>()
-		.init_resource::<PastePreview>()
-		.init_resource::<LayerState>()
-		.init_resource::<SelectionState>()
-		.init_resource::<ShiftMapSettings>()
-		.init_resource::<UndoStack>()
+		.add_plugins(EditorResourcesPlugin)
+		.add_plugins(EditorToolsPlugin)
 		.add_systems(
 			Startup,
 			(
@@ -102,16 +195,27 @@ pub fn run() {
 							ui::update_palette_search_text,
 							ui::sync_palette_zoom_button_styles,
 							ui::palette_tile_click,
+							ui::sync_palette_tile_styles,
 							ui::palette_scroll_wheel,
 						)
 							.chain(),
 						(
 							ui::tool_button_click,
 							ui::sync_tool_button_styles,
+							ui::sample_source_button_click,
+							ui::sync_sample_source_button,
 						ui::brush_size_button_click,
 						ui::sync_brush_size_button_styles,
 							ui::shift_mode_button_click,
 							ui::update_shift_mode_label,
+							ui::meta_brush_button_click,
+							ui::sync_meta_brush_styles,
+							ui::autotile_button_click,
+							ui::sync_autotile_styles,
+							ui::wang_brush_button_click,
+							ui::sync_wang_brush_styles,
+							ui::variation_brush_button_click,
+							ui::sync_variation_brush_styles,
 						)
 							.chain(),
 					)
@@ -150,6 +254,28 @@ pub fn run() {
 				ui::action_button_click,
 			),
 		)
+		.add_systems(
+			Update,
+			(
+				// --- UI: problems panel + relink / tileset grid / replace tile dialogs ---
+				ui::canvas_blockers_consume_clicks,
+				ui::tileset_grid_dialog_interactions,
+				ui::tileset_grid_text_input,
+				ui::apply_tileset_grid,
+				ui::update_tileset_grid_dialog,
+				ui::replace_tile_dialog_button_click,
+				ui::update_replace_tile_dialog,
+				ui::problems_panel_button_click,
+				ui::relink_dialog_button_click,
+				ui::apply_relink_pick,
+				ui::apply_relink_remap,
+				ui::sync_failed_tilesets_into_dialog,
+				ui::refresh_map_problems,
+				ui::problems_panel_visibility,
+				ui::relink_dialog_visibility,
+			)
+				.chain(),
+		)
 		.add_systems(
 			PostUpdate,
 			(
@@ -158,6 +284,10 @@ pub fn run() {
 				ui::context_menu_rebuild,
 				ui::rebuild_tileset_menu_when_needed,
 				ui::build_palette_when_ready,
+				ui::rebuild_problems_panel_when_needed,
+				ui::rebuild_relink_dialog_when_needed,
+				ui::rebuild_tile_animation_frames_when_needed,
+				ui::rebuild_tile_collision_editor_when_needed,
 			)
 				.chain()
 				.before(UiSystems::Layout),
@@ -172,27 +302,30 @@ pub fn run() {
 				.chain()
 				.after(UiSystems::Layout),
 		)
+		.add_systems(Update, world::save_load_shortcuts)
 		.add_systems(
 			Update,
 			(
-				// --- World: keyboard shortcuts ---
-				world::keyboard_shortcuts,
-				world::layer_shortcuts,
-				world::tool_shortcuts,
-				world::eyedropper_hold_shortcut,
-				world::copy_paste_shortcuts,
-				world::paste_transform_shortcuts,
-				world::context_menu_open_close,
-				world::context_menu_clear_consumption,
-				world::apply_context_menu_command,
-				world::shift_map_shortcuts,
-				world::move_selection_shortcuts,
-				world::selection_cut_delete_shortcuts,
-				world::selection_selectall_cancel_shortcuts,
-				world::undo_redo_shortcuts,
-				world::save_load_shortcuts,
+				// --- Tile animation: editor panel / palette / preview ---
+				(ui::tile_animation_editor_button_click, ui::tile_animation_editor_visibility)
+					.chain()
+					.after(world::advance_tile_animation_clock)
+					.before(world::animate_map_tiles),
+				(ui::animate_palette_tiles, ui::animate_tile_animation_preview)
+					.chain()
+					.after(world::animate_map_tiles),
 			),
 		)
+		.add_systems(
+			Update,
+			(
+				// --- Tile collision: editor panel ---
+				ui::tile_collision_editor_button_click,
+				ui::tile_collision_editor_drag,
+				ui::tile_collision_editor_visibility,
+			)
+				.chain(),
+		)
 		.add_systems(
 			Update,
 			(
@@ -206,15 +339,14 @@ pub fn run() {
 			)
 				.chain(),
 		)
-		// --- World: mouse tools + HUD ---
-		.add_systems(Update, world::draw_canvas_helpers)
-		.add_systems(Update, world::update_paste_preview)
-		.add_systems(Update, world::selection_move_with_mouse)
-		.add_systems(Update, world::eyedropper_with_mouse)
-		.add_systems(Update, world::paint_with_mouse)
-		.add_systems(Update, world::rect_with_mouse)
-		.add_systems(Update, world::fill_with_mouse)
-		.add_systems(Update, world::select_with_mouse)
-		.add_systems(Update, (world::paste_with_mouse, ui::update_hud_text))
+		// --- World: canvas overlays + HUD ---
+		.add_systems(Update, (world::draw_canvas_helpers, world::draw_collision_overlay, world::sync_meta_overlay))
+		.add_systems(
+			Update,
+			world::draw_shape_preview_frame
+				.after(world::line_with_mouse)
+				.after(world::rect_with_mouse),
+		)
+		.add_systems(Update, ui::update_hud_text.after(world::paste_with_mouse))
 		.run();
 }
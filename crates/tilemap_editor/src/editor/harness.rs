@@ -0,0 +1,207 @@
+//! Headless 测试夹具：用 `MinimalPlugins` 装配编辑器 App（无窗口、无 GPU），
+//! 注入合成的光标位置、鼠标按键与键盘输入，再断言 `TileMapData` / `UndoStack`。
+//!
+//! 编辑逻辑与正式运行共用 `EditorToolsPlugin`，系统集合与执行顺序一致；只额外挂上测试用到的几个 UI 系统。
+//!
+//! 说明：
+//! - 窗口只是一个带 `PrimaryWindow` 的实体，没有 winit 后端；光标位置直接写进 `Window`。
+//! - 相机的 `computed`（投影矩阵 + 渲染目标尺寸）平时由渲染侧的 camera_system 填写，这里按窗口尺寸手动算好。
+//! - 不插入 `TileEntities`，渲染同步会整体跳过，只验证数据层结果。
+
+use bevy::camera::{CameraProjection, RenderTargetInfo};
+use bevy::prelude::*;
+use bevy::window::{PrimaryWindow, WindowResolution};
+
+use super::app::{EditorResourcesPlugin, EditorToolsPlugin};
+use super::types::{
+    EditorConfig, EditorState, TileMapData, TilesetLibrary, ToolKind, ToolState, UndoStack,
+    WorldCamera,
+};
+use super::{ui, LEFT_PANEL_WIDTH_PX, UI_TOP_RESERVED_PX};
+
+mod tests;
+
+pub(super) const WINDOW_WIDTH: u32 = 1280;
+pub(super) const WINDOW_HEIGHT: u32 = 720;
+
+/// 测试用 tileset id（地图只记录 id，不需要真实贴图）。
+pub(super) const TEST_TILESET: &str = "test";
+
+pub(super) struct EditorHarness {
+    pub app: App,
+}
+
+impl EditorHarness {
+    /// 以给定地图构建 App；相机把地图中心对准画布区域中心。
+    pub fn new(map: TileMapData) -> Self {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugins(EditorResourcesPlugin)
+            .init_resource::<ButtonInput<KeyCode>>()
+            .init_resource::<ButtonInput<MouseButton>>()
+            .add_plugins(EditorToolsPlugin)
+            .add_systems(
+                Update,
+                (ui::tile_collision_editor_drag, ui::replace_tile_dialog_button_click),
+            );
+
+        let tile_size = app.world().resource::<EditorConfig>().tile_size.as_vec2();
+        let (map_w, map_h) = map.fixed_size().unwrap_or((0, 0));
+        app.insert_resource(map);
+        app.world_mut().resource_mut::<TilesetLibrary>().active_id = Some(TEST_TILESET.to_string());
+
+        app.world_mut().spawn((
+            Window {
+                resolution: WindowResolution::new(WINDOW_WIDTH, WINDOW_HEIGHT),
+                ..default()
+            },
+            PrimaryWindow,
+        ));
+
+        // 画布区域 = 窗口去掉左侧面板与顶部工具条。
+        let window_size = Vec2::new(WINDOW_WIDTH as f32, WINDOW_HEIGHT as f32);
+        let canvas_min = Vec2::new(LEFT_PANEL_WIDTH_PX, UI_TOP_RESERVED_PX);
+        let canvas_center = (canvas_min + window_size) * 0.5;
+        let offset = canvas_center - window_size * 0.5;
+        let map_center = Vec2::new(map_w as f32, map_h as f32) * tile_size * 0.5;
+        // 屏幕 y 向下、世界 y 向上。
+        let cam_pos = Vec3::new(map_center.x - offset.x, map_center.y + offset.y, 1000.0);
+
+        let mut projection = OrthographicProjection::default_2d();
+        projection.update(window_size.x, window_size.y);
+        let mut camera = Camera::default();
+        camera.computed.clip_from_view = projection.get_clip_from_view();
+        camera.computed.target_info = Some(RenderTargetInfo {
+            physical_size: UVec2::new(WINDOW_WIDTH, WINDOW_HEIGHT),
+            scale_factor: 1.0,
+        });
+        let transform = Transform::from_translation(cam_pos);
+        app.world_mut().spawn((
+            Camera2d,
+            camera,
+            Projection::Orthographic(projection),
+            transform,
+            GlobalTransform::from(transform),
+            WorldCamera,
+        ));
+
+        Self { app }
+    }
+
+    /// 固定尺寸空地图（默认 2 层）。
+    pub fn with_size(width: u32, height: u32) -> Self {
+        Self::new(TileMapData::new(width, height))
+    }
+
+    pub fn map(&self) -> &TileMapData {
+        self.app.world().resource::<TileMapData>()
+    }
+
+    pub fn map_mut(&mut self) -> Mut<'_, TileMapData> {
+        self.app.world_mut().resource_mut::<TileMapData>()
+    }
+
+    pub fn undo(&self) -> &UndoStack {
+        self.app.world().resource::<UndoStack>()
+    }
+
+    pub fn resource_mut<R: Resource>(&mut self) -> Mut<'_, R> {
+        self.app.world_mut().resource_mut::<R>()
+    }
+
+    pub fn set_tool(&mut self, tool: ToolKind) {
+        self.resource_mut::<ToolState>().tool = tool;
+    }
+
+    /// 选中测试 tileset 的第 `index` 个 tile。
+    pub fn select_tile(&mut self, index: u32) {
+        self.resource_mut::<EditorState>().selected_tile = index;
+    }
+
+    /// 跑一帧，然后像 InputPlugin 一样清掉本帧的 just_pressed / just_released。
+    pub fn update(&mut self) {
+        self.app.update();
+        self.resource_mut::<ButtonInput<KeyCode>>().clear();
+        self.resource_mut::<ButtonInput<MouseButton>>().clear();
+    }
+
+    /// 直接设置窗口内的逻辑光标位置（None = 光标离开窗口）。
+    pub fn set_cursor(&mut self, pos: Option<Vec2>) {
+        let world = self.app.world_mut();
+        let mut q = world.query_filtered::<&mut Window, With<PrimaryWindow>>();
+        q.single_mut(world)
+            .expect("harness window")
+            .set_cursor_position(pos);
+    }
+
+    /// 格子中心对应的屏幕坐标。
+    pub fn tile_screen_pos(&mut self, x: i32, y: i32) -> Vec2 {
+        let tile_size = self.app.world().resource::<EditorConfig>().tile_size.as_vec2();
+        let world_pos = (Vec2::new(x as f32, y as f32) + Vec2::splat(0.5)) * tile_size;
+        let world = self.app.world_mut();
+        let mut q = world.query_filtered::<(&Camera, &GlobalTransform), With<WorldCamera>>();
+        let (camera, camera_transform) = q.single(world).expect("harness camera");
+        camera
+            .world_to_viewport(camera_transform, world_pos.extend(0.0))
+            .expect("tile is in view")
+    }
+
+    /// 把光标移到格子中心（不跑帧）。
+    pub fn hover_tile(&mut self, x: i32, y: i32) {
+        let pos = self.tile_screen_pos(x, y);
+        self.set_cursor(Some(pos));
+    }
+
+    pub fn mouse_down(&mut self) {
+        self.resource_mut::<ButtonInput<MouseButton>>().press(MouseButton::Left);
+        self.update();
+    }
+
+    pub fn mouse_up(&mut self) {
+        self.resource_mut::<ButtonInput<MouseButton>>().release(MouseButton::Left);
+        self.update();
+    }
+
+    /// 在格子上单击左键（按下一帧 + 松开一帧）。
+    pub fn click_tile(&mut self, x: i32, y: i32) {
+        self.hover_tile(x, y);
+        self.mouse_down();
+        self.mouse_up();
+    }
+
+    /// 按住左键依次经过 `path` 中的格子后松开。
+    pub fn drag_tiles(&mut self, path: &[(i32, i32)]) {
+        let Some((&(x, y), rest)) = path.split_first() else {
+            return;
+        };
+        self.hover_tile(x, y);
+        self.mouse_down();
+        for &(x, y) in rest {
+            self.hover_tile(x, y);
+            self.update();
+        }
+        self.mouse_up();
+    }
+
+    pub fn hold_key(&mut self, key: KeyCode) {
+        self.resource_mut::<ButtonInput<KeyCode>>().press(key);
+    }
+
+    pub fn release_key(&mut self, key: KeyCode) {
+        self.resource_mut::<ButtonInput<KeyCode>>().release(key);
+    }
+
+    /// 按住 `modifiers` 敲一下 `key`（按下一帧 + 松开一帧）。
+    pub fn chord(&mut self, modifiers: &[KeyCode], key: KeyCode) {
+        for &m in modifiers {
+            self.hold_key(m);
+        }
+        self.hold_key(key);
+        self.update();
+        self.release_key(key);
+        for &m in modifiers {
+            self.release_key(m);
+        }
+        self.update();
+    }
+}
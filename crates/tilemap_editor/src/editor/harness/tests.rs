@@ -0,0 +1,882 @@
+use bevy::prelude::*;
+use bevy::ui::RelativeCursorPosition;
+use bevy_ecs_tilemap::prelude::TileTextureIndex;
+use tilemap_core::{blob_variant, TileGrid, TileMapData, AUTOMAP_EMPTY, AUTOMAP_TILESET, TileRng, WangColor, WangKind, WangSet, WangTile, AUTOTILE_E, AUTOTILE_W};
+use tilemap_format::AnimationFrame;
+
+use crate::editor::types::{
+    AnimatedTile, AutomapRule, AutomapState, AutotileBrushState, AutotileDef, Clipboard, CollisionShape, CollisionTileImage, EditorConfig, EditorState, FillMode, LineToolState, MetaBrushState, MetaField,
+    LayerState, Orientation, PasteState, palette_stamp, ReplaceLayers, ReplaceTileAction, ReplaceTileButton,
+    ReplaceTileDialogState, SampleSource, SelectionState, PASS_BLOCK_LEFT, PASS_BLOCK_UP,
+    ShapeMode, ShapePreview, ShiftMapMode, ShiftMapSettings, TileAnimationClock, TileCollisionEditorState, TileRef, TilesetEntry,
+    TilesetLibrary, TilesetRuntime, TilesetRuntimeEntry, ToolKind, ToolState, Variation, VariationBrushState, VariationSet,
+    WangBrushState,
+};
+
+use super::{EditorHarness, TEST_TILESET};
+
+fn tile(index: u32) -> TileRef {
+    TileRef {
+        tileset_id: TEST_TILESET.to_string(),
+        index,
+        rot: 0,
+        flip_x: false,
+        flip_y: false,
+    }
+}
+
+fn index_at(h: &EditorHarness, layer: u32, x: i32, y: i32) -> Option<u32> {
+    h.map().get(layer, x, y).map(|t| t.index)
+}
+
+const CTRL: KeyCode = KeyCode::ControlLeft;
+
+#[test]
+fn pencil_click_paints_one_undo_step() {
+    let mut h = EditorHarness::with_size(8, 8);
+    h.select_tile(3);
+    h.click_tile(2, 5);
+
+    assert_eq!(h.map().get(0, 2, 5), Some(tile(3)));
+    assert_eq!(h.map().iter_tiles().count(), 1);
+    assert_eq!(h.undo().undo.len(), 1);
+    assert_eq!(h.undo().undo[0].changes.len(), 1);
+}
+
+#[test]
+fn pencil_drag_is_a_single_stroke() {
+    let mut h = EditorHarness::with_size(8, 8);
+    h.select_tile(1);
+    h.drag_tiles(&[(0, 0), (1, 0), (2, 0), (2, 1)]);
+
+    for (x, y) in [(0, 0), (1, 0), (2, 0), (2, 1)] {
+        assert_eq!(index_at(&h, 0, x, y), Some(1), "({x}, {y})");
+    }
+    assert_eq!(h.undo().undo.len(), 1);
+    assert_eq!(h.undo().undo[0].changes.len(), 4);
+}
+
+#[test]
+fn clicks_outside_canvas_are_ignored() {
+    let mut h = EditorHarness::with_size(8, 8);
+    // 左侧面板
+    h.set_cursor(Some(Vec2::new(10.0, 400.0)));
+    h.mouse_down();
+    h.mouse_up();
+    // 顶部工具条
+    h.set_cursor(Some(Vec2::new(800.0, 10.0)));
+    h.mouse_down();
+    h.mouse_up();
+
+    assert_eq!(h.map().iter_tiles().count(), 0);
+    assert!(h.undo().undo.is_empty());
+}
+
+#[test]
+fn locked_layer_is_not_painted() {
+    let mut h = EditorHarness::with_size(8, 8);
+    h.map_mut().layer_data[0].locked = true;
+    h.click_tile(1, 1);
+
+    assert_eq!(h.map().get(0, 1, 1), None);
+    assert!(h.undo().undo.is_empty());
+}
+
+#[test]
+fn eraser_clears_painted_tile() {
+    let mut h = EditorHarness::with_size(8, 8);
+    h.click_tile(4, 4);
+    h.set_tool(ToolKind::Eraser);
+    h.click_tile(4, 4);
+
+    assert_eq!(h.map().get(0, 4, 4), None);
+    assert_eq!(h.undo().undo.len(), 2);
+}
+
+#[test]
+fn fill_then_undo_redo() {
+    let mut h = EditorHarness::with_size(4, 3);
+    // 用一列墙把地图分成左右两块。
+    for y in 0..3 {
+        h.map_mut().set(0, 1, y, Some(tile(9)));
+    }
+    h.set_tool(ToolKind::Fill);
+    h.select_tile(2);
+    h.click_tile(3, 0);
+
+    assert_eq!(h.undo().undo.len(), 1);
+    assert_eq!(h.undo().undo[0].changes.len(), 6);
+    for y in 0..3 {
+        assert_eq!(index_at(&h, 0, 0, y), None);
+        assert_eq!(index_at(&h, 0, 1, y), Some(9));
+        assert_eq!(index_at(&h, 0, 2, y), Some(2));
+        assert_eq!(index_at(&h, 0, 3, y), Some(2));
+    }
+
+    h.chord(&[CTRL], KeyCode::KeyZ);
+    assert_eq!(h.map().iter_tiles().count(), 3);
+    assert_eq!(h.undo().undo.len(), 0);
+    assert_eq!(h.undo().redo.len(), 1);
+
+    h.chord(&[CTRL], KeyCode::KeyY);
+    assert_eq!(h.map().iter_tiles().count(), 9);
+    assert_eq!(h.undo().undo.len(), 1);
+    assert!(h.undo().redo.is_empty());
+}
+
+#[test]
+fn meta_mode_paints_regions_and_passage_with_undo() {
+    let mut h = EditorHarness::with_size(6, 4);
+    // 元数据与图层无关：锁定图层也能写，且不改动 tile。
+    h.map_mut().layer_data[0].locked = true;
+    {
+        let mut brush = h.resource_mut::<MetaBrushState>();
+        brush.enabled = true;
+        brush.region = 4;
+    }
+    h.drag_tiles(&[(0, 0), (1, 0), (1, 1)]);
+    assert_eq!(h.undo().undo.len(), 1);
+    assert_eq!(h.undo().undo[0].meta.len(), 3);
+    assert!(h.undo().undo[0].changes.is_empty());
+    assert_eq!(h.map().meta.get(1, 1).region, 4);
+    assert_eq!(h.map().iter_tiles().count(), 0);
+
+    // 通行字段只改通行位，区域保持不变；油漆桶按通行值的连通区域填充。
+    {
+        let mut brush = h.resource_mut::<MetaBrushState>();
+        brush.field = MetaField::Passage;
+        brush.passage = PASS_BLOCK_UP | PASS_BLOCK_LEFT;
+    }
+    h.set_tool(ToolKind::Fill);
+    h.click_tile(5, 3);
+    assert_eq!(h.undo().undo[1].meta.len(), 24);
+    assert_eq!(h.map().meta.get(1, 0).region, 4);
+    assert_eq!(h.map().meta.get(1, 0).passage, PASS_BLOCK_UP | PASS_BLOCK_LEFT);
+
+    h.chord(&[CTRL], KeyCode::KeyZ);
+    assert_eq!(h.map().meta.len(), 3);
+    assert_eq!(h.map().meta.get(1, 0).passage, 0);
+    h.chord(&[CTRL], KeyCode::KeyZ);
+    assert!(h.map().meta.is_empty());
+    h.chord(&[CTRL], KeyCode::KeyY);
+    assert_eq!(h.map().meta.get(0, 0).region, 4);
+    assert_eq!(h.undo().redo.len(), 1);
+
+    // 元数据填充同样跟随填充方式：整层模式把不相连的 (5,3) 也一起改掉。
+    let mut isolated = h.map().meta.get(5, 3);
+    isolated.region = 4;
+    h.map_mut().meta.set(5, 3, isolated);
+    {
+        let mut brush = h.resource_mut::<MetaBrushState>();
+        brush.field = MetaField::Region;
+        brush.region = 7;
+    }
+    h.resource_mut::<ToolState>().fill_mode = FillMode::Global;
+    h.click_tile(0, 0);
+    assert_eq!(h.undo().undo.last().unwrap().meta.len(), 4);
+    assert_eq!(h.map().meta.get(5, 3).region, 7);
+}
+
+#[test]
+fn autotile_mode_picks_variants_and_updates_neighbors() {
+    let mut h = EditorHarness::with_size(6, 4);
+    let mut entry = TilesetEntry { id: TEST_TILESET.to_string(), ..Default::default() };
+    entry.set_autotile(AutotileDef { name: "grass".to_string(), first: 0 });
+    h.resource_mut::<TilesetLibrary>().entries.push(entry);
+    h.resource_mut::<AutotileBrushState>().enabled = true;
+    h.select_tile(5);
+
+    // 一笔拖过三格：两端各连一侧，中间两侧都连。
+    h.drag_tiles(&[(1, 1), (2, 1), (3, 1)]);
+    assert_eq!(h.undo().undo.len(), 1);
+    assert_eq!(index_at(&h, 0, 1, 1), Some(blob_variant(AUTOTILE_E)));
+    assert_eq!(index_at(&h, 0, 2, 1), Some(blob_variant(AUTOTILE_E | AUTOTILE_W)));
+    assert_eq!(index_at(&h, 0, 3, 1), Some(blob_variant(AUTOTILE_W)));
+
+    // 擦掉中间：两侧邻居在同一条撤销记录里变回孤立块。
+    h.set_tool(ToolKind::Eraser);
+    h.click_tile(2, 1);
+    assert_eq!(h.undo().undo.len(), 2);
+    assert_eq!(h.undo().undo[1].changes.len(), 3);
+    assert_eq!(index_at(&h, 0, 1, 1), Some(blob_variant(0)));
+    h.chord(&[CTRL], KeyCode::KeyZ);
+    assert_eq!(index_at(&h, 0, 1, 1), Some(blob_variant(AUTOTILE_E)));
+
+    // 油漆桶铺满空白区域：整张图连成一片（地图边界视为相连）。
+    h.set_tool(ToolKind::Fill);
+    h.click_tile(0, 3);
+    assert_eq!(h.map().iter_tiles().count(), 24);
+    assert!(h.map().iter_tiles().all(|(_, _, _, t)| t.index == blob_variant(0xFF)));
+    h.chord(&[CTRL], KeyCode::KeyZ);
+    assert_eq!(h.map().iter_tiles().count(), 3);
+    assert_eq!(index_at(&h, 0, 2, 1), Some(blob_variant(AUTOTILE_E | AUTOTILE_W)));
+}
+
+#[test]
+fn wang_mode_paints_corners_and_updates_neighbors() {
+    let mut h = EditorHarness::with_size(4, 4);
+    // 16 块 corner 过渡：index 的 bit0..3 = 右上 / 右下 / 左下 / 左上为沙（颜色 2），否则为草（颜色 1）。
+    let tiles = (0..16u32)
+        .map(|index| {
+            let mut wang_id = [0; 8];
+            for (bit, slot) in [1, 3, 5, 7].into_iter().enumerate() {
+                wang_id[slot] = 1 + ((index >> bit) & 1) as u8;
+            }
+            WangTile { index, wang_id }
+        })
+        .collect();
+    let set = WangSet {
+        name: "ground".to_string(),
+        kind: WangKind::Corner,
+        colors: vec![
+            WangColor { name: "grass".to_string(), ..Default::default() },
+            WangColor { name: "sand".to_string(), ..Default::default() },
+        ],
+        tiles,
+        ..Default::default()
+    };
+    let mut entry = TilesetEntry { id: TEST_TILESET.to_string(), ..Default::default() };
+    entry.wangsets.push(set);
+    h.resource_mut::<TilesetLibrary>().entries.push(entry);
+    {
+        let mut wang = h.resource_mut::<WangBrushState>();
+        wang.enabled = true;
+        wang.rng = TileRng::new(7);
+    }
+
+    // 油漆桶铺满草地，再用沙点一格：四个角都变沙，8 个邻居的共享角跟着更新，同一条撤销记录。
+    h.set_tool(ToolKind::Fill);
+    h.click_tile(0, 0);
+    assert!(h.map().iter_tiles().all(|(_, _, _, t)| t.index == 0));
+    h.resource_mut::<WangBrushState>().color = 2;
+    h.set_tool(ToolKind::Pencil);
+    h.click_tile(1, 1);
+    assert_eq!(h.undo().undo.len(), 2);
+    assert_eq!(h.undo().undo[1].changes.len(), 9);
+    assert_eq!(index_at(&h, 0, 1, 1), Some(15));
+    assert_eq!(index_at(&h, 0, 2, 1), Some(0b1100));
+    assert_eq!(index_at(&h, 0, 1, 0), Some(0b1001));
+    assert_eq!(index_at(&h, 0, 0, 2), Some(0b0010));
+    assert_eq!(index_at(&h, 0, 3, 3), Some(0));
+
+    // 橡皮不走地形求解，只擦当前格。
+    h.set_tool(ToolKind::Eraser);
+    h.click_tile(1, 1);
+    assert_eq!(index_at(&h, 0, 1, 1), None);
+    assert_eq!(index_at(&h, 0, 2, 1), Some(0b1100));
+    h.chord(&[CTRL], KeyCode::KeyZ);
+    h.chord(&[CTRL], KeyCode::KeyZ);
+    assert!(h.map().iter_tiles().all(|(_, _, _, t)| t.index == 0));
+}
+
+#[test]
+fn variation_brush_picks_weighted_members_reproducibly() {
+    let run = |seed| {
+        let mut h = EditorHarness::with_size(6, 6);
+        let mut entry = TilesetEntry { id: TEST_TILESET.to_string(), ..Default::default() };
+        entry.variations.push(VariationSet {
+            name: "grass".to_string(),
+            tiles: vec![Variation { index: 4, weight: 2.0 }, Variation { index: 5, weight: 1.0 }],
+            ..Default::default()
+        });
+        h.resource_mut::<TilesetLibrary>().entries.push(entry);
+        {
+            let mut variation = h.resource_mut::<VariationBrushState>();
+            variation.enabled = true;
+            variation.rng = TileRng::new(seed);
+        }
+        h.select_tile(9);
+
+        // 矩形框住左下 3x3，油漆桶填剩下的空白区域：每格都是变体集里的 tile，各一条撤销记录。
+        h.set_tool(ToolKind::Rect);
+        h.drag_tiles(&[(0, 0), (2, 2)]);
+        assert_eq!(h.map().iter_tiles().count(), 9);
+        h.set_tool(ToolKind::Fill);
+        h.click_tile(5, 5);
+        assert_eq!(h.undo().undo.len(), 2);
+        assert_eq!(h.map().iter_tiles().count(), 36);
+        assert!(h.map().iter_tiles().all(|(_, _, _, t)| t.index == 4 || t.index == 5));
+        h
+    };
+    let tiles = |h: &EditorHarness| {
+        let mut v: Vec<_> = h.map().iter_tiles().map(|(_, x, y, t)| (x, y, t.index)).collect();
+        v.sort_unstable();
+        v
+    };
+    let mut h = run(11);
+    assert_eq!(tiles(&h), tiles(&run(11)));
+    assert!(tiles(&h).iter().any(|t| t.2 == 4) && tiles(&h).iter().any(|t| t.2 == 5));
+
+    // 关闭随机模式后回到普通 tile。
+    h.resource_mut::<VariationBrushState>().enabled = false;
+    h.chord(&[CTRL], KeyCode::KeyZ);
+    h.click_tile(5, 5);
+    assert_eq!(index_at(&h, 0, 5, 5), Some(9));
+    assert_eq!(index_at(&h, 0, 1, 1).map(|i| i == 4 || i == 5), Some(true));
+}
+
+#[test]
+fn fill_modes_and_replace_tile_dialog() {
+    let mut h = EditorHarness::with_size(4, 4);
+    let mut turned = tile(1);
+    turned.set_orientation(Orientation::ROT_CW);
+    {
+        let mut map = h.map_mut();
+        for (x, y) in [(0, 0), (1, 1), (3, 0)] {
+            map.set(0, x, y, Some(tile(1)));
+        }
+        map.set(0, 3, 3, Some(turned));
+    }
+
+    // 再次选择油漆桶切到 8 邻接：对角相连的两格一起变，孤立的 (3,0) 不变。
+    h.set_tool(ToolKind::Fill);
+    h.resource_mut::<ToolState>().pick(ToolKind::Fill);
+    assert_eq!(h.app.world().resource::<ToolState>().fill_mode, FillMode::Diagonal);
+    h.select_tile(2);
+    h.click_tile(0, 0);
+    assert_eq!((index_at(&h, 0, 0, 0), index_at(&h, 0, 1, 1)), (Some(2), Some(2)));
+    assert_eq!(index_at(&h, 0, 3, 0), Some(1));
+
+    // 整层同类：不相连的空格子全部填上，一次撤销。
+    h.resource_mut::<ToolState>().pick(ToolKind::Fill);
+    h.select_tile(3);
+    h.click_tile(2, 3);
+    assert_eq!(h.map().iter_tiles().filter(|(_, _, _, t)| t.index == 3).count(), 12);
+    h.chord(&[CTRL], KeyCode::KeyZ);
+    assert_eq!(h.map().iter_tiles().count(), 4);
+
+    // 替换 Tile：忽略朝向时 (3,0) 与旋转过的 (3,3) 都换成 5 号，且保留朝向。
+    {
+        let mut dialog = h.resource_mut::<ReplaceTileDialogState>();
+        dialog.open_with(Some(tile(1)));
+        dialog.to = Some(tile(5));
+        dialog.ignore_orientation = true;
+        dialog.layers = ReplaceLayers::All;
+    }
+    h.app
+        .world_mut()
+        .spawn((Button, Interaction::Pressed, ReplaceTileButton(ReplaceTileAction::Apply)));
+    h.update();
+    assert_eq!(h.app.world().resource::<ReplaceTileDialogState>().replaced, Some(2));
+    assert_eq!(index_at(&h, 0, 3, 0), Some(5));
+    let replaced = h.map().get(0, 3, 3).unwrap();
+    assert_eq!((replaced.index, replaced.orientation()), (5, Orientation::ROT_CW));
+    assert_eq!(h.undo().undo.len(), 2);
+    h.chord(&[CTRL], KeyCode::KeyZ);
+    assert_eq!(index_at(&h, 0, 3, 3), Some(1));
+}
+
+#[test]
+fn fill_and_eyedropper_sample_other_layers() {
+    // 第 0 层满铺地面，第 1 层 x=2 一道竖墙，在空的第 2 层上填充。
+    let mut map = TileMapData::new_with_layers(4, 4, 3);
+    for y in 0..4 {
+        for x in 0..4 {
+            map.set(0, x, y, Some(tile(1)));
+        }
+        map.set(1, 2, y, Some(tile(9)));
+    }
+    let mut h = EditorHarness::new(map);
+    h.resource_mut::<LayerState>().active = 2;
+    h.resource_mut::<ToolState>().fill_sample = SampleSource::Visible;
+    h.set_tool(ToolKind::Fill);
+    h.select_tile(3);
+    h.click_tile(0, 0);
+    let filled: Vec<_> = h.map().iter_tiles().filter(|(l, _, _, _)| *l == 2).map(|(_, x, _, _)| x).collect();
+    assert_eq!(filled.len(), 8);
+    assert!(filled.iter().all(|&x| x < 2));
+    assert_eq!(h.undo().undo.len(), 1);
+
+    // 吸管：可见合成取最上层，指定图层只读该层；隐藏的图层不参与合成。
+    h.set_tool(ToolKind::Eyedropper);
+    h.click_tile(0, 0);
+    assert_eq!(h.app.world().resource::<EditorState>().selected_tile, 3);
+    h.resource_mut::<ToolState>().eyedropper_sample = SampleSource::Layer(0);
+    h.click_tile(0, 0);
+    assert_eq!(h.app.world().resource::<EditorState>().selected_tile, 1);
+    h.resource_mut::<ToolState>().eyedropper_sample = SampleSource::Visible;
+    h.click_tile(2, 0);
+    assert_eq!(h.app.world().resource::<EditorState>().selected_tile, 9);
+    h.map_mut().layer_data[1].visible = false;
+    h.click_tile(2, 0);
+    assert_eq!(h.app.world().resource::<EditorState>().selected_tile, 1);
+}
+
+#[test]
+fn box_select_then_drag_moves_and_ctrl_drag_copies() {
+    let mut h = EditorHarness::with_size(8, 6);
+    {
+        let mut map = h.map_mut();
+        for (x, y, i) in [(1, 1, 1), (2, 1, 2), (1, 2, 3), (2, 2, 4)] {
+            map.set(0, x, y, Some(tile(i)));
+        }
+    }
+    let rect = |h: &EditorHarness| {
+        let r = h.app.world().resource::<SelectionState>().rect.expect("selection");
+        (r.min, r.max)
+    };
+
+    // 选择工具在选区外拖动：框选，不产生撤销步骤。
+    h.set_tool(ToolKind::Select);
+    h.drag_tiles(&[(1, 1), (2, 1), (2, 2)]);
+    assert_eq!(rect(&h), (IVec2::new(1, 1), IVec2::new(2, 2)));
+    assert!(h.undo().undo.is_empty());
+
+    // 在选区内按下拖动：内容整体移动，选框跟着走，一次撤销。
+    h.drag_tiles(&[(1, 1), (3, 1), (4, 2)]);
+    assert_eq!(rect(&h), (IVec2::new(4, 2), IVec2::new(5, 3)));
+    assert_eq!((index_at(&h, 0, 4, 2), index_at(&h, 0, 5, 3)), (Some(1), Some(4)));
+    assert_eq!((index_at(&h, 0, 1, 1), index_at(&h, 0, 2, 2)), (None, None));
+    assert_eq!(h.undo().undo.len(), 1);
+    assert_eq!(h.undo().undo[0].changes.len(), 8);
+    h.chord(&[CTRL], KeyCode::KeyZ);
+    assert_eq!(index_at(&h, 0, 1, 1), Some(1));
+    assert_eq!(h.map().iter_tiles().count(), 4);
+
+    // Ctrl + 拖动：复制到目标位置，原处保留。
+    h.drag_tiles(&[(1, 1), (2, 2)]);
+    h.hold_key(CTRL);
+    h.drag_tiles(&[(2, 2), (2, 4)]);
+    h.release_key(CTRL);
+    assert_eq!(rect(&h), (IVec2::new(1, 3), IVec2::new(2, 4)));
+    assert_eq!((index_at(&h, 0, 1, 3), index_at(&h, 0, 2, 4)), (Some(1), Some(4)));
+    assert_eq!((index_at(&h, 0, 1, 1), index_at(&h, 0, 2, 2)), (Some(1), Some(4)));
+    assert_eq!(h.map().iter_tiles().count(), 8);
+}
+
+#[test]
+fn magic_wand_selects_a_mask_that_copy_delete_and_move_follow() {
+    let mut h = EditorHarness::with_size(5, 5);
+    {
+        let mut map = h.map_mut();
+        // L 形的 1 号；外接框里的 (1,1) 是 2 号；(4,4) 是不相连的 1 号。
+        for (x, y) in [(0, 0), (1, 0), (0, 1), (4, 4)] {
+            map.set(0, x, y, Some(tile(1)));
+        }
+        map.set(0, 1, 1, Some(tile(2)));
+    }
+    let selected = |h: &EditorHarness| {
+        let mut cells = h.app.world().resource::<SelectionState>().cells();
+        cells.sort();
+        cells
+    };
+
+    h.set_tool(ToolKind::MagicWand);
+    h.click_tile(0, 0);
+    assert_eq!(selected(&h), vec![(0, 0), (0, 1), (1, 0)]);
+    assert!(!h.app.world().resource::<SelectionState>().contains(IVec2::new(1, 1)));
+
+    // 复制：外接框大小，选区外的格子为空。
+    h.chord(&[CTRL], KeyCode::KeyC);
+    let clipboard = h.app.world().resource::<Clipboard>();
+    assert_eq!((clipboard.width, clipboard.height), (2, 2));
+    assert_eq!(clipboard.tiles, vec![Some(tile(1)), Some(tile(1)), Some(tile(1)), None]);
+
+    // 删除只清空选中的格子。
+    h.chord(&[], KeyCode::Delete);
+    assert_eq!(h.map().iter_tiles().count(), 2);
+    assert_eq!(index_at(&h, 0, 1, 1), Some(2));
+    h.chord(&[CTRL], KeyCode::KeyZ);
+
+    // Alt+↑ 移动：选区跟着走，(1,1) 的 2 号被移进来的 1 号覆盖，(1,2) 不受影响。
+    h.map_mut().set(0, 1, 2, Some(tile(3)));
+    h.chord(&[KeyCode::AltLeft], KeyCode::ArrowUp);
+    assert_eq!(selected(&h), vec![(0, 1), (0, 2), (1, 1)]);
+    assert_eq!((index_at(&h, 0, 0, 0), index_at(&h, 0, 1, 0)), (None, None));
+    assert_eq!((index_at(&h, 0, 0, 2), index_at(&h, 0, 1, 1)), (Some(1), Some(1)));
+    assert_eq!(index_at(&h, 0, 1, 2), Some(3));
+    h.chord(&[CTRL], KeyCode::KeyZ);
+    assert_eq!(index_at(&h, 0, 1, 1), Some(2));
+
+    // Shift 加选铺满外接框时退化为矩形选区；Ctrl 减去点中的连通区域。
+    h.click_tile(0, 0);
+    h.hold_key(KeyCode::ShiftLeft);
+    h.click_tile(1, 1);
+    h.release_key(KeyCode::ShiftLeft);
+    assert_eq!(selected(&h).len(), 4);
+    assert!(h.app.world().resource::<SelectionState>().mask.is_none());
+    h.hold_key(CTRL);
+    h.click_tile(1, 0);
+    h.release_key(CTRL);
+    assert_eq!(selected(&h), vec![(1, 1)]);
+
+    // 再按两次 8 切到整层同类：不相连的同类格子一起选中。
+    for _ in 0..2 {
+        h.resource_mut::<ToolState>().pick(ToolKind::MagicWand);
+    }
+    assert_eq!(h.app.world().resource::<ToolState>().wand_mode, FillMode::Global);
+    h.click_tile(4, 4);
+    assert_eq!(selected(&h), vec![(0, 0), (0, 1), (1, 0), (4, 4)]);
+}
+
+#[test]
+fn dragging_a_magic_wand_selection_moves_only_the_mask() {
+    let mut h = EditorHarness::with_size(6, 5);
+    {
+        let mut map = h.map_mut();
+        // L 形的 1 号，外接框里的 (1,1) 是 2 号。
+        for (x, y) in [(0, 0), (1, 0), (0, 1)] {
+            map.set(0, x, y, Some(tile(1)));
+        }
+        map.set(0, 1, 1, Some(tile(2)));
+    }
+    h.set_tool(ToolKind::MagicWand);
+    h.click_tile(0, 0);
+
+    // 切到选择工具，在选区内按下拖动：按下的那一帧不能被框选重置成 1x1。
+    h.set_tool(ToolKind::Select);
+    h.drag_tiles(&[(0, 0), (2, 1), (3, 2)]);
+    let mut cells = h.app.world().resource::<SelectionState>().cells();
+    cells.sort();
+    assert_eq!(cells, vec![(3, 2), (3, 3), (4, 2)]);
+    assert_eq!((index_at(&h, 0, 3, 2), index_at(&h, 0, 4, 2), index_at(&h, 0, 3, 3)), (Some(1), Some(1), Some(1)));
+    assert_eq!(index_at(&h, 0, 4, 3), None);
+    // 外接框里未选中的 2 号留在原处。
+    assert_eq!(index_at(&h, 0, 1, 1), Some(2));
+    assert_eq!(h.map().iter_tiles().count(), 4);
+
+    // 一个撤销步骤：3 格清空 + 3 格写入。
+    assert_eq!(h.undo().undo.len(), 1);
+    let mut moved: Vec<(i32, i32)> = h.undo().undo[0].changes.iter().map(|c| (c.x, c.y)).collect();
+    moved.sort();
+    assert_eq!(moved, vec![(0, 0), (0, 1), (1, 0), (3, 2), (3, 3), (4, 2)]);
+    h.chord(&[CTRL], KeyCode::KeyZ);
+    assert_eq!((index_at(&h, 0, 0, 0), index_at(&h, 0, 1, 0), index_at(&h, 0, 0, 1)), (Some(1), Some(1), Some(1)));
+    assert_eq!(h.map().iter_tiles().count(), 4);
+}
+
+#[test]
+fn automap_auto_run_joins_the_stroke_undo_step() {
+    let mut h = EditorHarness::with_size(4, 4);
+    // 墙（1 号）上方为空时，在第 2 层的上方一格放墙顶（2 号）。
+    let mut pattern = TileMapData::new(1, 2);
+    pattern.layer_data[0].name = "input_Layer 1".to_string();
+    pattern.layer_data[1].name = "output_Layer 2".to_string();
+    pattern.set(0, 0, 0, Some(tile(1)));
+    pattern.set(0, 0, 1, Some(TileRef { tileset_id: AUTOMAP_TILESET.to_string(), ..tile(AUTOMAP_EMPTY) }));
+    pattern.set(1, 0, 1, Some(tile(2)));
+    {
+        let mut automap = h.resource_mut::<AutomapState>();
+        automap.rules = vec![AutomapRule::new(pattern)];
+        automap.auto_run = true;
+    }
+
+    h.select_tile(1);
+    h.click_tile(1, 1);
+    assert_eq!(index_at(&h, 1, 1, 2), Some(2));
+    assert_eq!(h.undo().undo.len(), 1);
+    assert_eq!(h.undo().undo[0].changes.len(), 2);
+
+    // 在墙上方再画一格墙：新墙顶出现在更上面，旧墙顶留着（规则只加不删）。
+    h.click_tile(1, 2);
+    assert_eq!(index_at(&h, 1, 1, 3), Some(2));
+    assert_eq!(h.undo().undo.len(), 2);
+
+    h.chord(&[CTRL], KeyCode::KeyZ);
+    h.chord(&[CTRL], KeyCode::KeyZ);
+    assert_eq!(h.map().iter_tiles().count(), 0);
+}
+
+#[test]
+fn line_tool_snaps_and_polyline_commits_on_enter() {
+    let mut h = EditorHarness::with_size(8, 8);
+    h.select_tile(4);
+    h.set_tool(ToolKind::Line);
+
+    // Shift 吸附 45°：(1, 1) 拖到 (6, 2) 得到水平线。
+    h.hold_key(KeyCode::ShiftLeft);
+    h.drag_tiles(&[(1, 1), (6, 2)]);
+    h.release_key(KeyCode::ShiftLeft);
+    assert_eq!(h.map().iter_tiles().count(), 6);
+    assert!((1..=6).all(|x| index_at(&h, 0, x, 1) == Some(4)));
+    assert_eq!(h.undo().undo.len(), 1);
+
+    // 再选一次直线进入折线模式：点击只加顶点并预览，Enter 一次提交。
+    h.resource_mut::<ToolState>().pick(ToolKind::Line);
+    h.click_tile(0, 3);
+    h.click_tile(0, 6);
+    h.hover_tile(2, 6);
+    h.update();
+    assert_eq!(h.app.world().resource::<ShapePreview>().cells.len(), 6);
+    assert_eq!(h.map().iter_tiles().count(), 6);
+    h.chord(&[], KeyCode::Enter);
+    assert_eq!(h.map().iter_tiles().count(), 10);
+    assert_eq!(index_at(&h, 0, 0, 6), Some(4));
+    assert_eq!(index_at(&h, 0, 1, 6), None);
+    assert_eq!(h.undo().undo.len(), 2);
+
+    // Esc 丢弃未提交的折线。
+    h.click_tile(5, 5);
+    h.click_tile(7, 7);
+    h.chord(&[], KeyCode::Escape);
+    h.chord(&[], KeyCode::Enter);
+    assert_eq!(h.map().iter_tiles().count(), 10);
+    assert!(h.app.world().resource::<LineToolState>().vertices.is_empty());
+}
+
+#[test]
+fn rect_tool_shapes_constrain_erase_and_respect_lock() {
+    let mut h = EditorHarness::with_size(8, 8);
+    h.select_tile(2);
+    h.set_tool(ToolKind::Rect);
+    let layer_count = |h: &EditorHarness, layer: u32| h.map().iter_tiles().filter(|t| t.0 == layer).count();
+
+    // 再选一次矩形切到边框；拖动中按 Shift 约束为正方形：(1, 1) 拖到 (4, 2) 得 4x4 边框。
+    h.resource_mut::<ToolState>().pick(ToolKind::Rect);
+    h.hover_tile(1, 1);
+    h.mouse_down();
+    h.hold_key(KeyCode::ShiftLeft);
+    h.hover_tile(4, 2);
+    h.update();
+    h.mouse_up();
+    h.release_key(KeyCode::ShiftLeft);
+    assert_eq!(layer_count(&h, 0), 12);
+    assert_eq!(index_at(&h, 0, 4, 4), Some(2));
+    assert_eq!(index_at(&h, 0, 2, 2), None);
+    assert_eq!(h.undo().undo.len(), 1);
+
+    // 实心椭圆 + Ctrl 以按下点为中心：(4, 4) 拖到 (6, 6) 得 5x5 的圆；按住 Shift 再拖以同样形状擦除。
+    h.resource_mut::<ToolState>().shape = ShapeMode::Ellipse;
+    h.resource_mut::<LayerState>().active = 1;
+    h.hold_key(KeyCode::ControlLeft);
+    h.drag_tiles(&[(4, 4), (6, 6)]);
+    assert_eq!(layer_count(&h, 1), 21);
+    assert_eq!(index_at(&h, 1, 2, 2), None);
+    assert_eq!(index_at(&h, 1, 4, 2), Some(2));
+    h.hold_key(KeyCode::ShiftLeft);
+    h.drag_tiles(&[(4, 4), (6, 6)]);
+    h.release_key(KeyCode::ShiftLeft);
+    h.release_key(KeyCode::ControlLeft);
+    assert_eq!(layer_count(&h, 1), 0);
+    assert_eq!(h.undo().undo.len(), 3);
+
+    // 锁定图层不画。
+    h.map_mut().layer_data[1].locked = true;
+    h.drag_tiles(&[(0, 0), (3, 3)]);
+    assert_eq!(layer_count(&h, 1), 0);
+    assert_eq!(h.undo().undo.len(), 3);
+}
+
+#[test]
+fn shift_fill_erases_region() {
+    let mut h = EditorHarness::with_size(3, 3);
+    h.set_tool(ToolKind::Fill);
+    h.click_tile(0, 0);
+    assert_eq!(h.map().iter_tiles().count(), 9);
+
+    h.hold_key(KeyCode::ShiftLeft);
+    h.click_tile(1, 1);
+    h.release_key(KeyCode::ShiftLeft);
+    assert_eq!(h.map().iter_tiles().count(), 0);
+    assert_eq!(h.undo().undo.len(), 2);
+}
+
+#[test]
+fn paste_stamps_clipboard_and_returns_to_previous_tool() {
+    let mut h = EditorHarness::with_size(8, 8);
+    *h.resource_mut::<Clipboard>() = Clipboard {
+        width: 2,
+        height: 1,
+        tiles: vec![Some(tile(1)), Some(tile(2))],
+    };
+    {
+        let mut tools = h.resource_mut::<ToolState>();
+        tools.tool = ToolKind::Paste;
+        tools.return_after_paste = Some(ToolKind::Pencil);
+    }
+    h.click_tile(3, 3);
+
+    assert_eq!(index_at(&h, 0, 3, 3), Some(1));
+    assert_eq!(index_at(&h, 0, 4, 3), Some(2));
+    assert_eq!(h.undo().undo.len(), 1);
+    assert_eq!(h.resource_mut::<ToolState>().tool, ToolKind::Pencil);
+}
+
+#[test]
+fn rotated_paste_rotates_positions_and_tiles() {
+    let mut h = EditorHarness::with_size(8, 8);
+    *h.resource_mut::<Clipboard>() = Clipboard {
+        width: 2,
+        height: 1,
+        tiles: vec![Some(tile(1)), Some(tile(2))],
+    };
+    h.resource_mut::<PasteState>().orientation = Orientation::ROT_CW;
+    h.set_tool(ToolKind::Paste);
+    h.click_tile(3, 3);
+
+    // 横条顺时针转成竖条：原左端在上（y 向上）。
+    assert_eq!(index_at(&h, 0, 3, 4), Some(1));
+    assert_eq!(index_at(&h, 0, 3, 3), Some(2));
+    let painted = h.map().get(0, 3, 4).unwrap();
+    assert_eq!(painted.orientation(), Orientation::ROT_CW);
+    // 连续粘贴模式不自动切回。
+    assert_eq!(h.resource_mut::<ToolState>().tool, ToolKind::Paste);
+}
+
+#[test]
+fn pencil_stamp_tiles_from_stroke_origin_with_paste_orientation() {
+    let mut h = EditorHarness::with_size(8, 8);
+    // palette 4 列，tile 1 到 6 围成 2x2：图集上一行 (1, 2) 落在印章上方。
+    h.resource_mut::<EditorState>().stamp = palette_stamp(&TEST_TILESET.to_string(), 6, 1, 4);
+    h.drag_tiles(&[(1, 1), (2, 1), (3, 1), (4, 2)]);
+
+    for (x, y, index) in [(1, 1, 5), (2, 1, 6), (1, 2, 1), (2, 2, 2), (3, 1, 5), (4, 1, 6), (3, 2, 1), (4, 2, 2)] {
+        assert_eq!(index_at(&h, 0, x, y), Some(index), "({x}, {y})");
+    }
+    assert_eq!(h.map().iter_tiles().count(), 8);
+    assert_eq!(h.undo().undo.len(), 1);
+
+    // 印章随粘贴变换翻转，整块位置与图块朝向一起变。
+    h.resource_mut::<PasteState>().orientation = Orientation::FLIP_X;
+    h.click_tile(1, 5);
+    assert_eq!(index_at(&h, 0, 1, 5), Some(6));
+    assert_eq!(index_at(&h, 0, 2, 6), Some(1));
+    assert_eq!(h.map().get(0, 1, 5).unwrap().orientation(), Orientation::FLIP_X);
+
+    h.chord(&[CTRL], KeyCode::KeyZ);
+    h.chord(&[CTRL], KeyCode::KeyZ);
+    assert_eq!(h.map().iter_tiles().count(), 0);
+}
+
+#[test]
+fn rect_and_fill_tile_the_stamp_from_the_area_top_left() {
+    let mut h = EditorHarness::with_size(4, 4);
+    // 2x2 印章：下行 [5 6]，上行 [1 2]。
+    h.resource_mut::<EditorState>().stamp = palette_stamp(&TEST_TILESET.to_string(), 1, 6, 4);
+    h.set_tool(ToolKind::Rect);
+    h.drag_tiles(&[(1, 1), (3, 3)]);
+    for (x, y, index) in [(1, 3, 1), (2, 3, 2), (3, 3, 1), (1, 2, 5), (2, 2, 6), (1, 1, 1), (3, 1, 1)] {
+        assert_eq!(index_at(&h, 0, x, y), Some(index), "rect ({x}, {y})");
+    }
+    assert_eq!(h.map().iter_tiles().count(), 9);
+
+    // 油漆桶：剩下的空白 L 形区域对齐它自己的外接框 (0..=3, 0..=3)。
+    h.set_tool(ToolKind::Fill);
+    h.click_tile(0, 0);
+    assert_eq!(index_at(&h, 0, 0, 3), Some(1));
+    assert_eq!(index_at(&h, 0, 0, 2), Some(5));
+    assert_eq!(index_at(&h, 0, 1, 0), Some(6));
+    assert_eq!(index_at(&h, 0, 1, 1), Some(1));
+    assert_eq!(h.map().iter_tiles().count(), 16);
+    assert_eq!(h.undo().undo.len(), 2);
+}
+
+#[test]
+fn ctrl_arrow_shifts_map_blank_and_wrap() {
+    let mut h = EditorHarness::with_size(4, 4);
+    h.map_mut().set(0, 3, 0, Some(tile(5)));
+    let mut region = h.map().meta.get(3, 0);
+    region.region = 2;
+    h.map_mut().meta.set(3, 0, region);
+
+    // 元数据跟着 tile 一起移出边界，同一个撤销步骤恢复。
+    h.chord(&[CTRL], KeyCode::ArrowRight);
+    assert_eq!(h.map().iter_tiles().count(), 0);
+    assert!(h.map().meta.is_empty());
+    assert_eq!(h.undo().undo.len(), 1);
+
+    h.chord(&[CTRL], KeyCode::KeyZ);
+    assert_eq!(index_at(&h, 0, 3, 0), Some(5));
+    assert_eq!(h.map().meta.get(3, 0).region, 2);
+
+    h.resource_mut::<ShiftMapSettings>().mode = ShiftMapMode::Wrap;
+    h.chord(&[CTRL], KeyCode::ArrowRight);
+    assert_eq!(index_at(&h, 0, 0, 0), Some(5));
+    h.chord(&[CTRL], KeyCode::ArrowUp);
+    assert_eq!(index_at(&h, 0, 0, 1), Some(5));
+    assert_eq!(h.map().iter_tiles().count(), 1);
+    assert_eq!(h.map().meta.get(0, 1).region, 2);
+}
+
+#[test]
+fn arrows_without_ctrl_do_not_shift() {
+    let mut h = EditorHarness::with_size(4, 4);
+    h.map_mut().set(0, 1, 1, Some(tile(5)));
+    h.chord(&[], KeyCode::ArrowRight);
+
+    assert_eq!(index_at(&h, 0, 1, 1), Some(5));
+    assert!(h.undo().undo.is_empty());
+}
+
+#[test]
+fn animated_tiles_follow_clock_and_pause() {
+    let mut h = EditorHarness::with_size(4, 4);
+    let frames = vec![
+        AnimationFrame { index: 1, duration_ms: 100 },
+        AnimationFrame { index: 6, duration_ms: 50 },
+    ];
+    h.resource_mut::<TilesetRuntime>().by_id.insert(
+        TEST_TILESET.to_string(),
+        TilesetRuntimeEntry {
+            texture: Handle::default(),
+            tilemap_texture: Handle::default(),
+            grid: TileGrid::new(32, 32),
+            columns: 4,
+            rows: 2,
+            animations: [(1, frames)].into_iter().collect(),
+        },
+    );
+    let tile = h
+        .app
+        .world_mut()
+        .spawn((
+            AnimatedTile {
+                tileset_id: TEST_TILESET.to_string(),
+                base_index: 1,
+            },
+            TileTextureIndex(1),
+        ))
+        .id();
+    let shown = |h: &EditorHarness| h.app.world().get::<TileTextureIndex>(tile).map(|t| t.0);
+
+    // 暂停时时钟不走，显示帧完全由 elapsed_ms 决定。
+    h.resource_mut::<EditorConfig>().play_animations = false;
+    h.resource_mut::<TileAnimationClock>().elapsed_ms = 120;
+    h.update();
+    assert_eq!(shown(&h), Some(6));
+    h.update();
+    assert_eq!(h.app.world().resource::<TileAnimationClock>().elapsed_ms, 120);
+
+    h.resource_mut::<TileAnimationClock>().elapsed_ms = 150;
+    h.update();
+    assert_eq!(shown(&h), Some(1));
+}
+
+#[test]
+fn collision_vertex_drag_snaps_and_turns_full_into_polygon() {
+    let mut h = EditorHarness::with_size(4, 4);
+    let mut entry = TilesetEntry { id: TEST_TILESET.to_string(), ..Default::default() };
+    entry.set_collision(3, vec![CollisionShape::Full]);
+    let image = h
+        .app
+        .world_mut()
+        .spawn((RelativeCursorPosition::default(), CollisionTileImage))
+        .id();
+    let point_at = |h: &mut EditorHarness, normalized: Vec2| {
+        h.app.world_mut().get_mut::<RelativeCursorPosition>(image).unwrap().normalized = Some(normalized);
+    };
+    {
+        let mut dialog = h.resource_mut::<TileCollisionEditorState>();
+        dialog.open_for(&entry, 3);
+        // Full 的顶点顺序：左下、右下、右上、左上；抓右上角。
+        dialog.grab_vertex(0, 2);
+    }
+
+    // 光标在 tile 内 (0.52, 0.3)（y 向上），吸附到 1/16 网格。
+    point_at(&mut h, Vec2::new(0.02, 0.2));
+    h.mouse_down();
+    let shapes = h.app.world().resource::<TileCollisionEditorState>().shapes.clone();
+    assert_eq!(
+        shapes,
+        vec![CollisionShape::Polygon(vec![[0.0, 0.0], [1.0, 0.0], [0.5, 0.3125], [0.0, 1.0]])]
+    );
+
+    // 松开后不再跟随光标。
+    h.mouse_up();
+    point_at(&mut h, Vec2::new(0.5, -0.5));
+    h.update();
+    let dialog = h.app.world().resource::<TileCollisionEditorState>();
+    assert!(!dialog.dragging);
+    assert_eq!(dialog.shapes, shapes);
+    assert_eq!(dialog.shapes_to_save(), shapes);
+}
@@ -19,6 +19,9 @@ mod world;
 
 mod app;
 
+#[cfg(test)]
+mod harness;
+
 use bevy::prelude::Color;
 
 /// UI 相关常量
@@ -1,8 +1,13 @@
-//! 地图的保存/读取（RON）。
+//! 地图的保存/读取（RON）、导出图片与自动映射规则文件读取。
 
+use std::collections::HashMap;
 use std::path::PathBuf;
 
-use crate::editor::types::{TileMapData, TilesetEntry, TilesetLibrary};
+use tilemap_core::{ops, AUTOMAP_TILESET};
+use tilemap_render::{render_map, Atlas, RenderOptions};
+
+use crate::editor::paths::workspace_assets_dir;
+use crate::editor::types::{AutomapRule, TileMapData, TilesetEntry, TilesetLibrary};
 
 pub const DEFAULT_TILESET_LIBRARY_PATH: &str = "tilesets/library.ron";
 
@@ -35,13 +40,9 @@ pub fn save_map_to_file(map: &TileMapData, lib: &TilesetLibrary, path: &str) ->
 
     // 收集地图中实际使用到的 tileset（保证跨机器加载 map.ron 时可以自动把 tileset 加回库并回显）
     let mut used: Vec<String> = Vec::new();
-    for t in &map.tiles {
-        if let Some(r) = t.as_ref() {
-            if !r.tileset_id.is_empty() {
-                if !used.iter().any(|id| id == &r.tileset_id) {
-                    used.push(r.tileset_id.clone());
-                }
-            }
+    for (_, _, _, r) in map.iter_tiles() {
+        if !r.tileset_id.is_empty() && !used.contains(r.tileset_id) {
+            used.push(r.tileset_id.clone());
         }
     }
     used.sort();
@@ -56,6 +57,7 @@ pub fn save_map_to_file(map: &TileMapData, lib: &TilesetLibrary, path: &str) ->
                 name: id.clone(),
                 category: "default".to_string(),
                 asset_path: String::new(),
+                ..Default::default()
             });
         }
     }
@@ -67,8 +69,63 @@ pub fn save_map_to_file(map: &TileMapData, lib: &TilesetLibrary, path: &str) ->
     Ok(())
 }
 
+/// 把地图合成为 PNG；图集直接从 assets 目录读取。返回渲染过程中的警告（缺图集等）。
+pub fn export_map_image(
+    map: &TileMapData,
+    lib: &TilesetLibrary,
+    opts: &RenderOptions,
+    path: &str,
+) -> Result<Vec<String>, String> {
+    let mut warnings = Vec::new();
+    let mut atlases = HashMap::new();
+    for (_, _, _, r) in map.iter_tiles() {
+        if atlases.contains_key(r.tileset_id) {
+            continue;
+        }
+        let Some(entry) = lib.entries.iter().find(|e| e.id == *r.tileset_id) else {
+            continue;
+        };
+        let file = workspace_assets_dir().join(&entry.asset_path);
+        match image::open(&file) {
+            Ok(img) => {
+                let atlas = Atlas {
+                    image: img.to_rgba8(),
+                    grid: entry.grid(opts.tile_size),
+                };
+                atlases.insert(entry.id.clone(), atlas);
+            }
+            Err(e) => warnings.push(format!("tileset {} 的图片 {} 无法读取: {e}", entry.id, file.display())),
+        }
+    }
+
+    let rendered = render_map(map, &atlases, opts)?;
+    warnings.extend(rendered.warnings);
+    let path = PathBuf::from(path);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    rendered
+        .image
+        .save_with_format(&path, image::ImageFormat::Png)
+        .map_err(|e| e.to_string())?;
+    Ok(warnings)
+}
+
 pub fn load_map_from_file(path: &str) -> Result<(TileMapData, Vec<TilesetEntry>), String> {
     let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
 
     tilemap_format::decode_map_ron::<TilesetEntry>(&text)
 }
+
+/// 读取规则文件（RON 地图）并拆成规则；名为 `automap` 的 tileset 当作特殊 tile（空 / 忽略 / 非空 / 其它）。
+pub fn load_automap_rules_from_file(path: &str) -> Result<Vec<AutomapRule>, String> {
+    let (mut rules, tilesets) = load_map_from_file(path)?;
+    for entry in tilesets.iter().filter(|t| t.name.eq_ignore_ascii_case(AUTOMAP_TILESET)) {
+        ops::remap_tileset(&mut rules, &entry.id, AUTOMAP_TILESET);
+    }
+    let rules = AutomapRule::split(&rules);
+    if rules.is_empty() {
+        return Err(format!("{path}: no input_* / inputnot_* / output_* layers with tiles"));
+    }
+    Ok(rules)
+}
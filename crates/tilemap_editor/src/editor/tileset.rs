@@ -3,20 +3,26 @@
 //! 职责：
 //! - 通过文件对话框选择 tileset 图片，并复制到 workspace `assets/tilesets/`
 //! - 触发 Bevy AssetServer 加载图片
-//! - 图片加载完成后计算 columns/rows，并写入 `TilesetRuntime`
+//! - 图片加载完成后按条目的切分参数（tile 尺寸 / margin / spacing）计算 columns/rows，并写入 `TilesetRuntime`
 //!
 //! 该模块采用“门面（facade）+ 子模块”结构，具体实现位于 [crates/tilemap_editor/src/editor/tileset/](crates/tilemap_editor/src/editor/tileset/) 下。
 
+mod autotile;
 mod library;
 mod loading;
 mod map_setup;
 mod open;
 mod rect;
 mod spawn;
+mod variation;
+mod wang;
 
+pub use autotile::{define_autotile, import_rm_a2_autotile};
 pub use library::{load_tileset_library_startup, merge_tilesets_from_map, save_tileset_library};
 pub use loading::progress_spritesheet_loading;
 pub use map_setup::setup_map;
-pub use open::{open_spritesheet_shortcut, open_tileset_impl};
-pub use rect::rect_for_tile_index;
-pub use spawn::spawn_map_entities_with_layers;
+pub use open::{import_tileset_file, open_spritesheet_shortcut, open_tileset_and_ask_grid};
+pub use rect::{rect_for_tile_index, tile_anchor_offset};
+pub use spawn::spawn_map_entities;
+pub use variation::{cycle_variation_weight, define_variation_set, delete_variation_set};
+pub use wang::import_tiled_wangsets;
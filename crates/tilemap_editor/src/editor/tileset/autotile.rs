@@ -0,0 +1,93 @@
+//! 自动图块组的登记：当前 tileset 从选中 tile 起的 47 块，或把 RPG Maker A2 块烘焙成新的 blob tileset。
+
+use bevy::prelude::*;
+use tilemap_core::{TileGrid, AUTOTILE_VARIANTS};
+use tilemap_render::{bake_rm_a2_autotile, Atlas};
+
+use super::super::paths::workspace_assets_dir;
+use super::super::types::{
+	AutotileDef, EditorConfig, PendingTileset, TilesetEntry, TilesetId, TilesetLibrary, TilesetLoading, TilesetRuntime,
+};
+use super::open::import_tileset_file;
+
+/// 把当前 tileset 中 `first..first + 47` 登记为一组 blob 自动图块（已有同起点的组时覆盖）。
+pub fn define_autotile(lib: &mut TilesetLibrary, runtime: &TilesetRuntime, first: u32) -> Result<AutotileDef, String> {
+	let id = lib.active_id.clone().ok_or("no active tileset")?;
+	if let Some(rt) = runtime.by_id.get(&id) {
+		let capacity = rt.columns * rt.rows;
+		if first + AUTOTILE_VARIANTS > capacity {
+			return Err(format!("tiles {first}..{} exceed tileset capacity {capacity}", first + AUTOTILE_VARIANTS));
+		}
+	}
+	let entry = lib.entries.iter_mut().find(|e| e.id == id).ok_or("active tileset is not in the library")?;
+	let def = AutotileDef {
+		name: format!("{} #{first}", entry.name),
+		first,
+	};
+	entry.set_autotile(def.clone());
+	Ok(def)
+}
+
+/// 把当前 tileset 中以 `block` 为左上角的 RPG Maker A2 块（2x3 tile）烘焙成 47 块图集，
+/// 写入 `assets/<tileset_import_dir>/` 并作为新 tileset 导入（带一组 `first = 0` 的自动图块）。
+pub fn import_rm_a2_autotile(
+	asset_server: &AssetServer,
+	config: &EditorConfig,
+	lib: &mut TilesetLibrary,
+	loading: &mut TilesetLoading,
+	block: u32,
+) -> Result<TilesetId, String> {
+	let source = lib
+		.active_id
+		.as_ref()
+		.and_then(|id| lib.entries.iter().find(|e| e.id == *id))
+		.ok_or("no active tileset")?
+		.clone();
+	let grid_tile = (config.tile_size.x, config.tile_size.y);
+	let source_path = workspace_assets_dir().join(&source.asset_path);
+	let image = image::open(&source_path)
+		.map_err(|e| format!("failed to read {}: {e}", source_path.display()))?
+		.to_rgba8();
+	let grid = source.grid(grid_tile);
+	let sheet = bake_rm_a2_autotile(&Atlas { image, grid }, block)?;
+
+	let stem = std::path::Path::new(&source.asset_path)
+		.file_stem()
+		.map(|s| s.to_string_lossy().to_string())
+		.unwrap_or_else(|| "autotile".to_string());
+	let import_dir = workspace_assets_dir().join(&config.tileset_import_dir);
+	std::fs::create_dir_all(&import_dir).map_err(|e| format!("failed to create import dir: {e}"))?;
+	let dest = import_dir.join(format!("{stem}_a2_{block}.png"));
+	sheet
+		.save_with_format(&dest, image::ImageFormat::Png)
+		.map_err(|e| format!("failed to write {}: {e}", dest.display()))?;
+
+	let imported = import_tileset_file(config, &dest)?;
+	let def = AutotileDef {
+		name: format!("{} A2 #{block}", source.name),
+		first: 0,
+	};
+	match lib.entries.iter_mut().find(|e| e.id == imported.id) {
+		Some(entry) => {
+			entry.asset_path = imported.rel.clone();
+			entry.set_autotile(def);
+		}
+		None => {
+			let mut entry = TilesetEntry {
+				id: imported.id.clone(),
+				name: imported.name.clone(),
+				category: source.category.clone(),
+				asset_path: imported.rel.clone(),
+				..Default::default()
+			};
+			entry.set_grid(TileGrid::new(grid.tile_width, grid.tile_height), grid_tile);
+			entry.set_autotile(def);
+			lib.entries.push(entry);
+		}
+	}
+	lib.active_id = Some(imported.id.clone());
+
+	let texture: Handle<Image> = asset_server.load(imported.rel);
+	loading.pending.push(PendingTileset { id: imported.id.clone(), texture });
+	Ok(imported.id)
+}
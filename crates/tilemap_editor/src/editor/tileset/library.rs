@@ -73,6 +73,18 @@ pub fn merge_tilesets_from_map(
 				if existing.category.trim().is_empty() && !incoming.category.trim().is_empty() {
 					existing.category = incoming.category.clone();
 				}
+				if existing.tiles.is_empty() && !incoming.tiles.is_empty() {
+					existing.tiles = incoming.tiles.clone();
+				}
+				if existing.autotiles.is_empty() && !incoming.autotiles.is_empty() {
+					existing.autotiles = incoming.autotiles.clone();
+				}
+				if existing.wangsets.is_empty() && !incoming.wangsets.is_empty() {
+					existing.wangsets = incoming.wangsets.clone();
+				}
+				if existing.variations.is_empty() && !incoming.variations.is_empty() {
+					existing.variations = incoming.variations.clone();
+				}
 			}
 			None => {
 				lib.entries.push(incoming.clone());
@@ -1,55 +1,137 @@
 //! tileset 图片加载进度轮询与 runtime 构建。
 
+use bevy::asset::{LoadState, RenderAssetUsages};
 use bevy::prelude::*;
+use tilemap_core::TileGrid;
 
-use super::super::types::{EditorConfig, PendingTileset, TilesetLoading, TilesetRuntime, TilesetRuntimeEntry};
+use super::super::types::{
+	EditorConfig, FailedTileset, PendingTileset, TilesetId, TilesetLibrary, TilesetLoading, TilesetRuntime,
+	TilesetRuntimeEntry,
+};
 
-/// 轮询 tileset 图片是否加载完毕；加载完则写入 `TilesetRuntime`。
+/// 轮询 tileset 图片是否加载完毕；加载完则按条目的切分参数写入 `TilesetRuntime`，失败则记入 `TilesetLoading::failed`。
 pub fn progress_spritesheet_loading(
+	asset_server: Res<AssetServer>,
 	mut loading: ResMut<TilesetLoading>,
 	mut runtime: ResMut<TilesetRuntime>,
 	config: Res<EditorConfig>,
-	images: Res<Assets<Image>>,
+	lib: Res<TilesetLibrary>,
+	mut images: ResMut<Assets<Image>>,
 ) {
 	if loading.pending.is_empty() {
 		return;
 	}
 
 	let mut still_pending: Vec<PendingTileset> = Vec::new();
+	let mut failed: Vec<FailedTileset> = Vec::new();
+	let mut loaded: Vec<TilesetId> = Vec::new();
 	for p in loading.pending.drain(..) {
 		if runtime.by_id.contains_key(&p.id) {
 			continue;
 		}
+		let asset_path = || {
+			asset_server
+				.get_path(&p.texture)
+				.map(|path| path.to_string())
+				.unwrap_or_default()
+		};
 		let Some(image) = images.get(&p.texture) else {
-			still_pending.push(p);
+			// 文件不存在/格式不支持时 AssetServer 报 Failed，不会再变成可用状态。
+			if let LoadState::Failed(err) = asset_server.load_state(&p.texture) {
+				warn!("failed to load tileset {}: {err}", p.id);
+				failed.push(FailedTileset {
+					asset_path: asset_path(),
+					id: p.id,
+					error: err.to_string(),
+				});
+			} else {
+				still_pending.push(p);
+			}
 			continue;
 		};
 
 		let size = image.size();
-		let tile_w = config.tile_size.x.max(1);
-		let tile_h = config.tile_size.y.max(1);
-		let columns = (size.x as u32) / tile_w;
-		let rows = (size.y as u32) / tile_h;
+		let entry = lib.entries.iter().find(|e| e.id == p.id);
+		let grid = entry
+			.map(|e| e.grid((config.tile_size.x, config.tile_size.y)))
+			.unwrap_or_else(|| TileGrid::new(config.tile_size.x, config.tile_size.y));
+		let columns = grid.columns(size.x);
+		let rows = grid.rows(size.y);
 		if columns == 0 || rows == 0 {
 			warn!(
-				"tileset size too small or tile_size invalid: image={}x{}, tile={}x{}",
+				"tileset size too small or tile_size invalid: image={}x{}, tile={}x{}, margin={}",
 				size.x,
 				size.y,
-				tile_w,
-				tile_h
+				grid.tile_width,
+				grid.tile_height,
+				grid.margin
 			);
+			failed.push(FailedTileset {
+				asset_path: asset_path(),
+				id: p.id,
+				error: format!(
+					"图片 {}x{} 切不出 {}x{} 的 tile（外边距 {}）",
+					size.x, size.y, grid.tile_width, grid.tile_height, grid.margin
+				),
+			});
 			continue;
 		}
 
+		// bevy_ecs_tilemap 只认紧密排列的图集：有留白时生成一份去掉 margin / spacing 的副本。
+		let packed = (grid.margin > 0 || grid.spacing > 0).then(|| packed_atlas(image, grid, columns, rows));
+		let tilemap_texture = match packed {
+			None => p.texture.clone(),
+			Some(Some(packed)) => images.add(packed),
+			Some(None) => {
+				warn!("failed to repack tileset {} for margin/spacing; tilemap will ignore them", p.id);
+				p.texture.clone()
+			}
+		};
+
+		loaded.push(p.id.clone());
 		runtime.by_id.insert(
 			p.id,
 			TilesetRuntimeEntry {
 				texture: p.texture,
+				tilemap_texture,
+				grid,
 				columns,
 				rows,
+				animations: entry
+					.map(|e| {
+						e.tiles
+							.iter()
+							.filter(|t| !t.animation.is_empty())
+							.map(|t| (t.index, t.animation.clone()))
+							.collect()
+					})
+					.unwrap_or_default(),
 			},
 		);
 	}
 
 	loading.pending = still_pending;
+	if failed.is_empty() && loaded.is_empty() {
+		return;
+	}
+	loading.failed.retain(|f| !loaded.contains(&f.id) && !failed.iter().any(|n| n.id == f.id));
+	loading.failed.extend(failed);
+}
+
+/// 按 `grid` 切出 columns x rows 个 tile，紧密排列成新图集。
+fn packed_atlas(image: &Image, grid: TileGrid, columns: u32, rows: u32) -> Option<Image> {
+	let src = image.clone().try_into_dynamic().ok()?.to_rgba8();
+	let (tw, th) = (grid.tile_width, grid.tile_height);
+	let mut out = image::RgbaImage::new(columns * tw, rows * th);
+	for index in 0..columns * rows {
+		let (sx, sy) = grid.tile_origin(index, columns);
+		let tile = image::imageops::crop_imm(&src, sx, sy, tw, th).to_image();
+		let (dx, dy) = ((index % columns) * tw, (index / columns) * th);
+		image::imageops::replace(&mut out, &tile, dx as i64, dy as i64);
+	}
+	Some(Image::from_dynamic(
+		image::DynamicImage::ImageRgba8(out),
+		true,
+		RenderAssetUsages::default(),
+	))
 }
@@ -1,11 +1,14 @@
 //! tileset 导入（文件对话框选择 → 复制到 assets → 触发加载）。
 
+use std::path::Path;
+
 use bevy::prelude::*;
 use blake3;
 
 use super::super::paths::{path_join_asset, workspace_assets_dir};
 use super::super::types::{
-	EditorConfig, PendingTileset, TilesetEntry, TilesetLibrary, TilesetLoading, DEFAULT_SPRITESHEET,
+	EditorConfig, ImportedTileset, PendingTileset, TilesetEntry, TilesetGridDialogState, TilesetId,
+	TilesetLibrary, TilesetLoading, DEFAULT_SPRITESHEET,
 };
 
 /// 按 `O` 快捷键打开 tileset。
@@ -16,63 +19,83 @@ pub fn open_spritesheet_shortcut(
 	mut config: ResMut<EditorConfig>,
 	mut lib: ResMut<TilesetLibrary>,
 	mut loading: ResMut<TilesetLoading>,
+	mut grid_dialog: ResMut<TilesetGridDialogState>,
 ) {
-	if !keys.just_pressed(KeyCode::KeyO) {
+	if !keys.just_pressed(KeyCode::KeyO) || grid_dialog.captures_keys() {
 		return;
 	}
 
-	open_tileset_impl(&asset_server, &mut config, &mut lib, &mut loading);
+	open_tileset_and_ask_grid(&asset_server, &mut config, &mut lib, &mut loading, &mut grid_dialog);
 }
 
-/// 选择 tileset → 复制到 `assets/tilesets/` → 加载。
-///
-/// 这里做“复制到 assets”的原因：Bevy 的 AssetServer 默认只读 `AssetPlugin.file_path` 指向的目录。
-pub fn open_tileset_impl(
+/// 导入 tileset 后弹出切分参数对话框（菜单与快捷键共用）。
+pub fn open_tileset_and_ask_grid(
 	asset_server: &AssetServer,
 	config: &mut EditorConfig,
 	lib: &mut TilesetLibrary,
 	loading: &mut TilesetLoading,
+	grid_dialog: &mut TilesetGridDialogState,
 ) {
-	let Some(picked) = rfd::FileDialog::new()
-		.add_filter("Image", &["png", "jpg", "jpeg", "bmp"])
-		.pick_file()
-	else {
+	let Some(id) = open_tileset_impl(asset_server, config, lib, loading) else {
 		return;
 	};
+	if let Some(entry) = lib.entries.iter().find(|e| e.id == id) {
+		grid_dialog.open_for(entry, config.tile_size);
+	}
+}
 
+/// 把外部图片复制到 `assets/<tileset_import_dir>/` 并计算内容 hash（导入与重新链接共用）。
+pub fn import_tileset_file(config: &EditorConfig, picked: &Path) -> Result<ImportedTileset, String> {
 	let asset_root = workspace_assets_dir();
 	let import_dir = asset_root.join(&config.tileset_import_dir);
-	if let Err(err) = std::fs::create_dir_all(&import_dir) {
-		warn!("failed to create import dir: {err}");
-		return;
-	}
+	std::fs::create_dir_all(&import_dir).map_err(|e| format!("failed to create import dir: {e}"))?;
 
 	let file_name = picked
 		.file_name()
 		.map(|s| s.to_string_lossy().to_string())
 		.unwrap_or_else(|| DEFAULT_SPRITESHEET.to_string());
 	let dest_abs = import_dir.join(&file_name);
-	if let Err(err) = std::fs::copy(&picked, &dest_abs) {
-		warn!("failed to copy tileset: {err}");
-		return;
+	// 选中的就是 assets 里的文件时不要自我复制（某些平台会把文件截断为 0 字节）。
+	let same_file = matches!(
+		(picked.canonicalize(), dest_abs.canonicalize()),
+		(Ok(a), Ok(b)) if a == b
+	);
+	if !same_file {
+		std::fs::copy(picked, &dest_abs).map_err(|e| format!("failed to copy tileset: {e}"))?;
 	}
 
-	let bytes = match std::fs::read(&dest_abs) {
-		Ok(b) => b,
-		Err(err) => {
-			warn!("failed to read tileset for hashing: {err}");
-			return;
-		}
-	};
+	let bytes = std::fs::read(&dest_abs).map_err(|e| format!("failed to read tileset for hashing: {e}"))?;
 	let id = blake3::hash(&bytes).to_hex().to_string();
 
 	let rel = path_join_asset(&config.tileset_import_dir, &file_name);
-	info!("imported tileset: id={id} path={rel}");
-
 	let name = dest_abs
 		.file_stem()
 		.map(|s| s.to_string_lossy().to_string())
 		.unwrap_or_else(|| file_name.clone());
+	Ok(ImportedTileset { id, rel, name })
+}
+
+/// 选择 tileset → 复制到 `assets/tilesets/` → 加载；返回导入的 tileset id（用于接着询问切分参数）。
+///
+/// 这里做“复制到 assets”的原因：Bevy 的 AssetServer 默认只读 `AssetPlugin.file_path` 指向的目录。
+pub fn open_tileset_impl(
+	asset_server: &AssetServer,
+	config: &mut EditorConfig,
+	lib: &mut TilesetLibrary,
+	loading: &mut TilesetLoading,
+) -> Option<TilesetId> {
+	let picked = rfd::FileDialog::new()
+		.add_filter("Image", &["png", "jpg", "jpeg", "bmp"])
+		.pick_file()?;
+
+	let ImportedTileset { id, rel, name } = match import_tileset_file(config, &picked) {
+		Ok(imported) => imported,
+		Err(err) => {
+			warn!("{err}");
+			return None;
+		}
+	};
+	info!("imported tileset: id={id} path={rel}");
 
 	if let Some(existing) = lib.entries.iter_mut().find(|e| e.id == id) {
 		existing.asset_path = rel.clone();
@@ -85,10 +108,12 @@ pub fn open_tileset_impl(
 			name,
 			category: "default".to_string(),
 			asset_path: rel.clone(),
+			..Default::default()
 		});
 	}
 	lib.active_id = Some(id.clone());
 
 	let texture: Handle<Image> = asset_server.load(rel);
-	loading.pending.push(PendingTileset { id, texture });
+	loading.pending.push(PendingTileset { id: id.clone(), texture });
+	Some(id)
 }
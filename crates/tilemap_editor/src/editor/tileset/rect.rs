@@ -1,17 +1,21 @@
 //! tileset 纹理裁剪相关 helper。
 
 use bevy::prelude::*;
+use tilemap_core::TileGrid;
 
-/// 计算某个 tile index 在 tileset 纹理中的裁剪 Rect。
+/// 计算某个 tile index 在 tileset 纹理中的裁剪 Rect（含 margin / spacing）。
 ///
-/// 注意：Bevy 的 `Rect` 原点在纹理左下角。
-pub fn rect_for_tile_index(index: u32, columns: u32, tile_size: UVec2) -> Rect {
-	let tile_w = tile_size.x as f32;
-	let tile_h = tile_size.y as f32;
-	let col = index % columns;
-	let row = index / columns;
-
-	let min = Vec2::new(col as f32 * tile_w, row as f32 * tile_h);
-	let max = Vec2::new(min.x + tile_w, min.y + tile_h);
+/// 注意：`Sprite::rect` / `ImageNode::rect` 以纹理左上角为原点、y 向下。
+pub fn rect_for_tile_index(index: u32, columns: u32, grid: TileGrid) -> Rect {
+	let (x, y) = grid.tile_origin(index, columns);
+	let min = Vec2::new(x as f32, y as f32);
+	let max = min + Vec2::new(grid.tile_width as f32, grid.tile_height as f32);
 	Rect { min, max }
 }
+
+/// tile 与网格尺寸不同时，tile 中心相对格子中心的偏移：与 Tiled 一样左下角对齐格子左下角。
+///
+/// `tile` 为（旋转后的）tile 像素尺寸，`cell` 为地图网格尺寸。
+pub fn tile_anchor_offset(tile: UVec2, cell: UVec2) -> Vec2 {
+	(tile.as_vec2() - cell.as_vec2()) * 0.5
+}
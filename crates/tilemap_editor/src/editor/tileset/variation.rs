@@ -0,0 +1,57 @@
+//! 随机变体集的编辑：用 palette 选中的 tile 新建、逐个调整权重、删除（结果写入 tileset 库）。
+
+use super::super::types::{active_variation_sets_mut, EditorState, TilesetLibrary, Variation, VariationSet};
+
+/// 权重循环：不在集内 → 1 → 2 → 4 → 0.5 → 移出集合。
+const WEIGHT_STEPS: [f32; 4] = [1.0, 2.0, 4.0, 0.5];
+
+/// 用 palette 选中的块（没有印章时为选中的单个 tile）在当前 tileset 新建一个变体集，权重均为 1；返回新集合的下标。
+pub fn define_variation_set(lib: &mut TilesetLibrary, state: &EditorState) -> Result<usize, String> {
+	let id = lib.active_id.clone().ok_or("no active tileset")?;
+	let mut indices: Vec<u32> = match &state.stamp {
+		Some(stamp) => stamp.tiles.iter().flatten().filter(|t| t.tileset_id == id).map(|t| t.index).collect(),
+		None => vec![state.selected_tile],
+	};
+	indices.sort_unstable();
+	indices.dedup();
+	if indices.is_empty() {
+		return Err("the stamp has no tiles from the active tileset".to_string());
+	}
+	let sets = active_variation_sets_mut(lib).ok_or("active tileset is not in the library")?;
+	sets.push(VariationSet {
+		name: format!("变体 {}", sets.len() + 1),
+		tiles: indices.into_iter().map(|index| Variation { index, weight: 1.0 }).collect(),
+		..Default::default()
+	});
+	Ok(sets.len() - 1)
+}
+
+/// 在第 `set` 个变体集里循环 `index` 的权重（见 `WEIGHT_STEPS`）；返回新权重，移出集合时为 `None`。
+pub fn cycle_variation_weight(lib: &mut TilesetLibrary, set: usize, index: u32) -> Result<Option<f32>, String> {
+	let sets = active_variation_sets_mut(lib).ok_or("no active tileset")?;
+	let last = sets.len().checked_sub(1).ok_or("active tileset has no variation sets")?;
+	let set = &mut sets[set.min(last)];
+	let Some(pos) = set.tiles.iter().position(|v| v.index == index) else {
+		set.tiles.push(Variation { index, weight: WEIGHT_STEPS[0] });
+		return Ok(Some(WEIGHT_STEPS[0]));
+	};
+	let step = WEIGHT_STEPS.iter().position(|w| *w == set.tiles[pos].weight);
+	match step.map(|i| i + 1).and_then(|i| WEIGHT_STEPS.get(i)) {
+		Some(&weight) => {
+			set.tiles[pos].weight = weight;
+			Ok(Some(weight))
+		}
+		// 最后一档（或手写的其它权重）之后移出集合。
+		None => {
+			set.tiles.remove(pos);
+			Ok(None)
+		}
+	}
+}
+
+/// 删除当前 tileset 的第 `set` 个变体集，返回被删集合的名字。
+pub fn delete_variation_set(lib: &mut TilesetLibrary, set: usize) -> Result<String, String> {
+	let sets = active_variation_sets_mut(lib).ok_or("no active tileset")?;
+	let last = sets.len().checked_sub(1).ok_or("active tileset has no variation sets")?;
+	Ok(sets.remove(set.min(last)).name)
+}
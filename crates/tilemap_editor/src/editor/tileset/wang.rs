@@ -0,0 +1,22 @@
+//! Wang 集的导入：从 Tiled tileset 文件（.tsj / .json）读取 wangsets 写入当前 tileset。
+
+use std::path::Path;
+
+use tilemap_format::decode_tiled_tileset_wangsets;
+
+use super::super::types::TilesetLibrary;
+
+/// 用 `path` 中的 Wang 集替换当前 tileset 的 Wang 集，返回导入的集合数。
+///
+/// wang tile 的 index 按 Tiled 的 tile id 原样使用，需与当前图集的切分一致。
+pub fn import_tiled_wangsets(lib: &mut TilesetLibrary, path: &Path) -> Result<usize, String> {
+	let text = std::fs::read_to_string(path).map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+	let sets = decode_tiled_tileset_wangsets(&text).map_err(|e| format!("{}: {e}", path.display()))?;
+	if sets.is_empty() {
+		return Err(format!("{} has no wangsets", path.display()));
+	}
+	let id = lib.active_id.clone().ok_or("no active tileset")?;
+	let entry = lib.entries.iter_mut().find(|e| e.id == id).ok_or("active tileset is not in the library")?;
+	entry.wangsets = sets;
+	Ok(entry.wangsets.len())
+}
@@ -6,48 +6,91 @@ pub const DEFAULT_SAVE_PATH: &str = "maps/map.ron";
 pub const DEFAULT_UI_FONT_PATH: &str = "chinese.ttf";
 
 mod tilemap;
+mod animation;
+mod automap;
+mod autotile;
 mod camera;
 mod clipboard;
+mod collision;
 mod config;
 mod context_menu;
 mod editor_state;
 mod input;
+mod meta;
+mod problems;
+mod replace;
 mod selection;
 mod tileset;
 mod tools;
 mod undo;
+mod variation;
+mod wang;
 mod ui;
 
-pub use tilemap::{LayerState, TileEntities, DEFAULT_CHUNK_SIZE};
+pub use tilemap::{AnimatedTile, LayerState, TileAnimationClock, TileEntities, DEFAULT_CHUNK_SIZE};
 
-pub use tilemap_core::{TileMapData, TileRef, TilesetId, DEFAULT_LAYER_COUNT};
+pub use tilemap_core::{
+    Orientation, TileBounds, TileCell, TileMapData, TileRef, TileView, TilesetId,
+};
 
+pub use animation::{
+    AnimationEditorAction, AnimationEditorButton, AnimationEditorRoot, AnimationEditorTitleText,
+    AnimationFrameList, AnimationPreviewImage, TileAnimationEditorState,
+};
+pub use automap::{AutomapRule, AutomapState};
+pub use autotile::{active_autotile, AutotileBrushState, AutotileDef, AutotileLabel, AutotileToggleButton};
 pub use camera::WorldCamera;
 pub use clipboard::{Clipboard, PasteState};
+pub use collision::{
+    CollisionEditorAction, CollisionEditorButton, CollisionEditorRoot, CollisionEditorTitleText, CollisionPreset,
+    CollisionShape, CollisionShapeList, CollisionShapeOverlay, CollisionTileImage, CollisionVertexHandle,
+    TileCollisionEditorState,
+};
 pub use config::EditorConfig;
 pub use context_menu::{
     ContextMenuAction, ContextMenuBackdrop, ContextMenuCommand, ContextMenuDisabled, ContextMenuItem,
     ContextMenuRoot, ContextMenuState, PastePreview, PastePreviewTile,
 };
-pub use editor_state::EditorState;
+pub use editor_state::{palette_stamp, EditorState};
 pub use input::PanState;
+pub use meta::{
+    passage_label, MetaBrushAction, MetaBrushButton, MetaBrushLabel, MetaBrushState, MetaField, MetaOverlayTile,
+    PASSAGE_DIRECTIONS, PASS_BLOCK_DOWN, PASS_BLOCK_LEFT, PASS_BLOCK_RIGHT, PASS_BLOCK_UP,
+};
+pub use problems::{
+    CellPos, MapProblem, ProblemAction, ProblemButton, ProblemsCloseButton, ProblemsList, ProblemsPanelRoot,
+    ProblemsRefreshButton, ProblemsState, ProblemsSummaryText,
+};
+pub use replace::{
+    ReplaceLayers, ReplaceTileAction, ReplaceTileButton, ReplaceTileDialogRoot, ReplaceTileDialogState,
+    ReplaceTileSummaryText,
+};
 pub use selection::{SelectionMovePreviewTile, SelectionRect, SelectionState};
 pub use tileset::{
-    PendingTileset, TilesetEntry, TilesetLibrary, TilesetLoading, TilesetRuntime,
-    TilesetRuntimeEntry,
+    FailedTileset, ImportedTileset, PendingTileset, RelinkAction, RelinkButton, RelinkCloseButton,
+    RelinkDialogRoot, RelinkDialogState, RelinkList, TilesetEntry, TilesetGridApplyButton,
+    TilesetGridCancelButton, TilesetGridDialogRoot, TilesetGridDialogState, TilesetGridField,
+    TilesetGridFieldButton, TilesetGridFieldText, TilesetGridSummaryText, TilesetGridTitleText,
+    TilesetLibrary, TilesetLoading, TilesetRuntime, TilesetRuntimeEntry,
 };
 pub use tools::{
-    BrushSettings, ShiftMapMode, ShiftMapSettings, ShiftModeButton, ShiftModeLabel, ToolButton, ToolKind,
-    ToolState,
+    fill_mode_label, BrushSettings, FillMode, LineToolState, SampleSource, SampleSourceButton, SampleSourceLabel,
+    ShapeMode, ShapePreview, ShapePreviewTile, ShiftMapMode, ShiftMapSettings, ShiftModeButton, ShiftModeLabel,
+    ToolButton, ToolKind, ToolState,
 };
 pub use undo::{CellChange, EditCommand, UndoStack};
+pub use variation::{
+    active_variation_set, active_variation_sets_mut, Variation, VariationBrushAction, VariationBrushButton,
+    VariationBrushLabel, VariationBrushState, VariationSet,
+};
+pub use wang::{active_wangset, WangBrushAction, WangBrushButton, WangBrushLabel, WangBrushState};
 pub use ui::{
-    ActionButton, ActionKind, CanvasRoot, HudText,
+    ActionButton, ActionKind, CanvasClickBlocker, CanvasRoot, HudText,
     LayerPrevButton, LayerNextButton, LayerActiveLabel, LayerActiveVisLabel, LayerActiveVisToggleButton,
     LayerActiveLockLabel, LayerActiveLockToggleButton,
     MapSizeApplyButton, MapSizeFocus, MapSizeHeightField,
     MapSizeHeightText, MapSizeInput, MapSizeWidthField, MapSizeWidthText, PaletteRoot, PaletteScroll,
-    PaletteTileButton, TilesetActiveLabel,
+    PaletteTileButton, PaletteTileImage, TilesetActiveLabel,
     PaletteSearchClearButton, PaletteSearchField, PaletteSearchInput, PaletteSearchText,
     PaletteZoomButton, PaletteZoomLevel,
 	MenuBackdrop, MenuButton, MenuDropdown, MenuId, MenuItem, MenuState,
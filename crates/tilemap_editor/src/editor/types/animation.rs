@@ -0,0 +1,98 @@
+use bevy::prelude::*;
+
+use super::{TilesetEntry, TilesetId};
+
+/// tile 动画定义由 `tilemap_format` 提供（tileset 库与地图文件共用）。
+pub use tilemap_format::AnimationFrame;
+
+/// 新帧的默认时长（毫秒）。
+pub const DEFAULT_FRAME_MS: u32 = 100;
+/// 帧时长 +/- 按钮的步长（毫秒）。
+pub const FRAME_MS_STEP: u32 = 50;
+
+/// 动画编辑器上的操作（按钮携带帧下标）。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnimationEditorAction {
+    Shorter(usize),
+    Longer(usize),
+    Remove(usize),
+    Clear,
+    Save,
+    Cancel,
+}
+
+/// tile 动画编辑器：打开时点击 palette 中的 tile 追加为帧。
+#[derive(Resource, Default)]
+pub struct TileAnimationEditorState {
+    pub open: bool,
+    pub tileset_id: Option<TilesetId>,
+    /// 正在编辑动画的 tile（地图里存的 index）。
+    pub tile_index: u32,
+    pub frames: Vec<AnimationFrame>,
+    /// 帧列表需要重建。
+    pub rebuild: bool,
+}
+
+impl TileAnimationEditorState {
+    /// 以 tile 现有的动画打开编辑器。
+    pub fn open_for(&mut self, entry: &TilesetEntry, tile_index: u32) {
+        self.tileset_id = Some(entry.id.clone());
+        self.tile_index = tile_index;
+        self.frames = entry.animation(tile_index).to_vec();
+        self.open = true;
+        self.rebuild = true;
+    }
+
+    pub fn close(&mut self) {
+        self.open = false;
+        self.rebuild = true;
+    }
+
+    /// 追加一帧，时长沿用上一帧（没有时用默认值）。
+    pub fn push_frame(&mut self, index: u32) {
+        let duration_ms = self.frames.last().map_or(DEFAULT_FRAME_MS, |f| f.duration_ms);
+        self.frames.push(AnimationFrame { index, duration_ms });
+        self.rebuild = true;
+    }
+
+    /// 处理除 Save 以外的操作（Save 需要写回 tileset 库，由 UI 系统处理）。
+    pub fn apply(&mut self, action: AnimationEditorAction) {
+        match action {
+            AnimationEditorAction::Shorter(i) => {
+                if let Some(f) = self.frames.get_mut(i) {
+                    f.duration_ms = f.duration_ms.saturating_sub(FRAME_MS_STEP).max(FRAME_MS_STEP);
+                }
+            }
+            AnimationEditorAction::Longer(i) => {
+                if let Some(f) = self.frames.get_mut(i) {
+                    f.duration_ms = f.duration_ms.saturating_add(FRAME_MS_STEP);
+                }
+            }
+            AnimationEditorAction::Remove(i) => {
+                if i < self.frames.len() {
+                    self.frames.remove(i);
+                }
+            }
+            AnimationEditorAction::Clear => self.frames.clear(),
+            AnimationEditorAction::Cancel => self.close(),
+            AnimationEditorAction::Save => {}
+        }
+        self.rebuild = true;
+    }
+}
+
+#[derive(Component)]
+pub struct AnimationEditorRoot;
+
+#[derive(Component)]
+pub struct AnimationEditorTitleText;
+
+#[derive(Component)]
+pub struct AnimationFrameList;
+
+/// 按当前帧序列循环播放的预览图。
+#[derive(Component)]
+pub struct AnimationPreviewImage;
+
+#[derive(Component, Clone, Copy)]
+pub struct AnimationEditorButton(pub AnimationEditorAction);
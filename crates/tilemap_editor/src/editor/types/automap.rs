@@ -0,0 +1,14 @@
+use bevy::prelude::*;
+
+/// 规则与匹配由 `tilemap_core` 提供（规则文件就是一张普通地图，图层名为 `input_*` / `inputnot_*` / `output_*`）。
+pub use tilemap_core::AutomapRule;
+
+/// 自动映射规则：Map → 载入规则 / 应用规则；`auto_run` 开启时每次绘制后在改动附近自动应用。
+#[derive(Resource, Default)]
+pub struct AutomapState {
+    pub rules: Vec<AutomapRule>,
+    /// 规则文件路径（仅用于提示）。
+    pub source: Option<String>,
+    pub auto_run: bool,
+}
+
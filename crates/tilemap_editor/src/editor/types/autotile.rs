@@ -0,0 +1,50 @@
+use bevy::prelude::*;
+
+use super::{TilesetId, TilesetLibrary};
+
+/// 自动图块组定义、变体选择与绘制由 `tilemap_core` 提供（随 tileset 库保存）。
+pub use tilemap_core::AutotileDef;
+
+/// 自动图块模式：开启后铅笔 / 橡皮 / 矩形 / 油漆桶按当前组绘制，并连带更新 8 邻居的变体。
+///
+/// 元数据模式优先；当前组 = 当前 tileset 中包含选中 tile 的组，否则为该 tileset 的第一组。
+#[derive(Resource, Default)]
+pub struct AutotileBrushState {
+    pub enabled: bool,
+}
+
+impl AutotileBrushState {
+    /// 模式开启且当前 tileset 定义了自动图块组时返回 (tileset id, 组)。
+    pub fn active(&self, lib: &TilesetLibrary, selected_tile: u32) -> Option<(TilesetId, AutotileDef)> {
+        if !self.enabled {
+            return None;
+        }
+        active_autotile(lib, selected_tile)
+    }
+
+    /// 顶栏上的简短说明：关 / 组名 / 无（当前 tileset 没有自动图块组）。
+    pub fn label(&self, lib: &TilesetLibrary, selected_tile: u32) -> String {
+        if !self.enabled {
+            return "关".to_string();
+        }
+        match active_autotile(lib, selected_tile) {
+            Some((_, def)) => def.name,
+            None => "无".to_string(),
+        }
+    }
+}
+
+/// 当前 tileset 中与选中 tile 对应的自动图块组（不看模式开关）。
+pub fn active_autotile(lib: &TilesetLibrary, selected_tile: u32) -> Option<(TilesetId, AutotileDef)> {
+    let id = lib.active_id.as_ref()?;
+    let entry = lib.entries.iter().find(|e| e.id == *id)?;
+    let def = entry.autotile_at(selected_tile).or_else(|| entry.autotiles.first())?;
+    Some((id.clone(), def.clone()))
+}
+
+/// 顶栏的自动图块模式开关。
+#[derive(Component)]
+pub struct AutotileToggleButton;
+
+#[derive(Component)]
+pub struct AutotileLabel;
@@ -1,18 +1,14 @@
 use bevy::prelude::*;
 
-use super::TileRef;
+use tilemap_core::ops::TileRegion;
 
-#[derive(Resource, Default, Clone)]
-pub struct Clipboard {
-    pub width: u32,
-    pub height: u32,
-    pub tiles: Vec<Option<TileRef>>,
-}
+use super::Orientation;
+
+/// 剪贴板即一块 `TileRegion`（与地图的 tileset 表无关，可跨地图粘贴）。
+pub type Clipboard = TileRegion;
 
 #[derive(Resource, Default, Clone, Copy)]
 pub struct PasteState {
-    /// 0,1,2,3 => 0/90/180/270 度顺时针。
-    pub rot: u8,
-    pub flip_x: bool,
-    pub flip_y: bool,
+    /// 粘贴整体的朝向：同时作用于格子位置与每个 tile 的朝向。
+    pub orientation: Orientation,
 }
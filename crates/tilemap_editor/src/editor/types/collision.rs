@@ -0,0 +1,230 @@
+use bevy::prelude::*;
+
+use super::{TilesetEntry, TilesetId};
+
+/// tile 碰撞形状由 `tilemap_core` 提供（tileset 库、地图文件与游戏运行时共用）。
+pub use tilemap_core::CollisionShape;
+
+/// 拖动顶点时的吸附步长（tile 的 1/16）。
+const COLLISION_SNAP: f32 = 1.0 / 16.0;
+
+/// 常用碰撞形状预设（半格、斜坡等），添加后可再拖动顶点微调。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CollisionPreset {
+    Full,
+    BottomHalf,
+    TopHalf,
+    LeftHalf,
+    RightHalf,
+    /// 向右上升的斜坡（实心在右下）。
+    SlopeUp,
+    /// 向右下降的斜坡（实心在左下）。
+    SlopeDown,
+    /// 自由多边形：从居中的小方块开始编辑。
+    Polygon,
+}
+
+impl CollisionPreset {
+    pub const ALL: [CollisionPreset; 8] = [
+        CollisionPreset::Full,
+        CollisionPreset::BottomHalf,
+        CollisionPreset::TopHalf,
+        CollisionPreset::LeftHalf,
+        CollisionPreset::RightHalf,
+        CollisionPreset::SlopeUp,
+        CollisionPreset::SlopeDown,
+        CollisionPreset::Polygon,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CollisionPreset::Full => "整格",
+            CollisionPreset::BottomHalf => "下半",
+            CollisionPreset::TopHalf => "上半",
+            CollisionPreset::LeftHalf => "左半",
+            CollisionPreset::RightHalf => "右半",
+            CollisionPreset::SlopeUp => "斜坡 ◢",
+            CollisionPreset::SlopeDown => "斜坡 ◣",
+            CollisionPreset::Polygon => "多边形",
+        }
+    }
+
+    pub fn shape(self) -> CollisionShape {
+        let poly = |points: &[[f32; 2]]| CollisionShape::polygon(points.to_vec());
+        match self {
+            CollisionPreset::Full => CollisionShape::Full,
+            CollisionPreset::BottomHalf => poly(&[[0.0, 0.0], [1.0, 0.0], [1.0, 0.5], [0.0, 0.5]]),
+            CollisionPreset::TopHalf => poly(&[[0.0, 0.5], [1.0, 0.5], [1.0, 1.0], [0.0, 1.0]]),
+            CollisionPreset::LeftHalf => poly(&[[0.0, 0.0], [0.5, 0.0], [0.5, 1.0], [0.0, 1.0]]),
+            CollisionPreset::RightHalf => poly(&[[0.5, 0.0], [1.0, 0.0], [1.0, 1.0], [0.5, 1.0]]),
+            CollisionPreset::SlopeUp => poly(&[[0.0, 0.0], [1.0, 0.0], [1.0, 1.0]]),
+            CollisionPreset::SlopeDown => poly(&[[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]]),
+            CollisionPreset::Polygon => poly(&[[0.25, 0.25], [0.75, 0.25], [0.75, 0.75], [0.25, 0.75]]),
+        }
+    }
+}
+
+/// 碰撞编辑器上的操作。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CollisionEditorAction {
+    Add(CollisionPreset),
+    Select(usize),
+    DeleteShape,
+    /// 在选中顶点与下一个顶点之间插入中点。
+    AddVertex,
+    RemoveVertex,
+    Save,
+    Cancel,
+}
+
+/// tile 碰撞编辑器：放大显示 tile，拖动顶点编辑选中的形状。
+#[derive(Resource, Default)]
+pub struct TileCollisionEditorState {
+    pub open: bool,
+    pub tileset_id: Option<TilesetId>,
+    pub tile_index: u32,
+    pub shapes: Vec<CollisionShape>,
+    pub selected_shape: Option<usize>,
+    pub selected_vertex: Option<usize>,
+    /// 正在拖动选中的顶点（鼠标左键按住）。
+    pub dragging: bool,
+    /// 形状列表 / 顶点手柄需要重建。
+    pub rebuild: bool,
+}
+
+impl TileCollisionEditorState {
+    /// 以 tile 现有的碰撞形状打开编辑器。
+    pub fn open_for(&mut self, entry: &TilesetEntry, tile_index: u32) {
+        self.tileset_id = Some(entry.id.clone());
+        self.tile_index = tile_index;
+        self.shapes = entry.collision(tile_index).to_vec();
+        self.selected_shape = (!self.shapes.is_empty()).then_some(0);
+        self.selected_vertex = None;
+        self.dragging = false;
+        self.open = true;
+        self.rebuild = true;
+    }
+
+    pub fn close(&mut self) {
+        self.open = false;
+        self.dragging = false;
+        self.rebuild = true;
+    }
+
+    /// 处理除 Save 以外的操作（Save 需要写回 tileset 库，由 UI 系统处理）。
+    pub fn apply(&mut self, action: CollisionEditorAction) {
+        match action {
+            CollisionEditorAction::Add(preset) => {
+                self.shapes.push(preset.shape());
+                self.selected_shape = Some(self.shapes.len() - 1);
+                self.selected_vertex = None;
+            }
+            CollisionEditorAction::Select(i) => {
+                if i < self.shapes.len() {
+                    self.selected_shape = Some(i);
+                    self.selected_vertex = None;
+                }
+            }
+            CollisionEditorAction::DeleteShape => {
+                if let Some(i) = self.selected_shape.filter(|&i| i < self.shapes.len()) {
+                    self.shapes.remove(i);
+                    self.selected_shape = i.checked_sub(1).or((!self.shapes.is_empty()).then_some(0));
+                    self.selected_vertex = None;
+                }
+            }
+            CollisionEditorAction::AddVertex => {
+                if let Some((points, v)) = self.selected_points() {
+                    let [ax, ay] = points[v];
+                    let [bx, by] = points[(v + 1) % points.len()];
+                    points.insert(v + 1, [snap((ax + bx) * 0.5), snap((ay + by) * 0.5)]);
+                    self.selected_vertex = Some(v + 1);
+                }
+            }
+            CollisionEditorAction::RemoveVertex => {
+                if let Some((points, v)) = self.selected_points()
+                    && points.len() > 3
+                {
+                    points.remove(v);
+                    self.selected_vertex = Some(v.min(points.len() - 1));
+                }
+            }
+            CollisionEditorAction::Cancel => self.close(),
+            CollisionEditorAction::Save => {}
+        }
+        self.rebuild = true;
+    }
+
+    /// 选中并开始拖动某个顶点。
+    pub fn grab_vertex(&mut self, shape: usize, vertex: usize) {
+        self.selected_shape = Some(shape);
+        self.selected_vertex = Some(vertex);
+        self.dragging = true;
+        self.rebuild = true;
+    }
+
+    /// 把选中的顶点移到 `point`（tile 局部坐标，吸附到网格并限制在 tile 内）。
+    /// 整格形状被拖动时先转成四边形。
+    pub fn move_selected_vertex(&mut self, point: Vec2) {
+        let p = [snap(point.x.clamp(0.0, 1.0)), snap(point.y.clamp(0.0, 1.0))];
+        if let Some((points, v)) = self.selected_points()
+            && points[v] != p
+        {
+            points[v] = p;
+            self.rebuild = true;
+        }
+    }
+
+    /// 保存用的形状：顶点统一为逆时针，去掉退化的多边形。
+    pub fn shapes_to_save(&self) -> Vec<CollisionShape> {
+        self.shapes
+            .iter()
+            .map(|s| match s {
+                CollisionShape::Full => CollisionShape::Full,
+                CollisionShape::Polygon(points) => CollisionShape::polygon(points.clone()),
+            })
+            .filter(|s| !s.is_degenerate())
+            .collect()
+    }
+
+    fn selected_points(&mut self) -> Option<(&mut Vec<[f32; 2]>, usize)> {
+        let shape = self.shapes.get_mut(self.selected_shape?)?;
+        if *shape == CollisionShape::Full {
+            *shape = CollisionShape::Polygon(shape.points());
+        }
+        let CollisionShape::Polygon(points) = shape else {
+            return None;
+        };
+        let v = self.selected_vertex.filter(|&v| v < points.len())?;
+        Some((points, v))
+    }
+}
+
+fn snap(v: f32) -> f32 {
+    (v / COLLISION_SNAP).round() * COLLISION_SNAP
+}
+
+#[derive(Component)]
+pub struct CollisionEditorRoot;
+
+#[derive(Component)]
+pub struct CollisionEditorTitleText;
+
+/// 放大显示的 tile（带 `RelativeCursorPosition`，拖动时据此换算顶点坐标）。
+#[derive(Component)]
+pub struct CollisionTileImage;
+
+/// tile 上方的覆盖层：形状的边与顶点手柄都挂在这里，重建时整体清空。
+#[derive(Component)]
+pub struct CollisionShapeOverlay;
+
+#[derive(Component)]
+pub struct CollisionShapeList;
+
+#[derive(Component, Clone, Copy)]
+pub struct CollisionVertexHandle {
+    pub shape: usize,
+    pub vertex: usize,
+}
+
+#[derive(Component, Clone, Copy)]
+pub struct CollisionEditorButton(pub CollisionEditorAction);
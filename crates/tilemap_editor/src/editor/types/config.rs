@@ -16,6 +16,10 @@ pub struct EditorConfig {
     pub show_grid: bool,
     pub show_hover: bool,
     pub show_cursor: bool,
+    /// 播放 tile 动画（画布与 palette）。
+    pub play_animations: bool,
+    /// 在画布上叠加显示已放置 tile 的碰撞形状。
+    pub show_collision: bool,
 }
 
 impl Default for EditorConfig {
@@ -31,6 +35,8 @@ impl Default for EditorConfig {
             show_grid: true,
             show_hover: true,
             show_cursor: true,
+            play_animations: true,
+            show_collision: false,
         }
     }
 }
@@ -5,10 +5,14 @@ pub enum ContextMenuAction {
     Undo,
     Redo,
     EnterPaste,
+    /// 剪贴板设为铅笔印章并切到铅笔。
+    ClipboardAsStamp,
     SelectionCopy,
     SelectionCut,
     SelectionDelete,
     SelectionSelectAll,
+    /// 选中当前图层上所有与右键所指格子相同的 tile。
+    SelectSameTile,
     SelectionDeselect,
 
     PasteRotateCcw,
@@ -25,7 +29,7 @@ pub struct ContextMenuState {
     /// UI 屏幕坐标（原点左上）。
     pub screen_pos: Vec2,
     /// 打开菜单时鼠标所在的地图格子坐标（若不在地图上则为 None）。
-    pub map_pos: Option<UVec2>,
+    pub map_pos: Option<IVec2>,
     /// 用于“点击菜单项/点击空白关闭”时，避免同一帧触发画布左键操作。
     pub consume_left_click: bool,
     /// 用于 UI 动态重建菜单：状态签名（工具/选区/剪贴板等）变化时重建。
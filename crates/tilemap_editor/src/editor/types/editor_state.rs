@@ -1,12 +1,54 @@
 use bevy::prelude::*;
 
+use tilemap_core::ops::TileRegion;
+
+use super::{TileRef, TilesetId};
+
 #[derive(Resource)]
 pub struct EditorState {
     pub selected_tile: u32,
+    /// 铅笔的多格印章（palette 拖选或剪贴板生成）；`None` 时按 `selected_tile` 单格绘制。
+    pub stamp: Option<TileRegion>,
 }
 
 impl Default for EditorState {
     fn default() -> Self {
-        Self { selected_tile: 0 }
+        Self { selected_tile: 0, stamp: None }
+    }
+}
+
+impl EditorState {
+    /// palette 上是否高亮 `tileset_id` 的第 `index` 个 tile：有印章时为印章里的 tile，否则为选中的 tile。
+    pub fn is_palette_selected(&self, tileset_id: Option<&TilesetId>, index: u32) -> bool {
+        match &self.stamp {
+            Some(stamp) => stamp.tiles.iter().flatten().any(|t| Some(&t.tileset_id) == tileset_id && t.index == index),
+            None => self.selected_tile == index,
+        }
+    }
+}
+
+/// palette 里 `a`、`b` 两个 tile 围成的矩形块作为印章；同一个 tile 时返回 `None`。
+///
+/// 图集第 0 行在最上方，地图 y 向上，所以图集行号越大落在印章越低处。
+pub fn palette_stamp(tileset_id: &TilesetId, a: u32, b: u32, columns: u32) -> Option<TileRegion> {
+    if a == b {
+        return None;
+    }
+    let columns = columns.max(1);
+    let (col0, col1) = ((a % columns).min(b % columns), (a % columns).max(b % columns));
+    let (row0, row1) = ((a / columns).min(b / columns), (a / columns).max(b / columns));
+    let (width, height) = (col1 - col0 + 1, row1 - row0 + 1);
+    let mut tiles = Vec::with_capacity((width * height) as usize);
+    for sy in 0..height {
+        for sx in 0..width {
+            tiles.push(Some(TileRef {
+                tileset_id: tileset_id.clone(),
+                index: (row1 - sy) * columns + col0 + sx,
+                rot: 0,
+                flip_x: false,
+                flip_y: false,
+            }));
+        }
     }
+    Some(TileRegion { width, height, tiles })
 }
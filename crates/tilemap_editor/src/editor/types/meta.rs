@@ -0,0 +1,118 @@
+use bevy::prelude::*;
+
+use tilemap_core::PASS_BLOCK_ALL;
+
+/// 元数据网格由 `tilemap_core` 提供（随地图保存，游戏运行时直接读取）。
+pub use tilemap_core::{MetaField, PASS_BLOCK_DOWN, PASS_BLOCK_LEFT, PASS_BLOCK_RIGHT, PASS_BLOCK_UP};
+
+/// 通行方向：(标记位, 箭头)。
+pub const PASSAGE_DIRECTIONS: [(u8, &str); 4] = [
+    (PASS_BLOCK_UP, "↑"),
+    (PASS_BLOCK_DOWN, "↓"),
+    (PASS_BLOCK_LEFT, "←"),
+    (PASS_BLOCK_RIGHT, "→"),
+];
+
+/// 元数据模式：开启后铅笔 / 矩形 / 油漆桶写入当前字段的值（橡皮与 Shift 写 0），不改 tile。
+#[derive(Resource)]
+pub struct MetaBrushState {
+    pub enabled: bool,
+    pub field: MetaField,
+    pub region: u8,
+    /// `PASS_BLOCK_*` 位组合（置位 = 该方向不可通行）。
+    pub passage: u8,
+    pub terrain: u8,
+}
+
+impl Default for MetaBrushState {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            field: MetaField::Region,
+            region: 1,
+            passage: PASS_BLOCK_ALL,
+            terrain: 1,
+        }
+    }
+}
+
+impl MetaBrushState {
+    /// 当前字段要写入的值。
+    pub fn value(&self) -> u8 {
+        match self.field {
+            MetaField::Region => self.region,
+            MetaField::Passage => self.passage,
+            MetaField::Terrain => self.terrain,
+        }
+    }
+
+    pub fn apply(&mut self, action: MetaBrushAction) {
+        match action {
+            MetaBrushAction::ToggleMode => self.enabled = !self.enabled,
+            MetaBrushAction::CycleField => {
+                self.field = match self.field {
+                    MetaField::Region => MetaField::Passage,
+                    MetaField::Passage => MetaField::Terrain,
+                    MetaField::Terrain => MetaField::Region,
+                };
+            }
+            MetaBrushAction::Step(delta) => {
+                let value = match self.field {
+                    MetaField::Region => &mut self.region,
+                    MetaField::Terrain => &mut self.terrain,
+                    MetaField::Passage => return,
+                };
+                *value = (*value as i32 + delta).clamp(0, u8::MAX as i32) as u8;
+            }
+            MetaBrushAction::TogglePassage(bit) => {
+                self.field = MetaField::Passage;
+                self.passage ^= bit & PASS_BLOCK_ALL;
+            }
+        }
+    }
+
+    /// 顶栏上的简短说明，例如 `区域 3` / `通行 ↑←`。
+    pub fn label(&self) -> String {
+        if !self.enabled {
+            return "关".to_string();
+        }
+        match self.field {
+            MetaField::Region => format!("区域 {}", self.region),
+            MetaField::Passage => format!("通行 {}", passage_label(self.passage)),
+            MetaField::Terrain => format!("地形 {}", self.terrain),
+        }
+    }
+}
+
+/// 被阻挡的方向（箭头）；全通行显示“通”。
+pub fn passage_label(passage: u8) -> String {
+    if passage & PASS_BLOCK_ALL == 0 {
+        return "通".to_string();
+    }
+    PASSAGE_DIRECTIONS
+        .iter()
+        .filter(|(bit, _)| passage & bit != 0)
+        .map(|(_, arrow)| *arrow)
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MetaBrushAction {
+    ToggleMode,
+    /// 区域 -> 通行 -> 地形。
+    CycleField,
+    /// 区域 / 地形的值加减（0..=255）。
+    Step(i32),
+    /// 切换某个方向的阻挡标记（同时切到通行字段）。
+    TogglePassage(u8),
+}
+
+#[derive(Component, Clone, Copy)]
+pub struct MetaBrushButton(pub MetaBrushAction);
+
+#[derive(Component)]
+pub struct MetaBrushLabel;
+
+/// 画布上的元数据半透明色块（地图或笔刷字段变化时整体重建）。
+#[derive(Component)]
+pub struct MetaOverlayTile;
@@ -0,0 +1,56 @@
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+/// 地图检查结果由 `tilemap_format` 定义（CLI 的 validate 子命令共用）。
+pub use tilemap_format::{CellPos, MapProblem};
+
+/// Problems 面板状态。
+#[derive(Resource, Default)]
+pub struct ProblemsState {
+    pub open: bool,
+    pub problems: Vec<MapProblem>,
+    /// 需要重新检查（打开面板、修复后、点击刷新）。
+    pub dirty: bool,
+    /// 面板内容需要重建。
+    pub rebuild: bool,
+    /// asset_path -> 文件内容 blake3，避免每次检查都重读图片；点击刷新时清空。
+    pub hash_cache: HashMap<String, String>,
+}
+
+/// 问题条目上的操作按钮。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProblemAction {
+    /// 跳到第一个受影响的格子（切换图层并选中该格）。
+    Locate,
+    /// 打开重新链接对话框（选择替换图片，校验 hash 或重映射 id）。
+    Relink,
+    /// 清除 tile index 超出图集范围的格子。
+    ClearInvalid,
+    /// 清除引用该 tileset 的全部格子。
+    ClearTileset,
+    /// 按 layers 补齐/截断 layer_data。
+    FixLayers,
+}
+
+#[derive(Component, Clone, Copy)]
+pub struct ProblemButton {
+    /// `ProblemsState::problems` 中的下标。
+    pub index: usize,
+    pub action: ProblemAction,
+}
+
+#[derive(Component)]
+pub struct ProblemsPanelRoot;
+
+/// 问题列表容器（重建时只清空它的子节点）。
+#[derive(Component)]
+pub struct ProblemsList;
+
+#[derive(Component)]
+pub struct ProblemsSummaryText;
+
+#[derive(Component)]
+pub struct ProblemsRefreshButton;
+
+#[derive(Component)]
+pub struct ProblemsCloseButton;
@@ -0,0 +1,80 @@
+use bevy::prelude::*;
+
+use super::TileRef;
+
+/// 替换 tile 作用的图层。
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ReplaceLayers {
+    #[default]
+    Active,
+    Visible,
+    All,
+}
+
+impl ReplaceLayers {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Active => Self::Visible,
+            Self::Visible => Self::All,
+            Self::All => Self::Active,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Active => "当前图层",
+            Self::Visible => "可见图层",
+            Self::All => "所有图层",
+        }
+    }
+}
+
+/// 替换 Tile 对话框（Edit → 替换 Tile…）：把 `from` 换成 `to`（None 为清空），一次撤销。
+#[derive(Resource, Default)]
+pub struct ReplaceTileDialogState {
+    pub open: bool,
+    pub from: Option<TileRef>,
+    pub to: Option<TileRef>,
+    /// 只比较 tileset 与 index，替换后沿用原格子的朝向。
+    pub ignore_orientation: bool,
+    pub layers: ReplaceLayers,
+    /// 只替换选区内的格子。
+    pub selection_only: bool,
+    /// 上一次替换改动的格子数。
+    pub replaced: Option<usize>,
+}
+
+impl ReplaceTileDialogState {
+    /// 打开对话框，源 tile 预设为 palette 当前选中的 tile。
+    pub fn open_with(&mut self, selected: Option<TileRef>) {
+        if selected.is_some() {
+            self.from = selected;
+        }
+        self.replaced = None;
+        self.open = true;
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReplaceTileAction {
+    /// 源 tile 取 palette 当前选中的 tile。
+    PickFrom,
+    /// 目标 tile 取 palette 当前选中的 tile。
+    PickTo,
+    /// 目标为空（清除匹配的格子）。
+    ClearTo,
+    ToggleOrientation,
+    CycleLayers,
+    ToggleSelection,
+    Apply,
+    Close,
+}
+
+#[derive(Component, Clone, Copy)]
+pub struct ReplaceTileButton(pub ReplaceTileAction);
+
+#[derive(Component)]
+pub struct ReplaceTileDialogRoot;
+
+#[derive(Component)]
+pub struct ReplaceTileSummaryText;
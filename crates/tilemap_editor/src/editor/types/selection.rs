@@ -1,30 +1,105 @@
+use std::collections::HashSet;
+
 use bevy::prelude::*;
 
+use super::TileBounds;
+
 #[derive(Component)]
 pub struct SelectionMovePreviewTile;
 
 #[derive(Clone, Copy, Debug, Default)]
 pub struct SelectionRect {
-    pub min: UVec2,
-    pub max: UVec2,
+    pub min: IVec2,
+    pub max: IVec2,
 }
 
 impl SelectionRect {
     pub fn width(&self) -> u32 {
-        self.max.x.saturating_sub(self.min.x) + 1
+        (self.max.x - self.min.x).max(0) as u32 + 1
     }
 
     pub fn height(&self) -> u32 {
-        self.max.y.saturating_sub(self.min.y) + 1
+        (self.max.y - self.min.y).max(0) as u32 + 1
+    }
+
+    pub fn contains(&self, p: IVec2) -> bool {
+        p.x >= self.min.x && p.x <= self.max.x && p.y >= self.min.y && p.y <= self.max.y
+    }
+
+    pub fn bounds(&self) -> TileBounds {
+        TileBounds::from_corners(self.min.x, self.min.y, self.max.x, self.max.y)
     }
 }
 
 #[derive(Resource, Default)]
 pub struct SelectionState {
     pub dragging: bool,
-    pub start: UVec2,
-    pub current: UVec2,
+    pub start: IVec2,
+    pub current: IVec2,
+    /// 选区的外接框；`mask` 为 None 时选中整个矩形。
     pub rect: Option<SelectionRect>,
+    /// 魔棒 / 按 tile 选择得到的任意形状选区（格子都在 `rect` 内）。
+    pub mask: Option<HashSet<IVec2>>,
     /// 是否正在“拖拽移动选区内容”（与 dragging=框选不同）。
     pub moving: bool,
 }
+
+impl SelectionState {
+    /// 选中整个矩形（清掉任意形状的选区）。
+    pub fn set_rect(&mut self, rect: SelectionRect) {
+        self.dragging = false;
+        self.start = rect.min;
+        self.current = rect.max;
+        self.rect = Some(rect);
+        self.mask = None;
+    }
+
+    /// 选中一组格子；正好铺满外接框时退化为矩形选区，为空时取消选择。
+    pub fn set_cells(&mut self, cells: impl IntoIterator<Item = IVec2>) {
+        let cells: HashSet<IVec2> = cells.into_iter().collect();
+        let Some(first) = cells.iter().next().copied() else {
+            self.clear();
+            return;
+        };
+        let (min, max) = cells.iter().fold((first, first), |(lo, hi), &p| (lo.min(p), hi.max(p)));
+        let rect = SelectionRect { min, max };
+        self.set_rect(rect);
+        if cells.len() != (rect.width() * rect.height()) as usize {
+            self.mask = Some(cells);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.dragging = false;
+        self.rect = None;
+        self.mask = None;
+    }
+
+    pub fn contains(&self, p: IVec2) -> bool {
+        self.rect.is_some_and(|r| r.contains(p)) && self.mask.as_ref().is_none_or(|m| m.contains(&p))
+    }
+
+    /// 选中的格子（按行从下到上、从左到右）。
+    pub fn cells(&self) -> Vec<(i32, i32)> {
+        let Some(rect) = self.rect else {
+            return Vec::new();
+        };
+        (rect.min.y..=rect.max.y)
+            .flat_map(|y| (rect.min.x..=rect.max.x).map(move |x| IVec2::new(x, y)))
+            .filter(|&p| self.contains(p))
+            .map(|p| (p.x, p.y))
+            .collect()
+    }
+
+    /// 选区整体平移（内容移动后跟随）。
+    pub fn shift(&mut self, offset: IVec2) {
+        let mask = self.mask.take();
+        if let Some(rect) = self.rect {
+            self.set_rect(SelectionRect {
+                min: rect.min + offset,
+                max: rect.max + offset,
+            });
+        }
+        self.mask = mask.map(|m| m.into_iter().map(|p| p + offset).collect());
+    }
+}
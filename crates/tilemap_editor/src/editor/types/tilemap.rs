@@ -1,7 +1,7 @@
 use bevy::prelude::*;
 use std::collections::HashMap;
 
-use tilemap_core::TilesetId;
+use tilemap_core::{TileMapData, TilesetId};
 
 pub const DEFAULT_CHUNK_SIZE: u32 = 64;
 
@@ -11,21 +11,40 @@ pub struct TileEntities {
     pub width: u32,
     pub height: u32,
     pub layers: u32,
+    /// 无限地图：width/height 无意义，chunk 坐标可为负。
+    pub infinite: bool,
     /// 每个 tilemap chunk 的边长（格子数）
     pub chunk_size: u32,
     /// tileset_id -> 每层 tilemap chunk entity（len = layers）
-    pub tilemaps: HashMap<TilesetId, Vec<HashMap<(u32, u32), Entity>>>,
+    pub tilemaps: HashMap<TilesetId, Vec<HashMap<(i32, i32), Entity>>>,
     /// tileset 创建顺序（用于稳定 z 排序）
     pub tileset_order: Vec<TilesetId>,
 }
 
 impl TileEntities {
+    /// 固定尺寸地图返回 (width, height)；无限地图返回 None。
+    pub fn fixed_size(&self) -> Option<(u32, u32)> {
+        (!self.infinite).then_some((self.width, self.height))
+    }
+
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        match self.fixed_size() {
+            Some((w, h)) => x >= 0 && y >= 0 && (x as u32) < w && (y as u32) < h,
+            None => true,
+        }
+    }
+
+    /// 当前渲染容器是否与地图的尺寸/模式一致（不一致时需要重建）。
+    pub fn matches_map(&self, map: &TileMapData) -> bool {
+        self.fixed_size() == map.fixed_size() && self.layers == map.layers.max(1)
+    }
+
     pub fn chunk_entity(
         &self,
-        tileset_id: &TilesetId,
+        tileset_id: &str,
         layer: u32,
-        cx: u32,
-        cy: u32,
+        cx: i32,
+        cy: i32,
     ) -> Option<Entity> {
         let layers = self.tilemaps.get(tileset_id)?;
         let layer_map = layers.get(layer as usize)?;
@@ -36,8 +55,8 @@ impl TileEntities {
         &mut self,
         tileset_id: TilesetId,
         layer: u32,
-        cx: u32,
-        cy: u32,
+        cx: i32,
+        cy: i32,
         entity: Entity,
     ) {
         let layers = self.layers.max(1) as usize;
@@ -53,11 +72,11 @@ impl TileEntities {
         }
     }
 
-    pub fn tileset_index(&mut self, tileset_id: &TilesetId) -> usize {
+    pub fn tileset_index(&mut self, tileset_id: &str) -> usize {
         if let Some(i) = self.tileset_order.iter().position(|id| id == tileset_id) {
             return i;
         }
-        self.tileset_order.push(tileset_id.clone());
+        self.tileset_order.push(tileset_id.to_string());
         self.tileset_order.len() - 1
     }
 
@@ -74,6 +93,19 @@ impl TileEntities {
     }
 }
 
+/// 带动画的地图 tile：`base_index` 为地图里存的 index，显示的帧由动画系统每帧改写。
+#[derive(Component, Clone)]
+pub struct AnimatedTile {
+    pub tileset_id: TilesetId,
+    pub base_index: u32,
+}
+
+/// tile 动画的播放时钟（View 菜单暂停时停止累加，所有动画停在当前帧）。
+#[derive(Resource, Default)]
+pub struct TileAnimationClock {
+    pub elapsed_ms: u64,
+}
+
 /// 当前编辑图层（0=底层，1=上层…）。
 #[derive(Resource, Clone, Copy)]
 pub struct LayerState {
@@ -3,15 +3,11 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 use super::TilesetId;
+use tilemap_core::TileGrid;
+use tilemap_format::{animation_frame_at, AnimationFrame};
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
-pub struct TilesetEntry {
-    pub id: TilesetId,
-    pub name: String,
-    pub category: String,
-    /// 相对 `AssetPlugin.file_path` 的路径，例如：`tilesets/foo.png`
-    pub asset_path: String,
-}
+/// 地图文件与 tileset 库共用的条目定义。
+pub use tilemap_format::TilesetEntry;
 
 #[derive(Resource, Serialize, Deserialize, Clone, Default)]
 pub struct TilesetLibrary {
@@ -22,9 +18,33 @@ pub struct TilesetLibrary {
 
 #[derive(Clone)]
 pub struct TilesetRuntimeEntry {
+    /// 原始图片（palette、预览 sprite 按 `grid` 裁剪）。
     pub texture: Handle<Image>,
+    /// 给 tilemap 渲染用的图集：有 margin / spacing 时为去掉留白后紧密排列的副本，否则同 `texture`。
+    pub tilemap_texture: Handle<Image>,
+    pub grid: TileGrid,
     pub columns: u32,
     pub rows: u32,
+    /// 带动画的 tile：index -> 帧序列（取自 tileset 库条目）。
+    pub animations: HashMap<u32, Vec<AnimationFrame>>,
+}
+
+impl TilesetRuntimeEntry {
+    pub fn tile_size(&self) -> UVec2 {
+        UVec2::new(self.grid.tile_width, self.grid.tile_height)
+    }
+
+    pub fn is_animated(&self, index: u32) -> bool {
+        self.animations.contains_key(&index)
+    }
+
+    /// 播放到 `elapsed_ms` 时第 `index` 个 tile 应显示的帧；静态 tile 返回自身。
+    pub fn frame_at(&self, index: u32, elapsed_ms: u64) -> u32 {
+        self.animations
+            .get(&index)
+            .and_then(|frames| animation_frame_at(frames, elapsed_ms))
+            .unwrap_or(index)
+    }
 }
 
 #[derive(Resource, Default)]
@@ -38,7 +58,187 @@ pub struct PendingTileset {
     pub texture: Handle<Image>,
 }
 
+/// 加载失败的 tileset（文件缺失、格式不支持、尺寸小于 tile 等），等待用户重新链接。
+#[derive(Clone, Debug)]
+pub struct FailedTileset {
+    pub id: TilesetId,
+    pub asset_path: String,
+    pub error: String,
+}
+
 #[derive(Resource, Default)]
 pub struct TilesetLoading {
     pub pending: Vec<PendingTileset>,
+    /// 按 id 去重；重新加载成功后移除。
+    pub failed: Vec<FailedTileset>,
+}
+
+/// 已复制到 assets 的 tileset 图片。
+#[derive(Clone, Debug)]
+pub struct ImportedTileset {
+    /// 文件内容的 blake3（hex）。
+    pub id: TilesetId,
+    /// 相对 assets 目录的路径（`/` 分隔）。
+    pub rel: String,
+    pub name: String,
+}
+
+/// 重新链接对话框中的一项。
+#[derive(Clone, Debug)]
+pub struct RelinkItem {
+    pub id: TilesetId,
+    /// 需要重新链接的原因（加载错误、文件缺失、hash 不一致等）。
+    pub reason: String,
+    /// 已选择、但 hash 与原 id 不一致的图片：只有用户确认“重映射”后才会使用。
+    pub candidate: Option<ImportedTileset>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RelinkAction {
+    /// 选择替换图片（hash 一致则直接重新链接）。
+    Pick,
+    /// 把地图中旧 id 的格子全部改为候选图片的新 id。
+    Remap,
+    /// 放弃候选图片。
+    Discard,
+}
+
+#[derive(Resource, Default)]
+pub struct RelinkDialogState {
+    pub open: bool,
+    pub items: Vec<RelinkItem>,
+    /// 对话框内容需要重建。
+    pub rebuild: bool,
+    /// 本帧点击的按钮：(items 下标, 操作)，由对应的系统取走处理。
+    pub requested: Option<(usize, RelinkAction)>,
+}
+
+impl RelinkDialogState {
+    /// 加入（或更新原因）一项并打开对话框。
+    pub fn add(&mut self, id: &str, reason: String) {
+        match self.items.iter_mut().find(|i| i.id == id) {
+            Some(item) => item.reason = reason,
+            None => self.items.push(RelinkItem {
+                id: id.to_string(),
+                reason,
+                candidate: None,
+            }),
+        }
+        self.open = true;
+        self.rebuild = true;
+    }
 }
+
+#[derive(Component, Clone, Copy)]
+pub struct RelinkButton {
+    /// `RelinkDialogState::items` 中的下标。
+    pub index: usize,
+    pub action: RelinkAction,
+}
+
+#[derive(Component)]
+pub struct RelinkDialogRoot;
+
+#[derive(Component)]
+pub struct RelinkList;
+
+#[derive(Component)]
+pub struct RelinkCloseButton;
+
+/// tileset 切分对话框的输入框。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TilesetGridField {
+    TileWidth,
+    TileHeight,
+    Margin,
+    Spacing,
+}
+
+impl TilesetGridField {
+    pub const ALL: [Self; 4] = [Self::TileWidth, Self::TileHeight, Self::Margin, Self::Spacing];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::TileWidth => "tile 宽",
+            Self::TileHeight => "tile 高",
+            Self::Margin => "外边距",
+            Self::Spacing => "间距",
+        }
+    }
+
+    pub fn slot(self) -> usize {
+        self as usize
+    }
+}
+
+/// 导入 tileset 后询问切分参数（也可从菜单重新打开修改当前 tileset）。
+#[derive(Resource, Default)]
+pub struct TilesetGridDialogState {
+    pub open: bool,
+    pub tileset_id: Option<TilesetId>,
+    /// 输入框内容，顺序同 `TilesetGridField::ALL`。
+    pub bufs: [String; 4],
+    pub focus: Option<TilesetGridField>,
+    pub apply_requested: bool,
+}
+
+impl TilesetGridDialogState {
+    /// 以条目当前的切分参数打开对话框（未设置 tile 尺寸时填入地图网格尺寸）。
+    pub fn open_for(&mut self, entry: &TilesetEntry, grid_tile: UVec2) {
+        let grid = entry.grid((grid_tile.x, grid_tile.y));
+        self.bufs = [grid.tile_width, grid.tile_height, grid.margin, grid.spacing].map(|v| v.to_string());
+        self.tileset_id = Some(entry.id.clone());
+        self.focus = Some(TilesetGridField::TileWidth);
+        self.apply_requested = false;
+        self.open = true;
+    }
+
+    pub fn close(&mut self) {
+        self.open = false;
+        self.focus = None;
+        self.apply_requested = false;
+    }
+
+    /// 解析输入；tile 尺寸必须大于 0。
+    pub fn parsed(&self) -> Result<TileGrid, String> {
+        let mut values = [0u32; 4];
+        for field in TilesetGridField::ALL {
+            let buf = self.bufs[field.slot()].trim();
+            values[field.slot()] = buf
+                .parse()
+                .map_err(|_| format!("{} 不是有效数字: {buf:?}", field.label()))?;
+        }
+        let [tile_width, tile_height, margin, spacing] = values;
+        if tile_width == 0 || tile_height == 0 {
+            return Err("tile 尺寸必须大于 0".to_string());
+        }
+        Ok(TileGrid { tile_width, tile_height, margin, spacing })
+    }
+
+    /// 正在输入时，数字 / 退格等按键留给对话框。
+    pub fn captures_keys(&self) -> bool {
+        self.open && self.focus.is_some()
+    }
+}
+
+#[derive(Component)]
+pub struct TilesetGridDialogRoot;
+
+#[derive(Component)]
+pub struct TilesetGridTitleText;
+
+#[derive(Component, Clone, Copy)]
+pub struct TilesetGridFieldButton(pub TilesetGridField);
+
+#[derive(Component, Clone, Copy)]
+pub struct TilesetGridFieldText(pub TilesetGridField);
+
+/// 切分结果预览（列数 x 行数）或输入错误。
+#[derive(Component)]
+pub struct TilesetGridSummaryText;
+
+#[derive(Component)]
+pub struct TilesetGridApplyButton;
+
+#[derive(Component)]
+pub struct TilesetGridCancelButton;
@@ -1,5 +1,10 @@
 use bevy::prelude::*;
 
+use super::{TileBounds, TileMapData};
+
+/// 油漆桶 / 魔棒的选取方式由 `tilemap_core` 提供。
+pub use tilemap_core::ops::FillMode;
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ToolKind {
     Pencil,
@@ -9,6 +14,8 @@ pub enum ToolKind {
     Select,
     Paste,
     Eyedropper,
+    Line,
+    MagicWand,
 }
 
 impl Default for ToolKind {
@@ -17,13 +24,176 @@ impl Default for ToolKind {
     }
 }
 
+impl ToolKind {
+    /// 设置了印章时按印章图案绘制的工具。
+    pub fn uses_stamp(self) -> bool {
+        matches!(self, Self::Pencil | Self::Rect | Self::Fill | Self::Line)
+    }
+
+    /// 可以对选区做剪切 / 删除 / 移动 / 变换的工具。
+    pub fn edits_selection(self) -> bool {
+        matches!(self, Self::Select | Self::MagicWand)
+    }
+}
+
 #[derive(Resource)]
 pub struct ToolState {
     pub tool: ToolKind,
     /// 通过 Ctrl+V / 右键菜单进入粘贴时，记住进入前的工具，便于粘贴落地后自动恢复。
     pub return_after_paste: Option<ToolKind>,
+    /// 直线工具的折线模式：每次点击加一个顶点，Enter 提交、Esc 取消（再次选择直线工具切换）。
+    pub line_polyline: bool,
+    /// 矩形工具画的形状（再次选择矩形工具循环切换）。
+    pub shape: ShapeMode,
+    /// 油漆桶：4 邻接 / 8 邻接 / 整层同类（再次选择油漆桶循环切换）。
+    pub fill_mode: FillMode,
+    /// 油漆桶判断“同类”时看的图层（写入仍是当前层）。
+    pub fill_sample: SampleSource,
+    /// 吸管读取的图层。
+    pub eyedropper_sample: SampleSource,
+    /// 魔棒：4 邻接 / 8 邻接 / 整层同类（再次选择魔棒循环切换）。
+    pub wand_mode: FillMode,
+    /// 魔棒判断“同类”时看的图层。
+    pub wand_sample: SampleSource,
+}
+
+/// 油漆桶 / 吸管 / 魔棒的取样图层。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SampleSource {
+    /// 当前编辑层。
+    Active,
+    /// 所有可见图层合成：每格取最上层的非空 tile。
+    Visible,
+    /// 指定图层（0 起）。
+    Layer(u32),
+}
+
+impl SampleSource {
+    /// 循环：当前图层 → 可见图层 → 图层 1 … 图层 N → 当前图层。
+    pub fn next(self, layers: u32) -> Self {
+        match self {
+            Self::Active => Self::Visible,
+            Self::Visible if layers > 0 => Self::Layer(0),
+            Self::Layer(l) if l + 1 < layers => Self::Layer(l + 1),
+            _ => Self::Active,
+        }
+    }
+
+    pub fn label(self) -> String {
+        match self {
+            Self::Active => "当前图层".to_string(),
+            Self::Visible => "可见图层".to_string(),
+            Self::Layer(l) => format!("图层 {}", l + 1),
+        }
+    }
+
+    /// 从下到上排列的取样图层；指定图层越界时取最上层。
+    pub fn layers(self, map: &TileMapData, active: u32) -> Vec<u32> {
+        let top = map.layers.saturating_sub(1);
+        match self {
+            Self::Active => vec![active.min(top)],
+            Self::Visible => (0..map.layers)
+                .filter(|&l| map.layer_data.get(l as usize).is_none_or(|d| d.visible))
+                .collect(),
+            Self::Layer(l) => vec![l.min(top)],
+        }
+    }
+}
+
+/// 矩形工具的形状。
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ShapeMode {
+    #[default]
+    Rect,
+    RectOutline,
+    Ellipse,
+    EllipseOutline,
 }
 
+impl ShapeMode {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Rect => Self::RectOutline,
+            Self::RectOutline => Self::Ellipse,
+            Self::Ellipse => Self::EllipseOutline,
+            Self::EllipseOutline => Self::Rect,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Rect => "实心矩形",
+            Self::RectOutline => "矩形边框",
+            Self::Ellipse => "实心椭圆",
+            Self::EllipseOutline => "椭圆边框",
+        }
+    }
+}
+
+/// HUD 上油漆桶 / 魔棒模式的说明。
+pub fn fill_mode_label(mode: FillMode) -> &'static str {
+    match mode {
+        FillMode::Contiguous => "连通（4 邻接）",
+        FillMode::Diagonal => "连通（8 邻接）",
+        FillMode::Global => "整层同类（不要求相连）",
+    }
+}
+
+impl ToolState {
+    /// 选择工具；已是直线 / 矩形 / 油漆桶 / 魔棒工具时再次选择切换两点 / 折线模式、循环形状或选取方式。
+    pub fn pick(&mut self, tool: ToolKind) {
+        if tool == self.tool {
+            match tool {
+                ToolKind::Line => self.line_polyline = !self.line_polyline,
+                ToolKind::Rect => self.shape = self.shape.next(),
+                ToolKind::Fill => self.fill_mode = self.fill_mode.next(),
+                ToolKind::MagicWand => self.wand_mode = self.wand_mode.next(),
+                _ => {}
+            }
+        }
+        self.tool = tool;
+    }
+
+    /// 当前工具的取样设置（只有油漆桶、吸管与魔棒有）。
+    pub fn sample_mut(&mut self) -> Option<&mut SampleSource> {
+        match self.tool {
+            ToolKind::Fill => Some(&mut self.fill_sample),
+            ToolKind::Eyedropper => Some(&mut self.eyedropper_sample),
+            ToolKind::MagicWand => Some(&mut self.wand_sample),
+            _ => None,
+        }
+    }
+
+    pub fn sample(&self) -> Option<SampleSource> {
+        match self.tool {
+            ToolKind::Fill => Some(self.fill_sample),
+            ToolKind::Eyedropper => Some(self.eyedropper_sample),
+            ToolKind::MagicWand => Some(self.wand_sample),
+            _ => None,
+        }
+    }
+}
+
+/// 直线工具进行中的顶点。
+#[derive(Resource, Default)]
+pub struct LineToolState {
+    /// 顶点所属的模式；模式切换时丢弃未提交的顶点。
+    pub polyline: bool,
+    pub vertices: Vec<IVec2>,
+}
+
+/// 直线 / 矩形工具将要绘制的格子（已裁掉图外格子）与幽灵预览 sprite 池。
+#[derive(Resource, Default)]
+pub struct ShapePreview {
+    pub cells: Vec<IVec2>,
+    /// 矩形工具拖拽中的外接框（画蓝色预览框）。
+    pub frame: Option<TileBounds>,
+    pub entities: Vec<Entity>,
+}
+
+#[derive(Component)]
+pub struct ShapePreviewTile;
+
 /// 笔刷设置（P1）：目前仅支持方形尺寸 1/2/3。
 #[derive(Resource, Clone, Copy)]
 pub struct BrushSettings {
@@ -41,6 +211,13 @@ impl Default for ToolState {
         Self {
             tool: ToolKind::default(),
             return_after_paste: None,
+            line_polyline: false,
+            shape: ShapeMode::Rect,
+            fill_mode: FillMode::Contiguous,
+            fill_sample: SampleSource::Active,
+            eyedropper_sample: SampleSource::Visible,
+            wand_mode: FillMode::Contiguous,
+            wand_sample: SampleSource::Active,
         }
     }
 }
@@ -65,6 +242,13 @@ pub struct ShiftMapSettings {
 #[derive(Component, Clone, Copy)]
 pub struct ToolButton(pub ToolKind);
 
+/// 工具栏“取样”按钮：循环当前工具（油漆桶 / 吸管 / 魔棒）的取样图层。
+#[derive(Component)]
+pub struct SampleSourceButton;
+
+#[derive(Component)]
+pub struct SampleSourceLabel;
+
 #[derive(Component)]
 pub struct ShiftModeButton;
 
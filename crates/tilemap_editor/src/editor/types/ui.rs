@@ -23,6 +23,12 @@ pub struct PaletteTileButton {
     pub index: u32,
 }
 
+/// palette 按钮里的缩略图（带动画的 tile 按帧切换裁剪区域）。
+#[derive(Component)]
+pub struct PaletteTileImage {
+    pub index: u32,
+}
+
 #[derive(Component)]
 pub struct CanvasRoot;
 
@@ -57,20 +63,58 @@ pub enum ActionKind {
     NewMap,
     Undo,
     Redo,
+    /// 打开替换 Tile 对话框（源 tile 预设为 palette 选中的 tile）。
+    OpenReplaceTile,
     /// 切换地图尺寸（会重建格子实体）。
     SetMapSize { width: u32, height: u32 },
     /// 从文件导入地图（文件选择器）。
     ImportMap,
     /// 导出地图到文件（文件选择器）。
     ExportMap,
+    /// 把地图合成为 PNG 图片（文件选择器）。
+    ExportImage,
     /// 切换网格显示。
     ToggleGrid,
     /// 切换 hover 高亮显示。
     ToggleHover,
     /// 切换 HUD 坐标显示。
     ToggleCursor,
+    /// 打开/关闭 Problems 面板（地图检查结果）。
+    ToggleProblems,
+    /// 打开“重新链接 tileset”对话框（列出加载失败的 tileset）。
+    OpenRelinkDialog,
+    /// 编辑当前 tileset 的切分参数（tile 尺寸 / 外边距 / 间距）。
+    EditTilesetGrid,
+    /// 编辑当前选中 tile 的动画（点击 palette 追加帧）。
+    EditTileAnimation,
+    /// 编辑当前选中 tile 的碰撞形状。
+    EditTileCollision,
+    /// 把当前 tileset 从选中 tile 起的 47 块登记为一组自动图块。
+    DefineAutotile,
+    /// 以选中 tile 为左上角，把 RPG Maker A2 块烘焙成新的自动图块 tileset。
+    ImportRmA2Autotile,
+    /// 从 Tiled tileset 文件导入 Wang 集到当前 tileset。
+    ImportWangSets,
+    /// 用 palette 选中的块（或单个 tile）新建一个随机变体集。
+    DefineVariationSet,
+    /// 把选中 tile 加入当前变体集，或循环它的权重（1 → 2 → 4 → 0.5 → 移出）。
+    CycleVariationWeight,
+    /// 删除当前变体集。
+    DeleteVariationSet,
+    /// 播放 / 暂停 tile 动画。
+    ToggleTileAnimations,
+    /// 切换画布上的碰撞形状叠加显示。
+    ToggleCollision,
 	/// Shift Map 模式 Blank <-> Wrap。
 	ToggleShiftMode,
+    /// 固定尺寸地图 <-> 无限地图（会重建格子实体并清空撤销栈）。
+    ToggleInfiniteMap,
+    /// 载入自动映射规则文件（RON 地图）。
+    LoadAutomapRules,
+    /// 对整张地图（有选区时只对选区）应用规则。
+    ApplyAutomapRules,
+    /// 绘制后自动应用规则开关。
+    ToggleAutomapAutoRun,
 }
 
 #[derive(Component)]
@@ -105,6 +149,10 @@ pub struct MenuBackdrop;
 #[derive(Component)]
 pub struct MenuItem;
 
+/// 悬浮在画布上的面板（及其按钮）：按下时吞掉这次左键，避免同时在画布上绘制。
+#[derive(Component)]
+pub struct CanvasClickBlocker;
+
 #[derive(Resource)]
 pub struct UiState {
     pub built_for_tileset_path: String,
@@ -1,17 +1,14 @@
 use bevy::prelude::*;
 
-use super::TileRef;
-
-#[derive(Clone, Debug)]
-pub struct CellChange {
-    pub idx: usize,
-    pub before: Option<TileRef>,
-    pub after: Option<TileRef>,
-}
+/// 单格变更由 `tilemap_core::ops` 定义；slot 指向当前地图的 tileset 表（换图时撤销栈会被清空）。
+pub use tilemap_core::ops::CellChange;
+pub use tilemap_core::MetaChange;
 
 #[derive(Clone, Debug, Default)]
 pub struct EditCommand {
     pub changes: Vec<CellChange>,
+    /// 元数据模式下的区域 / 通行 / 地形变更。
+    pub meta: Vec<MetaChange>,
 }
 
 #[derive(Resource, Default)]
@@ -19,6 +16,8 @@ pub struct UndoStack {
     pub undo: Vec<EditCommand>,
     pub redo: Vec<EditCommand>,
     pub max_len: usize,
+    /// 每次 push 新命令加一（撤销 / 重做不变），供“绘制后自动处理”的系统判断是否有新的编辑。
+    pub revision: u64,
 }
 
 impl UndoStack {
@@ -28,11 +27,12 @@ impl UndoStack {
     }
 
     pub fn push(&mut self, cmd: EditCommand) {
-        if cmd.changes.is_empty() {
+        if cmd.changes.is_empty() && cmd.meta.is_empty() {
             return;
         }
         self.redo.clear();
         self.undo.push(cmd);
+        self.revision += 1;
         let max_len = if self.max_len == 0 { 200 } else { self.max_len };
         if self.undo.len() > max_len {
             let drain = self.undo.len() - max_len;
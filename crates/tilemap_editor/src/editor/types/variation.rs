@@ -0,0 +1,114 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+
+use tilemap_core::TileRng;
+
+use super::{TilesetId, TilesetLibrary};
+
+/// 变体集定义与随机挑选由 `tilemap_core` 提供（变体集随 tileset 库保存）。
+pub use tilemap_core::{Variation, VariationSet};
+
+/// 随机笔刷：开启后铅笔 / 矩形 / 油漆桶为每个格子从当前变体集按权重挑 tile（可选随机旋转 / 翻转）。
+///
+/// 元数据、印章、自动图块、Wang 地形优先；橡皮与 Shift 仍按普通方式擦除。
+#[derive(Resource)]
+pub struct VariationBrushState {
+    pub enabled: bool,
+    /// 当前 tileset 中的变体集下标。
+    pub set: usize,
+    pub rng: TileRng,
+}
+
+impl Default for VariationBrushState {
+    fn default() -> Self {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0);
+        Self {
+            enabled: false,
+            set: 0,
+            rng: TileRng::new(seed),
+        }
+    }
+}
+
+/// 顶栏随机笔刷按钮的动作；旋转 / 翻转开关写在当前变体集上（随 tileset 库保存）。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VariationBrushAction {
+    ToggleMode,
+    CycleSet,
+    ToggleRotation,
+    ToggleFlip,
+}
+
+impl VariationBrushState {
+    /// 模式开启且当前 tileset 有非空变体集时返回 (tileset id, 变体集)。
+    pub fn active(&self, lib: &TilesetLibrary) -> Option<(TilesetId, VariationSet)> {
+        if !self.enabled {
+            return None;
+        }
+        active_variation_set(lib, self.set).filter(|(_, set)| !set.tiles.is_empty())
+    }
+
+    /// 执行按钮动作；改动了 tileset 库（旋转 / 翻转开关）时返回 true，调用方负责保存。
+    pub fn apply(&mut self, action: VariationBrushAction, lib: &mut TilesetLibrary) -> bool {
+        let count = active_variation_sets(lib).len();
+        let index = self.set.min(count.saturating_sub(1));
+        let set = active_variation_sets_mut(lib).and_then(|sets| sets.get_mut(index));
+        match (action, set) {
+            (VariationBrushAction::ToggleMode, _) => self.enabled = !self.enabled,
+            (VariationBrushAction::CycleSet, _) => self.set = (self.set + 1) % count.max(1),
+            (VariationBrushAction::ToggleRotation, Some(set)) => {
+                set.random_rotation = !set.random_rotation;
+                return true;
+            }
+            (VariationBrushAction::ToggleFlip, Some(set)) => {
+                set.random_flip = !set.random_flip;
+                return true;
+            }
+            _ => {}
+        }
+        false
+    }
+
+    /// 顶栏上的简短说明：关 / `集合名 (tile 数)` / 无（当前 tileset 没有变体集）。
+    pub fn label(&self, lib: &TilesetLibrary) -> String {
+        if !self.enabled {
+            return "关".to_string();
+        }
+        match self.active(lib) {
+            Some((_, set)) => format!("{} ({})", set.name, set.tiles.len()),
+            None => "无".to_string(),
+        }
+    }
+}
+
+fn active_variation_sets(lib: &TilesetLibrary) -> &[VariationSet] {
+    lib.active_id
+        .as_ref()
+        .and_then(|id| lib.entries.iter().find(|e| e.id == *id))
+        .map_or(&[], |e| e.variations.as_slice())
+}
+
+/// 当前 tileset 的变体集列表（可写）；没有当前 tileset 时返回 `None`。
+pub fn active_variation_sets_mut(lib: &mut TilesetLibrary) -> Option<&mut Vec<VariationSet>> {
+    let id = lib.active_id.clone()?;
+    lib.entries.iter_mut().find(|e| e.id == id).map(|e| &mut e.variations)
+}
+
+/// 当前 tileset 的第 `index` 个变体集（越界时取最后一个，不看模式开关）。
+pub fn active_variation_set(lib: &TilesetLibrary, index: usize) -> Option<(TilesetId, VariationSet)> {
+    let id = lib.active_id.as_ref()?;
+    let sets = active_variation_sets(lib);
+    let set = sets.get(index).or_else(|| sets.last())?;
+    Some((id.clone(), set.clone()))
+}
+
+/// 顶栏的随机笔刷按钮。
+#[derive(Component)]
+pub struct VariationBrushButton(pub VariationBrushAction);
+
+#[derive(Component)]
+pub struct VariationBrushLabel;
@@ -0,0 +1,109 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+
+use super::{TilesetId, TilesetLibrary};
+
+/// Wang 集定义、tile 求解与随机数由 `tilemap_core` 提供（Wang 集随 tileset 库保存）。
+pub use tilemap_core::{TileRng, WangSet};
+
+/// Wang 地形模式：开启后铅笔 / 矩形 / 油漆桶用当前颜色涂角 / 边，并为格子与邻居挑选匹配的 tile。
+///
+/// 元数据、自动图块模式优先；橡皮与 Shift 仍按普通方式擦除。
+#[derive(Resource)]
+pub struct WangBrushState {
+    pub enabled: bool,
+    /// 当前 tileset 中的 Wang 集下标。
+    pub set: usize,
+    /// 颜色编号（1 起）。
+    pub color: u8,
+    /// 等价候选之间的随机挑选。
+    pub rng: TileRng,
+}
+
+impl Default for WangBrushState {
+    fn default() -> Self {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0);
+        Self {
+            enabled: false,
+            set: 0,
+            color: 1,
+            rng: TileRng::new(seed),
+        }
+    }
+}
+
+/// 顶栏 Wang 地形按钮的动作。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WangBrushAction {
+    ToggleMode,
+    CycleSet,
+    CycleColor,
+}
+
+impl WangBrushState {
+    /// 模式开启且当前 tileset 有 Wang 集时返回 (tileset id, 集合, 颜色)。
+    pub fn active(&self, lib: &TilesetLibrary) -> Option<(TilesetId, WangSet, u8)> {
+        if !self.enabled {
+            return None;
+        }
+        let (id, set) = active_wangset(lib, self.set)?;
+        let color = self.color.clamp(1, set.colors.len().clamp(1, u8::MAX as usize) as u8);
+        Some((id, set, color))
+    }
+
+    /// 切换集合时颜色回到 1；颜色在当前集合内循环。
+    pub fn apply(&mut self, action: WangBrushAction, lib: &TilesetLibrary) {
+        let sets = active_wangsets(lib);
+        match action {
+            WangBrushAction::ToggleMode => self.enabled = !self.enabled,
+            WangBrushAction::CycleSet => {
+                self.set = (self.set + 1) % sets.len().max(1);
+                self.color = 1;
+            }
+            WangBrushAction::CycleColor => {
+                let colors = sets.get(self.set).map_or(0, |s| s.colors.len()).min(u8::MAX as usize);
+                self.color = if (self.color as usize) < colors { self.color + 1 } else { 1 };
+            }
+        }
+    }
+
+    /// 顶栏上的简短说明：关 / `集合: 颜色` / 无（当前 tileset 没有 Wang 集）。
+    pub fn label(&self, lib: &TilesetLibrary) -> String {
+        if !self.enabled {
+            return "关".to_string();
+        }
+        match self.active(lib) {
+            Some((_, set, color)) => {
+                let name = set.color(color).map_or("?", |c| c.name.as_str());
+                format!("{}: {name}", set.name)
+            }
+            None => "无".to_string(),
+        }
+    }
+}
+
+fn active_wangsets(lib: &TilesetLibrary) -> &[WangSet] {
+    lib.active_id
+        .as_ref()
+        .and_then(|id| lib.entries.iter().find(|e| e.id == *id))
+        .map_or(&[], |e| e.wangsets.as_slice())
+}
+
+/// 当前 tileset 的第 `index` 个 Wang 集（越界时取第一个，不看模式开关）。
+pub fn active_wangset(lib: &TilesetLibrary, index: usize) -> Option<(TilesetId, WangSet)> {
+    let id = lib.active_id.as_ref()?;
+    let sets = active_wangsets(lib);
+    let set = sets.get(index).or_else(|| sets.first())?;
+    Some((id.clone(), set.clone()))
+}
+
+/// 顶栏的 Wang 地形按钮。
+#[derive(Component)]
+pub struct WangBrushButton(pub WangBrushAction);
+
+#[derive(Component)]
+pub struct WangBrushLabel;
@@ -12,7 +12,13 @@ mod layers;
 mod map_size;
 mod menubar;
 mod palette;
+mod problems;
+mod relink;
+mod replace_tile;
 mod root;
+mod tile_animation;
+mod tile_collision;
+mod tileset_grid;
 mod tileset_menu;
 mod tools;
 mod util;
@@ -40,18 +46,41 @@ pub use menubar::{
 };
 
 pub use palette::{
-	build_palette_when_ready, palette_scroll_wheel, palette_tile_click,
+	animate_palette_tiles, build_palette_when_ready, palette_scroll_wheel, palette_tile_click,
 	palette_clamp_scroll_position, palette_apply_scroll_position_to_root,
 	palette_search_text_input, palette_search_widget_interactions, palette_zoom_button_click,
-	sync_palette_zoom_button_styles, update_palette_search_text,
+	sync_palette_tile_styles, sync_palette_zoom_button_styles, update_palette_search_text,
 };
+pub use problems::{
+	problems_panel_button_click, problems_panel_visibility, rebuild_problems_panel_when_needed,
+	refresh_map_problems,
+};
+pub use relink::{
+	apply_relink_pick, apply_relink_remap, canvas_blockers_consume_clicks, rebuild_relink_dialog_when_needed,
+	relink_dialog_button_click, relink_dialog_visibility, sync_failed_tilesets_into_dialog,
+};
+pub use replace_tile::{replace_tile_dialog_button_click, update_replace_tile_dialog};
 pub use root::setup_ui;
+pub use tile_animation::{
+	animate_tile_animation_preview, rebuild_tile_animation_frames_when_needed,
+	tile_animation_editor_button_click, tile_animation_editor_visibility,
+};
+pub use tile_collision::{
+	rebuild_tile_collision_editor_when_needed, tile_collision_editor_button_click, tile_collision_editor_drag,
+	tile_collision_editor_visibility,
+};
+pub use tileset_grid::{
+	apply_tileset_grid, tileset_grid_dialog_interactions, tileset_grid_text_input, update_tileset_grid_dialog,
+};
 pub use tileset_menu::{
 	rebuild_tileset_menu_when_needed, tileset_category_cycle_click, tileset_menu_item_click,
 	tileset_menu_visibility, tileset_toggle_button_click, update_tileset_active_label,
 	update_tileset_category_label,
 };
 pub use tools::{
-	brush_size_button_click, shift_mode_button_click, sync_brush_size_button_styles,
-	sync_tool_button_styles, tool_button_click, update_shift_mode_label,
+	autotile_button_click, brush_size_button_click, meta_brush_button_click, sample_source_button_click,
+	shift_mode_button_click, sync_sample_source_button,
+	sync_autotile_styles, sync_brush_size_button_styles, sync_meta_brush_styles, sync_tool_button_styles,
+	sync_variation_brush_styles, sync_wang_brush_styles, tool_button_click, update_shift_mode_label,
+	variation_brush_button_click, wang_brush_button_click,
 };
@@ -1,19 +1,38 @@
 //! 左侧工具栏 ActionButton 的点击处理（打开/新建/保存/读取/导入/导出等）。
 
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 use bevy_ecs_tilemap::prelude::TilemapId;
+use tilemap_render::RenderOptions;
 
-use crate::editor::persistence::{load_map_from_file, save_map_to_file};
-use crate::editor::tileset::{merge_tilesets_from_map, open_tileset_impl, save_tileset_library};
+use crate::editor::persistence::{export_map_image, load_automap_rules_from_file, load_map_from_file, save_map_to_file};
+use crate::editor::tileset::{
+    cycle_variation_weight, define_autotile, define_variation_set, delete_variation_set, import_rm_a2_autotile, import_tiled_wangsets, merge_tilesets_from_map, open_tileset_and_ask_grid, save_tileset_library,
+};
 use crate::editor::types::{
-    ActionButton, ActionKind, EditorConfig, TileMapData, TilesetLibrary, TilesetLoading,
-    ShiftMapMode, ShiftMapSettings, UiState, UndoStack,
+    ActionButton, ActionKind, AutomapState, AutotileBrushState, EditorConfig, EditorState, ProblemsState, RelinkDialogState, ReplaceTileDialogState, TileMapData,
+    TileAnimationEditorState, TileCollisionEditorState, TilesetGridDialogState, TilesetLibrary, TilesetLoading, TilesetRuntime, ShiftMapMode,
+    SelectionState, ShiftMapSettings, UiState, TileRef, UndoStack, VariationBrushState, WangBrushState,
 };
 use crate::editor::util::despawn_silently;
-use crate::editor::world::{apply_tile_change, rebuild_tilemaps, TilemapRenderParams};
+use crate::editor::world::{rebuild_tilemaps, redo_once, run_automap, undo_once, TilemapRenderParams};
 use crate::editor::{UI_BUTTON, UI_BUTTON_HOVER, UI_BUTTON_PRESS};
 
-use super::util::resized_map_copy;
+use super::util::{resized_map_copy, respawn_map_render};
+
+/// 按钮处理中用到的 tile 编辑器 / 笔刷模式 / 自动映射状态（打包以控制系统参数个数）。
+#[derive(SystemParam)]
+pub struct TileEditorParams<'w> {
+    animation_editor: ResMut<'w, TileAnimationEditorState>,
+    collision_editor: ResMut<'w, TileCollisionEditorState>,
+    autotile: ResMut<'w, AutotileBrushState>,
+    wang: ResMut<'w, WangBrushState>,
+    variation: ResMut<'w, VariationBrushState>,
+    automap: ResMut<'w, AutomapState>,
+    replace: ResMut<'w, ReplaceTileDialogState>,
+    selection: Res<'w, SelectionState>,
+    runtime: Res<'w, TilesetRuntime>,
+}
 
 /// 左侧工具栏按钮点击处理。
 pub fn action_button_click(
@@ -28,7 +47,23 @@ pub fn action_button_click(
     tile_q: Query<Entity, With<TilemapId>>,
     map: Option<ResMut<TileMapData>>,
     mut undo: ResMut<UndoStack>,
+    mut problems: ResMut<ProblemsState>,
+    mut relink: ResMut<RelinkDialogState>,
+    mut grid_dialog: ResMut<TilesetGridDialogState>,
+    editors: TileEditorParams,
+    editor_state: Res<EditorState>,
 ) {
+    let TileEditorParams {
+        mut animation_editor,
+        mut collision_editor,
+        mut autotile,
+        mut wang,
+        mut variation,
+        mut automap,
+        mut replace,
+        selection,
+        runtime,
+    } = editors;
     let mut requested: Option<ActionKind> = None;
 
     for (interaction, action, mut bg) in action_q.iter_mut() {
@@ -55,41 +90,13 @@ pub fn action_button_click(
             let Some(mut map) = map else {
                 return;
             };
-            let Some(cmd) = undo.undo.pop() else {
-                return;
-            };
-            let layer_len = map.layer_len();
-            for ch in &cmd.changes {
-                if ch.idx < map.tiles.len() {
-                    map.tiles[ch.idx] = ch.before.clone();
-                    let layer = (ch.idx / layer_len) as u32;
-                    let local = ch.idx % layer_len;
-                    let x = (local % map.width as usize) as u32;
-                    let y = (local / map.width as usize) as u32;
-                    apply_tile_change(&mut render, &config, layer, x, y, &ch.after, &ch.before);
-                }
-            }
-            undo.redo.push(cmd);
+            undo_once(&mut map, &mut render, &config, &mut undo);
         }
         ActionKind::Redo => {
             let Some(mut map) = map else {
                 return;
             };
-            let Some(cmd) = undo.redo.pop() else {
-                return;
-            };
-            let layer_len = map.layer_len();
-            for ch in &cmd.changes {
-                if ch.idx < map.tiles.len() {
-                    map.tiles[ch.idx] = ch.after.clone();
-                    let layer = (ch.idx / layer_len) as u32;
-                    let local = ch.idx % layer_len;
-                    let x = (local % map.width as usize) as u32;
-                    let y = (local / map.width as usize) as u32;
-                    apply_tile_change(&mut render, &config, layer, x, y, &ch.before, &ch.after);
-                }
-            }
-            undo.undo.push(cmd);
+            redo_once(&mut map, &mut render, &config, &mut undo);
         }
         ActionKind::ToggleGrid => {
             config.show_grid = !config.show_grid;
@@ -100,6 +107,116 @@ pub fn action_button_click(
         ActionKind::ToggleCursor => {
             config.show_cursor = !config.show_cursor;
         }
+        ActionKind::ToggleTileAnimations => {
+            config.play_animations = !config.play_animations;
+        }
+        ActionKind::ToggleCollision => {
+            config.show_collision = !config.show_collision;
+        }
+        ActionKind::ToggleProblems => {
+            problems.open = !problems.open;
+            problems.dirty = problems.open;
+        }
+        ActionKind::OpenReplaceTile => {
+            let selected = lib.active_id.clone().map(|tileset_id| TileRef {
+                tileset_id,
+                index: editor_state.selected_tile,
+                rot: 0,
+                flip_x: false,
+                flip_y: false,
+            });
+            replace.open_with(selected);
+        }
+        ActionKind::OpenRelinkDialog => {
+            relink.open = true;
+            relink.rebuild = true;
+        }
+        ActionKind::EditTilesetGrid => {
+            let active = lib.active_id.as_ref().and_then(|id| lib.entries.iter().find(|e| e.id == *id));
+            match active {
+                Some(entry) => grid_dialog.open_for(entry, config.tile_size),
+                None => warn!("no active tileset to edit"),
+            }
+        }
+        ActionKind::EditTileAnimation => {
+            let active = lib.active_id.as_ref().and_then(|id| lib.entries.iter().find(|e| e.id == *id));
+            match active {
+                Some(entry) => animation_editor.open_for(entry, editor_state.selected_tile),
+                None => warn!("no active tileset to animate"),
+            }
+        }
+        ActionKind::EditTileCollision => {
+            let active = lib.active_id.as_ref().and_then(|id| lib.entries.iter().find(|e| e.id == *id));
+            match active {
+                Some(entry) => collision_editor.open_for(entry, editor_state.selected_tile),
+                None => warn!("no active tileset to edit collision for"),
+            }
+        }
+        ActionKind::DefineAutotile => match define_autotile(&mut lib, &runtime, editor_state.selected_tile) {
+            Ok(def) => {
+                info!("defined autotile: {} (tiles {}..)", def.name, def.first);
+                save_tileset_library(&lib);
+                autotile.enabled = true;
+            }
+            Err(err) => warn!("define autotile failed: {err}"),
+        },
+        ActionKind::ImportRmA2Autotile => {
+            match import_rm_a2_autotile(&asset_server, &config, &mut lib, &mut tileset_loading, editor_state.selected_tile) {
+                Ok(id) => {
+                    info!("baked RPG Maker A2 autotile into tileset {id}");
+                    save_tileset_library(&lib);
+                    ui_state.built_for_tileset_path.clear();
+                    autotile.enabled = true;
+                }
+                Err(err) => warn!("A2 autotile import failed: {err}"),
+            }
+        }
+        ActionKind::ImportWangSets => {
+            let Some(path) = rfd::FileDialog::new()
+                .add_filter("Tiled tileset", &["tsj", "json"])
+                .pick_file()
+            else {
+                return;
+            };
+            match import_tiled_wangsets(&mut lib, &path) {
+                Ok(count) => {
+                    info!("imported {count} wang sets from {}", path.display());
+                    save_tileset_library(&lib);
+                    wang.set = 0;
+                    wang.color = 1;
+                    wang.enabled = true;
+                }
+                Err(err) => warn!("wang set import failed: {err}"),
+            }
+        }
+        ActionKind::DefineVariationSet => match define_variation_set(&mut lib, &editor_state) {
+            Ok(index) => {
+                info!("defined variation set #{index}");
+                save_tileset_library(&lib);
+                variation.set = index;
+                variation.enabled = true;
+            }
+            Err(err) => warn!("define variation set failed: {err}"),
+        },
+        ActionKind::CycleVariationWeight => {
+            match cycle_variation_weight(&mut lib, variation.set, editor_state.selected_tile) {
+                Ok(Some(weight)) => info!("variation tile {} weight: {weight}", editor_state.selected_tile),
+                Ok(None) => info!("removed tile {} from the variation set", editor_state.selected_tile),
+                Err(err) => {
+                    warn!("variation weight change failed: {err}");
+                    return;
+                }
+            }
+            save_tileset_library(&lib);
+        }
+        ActionKind::DeleteVariationSet => match delete_variation_set(&mut lib, variation.set) {
+            Ok(name) => {
+                info!("deleted variation set {name}");
+                save_tileset_library(&lib);
+                variation.set = variation.set.saturating_sub(1);
+            }
+            Err(err) => warn!("delete variation set failed: {err}"),
+        },
         ActionKind::ToggleShiftMode => {
             shift.mode = match shift.mode {
                 ShiftMapMode::Blank => ShiftMapMode::Wrap,
@@ -107,7 +224,7 @@ pub fn action_button_click(
             };
         }
         ActionKind::OpenTileset => {
-            open_tileset_impl(&asset_server, &mut config, &mut lib, &mut tileset_loading);
+            open_tileset_and_ask_grid(&asset_server, &mut config, &mut lib, &mut tileset_loading, &mut grid_dialog);
             save_tileset_library(&lib);
             ui_state.built_for_tileset_path.clear();
         }
@@ -135,7 +252,12 @@ pub fn action_button_click(
             undo.clear();
 
             // 尺寸变化：更新 config + 重建格子实体
-            if config.map_size.x != loaded.width || config.map_size.y != loaded.height {
+            let needs_respawn = render
+                .tile_entities
+                .as_deref()
+                .map(|te| !te.matches_map(&loaded))
+                .unwrap_or(true);
+            if needs_respawn {
                 if let Some(existing_tiles) = render.tile_entities.as_deref() {
                     for e in existing_tiles.all_tilemap_entities() {
                         despawn_silently(&mut render.commands, e);
@@ -145,12 +267,11 @@ pub fn action_button_click(
                     despawn_silently(&mut render.commands, e);
                 }
 
-                config.map_size = UVec2::new(loaded.width, loaded.height);
-                let mut tiles = crate::editor::tileset::spawn_map_entities_with_layers(
-                    &mut render.commands,
-                    &config,
-                    loaded.layers,
-                );
+                if let Some((w, h)) = loaded.fixed_size() {
+                    config.map_size = UVec2::new(w, h);
+                }
+                let mut tiles =
+                    crate::editor::tileset::spawn_map_entities(&mut render.commands, &config, &loaded);
                 render.commands.insert_resource(loaded.clone());
                 {
                     let TilemapRenderParams {
@@ -198,7 +319,14 @@ pub fn action_button_click(
         }
         ActionKind::NewMap => {
             if let Some(mut map) = map {
-                *map = TileMapData::new(map.width, map.height);
+                // 新建时保持当前模式：无限地图仍为无限地图。
+                *map = if map.infinite {
+                    TileMapData::new_infinite(map.layers)
+                } else {
+                    TileMapData::new(map.width, map.height)
+                };
+                // 撤销栈里的格子引用旧地图的 tileset 表，不能跨地图使用。
+                undo.clear();
                 let TilemapRenderParams {
                     commands,
                     tile_entities,
@@ -231,39 +359,57 @@ pub fn action_button_click(
             render.commands.insert_resource(new_map.clone());
             undo.clear();
 
-            // 重建格子实体
-            if let Some(existing_tiles) = render.tile_entities.as_deref() {
-                for e in existing_tiles.all_tilemap_entities() {
-                    despawn_silently(&mut render.commands, e);
-                }
+            respawn_map_render(&mut render, &tile_q, &config, &new_map);
+        }
+        ActionKind::ToggleInfiniteMap => {
+            let Some(old_map) = map.as_deref() else {
+                return;
+            };
+            // 无限 -> 固定：尺寸至少为当前 config.map_size，并覆盖全部已绘制格子。
+            let new_map = if old_map.infinite {
+                old_map.to_fixed(config.map_size.x, config.map_size.y).0
+            } else {
+                old_map.to_infinite()
+            };
+            if let Some((w, h)) = new_map.fixed_size() {
+                config.map_size = UVec2::new(w, h);
             }
-            for e in tile_q.iter() {
-                despawn_silently(&mut render.commands, e);
+            render.commands.insert_resource(new_map.clone());
+            undo.clear();
+            respawn_map_render(&mut render, &tile_q, &config, &new_map);
+        }
+        ActionKind::LoadAutomapRules => {
+            let Some(path) = rfd::FileDialog::new()
+                .add_filter("RON", &["ron"])
+                .pick_file()
+            else {
+                return;
+            };
+            let path = path.to_string_lossy().to_string();
+            match load_automap_rules_from_file(&path) {
+                Ok(rules) => {
+                    info!("loaded {} automap rules from {path}", rules.len());
+                    automap.rules = rules;
+                    automap.source = Some(path);
+                }
+                Err(err) => warn!("automap rules load failed: {err}"),
             }
-            let mut tiles = crate::editor::tileset::spawn_map_entities_with_layers(
-                &mut render.commands,
-                &config,
-                new_map.layers,
-            );
-            {
-                let TilemapRenderParams {
-                    commands,
-                    tile_entities: _,
-                    runtime,
-                    tile_storage_q,
-                    ..
-                } = &mut render;
-                rebuild_tilemaps(
-                    commands,
-                    &tile_q,
-                    runtime,
-                    &new_map,
-                    &mut tiles,
-                    tile_storage_q,
-                    &config,
-                );
+        }
+        ActionKind::ApplyAutomapRules => {
+            if automap.rules.is_empty() {
+                warn!("no automap rules loaded");
+                return;
             }
-            render.commands.insert_resource(tiles);
+            let Some(mut map) = map else {
+                return;
+            };
+            let bounds = selection.rect.map(|r| r.bounds());
+            let count = run_automap(&mut map, &mut render, &config, &mut undo, &automap, bounds);
+            info!("automap changed {count} cells");
+        }
+        ActionKind::ToggleAutomapAutoRun => {
+            automap.auto_run = !automap.auto_run;
+            info!("automap auto-run: {}", automap.auto_run);
         }
         ActionKind::ImportMap => {
             let Some(path) = rfd::FileDialog::new()
@@ -286,7 +432,12 @@ pub fn action_button_click(
             undo.clear();
 
             // 尺寸变化：更新 config + 重建格子实体
-            if config.map_size.x != loaded.width || config.map_size.y != loaded.height {
+            let needs_respawn = render
+                .tile_entities
+                .as_deref()
+                .map(|te| !te.matches_map(&loaded))
+                .unwrap_or(true);
+            if needs_respawn {
                 if let Some(existing_tiles) = render.tile_entities.as_deref() {
                     for e in existing_tiles.all_tilemap_entities() {
                         despawn_silently(&mut render.commands, e);
@@ -296,12 +447,11 @@ pub fn action_button_click(
                     despawn_silently(&mut render.commands, e);
                 }
 
-                config.map_size = UVec2::new(loaded.width, loaded.height);
-                let mut tiles = crate::editor::tileset::spawn_map_entities_with_layers(
-                    &mut render.commands,
-                    &config,
-                    loaded.layers,
-                );
+                if let Some((w, h)) = loaded.fixed_size() {
+                    config.map_size = UVec2::new(w, h);
+                }
+                let mut tiles =
+                    crate::editor::tileset::spawn_map_entities(&mut render.commands, &config, &loaded);
                 render.commands.insert_resource(loaded.clone());
                 {
                     let TilemapRenderParams {
@@ -367,5 +517,31 @@ pub fn action_button_click(
                 info!("exported map: {}", path.to_string_lossy());
             }
         }
+        ActionKind::ExportImage => {
+            let Some(map) = map.as_deref() else {
+                return;
+            };
+            let Some(path) = rfd::FileDialog::new()
+                .add_filter("PNG", &["png"])
+                .set_file_name("map.png")
+                .save_file()
+            else {
+                return;
+            };
+
+            let opts = RenderOptions {
+                tile_size: (config.tile_size.x, config.tile_size.y),
+                ..default()
+            };
+            match export_map_image(map, &lib, &opts, path.to_string_lossy().as_ref()) {
+                Ok(warnings) => {
+                    for w in warnings {
+                        warn!("export image: {w}");
+                    }
+                    info!("exported image: {}", path.to_string_lossy());
+                }
+                Err(err) => warn!("export image failed: {err}"),
+            }
+        }
     }
 }
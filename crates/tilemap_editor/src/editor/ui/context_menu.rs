@@ -163,17 +163,11 @@ pub fn context_menu_rebuild(
     // 动态条目
     let has_sel = selection.rect.is_some();
     let has_clip = clipboard.width > 0 && clipboard.height > 0 && !clipboard.tiles.is_empty();
+    let has_cursor_cell = state.map_pos.is_some() && map.is_some();
     let has_tile_under_cursor = state
         .map_pos
-        .and_then(|p| {
-            let map = map.as_deref()?;
-            if p.x >= map.width || p.y >= map.height {
-                return None;
-            }
-            let idx = map.idx(p.x, p.y);
-            map.tiles.get(idx).cloned().flatten()
-        })
-        .is_some();
+        .zip(map.as_deref())
+        .is_some_and(|(p, map)| !map.cell(0, p.x, p.y).is_empty());
     let can_undo = !undo.undo.is_empty();
     let can_redo = !undo.redo.is_empty();
     let in_paste = tools.tool == ToolKind::Paste;
@@ -207,6 +201,14 @@ pub fn context_menu_rebuild(
         ContextMenuAction::EnterPaste,
         has_clip,
     );
+    spawn_menu_item(
+        &mut commands,
+        menu,
+        "设为印章笔刷",
+        "",
+        ContextMenuAction::ClipboardAsStamp,
+        has_clip,
+    );
     spawn_menu_separator(&mut commands, menu);
 
     // 选择相关
@@ -243,6 +245,14 @@ pub fn context_menu_rebuild(
         ContextMenuAction::SelectionSelectAll,
         true,
     );
+    spawn_menu_item(
+        &mut commands,
+        menu,
+        "选择相同 Tile",
+        "",
+        ContextMenuAction::SelectSameTile,
+        has_cursor_cell,
+    );
     spawn_menu_item(
         &mut commands,
         menu,
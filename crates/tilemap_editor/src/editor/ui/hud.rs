@@ -4,8 +4,8 @@ use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
 
 use crate::editor::types::{
-	Clipboard, EditorConfig, EditorState, HudText, PasteState, TileMapData, TilesetLibrary,
-	BrushSettings, TilesetRuntime, ToolKind, ToolState, WorldCamera,
+	Clipboard, EditorConfig, EditorState, HudText, MetaBrushState, PasteState, TileMapData, TilesetLibrary,
+	BrushSettings, fill_mode_label, TilesetRuntime, ToolKind, ToolState, VariationBrushState, WorldCamera, passage_label,
 };
 
 /// 更新右上角 HUD（选中 tile、地图路径、图层/工具/剪贴板等状态）。
@@ -17,10 +17,11 @@ pub fn update_hud_text(
     runtime: Res<TilesetRuntime>,
     tools: Res<ToolState>,
 	brush: Res<BrushSettings>,
+	meta_brush: Res<MetaBrushState>,
     layer_state: Res<crate::editor::types::LayerState>,
     map: Option<Res<TileMapData>>,
     clipboard: Res<Clipboard>,
-    paste: Res<PasteState>,
+    (paste, variation): (Res<PasteState>, Res<VariationBrushState>),
 	windows: Query<&Window, With<PrimaryWindow>>,
 	camera_q: Query<(&Camera, &GlobalTransform), With<WorldCamera>>,
     hud_q: Query<Entity, With<HudText>>,
@@ -51,10 +52,10 @@ pub fn update_hud_text(
 	let mut msg = if tile_count == 0 {
 		"未选择 tileset：按 O 或点左上角【打开】导入".to_string()
 	} else {
-		let (map_w, map_h) = map
-			.as_deref()
-			.map(|m| (m.width, m.height))
-			.unwrap_or((config.map_size.x, config.map_size.y));
+		let fixed_size = match map.as_deref() {
+			Some(m) => m.fixed_size(),
+			None => Some((config.map_size.x, config.map_size.y)),
+		};
 
 		let cursor_tile = (|| {
 			let Ok(window) = windows.single() else {
@@ -63,9 +64,9 @@ pub fn update_hud_text(
 			let Ok((camera, camera_transform)) = camera_q.single() else {
 				return None;
 			};
-			crate::editor::world::cursor_tile_pos(window, camera, camera_transform, &config, map_w, map_h)
+			crate::editor::world::cursor_tile_pos(window, camera, camera_transform, &config, fixed_size)
 		})();
-		let cursor_line = if config.show_cursor {
+		let mut cursor_line = if config.show_cursor {
 			match cursor_tile {
 				Some(p) => format!("鼠标: ({}, {})", p.x, p.y),
 				None => "鼠标: -".to_string(),
@@ -73,13 +74,28 @@ pub fn update_hud_text(
 		} else {
 			"鼠标: (隐藏)".to_string()
 		};
+		if meta_brush.enabled
+			&& let (Some(p), Some(m)) = (cursor_tile, map.as_deref())
+		{
+			let meta = m.meta.get(p.x, p.y);
+			cursor_line.push_str(&format!(
+				" | 区域 {} 通行 {} 地形 {}",
+				meta.region,
+				passage_label(meta.passage),
+				meta.terrain
+			));
+		}
+
+		let size_label = match fixed_size {
+			Some((w, h)) => format!("{}x{}", w, h),
+			None => "无限".to_string(),
+		};
 
 		format!(
-			"选中 tile: {}\n地图: {} ({}x{})\n{}\n图层: {}/{} ({})\n笔刷: {}x{}\n图块: {}x{} | tiles: {}",
+			"选中 tile: {}\n地图: {} ({})\n{}\n图层: {}/{} ({})\n笔刷: {}x{}\n图块: {}x{} | tiles: {}",
 			state.selected_tile,
 			config.save_path,
-			config.map_size.x,
-			config.map_size.y,
+			size_label,
 			cursor_line,
 			active_layer + 1,
 			total_layers,
@@ -92,14 +108,69 @@ pub fn update_hud_text(
 		)
 	};
 
+	if tools.tool == ToolKind::Rect {
+		msg.push_str(&format!(
+			"\n\n形状: {}（再按 2 切换；拖动中按 Shift 正方形 / 圆，Ctrl 从中心，Shift + 拖拽擦除）",
+			tools.shape.label()
+		));
+	}
+	if tools.tool == ToolKind::Fill {
+		msg.push_str(&format!("\n\n填充: {}（再按 3 切换；Shift 擦除）", fill_mode_label(tools.fill_mode)));
+	}
+	if tools.tool == ToolKind::MagicWand {
+		msg.push_str(&format!(
+			"\n\n魔棒: {}（再按 8 切换；Shift 加选，Ctrl 减选）",
+			fill_mode_label(tools.wand_mode)
+		));
+	}
+	if tools.tool == ToolKind::Fill && meta_brush.enabled {
+		msg.push_str("\n取样: 元数据（按当前字段比较，与图层无关）");
+	} else if let Some(sample) = tools.sample() {
+		msg.push_str(&format!("\n取样: {}（工具栏“取样”按钮切换）", sample.label()));
+	}
+	if tools.tool == ToolKind::Line {
+		msg.push_str(if tools.line_polyline {
+			"\n\n直线: 折线模式（点击加顶点，Enter 提交，Esc 取消；Shift 吸附 45°；再按 7 切回两点）"
+		} else {
+			"\n\n直线: 两点模式（拖拽画线；Shift 吸附 45°；再按 7 切换折线）"
+		});
+	}
+
+	if tools.tool.uses_stamp()
+		&& let Some(stamp) = state.stamp.as_ref()
+	{
+		let (rot, flip_x, _) = paste.orientation.to_rot_flip();
+		let (pw, ph) = paste.orientation.dims(stamp.width, stamp.height);
+		msg.push_str(&format!(
+			"\n\n印章: {}x{} | 平铺尺寸: {}x{} | 变换: {}° flipX:{}（Q/E 旋转，H/V 翻转；单击 palette 取消）",
+			stamp.width,
+			stamp.height,
+			pw,
+			ph,
+			rot as u32 * 90,
+			if flip_x { "开" } else { "关" },
+		));
+	}
+
+	if matches!(tools.tool, ToolKind::Pencil | ToolKind::Rect | ToolKind::Fill | ToolKind::Line)
+		&& let Some((_, set)) = variation.active(&lib)
+	{
+		let tiles: Vec<String> = set.tiles.iter().map(|v| format!("#{}×{}", v.index, v.weight)).collect();
+		msg.push_str(&format!(
+			"\n\n随机变体: {} | {} | 随机旋转:{} 随机翻转:{}",
+			set.name,
+			tiles.join(" "),
+			if set.random_rotation { "开" } else { "关" },
+			if set.random_flip { "开" } else { "关" },
+		));
+	}
+
 	if tools.tool == ToolKind::Paste {
-		let rot = paste.rot % 4;
-		let deg = (rot as u32) * 90;
+		let (rot, flip_x, _) = paste.orientation.to_rot_flip();
 		msg.push_str(&format!(
-			"\n\n粘贴变换: {}° | flipX:{} flipY:{}",
-			deg,
-			if paste.flip_x { "开" } else { "关" },
-			if paste.flip_y { "开" } else { "关" },
+			"\n\n粘贴变换: {}° | flipX:{}",
+			rot as u32 * 90,
+			if flip_x { "开" } else { "关" },
 		));
 		if tools.return_after_paste.is_some() {
 			msg.push_str("\n模式: 临时粘贴（贴一次自动返回，按 Esc 取消）");
@@ -110,11 +181,7 @@ pub fn update_hud_text(
 		if clipboard.width == 0 || clipboard.height == 0 || clipboard.tiles.is_empty() {
 			msg.push_str("\n剪贴板: 空（先用选择工具 Ctrl+C 复制一块区域）");
 		} else {
-			let (pw, ph) = if rot == 1 || rot == 3 {
-				(clipboard.height, clipboard.width)
-			} else {
-				(clipboard.width, clipboard.height)
-			};
+			let (pw, ph) = paste.orientation.dims(clipboard.width, clipboard.height);
 			msg.push_str(&format!(
 				"\n剪贴板: {}x{} | 粘贴尺寸: {}x{}",
 				clipboard.width, clipboard.height, pw, ph
@@ -125,22 +192,16 @@ pub fn update_hud_text(
 	// 非粘贴模式下也展示剪贴板与变换状态（便于“先旋转/翻转再 Ctrl+V”）。
 	if tools.tool != ToolKind::Paste {
 		if clipboard.width > 0 && clipboard.height > 0 && !clipboard.tiles.is_empty() {
-			let rot = paste.rot % 4;
-			let deg = (rot as u32) * 90;
-			let (pw, ph) = if rot == 1 || rot == 3 {
-				(clipboard.height, clipboard.width)
-			} else {
-				(clipboard.width, clipboard.height)
-			};
+			let (rot, flip_x, _) = paste.orientation.to_rot_flip();
+			let (pw, ph) = paste.orientation.dims(clipboard.width, clipboard.height);
 			msg.push_str(&format!(
-				"\n\n剪贴板: {}x{} | 预设粘贴: {}x{} | 变换: {}° flipX:{} flipY:{}",
+				"\n\n剪贴板: {}x{} | 预设粘贴: {}x{} | 变换: {}° flipX:{}",
 				clipboard.width,
 				clipboard.height,
 				pw,
 				ph,
-				deg,
-				if paste.flip_x { "开" } else { "关" },
-				if paste.flip_y { "开" } else { "关" },
+				rot as u32 * 90,
+				if flip_x { "开" } else { "关" },
 			));
 		}
 	}
@@ -8,10 +8,9 @@ use crate::editor::types::{
     EditorConfig, MapSizeApplyButton, MapSizeFocus, MapSizeHeightField, MapSizeHeightText,
     MapSizeInput, MapSizeWidthField, MapSizeWidthText, TileMapData, UndoStack,
 };
-use crate::editor::world::{rebuild_tilemaps, TilemapRenderParams};
+use crate::editor::world::TilemapRenderParams;
 
-use super::util::resized_map_copy;
-use crate::editor::util::despawn_silently;
+use super::util::{resized_map_copy, respawn_map_render};
 
 pub fn map_size_widget_interactions(
     mut input: ResMut<MapSizeInput>,
@@ -233,37 +232,5 @@ pub fn apply_custom_map_size(
     render.commands.insert_resource(new_map.clone());
     undo.clear();
 
-    // 重建格子实体
-    if let Some(existing_tiles) = render.tile_entities.as_deref() {
-        for e in existing_tiles.all_tilemap_entities() {
-            despawn_silently(&mut render.commands, e);
-        }
-    }
-    for e in tile_q.iter() {
-        despawn_silently(&mut render.commands, e);
-    }
-    let mut tiles = crate::editor::tileset::spawn_map_entities_with_layers(
-        &mut render.commands,
-        &config,
-        new_map.layers,
-    );
-    {
-        let TilemapRenderParams {
-            commands,
-            tile_entities: _,
-            runtime,
-            tile_storage_q,
-            ..
-        } = &mut render;
-        rebuild_tilemaps(
-            commands,
-            &tile_q,
-            runtime,
-            &new_map,
-            &mut tiles,
-            tile_storage_q,
-            &config,
-        );
-    }
-    render.commands.insert_resource(tiles);
+    respawn_map_render(&mut render, &tile_q, &config, &new_map);
 }
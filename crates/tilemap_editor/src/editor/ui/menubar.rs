@@ -179,20 +179,35 @@ pub fn menubar_rebuild_dropdown_when_needed(
 			match open {
 				MenuId::File => {
 					item!("打开 Tileset…", ActionKind::OpenTileset);
+					item!("Tileset 切分设置…", ActionKind::EditTilesetGrid);
+					item!("Tile 动画…", ActionKind::EditTileAnimation);
+					item!("Tile 碰撞…", ActionKind::EditTileCollision);
+					item!("设为自动图块（选中起 47 块）", ActionKind::DefineAutotile);
+					item!("从 A2 块生成自动图块", ActionKind::ImportRmA2Autotile);
+					item!("导入 Wang 集（Tiled tileset）", ActionKind::ImportWangSets);
+					item!("新建随机变体集（palette 选中块）", ActionKind::DefineVariationSet);
+					item!("变体集：加入 / 调整选中 tile 权重", ActionKind::CycleVariationWeight);
+					item!("删除当前变体集", ActionKind::DeleteVariationSet);
 					item!("新建地图", ActionKind::NewMap);
 					item!("保存地图", ActionKind::SaveMap);
 					item!("读取地图", ActionKind::LoadMap);
 					item!("导入地图…", ActionKind::ImportMap);
 					item!("导出地图…", ActionKind::ExportMap);
+					item!("导出图片…", ActionKind::ExportImage);
+					item!("重新链接 Tileset…", ActionKind::OpenRelinkDialog);
 				}
 				MenuId::Edit => {
 					item!("撤销 (Ctrl+Z)", ActionKind::Undo);
 					item!("重做 (Ctrl+Y)", ActionKind::Redo);
+					item!("替换 Tile…", ActionKind::OpenReplaceTile);
 				}
 				MenuId::View => {
 					item!("网格开关", ActionKind::ToggleGrid);
 					item!("Hover 高亮开关", ActionKind::ToggleHover);
 					item!("坐标显示开关", ActionKind::ToggleCursor);
+					item!("动画播放开关", ActionKind::ToggleTileAnimations);
+					item!("碰撞显示开关", ActionKind::ToggleCollision);
+					item!("问题面板", ActionKind::ToggleProblems);
 				}
 				MenuId::Map => {
 					item!("地图尺寸: 40x25", ActionKind::SetMapSize { width: 40, height: 25 });
@@ -290,6 +305,10 @@ pub fn menubar_rebuild_dropdown_when_needed(
 					});
 
 					item!("Shift 模式切换", ActionKind::ToggleShiftMode);
+					item!("无限地图开关", ActionKind::ToggleInfiniteMap);
+					item!("载入规则…", ActionKind::LoadAutomapRules);
+					item!("应用规则（有选区时仅选区）", ActionKind::ApplyAutomapRules);
+					item!("绘制后自动应用规则开关", ActionKind::ToggleAutomapAutoRun);
 				}
 				MenuId::Layer => {
 					label!("当前层名称（回车/按钮应用）");
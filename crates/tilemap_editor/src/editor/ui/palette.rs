@@ -10,8 +10,8 @@ use crate::editor::{LEFT_PANEL_WIDTH_PX, UI_BUTTON, UI_BUTTON_HOVER, UI_HIGHLIGH
 use crate::editor::tileset::rect_for_tile_index;
 use crate::editor::util::despawn_silently;
 use crate::editor::types::{
-    EditorConfig, EditorState, PaletteRoot, PaletteScroll, PaletteTileButton, TilesetLibrary,
-    TilesetRuntime, UiState,
+    palette_stamp, EditorState, PaletteRoot, PaletteScroll, PaletteTileButton, PaletteTileImage, TileAnimationClock,
+    TileAnimationEditorState, TilesetLibrary, TilesetRuntime, UiState,
     PaletteSearchClearButton, PaletteSearchField, PaletteSearchInput, PaletteSearchText,
     PaletteZoomButton, PaletteZoomLevel,
 };
@@ -480,7 +480,6 @@ pub fn palette_clamp_scroll_position(
 /// tileset 加载完成后，动态生成左侧 palette（缩略图按钮网格）。
 pub fn build_palette_when_ready(
     mut commands: Commands,
-    config: Res<EditorConfig>,
     lib: Res<TilesetLibrary>,
     runtime: Res<TilesetRuntime>,
     mut ui_state: ResMut<UiState>,
@@ -547,7 +546,7 @@ pub fn build_palette_when_ready(
 
     commands.entity(palette_entity).with_children(|p| {
         for &index in filtered_indices.iter() {
-            let rect = rect_for_tile_index(index, columns, config.tile_size);
+            let rect = rect_for_tile_index(index, columns, active.grid);
 
             p.spawn((
                 Button,
@@ -571,6 +570,7 @@ pub fn build_palette_when_ready(
                         height: Val::Percent(100.0),
                         ..default()
                     },
+                    PaletteTileImage { index },
                 ));
             });
         }
@@ -588,25 +588,59 @@ pub fn build_palette_when_ready(
     }
 }
 
-/// palette 点击选择 tile。
+/// palette 点击选择 tile；按住拖到另一个 tile、或 Shift+点击时，把与选中 tile 围成的矩形块设为印章
+/// （铅笔 / 矩形 / 油漆桶按它平铺）；动画编辑器打开时改为把点击的 tile 追加为动画帧。
 pub fn palette_tile_click(
     mut state: ResMut<EditorState>,
+    lib: Res<TilesetLibrary>,
+    runtime: Res<TilesetRuntime>,
+    (mouse, keys): (Res<ButtonInput<MouseButton>>, Res<ButtonInput<KeyCode>>),
+    mut animation_editor: ResMut<TileAnimationEditorState>,
+    mut anchor: Local<Option<u32>>,
     mut buttons_q: Query<(&Interaction, &PaletteTileButton, &mut BackgroundColor), Changed<Interaction>>,
 ) {
-    let selected_before = state.selected_tile;
+    if !mouse.pressed(MouseButton::Left) {
+        *anchor = None;
+    }
+    let active_id = lib.active_id.as_ref();
+    let columns = active_id.and_then(|id| runtime.by_id.get(id)).map_or(1, |e| e.columns);
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
     for (interaction, tile, mut bg) in buttons_q.iter_mut() {
         match *interaction {
+            Interaction::Pressed if animation_editor.open => {
+                // 帧只能来自正在编辑的 tileset。
+                if lib.active_id == animation_editor.tileset_id {
+                    animation_editor.push_frame(tile.index);
+                } else {
+                    warn!("animation frames must come from the tileset being edited");
+                }
+            }
+            Interaction::Pressed if shift => {
+                if let Some(id) = active_id {
+                    state.stamp = palette_stamp(id, state.selected_tile, tile.index, columns);
+                }
+                *bg = BackgroundColor(UI_HIGHLIGHT);
+            }
             Interaction::Pressed => {
                 state.selected_tile = tile.index;
+                state.stamp = None;
+                *anchor = Some(tile.index);
                 *bg = BackgroundColor(UI_HIGHLIGHT);
             }
+            Interaction::Hovered if anchor.is_some() => {
+                let (Some(start), Some(id)) = (*anchor, active_id) else {
+                    continue;
+                };
+                state.stamp = palette_stamp(id, start, tile.index, columns);
+                *bg = BackgroundColor(UI_BUTTON_HOVER);
+            }
             Interaction::Hovered => {
-                if selected_before != tile.index {
+                if !state.is_palette_selected(active_id, tile.index) {
                     *bg = BackgroundColor(UI_BUTTON_HOVER);
                 }
             }
             Interaction::None => {
-                if selected_before == tile.index {
+                if state.is_palette_selected(active_id, tile.index) {
                     *bg = BackgroundColor(UI_HIGHLIGHT);
                 } else {
                     *bg = BackgroundColor(UI_BUTTON);
@@ -615,3 +649,51 @@ pub fn palette_tile_click(
         }
     }
 }
+
+/// 选中 tile / 印章变化（或 palette 重建）时刷新整块高亮；悬停中的按钮由 `palette_tile_click` 负责。
+pub fn sync_palette_tile_styles(
+    state: Res<EditorState>,
+    lib: Res<TilesetLibrary>,
+    added_q: Query<(), Added<PaletteTileButton>>,
+    mut q: Query<(&Interaction, &PaletteTileButton, &mut BackgroundColor)>,
+) {
+    if !state.is_changed() && !lib.is_changed() && added_q.is_empty() {
+        return;
+    }
+    for (interaction, tile, mut bg) in q.iter_mut() {
+        if *interaction != Interaction::None {
+            continue;
+        }
+        let color = if state.is_palette_selected(lib.active_id.as_ref(), tile.index) {
+            UI_HIGHLIGHT
+        } else {
+            UI_BUTTON
+        };
+        if bg.0 != color {
+            *bg = BackgroundColor(color);
+        }
+    }
+}
+
+/// 带动画的 tile 在 palette 里按当前帧显示。
+pub fn animate_palette_tiles(
+    clock: Res<TileAnimationClock>,
+    lib: Res<TilesetLibrary>,
+    runtime: Res<TilesetRuntime>,
+    mut image_q: Query<(&PaletteTileImage, &mut ImageNode)>,
+) {
+    let Some(active) = lib.active_id.as_ref().and_then(|id| runtime.by_id.get(id)) else {
+        return;
+    };
+    if active.animations.is_empty() && !runtime.is_changed() {
+        return;
+    }
+    let columns = active.columns.max(1);
+    for (tile, mut node) in image_q.iter_mut() {
+        let frame = active.frame_at(tile.index, clock.elapsed_ms);
+        let rect = rect_for_tile_index(frame, columns, active.grid);
+        if node.rect != Some(rect) {
+            node.rect = Some(rect);
+        }
+    }
+}
@@ -0,0 +1,401 @@
+//! Problems 面板：地图检查结果（缺失/占位 tileset、index 越界、图层元数据、hash 不一致），
+//! 支持点击定位与一键修复（清除无效格子、修复图层数据），缺图的 tileset 转交重新链接对话框。
+
+use bevy::prelude::*;
+use tilemap_core::ops;
+use tilemap_format::{validate_map, AssetProbe};
+
+use crate::editor::paths::workspace_assets_dir;
+use crate::editor::types::{
+    CanvasClickBlocker, CellPos, EditCommand, EditorConfig, LayerState, MapProblem, ProblemAction,
+    ProblemButton, ProblemsCloseButton, ProblemsList, ProblemsPanelRoot, ProblemsRefreshButton,
+    ProblemsState, ProblemsSummaryText, RelinkDialogState, SelectionRect, SelectionState, TileMapData,
+    TilesetLibrary, TilesetLoading, TilesetRuntime, UndoStack, WorldCamera,
+};
+use crate::editor::util::despawn_silently;
+use crate::editor::world::{render_cell_changes, TilemapRenderParams};
+use crate::editor::{UI_BUTTON, UI_BUTTON_HOVER, UI_BUTTON_PRESS, UI_PANEL};
+
+/// UI 初始化时创建面板（默认隐藏；内容在打开/检查结果变化时重建）。
+pub(super) fn spawn_problems_panel(commands: &mut Commands) {
+    commands
+        .spawn((
+            // 面板本身参与 hit-test，点击空白处不会穿透到画布。
+            Button,
+            Node {
+                position_type: PositionType::Absolute,
+                right: Val::Px(10.0),
+                bottom: Val::Px(10.0),
+                width: Val::Px(460.0),
+                max_height: Val::Px(360.0),
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(8.0)),
+                row_gap: Val::Px(6.0),
+                ..default()
+            },
+            BackgroundColor(UI_PANEL),
+            ZIndex(900),
+            Visibility::Hidden,
+            CanvasClickBlocker,
+            ProblemsPanelRoot,
+        ))
+        .with_children(|p| {
+            p.spawn(Node {
+                width: Val::Percent(100.0),
+                flex_direction: FlexDirection::Row,
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(6.0),
+                ..default()
+            })
+            .with_children(|p| {
+                p.spawn((
+                    Text::new("问题"),
+                    TextFont { font_size: 13.0, ..default() },
+                    TextColor(Color::WHITE),
+                    Node { flex_grow: 1.0, ..default() },
+                    ProblemsSummaryText,
+                ));
+                header_button(p, "重新检查", (CanvasClickBlocker, ProblemsRefreshButton));
+                header_button(p, "关闭", (CanvasClickBlocker, ProblemsCloseButton));
+            });
+            p.spawn((
+                Node {
+                    width: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(6.0),
+                    overflow: Overflow::scroll_y(),
+                    min_height: Val::Px(0.0),
+                    ..default()
+                },
+                ScrollPosition::default(),
+                ProblemsList,
+            ));
+        });
+}
+
+fn header_button(p: &mut ChildSpawnerCommands, label: &str, marker: impl Bundle) {
+    p.spawn((
+        Button,
+        Node {
+            height: Val::Px(24.0),
+            padding: UiRect::axes(Val::Px(8.0), Val::Px(2.0)),
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        BackgroundColor(UI_BUTTON),
+        marker,
+    ))
+    .with_children(|p| {
+        p.spawn((
+            Text::new(label),
+            TextFont { font_size: 12.0, ..default() },
+            TextColor(Color::WHITE),
+        ));
+    });
+}
+
+/// 每类问题可用的操作（按钮顺序即显示顺序）。
+fn actions_for(problem: &MapProblem) -> Vec<ProblemAction> {
+    let mut actions = Vec::new();
+    if problem.location().is_some() {
+        actions.push(ProblemAction::Locate);
+    }
+    match problem {
+        MapProblem::LayerDataMismatch { .. } => actions.push(ProblemAction::FixLayers),
+        MapProblem::DuplicateTileset { .. } => {}
+        MapProblem::UnlistedTileset { .. } => actions.push(ProblemAction::ClearTileset),
+        MapProblem::PlaceholderTileset { cells, .. }
+        | MapProblem::MissingAsset { cells, .. }
+        | MapProblem::UnreadableAsset { cells, .. } => {
+            actions.push(ProblemAction::Relink);
+            if *cells > 0 {
+                actions.push(ProblemAction::ClearTileset);
+            }
+        }
+        MapProblem::IndexOutOfRange { .. } => actions.push(ProblemAction::ClearInvalid),
+        MapProblem::HashMismatch { .. } => actions.push(ProblemAction::Relink),
+    }
+    actions
+}
+
+fn action_label(action: ProblemAction) -> &'static str {
+    match action {
+        ProblemAction::Locate => "定位",
+        ProblemAction::Relink => "重新链接…",
+        ProblemAction::ClearInvalid => "清除越界格子",
+        ProblemAction::ClearTileset => "清除这些格子",
+        ProblemAction::FixLayers => "修复图层数据",
+    }
+}
+
+/// 面板打开时检查地图；地图 / tileset 库 / 图集加载状态变化后自动重新检查。
+pub fn refresh_map_problems(
+    map: Option<Res<TileMapData>>,
+    lib: Res<TilesetLibrary>,
+    runtime: Res<TilesetRuntime>,
+    loading: Res<TilesetLoading>,
+    mut state: ResMut<ProblemsState>,
+) {
+    if !state.open {
+        return;
+    }
+    let map_changed = map.as_ref().is_some_and(|m| m.is_changed());
+    if !(state.dirty || map_changed || lib.is_changed() || runtime.is_changed()) {
+        return;
+    }
+    let rebuild = state.dirty;
+    state.dirty = false;
+
+    let Some(map) = map else {
+        state.problems.clear();
+        state.rebuild = true;
+        return;
+    };
+
+    let state = &mut *state;
+    let hash_cache = &mut state.hash_cache;
+    let problems = validate_map(&map, &lib.entries, |entry| {
+        let abs = workspace_assets_dir().join(&entry.asset_path);
+        if !abs.exists() {
+            return AssetProbe::Missing { path: entry.asset_path.clone() };
+        }
+        if let Some(atlas) = runtime.by_id.get(&entry.id) {
+            let hash = match hash_cache.get(&entry.asset_path) {
+                Some(h) => Some(h.clone()),
+                None => std::fs::read(&abs).ok().map(|bytes| {
+                    let h = blake3::hash(&bytes).to_hex().to_string();
+                    hash_cache.insert(entry.asset_path.clone(), h.clone());
+                    h
+                }),
+            };
+            return AssetProbe::Ready { capacity: atlas.columns * atlas.rows, hash };
+        }
+        if loading.pending.iter().any(|p| p.id == entry.id) {
+            return AssetProbe::Pending;
+        }
+        AssetProbe::Unreadable {
+            path: entry.asset_path.clone(),
+            error: "图片未能加载（格式不支持，或小于 tile 尺寸）".to_string(),
+        }
+    });
+
+    if rebuild || problems != state.problems {
+        state.problems = problems;
+        state.rebuild = true;
+    }
+}
+
+/// 根据 ProblemsState 显示/隐藏面板。
+pub fn problems_panel_visibility(
+    state: Res<ProblemsState>,
+    mut panel_q: Query<&mut Visibility, With<ProblemsPanelRoot>>,
+) {
+    if !state.is_changed() {
+        return;
+    }
+    let Ok(mut vis) = panel_q.single_mut() else {
+        return;
+    };
+    *vis = if state.open { Visibility::Visible } else { Visibility::Hidden };
+}
+
+/// 检查结果变化后重建问题列表。
+pub fn rebuild_problems_panel_when_needed(
+    mut commands: Commands,
+    mut state: ResMut<ProblemsState>,
+    list_q: Query<Entity, With<ProblemsList>>,
+    children_q: Query<&Children>,
+    mut summary_q: Query<&mut Text, With<ProblemsSummaryText>>,
+) {
+    if !state.rebuild {
+        return;
+    }
+    let Ok(list) = list_q.single() else {
+        return;
+    };
+    state.rebuild = false;
+
+    if let Ok(children) = children_q.get(list) {
+        for child in children.iter() {
+            despawn_silently(&mut commands, child);
+        }
+    }
+
+    let summary = if state.problems.is_empty() {
+        "问题：无".to_string()
+    } else {
+        format!("问题：{} 项", state.problems.len())
+    };
+    for mut t in summary_q.iter_mut() {
+        *t = Text::new(summary.clone());
+    }
+
+    commands.entity(list).with_children(|p| {
+        if state.problems.is_empty() {
+            p.spawn((
+                Text::new("地图检查通过"),
+                TextFont { font_size: 12.0, ..default() },
+                TextColor(Color::srgba(1.0, 1.0, 1.0, 0.75)),
+            ));
+            return;
+        }
+        for (index, problem) in state.problems.iter().enumerate() {
+            p.spawn((
+                Node {
+                    width: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(6.0)),
+                    row_gap: Val::Px(4.0),
+                    ..default()
+                },
+                BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.25)),
+            ))
+            .with_children(|p| {
+                p.spawn((
+                    Text::new(problem.to_string()),
+                    TextFont { font_size: 12.0, ..default() },
+                    TextColor(Color::WHITE),
+                ));
+                let actions = actions_for(problem);
+                if actions.is_empty() {
+                    return;
+                }
+                p.spawn(Node {
+                    flex_direction: FlexDirection::Row,
+                    column_gap: Val::Px(6.0),
+                    ..default()
+                })
+                .with_children(|p| {
+                    for action in actions {
+                        p.spawn((
+                            Button,
+                            Node {
+                                height: Val::Px(22.0),
+                                padding: UiRect::axes(Val::Px(8.0), Val::Px(2.0)),
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            BackgroundColor(UI_BUTTON),
+                            CanvasClickBlocker,
+                            ProblemButton { index, action },
+                        ))
+                        .with_children(|p| {
+                            p.spawn((
+                                Text::new(action_label(action)),
+                                TextFont { font_size: 12.0, ..default() },
+                                TextColor(Color::WHITE),
+                            ));
+                        });
+                    }
+                });
+            });
+        }
+    });
+}
+
+/// 面板按钮：刷新/关闭/定位/修复；重新链接交给重新链接对话框。
+pub fn problems_panel_button_click(
+    mut state: ResMut<ProblemsState>,
+    mut button_q: Query<(&Interaction, &ProblemButton, &mut BackgroundColor), Changed<Interaction>>,
+    mut header_q: Query<
+        (&Interaction, Has<ProblemsRefreshButton>, &mut BackgroundColor),
+        (
+            Changed<Interaction>,
+            Or<(With<ProblemsRefreshButton>, With<ProblemsCloseButton>)>,
+            Without<ProblemButton>,
+        ),
+    >,
+    map: Option<ResMut<TileMapData>>,
+    mut render: TilemapRenderParams,
+    config: Res<EditorConfig>,
+    mut undo: ResMut<UndoStack>,
+    mut layer_state: ResMut<LayerState>,
+    mut selection: ResMut<SelectionState>,
+    mut cam_q: Query<&mut Transform, With<WorldCamera>>,
+    mut relink: ResMut<RelinkDialogState>,
+) {
+    for (interaction, is_refresh, mut bg) in header_q.iter_mut() {
+        match *interaction {
+            Interaction::Pressed => {
+                *bg = BackgroundColor(UI_BUTTON_PRESS);
+                if is_refresh {
+                    state.hash_cache.clear();
+                    state.dirty = true;
+                } else {
+                    state.open = false;
+                }
+            }
+            Interaction::Hovered => *bg = BackgroundColor(UI_BUTTON_HOVER),
+            Interaction::None => *bg = BackgroundColor(UI_BUTTON),
+        }
+    }
+
+    let mut requested: Option<ProblemButton> = None;
+    for (interaction, button, mut bg) in button_q.iter_mut() {
+        match *interaction {
+            Interaction::Pressed => {
+                *bg = BackgroundColor(UI_BUTTON_PRESS);
+                requested = Some(*button);
+            }
+            Interaction::Hovered => *bg = BackgroundColor(UI_BUTTON_HOVER),
+            Interaction::None => *bg = BackgroundColor(UI_BUTTON),
+        }
+    }
+    let Some(ProblemButton { index, action }) = requested else {
+        return;
+    };
+    let Some(problem) = state.problems.get(index).cloned() else {
+        return;
+    };
+    let Some(mut map) = map else {
+        return;
+    };
+
+    match action {
+        ProblemAction::Locate => {
+            let Some(CellPos { layer, x, y }) = problem.location() else {
+                return;
+            };
+            layer_state.active = layer.min(map.layers.saturating_sub(1));
+            selection.set_rect(SelectionRect {
+                min: IVec2::new(x, y),
+                max: IVec2::new(x, y),
+            });
+            if let Ok(mut tf) = cam_q.single_mut() {
+                tf.translation.x = (x as f32 + 0.5) * config.tile_size.x as f32;
+                tf.translation.y = (y as f32 + 0.5) * config.tile_size.y as f32;
+            }
+        }
+        ProblemAction::Relink => {
+            if let Some(id) = problem.tileset_id() {
+                relink.add(id, problem.to_string());
+            }
+        }
+        ProblemAction::ClearInvalid | ProblemAction::ClearTileset => {
+            let Some(id) = problem.tileset_id().map(str::to_string) else {
+                return;
+            };
+            let capacity = match action {
+                ProblemAction::ClearInvalid => match render.runtime.by_id.get(&id) {
+                    Some(atlas) => atlas.columns * atlas.rows,
+                    None => return,
+                },
+                _ => 0,
+            };
+            let changes = ops::clear_where(&mut map, |_, _, _, t| {
+                *t.tileset_id == id && t.index >= capacity
+            });
+            if changes.is_empty() {
+                return;
+            }
+            info!("cleared {} cells of tileset {id}", changes.len());
+            render_cell_changes(&mut render, &config, &map, &changes);
+            undo.push(EditCommand { changes, ..Default::default() });
+        }
+        ProblemAction::FixLayers => {
+            let layers = map.layers;
+            map.layer_data.truncate(layers as usize);
+            map.ensure_layers(layers);
+        }
+    }
+}
@@ -0,0 +1,407 @@
+//! 重新链接 tileset 对话框：列出加载失败 / 图片缺失的 tileset，选择替换图片。
+//!
+//! 替换图片的 hash 与原 id 一致时直接改 asset_path 并重新加载；不一致时必须显式确认
+//! “重映射”，把地图中旧 id 的格子全部改为新 id（可撤销）。
+
+use bevy::prelude::*;
+use tilemap_core::ops;
+
+use crate::editor::tileset::{import_tileset_file, save_tileset_library};
+use crate::editor::types::{
+    CanvasClickBlocker, ContextMenuState, EditCommand, EditorConfig, ImportedTileset, PendingTileset,
+    ProblemsState, RelinkAction, RelinkButton, RelinkCloseButton, RelinkDialogRoot, RelinkDialogState,
+    RelinkList, TileMapData, TilesetEntry, TilesetLibrary, TilesetLoading, TilesetRuntime, UndoStack,
+};
+use crate::editor::util::despawn_silently;
+use crate::editor::world::{render_cell_changes, TilemapRenderParams};
+use crate::editor::{
+    LEFT_PANEL_WIDTH_PX, UI_BUTTON, UI_BUTTON_HOVER, UI_BUTTON_PRESS, UI_PANEL, UI_TOP_RESERVED_PX,
+};
+
+/// UI 初始化时创建对话框（默认隐藏；内容在条目变化时重建）。
+pub(super) fn spawn_relink_dialog(commands: &mut Commands) {
+    commands
+        .spawn((
+            Button,
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(UI_TOP_RESERVED_PX + 40.0),
+                left: Val::Px(LEFT_PANEL_WIDTH_PX + 40.0),
+                width: Val::Px(560.0),
+                max_height: Val::Px(420.0),
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(8.0)),
+                row_gap: Val::Px(6.0),
+                ..default()
+            },
+            BackgroundColor(UI_PANEL),
+            ZIndex(3000),
+            Visibility::Hidden,
+            CanvasClickBlocker,
+            RelinkDialogRoot,
+        ))
+        .with_children(|p| {
+            p.spawn(Node {
+                width: Val::Percent(100.0),
+                flex_direction: FlexDirection::Row,
+                align_items: AlignItems::Center,
+                ..default()
+            })
+            .with_children(|p| {
+                p.spawn((
+                    Text::new("重新链接 tileset"),
+                    TextFont { font_size: 13.0, ..default() },
+                    TextColor(Color::WHITE),
+                    Node { flex_grow: 1.0, ..default() },
+                ));
+                spawn_button(p, "关闭", (CanvasClickBlocker, RelinkCloseButton));
+            });
+            p.spawn((
+                Node {
+                    width: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(6.0),
+                    overflow: Overflow::scroll_y(),
+                    min_height: Val::Px(0.0),
+                    ..default()
+                },
+                ScrollPosition::default(),
+                RelinkList,
+            ));
+        });
+}
+
+fn spawn_button(p: &mut ChildSpawnerCommands, label: &str, marker: impl Bundle) {
+    p.spawn((
+        Button,
+        Node {
+            height: Val::Px(22.0),
+            padding: UiRect::axes(Val::Px(8.0), Val::Px(2.0)),
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        BackgroundColor(UI_BUTTON),
+        marker,
+    ))
+    .with_children(|p| {
+        p.spawn((
+            Text::new(label),
+            TextFont { font_size: 12.0, ..default() },
+            TextColor(Color::WHITE),
+        ));
+    });
+}
+
+/// 新出现的加载失败加入对话框并自动打开；已链接成功的条目从列表移除。
+pub fn sync_failed_tilesets_into_dialog(
+    loading: Res<TilesetLoading>,
+    runtime: Res<TilesetRuntime>,
+    mut dialog: ResMut<RelinkDialogState>,
+) {
+    if loading.is_changed() {
+        for failed in &loading.failed {
+            let known = dialog
+                .items
+                .iter()
+                .any(|i| i.id == failed.id && i.reason.contains(&failed.error));
+            if !known {
+                let reason = format!("加载 {} 失败: {}", failed.asset_path, failed.error);
+                dialog.add(&failed.id, reason);
+            }
+        }
+    }
+    if runtime.is_changed() && dialog.items.iter().any(|i| runtime.by_id.contains_key(&i.id)) {
+        dialog.items.retain(|i| !runtime.by_id.contains_key(&i.id));
+        dialog.rebuild = true;
+    }
+}
+
+/// 根据 RelinkDialogState 显示/隐藏对话框。
+pub fn relink_dialog_visibility(
+    dialog: Res<RelinkDialogState>,
+    mut root_q: Query<&mut Visibility, With<RelinkDialogRoot>>,
+) {
+    if !dialog.is_changed() {
+        return;
+    }
+    let Ok(mut vis) = root_q.single_mut() else {
+        return;
+    };
+    *vis = if dialog.open { Visibility::Visible } else { Visibility::Hidden };
+}
+
+/// 条目变化后重建列表。
+pub fn rebuild_relink_dialog_when_needed(
+    mut commands: Commands,
+    mut dialog: ResMut<RelinkDialogState>,
+    lib: Res<TilesetLibrary>,
+    list_q: Query<Entity, With<RelinkList>>,
+    children_q: Query<&Children>,
+) {
+    if !dialog.rebuild {
+        return;
+    }
+    let Ok(list) = list_q.single() else {
+        return;
+    };
+    dialog.rebuild = false;
+
+    if let Ok(children) = children_q.get(list) {
+        for child in children.iter() {
+            despawn_silently(&mut commands, child);
+        }
+    }
+
+    commands.entity(list).with_children(|p| {
+        if dialog.items.is_empty() {
+            p.spawn((
+                Text::new("没有需要重新链接的 tileset"),
+                TextFont { font_size: 12.0, ..default() },
+                TextColor(Color::srgba(1.0, 1.0, 1.0, 0.75)),
+            ));
+            return;
+        }
+        for (index, item) in dialog.items.iter().enumerate() {
+            let entry = lib.entries.iter().find(|e| e.id == item.id);
+            let name = entry.map(|e| e.name.as_str()).filter(|n| !n.trim().is_empty());
+            let path = entry.map(|e| e.asset_path.as_str()).unwrap_or("");
+            let mut lines = vec![
+                format!("{} ({})", name.unwrap_or("未命名"), item.id),
+                format!("路径: {}", if path.is_empty() { "（空）" } else { path }),
+                item.reason.clone(),
+            ];
+            if let Some(candidate) = &item.candidate {
+                lines.push(format!(
+                    "所选图片 {} 的 hash 不一致: {}",
+                    candidate.rel, candidate.id
+                ));
+            }
+            p.spawn((
+                Node {
+                    width: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(6.0)),
+                    row_gap: Val::Px(4.0),
+                    ..default()
+                },
+                BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.25)),
+            ))
+            .with_children(|p| {
+                p.spawn((
+                    Text::new(lines.join("\n")),
+                    TextFont { font_size: 12.0, ..default() },
+                    TextColor(Color::WHITE),
+                ));
+                p.spawn(Node {
+                    flex_direction: FlexDirection::Row,
+                    column_gap: Val::Px(6.0),
+                    ..default()
+                })
+                .with_children(|p| {
+                    let buttons: &[(&str, RelinkAction)] = if item.candidate.is_some() {
+                        &[
+                            ("重映射到新 id", RelinkAction::Remap),
+                            ("重新选择…", RelinkAction::Pick),
+                            ("取消", RelinkAction::Discard),
+                        ]
+                    } else {
+                        &[("选择图片…", RelinkAction::Pick)]
+                    };
+                    for &(label, action) in buttons {
+                        spawn_button(p, label, (CanvasClickBlocker, RelinkButton { index, action }));
+                    }
+                });
+            });
+        }
+    });
+}
+
+/// 点击悬浮面板（Problems、重新链接对话框）时吞掉这次左键，避免同一帧在下面的画布上绘制。
+pub fn canvas_blockers_consume_clicks(
+    mut menu: ResMut<ContextMenuState>,
+    q: Query<(&Interaction, &InheritedVisibility), With<CanvasClickBlocker>>,
+) {
+    if q.iter().any(|(i, vis)| *i == Interaction::Pressed && vis.get()) {
+        menu.consume_left_click = true;
+    }
+}
+
+/// 对话框按钮：关闭 / 记录请求（由 `apply_relink_pick` / `apply_relink_remap` 处理）。
+pub fn relink_dialog_button_click(
+    mut dialog: ResMut<RelinkDialogState>,
+    mut button_q: Query<(&Interaction, &RelinkButton, &mut BackgroundColor), Changed<Interaction>>,
+    mut close_q: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<RelinkCloseButton>, Without<RelinkButton>),
+    >,
+) {
+    for (interaction, mut bg) in close_q.iter_mut() {
+        match *interaction {
+            Interaction::Pressed => {
+                *bg = BackgroundColor(UI_BUTTON_PRESS);
+                dialog.open = false;
+            }
+            Interaction::Hovered => *bg = BackgroundColor(UI_BUTTON_HOVER),
+            Interaction::None => *bg = BackgroundColor(UI_BUTTON),
+        }
+    }
+    for (interaction, button, mut bg) in button_q.iter_mut() {
+        match *interaction {
+            Interaction::Pressed => {
+                *bg = BackgroundColor(UI_BUTTON_PRESS);
+                dialog.requested = Some((button.index, button.action));
+            }
+            Interaction::Hovered => *bg = BackgroundColor(UI_BUTTON_HOVER),
+            Interaction::None => *bg = BackgroundColor(UI_BUTTON),
+        }
+    }
+}
+
+/// 选择替换图片：hash 一致则保留原 id 重新加载；不一致则记为候选，等待用户确认重映射。
+pub fn apply_relink_pick(
+    asset_server: Res<AssetServer>,
+    config: Res<EditorConfig>,
+    mut dialog: ResMut<RelinkDialogState>,
+    mut lib: ResMut<TilesetLibrary>,
+    mut runtime: ResMut<TilesetRuntime>,
+    mut loading: ResMut<TilesetLoading>,
+    mut problems: ResMut<ProblemsState>,
+) {
+    let Some((index, action)) = dialog.requested else {
+        return;
+    };
+    if action == RelinkAction::Remap {
+        return;
+    }
+    dialog.requested = None;
+    let Some(id) = dialog.items.get(index).map(|i| i.id.clone()) else {
+        return;
+    };
+    if action == RelinkAction::Discard {
+        dialog.items[index].candidate = None;
+        dialog.rebuild = true;
+        return;
+    }
+
+    let Some(picked) = rfd::FileDialog::new()
+        .add_filter("Image", &["png", "jpg", "jpeg", "bmp"])
+        .pick_file()
+    else {
+        return;
+    };
+    let imported = match import_tileset_file(&config, &picked) {
+        Ok(imported) => imported,
+        Err(err) => {
+            warn!("relink failed: {err}");
+            dialog.items[index].reason = format!("导入失败: {err}");
+            dialog.rebuild = true;
+            return;
+        }
+    };
+    if imported.id != id {
+        info!("relink candidate for {id} has a different content hash: {}", imported.id);
+        dialog.items[index].candidate = Some(imported);
+        dialog.rebuild = true;
+        return;
+    }
+
+    info!("relinked tileset: id={id} path={}", imported.rel);
+    match lib.entries.iter_mut().find(|e| e.id == id) {
+        Some(entry) => {
+            entry.asset_path = imported.rel.clone();
+            if entry.name.trim().is_empty() || entry.name == id {
+                entry.name = imported.name.clone();
+            }
+        }
+        None => lib.entries.push(TilesetEntry {
+            id: id.clone(),
+            name: imported.name.clone(),
+            category: "default".to_string(),
+            asset_path: imported.rel.clone(),
+            ..Default::default()
+        }),
+    }
+    save_tileset_library(&lib);
+
+    // 去掉旧的运行时条目，新图片加载完成后由 progress_spritesheet_loading 重新登记并刷新渲染。
+    runtime.by_id.remove(&id);
+    queue_load(&asset_server, &mut loading, &id, &imported);
+    dialog.items.remove(index);
+    dialog.rebuild = true;
+    problems.hash_cache.clear();
+    problems.dirty = true;
+}
+
+/// 确认重映射：地图中旧 id 的格子全部改为候选图片的 id（index / 朝向不变，可撤销）。
+pub fn apply_relink_remap(
+    asset_server: Res<AssetServer>,
+    map: Option<ResMut<TileMapData>>,
+    mut render: TilemapRenderParams,
+    config: Res<EditorConfig>,
+    mut dialog: ResMut<RelinkDialogState>,
+    mut lib: ResMut<TilesetLibrary>,
+    mut loading: ResMut<TilesetLoading>,
+    mut undo: ResMut<UndoStack>,
+    mut problems: ResMut<ProblemsState>,
+) {
+    let Some((index, RelinkAction::Remap)) = dialog.requested else {
+        return;
+    };
+    dialog.requested = None;
+    let Some(item) = dialog.items.get(index) else {
+        return;
+    };
+    let Some(imported) = item.candidate.clone() else {
+        return;
+    };
+    let old_id = item.id.clone();
+    let new_id = imported.id.clone();
+
+    if let Some(mut map) = map {
+        let changes = ops::remap_tileset(&mut map, &old_id, &new_id);
+        if !changes.is_empty() {
+            info!("remapped {} cells from tileset {old_id} to {new_id}", changes.len());
+            render_cell_changes(&mut render, &config, &map, &changes);
+            undo.push(EditCommand { changes, ..Default::default() });
+        }
+    }
+
+    // 新条目沿用旧条目的分类与切分参数（替换图通常是同一套图块）。
+    let old_entry = lib.entries.iter().find(|e| e.id == old_id).cloned();
+    match lib.entries.iter_mut().find(|e| e.id == new_id) {
+        Some(entry) => entry.asset_path = imported.rel.clone(),
+        None => lib.entries.push(TilesetEntry {
+            id: new_id.clone(),
+            name: imported.name.clone(),
+            asset_path: imported.rel.clone(),
+            ..old_entry.unwrap_or_else(|| TilesetEntry {
+                category: "default".to_string(),
+                ..Default::default()
+            })
+        }),
+    }
+    if lib.active_id.as_deref() == Some(old_id.as_str()) {
+        lib.active_id = Some(new_id.clone());
+    }
+    save_tileset_library(&lib);
+
+    let known = render.runtime.by_id.contains_key(&new_id) || loading.pending.iter().any(|p| p.id == new_id);
+    if !known {
+        queue_load(&asset_server, &mut loading, &new_id, &imported);
+    }
+    loading.failed.retain(|f| f.id != old_id);
+    dialog.items.remove(index);
+    dialog.rebuild = true;
+    problems.hash_cache.clear();
+    problems.dirty = true;
+}
+
+fn queue_load(asset_server: &AssetServer, loading: &mut TilesetLoading, id: &str, imported: &ImportedTileset) {
+    loading.pending.retain(|p| p.id != id);
+    loading.failed.retain(|f| f.id != id);
+    let texture: Handle<Image> = asset_server.load(imported.rel.clone());
+    loading.pending.push(PendingTileset {
+        id: id.to_string(),
+        texture,
+    });
+}
@@ -0,0 +1,212 @@
+//! 替换 Tile 对话框：把指定图层（或选区内）的某个 tile 全部换成另一个 tile / 清空，整体一个撤销步骤。
+
+use bevy::prelude::*;
+use tilemap_core::ops;
+
+use crate::editor::types::{
+    CanvasClickBlocker, EditCommand, EditorConfig, EditorState, LayerState, ReplaceLayers, ReplaceTileAction,
+    ReplaceTileButton, ReplaceTileDialogRoot, ReplaceTileDialogState, ReplaceTileSummaryText, SelectionState,
+    TileMapData, TileRef, TilesetLibrary, UndoStack,
+};
+use crate::editor::world::{render_cell_changes, TilemapRenderParams};
+use crate::editor::{
+    LEFT_PANEL_WIDTH_PX, UI_BUTTON, UI_BUTTON_HOVER, UI_BUTTON_PRESS, UI_HIGHLIGHT, UI_PANEL, UI_TOP_RESERVED_PX,
+};
+
+/// UI 初始化时创建对话框（默认隐藏）。
+pub(super) fn spawn_replace_tile_dialog(commands: &mut Commands) {
+    commands
+        .spawn((
+            Button,
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(UI_TOP_RESERVED_PX + 60.0),
+                left: Val::Px(LEFT_PANEL_WIDTH_PX + 60.0),
+                width: Val::Px(340.0),
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(10.0)),
+                row_gap: Val::Px(6.0),
+                ..default()
+            },
+            BackgroundColor(UI_PANEL),
+            ZIndex(3100),
+            Visibility::Hidden,
+            CanvasClickBlocker,
+            ReplaceTileDialogRoot,
+        ))
+        .with_children(|p| {
+            p.spawn((
+                Text::new("替换 Tile"),
+                TextFont { font_size: 13.0, ..default() },
+                TextColor(Color::WHITE),
+            ));
+            p.spawn((
+                Text::new("先在 palette 中选中 tile（或用吸管），再点“取选中”"),
+                TextFont { font_size: 12.0, ..default() },
+                TextColor(Color::srgba(1.0, 1.0, 1.0, 0.75)),
+            ));
+            p.spawn((
+                Text::new(""),
+                TextFont { font_size: 12.0, ..default() },
+                TextColor(Color::WHITE),
+                ReplaceTileSummaryText,
+            ));
+            button_row(p, &[("源 = 取选中", ReplaceTileAction::PickFrom), ("目标 = 取选中", ReplaceTileAction::PickTo), ("目标 = 空", ReplaceTileAction::ClearTo)]);
+            button_row(p, &[("忽略朝向", ReplaceTileAction::ToggleOrientation), ("图层范围", ReplaceTileAction::CycleLayers), ("仅选区", ReplaceTileAction::ToggleSelection)]);
+            button_row(p, &[("替换", ReplaceTileAction::Apply), ("关闭", ReplaceTileAction::Close)]);
+        });
+}
+
+fn button_row(p: &mut ChildSpawnerCommands, buttons: &[(&str, ReplaceTileAction)]) {
+    p.spawn(Node {
+        flex_direction: FlexDirection::Row,
+        column_gap: Val::Px(6.0),
+        ..default()
+    })
+    .with_children(|p| {
+        for &(label, action) in buttons {
+            p.spawn((
+                Button,
+                Node {
+                    height: Val::Px(24.0),
+                    padding: UiRect::axes(Val::Px(8.0), Val::Px(2.0)),
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                BackgroundColor(UI_BUTTON),
+                CanvasClickBlocker,
+                ReplaceTileButton(action),
+            ))
+            .with_children(|p| {
+                p.spawn((
+                    Text::new(label),
+                    TextFont { font_size: 12.0, ..default() },
+                    TextColor(Color::WHITE),
+                ));
+            });
+        }
+    });
+}
+
+/// 按钮：设置源 / 目标、切换选项、执行替换或关闭。
+pub fn replace_tile_dialog_button_click(
+    mut dialog: ResMut<ReplaceTileDialogState>,
+    button_q: Query<(&Interaction, &ReplaceTileButton), Changed<Interaction>>,
+    (lib, editor_state): (Res<TilesetLibrary>, Res<EditorState>),
+    (layer_state, selection): (Res<LayerState>, Res<SelectionState>),
+    map: Option<ResMut<TileMapData>>,
+    mut undo: ResMut<UndoStack>,
+    (mut render, config): (TilemapRenderParams, Res<EditorConfig>),
+) {
+    let Some(action) = button_q
+        .iter()
+        .find(|(interaction, _)| **interaction == Interaction::Pressed)
+        .map(|(_, button)| button.0)
+    else {
+        return;
+    };
+    let selected = || {
+        lib.active_id.clone().map(|tileset_id| TileRef {
+            tileset_id,
+            index: editor_state.selected_tile,
+            rot: 0,
+            flip_x: false,
+            flip_y: false,
+        })
+    };
+    match action {
+        ReplaceTileAction::PickFrom => dialog.from = selected(),
+        ReplaceTileAction::PickTo => dialog.to = selected(),
+        ReplaceTileAction::ClearTo => dialog.to = None,
+        ReplaceTileAction::ToggleOrientation => dialog.ignore_orientation = !dialog.ignore_orientation,
+        ReplaceTileAction::CycleLayers => dialog.layers = dialog.layers.next(),
+        ReplaceTileAction::ToggleSelection => dialog.selection_only = !dialog.selection_only,
+        ReplaceTileAction::Close => dialog.open = false,
+        ReplaceTileAction::Apply => {
+            let Some(mut map) = map else {
+                return;
+            };
+            let Some(from) = dialog.from.clone() else {
+                warn!("replace tile: no source tile");
+                return;
+            };
+            if dialog.selection_only && selection.rect.is_none() {
+                warn!("replace tile: no selection");
+                return;
+            }
+            let area = |x, y| !dialog.selection_only || selection.contains(IVec2::new(x, y));
+            // 锁定的图层不参与替换。
+            let active = layer_state.active.min(map.layers.saturating_sub(1));
+            let layers: Vec<u32> = (0..map.layers)
+                .filter(|&l| {
+                    let data = map.layer_data.get(l as usize);
+                    let wanted = match dialog.layers {
+                        ReplaceLayers::Active => l == active,
+                        ReplaceLayers::Visible => data.is_none_or(|d| d.visible),
+                        ReplaceLayers::All => true,
+                    };
+                    wanted && !data.is_some_and(|d| d.locked)
+                })
+                .collect();
+            let changes = ops::replace_tiles(&mut map, &layers, area, &from, dialog.to.as_ref(), dialog.ignore_orientation);
+            info!("replace tile: {} cells changed", changes.len());
+            dialog.replaced = Some(changes.len());
+            if !changes.is_empty() {
+                render_cell_changes(&mut render, &config, &map, &changes);
+                undo.push(EditCommand { changes, ..Default::default() });
+            }
+        }
+    }
+}
+
+fn tile_label(tile: Option<&TileRef>) -> String {
+    match tile {
+        Some(t) => format!("{} #{}", t.tileset_id, t.index),
+        None => "空".to_string(),
+    }
+}
+
+/// 显示 / 隐藏对话框，刷新摘要与开关按钮的样式。
+pub fn update_replace_tile_dialog(
+    dialog: Res<ReplaceTileDialogState>,
+    mut root_q: Query<&mut Visibility, With<ReplaceTileDialogRoot>>,
+    mut summary_q: Query<&mut Text, With<ReplaceTileSummaryText>>,
+    mut button_q: Query<(&ReplaceTileButton, &Interaction, &mut BackgroundColor)>,
+) {
+    if dialog.is_changed() {
+        if let Ok(mut vis) = root_q.single_mut() {
+            *vis = if dialog.open { Visibility::Visible } else { Visibility::Hidden };
+        }
+        let from = dialog.from.as_ref().map_or("（未设置）".to_string(), |t| tile_label(Some(t)));
+        let mut summary = format!(
+            "源: {from}\n目标: {}\n范围: {}{} | 忽略朝向: {}",
+            tile_label(dialog.to.as_ref()),
+            dialog.layers.label(),
+            if dialog.selection_only { "（仅选区）" } else { "" },
+            if dialog.ignore_orientation { "开" } else { "关" },
+        );
+        if let Some(count) = dialog.replaced {
+            summary.push_str(&format!("\n已替换 {count} 格"));
+        }
+        for mut t in summary_q.iter_mut() {
+            *t = Text::new(summary.clone());
+        }
+    }
+    if !dialog.open {
+        return;
+    }
+    for (button, interaction, mut bg) in button_q.iter_mut() {
+        let on = match button.0 {
+            ReplaceTileAction::ToggleOrientation => dialog.ignore_orientation,
+            ReplaceTileAction::ToggleSelection => dialog.selection_only,
+            ReplaceTileAction::ClearTo => dialog.to.is_none(),
+            _ => false,
+        };
+        *bg = match *interaction {
+            _ if on => BackgroundColor(UI_HIGHLIGHT),
+            Interaction::Pressed => BackgroundColor(UI_BUTTON_PRESS),
+            Interaction::Hovered => BackgroundColor(UI_BUTTON_HOVER),
+            Interaction::None => BackgroundColor(UI_BUTTON),
+        };
+    }
+}
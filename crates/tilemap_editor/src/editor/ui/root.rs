@@ -13,10 +13,15 @@ use crate::editor::types::{
 	PaletteRoot, PaletteScroll,
 	PaletteSearchClearButton, PaletteSearchField, PaletteSearchText,
 	PaletteZoomButton, PaletteZoomLevel,
+	AutotileLabel, AutotileToggleButton,
+	MetaBrushAction, MetaBrushButton, MetaBrushLabel, PASSAGE_DIRECTIONS,
+	SampleSourceButton, SampleSourceLabel,
 	ShiftModeButton, ShiftModeLabel,
 	TilesetBar, TilesetCategoryCycleButton, TilesetCategoryLabel, TilesetMenuRoot, TilesetToggleButton,
 	ToolButton, ToolKind,
 	UiRoot,
+	VariationBrushAction, VariationBrushButton, VariationBrushLabel,
+	WangBrushAction, WangBrushButton, WangBrushLabel,
 };
 use crate::editor::MENUBAR_HEIGHT_PX;
 
@@ -45,6 +50,12 @@ pub fn setup_ui(mut commands: Commands) {
 
 	spawn_ui_root(&mut commands);
 	super::context_menu::spawn_context_menu(&mut commands);
+	super::problems::spawn_problems_panel(&mut commands);
+	super::relink::spawn_relink_dialog(&mut commands);
+	super::tileset_grid::spawn_tileset_grid_dialog(&mut commands);
+	super::replace_tile::spawn_replace_tile_dialog(&mut commands);
+	super::tile_animation::spawn_tile_animation_editor(&mut commands);
+	super::tile_collision::spawn_tile_collision_editor(&mut commands);
 }
 
 fn spawn_ui_root(commands: &mut Commands) {
@@ -265,6 +276,29 @@ fn spawn_ui_root(commands: &mut Commands) {
 			));
 		});
 
+		p.spawn((
+			Button,
+			Node {
+				height: Val::Px(36.0),
+				padding: UiRect::axes(Val::Px(10.0), Val::Px(6.0)),
+				align_items: AlignItems::Center,
+				justify_content: JustifyContent::Center,
+				..default()
+			},
+			BackgroundColor(UI_BUTTON),
+			ToolButton(ToolKind::Line),
+		))
+		.with_children(|p| {
+			p.spawn((
+				Text::new("直线(7)"),
+				TextFont {
+					font_size: 14.0,
+					..default()
+				},
+				TextColor(Color::WHITE),
+			));
+		});
+
 		// 不再提供“粘贴工具”按钮：粘贴通过 Ctrl+V / 右键菜单进入，避免与菜单重复。
 
 		p.spawn((
@@ -289,6 +323,54 @@ fn spawn_ui_root(commands: &mut Commands) {
 				TextColor(Color::WHITE),
 			));
 		});
+
+		p.spawn((
+			Button,
+			Node {
+				height: Val::Px(36.0),
+				padding: UiRect::axes(Val::Px(10.0), Val::Px(6.0)),
+				align_items: AlignItems::Center,
+				justify_content: JustifyContent::Center,
+				..default()
+			},
+			BackgroundColor(UI_BUTTON),
+			ToolButton(ToolKind::MagicWand),
+		))
+		.with_children(|p| {
+			p.spawn((
+				Text::new("魔棒(8)"),
+				TextFont {
+					font_size: 14.0,
+					..default()
+				},
+				TextColor(Color::WHITE),
+			));
+		});
+
+		// 油漆桶 / 吸管 / 魔棒的取样图层（文字随当前工具变化）
+		p.spawn((
+			Button,
+			Node {
+				height: Val::Px(36.0),
+				padding: UiRect::axes(Val::Px(10.0), Val::Px(6.0)),
+				align_items: AlignItems::Center,
+				justify_content: JustifyContent::Center,
+				..default()
+			},
+			BackgroundColor(UI_BUTTON),
+			SampleSourceButton,
+		))
+		.with_children(|p| {
+			p.spawn((
+				Text::new("取样: -"),
+				TextFont {
+					font_size: 14.0,
+					..default()
+				},
+				TextColor(Color::WHITE),
+				SampleSourceLabel,
+			));
+		});
 	});
 
 	let tileset_bar = commands
@@ -567,6 +649,83 @@ fn spawn_ui_root(commands: &mut Commands) {
 				ShiftModeLabel,
 			));
 		});
+
+		// 元数据（区域 / 通行 / 地形）：开启后绘制工具改写元数据而不是 tile
+		p.spawn((
+			Text::new("元数据:"),
+			TextFont {
+				font_size: 13.0,
+				..default()
+			},
+			TextColor(Color::WHITE),
+		));
+		spawn_meta_brush_button(p, "关", MetaBrushAction::ToggleMode);
+		spawn_meta_brush_button(p, "字段", MetaBrushAction::CycleField);
+		spawn_meta_brush_button(p, "-", MetaBrushAction::Step(-1));
+		spawn_meta_brush_button(p, "+", MetaBrushAction::Step(1));
+		for (bit, arrow) in PASSAGE_DIRECTIONS {
+			spawn_meta_brush_button(p, arrow, MetaBrushAction::TogglePassage(bit));
+		}
+
+		// 自动图块：开启后绘制工具按当前 tileset 的自动图块组绘制（连带更新邻居）
+		p.spawn((
+			Text::new("自动图块:"),
+			TextFont {
+				font_size: 13.0,
+				..default()
+			},
+			TextColor(Color::WHITE),
+		));
+		p.spawn((
+			Button,
+			Node {
+				height: Val::Px(28.0),
+				padding: UiRect::axes(Val::Px(8.0), Val::Px(4.0)),
+				align_items: AlignItems::Center,
+				justify_content: JustifyContent::Center,
+				..default()
+			},
+			BackgroundColor(UI_BUTTON),
+			AutotileToggleButton,
+		))
+		.with_children(|p| {
+			p.spawn((
+				Text::new("关"),
+				TextFont {
+					font_size: 13.0,
+					..default()
+				},
+				TextColor(Color::WHITE),
+				AutotileLabel,
+			));
+		});
+
+		// Wang 地形：开启后绘制工具用当前颜色涂角 / 边，并挑选匹配的过渡 tile
+		p.spawn((
+			Text::new("Wang 地形:"),
+			TextFont {
+				font_size: 13.0,
+				..default()
+			},
+			TextColor(Color::WHITE),
+		));
+		spawn_wang_brush_button(p, "关", WangBrushAction::ToggleMode);
+		spawn_wang_brush_button(p, "集合", WangBrushAction::CycleSet);
+		spawn_wang_brush_button(p, "颜色", WangBrushAction::CycleColor);
+
+		// 随机变体：开启后绘制工具为每格从当前变体集按权重挑 tile
+		p.spawn((
+			Text::new("随机:"),
+			TextFont {
+				font_size: 13.0,
+				..default()
+			},
+			TextColor(Color::WHITE),
+		));
+		spawn_variation_brush_button(p, "关", VariationBrushAction::ToggleMode);
+		spawn_variation_brush_button(p, "集合", VariationBrushAction::CycleSet);
+		spawn_variation_brush_button(p, "旋转", VariationBrushAction::ToggleRotation);
+		spawn_variation_brush_button(p, "翻转", VariationBrushAction::ToggleFlip);
 	});
 
 	// 悬浮：地图右上角图层切换（不是顶栏）
@@ -834,3 +993,89 @@ fn spawn_ui_root(commands: &mut Commands) {
 	commands.entity(root).add_child(menubar);
 	commands.entity(root).add_child(main_row);
 }
+
+/// 顶栏的元数据笔刷按钮；开关按钮的文字同时显示当前字段与值。
+fn spawn_wang_brush_button(p: &mut ChildSpawnerCommands, label: &str, action: WangBrushAction) {
+	p.spawn((
+		Button,
+		Node {
+			height: Val::Px(28.0),
+			padding: UiRect::axes(Val::Px(8.0), Val::Px(4.0)),
+			align_items: AlignItems::Center,
+			justify_content: JustifyContent::Center,
+			..default()
+		},
+		BackgroundColor(UI_BUTTON),
+		WangBrushButton(action),
+	))
+	.with_children(|p| {
+		let mut text = p.spawn((
+			Text::new(label),
+			TextFont {
+				font_size: 13.0,
+				..default()
+			},
+			TextColor(Color::WHITE),
+		));
+		if action == WangBrushAction::ToggleMode {
+			text.insert(WangBrushLabel);
+		}
+	});
+}
+
+/// 顶栏的随机变体按钮；开关按钮的文字显示当前变体集。
+fn spawn_variation_brush_button(p: &mut ChildSpawnerCommands, label: &str, action: VariationBrushAction) {
+	p.spawn((
+		Button,
+		Node {
+			height: Val::Px(28.0),
+			padding: UiRect::axes(Val::Px(8.0), Val::Px(4.0)),
+			align_items: AlignItems::Center,
+			justify_content: JustifyContent::Center,
+			..default()
+		},
+		BackgroundColor(UI_BUTTON),
+		VariationBrushButton(action),
+	))
+	.with_children(|p| {
+		let mut text = p.spawn((
+			Text::new(label),
+			TextFont {
+				font_size: 13.0,
+				..default()
+			},
+			TextColor(Color::WHITE),
+		));
+		if action == VariationBrushAction::ToggleMode {
+			text.insert(VariationBrushLabel);
+		}
+	});
+}
+
+fn spawn_meta_brush_button(p: &mut ChildSpawnerCommands, label: &str, action: MetaBrushAction) {
+	p.spawn((
+		Button,
+		Node {
+			height: Val::Px(28.0),
+			padding: UiRect::axes(Val::Px(8.0), Val::Px(4.0)),
+			align_items: AlignItems::Center,
+			justify_content: JustifyContent::Center,
+			..default()
+		},
+		BackgroundColor(UI_BUTTON),
+		MetaBrushButton(action),
+	))
+	.with_children(|p| {
+		let mut text = p.spawn((
+			Text::new(label),
+			TextFont {
+				font_size: 13.0,
+				..default()
+			},
+			TextColor(Color::WHITE),
+		));
+		if action == MetaBrushAction::ToggleMode {
+			text.insert(MetaBrushLabel);
+		}
+	});
+}
@@ -0,0 +1,275 @@
+//! tile 动画编辑器：为当前选中的 tile 编排帧序列（点击 palette 追加帧），保存到 tileset 库。
+
+use bevy::prelude::*;
+
+use crate::editor::tileset::{rect_for_tile_index, save_tileset_library};
+use crate::editor::types::{
+    AnimationEditorAction, AnimationEditorButton, AnimationEditorRoot, AnimationEditorTitleText,
+    AnimationFrameList, AnimationPreviewImage, CanvasClickBlocker, TileAnimationClock,
+    TileAnimationEditorState, TilesetLibrary, TilesetRuntime,
+};
+use crate::editor::util::despawn_silently;
+use crate::editor::{UI_BUTTON, UI_BUTTON_HOVER, UI_BUTTON_PRESS, UI_PANEL, UI_TOP_RESERVED_PX};
+
+const PREVIEW_PX: f32 = 64.0;
+const FRAME_THUMB_PX: f32 = 24.0;
+
+/// UI 初始化时创建编辑器面板（默认隐藏；放在右侧，不挡住左侧 palette）。
+pub(super) fn spawn_tile_animation_editor(commands: &mut Commands) {
+    commands
+        .spawn((
+            Button,
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(UI_TOP_RESERVED_PX + 60.0),
+                right: Val::Px(10.0),
+                width: Val::Px(300.0),
+                max_height: Val::Px(480.0),
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(8.0)),
+                row_gap: Val::Px(6.0),
+                ..default()
+            },
+            BackgroundColor(UI_PANEL),
+            ZIndex(3050),
+            Visibility::Hidden,
+            CanvasClickBlocker,
+            AnimationEditorRoot,
+        ))
+        .with_children(|p| {
+            p.spawn((
+                Text::new("Tile 动画"),
+                TextFont { font_size: 13.0, ..default() },
+                TextColor(Color::WHITE),
+                AnimationEditorTitleText,
+            ));
+            p.spawn((
+                Text::new("点击左侧 palette 中的 tile 追加一帧"),
+                TextFont { font_size: 12.0, ..default() },
+                TextColor(Color::srgba(1.0, 1.0, 1.0, 0.75)),
+            ));
+            p.spawn((
+                ImageNode::default(),
+                Node {
+                    width: Val::Px(PREVIEW_PX),
+                    height: Val::Px(PREVIEW_PX),
+                    align_self: AlignSelf::Center,
+                    ..default()
+                },
+                AnimationPreviewImage,
+            ));
+            p.spawn((
+                Node {
+                    width: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(4.0),
+                    overflow: Overflow::scroll_y(),
+                    min_height: Val::Px(0.0),
+                    ..default()
+                },
+                ScrollPosition::default(),
+                AnimationFrameList,
+            ));
+            p.spawn(Node {
+                flex_direction: FlexDirection::Row,
+                column_gap: Val::Px(6.0),
+                ..default()
+            })
+            .with_children(|p| {
+                spawn_button(p, "清空", AnimationEditorAction::Clear);
+                spawn_button(p, "保存", AnimationEditorAction::Save);
+                spawn_button(p, "取消", AnimationEditorAction::Cancel);
+            });
+        });
+}
+
+fn spawn_button(p: &mut ChildSpawnerCommands, label: &str, action: AnimationEditorAction) {
+    p.spawn((
+        Button,
+        Node {
+            height: Val::Px(22.0),
+            padding: UiRect::axes(Val::Px(8.0), Val::Px(2.0)),
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        BackgroundColor(UI_BUTTON),
+        CanvasClickBlocker,
+        AnimationEditorButton(action),
+    ))
+    .with_children(|p| {
+        p.spawn((
+            Text::new(label),
+            TextFont { font_size: 12.0, ..default() },
+            TextColor(Color::WHITE),
+        ));
+    });
+}
+
+/// 按钮：调整帧时长 / 删除帧 / 清空 / 保存 / 取消。
+pub fn tile_animation_editor_button_click(
+    mut dialog: ResMut<TileAnimationEditorState>,
+    mut lib: ResMut<TilesetLibrary>,
+    mut runtime: ResMut<TilesetRuntime>,
+    mut button_q: Query<(&Interaction, &AnimationEditorButton, &mut BackgroundColor), Changed<Interaction>>,
+) {
+    let mut requested = None;
+    for (interaction, button, mut bg) in button_q.iter_mut() {
+        match *interaction {
+            Interaction::Pressed => {
+                *bg = BackgroundColor(UI_BUTTON_PRESS);
+                requested = Some(button.0);
+            }
+            Interaction::Hovered => *bg = BackgroundColor(UI_BUTTON_HOVER),
+            Interaction::None => *bg = BackgroundColor(UI_BUTTON),
+        }
+    }
+    let Some(action) = requested else {
+        return;
+    };
+    if action != AnimationEditorAction::Save {
+        dialog.apply(action);
+        return;
+    }
+
+    let Some(id) = dialog.tileset_id.clone() else {
+        dialog.close();
+        return;
+    };
+    let Some(entry) = lib.entries.iter_mut().find(|e| e.id == id) else {
+        warn!("tileset {id} is no longer in the library; animation not saved");
+        dialog.close();
+        return;
+    };
+    let (index, frames) = (dialog.tile_index, dialog.frames.clone());
+    entry.set_animation(index, frames.clone());
+    save_tileset_library(&lib);
+    info!("tileset {id} tile {index}: {} animation frames", frames.len());
+
+    // runtime 变化会触发地图全量重建，tile 实体按新的动画定义重新标记。
+    if let Some(rt) = runtime.by_id.get_mut(&id) {
+        if frames.is_empty() {
+            rt.animations.remove(&index);
+        } else {
+            rt.animations.insert(index, frames);
+        }
+    }
+    dialog.close();
+}
+
+/// 显示 / 隐藏面板并刷新标题。
+pub fn tile_animation_editor_visibility(
+    dialog: Res<TileAnimationEditorState>,
+    lib: Res<TilesetLibrary>,
+    mut root_q: Query<&mut Visibility, With<AnimationEditorRoot>>,
+    mut title_q: Query<&mut Text, With<AnimationEditorTitleText>>,
+) {
+    if !dialog.is_changed() {
+        return;
+    }
+    if let Ok(mut vis) = root_q.single_mut() {
+        *vis = if dialog.open { Visibility::Visible } else { Visibility::Hidden };
+    }
+    let name = dialog
+        .tileset_id
+        .as_ref()
+        .and_then(|id| lib.entries.iter().find(|e| e.id == *id))
+        .map(|e| e.name.as_str())
+        .unwrap_or("");
+    for mut t in title_q.iter_mut() {
+        *t = Text::new(format!("Tile 动画：{name} #{}", dialog.tile_index));
+    }
+}
+
+/// 帧序列变化后重建帧列表。
+pub fn rebuild_tile_animation_frames_when_needed(
+    mut commands: Commands,
+    mut dialog: ResMut<TileAnimationEditorState>,
+    runtime: Res<TilesetRuntime>,
+    list_q: Query<Entity, With<AnimationFrameList>>,
+    children_q: Query<&Children>,
+) {
+    if !dialog.rebuild {
+        return;
+    }
+    let Ok(list) = list_q.single() else {
+        return;
+    };
+    dialog.rebuild = false;
+
+    if let Ok(children) = children_q.get(list) {
+        for child in children.iter() {
+            despawn_silently(&mut commands, child);
+        }
+    }
+    if !dialog.open {
+        return;
+    }
+
+    let rt = dialog.tileset_id.as_ref().and_then(|id| runtime.by_id.get(id));
+    commands.entity(list).with_children(|p| {
+        if dialog.frames.is_empty() {
+            p.spawn((
+                Text::new("（没有帧：保存后该 tile 为静态 tile）"),
+                TextFont { font_size: 12.0, ..default() },
+                TextColor(Color::srgba(1.0, 1.0, 1.0, 0.75)),
+            ));
+            return;
+        }
+        for (i, frame) in dialog.frames.iter().enumerate() {
+            p.spawn(Node {
+                width: Val::Percent(100.0),
+                flex_direction: FlexDirection::Row,
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(6.0),
+                ..default()
+            })
+            .with_children(|p| {
+                if let Some(rt) = rt {
+                    let rect = rect_for_tile_index(frame.index, rt.columns.max(1), rt.grid);
+                    p.spawn((
+                        ImageNode::new(rt.texture.clone()).with_rect(rect),
+                        Node {
+                            width: Val::Px(FRAME_THUMB_PX),
+                            height: Val::Px(FRAME_THUMB_PX),
+                            ..default()
+                        },
+                    ));
+                }
+                p.spawn((
+                    Text::new(format!("{}. #{}  {}ms", i + 1, frame.index, frame.duration_ms)),
+                    TextFont { font_size: 12.0, ..default() },
+                    TextColor(Color::WHITE),
+                    Node { flex_grow: 1.0, ..default() },
+                ));
+                spawn_button(p, "-", AnimationEditorAction::Shorter(i));
+                spawn_button(p, "+", AnimationEditorAction::Longer(i));
+                spawn_button(p, "×", AnimationEditorAction::Remove(i));
+            });
+        }
+    });
+}
+
+/// 面板里的预览按正在编辑的帧序列播放（未保存也能看到效果）。
+pub fn animate_tile_animation_preview(
+    clock: Res<TileAnimationClock>,
+    dialog: Res<TileAnimationEditorState>,
+    runtime: Res<TilesetRuntime>,
+    mut preview_q: Query<&mut ImageNode, With<AnimationPreviewImage>>,
+) {
+    if !dialog.open {
+        return;
+    }
+    let Some(rt) = dialog.tileset_id.as_ref().and_then(|id| runtime.by_id.get(id)) else {
+        return;
+    };
+    let index = tilemap_format::animation_frame_at(&dialog.frames, clock.elapsed_ms).unwrap_or(dialog.tile_index);
+    let rect = rect_for_tile_index(index, rt.columns.max(1), rt.grid);
+    for mut node in preview_q.iter_mut() {
+        if node.image != rt.texture {
+            node.image = rt.texture.clone();
+        }
+        if node.rect != Some(rect) {
+            node.rect = Some(rect);
+        }
+    }
+}
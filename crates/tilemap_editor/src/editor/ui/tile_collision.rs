@@ -0,0 +1,368 @@
+//! tile 碰撞编辑器：放大显示选中的 tile，添加预设形状并拖动顶点编辑，保存到 tileset 库。
+
+use bevy::prelude::*;
+use bevy::ui::RelativeCursorPosition;
+
+use crate::editor::tileset::{rect_for_tile_index, save_tileset_library};
+use crate::editor::types::{
+    CanvasClickBlocker, CollisionEditorAction, CollisionEditorButton, CollisionEditorRoot, CollisionEditorTitleText,
+    CollisionPreset, CollisionShape, CollisionShapeList, CollisionShapeOverlay, CollisionTileImage,
+    CollisionVertexHandle, TileCollisionEditorState, TilesetLibrary, TilesetRuntime,
+};
+use crate::editor::util::despawn_silently;
+use crate::editor::{UI_BUTTON, UI_BUTTON_HOVER, UI_BUTTON_PRESS, UI_PANEL, UI_TOP_RESERVED_PX};
+
+/// 放大后的 tile 边长。
+const TILE_PX: f32 = 192.0;
+const HANDLE_PX: f32 = 10.0;
+const EDGE_PX: f32 = 2.0;
+
+const SHAPE_COLOR: Color = Color::srgba(1.0, 0.55, 0.1, 0.55);
+const SELECTED_SHAPE_COLOR: Color = Color::srgb(1.0, 0.6, 0.1);
+const HANDLE_COLOR: Color = Color::srgb(1.0, 1.0, 1.0);
+const SELECTED_HANDLE_COLOR: Color = Color::srgb(1.0, 0.9, 0.2);
+
+/// UI 初始化时创建编辑器面板（默认隐藏；与动画编辑器一样放在右侧）。
+pub(super) fn spawn_tile_collision_editor(commands: &mut Commands) {
+    commands
+        .spawn((
+            Button,
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(UI_TOP_RESERVED_PX + 60.0),
+                right: Val::Px(10.0),
+                width: Val::Px(300.0),
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(8.0)),
+                row_gap: Val::Px(6.0),
+                ..default()
+            },
+            BackgroundColor(UI_PANEL),
+            ZIndex(3060),
+            Visibility::Hidden,
+            CanvasClickBlocker,
+            CollisionEditorRoot,
+        ))
+        .with_children(|p| {
+            p.spawn((
+                Text::new("Tile 碰撞"),
+                TextFont { font_size: 13.0, ..default() },
+                TextColor(Color::WHITE),
+                CollisionEditorTitleText,
+            ));
+            p.spawn((
+                Text::new("拖动顶点编辑选中的形状（吸附到 1/16 格）"),
+                TextFont { font_size: 12.0, ..default() },
+                TextColor(Color::srgba(1.0, 1.0, 1.0, 0.75)),
+            ));
+            p.spawn((
+                ImageNode::default(),
+                Node {
+                    width: Val::Px(TILE_PX),
+                    height: Val::Px(TILE_PX),
+                    align_self: AlignSelf::Center,
+                    ..default()
+                },
+                BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.4)),
+                RelativeCursorPosition::default(),
+                CollisionTileImage,
+            ))
+            .with_children(|p| {
+                p.spawn((
+                    Node {
+                        position_type: PositionType::Absolute,
+                        left: Val::Px(0.0),
+                        top: Val::Px(0.0),
+                        width: Val::Px(TILE_PX),
+                        height: Val::Px(TILE_PX),
+                        ..default()
+                    },
+                    CollisionShapeOverlay,
+                ));
+            });
+            p.spawn(Node {
+                flex_direction: FlexDirection::Row,
+                flex_wrap: FlexWrap::Wrap,
+                column_gap: Val::Px(4.0),
+                row_gap: Val::Px(4.0),
+                ..default()
+            })
+            .with_children(|p| {
+                for preset in CollisionPreset::ALL {
+                    spawn_button(p, preset.label(), CollisionEditorAction::Add(preset));
+                }
+            });
+            p.spawn((
+                Node {
+                    width: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Row,
+                    flex_wrap: FlexWrap::Wrap,
+                    column_gap: Val::Px(4.0),
+                    row_gap: Val::Px(4.0),
+                    ..default()
+                },
+                CollisionShapeList,
+            ));
+            p.spawn(Node {
+                flex_direction: FlexDirection::Row,
+                flex_wrap: FlexWrap::Wrap,
+                column_gap: Val::Px(6.0),
+                row_gap: Val::Px(4.0),
+                ..default()
+            })
+            .with_children(|p| {
+                spawn_button(p, "删除形状", CollisionEditorAction::DeleteShape);
+                spawn_button(p, "加顶点", CollisionEditorAction::AddVertex);
+                spawn_button(p, "删顶点", CollisionEditorAction::RemoveVertex);
+                spawn_button(p, "保存", CollisionEditorAction::Save);
+                spawn_button(p, "取消", CollisionEditorAction::Cancel);
+            });
+        });
+}
+
+fn spawn_button(p: &mut ChildSpawnerCommands, label: &str, action: CollisionEditorAction) {
+    p.spawn((
+        Button,
+        Node {
+            height: Val::Px(22.0),
+            padding: UiRect::axes(Val::Px(8.0), Val::Px(2.0)),
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        BackgroundColor(UI_BUTTON),
+        CanvasClickBlocker,
+        CollisionEditorButton(action),
+    ))
+    .with_children(|p| {
+        p.spawn((
+            Text::new(label),
+            TextFont { font_size: 12.0, ..default() },
+            TextColor(Color::WHITE),
+        ));
+    });
+}
+
+/// 按钮：添加预设 / 选择形状 / 删除 / 增删顶点 / 保存 / 取消。
+pub fn tile_collision_editor_button_click(
+    mut dialog: ResMut<TileCollisionEditorState>,
+    mut lib: ResMut<TilesetLibrary>,
+    mut button_q: Query<(&Interaction, &CollisionEditorButton, &mut BackgroundColor), Changed<Interaction>>,
+) {
+    let mut requested = None;
+    for (interaction, button, mut bg) in button_q.iter_mut() {
+        match *interaction {
+            Interaction::Pressed => {
+                *bg = BackgroundColor(UI_BUTTON_PRESS);
+                requested = Some(button.0);
+            }
+            Interaction::Hovered => *bg = BackgroundColor(UI_BUTTON_HOVER),
+            Interaction::None => *bg = BackgroundColor(UI_BUTTON),
+        }
+    }
+    let Some(action) = requested else {
+        return;
+    };
+    if action != CollisionEditorAction::Save {
+        dialog.apply(action);
+        return;
+    }
+
+    let Some(id) = dialog.tileset_id.clone() else {
+        dialog.close();
+        return;
+    };
+    let Some(entry) = lib.entries.iter_mut().find(|e| e.id == id) else {
+        warn!("tileset {id} is no longer in the library; collision not saved");
+        dialog.close();
+        return;
+    };
+    let index = dialog.tile_index;
+    let shapes = dialog.shapes_to_save();
+    let count = shapes.len();
+    entry.set_collision(index, shapes);
+    save_tileset_library(&lib);
+    info!("tileset {id} tile {index}: {count} collision shapes");
+    dialog.close();
+}
+
+/// 拖动顶点：按下手柄开始拖动，按住左键时跟随光标，松开结束。
+pub fn tile_collision_editor_drag(
+    mut dialog: ResMut<TileCollisionEditorState>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    handle_q: Query<(&Interaction, &CollisionVertexHandle), Changed<Interaction>>,
+    image_q: Query<&RelativeCursorPosition, With<CollisionTileImage>>,
+) {
+    if !dialog.open {
+        return;
+    }
+    for (interaction, handle) in handle_q.iter() {
+        if *interaction == Interaction::Pressed {
+            dialog.grab_vertex(handle.shape, handle.vertex);
+        }
+    }
+    if !dialog.dragging {
+        return;
+    }
+    if !mouse.pressed(MouseButton::Left) {
+        dialog.dragging = false;
+        return;
+    }
+    // normalized：节点左上角 (-0.5, -0.5)，右下角 (0.5, 0.5)；tile 坐标 y 向上。
+    let Some(n) = image_q.single().ok().and_then(|rel| rel.normalized) else {
+        return;
+    };
+    dialog.move_selected_vertex(Vec2::new(n.x + 0.5, 0.5 - n.y));
+}
+
+/// 显示 / 隐藏面板，刷新标题与放大的 tile 图。
+pub fn tile_collision_editor_visibility(
+    dialog: Res<TileCollisionEditorState>,
+    lib: Res<TilesetLibrary>,
+    runtime: Res<TilesetRuntime>,
+    mut root_q: Query<&mut Visibility, With<CollisionEditorRoot>>,
+    mut title_q: Query<&mut Text, With<CollisionEditorTitleText>>,
+    mut image_q: Query<&mut ImageNode, With<CollisionTileImage>>,
+) {
+    if !dialog.is_changed() {
+        return;
+    }
+    if let Ok(mut vis) = root_q.single_mut() {
+        let want = if dialog.open { Visibility::Visible } else { Visibility::Hidden };
+        if *vis != want {
+            *vis = want;
+        }
+    }
+    if !dialog.open {
+        return;
+    }
+    let name = dialog
+        .tileset_id
+        .as_ref()
+        .and_then(|id| lib.entries.iter().find(|e| e.id == *id))
+        .map(|e| e.name.as_str())
+        .unwrap_or("");
+    for mut t in title_q.iter_mut() {
+        let text = format!("Tile 碰撞：{name} #{}", dialog.tile_index);
+        if t.0 != text {
+            *t = Text::new(text);
+        }
+    }
+    let Some(rt) = dialog.tileset_id.as_ref().and_then(|id| runtime.by_id.get(id)) else {
+        return;
+    };
+    let rect = rect_for_tile_index(dialog.tile_index, rt.columns.max(1), rt.grid);
+    for mut node in image_q.iter_mut() {
+        if node.image != rt.texture {
+            node.image = rt.texture.clone();
+        }
+        if node.rect != Some(rect) {
+            node.rect = Some(rect);
+        }
+    }
+}
+
+/// 形状变化后重建覆盖层（边 + 顶点手柄）与形状列表。
+pub fn rebuild_tile_collision_editor_when_needed(
+    mut commands: Commands,
+    mut dialog: ResMut<TileCollisionEditorState>,
+    overlay_q: Query<Entity, With<CollisionShapeOverlay>>,
+    list_q: Query<Entity, With<CollisionShapeList>>,
+    children_q: Query<&Children>,
+) {
+    if !dialog.rebuild {
+        return;
+    }
+    let (Ok(overlay), Ok(list)) = (overlay_q.single(), list_q.single()) else {
+        return;
+    };
+    dialog.rebuild = false;
+
+    for parent in [overlay, list] {
+        if let Ok(children) = children_q.get(parent) {
+            for child in children.iter() {
+                despawn_silently(&mut commands, child);
+            }
+        }
+    }
+    if !dialog.open {
+        return;
+    }
+
+    let to_px = |[x, y]: [f32; 2]| Vec2::new(x * TILE_PX, (1.0 - y) * TILE_PX);
+    commands.entity(overlay).with_children(|p| {
+        // 先画未选中的形状，选中的形状和它的手柄画在最上面。
+        let mut order: Vec<usize> = (0..dialog.shapes.len()).filter(|&i| Some(i) != dialog.selected_shape).collect();
+        order.extend(dialog.selected_shape.filter(|&i| i < dialog.shapes.len()));
+        for i in order {
+            let selected = Some(i) == dialog.selected_shape;
+            let points: Vec<Vec2> = dialog.shapes[i].points().into_iter().map(to_px).collect();
+            let color = if selected { SELECTED_SHAPE_COLOR } else { SHAPE_COLOR };
+            for (k, &a) in points.iter().enumerate() {
+                spawn_edge(p, a, points[(k + 1) % points.len()], color);
+            }
+            if !selected {
+                continue;
+            }
+            for (vertex, &pos) in points.iter().enumerate() {
+                let color = if dialog.selected_vertex == Some(vertex) { SELECTED_HANDLE_COLOR } else { HANDLE_COLOR };
+                p.spawn((
+                    Button,
+                    Node {
+                        position_type: PositionType::Absolute,
+                        left: Val::Px(pos.x - HANDLE_PX * 0.5),
+                        top: Val::Px(pos.y - HANDLE_PX * 0.5),
+                        width: Val::Px(HANDLE_PX),
+                        height: Val::Px(HANDLE_PX),
+                        ..default()
+                    },
+                    BackgroundColor(color),
+                    CanvasClickBlocker,
+                    CollisionVertexHandle { shape: i, vertex },
+                ));
+            }
+        }
+    });
+
+    commands.entity(list).with_children(|p| {
+        if dialog.shapes.is_empty() {
+            p.spawn((
+                Text::new("（没有形状：保存后该 tile 无碰撞）"),
+                TextFont { font_size: 12.0, ..default() },
+                TextColor(Color::srgba(1.0, 1.0, 1.0, 0.75)),
+            ));
+            return;
+        }
+        for (i, shape) in dialog.shapes.iter().enumerate() {
+            let label = match shape {
+                CollisionShape::Full => format!("{}. 整格", i + 1),
+                CollisionShape::Polygon(points) => format!("{}. 多边形 ({} 点)", i + 1, points.len()),
+            };
+            let label = if Some(i) == dialog.selected_shape { format!("▶ {label}") } else { label };
+            spawn_button(p, &label, CollisionEditorAction::Select(i));
+        }
+    });
+}
+
+/// 用旋转的细长节点画一条边（UI 坐标，y 向下）。
+fn spawn_edge(p: &mut ChildSpawnerCommands, a: Vec2, b: Vec2, color: Color) {
+    let d = b - a;
+    let len = d.length();
+    if len <= f32::EPSILON {
+        return;
+    }
+    let mid = (a + b) * 0.5;
+    p.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(mid.x - len * 0.5),
+            top: Val::Px(mid.y - EDGE_PX * 0.5),
+            width: Val::Px(len),
+            height: Val::Px(EDGE_PX),
+            ..default()
+        },
+        // y 向下时 atan2 的正角度即顺时针，与 UiTransform 的旋转方向一致。
+        UiTransform::from_rotation(Rot2::radians(d.y.atan2(d.x))),
+        BackgroundColor(color),
+        bevy::ui::FocusPolicy::Pass,
+    ));
+}
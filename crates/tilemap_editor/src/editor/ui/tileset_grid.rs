@@ -0,0 +1,329 @@
+//! tileset 切分对话框：导入后询问 tile 尺寸 / 外边距 / 间距，确定后按新参数重新切分图集。
+
+use bevy::prelude::*;
+
+use crate::editor::tileset::save_tileset_library;
+use crate::editor::types::{
+    CanvasClickBlocker, EditorConfig, PendingTileset, TilesetGridApplyButton, TilesetGridCancelButton,
+    TilesetGridDialogRoot, TilesetGridDialogState, TilesetGridField, TilesetGridFieldButton,
+    TilesetGridFieldText, TilesetGridSummaryText, TilesetGridTitleText, TilesetLibrary, TilesetLoading,
+    TilesetRuntime, UiState,
+};
+use crate::editor::{
+    LEFT_PANEL_WIDTH_PX, UI_BUTTON, UI_BUTTON_HOVER, UI_BUTTON_PRESS, UI_HIGHLIGHT, UI_PANEL,
+    UI_TOP_RESERVED_PX,
+};
+
+/// UI 初始化时创建对话框（默认隐藏）。
+pub(super) fn spawn_tileset_grid_dialog(commands: &mut Commands) {
+    commands
+        .spawn((
+            Button,
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(UI_TOP_RESERVED_PX + 60.0),
+                left: Val::Px(LEFT_PANEL_WIDTH_PX + 60.0),
+                width: Val::Px(320.0),
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(10.0)),
+                row_gap: Val::Px(6.0),
+                ..default()
+            },
+            BackgroundColor(UI_PANEL),
+            ZIndex(3100),
+            Visibility::Hidden,
+            CanvasClickBlocker,
+            TilesetGridDialogRoot,
+        ))
+        .with_children(|p| {
+            p.spawn((
+                Text::new("Tileset 切分"),
+                TextFont { font_size: 13.0, ..default() },
+                TextColor(Color::WHITE),
+                TilesetGridTitleText,
+            ));
+            for field in TilesetGridField::ALL {
+                p.spawn(Node {
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::Center,
+                    column_gap: Val::Px(8.0),
+                    ..default()
+                })
+                .with_children(|p| {
+                    p.spawn((
+                        Text::new(field.label()),
+                        TextFont { font_size: 12.0, ..default() },
+                        TextColor(Color::WHITE),
+                        Node { width: Val::Px(64.0), ..default() },
+                    ));
+                    p.spawn((
+                        Button,
+                        Node {
+                            width: Val::Px(80.0),
+                            height: Val::Px(22.0),
+                            padding: UiRect::axes(Val::Px(6.0), Val::Px(2.0)),
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        BackgroundColor(UI_BUTTON),
+                        CanvasClickBlocker,
+                        TilesetGridFieldButton(field),
+                    ))
+                    .with_children(|p| {
+                        p.spawn((
+                            Text::new(""),
+                            TextFont { font_size: 12.0, ..default() },
+                            TextColor(Color::WHITE),
+                            TilesetGridFieldText(field),
+                        ));
+                    });
+                    p.spawn((
+                        Text::new("px"),
+                        TextFont { font_size: 12.0, ..default() },
+                        TextColor(Color::srgba(1.0, 1.0, 1.0, 0.6)),
+                    ));
+                });
+            }
+            p.spawn((
+                Text::new(""),
+                TextFont { font_size: 12.0, ..default() },
+                TextColor(Color::srgba(1.0, 1.0, 1.0, 0.75)),
+                TilesetGridSummaryText,
+            ));
+            p.spawn(Node {
+                flex_direction: FlexDirection::Row,
+                column_gap: Val::Px(6.0),
+                ..default()
+            })
+            .with_children(|p| {
+                footer_button(p, "确定", TilesetGridApplyButton);
+                footer_button(p, "取消", TilesetGridCancelButton);
+            });
+        });
+}
+
+fn footer_button(p: &mut ChildSpawnerCommands, label: &str, marker: impl Component) {
+    p.spawn((
+        Button,
+        Node {
+            height: Val::Px(24.0),
+            padding: UiRect::axes(Val::Px(10.0), Val::Px(2.0)),
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        BackgroundColor(UI_BUTTON),
+        CanvasClickBlocker,
+        marker,
+    ))
+    .with_children(|p| {
+        p.spawn((
+            Text::new(label),
+            TextFont { font_size: 12.0, ..default() },
+            TextColor(Color::WHITE),
+        ));
+    });
+}
+
+/// 输入框聚焦、确定 / 取消按钮。
+pub fn tileset_grid_dialog_interactions(
+    mut dialog: ResMut<TilesetGridDialogState>,
+    mut field_q: Query<(&Interaction, &TilesetGridFieldButton), Changed<Interaction>>,
+    mut footer_q: Query<
+        (&Interaction, Has<TilesetGridApplyButton>, &mut BackgroundColor),
+        (
+            Changed<Interaction>,
+            Or<(With<TilesetGridApplyButton>, With<TilesetGridCancelButton>)>,
+        ),
+    >,
+) {
+    for (interaction, field) in field_q.iter_mut() {
+        if *interaction == Interaction::Pressed {
+            dialog.focus = Some(field.0);
+        }
+    }
+    for (interaction, is_apply, mut bg) in footer_q.iter_mut() {
+        match *interaction {
+            Interaction::Pressed => {
+                *bg = BackgroundColor(UI_BUTTON_PRESS);
+                if is_apply {
+                    dialog.apply_requested = true;
+                } else {
+                    dialog.close();
+                }
+            }
+            Interaction::Hovered => *bg = BackgroundColor(UI_BUTTON_HOVER),
+            Interaction::None => *bg = BackgroundColor(UI_BUTTON),
+        }
+    }
+}
+
+/// 数字录入：Tab 切换输入框，Enter 确定，Esc 取消。
+pub fn tileset_grid_text_input(keys: Res<ButtonInput<KeyCode>>, mut dialog: ResMut<TilesetGridDialogState>) {
+    if !dialog.open {
+        return;
+    }
+    if keys.just_pressed(KeyCode::Escape) {
+        dialog.close();
+        return;
+    }
+    if keys.just_pressed(KeyCode::Enter) || keys.just_pressed(KeyCode::NumpadEnter) {
+        dialog.apply_requested = true;
+        return;
+    }
+    if keys.just_pressed(KeyCode::Tab) {
+        let next = match dialog.focus {
+            Some(field) => TilesetGridField::ALL[(field.slot() + 1) % TilesetGridField::ALL.len()],
+            None => TilesetGridField::TileWidth,
+        };
+        dialog.focus = Some(next);
+        return;
+    }
+    let Some(field) = dialog.focus else {
+        return;
+    };
+
+    let digit_keys: &[(KeyCode, KeyCode, char)] = &[
+        (KeyCode::Digit0, KeyCode::Numpad0, '0'),
+        (KeyCode::Digit1, KeyCode::Numpad1, '1'),
+        (KeyCode::Digit2, KeyCode::Numpad2, '2'),
+        (KeyCode::Digit3, KeyCode::Numpad3, '3'),
+        (KeyCode::Digit4, KeyCode::Numpad4, '4'),
+        (KeyCode::Digit5, KeyCode::Numpad5, '5'),
+        (KeyCode::Digit6, KeyCode::Numpad6, '6'),
+        (KeyCode::Digit7, KeyCode::Numpad7, '7'),
+        (KeyCode::Digit8, KeyCode::Numpad8, '8'),
+        (KeyCode::Digit9, KeyCode::Numpad9, '9'),
+    ];
+    let buf = &mut dialog.bufs[field.slot()];
+    for &(key, numpad, ch) in digit_keys {
+        if (keys.just_pressed(key) || keys.just_pressed(numpad)) && buf.len() < 4 {
+            buf.push(ch);
+        }
+    }
+    if keys.just_pressed(KeyCode::Backspace) {
+        buf.pop();
+    }
+}
+
+/// 显示 / 隐藏，刷新输入框文字与切分预览。
+pub fn update_tileset_grid_dialog(
+    dialog: Res<TilesetGridDialogState>,
+    lib: Res<TilesetLibrary>,
+    runtime: Res<TilesetRuntime>,
+    images: Res<Assets<Image>>,
+    mut root_q: Query<&mut Visibility, With<TilesetGridDialogRoot>>,
+    mut title_q: Query<&mut Text, (With<TilesetGridTitleText>, Without<TilesetGridFieldText>)>,
+    mut field_q: Query<(&TilesetGridFieldText, &mut Text), Without<TilesetGridTitleText>>,
+    mut field_bg_q: Query<(&TilesetGridFieldButton, &Interaction, &mut BackgroundColor)>,
+    mut summary_q: Query<
+        &mut Text,
+        (With<TilesetGridSummaryText>, Without<TilesetGridTitleText>, Without<TilesetGridFieldText>),
+    >,
+) {
+    if let Ok(mut vis) = root_q.single_mut() {
+        let want = if dialog.open { Visibility::Visible } else { Visibility::Hidden };
+        if *vis != want {
+            *vis = want;
+        }
+    }
+    if !dialog.open || !(dialog.is_changed() || runtime.is_changed()) {
+        return;
+    }
+
+    let entry = dialog
+        .tileset_id
+        .as_ref()
+        .and_then(|id| lib.entries.iter().find(|e| e.id == *id));
+    let name = entry.map(|e| e.name.as_str()).unwrap_or("");
+    for mut t in title_q.iter_mut() {
+        *t = Text::new(format!("Tileset 切分：{name}"));
+    }
+    for (field, mut t) in field_q.iter_mut() {
+        let caret = if dialog.focus == Some(field.0) { "|" } else { "" };
+        *t = Text::new(format!("{}{caret}", dialog.bufs[field.0.slot()]));
+    }
+    for (field, interaction, mut bg) in field_bg_q.iter_mut() {
+        *bg = BackgroundColor(if dialog.focus == Some(field.0) {
+            UI_HIGHLIGHT
+        } else if *interaction == Interaction::Hovered {
+            UI_BUTTON_HOVER
+        } else {
+            UI_BUTTON
+        });
+    }
+
+    let image_size = dialog
+        .tileset_id
+        .as_ref()
+        .and_then(|id| runtime.by_id.get(id))
+        .and_then(|rt| images.get(&rt.texture))
+        .map(|img| img.size());
+    let summary = match (dialog.parsed(), image_size) {
+        (Err(err), _) => err,
+        (Ok(_), None) => "图片尚未加载，确定后按新参数切分".to_string(),
+        (Ok(grid), Some(size)) => {
+            let (columns, rows) = (grid.columns(size.x), grid.rows(size.y));
+            if columns == 0 || rows == 0 {
+                format!("图片 {}x{} 切不出这个尺寸的 tile", size.x, size.y)
+            } else {
+                format!("图片 {}x{} → {columns} 列 x {rows} 行（{} 个 tile）", size.x, size.y, columns * rows)
+            }
+        }
+    };
+    for mut t in summary_q.iter_mut() {
+        *t = Text::new(summary.clone());
+    }
+}
+
+/// 确定：写入 tileset 库并重新加载该 tileset（runtime 变化会触发地图与 palette 重建）。
+pub fn apply_tileset_grid(
+    asset_server: Res<AssetServer>,
+    config: Res<EditorConfig>,
+    mut dialog: ResMut<TilesetGridDialogState>,
+    mut lib: ResMut<TilesetLibrary>,
+    mut runtime: ResMut<TilesetRuntime>,
+    mut loading: ResMut<TilesetLoading>,
+    mut ui_state: ResMut<UiState>,
+) {
+    if !dialog.apply_requested {
+        return;
+    }
+    dialog.apply_requested = false;
+    let grid = match dialog.parsed() {
+        Ok(grid) => grid,
+        Err(err) => {
+            warn!("invalid tileset grid: {err}");
+            return;
+        }
+    };
+    let Some(id) = dialog.tileset_id.clone() else {
+        dialog.close();
+        return;
+    };
+    let Some(entry) = lib.entries.iter_mut().find(|e| e.id == id) else {
+        dialog.close();
+        return;
+    };
+    entry.set_grid(grid, (config.tile_size.x, config.tile_size.y));
+    let asset_path = entry.asset_path.clone();
+    save_tileset_library(&lib);
+    info!(
+        "tileset {id} grid: {}x{} margin={} spacing={}",
+        grid.tile_width, grid.tile_height, grid.margin, grid.spacing
+    );
+
+    // 已加载的图片直接复用句柄，下一帧按新参数重新切分。
+    let texture = match runtime.by_id.remove(&id) {
+        Some(rt) => Some(rt.texture),
+        None if !loading.pending.iter().any(|p| p.id == id) && !asset_path.is_empty() => {
+            Some(asset_server.load(asset_path))
+        }
+        None => None,
+    };
+    if let Some(texture) = texture {
+        loading.failed.retain(|f| f.id != id);
+        loading.pending.push(PendingTileset { id, texture });
+    }
+    ui_state.built_for_tileset_path.clear();
+    dialog.close();
+}
@@ -1,11 +1,13 @@
-//! 工具栏交互：工具选择、ShiftMap 模式切换等。
+//! 工具栏交互：工具选择、油漆桶 / 吸管 / 魔棒取样图层、ShiftMap 模式切换、元数据笔刷、自动图块、Wang 地形与随机变体模式等。
 
 use bevy::prelude::*;
 
 use crate::editor::{UI_BUTTON, UI_BUTTON_HOVER, UI_BUTTON_PRESS, UI_HIGHLIGHT};
+use crate::editor::tileset::save_tileset_library;
 use crate::editor::types::{
-	BrushSettings, BrushSizeButton, PasteState, ShiftMapMode, ShiftMapSettings, ShiftModeButton,
-	ShiftModeLabel, ToolButton, ToolKind, ToolState,
+	active_autotile, active_variation_set, active_wangset, AutotileBrushState, AutotileLabel, AutotileToggleButton, BrushSettings, BrushSizeButton, MetaBrushAction, MetaBrushButton, MetaBrushLabel, MetaBrushState, MetaField, PasteState,
+	EditorState, SampleSource, SampleSourceButton, SampleSourceLabel, ShiftMapMode, ShiftMapSettings, ShiftModeButton, ShiftModeLabel, TileMapData, TilesetLibrary, ToolButton, ToolKind,
+	ToolState, VariationBrushAction, VariationBrushButton, VariationBrushLabel, VariationBrushState, WangBrushAction, WangBrushButton, WangBrushLabel, WangBrushState,
 };
 
 /// 工具按钮点击：切换当前工具；进入粘贴工具时重置粘贴变换。
@@ -31,13 +33,49 @@ pub fn tool_button_click(
 	}
 
 	if let Some(next) = picked {
-		tools.tool = next;
+		tools.pick(next);
 		if next == ToolKind::Paste {
 			*paste = PasteState::default();
 		}
 	}
 }
 
+/// 取样按钮：循环当前工具（油漆桶 / 吸管 / 魔棒）的取样图层；其它工具无取样设置。
+pub fn sample_source_button_click(
+	mut tools: ResMut<ToolState>,
+	map: Option<Res<TileMapData>>,
+	q: Query<&Interaction, (Changed<Interaction>, With<SampleSourceButton>)>,
+) {
+	if !q.iter().any(|i| *i == Interaction::Pressed) {
+		return;
+	}
+	let layers = map.map_or(1, |m| m.layers);
+	if let Some(sample) = tools.sample_mut() {
+		*sample = sample.next(layers);
+	}
+}
+
+/// 取样按钮文字（随工具切换）与悬停样式。
+pub fn sync_sample_source_button(
+	tools: Res<ToolState>,
+	mut buttons: Query<(&Interaction, &mut BackgroundColor), With<SampleSourceButton>>,
+	mut labels: Query<&mut Text, With<SampleSourceLabel>>,
+) {
+	if tools.is_changed() {
+		let label = format!("取样: {}", tools.sample().map_or("-".to_string(), SampleSource::label));
+		for mut t in labels.iter_mut() {
+			*t = Text::new(label.clone());
+		}
+	}
+	for (interaction, mut bg) in buttons.iter_mut() {
+		*bg = match *interaction {
+			Interaction::Pressed => BackgroundColor(UI_BUTTON_PRESS),
+			Interaction::Hovered => BackgroundColor(UI_BUTTON_HOVER),
+			Interaction::None => BackgroundColor(UI_BUTTON),
+		};
+	}
+}
+
 /// 工具按钮样式同步：高亮当前工具。
 pub fn sync_tool_button_styles(
     tools: Res<ToolState>,
@@ -138,3 +176,187 @@ pub fn update_shift_mode_label(settings: Res<ShiftMapSettings>, mut q: Query<&mu
 		*t = Text::new(label);
 	}
 }
+
+/// 元数据笔刷按钮：开关模式、切换字段、调整值、切换通行方向。
+pub fn meta_brush_button_click(
+	mut brush: ResMut<MetaBrushState>,
+	mut q: Query<(&Interaction, &MetaBrushButton, &mut BackgroundColor), Changed<Interaction>>,
+) {
+	for (interaction, btn, mut bg) in q.iter_mut() {
+		match *interaction {
+			Interaction::Pressed => {
+				brush.apply(btn.0);
+				*bg = BackgroundColor(UI_BUTTON_PRESS);
+			}
+			Interaction::Hovered => {
+				*bg = BackgroundColor(UI_BUTTON_HOVER);
+			}
+			Interaction::None => {
+				*bg = BackgroundColor(UI_BUTTON);
+			}
+		}
+	}
+}
+
+/// 元数据笔刷样式同步：模式开启时高亮开关，通行字段下高亮被阻挡的方向。
+pub fn sync_meta_brush_styles(
+	brush: Res<MetaBrushState>,
+	mut buttons: Query<(&MetaBrushButton, &Interaction, &mut BackgroundColor)>,
+	mut labels: Query<&mut Text, With<MetaBrushLabel>>,
+) {
+	if brush.is_changed() {
+		for mut t in labels.iter_mut() {
+			*t = Text::new(brush.label());
+		}
+	}
+	for (btn, interaction, mut bg) in buttons.iter_mut() {
+		let active = match btn.0 {
+			MetaBrushAction::ToggleMode => brush.enabled,
+			MetaBrushAction::TogglePassage(bit) => {
+				brush.enabled && brush.field == MetaField::Passage && brush.passage & bit != 0
+			}
+			_ => false,
+		};
+		if active {
+			*bg = BackgroundColor(UI_HIGHLIGHT);
+			continue;
+		}
+		*bg = match *interaction {
+			Interaction::Pressed => BackgroundColor(UI_BUTTON_PRESS),
+			Interaction::Hovered => BackgroundColor(UI_BUTTON_HOVER),
+			Interaction::None => BackgroundColor(UI_BUTTON),
+		};
+	}
+}
+
+/// 自动图块模式开关；当前 tileset 没有自动图块组时不开启。
+pub fn autotile_button_click(
+	mut brush: ResMut<AutotileBrushState>,
+	lib: Res<TilesetLibrary>,
+	state: Res<EditorState>,
+	q: Query<&Interaction, (Changed<Interaction>, With<AutotileToggleButton>)>,
+) {
+	if !q.iter().any(|i| *i == Interaction::Pressed) {
+		return;
+	}
+	if !brush.enabled && active_autotile(&lib, state.selected_tile).is_none() {
+		warn!("active tileset has no autotile sets");
+		return;
+	}
+	brush.enabled = !brush.enabled;
+}
+
+/// 自动图块开关样式与文字（当前组随选中 tile / tileset 变化）。
+pub fn sync_autotile_styles(
+	brush: Res<AutotileBrushState>,
+	lib: Res<TilesetLibrary>,
+	state: Res<EditorState>,
+	mut buttons: Query<(&Interaction, &mut BackgroundColor), With<AutotileToggleButton>>,
+	mut labels: Query<&mut Text, With<AutotileLabel>>,
+) {
+	if brush.is_changed() || lib.is_changed() || state.is_changed() {
+		let label = brush.label(&lib, state.selected_tile);
+		for mut t in labels.iter_mut() {
+			*t = Text::new(label.clone());
+		}
+	}
+	for (interaction, mut bg) in buttons.iter_mut() {
+		*bg = match *interaction {
+			_ if brush.enabled => BackgroundColor(UI_HIGHLIGHT),
+			Interaction::Pressed => BackgroundColor(UI_BUTTON_PRESS),
+			Interaction::Hovered => BackgroundColor(UI_BUTTON_HOVER),
+			Interaction::None => BackgroundColor(UI_BUTTON),
+		};
+	}
+}
+
+/// Wang 地形按钮：开关模式、切换集合、切换颜色；当前 tileset 没有 Wang 集时不开启。
+pub fn wang_brush_button_click(
+	mut brush: ResMut<WangBrushState>,
+	lib: Res<TilesetLibrary>,
+	q: Query<(&Interaction, &WangBrushButton), Changed<Interaction>>,
+) {
+	for (interaction, btn) in q.iter() {
+		if *interaction != Interaction::Pressed {
+			continue;
+		}
+		if btn.0 == WangBrushAction::ToggleMode && !brush.enabled && active_wangset(&lib, brush.set).is_none() {
+			warn!("active tileset has no wang sets");
+			continue;
+		}
+		brush.apply(btn.0, &lib);
+	}
+}
+
+/// Wang 地形按钮样式与文字（当前集合随 tileset 变化）。
+pub fn sync_wang_brush_styles(
+	brush: Res<WangBrushState>,
+	lib: Res<TilesetLibrary>,
+	mut buttons: Query<(&WangBrushButton, &Interaction, &mut BackgroundColor)>,
+	mut labels: Query<&mut Text, With<WangBrushLabel>>,
+) {
+	if brush.is_changed() || lib.is_changed() {
+		let label = brush.label(&lib);
+		for mut t in labels.iter_mut() {
+			*t = Text::new(label.clone());
+		}
+	}
+	for (btn, interaction, mut bg) in buttons.iter_mut() {
+		*bg = match *interaction {
+			_ if btn.0 == WangBrushAction::ToggleMode && brush.enabled => BackgroundColor(UI_HIGHLIGHT),
+			Interaction::Pressed => BackgroundColor(UI_BUTTON_PRESS),
+			Interaction::Hovered => BackgroundColor(UI_BUTTON_HOVER),
+			Interaction::None => BackgroundColor(UI_BUTTON),
+		};
+	}
+}
+
+/// 随机变体按钮：开关模式、切换集合、切换当前集合的随机旋转 / 翻转（写回 tileset 库并保存）；当前 tileset 没有变体集时不开启。
+pub fn variation_brush_button_click(
+	mut brush: ResMut<VariationBrushState>,
+	mut lib: ResMut<TilesetLibrary>,
+	q: Query<(&Interaction, &VariationBrushButton), Changed<Interaction>>,
+) {
+	for (interaction, btn) in q.iter() {
+		if *interaction != Interaction::Pressed {
+			continue;
+		}
+		if btn.0 == VariationBrushAction::ToggleMode && !brush.enabled && active_variation_set(&lib, brush.set).is_none() {
+			warn!("active tileset has no variation sets");
+			continue;
+		}
+		if brush.apply(btn.0, &mut lib) {
+			save_tileset_library(&lib);
+		}
+	}
+}
+
+/// 随机变体按钮样式与文字：开关随模式高亮，旋转 / 翻转随当前集合的设置高亮。
+pub fn sync_variation_brush_styles(
+	brush: Res<VariationBrushState>,
+	lib: Res<TilesetLibrary>,
+	mut buttons: Query<(&VariationBrushButton, &Interaction, &mut BackgroundColor)>,
+	mut labels: Query<&mut Text, With<VariationBrushLabel>>,
+) {
+	if brush.is_changed() || lib.is_changed() {
+		let label = brush.label(&lib);
+		for mut t in labels.iter_mut() {
+			*t = Text::new(label.clone());
+		}
+	}
+	let set = active_variation_set(&lib, brush.set).map(|(_, set)| set);
+	for (btn, interaction, mut bg) in buttons.iter_mut() {
+		let on = match btn.0 {
+			VariationBrushAction::ToggleMode => brush.enabled,
+			VariationBrushAction::CycleSet => false,
+			VariationBrushAction::ToggleRotation => set.as_ref().is_some_and(|s| s.random_rotation),
+			VariationBrushAction::ToggleFlip => set.as_ref().is_some_and(|s| s.random_flip),
+		};
+		*bg = match *interaction {
+			_ if on => BackgroundColor(UI_HIGHLIGHT),
+			Interaction::Pressed => BackgroundColor(UI_BUTTON_PRESS),
+			Interaction::Hovered => BackgroundColor(UI_BUTTON_HOVER),
+			Interaction::None => BackgroundColor(UI_BUTTON),
+		};
+	}
+}
@@ -1,53 +1,48 @@
 //! UI 内部复用的 helper。
 
-use crate::editor::types::TileMapData;
+use bevy::prelude::*;
+use bevy_ecs_tilemap::prelude::TilemapId;
+use tilemap_core::ops;
 
-/// 调整地图尺寸时，把旧地图内容尽量拷贝到新地图。
-///
-/// 规则：
-/// - 拷贝范围是新旧宽高的交集。
-/// - 拷贝 layer 数是 `min(old.layers, new.layers)`（至少 1）。
+use crate::editor::types::{EditorConfig, TileMapData};
+use crate::editor::util::despawn_silently;
+use crate::editor::world::{rebuild_tilemaps, TilemapRenderParams};
+
+/// 调整地图尺寸时，把旧地图内容尽量拷贝到新地图（保留图层与 tileset 表，超出新范围的格子丢弃）。
 pub(super) fn resized_map_copy(old: Option<&TileMapData>, width: u32, height: u32) -> TileMapData {
-    let mut new_map = TileMapData::new(width, height);
     let Some(old) = old else {
-        return new_map;
+        return TileMapData::new(width, height);
     };
+    let mut new_map = old.clone();
+    ops::resize(&mut new_map, width, height);
+    new_map
+}
 
-    let copy_w = old.width.min(width) as usize;
-    let copy_h = old.height.min(height);
-    if copy_w == 0 || copy_h == 0 {
-        return new_map;
+/// 清理旧的 tilemap/tile 实体，按 `map` 重新生成渲染容器并重建。
+pub(super) fn respawn_map_render(
+    render: &mut TilemapRenderParams,
+    tile_q: &Query<Entity, With<TilemapId>>,
+    config: &EditorConfig,
+    map: &TileMapData,
+) {
+    if let Some(existing_tiles) = render.tile_entities.as_deref() {
+        for e in existing_tiles.all_tilemap_entities() {
+            despawn_silently(&mut render.commands, e);
+        }
     }
-
-    let old_layer_len = old.layer_len();
-    let new_layer_len = new_map.layer_len();
-    if old_layer_len == 0 || new_layer_len == 0 {
-        return new_map;
+    for e in tile_q.iter() {
+        despawn_silently(&mut render.commands, e);
     }
-
-    // 旧数据可能来自老版本/外部编辑器，tiles 长度不一定严格匹配 width*height*layers。
-    // 这里做一次“可安全拷贝的层数”裁剪，避免越界 panic。
-    let max_old_layers_by_len = (old.tiles.len() / old_layer_len) as u32;
-    let max_new_layers_by_len = (new_map.tiles.len() / new_layer_len) as u32;
-    let layers_to_copy = old
-        .layers
-        .min(new_map.layers)
-        .min(max_old_layers_by_len)
-        .min(max_new_layers_by_len)
-        .max(1);
-
-    for layer in 0..layers_to_copy {
-        let old_layer_base = (layer as usize) * old_layer_len;
-        let new_layer_base = (layer as usize) * new_layer_len;
-
-        for y in 0..copy_h {
-            let old_row = old_layer_base + (y as usize) * (old.width as usize);
-            let new_row = new_layer_base + (y as usize) * (new_map.width as usize);
-            let src = &old.tiles[old_row..old_row + copy_w];
-            let dst = &mut new_map.tiles[new_row..new_row + copy_w];
-            dst.clone_from_slice(src);
-        }
+    let mut tiles = crate::editor::tileset::spawn_map_entities(&mut render.commands, config, map);
+    {
+        let TilemapRenderParams {
+            commands,
+            tile_entities: _,
+            runtime,
+            tile_storage_q,
+            ..
+        } = render;
+        rebuild_tilemaps(commands, tile_q, runtime, map, &mut tiles, tile_storage_q, config);
     }
-
-    new_map
+    render.commands.insert_resource(tiles);
 }
@@ -10,12 +10,14 @@ use bevy::ecs::system::SystemParam;
 use bevy_ecs_tilemap::prelude::*;
 
 use super::types::{
-    CellChange, EditCommand, EditorConfig, SelectionRect, TileEntities, TileMapData, TileRef,
-    TilesetRuntime, UndoStack,
+    AnimatedTile, CellChange, EditCommand, EditorConfig, Orientation, SelectionRect, TileBounds, TileCell,
+    TileEntities, TileMapData, TileRef, TileView, TilesetRuntime, UndoStack,
 };
 use super::{LEFT_PANEL_WIDTH_PX, UI_TOP_RESERVED_PX};
+use crate::editor::tileset::tile_anchor_offset;
 use crate::editor::util::despawn_silently;
 
+mod automap;
 mod layers;
 mod context_menu;
 mod camera;
@@ -30,15 +32,22 @@ mod save_load;
 mod paste_helpers;
 mod paste_apply;
 mod paste_preview;
+mod shape_preview;
 mod paste_transform;
 mod render_sync;
 mod selection_move;
 mod selection_transform;
+mod tile_animation;
+mod collision_overlay;
+mod meta_overlay;
 mod tools;
 
+pub use automap::{automap_after_edit, run_automap};
 pub use camera::{camera_pan, camera_zoom, recenter_camera_on_map_change, setup_world};
 pub use canvas::draw_canvas_helpers;
-pub use undo::undo_redo_shortcuts;
+pub use collision_overlay::draw_collision_overlay;
+pub use meta_overlay::sync_meta_overlay;
+pub use undo::{redo_once, undo_once, undo_redo_shortcuts};
 pub use shortcuts::{keyboard_shortcuts, tool_shortcuts};
 pub use eyedropper::{eyedropper_hold_shortcut, eyedropper_with_mouse};
 pub use shift_map::shift_map_shortcuts;
@@ -46,16 +55,18 @@ pub use selection_shortcuts::{
     copy_paste_shortcuts, move_selection_shortcuts, selection_cut_delete_shortcuts,
     selection_selectall_cancel_shortcuts,
 };
-pub use selection_box::select_with_mouse;
+pub use selection_box::{magic_wand_with_mouse, select_with_mouse};
 pub use save_load::save_load_shortcuts;
 pub use layers::layer_shortcuts;
 pub use context_menu::{apply_context_menu_command, context_menu_clear_consumption, context_menu_open_close};
 pub use paste_apply::paste_with_mouse;
 pub use paste_preview::update_paste_preview;
+pub use shape_preview::{draw_shape_preview_frame, update_shape_preview};
 pub use paste_transform::paste_transform_shortcuts;
 pub use render_sync::{refresh_map_on_tileset_runtime_change, rebuild_tilemaps, sync_layer_visibility_on_layer_data_change, update_visible_chunks};
 pub use selection_move::selection_move_with_mouse;
-pub use tools::{fill_with_mouse, paint_with_mouse, rect_with_mouse};
+pub use tile_animation::{advance_tile_animation_clock, animate_map_tiles};
+pub use tools::{fill_with_mouse, line_with_mouse, paint_with_mouse, rect_with_mouse};
 
 #[derive(SystemParam)]
 pub struct TilemapRenderParams<'w, 's> {
@@ -65,26 +76,30 @@ pub struct TilemapRenderParams<'w, 's> {
     pub tile_storage_q: Query<'w, 's, &'static mut TileStorage>,
 }
 
+/// 把单格变更同步到渲染；`before` / `after` 按 `map` 的 tileset 表解析。
 pub(crate) fn apply_tile_change(
     render: &mut TilemapRenderParams,
     config: &EditorConfig,
+    map: &TileMapData,
     layer: u32,
-    x: u32,
-    y: u32,
-    before: &Option<TileRef>,
-    after: &Option<TileRef>,
+    x: i32,
+    y: i32,
+    before: TileCell,
+    after: TileCell,
 ) {
     let Some(tile_entities) = render.tile_entities.as_mut() else {
         return;
     };
+    let before = map.view(before);
+    let after = map.view(after);
 
     if let Some(before_tile) = before {
-        if after.as_ref().map(|t| &t.tileset_id) != Some(&before_tile.tileset_id) {
+        if after.map(|t| t.tileset_id) != Some(before_tile.tileset_id) {
             remove_tile_from_tileset(
                 &mut render.commands,
                 &mut render.tile_storage_q,
                 tile_entities,
-                &before_tile.tileset_id,
+                before_tile.tileset_id,
                 layer,
                 x,
                 y,
@@ -99,7 +114,7 @@ pub(crate) fn apply_tile_change(
             tile_entities,
             &render.runtime,
             config,
-            &after_tile.tileset_id,
+            after_tile.tileset_id,
             layer,
             x,
             y,
@@ -110,7 +125,7 @@ pub(crate) fn apply_tile_change(
             &mut render.commands,
             &mut render.tile_storage_q,
             tile_entities,
-            &before_tile.tileset_id,
+            before_tile.tileset_id,
             layer,
             x,
             y,
@@ -118,6 +133,18 @@ pub(crate) fn apply_tile_change(
     }
 }
 
+/// 把一组格子变更同步到渲染（按 CellChange 的 before -> after）。
+pub(crate) fn render_cell_changes(
+    render: &mut TilemapRenderParams,
+    config: &EditorConfig,
+    map: &TileMapData,
+    changes: &[CellChange],
+) {
+    for ch in changes {
+        apply_tile_change(render, config, map, ch.layer, ch.x, ch.y, ch.before, ch.after);
+    }
+}
+
 fn ensure_tilemap_chunk(
     commands: &mut Commands,
     tile_entities: &mut TileEntities,
@@ -125,37 +152,40 @@ fn ensure_tilemap_chunk(
     config: &EditorConfig,
     tileset_id: &str,
     layer: u32,
-    cx: u32,
-    cy: u32,
+    cx: i32,
+    cy: i32,
 ) -> Option<Entity> {
-    let tileset_id = tileset_id.to_string();
-    if let Some(entity) = tile_entities.chunk_entity(&tileset_id, layer, cx, cy) {
+    if let Some(entity) = tile_entities.chunk_entity(tileset_id, layer, cx, cy) {
         return Some(entity);
     }
 
-    let Some(rt) = runtime.by_id.get(&tileset_id) else {
-        return None;
-    };
+    let rt = runtime.by_id.get(tileset_id)?;
 
     let chunk_size = tile_entities.chunk_size.max(1);
     let map_size = TilemapSize {
         x: chunk_size,
         y: chunk_size,
     };
+    // tile 尺寸取自 tileset，网格取地图网格；两者不同时整块 chunk 平移，使 tile 左下角对齐格子。
     let tile_size = TilemapTileSize {
-        x: config.tile_size.x as f32,
-        y: config.tile_size.y as f32,
+        x: rt.grid.tile_width as f32,
+        y: rt.grid.tile_height as f32,
     };
     let grid_size = TilemapGridSize {
         x: config.tile_size.x as f32,
         y: config.tile_size.y as f32,
     };
     let storage = TileStorage::empty(map_size);
-    let order = tile_entities.tileset_index(&tileset_id);
+    let order = tile_entities.tileset_index(tileset_id);
     let z = layer as f32 * 10.0 + order as f32 * 0.01;
-    let origin_x = cx as f32 * chunk_size as f32 * tile_size.x;
-    let origin_y = cy as f32 * chunk_size as f32 * tile_size.y;
-    let offset = Vec3::new(origin_x + tile_size.x * 0.5, origin_y + tile_size.y * 0.5, z);
+    let origin_x = cx as f32 * chunk_size as f32 * grid_size.x;
+    let origin_y = cy as f32 * chunk_size as f32 * grid_size.y;
+    let anchor = tile_anchor_offset(rt.tile_size(), config.tile_size);
+    let offset = Vec3::new(
+        origin_x + grid_size.x * 0.5 + anchor.x,
+        origin_y + grid_size.y * 0.5 + anchor.y,
+        z,
+    );
 
     let map_entity = commands.spawn_empty().id();
     commands.entity(map_entity).insert(TilemapBundle {
@@ -163,12 +193,12 @@ fn ensure_tilemap_chunk(
         storage,
         tile_size,
         grid_size,
-        texture: TilemapTexture::Single(rt.texture.clone()),
+        texture: TilemapTexture::Single(rt.tilemap_texture.clone()),
         transform: Transform::from_translation(offset),
         ..Default::default()
     });
 
-    tile_entities.set_chunk_entity(tileset_id, layer, cx, cy, map_entity);
+    tile_entities.set_chunk_entity(tileset_id.to_string(), layer, cx, cy, map_entity);
     Some(map_entity)
 }
 
@@ -178,16 +208,11 @@ fn remove_tile_from_tileset(
     tile_entities: &mut TileEntities,
     tileset_id: &str,
     layer: u32,
-    x: u32,
-    y: u32,
+    x: i32,
+    y: i32,
 ) {
-    let tileset_id = tileset_id.to_string();
-    let chunk_size = tile_entities.chunk_size.max(1);
-    let cx = x / chunk_size;
-    let cy = y / chunk_size;
-    let lx = x % chunk_size;
-    let ly = y % chunk_size;
-    let Some(map_entity) = tile_entities.chunk_entity(&tileset_id, layer, cx, cy) else {
+    let (cx, cy, lx, ly) = chunk_coords(tile_entities.chunk_size, x, y);
+    let Some(map_entity) = tile_entities.chunk_entity(tileset_id, layer, cx, cy) else {
         return;
     };
     let Ok(mut storage) = tile_storage_q.get_mut(map_entity) else {
@@ -208,15 +233,11 @@ fn set_tile_in_tileset(
     config: &EditorConfig,
     tileset_id: &str,
     layer: u32,
-    x: u32,
-    y: u32,
-    tile: &TileRef,
+    x: i32,
+    y: i32,
+    tile: TileView,
 ) {
-    let chunk_size = tile_entities.chunk_size.max(1);
-    let cx = x / chunk_size;
-    let cy = y / chunk_size;
-    let lx = x % chunk_size;
-    let ly = y % chunk_size;
+    let (cx, cy, lx, ly) = chunk_coords(tile_entities.chunk_size, x, y);
     let Some(map_entity) = ensure_tilemap_chunk(
         commands,
         tile_entities,
@@ -234,51 +255,64 @@ fn set_tile_in_tileset(
         return;
     };
     let pos = TilePos { x: lx, y: ly };
-    if let Some(tile_entity) = storage.get(&pos) {
-        commands.entity(tile_entity).insert(TileTextureIndex(tile.index));
-        commands.entity(tile_entity).insert(tile_flip_from_ref(tile));
-        return;
-    }
-
-    let tile_entity = commands
-        .spawn(TileBundle {
-            position: pos,
-            tilemap_id: TilemapId(map_entity),
-            texture_index: TileTextureIndex(tile.index),
-            flip: tile_flip_from_ref(tile),
-            ..Default::default()
-        })
-        .id();
-    storage.set(&pos, tile_entity);
-}
-
-fn tile_flip_from_ref(tile: &TileRef) -> TileFlip {
-    let mut flip = TileFlip {
-        x: tile.flip_x,
-        y: tile.flip_y,
-        d: false,
-    };
-    match tile.rot % 4 {
-        0 => {}
-        1 => {
-            flip.d = true;
-            flip.x = !flip.x;
-        }
-        2 => {
-            flip.x = !flip.x;
-            flip.y = !flip.y;
+    let tile_entity = match storage.get(&pos) {
+        Some(tile_entity) => {
+            commands.entity(tile_entity).insert(TileTextureIndex(tile.index));
+            commands.entity(tile_entity).insert(TileFlip::from(tile.orientation()));
+            tile_entity
         }
-        3 => {
-            flip.d = true;
-            flip.y = !flip.y;
+        None => {
+            let tile_entity = commands
+                .spawn(TileBundle {
+                    position: pos,
+                    tilemap_id: TilemapId(map_entity),
+                    texture_index: TileTextureIndex(tile.index),
+                    flip: TileFlip::from(tile.orientation()),
+                    ..Default::default()
+                })
+                .id();
+            storage.set(&pos, tile_entity);
+            tile_entity
         }
-        _ => {}
+    };
+    mark_animated_tile(commands, runtime, tile_entity, tileset_id, tile.index);
+}
+
+/// 按 tileset 的动画定义给 tile 实体加上 / 去掉 `AnimatedTile`。
+pub(crate) fn mark_animated_tile(
+    commands: &mut Commands,
+    runtime: &TilesetRuntime,
+    tile_entity: Entity,
+    tileset_id: &str,
+    index: u32,
+) {
+    let animated = runtime
+        .by_id
+        .get(tileset_id)
+        .is_some_and(|rt| rt.is_animated(index));
+    if animated {
+        commands.entity(tile_entity).insert(AnimatedTile {
+            tileset_id: tileset_id.to_string(),
+            base_index: index,
+        });
+    } else {
+        commands.entity(tile_entity).remove::<AnimatedTile>();
     }
-    flip
+}
+
+/// 格子坐标 -> (渲染 chunk 坐标, chunk 内局部坐标)，支持负坐标。
+pub(crate) fn chunk_coords(chunk_size: u32, x: i32, y: i32) -> (i32, i32, u32, u32) {
+    let size = chunk_size.max(1) as i32;
+    (
+        x.div_euclid(size),
+        y.div_euclid(size),
+        x.rem_euclid(size) as u32,
+        y.rem_euclid(size) as u32,
+    )
 }
 
 fn try_edit_single_map_tile<F>(
-    map_pos: Option<UVec2>,
+    map_pos: Option<IVec2>,
     map: Option<ResMut<TileMapData>>,
     render: &mut TilemapRenderParams,
     config: &EditorConfig,
@@ -294,36 +328,34 @@ where
     let Some(layer) = map.topmost_layer_at(pos.x, pos.y) else {
         return false;
     };
-    let idx = map.idx_layer(layer, pos.x, pos.y);
-    if idx >= map.tiles.len() {
-        return false;
-    }
-    let Some(mut after_tile) = map.tiles[idx].clone() else {
+    let Some(mut after_tile) = map.get(layer, pos.x, pos.y) else {
         return false;
     };
-    let before = map.tiles[idx].clone();
     editor(&mut after_tile);
-    after_tile.rot %= 4;
-    let after = Some(after_tile.clone());
+    let before = map.cell(layer, pos.x, pos.y);
+    let after = map.pack(Some(&after_tile));
     if before == after {
         return false;
     }
 
-    map.tiles[idx] = after.clone();
+    map.set_cell(layer, pos.x, pos.y, after);
     undo.push(EditCommand {
         changes: vec![CellChange {
-            idx,
-            before: before.clone(),
-            after: after.clone(),
+            layer,
+            x: pos.x,
+            y: pos.y,
+            before,
+            after,
         }],
+        ..Default::default()
     });
 
-    apply_tile_change(render, config, layer, pos.x, pos.y, &before, &after);
+    apply_tile_change(render, config, &map, layer, pos.x, pos.y, before, after);
     true
 }
 
 fn try_rotate_map_tile_ccw(
-    map_pos: Option<UVec2>,
+    map_pos: Option<IVec2>,
     map: Option<ResMut<TileMapData>>,
     render: &mut TilemapRenderParams,
     config: &EditorConfig,
@@ -335,12 +367,12 @@ fn try_rotate_map_tile_ccw(
         render,
         config,
         undo,
-        |t| t.rot = (t.rot + 3) % 4,
+        |t| t.transform(Orientation::ROT_CCW),
     )
 }
 
 fn try_rotate_map_tile_cw(
-    map_pos: Option<UVec2>,
+    map_pos: Option<IVec2>,
     map: Option<ResMut<TileMapData>>,
     render: &mut TilemapRenderParams,
     config: &EditorConfig,
@@ -352,12 +384,12 @@ fn try_rotate_map_tile_cw(
         render,
         config,
         undo,
-        |t| t.rot = (t.rot + 1) % 4,
+        |t| t.transform(Orientation::ROT_CW),
     )
 }
 
 fn try_flip_map_tile_x(
-    map_pos: Option<UVec2>,
+    map_pos: Option<IVec2>,
     map: Option<ResMut<TileMapData>>,
     render: &mut TilemapRenderParams,
     config: &EditorConfig,
@@ -369,12 +401,12 @@ fn try_flip_map_tile_x(
         render,
         config,
         undo,
-        |t| t.flip_x = !t.flip_x,
+        |t| t.transform(Orientation::FLIP_X),
     )
 }
 
 fn try_flip_map_tile_y(
-    map_pos: Option<UVec2>,
+    map_pos: Option<IVec2>,
     map: Option<ResMut<TileMapData>>,
     render: &mut TilemapRenderParams,
     config: &EditorConfig,
@@ -386,12 +418,12 @@ fn try_flip_map_tile_y(
         render,
         config,
         undo,
-        |t| t.flip_y = !t.flip_y,
+        |t| t.transform(Orientation::FLIP_Y),
     )
 }
 
 fn try_reset_map_tile_transform(
-    map_pos: Option<UVec2>,
+    map_pos: Option<IVec2>,
     map: Option<ResMut<TileMapData>>,
     render: &mut TilemapRenderParams,
     config: &EditorConfig,
@@ -403,15 +435,11 @@ fn try_reset_map_tile_transform(
         render,
         config,
         undo,
-        |t| {
-            t.rot = 0;
-            t.flip_x = false;
-            t.flip_y = false;
-        },
+        |t| t.set_orientation(Orientation::IDENTITY),
     )
 }
 
-fn tile_world_center(x: u32, y: u32, tile_size: UVec2, z: f32) -> Vec3 {
+fn tile_world_center(x: i32, y: i32, tile_size: UVec2, z: f32) -> Vec3 {
     let tile_w = tile_size.x as f32;
     let tile_h = tile_size.y as f32;
     let world_x = (x as f32 + 0.5) * tile_w;
@@ -424,9 +452,8 @@ pub(crate) fn cursor_tile_pos(
     camera: &Camera,
     camera_transform: &GlobalTransform,
     config: &EditorConfig,
-    map_w: u32,
-    map_h: u32,
-) -> Option<UVec2> {
+    fixed_size: Option<(u32, u32)>,
+) -> Option<IVec2> {
     let cursor_pos = window.cursor_position()?;
     if cursor_pos.x <= LEFT_PANEL_WIDTH_PX {
         return None;
@@ -447,20 +474,32 @@ pub(crate) fn cursor_tile_pos(
 
     let x = (world_pos.x / tile_w).floor() as i32;
     let y = (world_pos.y / tile_h).floor() as i32;
-    if x < 0 || y < 0 {
+    // 无限地图不做边界裁剪。
+    if let Some((map_w, map_h)) = fixed_size
+        && (x < 0 || y < 0 || x as u32 >= map_w || y as u32 >= map_h)
+    {
         return None;
     }
-    let (x, y) = (x as u32, y as u32);
-    if x >= map_w || y >= map_h {
-        return None;
-    }
-    Some(UVec2::new(x, y))
+    Some(IVec2::new(x, y))
+}
+
+/// “全选”范围：固定尺寸地图为整张地图；无限地图为已绘制内容的包围盒。
+fn select_all_rect(map: &TileMapData) -> Option<SelectionRect> {
+    let bounds = match map.fixed_size() {
+        Some((0, _)) | Some((_, 0)) => return None,
+        Some((w, h)) => TileBounds::from_corners(0, 0, w as i32 - 1, h as i32 - 1),
+        None => map.used_bounds()?,
+    };
+    Some(SelectionRect {
+        min: IVec2::new(bounds.min_x, bounds.min_y),
+        max: IVec2::new(bounds.max_x, bounds.max_y),
+    })
 }
 
-fn rect_from_two(a: UVec2, b: UVec2) -> SelectionRect {
+fn rect_from_two(a: IVec2, b: IVec2) -> SelectionRect {
     SelectionRect {
-        min: UVec2::new(a.x.min(b.x), a.y.min(b.y)),
-        max: UVec2::new(a.x.max(b.x), a.y.max(b.y)),
+        min: a.min(b),
+        max: a.max(b),
     }
 }
 
@@ -0,0 +1,63 @@
+use bevy::prelude::*;
+
+use tilemap_core::{apply_automap, automap_reach};
+
+use crate::editor::types::{AutomapState, EditCommand, EditorConfig, TileBounds, TileMapData, UndoStack};
+
+use super::{render_cell_changes, TilemapRenderParams};
+
+/// 应用规则（Map → 应用规则）：`bounds` 为 `None` 时作用于整张地图；结果作为一个撤销步骤。
+pub fn run_automap(
+    map: &mut TileMapData,
+    render: &mut TilemapRenderParams,
+    config: &EditorConfig,
+    undo: &mut UndoStack,
+    automap: &AutomapState,
+    bounds: Option<TileBounds>,
+) -> usize {
+    let changes = apply_automap(map, &automap.rules, bounds);
+    let count = changes.len();
+    render_cell_changes(render, config, map, &changes);
+    undo.push(EditCommand { changes, ..Default::default() });
+    count
+}
+
+/// 自动应用规则：有新的编辑命令入栈后，在其改动附近跑一遍规则，结果并入同一条撤销记录。
+pub fn automap_after_edit(
+    automap: Res<AutomapState>,
+    config: Res<EditorConfig>,
+    map: Option<ResMut<TileMapData>>,
+    mut undo: ResMut<UndoStack>,
+    mut render: TilemapRenderParams,
+    mut seen: Local<u64>,
+) {
+    if undo.revision == *seen {
+        return;
+    }
+    *seen = undo.revision;
+    if !automap.auto_run || automap.rules.is_empty() {
+        return;
+    }
+    let Some(mut map) = map else {
+        return;
+    };
+    let Some(cmd) = undo.undo.last_mut() else {
+        return;
+    };
+    let mut bounds: Option<TileBounds> = None;
+    for ch in &cmd.changes {
+        match bounds.as_mut() {
+            Some(b) => b.include(ch.x, ch.y),
+            None => bounds = Some(TileBounds::from_corners(ch.x, ch.y, ch.x, ch.y)),
+        }
+    }
+    let Some(b) = bounds else {
+        return;
+    };
+    let reach = automap_reach(&automap.rules);
+    let area = TileBounds::from_corners(b.min_x - reach, b.min_y - reach, b.max_x + reach, b.max_y + reach);
+    let changes = apply_automap(&mut map, &automap.rules, Some(area));
+    render_cell_changes(&mut render, &config, &map, &changes);
+    // 撤销按逆序回放，追加在后面的规则输出会先被撤掉。
+    cmd.changes.extend(changes);
+}
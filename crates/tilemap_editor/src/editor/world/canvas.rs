@@ -8,6 +8,7 @@ use crate::editor::types::{
 use crate::editor::{LEFT_PANEL_WIDTH_PX, UI_TOP_RESERVED_PX};
 
 use super::paste_helpers::paste_dims;
+use super::selection_box::draw_selection_outline;
 
 /// 在画布上绘制辅助线（网格 + hover 高亮）。
 ///
@@ -19,7 +20,7 @@ pub fn draw_canvas_helpers(
     config: Res<EditorConfig>,
     tile_entities: Option<Res<TileEntities>>,
     tools: Res<ToolState>,
-    selection: Res<SelectionState>,
+    (selection, time): (Res<SelectionState>, Res<Time>),
     clipboard: Res<Clipboard>,
     paste: Res<PasteState>,
 ) {
@@ -38,10 +39,19 @@ pub fn draw_canvas_helpers(
     }
 
     // 有 TileEntities 时使用其实际尺寸（更可靠）；否则回退到 config.map_size。
-    let (map_w, map_h) = if let Some(te) = tile_entities.as_deref() {
-        (te.width, te.height)
-    } else {
-        (config.map_size.x, config.map_size.y)
+    let fixed_size = match tile_entities.as_deref() {
+        Some(te) => te.fixed_size(),
+        None => Some((config.map_size.x, config.map_size.y)),
+    };
+    let grid_color = Color::srgba(1.0, 1.0, 1.0, 0.12);
+    let border_color = Color::srgba(1.0, 1.0, 1.0, 0.30);
+
+    let Some((map_w, map_h)) = fixed_size else {
+        // 无限地图：只在视野范围内画网格，并用较亮的线标出原点坐标轴。
+        draw_infinite_canvas(&mut gizmos, window, camera, camera_transform, &config, grid_color, border_color);
+        draw_selection_outline(&mut gizmos, &config, &selection, time.elapsed_secs());
+        draw_overlays(&mut gizmos, window, camera, camera_transform, &config, None, &tools, &clipboard, &paste);
+        return;
     };
     if map_w == 0 || map_h == 0 {
         return;
@@ -50,9 +60,6 @@ pub fn draw_canvas_helpers(
     let width_px = map_w as f32 * tile_w;
     let height_px = map_h as f32 * tile_h;
 
-    let grid_color = Color::srgba(1.0, 1.0, 1.0, 0.12);
-    let border_color = Color::srgba(1.0, 1.0, 1.0, 0.30);
-
     if config.show_grid {
         // 网格线
         for x in 0..=map_w {
@@ -80,18 +87,85 @@ pub fn draw_canvas_helpers(
     );
 
     // 选择框：不要求鼠标在画布内，避免“按了快捷键但光标在 UI 上看不到”。
-    if let Some(rect) = selection.rect {
-        let sx0 = rect.min.x as f32 * tile_w;
-        let sy0 = rect.min.y as f32 * tile_h;
-        let sx1 = (rect.max.x as f32 + 1.0) * tile_w;
-        let sy1 = (rect.max.y as f32 + 1.0) * tile_h;
-        let c = Color::srgba(1.0, 1.0, 0.0, 0.85);
-        gizmos.line_2d(Vec2::new(sx0, sy0), Vec2::new(sx1, sy0), c);
-        gizmos.line_2d(Vec2::new(sx1, sy0), Vec2::new(sx1, sy1), c);
-        gizmos.line_2d(Vec2::new(sx1, sy1), Vec2::new(sx0, sy1), c);
-        gizmos.line_2d(Vec2::new(sx0, sy1), Vec2::new(sx0, sy0), c);
+    draw_selection_outline(&mut gizmos, &config, &selection, time.elapsed_secs());
+
+    draw_overlays(
+        &mut gizmos,
+        window,
+        camera,
+        camera_transform,
+        &config,
+        Some((map_w, map_h)),
+        &tools,
+        &clipboard,
+        &paste,
+    );
+}
+
+/// 无限地图的网格：覆盖当前视野；缩得太小时不画，避免线条过密。
+fn draw_infinite_canvas(
+    gizmos: &mut Gizmos,
+    window: &Window,
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+    config: &EditorConfig,
+    grid_color: Color,
+    axis_color: Color,
+) {
+    let tile_w = config.tile_size.x as f32;
+    let tile_h = config.tile_size.y as f32;
+    let corners = [
+        Vec2::ZERO,
+        Vec2::new(window.width(), window.height()),
+    ];
+    let Ok(a) = camera.viewport_to_world_2d(camera_transform, corners[0]) else {
+        return;
+    };
+    let Ok(b) = camera.viewport_to_world_2d(camera_transform, corners[1]) else {
+        return;
+    };
+    let min = a.min(b);
+    let max = a.max(b);
+    let x0 = (min.x / tile_w).floor() as i32;
+    let x1 = (max.x / tile_w).ceil() as i32;
+    let y0 = (min.y / tile_h).floor() as i32;
+    let y1 = (max.y / tile_h).ceil() as i32;
+
+    const MAX_GRID_LINES: i32 = 400;
+    if config.show_grid && (x1 - x0) + (y1 - y0) <= MAX_GRID_LINES {
+        for x in x0..=x1 {
+            let x_pos = x as f32 * tile_w;
+            gizmos.line_2d(Vec2::new(x_pos, min.y), Vec2::new(x_pos, max.y), grid_color);
+        }
+        for y in y0..=y1 {
+            let y_pos = y as f32 * tile_h;
+            gizmos.line_2d(Vec2::new(min.x, y_pos), Vec2::new(max.x, y_pos), grid_color);
+        }
     }
 
+    gizmos.line_2d(Vec2::new(0.0, min.y), Vec2::new(0.0, max.y), axis_color);
+    gizmos.line_2d(Vec2::new(min.x, 0.0), Vec2::new(max.x, 0.0), axis_color);
+}
+
+/// hover / 粘贴预览框（固定尺寸与无限地图共用）。
+fn draw_overlays(
+    gizmos: &mut Gizmos,
+    window: &Window,
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+    config: &EditorConfig,
+    fixed_size: Option<(u32, u32)>,
+    tools: &ToolState,
+    clipboard: &Clipboard,
+    paste: &PasteState,
+) {
+    let tile_w = config.tile_size.x as f32;
+    let tile_h = config.tile_size.y as f32;
+    let in_map = |x: i32, y: i32| match fixed_size {
+        Some((w, h)) => x >= 0 && y >= 0 && (x as u32) < w && (y as u32) < h,
+        None => true,
+    };
+
     // hover 格子高亮（仅在鼠标在右侧画布区域时）
     if config.show_hover {
         if let Some(cursor_pos) = window.cursor_position() {
@@ -99,19 +173,16 @@ pub fn draw_canvas_helpers(
                 if let Ok(world_pos) = camera.viewport_to_world_2d(camera_transform, cursor_pos) {
                     let x = (world_pos.x / tile_w).floor() as i32;
                     let y = (world_pos.y / tile_h).floor() as i32;
-                    if x >= 0 && y >= 0 {
-                        let (x, y) = (x as u32, y as u32);
-                        if x < map_w && y < map_h {
-                            let x0 = x as f32 * tile_w;
-                            let y0 = y as f32 * tile_h;
-                            let x1 = x0 + tile_w;
-                            let y1 = y0 + tile_h;
-                            let hover_color = Color::srgba(0.25, 0.45, 0.95, 0.85);
-                            gizmos.line_2d(Vec2::new(x0, y0), Vec2::new(x1, y0), hover_color);
-                            gizmos.line_2d(Vec2::new(x1, y0), Vec2::new(x1, y1), hover_color);
-                            gizmos.line_2d(Vec2::new(x1, y1), Vec2::new(x0, y1), hover_color);
-                            gizmos.line_2d(Vec2::new(x0, y1), Vec2::new(x0, y0), hover_color);
-                        }
+                    if in_map(x, y) {
+                        let x0 = x as f32 * tile_w;
+                        let y0 = y as f32 * tile_h;
+                        let x1 = x0 + tile_w;
+                        let y1 = y0 + tile_h;
+                        let hover_color = Color::srgba(0.25, 0.45, 0.95, 0.85);
+                        gizmos.line_2d(Vec2::new(x0, y0), Vec2::new(x1, y0), hover_color);
+                        gizmos.line_2d(Vec2::new(x1, y0), Vec2::new(x1, y1), hover_color);
+                        gizmos.line_2d(Vec2::new(x1, y1), Vec2::new(x0, y1), hover_color);
+                        gizmos.line_2d(Vec2::new(x0, y1), Vec2::new(x0, y0), hover_color);
                     }
                 }
             }
@@ -131,11 +202,10 @@ pub fn draw_canvas_helpers(
         };
         let px = (world_pos.x / tile_w).floor() as i32;
         let py = (world_pos.y / tile_h).floor() as i32;
-        if px < 0 || py < 0 {
+        if !in_map(px, py) {
             return;
         }
-        let (px, py) = (px as u32, py as u32);
-        let (pw, ph) = paste_dims(&clipboard, &paste);
+        let (pw, ph) = paste_dims(clipboard, paste);
         let x1 = (px as f32 + pw as f32) * tile_w;
         let y1 = (py as f32 + ph as f32) * tile_h;
         let x0 = px as f32 * tile_w;
@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::editor::types::{EditorConfig, TileBounds, TileMapData, TilesetLibrary, WorldCamera};
+
+/// 视野内格子过多（缩得很小）时不画，避免每帧遍历大量格子。
+const MAX_OVERLAY_CELLS: u64 = 200_000;
+
+/// 在画布上叠加显示已放置 tile 的碰撞形状（按格子上的旋转 / 翻转变换；隐藏图层跳过）。
+pub fn draw_collision_overlay(
+    mut gizmos: Gizmos,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_q: Query<(&Camera, &GlobalTransform), With<WorldCamera>>,
+    config: Res<EditorConfig>,
+    map: Option<Res<TileMapData>>,
+    lib: Res<TilesetLibrary>,
+) {
+    if !config.show_collision {
+        return;
+    }
+    let Some(map) = map else {
+        return;
+    };
+    let Ok(window) = windows.single() else {
+        return;
+    };
+    let Ok((camera, camera_transform)) = camera_q.single() else {
+        return;
+    };
+    let tile = config.tile_size.as_vec2();
+    if tile.x <= 0.0 || tile.y <= 0.0 {
+        return;
+    }
+
+    let Ok(a) = camera.viewport_to_world_2d(camera_transform, Vec2::ZERO) else {
+        return;
+    };
+    let Ok(b) = camera.viewport_to_world_2d(camera_transform, Vec2::new(window.width(), window.height())) else {
+        return;
+    };
+    let (min, max) = (a.min(b) / tile, a.max(b) / tile);
+    let view = TileBounds::from_corners(
+        min.x.floor() as i32,
+        min.y.floor() as i32,
+        max.x.ceil() as i32,
+        max.y.ceil() as i32,
+    );
+    let view = match map.fixed_size() {
+        Some((w, h)) if w > 0 && h > 0 => {
+            match view.intersect(&TileBounds::from_corners(0, 0, w as i32 - 1, h as i32 - 1)) {
+                Some(v) => v,
+                None => return,
+            }
+        }
+        Some(_) => return,
+        None => view,
+    };
+    if view.width() as u64 * view.height() as u64 > MAX_OVERLAY_CELLS {
+        return;
+    }
+
+    let entries: HashMap<&str, _> = lib
+        .entries
+        .iter()
+        .filter(|e| !e.tiles.is_empty())
+        .map(|e| (e.id.as_str(), e))
+        .collect();
+    if entries.is_empty() {
+        return;
+    }
+
+    let color = Color::srgba(1.0, 0.55, 0.1, 0.9);
+    for layer in 0..map.layers {
+        if map.layer_data.get(layer as usize).is_some_and(|d| !d.visible) {
+            continue;
+        }
+        for (x, y, tile_view) in map.tiles_in_rect(layer, view) {
+            let Some(entry) = entries.get(tile_view.tileset_id.as_str()) else {
+                continue;
+            };
+            let shapes = entry.collision(tile_view.index);
+            if shapes.is_empty() {
+                continue;
+            }
+            let origin = Vec2::new(x as f32, y as f32);
+            for shape in shapes {
+                let points: Vec<Vec2> = shape
+                    .transformed(tile_view.orientation())
+                    .points()
+                    .into_iter()
+                    .map(|[px, py]| (origin + Vec2::new(px, py)) * tile)
+                    .collect();
+                let Some(&first) = points.first() else {
+                    continue;
+                };
+                gizmos.linestrip_2d(points.into_iter().chain(std::iter::once(first)), color);
+            }
+        }
+    }
+}
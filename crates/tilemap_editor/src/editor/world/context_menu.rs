@@ -1,14 +1,19 @@
 use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
 
+use tilemap_core::ops;
+
 use crate::editor::types::{
-    CellChange, Clipboard, ContextMenuAction, ContextMenuCommand, ContextMenuState, EditCommand,
-    EditorConfig, LayerState, PasteState, SelectionRect, SelectionState, TileEntities, TileMapData,
+    Clipboard, ContextMenuAction, ContextMenuCommand, ContextMenuState, EditCommand, FillMode,
+    EditorConfig, EditorState, LayerState, PasteState, SelectionState, TileCell, TileMapData,
     ToolKind, ToolState, UndoStack, WorldCamera,
 };
 use crate::editor::{LEFT_PANEL_WIDTH_PX, UI_TOP_RESERVED_PX};
 
-use super::{apply_tile_change, cursor_tile_pos, TilemapRenderParams};
+use super::{
+    cursor_tile_pos, redo_once, render_cell_changes, select_all_rect, undo_once,
+    TilemapRenderParams,
+};
 
 /// 右键菜单：先支持粘贴模式的变换控制（后续可扩展到其他工具）。
 pub fn context_menu_open_close(
@@ -18,7 +23,7 @@ pub fn context_menu_open_close(
     tools: Res<ToolState>,
     _clipboard: Res<Clipboard>,
     config: Res<EditorConfig>,
-    tile_entities: Option<Res<TileEntities>>,
+    map: Option<Res<TileMapData>>,
     camera_q: Query<(&Camera, &GlobalTransform), With<WorldCamera>>,
     mut menu: ResMut<ContextMenuState>,
 ) {
@@ -50,15 +55,14 @@ pub fn context_menu_open_close(
         menu.consume_left_click = false;
         menu.screen_pos = pos;
         menu.map_pos = None;
-        if let Some(tile_entities) = tile_entities.as_deref() {
+        if let Some(map) = map.as_deref() {
             if let Ok((camera, camera_transform)) = camera_q.single() {
                 menu.map_pos = cursor_tile_pos(
                     window,
                     camera,
                     camera_transform,
                     &config,
-                    tile_entities.width,
-                    tile_entities.height,
+                    map.fixed_size(),
                 );
             }
         }
@@ -71,65 +75,36 @@ pub fn context_menu_open_close(
     // “点空白关闭”交给 UI 侧的 ContextMenuBackdrop 处理，避免 world 侧做不精确的 bounds 判断。
 }
 
+/// 复制选区：剪贴板为选区外接框，不在选区里的格子为空。
 pub(super) fn copy_selection_to_clipboard(
     layer: u32,
-    rect: SelectionRect,
+    selection: &SelectionState,
     map: &TileMapData,
     clipboard: &mut Clipboard,
 ) {
-    let w = rect.width();
-    let h = rect.height();
-    let mut tiles = Vec::with_capacity((w * h) as usize);
-    for y in rect.min.y..=rect.max.y {
-        for x in rect.min.x..=rect.max.x {
-            let idx = map.idx_layer(layer, x, y);
-            tiles.push(map.tiles[idx].clone());
-        }
-    }
-    clipboard.width = w;
-    clipboard.height = h;
-    clipboard.tiles = tiles;
+    *clipboard = ops::extract_cells(map, layer, &selection.cells());
 }
 
 pub(super) fn clear_selection_to_none(
     layer: u32,
-    rect: SelectionRect,
+    selection: &SelectionState,
     map: &mut TileMapData,
     config: &EditorConfig,
     render: &mut TilemapRenderParams,
     undo: &mut UndoStack,
 ) {
-    let mut cmd = EditCommand::default();
-    for y in rect.min.y..=rect.max.y {
-        for x in rect.min.x..=rect.max.x {
-            let idx = map.idx_layer(layer, x, y);
-            if map.tiles[idx].is_none() {
-                continue;
-            }
-            let before = map.tiles[idx].clone();
-            let after = None;
-            map.tiles[idx] = None;
-            cmd.changes.push(CellChange { idx, before, after });
-        }
-    }
-    if cmd.changes.is_empty() {
+    let changes = ops::fill_cells(map, layer, &selection.cells(), TileCell::EMPTY);
+    if changes.is_empty() {
         return;
     }
 
-    let layer_len = map.layer_len();
-    let layer_offset = (layer as usize) * layer_len;
-    for ch in &cmd.changes {
-        let local = ch.idx.saturating_sub(layer_offset);
-        let x = (local % map.width as usize) as u32;
-        let y = (local / map.width as usize) as u32;
-        apply_tile_change(render, config, layer, x, y, &ch.before, &ch.after);
-    }
-    undo.push(cmd);
+    render_cell_changes(render, config, map, &changes);
+    undo.push(EditCommand { changes, ..Default::default() });
 }
 
 pub fn apply_context_menu_command(
     mut cmd: ResMut<ContextMenuCommand>,
-    mut tools: ResMut<ToolState>,
+    (mut tools, mut editor_state): (ResMut<ToolState>, ResMut<EditorState>),
     mut paste: ResMut<PasteState>,
     mut selection: ResMut<SelectionState>,
     mut clipboard: ResMut<Clipboard>,
@@ -149,43 +124,17 @@ pub fn apply_context_menu_command(
             let Some(mut map) = map else {
                 return;
             };
-            let Some(cmd) = undo.undo.pop() else {
-                return;
-            };
-            let layer_len = map.layer_len();
-            for ch in &cmd.changes {
-                if ch.idx < map.tiles.len() {
-                    map.tiles[ch.idx] = ch.before.clone();
-                    let layer = (ch.idx / layer_len) as u32;
-                    let local = ch.idx % layer_len;
-                    let x = (local % map.width as usize) as u32;
-                    let y = (local / map.width as usize) as u32;
-                    apply_tile_change(&mut render, &config, layer, x, y, &ch.after, &ch.before);
-                }
+            if undo_once(&mut map, &mut render, &config, &mut undo) {
+                info!("context cmd: undo");
             }
-            undo.redo.push(cmd);
-            info!("context cmd: undo");
         }
         ContextMenuAction::Redo => {
             let Some(mut map) = map else {
                 return;
             };
-            let Some(cmd) = undo.redo.pop() else {
-                return;
-            };
-            let layer_len = map.layer_len();
-            for ch in &cmd.changes {
-                if ch.idx < map.tiles.len() {
-                    map.tiles[ch.idx] = ch.after.clone();
-                    let layer = (ch.idx / layer_len) as u32;
-                    let local = ch.idx % layer_len;
-                    let x = (local % map.width as usize) as u32;
-                    let y = (local / map.width as usize) as u32;
-                    apply_tile_change(&mut render, &config, layer, x, y, &ch.before, &ch.after);
-                }
+            if redo_once(&mut map, &mut render, &config, &mut undo) {
+                info!("context cmd: redo");
             }
-            undo.undo.push(cmd);
-            info!("context cmd: redo");
         }
         ContextMenuAction::EnterPaste => {
             if clipboard.width > 0 && clipboard.height > 0 && !clipboard.tiles.is_empty() {
@@ -193,25 +142,28 @@ pub fn apply_context_menu_command(
                     tools.return_after_paste = Some(tools.tool);
                 }
                 tools.tool = ToolKind::Paste;
-                info!(
-                    "context cmd: enter paste (keep transform rot={} flip_x={} flip_y={})",
-                    paste.rot % 4,
-                    paste.flip_x,
-                    paste.flip_y
-                );
+                info!("context cmd: enter paste (keep transform {})", paste.orientation);
             } else {
                 info!("context cmd: enter paste ignored (clipboard empty)");
             }
         }
+        ContextMenuAction::ClipboardAsStamp => {
+            if clipboard.width > 0 && clipboard.height > 0 && !clipboard.tiles.is_empty() {
+                editor_state.stamp = Some(clipboard.clone());
+                tools.return_after_paste = None;
+                tools.tool = ToolKind::Pencil;
+                info!("context cmd: clipboard as stamp {}x{}", clipboard.width, clipboard.height);
+            }
+        }
         ContextMenuAction::SelectionCopy => {
             let Some(map) = map.as_deref() else {
                 return;
             };
-            let Some(rect) = selection.rect else {
+            if selection.rect.is_none() {
                 return;
-            };
+            }
             let layer = layer_state.active.min(map.layers.saturating_sub(1));
-            copy_selection_to_clipboard(layer, rect, map, &mut clipboard);
+            copy_selection_to_clipboard(layer, &selection, map, &mut clipboard);
             tools.tool = ToolKind::Select;
             info!("context cmd: selection copy {}x{}", clipboard.width, clipboard.height);
         }
@@ -219,12 +171,12 @@ pub fn apply_context_menu_command(
             let Some(mut map) = map else {
                 return;
             };
-            let Some(rect) = selection.rect else {
+            if selection.rect.is_none() {
                 return;
-            };
+            }
             let layer = layer_state.active.min(map.layers.saturating_sub(1));
-            copy_selection_to_clipboard(layer, rect, &map, &mut clipboard);
-            clear_selection_to_none(layer, rect, &mut map, &config, &mut render, &mut undo);
+            copy_selection_to_clipboard(layer, &selection, &map, &mut clipboard);
+            clear_selection_to_none(layer, &selection, &mut map, &config, &mut render, &mut undo);
             tools.tool = ToolKind::Select;
             info!("context cmd: selection cut {}x{}", clipboard.width, clipboard.height);
         }
@@ -232,11 +184,11 @@ pub fn apply_context_menu_command(
             let Some(mut map) = map else {
                 return;
             };
-            let Some(rect) = selection.rect else {
+            if selection.rect.is_none() {
                 return;
-            };
+            }
             let layer = layer_state.active.min(map.layers.saturating_sub(1));
-            clear_selection_to_none(layer, rect, &mut map, &config, &mut render, &mut undo);
+            clear_selection_to_none(layer, &selection, &mut map, &config, &mut render, &mut undo);
             tools.tool = ToolKind::Select;
             info!("context cmd: selection delete");
         }
@@ -244,23 +196,28 @@ pub fn apply_context_menu_command(
             let Some(map) = map.as_deref() else {
                 return;
             };
-            if map.width == 0 || map.height == 0 {
+            let Some(rect) = select_all_rect(map) else {
                 return;
-            }
-            let rect = SelectionRect {
-                min: UVec2::ZERO,
-                max: UVec2::new(map.width - 1, map.height - 1),
             };
-            selection.dragging = false;
-            selection.start = rect.min;
-            selection.current = rect.max;
-            selection.rect = Some(rect);
+            selection.set_rect(rect);
             tools.tool = ToolKind::Select;
             info!("context cmd: select all");
         }
+        ContextMenuAction::SelectSameTile => {
+            let (Some(map), Some(pos)) = (map.as_deref(), menu.map_pos) else {
+                return;
+            };
+            // 与魔棒共用取样图层；不要求相连。
+            let sample = tools.wand_sample.layers(map, layer_state.active);
+            let cells = ops::fill_region(map, &sample, pos.x, pos.y, FillMode::Global);
+            selection.set_cells(cells.into_iter().map(|(x, y)| IVec2::new(x, y)));
+            if !tools.tool.edits_selection() {
+                tools.tool = ToolKind::Select;
+            }
+            info!("context cmd: select same tile at ({}, {})", pos.x, pos.y);
+        }
         ContextMenuAction::SelectionDeselect => {
-            selection.dragging = false;
-            selection.rect = None;
+            selection.clear();
             tools.tool = ToolKind::Select;
             info!("context cmd: deselect");
         }
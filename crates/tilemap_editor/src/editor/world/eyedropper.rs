@@ -2,8 +2,8 @@ use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
 
 use crate::editor::types::{
-    ContextMenuState, EditorConfig, EditorState, MapSizeFocus, MapSizeInput, TileEntities,
-    TileMapData, TilesetLibrary, ToolKind, ToolState, WorldCamera,
+    ContextMenuState, EditorConfig, EditorState, LayerState, MapSizeFocus, MapSizeInput, TileMapData,
+    TilesetLibrary, ToolKind, ToolState, WorldCamera,
 };
 
 pub fn eyedropper_hold_shortcut(
@@ -33,7 +33,7 @@ pub fn eyedropper_hold_shortcut(
     }
 }
 
-/// 吸管工具：点击格子后把该格子的 tile 设为当前选择。
+/// 吸管工具：点击格子后把该格子的 tile 设为当前选择（读取哪些图层见 `ToolState::eyedropper_sample`）。
 pub fn eyedropper_with_mouse(
     buttons: Res<ButtonInput<MouseButton>>,
     keys: Res<ButtonInput<KeyCode>>,
@@ -41,9 +41,8 @@ pub fn eyedropper_with_mouse(
     menu: Res<ContextMenuState>,
     windows: Query<&Window, With<PrimaryWindow>>,
     camera_q: Query<(&Camera, &GlobalTransform), With<WorldCamera>>,
-    config: Res<EditorConfig>,
+    (config, layer_state): (Res<EditorConfig>, Res<LayerState>),
     map: Option<Res<TileMapData>>,
-    tile_entities: Option<Res<TileEntities>>,
     mut state: ResMut<EditorState>,
     mut lib: ResMut<TilesetLibrary>,
 ) {
@@ -67,9 +66,6 @@ pub fn eyedropper_with_mouse(
     let Some(map) = map else {
         return;
     };
-    let Some(tile_entities) = tile_entities else {
-        return;
-    };
     let Ok(window) = windows.single() else {
         return;
     };
@@ -81,22 +77,23 @@ pub fn eyedropper_with_mouse(
         camera,
         camera_transform,
         &config,
-        tile_entities.width,
-        tile_entities.height,
+        map.fixed_size(),
     ) else {
         return;
     };
 
-    let Some(idx) = map.topmost_idx_at(pos.x, pos.y) else {
-        return;
-    };
-    let Some(tile) = map.tiles.get(idx).cloned().flatten() else {
+    let sample = tools.eyedropper_sample.layers(&map, layer_state.active);
+    let Some(tile) = map
+        .topmost_layer_among(&sample, pos.x, pos.y)
+        .and_then(|layer| map.view(map.cell(layer, pos.x, pos.y)))
+    else {
         return;
     };
 
     state.selected_tile = tile.index;
+    state.stamp = None;
     lib.active_id = Some(tile.tileset_id.clone());
-    if let Some(entry) = lib.entries.iter().find(|e| e.id == tile.tileset_id) {
+    if let Some(entry) = lib.entries.iter().find(|e| &e.id == tile.tileset_id) {
         let cat = entry.category.trim();
         if !cat.is_empty() {
             lib.active_category = cat.to_string();
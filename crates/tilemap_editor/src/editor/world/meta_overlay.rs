@@ -0,0 +1,79 @@
+use bevy::prelude::*;
+
+use crate::editor::types::{
+    EditorConfig, MetaBrushState, MetaField, MetaOverlayTile, TileMapData, PASSAGE_DIRECTIONS, PASS_BLOCK_DOWN,
+    PASS_BLOCK_LEFT, PASS_BLOCK_RIGHT, PASS_BLOCK_UP,
+};
+use crate::editor::util::despawn_silently;
+
+use super::tile_world_center;
+
+/// 叠加层的 z：高于所有图层（每层占 10）。
+const META_OVERLAY_Z: f32 = 900.0;
+
+/// 元数据模式下的画布叠加层：区域 / 地形按值着色，通行在被阻挡的一侧画红条。
+///
+/// 只在地图或笔刷变化时整体重建（元数据是稀疏的，数量通常远小于格子数）。
+pub fn sync_meta_overlay(
+    mut commands: Commands,
+    brush: Res<MetaBrushState>,
+    config: Res<EditorConfig>,
+    map: Option<Res<TileMapData>>,
+    existing: Query<Entity, With<MetaOverlayTile>>,
+) {
+    let map_changed = map.as_ref().is_some_and(|m| m.is_changed());
+    if !(brush.is_changed() || map_changed || (map.is_none() && !existing.is_empty())) {
+        return;
+    }
+    for e in existing.iter() {
+        despawn_silently(&mut commands, e);
+    }
+    let Some(map) = map else {
+        return;
+    };
+    if !brush.enabled {
+        return;
+    }
+
+    let tile = config.tile_size.as_vec2();
+    for (x, y, meta) in map.meta.iter() {
+        let value = meta.get(brush.field);
+        if value == 0 {
+            continue;
+        }
+        let center = tile_world_center(x, y, config.tile_size, META_OVERLAY_Z);
+        if brush.field != MetaField::Passage {
+            commands.spawn((
+                Sprite::from_color(value_color(brush.field, value), tile),
+                Transform::from_translation(center),
+                MetaOverlayTile,
+            ));
+            continue;
+        }
+        let bar = (tile.x.min(tile.y) / 6.0).max(1.0);
+        for (bit, _) in PASSAGE_DIRECTIONS {
+            if value & bit == 0 {
+                continue;
+            }
+            let (offset, size) = match bit {
+                PASS_BLOCK_UP => (Vec2::new(0.0, (tile.y - bar) * 0.5), Vec2::new(tile.x, bar)),
+                PASS_BLOCK_DOWN => (Vec2::new(0.0, (bar - tile.y) * 0.5), Vec2::new(tile.x, bar)),
+                PASS_BLOCK_LEFT => (Vec2::new((bar - tile.x) * 0.5, 0.0), Vec2::new(bar, tile.y)),
+                PASS_BLOCK_RIGHT => (Vec2::new((tile.x - bar) * 0.5, 0.0), Vec2::new(bar, tile.y)),
+                _ => continue,
+            };
+            commands.spawn((
+                Sprite::from_color(Color::srgba(0.95, 0.15, 0.15, 0.7), size),
+                Transform::from_translation(center + offset.extend(0.0)),
+                MetaOverlayTile,
+            ));
+        }
+    }
+}
+
+/// 相邻的值用黄金角错开色相，便于区分；地形比区域更暗一些。
+fn value_color(field: MetaField, value: u8) -> Color {
+    let hue = (value as f32 * 137.5) % 360.0;
+    let lightness = if field == MetaField::Terrain { 0.4 } else { 0.55 };
+    Color::hsla(hue, 0.85, lightness, 0.4)
+}
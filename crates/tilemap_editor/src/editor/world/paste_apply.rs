@@ -2,13 +2,15 @@ use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
 
+use tilemap_core::ops;
+
 use crate::editor::types::{
-    CellChange, Clipboard, ContextMenuState, EditCommand, EditorConfig, LayerState, PasteState,
-    TileMapData, TileRef, ToolKind, ToolState, UndoStack, WorldCamera,
+    Clipboard, ContextMenuState, EditCommand, EditorConfig, LayerState, PasteState,
+    TileMapData, ToolKind, ToolState, UndoStack, WorldCamera,
 };
 
-use super::{apply_tile_change, cursor_tile_pos, TilemapRenderParams};
-use super::paste_helpers::{paste_dims, paste_dst_xy};
+use super::{cursor_tile_pos, render_cell_changes, TilemapRenderParams};
+use super::paste_helpers::paste_dims;
 
 #[derive(SystemParam)]
 pub(in crate::editor) struct PasteWithMouseParams<'w, 's> {
@@ -86,113 +88,44 @@ pub fn paste_with_mouse(params: PasteWithMouseParams) {
         camera,
         camera_transform,
         &config,
-        map.width,
-        map.height,
+        map.fixed_size(),
     ) else {
         return;
     };
 
     info!(
-        "paste click at map ({}, {}): clipboard {}x{} tiles={} | rot={} flip_x={} flip_y={}",
+        "paste click at map ({}, {}): clipboard {}x{} tiles={} | transform={}",
         pos.x,
         pos.y,
         clipboard.width,
         clipboard.height,
         clipboard.tiles.len(),
-        paste.rot,
-        paste.flip_x,
-        paste.flip_y
+        paste.orientation
     );
 
     let (pw, ph) = paste_dims(&clipboard, &paste);
-    let mut cmd = EditCommand::default();
-    let mut attempted = 0u32;
-    let mut oob = 0u32;
-    let mut same = 0u32;
-    let mut sampled = 0u32;
-
-    for sy in 0..clipboard.height {
-        for sx in 0..clipboard.width {
-            let Some((cx, cy)) = paste_dst_xy(sx, sy, &clipboard, &paste) else {
-                continue;
-            };
-            debug_assert!(cx < pw && cy < ph);
-            attempted += 1;
-
-            let dst_x = pos.x + cx;
-            let dst_y = pos.y + cy;
-            if dst_x >= map.width || dst_y >= map.height {
-                oob += 1;
-                continue;
-            }
-
-            let src_idx = (sy * clipboard.width + sx) as usize;
-            let after = clipboard.tiles.get(src_idx).cloned().unwrap_or(None);
-            let dst_idx = map.idx_layer(layer, dst_x, dst_y);
-            if map.tiles[dst_idx] == after {
-                same += 1;
-                continue;
-            }
-
-            if sampled < 8 {
-                sampled += 1;
-                let after_label = match &after {
-                    Some(t) => format!("{}:{}", t.tileset_id, t.index),
-                    None => "None".to_string(),
-                };
-                let before_label = match &map.tiles[dst_idx] {
-                    Some(t) => format!("{}:{}", t.tileset_id, t.index),
-                    None => "None".to_string(),
-                };
-                info!(
-                    "paste sample: src({},{}) -> local({},{}) -> dst({},{}) before={} after={}",
-                    sx, sy, cx, cy, dst_x, dst_y, before_label, after_label
-                );
-            }
-
-            let before = map.tiles[dst_idx].clone();
-            map.tiles[dst_idx] = after.clone();
-            cmd.changes.push(CellChange {
-                idx: dst_idx,
-                before,
-                after,
-            });
-        }
-    }
+    let cmd = EditCommand {
+        changes: ops::stamp_region(&mut map, layer, &clipboard, pos.x, pos.y, paste.orientation),
+        ..Default::default()
+    };
 
     if cmd.changes.is_empty() {
-        info!(
-            "paste result: no changes (attempted={}, oob={}, same={}) pw={} ph={}",
-            attempted, oob, same, pw, ph
-        );
+        info!("paste result: no changes pw={} ph={}", pw, ph);
         return;
     }
 
-    info!(
-        "paste result: changes={} (attempted={}, oob={}, same={}) pw={} ph={}",
-        cmd.changes.len(),
-        attempted,
-        oob,
-        same,
-        pw,
-        ph
-    );
+    info!("paste result: changes={} pw={} ph={}", cmd.changes.len(), pw, ph);
 
     // 局部刷新渲染
     let mut missing_atlas = 0u32;
-    let layer_len = map.layer_len();
-    let layer_offset = (layer as usize) * layer_len;
     for ch in &cmd.changes {
-        let local = ch.idx.saturating_sub(layer_offset);
-        let x = (local % map.width as usize) as u32;
-        let y = (local / map.width as usize) as u32;
-        if let Some(TileRef { tileset_id, .. }) = &ch.after {
-            if render.runtime.by_id.get(tileset_id).is_none() {
+        if let Some(tile) = map.view(ch.after) {
+            if !render.runtime.by_id.contains_key(tile.tileset_id) {
                 missing_atlas += 1;
             }
         }
-        apply_tile_change(&mut render, &config, layer, x, y, &ch.before, &ch.after);
     }
+    render_cell_changes(&mut render, &config, &map, &cmd.changes);
 
     if missing_atlas > 0 {
         warn!(
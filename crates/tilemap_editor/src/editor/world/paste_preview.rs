@@ -6,7 +6,7 @@ use crate::editor::types::{
     TilesetRuntime, ToolKind, ToolState, WorldCamera,
 };
 use crate::editor::util::despawn_silently;
-use crate::editor::tileset::rect_for_tile_index;
+use crate::editor::tileset::{rect_for_tile_index, tile_anchor_offset};
 
 use super::{cursor_tile_pos, tile_world_center};
 use super::paste_helpers::{paste_dims, paste_dst_xy};
@@ -57,8 +57,7 @@ pub fn update_paste_preview(
         camera,
         camera_transform,
         &config,
-        tile_entities.width,
-        tile_entities.height,
+        tile_entities.fixed_size(),
     ) else {
         for &e in &preview.entities {
             if let Ok((_s, _t, mut v)) = q.get_mut(e) {
@@ -106,7 +105,10 @@ pub fn update_paste_preview(
             let src_idx = (sy * clipboard.width + sx) as usize;
             let dst_idx = (cy * pw + cx) as usize;
             if dst_idx < transformed.len() {
-                transformed[dst_idx] = clipboard.tiles.get(src_idx).cloned().unwrap_or(None);
+                transformed[dst_idx] = clipboard.tiles.get(src_idx).cloned().flatten().map(|mut t| {
+                    t.transform(paste.orientation);
+                    t
+                });
             }
         }
     }
@@ -119,9 +121,9 @@ pub fn update_paste_preview(
                 continue;
             };
 
-            let dst_x = pos.x + cx;
-            let dst_y = pos.y + cy;
-            if dst_x >= tile_entities.width || dst_y >= tile_entities.height {
+            let dst_x = pos.x + cx as i32;
+            let dst_y = pos.y + cy as i32;
+            if !tile_entities.contains(dst_x, dst_y) {
                 *vis = Visibility::Hidden;
                 continue;
             }
@@ -133,7 +135,7 @@ pub fn update_paste_preview(
     }
 }
 
-fn apply_preview_tile_visual(
+pub(super) fn apply_preview_tile_visual(
     runtime: &TilesetRuntime,
     tile: &Option<TileRef>,
     sprite: &mut Sprite,
@@ -142,8 +144,8 @@ fn apply_preview_tile_visual(
     config: &EditorConfig,
 ) {
     match tile {
-        Some(TileRef { tileset_id, index, rot, flip_x, flip_y }) => {
-            let Some(atlas) = runtime.by_id.get(tileset_id) else {
+        Some(tile) => {
+            let Some(atlas) = runtime.by_id.get(&tile.tileset_id) else {
                 sprite.rect = None;
                 sprite.flip_x = false;
                 sprite.flip_y = false;
@@ -152,10 +154,15 @@ fn apply_preview_tile_visual(
                 return;
             };
             sprite.image = atlas.texture.clone();
-            sprite.rect = Some(rect_for_tile_index(*index, atlas.columns, config.tile_size));
-            sprite.flip_x = *flip_x;
-            sprite.flip_y = *flip_y;
-            let r = (*rot % 4) as f32;
+            sprite.rect = Some(rect_for_tile_index(tile.index, atlas.columns, atlas.grid));
+            let (w, h) = tile.orientation().dims(atlas.grid.tile_width, atlas.grid.tile_height);
+            let anchor = tile_anchor_offset(UVec2::new(w, h), config.tile_size);
+            tf.translation += anchor.extend(0.0);
+            // Sprite 先翻转再由 Transform 旋转，需换成对应的拆分形式。
+            let (rot, flip_x) = tile.orientation().to_sprite_parts();
+            sprite.flip_x = flip_x;
+            sprite.flip_y = false;
+            let r = rot as f32;
             tf.rotation = Quat::from_rotation_z(-r * std::f32::consts::FRAC_PI_2);
             *vis = Visibility::Visible;
         }
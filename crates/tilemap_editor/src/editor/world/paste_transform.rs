@@ -2,10 +2,11 @@ use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
 
 use crate::editor::types::{
-    Clipboard, ContextMenuAction, ContextMenuState, EditorConfig, LayerState, PasteState,
-    SelectionState, TileMapData, ToolKind, ToolState, UndoStack, WorldCamera,
+    Clipboard, ContextMenuAction, ContextMenuState, EditorConfig, EditorState, LayerState, Orientation,
+    PasteState, SelectionState, TileMapData, ToolKind, ToolState, UndoStack, WorldCamera,
 };
 
+use super::paste_helpers::action_orientation;
 use super::{
     cursor_tile_pos, try_flip_map_tile_x, try_flip_map_tile_y, try_reset_map_tile_transform,
     try_rotate_map_tile_ccw, try_rotate_map_tile_cw, TilemapRenderParams,
@@ -16,7 +17,7 @@ pub fn paste_transform_shortcuts(
     keys: Res<ButtonInput<KeyCode>>,
     windows: Query<&Window, With<PrimaryWindow>>,
     camera_q: Query<(&Camera, &GlobalTransform), With<WorldCamera>>,
-    tools: Res<ToolState>,
+    (tools, editor_state): (Res<ToolState>, Res<EditorState>),
     layer_state: Res<LayerState>,
     clipboard: Res<Clipboard>,
     config: Res<EditorConfig>,
@@ -52,27 +53,18 @@ pub fn paste_transform_shortcuts(
     // 因此不能提前把 map move 掉。
     let mut map_opt = map;
 
-    // 粘贴模式：永远调整粘贴变换（预览/落地）。
-    if tools.tool == ToolKind::Paste {
-        match action {
-            ContextMenuAction::PasteRotateCcw => paste.rot = (paste.rot + 3) % 4,
-            ContextMenuAction::PasteRotateCw => paste.rot = (paste.rot + 1) % 4,
-            ContextMenuAction::PasteFlipX => paste.flip_x = !paste.flip_x,
-            ContextMenuAction::PasteFlipY => paste.flip_y = !paste.flip_y,
-            _ => {}
+    // 粘贴模式 / 带印章的绘制工具：永远调整粘贴变换（预览/落地）。
+    let stamping = tools.tool.uses_stamp() && editor_state.stamp.is_some();
+    if tools.tool == ToolKind::Paste || stamping {
+        if let Some(op) = action_orientation(action) {
+            paste.orientation = paste.orientation.then(op);
         }
-        info!(
-            "paste transform changed (tool=Paste): rot={} ({}deg), flip_x={}, flip_y={}",
-            paste.rot % 4,
-            (paste.rot as u32 % 4) * 90,
-            paste.flip_x,
-            paste.flip_y
-        );
+        info!("paste transform changed (tool={:?}): {}", tools.tool, paste.orientation);
         return;
     }
 
-    // 选择工具且存在选区：优先对“选区内容”做旋转/翻转/重置（更接近 RM 的使用习惯）。
-    if tools.tool == ToolKind::Select {
+    // 选择 / 魔棒工具且存在选区：优先对“选区内容”做旋转/翻转/重置（更接近 RM 的使用习惯）。
+    if tools.tool.edits_selection() {
         if selection.rect.is_some() {
             if let Some(mut map) = map_opt.take() {
                 let layer = layer_state.active.min(map.layers.saturating_sub(1));
@@ -111,8 +103,7 @@ pub fn paste_transform_shortcuts(
             camera,
             camera_transform,
             &config,
-            tile_entities.width,
-            tile_entities.height,
+            tile_entities.fixed_size(),
         )
     })();
 
@@ -144,20 +135,12 @@ pub fn paste_transform_shortcuts(
         return;
     }
 
-    match action {
-        ContextMenuAction::PasteRotateCcw => paste.rot = (paste.rot + 3) % 4,
-        ContextMenuAction::PasteRotateCw => paste.rot = (paste.rot + 1) % 4,
-        ContextMenuAction::PasteFlipX => paste.flip_x = !paste.flip_x,
-        ContextMenuAction::PasteFlipY => paste.flip_y = !paste.flip_y,
-        _ => {}
+    if let Some(op) = action_orientation(action) {
+        paste.orientation = paste.orientation.then(op);
     }
     info!(
-        "paste preset transform changed (tool={:?}): rot={} ({}deg), flip_x={}, flip_y={}",
-        tools.tool,
-        paste.rot % 4,
-        (paste.rot as u32 % 4) * 90,
-        paste.flip_x,
-        paste.flip_y
+        "paste preset transform changed (tool={:?}): {}",
+        tools.tool, paste.orientation
     );
 }
 
@@ -176,8 +159,8 @@ pub(super) fn apply_context_menu_paste_action(
         ContextMenuAction::PasteRotateCcw => {
             // 粘贴模式下：调整粘贴预览/落地变换；非粘贴模式：若右键指向某个已有图块，则旋转该图块。
             if tools.tool == ToolKind::Paste {
-                paste.rot = (paste.rot + 3) % 4;
-                info!("context cmd: rotate ccw (paste) -> rot={}", paste.rot % 4);
+                paste.orientation = paste.orientation.then(Orientation::ROT_CCW);
+                info!("context cmd: rotate ccw (paste) -> {}", paste.orientation);
                 return;
             }
             if try_rotate_map_tile_ccw(menu.map_pos, map, render, config, undo) {
@@ -185,14 +168,14 @@ pub(super) fn apply_context_menu_paste_action(
                 return;
             }
             if clipboard.width > 0 && clipboard.height > 0 && !clipboard.tiles.is_empty() {
-                paste.rot = (paste.rot + 3) % 4;
-                info!("context cmd: rotate ccw (preset) -> rot={}", paste.rot % 4);
+                paste.orientation = paste.orientation.then(Orientation::ROT_CCW);
+                info!("context cmd: rotate ccw (preset) -> {}", paste.orientation);
             }
         }
         ContextMenuAction::PasteRotateCw => {
             if tools.tool == ToolKind::Paste {
-                paste.rot = (paste.rot + 1) % 4;
-                info!("context cmd: rotate cw (paste) -> rot={}", paste.rot % 4);
+                paste.orientation = paste.orientation.then(Orientation::ROT_CW);
+                info!("context cmd: rotate cw (paste) -> {}", paste.orientation);
                 return;
             }
             if try_rotate_map_tile_cw(menu.map_pos, map, render, config, undo) {
@@ -200,14 +183,14 @@ pub(super) fn apply_context_menu_paste_action(
                 return;
             }
             if clipboard.width > 0 && clipboard.height > 0 && !clipboard.tiles.is_empty() {
-                paste.rot = (paste.rot + 1) % 4;
-                info!("context cmd: rotate cw (preset) -> rot={}", paste.rot % 4);
+                paste.orientation = paste.orientation.then(Orientation::ROT_CW);
+                info!("context cmd: rotate cw (preset) -> {}", paste.orientation);
             }
         }
         ContextMenuAction::PasteFlipX => {
             if tools.tool == ToolKind::Paste {
-                paste.flip_x = !paste.flip_x;
-                info!("context cmd: flip x (paste) -> {}", paste.flip_x);
+                paste.orientation = paste.orientation.then(Orientation::FLIP_X);
+                info!("context cmd: flip x (paste) -> {}", paste.orientation);
                 return;
             }
             if try_flip_map_tile_x(menu.map_pos, map, render, config, undo) {
@@ -215,14 +198,14 @@ pub(super) fn apply_context_menu_paste_action(
                 return;
             }
             if clipboard.width > 0 && clipboard.height > 0 && !clipboard.tiles.is_empty() {
-                paste.flip_x = !paste.flip_x;
-                info!("context cmd: flip x (preset) -> {}", paste.flip_x);
+                paste.orientation = paste.orientation.then(Orientation::FLIP_X);
+                info!("context cmd: flip x (preset) -> {}", paste.orientation);
             }
         }
         ContextMenuAction::PasteFlipY => {
             if tools.tool == ToolKind::Paste {
-                paste.flip_y = !paste.flip_y;
-                info!("context cmd: flip y (paste) -> {}", paste.flip_y);
+                paste.orientation = paste.orientation.then(Orientation::FLIP_Y);
+                info!("context cmd: flip y (paste) -> {}", paste.orientation);
                 return;
             }
             if try_flip_map_tile_y(menu.map_pos, map, render, config, undo) {
@@ -230,8 +213,8 @@ pub(super) fn apply_context_menu_paste_action(
                 return;
             }
             if clipboard.width > 0 && clipboard.height > 0 && !clipboard.tiles.is_empty() {
-                paste.flip_y = !paste.flip_y;
-                info!("context cmd: flip y (preset) -> {}", paste.flip_y);
+                paste.orientation = paste.orientation.then(Orientation::FLIP_Y);
+                info!("context cmd: flip y (preset) -> {}", paste.orientation);
             }
         }
         ContextMenuAction::PasteReset => {
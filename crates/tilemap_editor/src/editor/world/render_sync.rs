@@ -2,9 +2,10 @@ use bevy::prelude::*;
 use bevy_ecs_tilemap::prelude::*;
 use bevy::window::PrimaryWindow;
 
-use crate::editor::types::{EditorConfig, TileEntities, TileMapData, TilesetRuntime, WorldCamera};
+use crate::editor::types::{AnimatedTile, EditorConfig, TileEntities, TileMapData, TilesetRuntime, WorldCamera};
+use crate::editor::tileset::tile_anchor_offset;
 use crate::editor::util::despawn_silently;
-use tilemap_core::TileRef;
+use tilemap_core::{TileBounds, TileView};
 
 #[derive(Component)]
 pub(crate) struct ChunkFilled;
@@ -59,15 +60,12 @@ pub fn rebuild_tilemaps(
     tile_entities.tileset_order.clear();
 
     // 收集 tileset_id
-    let mut tileset_ids: Vec<String> = map
-        .tiles
-        .iter()
-        .filter_map(|t| t.as_ref().map(|r| r.tileset_id.clone()))
+    let tileset_ids: std::collections::BTreeSet<&String> = map
+        .iter_tiles()
+        .map(|(_, _, _, t)| t.tileset_id)
         .collect();
-    tileset_ids.sort();
-    tileset_ids.dedup();
 
-    tile_entities.tileset_order = tileset_ids;
+    tile_entities.tileset_order = tileset_ids.into_iter().cloned().collect();
 }
 
 fn ensure_chunk_tilemap(
@@ -77,34 +75,39 @@ fn ensure_chunk_tilemap(
     config: &EditorConfig,
     tileset_id: &str,
     layer: u32,
-    cx: u32,
-    cy: u32,
+    cx: i32,
+    cy: i32,
     visible: bool,
 ) -> Option<Entity> {
-    let tileset_id = tileset_id.to_string();
-    if let Some(entity) = tile_entities.chunk_entity(&tileset_id, layer, cx, cy) {
+    if let Some(entity) = tile_entities.chunk_entity(tileset_id, layer, cx, cy) {
         return Some(entity);
     }
 
-    let rt = runtime.by_id.get(&tileset_id)?;
+    let rt = runtime.by_id.get(tileset_id)?;
     let chunk_size = tile_entities.chunk_size.max(1);
     let map_size = TilemapSize {
         x: chunk_size,
         y: chunk_size,
     };
+    // tile 尺寸取自 tileset，网格取地图网格；两者不同时整块 chunk 平移，使 tile 左下角对齐格子。
     let tile_size = TilemapTileSize {
-        x: config.tile_size.x as f32,
-        y: config.tile_size.y as f32,
+        x: rt.grid.tile_width as f32,
+        y: rt.grid.tile_height as f32,
     };
     let grid_size = TilemapGridSize {
         x: config.tile_size.x as f32,
         y: config.tile_size.y as f32,
     };
-    let order = tile_entities.tileset_index(&tileset_id);
+    let order = tile_entities.tileset_index(tileset_id);
     let z = layer as f32 * 10.0 + order as f32 * 0.01;
-    let origin_x = cx as f32 * chunk_size as f32 * tile_size.x;
-    let origin_y = cy as f32 * chunk_size as f32 * tile_size.y;
-    let offset = Vec3::new(origin_x + tile_size.x * 0.5, origin_y + tile_size.y * 0.5, z);
+    let origin_x = cx as f32 * chunk_size as f32 * grid_size.x;
+    let origin_y = cy as f32 * chunk_size as f32 * grid_size.y;
+    let anchor = tile_anchor_offset(rt.tile_size(), config.tile_size);
+    let offset = Vec3::new(
+        origin_x + grid_size.x * 0.5 + anchor.x,
+        origin_y + grid_size.y * 0.5 + anchor.y,
+        z,
+    );
 
     let map_entity = commands.spawn_empty().id();
     let storage = TileStorage::empty(map_size);
@@ -113,13 +116,13 @@ fn ensure_chunk_tilemap(
         storage,
         tile_size,
         grid_size,
-        texture: TilemapTexture::Single(rt.texture.clone()),
+        texture: TilemapTexture::Single(rt.tilemap_texture.clone()),
         transform: Transform::from_translation(offset),
         visibility: if visible { Visibility::Visible } else { Visibility::Hidden },
         ..Default::default()
     });
 
-    tile_entities.set_chunk_entity(tileset_id, layer, cx, cy, map_entity);
+    tile_entities.set_chunk_entity(tileset_id.to_string(), layer, cx, cy, map_entity);
     Some(map_entity)
 }
 
@@ -168,16 +171,20 @@ pub fn update_visible_chunks(
     let min = center - Vec2::new(world_w * 0.5, world_h * 0.5);
     let max = center + Vec2::new(world_w * 0.5, world_h * 0.5);
 
-    let max_cx = (map.width.saturating_sub(1) / chunk_size) as i32;
-    let max_cy = (map.height.saturating_sub(1) / chunk_size) as i32;
     let mut cx_min = (min.x / chunk_w).floor() as i32;
     let mut cy_min = (min.y / chunk_h).floor() as i32;
     let mut cx_max = (max.x / chunk_w).floor() as i32;
     let mut cy_max = (max.y / chunk_h).floor() as i32;
-    cx_min = cx_min.clamp(0, max_cx);
-    cy_min = cy_min.clamp(0, max_cy);
-    cx_max = cx_max.clamp(0, max_cx);
-    cy_max = cy_max.clamp(0, max_cy);
+    // 固定尺寸地图裁剪到地图范围；无限地图不裁剪（空白 chunk 查询不到格子，开销很小）。
+    if let Some((w, h)) = map.fixed_size() {
+        let max_cx = (w.saturating_sub(1) / chunk_size) as i32;
+        let max_cy = (h.saturating_sub(1) / chunk_size) as i32;
+        cx_min = cx_min.clamp(0, max_cx);
+        cy_min = cy_min.clamp(0, max_cy);
+        cx_max = cx_max.clamp(0, max_cx);
+        cy_max = cy_max.clamp(0, max_cy);
+    }
+    let cs = chunk_size as i32;
 
     let layer_count = map.layers.max(1);
     for layer in 0..layer_count {
@@ -189,25 +196,11 @@ pub fn update_visible_chunks(
 
         for cy in cy_min..=cy_max {
             for cx in cx_min..=cx_max {
-                let cx_u = cx as u32;
-                let cy_u = cy as u32;
-                let start_x = cx_u * chunk_size;
-                let start_y = cy_u * chunk_size;
-                let end_x = (start_x + chunk_size).min(map.width);
-                let end_y = (start_y + chunk_size).min(map.height);
-
-                let mut tiles_by_set: std::collections::HashMap<String, Vec<(u32, u32, TileRef)>> =
+                let bounds = TileBounds::from_corners(cx * cs, cy * cs, cx * cs + cs - 1, cy * cs + cs - 1);
+                let mut tiles_by_set: std::collections::HashMap<&String, Vec<(i32, i32, TileView)>> =
                     Default::default();
-                for y in start_y..end_y {
-                    for x in start_x..end_x {
-                        let idx = map.idx_layer(layer, x, y);
-                        if let Some(tile) = &map.tiles[idx] {
-                            tiles_by_set
-                                .entry(tile.tileset_id.clone())
-                                .or_default()
-                                .push((x, y, tile.clone()));
-                        }
-                    }
+                for (x, y, tile) in map.tiles_in_rect(layer, bounds) {
+                    tiles_by_set.entry(tile.tileset_id).or_default().push((x, y, tile));
                 }
 
                 for (tileset_id, tiles) in tiles_by_set {
@@ -216,10 +209,10 @@ pub fn update_visible_chunks(
                         tile_entities,
                         &runtime,
                         &config,
-                        &tileset_id,
+                        tileset_id,
                         layer,
-                        cx_u,
-                        cy_u,
+                        cx,
+                        cy,
                         layer_visible,
                     ) else {
                         continue;
@@ -235,9 +228,10 @@ pub fn update_visible_chunks(
                             continue;
                         };
                         for (x, y, tile) in tiles {
-                            let lx = x % chunk_size;
-                            let ly = y % chunk_size;
-                            let pos = TilePos { x: lx, y: ly };
+                            let pos = TilePos {
+                                x: x.rem_euclid(cs) as u32,
+                                y: y.rem_euclid(cs) as u32,
+                            };
                             if storage.get(&pos).is_some() {
                                 continue;
                             }
@@ -246,11 +240,17 @@ pub fn update_visible_chunks(
                                     position: pos,
                                     tilemap_id: TilemapId(map_entity),
                                     texture_index: TileTextureIndex(tile.index),
-                                    flip: tile_flip_from_ref(&tile),
+                                    flip: TileFlip::from(tile.orientation()),
                                     ..Default::default()
                                 })
                                 .id();
                             storage.set(&pos, tile_entity);
+                            if runtime.by_id.get(tileset_id).is_some_and(|rt| rt.is_animated(tile.index)) {
+                                commands.entity(tile_entity).insert(AnimatedTile {
+                                    tileset_id: tileset_id.clone(),
+                                    base_index: tile.index,
+                                });
+                            }
                         }
                         commands.entity(map_entity).insert(ChunkFilled);
                     }
@@ -267,10 +267,7 @@ pub fn update_visible_chunks(
                 .map(|d| d.visible)
                 .unwrap_or(true);
             for (&(cx, cy), &entity) in layer_map.iter() {
-                let in_view = (cx as i32) >= cx_min
-                    && (cx as i32) <= cx_max
-                    && (cy as i32) >= cy_min
-                    && (cy as i32) <= cy_max;
+                let in_view = cx >= cx_min && cx <= cx_max && cy >= cy_min && cy <= cy_max;
                 if let Ok(mut vis) = map_vis_q.get_mut(entity) {
                     *vis = if layer_visible && in_view {
                         Visibility::Visible
@@ -330,28 +327,3 @@ pub fn sync_layer_visibility_on_layer_data_change(
 
     *prev_visible = current;
 }
-
-fn tile_flip_from_ref(tile: &tilemap_core::TileRef) -> TileFlip {
-    let mut flip = TileFlip {
-        x: tile.flip_x,
-        y: tile.flip_y,
-        d: false,
-    };
-    match tile.rot % 4 {
-        0 => {}
-        1 => {
-            flip.d = true;
-            flip.x = !flip.x;
-        }
-        2 => {
-            flip.x = !flip.x;
-            flip.y = !flip.y;
-        }
-        3 => {
-            flip.d = true;
-            flip.y = !flip.y;
-        }
-        _ => {}
-    }
-    flip
-}
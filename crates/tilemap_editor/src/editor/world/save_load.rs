@@ -2,9 +2,7 @@ use bevy::prelude::*;
 use bevy_ecs_tilemap::prelude::{TileStorage, TilemapId};
 
 use crate::editor::persistence::{load_map_from_file, save_map_to_file};
-use crate::editor::tileset::{
-    merge_tilesets_from_map, save_tileset_library, spawn_map_entities_with_layers,
-};
+use crate::editor::tileset::{merge_tilesets_from_map, save_tileset_library, spawn_map_entities};
 use crate::editor::types::{
     EditorConfig, TileEntities, TileMapData, TilesetLibrary, TilesetLoading, TilesetRuntime,
     UndoStack,
@@ -51,8 +49,11 @@ pub fn save_load_shortcuts(
         merge_tilesets_from_map(&asset_server, &mut lib, &mut tileset_loading, tilesets);
         save_tileset_library(&lib);
 
-        let needs_resize = config.map_size.x != loaded.width || config.map_size.y != loaded.height;
         let current_tile_entities = tile_entities;
+        let needs_resize = current_tile_entities
+            .as_deref()
+            .map(|te| !te.matches_map(&loaded))
+            .unwrap_or(true);
 
         if needs_resize {
             if let Some(existing_tiles) = current_tile_entities.as_deref() {
@@ -64,8 +65,10 @@ pub fn save_load_shortcuts(
                 despawn_silently(&mut commands, e);
             }
 
-            config.map_size = UVec2::new(loaded.width, loaded.height);
-            let tiles = spawn_map_entities_with_layers(&mut commands, &config, loaded.layers);
+            if let Some((w, h)) = loaded.fixed_size() {
+                config.map_size = UVec2::new(w, h);
+            }
+            let tiles = spawn_map_entities(&mut commands, &config, &loaded);
             commands.insert_resource(loaded.clone());
             let mut tiles = tiles;
             rebuild_tilemaps(
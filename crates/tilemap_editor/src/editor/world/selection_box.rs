@@ -1,8 +1,12 @@
+use std::collections::HashSet;
+
 use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
 
+use tilemap_core::ops;
+
 use crate::editor::types::{
-    ContextMenuState, EditorConfig, SelectionState, TileEntities, ToolKind, ToolState, WorldCamera,
+    ContextMenuState, EditorConfig, LayerState, SelectionState, TileMapData, ToolKind, ToolState, WorldCamera,
 };
 
 /// 选择工具：拖拽框选矩形。
@@ -14,7 +18,7 @@ pub fn select_with_mouse(
     windows: Query<&Window, With<PrimaryWindow>>,
     camera_q: Query<(&Camera, &GlobalTransform), With<WorldCamera>>,
     config: Res<EditorConfig>,
-    tile_entities: Option<Res<TileEntities>>,
+    map: Option<Res<TileMapData>>,
     mut selection: ResMut<SelectionState>,
 ) {
     // 正在拖拽移动选区内容时，不要同时开始框选。
@@ -32,7 +36,7 @@ pub fn select_with_mouse(
     if keys.pressed(KeyCode::Space) {
         return;
     }
-    let Some(tile_entities) = tile_entities else {
+    let Some(map) = map else {
         return;
     };
     let Ok(window) = windows.single() else {
@@ -51,8 +55,7 @@ pub fn select_with_mouse(
         camera,
         camera_transform,
         &config,
-        tile_entities.width,
-        tile_entities.height,
+        map.fixed_size(),
     );
 
     if !selection.dragging {
@@ -66,10 +69,8 @@ pub fn select_with_mouse(
                 tools.tool = ToolKind::Select;
             }
 
+            selection.set_rect(super::rect_from_two(pos, pos));
             selection.dragging = true;
-            selection.start = pos;
-            selection.current = pos;
-            selection.rect = Some(super::rect_from_two(pos, pos));
         }
         return;
     }
@@ -87,3 +88,119 @@ pub fn select_with_mouse(
         }
     }
 }
+
+/// 魔棒：点击选中与该格内容相同的格子（选取方式见 `ToolState::wand_mode`，取样图层见 `wand_sample`）。
+///
+/// Shift 加选，Ctrl 从当前选区中减去。
+pub fn magic_wand_with_mouse(
+    (buttons, keys): (Res<ButtonInput<MouseButton>>, Res<ButtonInput<KeyCode>>),
+    (tools, menu): (Res<ToolState>, Res<ContextMenuState>),
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_q: Query<(&Camera, &GlobalTransform), With<WorldCamera>>,
+    (config, layer_state): (Res<EditorConfig>, Res<LayerState>),
+    map: Option<Res<TileMapData>>,
+    mut selection: ResMut<SelectionState>,
+) {
+    if tools.tool != ToolKind::MagicWand {
+        return;
+    }
+    if menu.open || menu.consume_left_click {
+        return;
+    }
+    // Alt + 拖拽用于“从任意工具框选”。
+    if keys.pressed(KeyCode::AltLeft) || keys.pressed(KeyCode::AltRight) {
+        return;
+    }
+    if keys.pressed(KeyCode::Space) {
+        return;
+    }
+    if !buttons.just_pressed(MouseButton::Left) {
+        return;
+    }
+
+    let Some(map) = map else {
+        return;
+    };
+    let Ok(window) = windows.single() else {
+        return;
+    };
+    let Ok((camera, camera_transform)) = camera_q.single() else {
+        return;
+    };
+    let Some(pos) = super::cursor_tile_pos(
+        window,
+        camera,
+        camera_transform,
+        &config,
+        map.fixed_size(),
+    ) else {
+        return;
+    };
+
+    let sample = tools.wand_sample.layers(&map, layer_state.active);
+    let picked = ops::fill_region(&map, &sample, pos.x, pos.y, tools.wand_mode)
+        .into_iter()
+        .map(|(x, y)| IVec2::new(x, y));
+    let shift = keys.pressed(KeyCode::ShiftLeft) || keys.pressed(KeyCode::ShiftRight);
+    let ctrl = keys.pressed(KeyCode::ControlLeft) || keys.pressed(KeyCode::ControlRight);
+    if shift || ctrl {
+        let mut cells: HashSet<IVec2> = selection.cells().into_iter().map(|(x, y)| IVec2::new(x, y)).collect();
+        if shift {
+            cells.extend(picked);
+        } else {
+            for p in picked {
+                cells.remove(&p);
+            }
+        }
+        selection.set_cells(cells);
+    } else {
+        selection.set_cells(picked);
+    }
+    info!("magic wand at ({}, {}): {} cells selected", pos.x, pos.y, selection.cells().len());
+}
+
+/// 选区外轮廓的单位线段（格子坐标的角点）；任意形状的选区只描出与未选格子相邻的边。
+fn outline_edges(selection: &SelectionState) -> Vec<(IVec2, IVec2)> {
+    let Some(rect) = selection.rect else {
+        return Vec::new();
+    };
+    let mut edges = Vec::new();
+    let Some(mask) = selection.mask.as_ref() else {
+        for x in rect.min.x..=rect.max.x {
+            edges.push((IVec2::new(x, rect.min.y), IVec2::new(x + 1, rect.min.y)));
+            edges.push((IVec2::new(x, rect.max.y + 1), IVec2::new(x + 1, rect.max.y + 1)));
+        }
+        for y in rect.min.y..=rect.max.y {
+            edges.push((IVec2::new(rect.min.x, y), IVec2::new(rect.min.x, y + 1)));
+            edges.push((IVec2::new(rect.max.x + 1, y), IVec2::new(rect.max.x + 1, y + 1)));
+        }
+        return edges;
+    };
+    for &p in mask {
+        if !mask.contains(&(p - IVec2::Y)) {
+            edges.push((p, p + IVec2::X));
+        }
+        if !mask.contains(&(p + IVec2::Y)) {
+            edges.push((p + IVec2::Y, p + IVec2::ONE));
+        }
+        if !mask.contains(&(p - IVec2::X)) {
+            edges.push((p, p + IVec2::Y));
+        }
+        if !mask.contains(&(p + IVec2::X)) {
+            edges.push((p + IVec2::X, p + IVec2::ONE));
+        }
+    }
+    edges
+}
+
+/// 选区“蚂蚁线”：每格边长一段、黄黑相间，随时间沿边移动。
+pub(super) fn draw_selection_outline(gizmos: &mut Gizmos, config: &EditorConfig, selection: &SelectionState, secs: f32) {
+    let tile = config.tile_size.as_vec2();
+    let phase = (secs * 4.0) as i32;
+    let light = Color::srgba(1.0, 1.0, 0.0, 0.85);
+    let dark = Color::srgba(0.0, 0.0, 0.0, 0.85);
+    for (a, b) in outline_edges(selection) {
+        let c = if (a.x + a.y + phase).rem_euclid(2) == 0 { light } else { dark };
+        gizmos.line_2d(a.as_vec2() * tile, b.as_vec2() * tile, c);
+    }
+}
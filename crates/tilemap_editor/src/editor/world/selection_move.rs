@@ -2,17 +2,17 @@ use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
 use bevy::ecs::system::SystemParam;
 
-use std::collections::HashSet;
+use tilemap_core::ops;
 
 use crate::editor::types::{
-    CellChange, ContextMenuState, EditCommand, EditorConfig, LayerState, SelectionMovePreviewTile,
+    ContextMenuState, EditCommand, EditorConfig, LayerState, SelectionMovePreviewTile,
     SelectionRect, SelectionState, TileMapData, TileRef, TilesetRuntime, ToolKind, ToolState,
     UndoStack, WorldCamera,
 };
-use crate::editor::tileset::rect_for_tile_index;
+use crate::editor::tileset::{rect_for_tile_index, tile_anchor_offset};
 use crate::editor::util::despawn_silently;
 
-use super::{apply_tile_change, cursor_tile_pos, tile_world_center, TilemapRenderParams};
+use super::{cursor_tile_pos, render_cell_changes, tile_world_center, TilemapRenderParams};
 
 #[derive(SystemParam)]
 pub(in crate::editor) struct SelectionMoveParams<'w, 's> {
@@ -40,18 +40,16 @@ pub(in crate::editor) struct SelectionMoveParams<'w, 's> {
 pub(in crate::editor) struct SelectionMoveDrag {
     active: bool,
     copy: bool,
-    start: UVec2,
-    current: UVec2,
+    start: IVec2,
+    current: IVec2,
     rect: SelectionRect,
+    cells: Vec<(i32, i32)>,
+    /// 外接框内的 tile（行优先），不在选区里的格子为 None。
     buf: Vec<Option<TileRef>>,
     preview_entities: Vec<Entity>,
     preview_dims: (u32, u32),
 }
 
-fn point_in_rect(p: UVec2, r: SelectionRect) -> bool {
-    p.x >= r.min.x && p.x <= r.max.x && p.y >= r.min.y && p.y <= r.max.y
-}
-
 fn clamp_i32(v: i32, lo: i32, hi: i32) -> i32 {
     v.max(lo).min(hi)
 }
@@ -65,8 +63,8 @@ fn apply_preview_tile_visual(
     config: &EditorConfig,
 ) {
     match tile {
-        Some(TileRef { tileset_id, index, rot, flip_x, flip_y }) => {
-            let Some(atlas) = runtime.by_id.get(tileset_id) else {
+        Some(tile) => {
+            let Some(atlas) = runtime.by_id.get(&tile.tileset_id) else {
                 sprite.rect = None;
                 sprite.flip_x = false;
                 sprite.flip_y = false;
@@ -75,10 +73,15 @@ fn apply_preview_tile_visual(
                 return;
             };
             sprite.image = atlas.texture.clone();
-            sprite.rect = Some(rect_for_tile_index(*index, atlas.columns, config.tile_size));
-            sprite.flip_x = *flip_x;
-            sprite.flip_y = *flip_y;
-            let r = (*rot % 4) as f32;
+            sprite.rect = Some(rect_for_tile_index(tile.index, atlas.columns, atlas.grid));
+            let (w, h) = tile.orientation().dims(atlas.grid.tile_width, atlas.grid.tile_height);
+            let anchor = tile_anchor_offset(UVec2::new(w, h), config.tile_size);
+            tf.translation += anchor.extend(0.0);
+            // Sprite 先翻转再由 Transform 旋转，需换成对应的拆分形式。
+            let (rot, flip_x) = tile.orientation().to_sprite_parts();
+            sprite.flip_x = flip_x;
+            sprite.flip_y = false;
+            let r = rot as f32;
             tf.rotation = Quat::from_rotation_z(-r * std::f32::consts::FRAC_PI_2);
             *vis = Visibility::Visible;
         }
@@ -92,13 +95,10 @@ fn apply_preview_tile_visual(
 }
 
 fn rect_shift(rect: SelectionRect, dx: i32, dy: i32) -> SelectionRect {
-    let min_x = (rect.min.x as i32 + dx) as u32;
-    let min_y = (rect.min.y as i32 + dy) as u32;
-    let max_x = (rect.max.x as i32 + dx) as u32;
-    let max_y = (rect.max.y as i32 + dy) as u32;
+    let d = IVec2::new(dx, dy);
     SelectionRect {
-        min: UVec2::new(min_x, min_y),
-        max: UVec2::new(max_x, max_y),
+        min: rect.min + d,
+        max: rect.max + d,
     }
 }
 
@@ -184,8 +184,7 @@ pub fn selection_move_with_mouse(
         camera,
         camera_transform,
         &config,
-        map.width,
-        map.height,
+        map.fixed_size(),
     );
 
     // 开始拖拽：必须点击在当前选区内。
@@ -196,7 +195,7 @@ pub fn selection_move_with_mouse(
         let Some(pos) = pos else {
             return;
         };
-        if !point_in_rect(pos, rect) {
+        if !selection.contains(pos) {
             return;
         }
         let ctrl = keys.pressed(KeyCode::ControlLeft) || keys.pressed(KeyCode::ControlRight);
@@ -205,18 +204,11 @@ pub fn selection_move_with_mouse(
         drag.current = pos;
         drag.rect = rect;
 
-        let w = rect.width();
-        let h = rect.height();
         let layer = layer_state.active.min(map.layers.saturating_sub(1));
-        drag.buf.clear();
-        drag.buf.reserve((w * h) as usize);
-        for y in rect.min.y..=rect.max.y {
-            for x in rect.min.x..=rect.max.x {
-                let idx = map.idx_layer(layer, x, y);
-                drag.buf.push(map.tiles[idx].clone());
-            }
-        }
+        drag.cells = selection.cells();
+        drag.buf = ops::extract_cells(&map, layer, &drag.cells).tiles;
 
+        drag.active = true;
         selection.moving = true;
     }
 
@@ -231,15 +223,17 @@ pub fn selection_move_with_mouse(
         }
     }
 
-    // 计算偏移，并限制在地图范围内。
-    let mut dx = drag.current.x as i32 - drag.start.x as i32;
-    let mut dy = drag.current.y as i32 - drag.start.y as i32;
-    let min_dx = -(drag.rect.min.x as i32);
-    let max_dx = map.width as i32 - 1 - drag.rect.max.x as i32;
-    let min_dy = -(drag.rect.min.y as i32);
-    let max_dy = map.height as i32 - 1 - drag.rect.max.y as i32;
-    dx = clamp_i32(dx, min_dx, max_dx);
-    dy = clamp_i32(dy, min_dy, max_dy);
+    // 计算偏移，并限制在地图范围内（无限地图不限制）。
+    let mut dx = drag.current.x - drag.start.x;
+    let mut dy = drag.current.y - drag.start.y;
+    if let Some((map_w, map_h)) = map.fixed_size() {
+        let min_dx = -drag.rect.min.x;
+        let max_dx = map_w as i32 - 1 - drag.rect.max.x;
+        let min_dy = -drag.rect.min.y;
+        let max_dy = map_h as i32 - 1 - drag.rect.max.y;
+        dx = clamp_i32(dx, min_dx, max_dx);
+        dy = clamp_i32(dy, min_dy, max_dy);
+    }
 
     let new_rect = rect_shift(drag.rect, dx, dy);
     let w = drag.rect.width();
@@ -279,8 +273,8 @@ pub fn selection_move_with_mouse(
             let Ok((mut sprite, mut tf, mut vis)) = preview_q.get_mut(e) else {
                 continue;
             };
-            let dst_x = new_rect.min.x + cx;
-            let dst_y = new_rect.min.y + cy;
+            let dst_x = new_rect.min.x + cx as i32;
+            let dst_y = new_rect.min.y + cy as i32;
             tf.translation = tile_world_center(dst_x, dst_y, config.tile_size, 6.0);
             apply_preview_tile_visual(&render.runtime, &drag.buf[i], &mut sprite, &mut tf, &mut vis, &config);
             sprite.color = Color::srgba(1.0, 1.0, 1.0, 0.60);
@@ -300,74 +294,22 @@ pub fn selection_move_with_mouse(
         }
     }
 
-    let mut cmd = EditCommand::default();
-    let mut touched: HashSet<usize> = HashSet::new();
     let layer = layer_state.active.min(map.layers.saturating_sub(1));
-
-    // helper：写一个格子的 after，并记录变更
-    let mut apply_cell = |x: u32, y: u32, after: Option<TileRef>, map: &mut TileMapData, cmd: &mut EditCommand| {
-        let idx = map.idx_layer(layer, x, y);
-        if !touched.insert(idx) {
-            return;
-        }
-        let before = map.tiles[idx].clone();
-        if before != after {
-            map.tiles[idx] = after.clone();
-            cmd.changes.push(CellChange { idx, before, after });
-        }
-    };
-
-    // 计算 new_rect 内某格对应的 buf tile
-    let buf_at = |x: u32, y: u32, new_rect: SelectionRect, w: u32, buf: &Vec<Option<TileRef>>| -> Option<TileRef> {
-        let lx = x - new_rect.min.x;
-        let ly = y - new_rect.min.y;
-        buf[(ly * w + lx) as usize].clone()
-    };
-
-    if drag.copy {
-        // 复制移动：只写入 new_rect，原区域保留。
-        for y in new_rect.min.y..=new_rect.max.y {
-            for x in new_rect.min.x..=new_rect.max.x {
-                let after = buf_at(x, y, new_rect, w, &drag.buf);
-                apply_cell(x, y, after, &mut map, &mut cmd);
-            }
-        }
+    let offset = new_rect.min - drag.rect.min;
+    let changes = if drag.copy {
+        // 复制移动：只写入目标格子，原区域保留。
+        ops::copy_cells(&mut map, layer, &drag.cells, offset.x, offset.y)
     } else {
-        // 移动：更新 old_rect ∪ new_rect。
-        for y in drag.rect.min.y..=drag.rect.max.y {
-            for x in drag.rect.min.x..=drag.rect.max.x {
-                let after = if point_in_rect(UVec2::new(x, y), new_rect) {
-                    buf_at(x, y, new_rect, w, &drag.buf)
-                } else {
-                    None
-                };
-                apply_cell(x, y, after, &mut map, &mut cmd);
-            }
-        }
-        for y in new_rect.min.y..=new_rect.max.y {
-            for x in new_rect.min.x..=new_rect.max.x {
-                let after = buf_at(x, y, new_rect, w, &drag.buf);
-                apply_cell(x, y, after, &mut map, &mut cmd);
-            }
-        }
-    }
+        ops::move_cells(&mut map, layer, &drag.cells, offset.x, offset.y)
+    };
 
     // 刷新渲染
-    let layer_len = map.layer_len();
-    let layer_offset = (layer as usize) * layer_len;
-    for ch in &cmd.changes {
-        let local = ch.idx.saturating_sub(layer_offset);
-        let x = (local % map.width as usize) as u32;
-        let y = (local / map.width as usize) as u32;
-        apply_tile_change(&mut render, &config, layer, x, y, &ch.before, &ch.after);
-    }
+    render_cell_changes(&mut render, &config, &map, &changes);
 
-    undo.push(cmd);
+    undo.push(EditCommand { changes, ..Default::default() });
 
     // 更新选择框
-    selection.rect = Some(new_rect);
-    selection.start = new_rect.min;
-    selection.current = new_rect.max;
+    selection.shift(offset);
 
     drag.active = false;
     selection.moving = false;
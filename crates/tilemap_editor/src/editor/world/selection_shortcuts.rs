@@ -1,16 +1,19 @@
 use bevy::prelude::*;
 
+use tilemap_core::ops;
+
 use crate::editor::types::{
-    CellChange, Clipboard, EditCommand, EditorConfig, LayerState, MapSizeFocus, MapSizeInput,
-    PasteState, SelectionRect, SelectionState, TileMapData, TileRef, ToolKind, ToolState, UndoStack,
+    Clipboard, EditCommand, EditorConfig, LayerState, MapSizeFocus, MapSizeInput, PasteState,
+    SelectionState, TileMapData, TilesetGridDialogState, ToolKind, ToolState, UndoStack,
 };
 
-use super::{apply_tile_change, context_menu, TilemapRenderParams};
+use super::{context_menu, render_cell_changes, select_all_rect, TilemapRenderParams};
 
 /// Ctrl+C 复制选择区域到 Clipboard；Ctrl+V 进入粘贴模式；Esc 退出粘贴。
 pub fn copy_paste_shortcuts(
     keys: Res<ButtonInput<KeyCode>>,
     input: Res<MapSizeInput>,
+    grid_dialog: Res<TilesetGridDialogState>,
     mut tools: ResMut<ToolState>,
     layer_state: Res<LayerState>,
     selection: Res<SelectionState>,
@@ -19,7 +22,7 @@ pub fn copy_paste_shortcuts(
     paste: ResMut<PasteState>,
 ) {
     // 输入框聚焦时不抢快捷键
-    if input.focus != MapSizeFocus::None {
+    if input.focus != MapSizeFocus::None || grid_dialog.captures_keys() {
         return;
     }
 
@@ -44,11 +47,11 @@ pub fn copy_paste_shortcuts(
         let Some(map) = map else {
             return;
         };
-        let Some(rect) = selection.rect else {
+        if selection.rect.is_none() {
             return;
-        };
+        }
         let layer = layer_state.active.min(map.layers.saturating_sub(1));
-        context_menu::copy_selection_to_clipboard(layer, rect, &map, &mut clipboard);
+        context_menu::copy_selection_to_clipboard(layer, &selection, &map, &mut clipboard);
     }
 
     if keys.just_pressed(KeyCode::KeyV) {
@@ -61,21 +64,20 @@ pub fn copy_paste_shortcuts(
         }
         tools.tool = ToolKind::Paste;
         info!(
-            "enter paste: clipboard {}x{} (tiles={}), keep transform rot={} flip_x={} flip_y={}",
+            "enter paste: clipboard {}x{} (tiles={}), keep transform {}",
             clipboard.width,
             clipboard.height,
             clipboard.tiles.len(),
-            paste.rot % 4,
-            paste.flip_x,
-            paste.flip_y
+            paste.orientation
         );
     }
 }
 
-/// 选择编辑：Ctrl+X 剪切（复制到剪贴板并清空选区），Delete/Backspace 清空选区。
+/// 选择编辑（选择 / 魔棒工具）：Ctrl+X 剪切（复制到剪贴板并清空选区），Delete/Backspace 清空选区。
 pub fn selection_cut_delete_shortcuts(
     keys: Res<ButtonInput<KeyCode>>,
     input: Res<MapSizeInput>,
+    grid_dialog: Res<TilesetGridDialogState>,
     tools: Res<ToolState>,
     layer_state: Res<LayerState>,
     config: Res<EditorConfig>,
@@ -85,15 +87,12 @@ pub fn selection_cut_delete_shortcuts(
     mut clipboard: ResMut<Clipboard>,
     mut undo: ResMut<UndoStack>,
 ) {
-    if input.focus != MapSizeFocus::None {
+    if input.focus != MapSizeFocus::None || grid_dialog.captures_keys() {
         return;
     }
-    if tools.tool != ToolKind::Select {
+    if !tools.tool.edits_selection() || selection.rect.is_none() {
         return;
     }
-    let Some(rect) = selection.rect else {
-        return;
-    };
 
     let ctrl = keys.pressed(KeyCode::ControlLeft) || keys.pressed(KeyCode::ControlRight);
     let want_cut = ctrl && keys.just_pressed(KeyCode::KeyX);
@@ -108,51 +107,10 @@ pub fn selection_cut_delete_shortcuts(
     let layer = layer_state.active.min(map.layers.saturating_sub(1));
 
     if want_cut {
-        let w = rect.width();
-        let h = rect.height();
-        let mut tiles = Vec::with_capacity((w * h) as usize);
-        for y in rect.min.y..=rect.max.y {
-            for x in rect.min.x..=rect.max.x {
-                let idx = map.idx_layer(layer, x, y);
-                tiles.push(map.tiles[idx].clone());
-            }
-        }
-        clipboard.width = w;
-        clipboard.height = h;
-        clipboard.tiles = tiles;
-    }
-
-    let mut cmd = EditCommand::default();
-    for y in rect.min.y..=rect.max.y {
-        for x in rect.min.x..=rect.max.x {
-            let idx = map.idx_layer(layer, x, y);
-            if map.tiles[idx].is_none() {
-                continue;
-            }
-            let before = map.tiles[idx].clone();
-            map.tiles[idx] = None;
-            cmd.changes.push(CellChange {
-                idx,
-                before,
-                after: None,
-            });
-        }
+        context_menu::copy_selection_to_clipboard(layer, &selection, &map, &mut clipboard);
     }
 
-    if cmd.changes.is_empty() {
-        return;
-    }
-
-    let layer_len = map.layer_len();
-    let layer_offset = (layer as usize) * layer_len;
-    for ch in &cmd.changes {
-        let local = ch.idx.saturating_sub(layer_offset);
-        let x = (local % map.width as usize) as u32;
-        let y = (local / map.width as usize) as u32;
-        apply_tile_change(&mut render, &config, layer, x, y, &ch.before, &ch.after);
-    }
-
-    undo.push(cmd);
+    context_menu::clear_selection_to_none(layer, &selection, &mut map, &config, &mut render, &mut undo);
 }
 
 /// 选择辅助：Ctrl+A 全选，Ctrl+D 取消选择。
@@ -175,8 +133,7 @@ pub fn selection_selectall_cancel_shortcuts(
         if tools.tool == ToolKind::Paste {
             tools.tool = ToolKind::Select;
         }
-        selection.dragging = false;
-        selection.rect = None;
+        selection.clear();
         return;
     }
 
@@ -184,23 +141,16 @@ pub fn selection_selectall_cancel_shortcuts(
         let Some(map) = map else {
             return;
         };
-        if map.width == 0 || map.height == 0 {
+        let Some(rect) = select_all_rect(&map) else {
             return;
-        }
-        let rect = SelectionRect {
-            min: UVec2::ZERO,
-            max: UVec2::new(map.width - 1, map.height - 1),
         };
-        selection.dragging = false;
-        selection.start = rect.min;
-        selection.current = rect.max;
-        selection.rect = Some(rect);
+        selection.set_rect(rect);
         tools.tool = ToolKind::Select;
         return;
     }
 }
 
-/// 选择区移动：在 Select 工具下按 Alt + 方向键，把选择框内内容整体移动 1 格（可撤销）。
+/// 选择区移动：在选择 / 魔棒工具下按 Alt + 方向键，把选区内容整体移动 1 格（可撤销）。
 pub fn move_selection_shortcuts(
     keys: Res<ButtonInput<KeyCode>>,
     input: Res<MapSizeInput>,
@@ -215,7 +165,7 @@ pub fn move_selection_shortcuts(
     if input.focus != MapSizeFocus::None {
         return;
     }
-    if tools.tool != ToolKind::Select {
+    if !tools.tool.edits_selection() {
         return;
     }
     let Some(rect) = selection.rect else {
@@ -246,85 +196,21 @@ pub fn move_selection_shortcuts(
     };
 
     // 不允许越界移动，避免裁剪导致“选区变形”。
-    let new_min_x = rect.min.x as i32 + dx;
-    let new_min_y = rect.min.y as i32 + dy;
-    let new_max_x = rect.max.x as i32 + dx;
-    let new_max_y = rect.max.y as i32 + dy;
-    if new_min_x < 0
-        || new_min_y < 0
-        || new_max_x >= map.width as i32
-        || new_max_y >= map.height as i32
-    {
+    let offset = IVec2::new(dx, dy);
+    let (new_min, new_max) = (rect.min + offset, rect.max + offset);
+    if !map.contains(new_min.x, new_min.y) || !map.contains(new_max.x, new_max.y) {
         return;
     }
 
-    let new_rect = SelectionRect {
-        min: UVec2::new(new_min_x as u32, new_min_y as u32),
-        max: UVec2::new(new_max_x as u32, new_max_y as u32),
-    };
-
-    // 先备份原内容
-    let w = rect.width();
-    let h = rect.height();
-    let mut buf: Vec<Option<TileRef>> = Vec::with_capacity((w * h) as usize);
     let layer = layer_state.active.min(map.layers.saturating_sub(1));
-    for y in rect.min.y..=rect.max.y {
-        for x in rect.min.x..=rect.max.x {
-            let idx = map.idx_layer(layer, x, y);
-            buf.push(map.tiles[idx].clone());
-        }
-    }
-
-    let mut cmd = EditCommand::default();
-
-    // 清空原区域
-    for y in rect.min.y..=rect.max.y {
-        for x in rect.min.x..=rect.max.x {
-            let idx = map.idx_layer(layer, x, y);
-            if map.tiles[idx].is_some() {
-                let before = map.tiles[idx].clone();
-                map.tiles[idx] = None;
-                cmd.changes.push(CellChange {
-                    idx,
-                    before,
-                    after: None,
-                });
-            }
-        }
-    }
-
-    // 写入新区域
-    for cy in 0..h {
-        for cx in 0..w {
-            let dst_x = new_rect.min.x + cx;
-            let dst_y = new_rect.min.y + cy;
-            let after = buf[(cy * w + cx) as usize].clone();
-            let idx = map.idx_layer(layer, dst_x, dst_y);
-            if map.tiles[idx] == after {
-                continue;
-            }
-            let before = map.tiles[idx].clone();
-            map.tiles[idx] = after.clone();
-            cmd.changes.push(CellChange { idx, before, after });
-        }
-    }
-
-    if cmd.changes.is_empty() {
+    let changes = ops::move_cells(&mut map, layer, &selection.cells(), dx, dy);
+    if changes.is_empty() {
         return;
     }
 
     // 局部刷新渲染
-    let layer_len = map.layer_len();
-    let layer_offset = (layer as usize) * layer_len;
-    for ch in &cmd.changes {
-        let local = ch.idx.saturating_sub(layer_offset);
-        let x = (local % map.width as usize) as u32;
-        let y = (local / map.width as usize) as u32;
-        apply_tile_change(&mut render, &config, layer, x, y, &ch.before, &ch.after);
-    }
+    render_cell_changes(&mut render, &config, &map, &changes);
 
-    undo.push(cmd);
-    selection.rect = Some(new_rect);
-    selection.start = new_rect.min;
-    selection.current = new_rect.max;
+    undo.push(EditCommand { changes, ..Default::default() });
+    selection.shift(offset);
 }
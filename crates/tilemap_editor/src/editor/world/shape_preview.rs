@@ -0,0 +1,93 @@
+use bevy::prelude::*;
+
+use tilemap_core::ops::TilePattern;
+
+use crate::editor::types::{
+    EditorConfig, EditorState, PasteState, ShapePreview, ShapePreviewTile, TileRef, TilesetLibrary, TilesetRuntime, ToolKind,
+    ToolState,
+};
+
+use super::paste_preview::apply_preview_tile_visual;
+use super::tile_world_center;
+
+/// 直线 / 矩形工具的“幽灵预览”：在将要绘制的格子上半透明显示当前 tile（有印章时显示平铺后的图案）。
+pub fn update_shape_preview(
+    mut commands: Commands,
+    tools: Res<ToolState>,
+    config: Res<EditorConfig>,
+    (state, lib, paste): (Res<EditorState>, Res<TilesetLibrary>, Res<PasteState>),
+    runtime: Res<TilesetRuntime>,
+    mut preview: ResMut<ShapePreview>,
+    mut q: Query<(&mut Sprite, &mut Transform, &mut Visibility), With<ShapePreviewTile>>,
+) {
+    let preview = &mut *preview;
+    // 切走工具时残留的格子不显示。
+    let cells: &[IVec2] = match tools.tool {
+        ToolKind::Line | ToolKind::Rect => &preview.cells,
+        _ => &[],
+    };
+    // sprite 池只增不减，多出的隐藏。
+    while preview.entities.len() < cells.len() {
+        let e = commands
+            .spawn((
+                Sprite {
+                    color: Color::srgba(1.0, 1.0, 1.0, 0.55),
+                    ..default()
+                },
+                Transform::default(),
+                Visibility::Hidden,
+                ShapePreviewTile,
+            ))
+            .id();
+        preview.entities.push(e);
+    }
+
+    let pairs: Vec<(i32, i32)> = cells.iter().map(|&c| c.into()).collect();
+    let pattern = state.stamp.as_ref().and_then(|stamp| TilePattern::new(stamp, paste.orientation, &pairs));
+    let tile = lib.active_id.clone().map(|tileset_id| TileRef {
+        tileset_id,
+        index: state.selected_tile,
+        rot: 0,
+        flip_x: false,
+        flip_y: false,
+    });
+    for (i, &e) in preview.entities.iter().enumerate() {
+        let Ok((mut sprite, mut tf, mut vis)) = q.get_mut(e) else {
+            continue;
+        };
+        let Some(cell) = cells.get(i) else {
+            *vis = Visibility::Hidden;
+            continue;
+        };
+        tf.translation = tile_world_center(cell.x, cell.y, config.tile_size, 5.0);
+        let cell_tile = match &pattern {
+            Some(pattern) => pattern.tile_at(cell.x, cell.y).cloned(),
+            None => tile.clone(),
+        };
+        apply_preview_tile_visual(&runtime, &cell_tile, &mut sprite, &mut tf, &mut vis, &config);
+        sprite.color = Color::srgba(1.0, 1.0, 1.0, 0.55);
+    }
+}
+
+/// 矩形工具拖拽中的外接框。
+pub fn draw_shape_preview_frame(
+    mut gizmos: Gizmos,
+    tools: Res<ToolState>,
+    config: Res<EditorConfig>,
+    preview: Res<ShapePreview>,
+) {
+    let Some(bounds) = preview.frame.filter(|_| tools.tool == ToolKind::Rect) else {
+        return;
+    };
+    let tile_w = config.tile_size.x as f32;
+    let tile_h = config.tile_size.y as f32;
+    let x0 = bounds.min_x as f32 * tile_w;
+    let y0 = bounds.min_y as f32 * tile_h;
+    let x1 = (bounds.max_x as f32 + 1.0) * tile_w;
+    let y1 = (bounds.max_y as f32 + 1.0) * tile_h;
+    let preview_color = Color::srgba(0.25, 0.45, 0.95, 0.95);
+    gizmos.line_2d(Vec2::new(x0, y0), Vec2::new(x1, y0), preview_color);
+    gizmos.line_2d(Vec2::new(x1, y0), Vec2::new(x1, y1), preview_color);
+    gizmos.line_2d(Vec2::new(x1, y1), Vec2::new(x0, y1), preview_color);
+    gizmos.line_2d(Vec2::new(x0, y1), Vec2::new(x0, y0), preview_color);
+}
@@ -1,13 +1,17 @@
 use bevy::prelude::*;
 
+use tilemap_core::ops;
+
 use crate::editor::types::{
-    CellChange, EditCommand, EditorConfig, MapSizeFocus, MapSizeInput, ShiftMapMode,
-    ShiftMapSettings, TileMapData, UndoStack,
+    EditCommand, EditorConfig, MapSizeFocus, MapSizeInput, ShiftMapMode, ShiftMapSettings,
+    TileMapData, UndoStack,
 };
 
-use super::{apply_tile_change, TilemapRenderParams};
+use super::{render_cell_changes, TilemapRenderParams};
 
-/// Shift Map：Ctrl + 方向键整体平移一格（空出来的格子填 None），并可撤销。
+/// Shift Map：Ctrl + 方向键整体平移一格（空出来的格子填 None，元数据一起平移），并可撤销。
+///
+/// 无限地图没有边界，Blank/Wrap 都等价于整体平移。
 pub fn shift_map_shortcuts(
     keys: Res<ButtonInput<KeyCode>>,
     input: Res<MapSizeInput>,
@@ -44,62 +48,12 @@ pub fn shift_map_shortcuts(
     let Some(mut map) = map else {
         return;
     };
-    let w = map.width;
-    let h = map.height;
-    if w == 0 || h == 0 {
-        return;
-    }
-
-    let layers = map.layers.max(1);
-    let layer_len = map.layer_len();
-    let mut new_tiles = vec![None; layer_len * layers as usize];
-    for layer in 0..layers {
-        for y in 0..h {
-            for x in 0..w {
-                let (dst_x, dst_y) = match settings.mode {
-                    ShiftMapMode::Blank => {
-                        let nx = x as i32 + dx;
-                        let ny = y as i32 + dy;
-                        if nx < 0 || ny < 0 || nx >= w as i32 || ny >= h as i32 {
-                            continue;
-                        }
-                        (nx as u32, ny as u32)
-                    }
-                    ShiftMapMode::Wrap => {
-                        let nx = (x as i32 + dx).rem_euclid(w as i32) as u32;
-                        let ny = (y as i32 + dy).rem_euclid(h as i32) as u32;
-                        (nx, ny)
-                    }
-                };
-
-                let src = map.idx_layer(layer, x, y);
-                let dst = map.idx_layer(layer, dst_x, dst_y);
-                new_tiles[dst] = map.tiles[src].clone();
-            }
-        }
-    }
-
-    let mut cmd = EditCommand::default();
-    for i in 0..map.tiles.len() {
-        let before = map.tiles[i].clone();
-        let after = new_tiles[i].clone();
-        if before != after {
-            cmd.changes.push(CellChange { idx: i, before, after });
-        }
-    }
-
-    if cmd.changes.is_empty() {
+    let wrap = settings.mode == ShiftMapMode::Wrap;
+    let (changes, meta) = ops::shift(&mut map, dx, dy, wrap);
+    if changes.is_empty() && meta.is_empty() {
         return;
     }
 
-    map.tiles = new_tiles;
-    let layer_len = map.layer_len();
-    for ch in &cmd.changes {
-        let layer = (ch.idx / layer_len) as u32;
-        let local = ch.idx % layer_len;
-        let x = (local % map.width as usize) as u32;
-        let y = (local / map.width as usize) as u32;
-        apply_tile_change(&mut render, &config, layer, x, y, &ch.before, &ch.after);
-    }
-    undo.push(cmd);
+    render_cell_changes(&mut render, &config, &map, &changes);
+    undo.push(EditCommand { changes, meta });
 }
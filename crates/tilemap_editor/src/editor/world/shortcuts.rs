@@ -2,8 +2,8 @@ use bevy::prelude::*;
 
 use crate::editor::types::{
     CellChange, EditCommand, EditorConfig, EditorState, MapSizeFocus, MapSizeInput, BrushSettings,
-    TileMapData, TilesetLibrary, TilesetRuntime, ToolKind, ToolState, UndoStack, PaletteSearchInput,
-    LayerNameInput,
+    TileCell, TileMapData, TilesetLibrary, TilesetRuntime, ToolKind, ToolState, UndoStack, PaletteSearchInput,
+    LayerNameInput, TilesetGridDialogState,
 };
 
 use super::{apply_tile_change, TilemapRenderParams};
@@ -29,49 +29,50 @@ pub fn keyboard_shortcuts(
 
     if keys.just_pressed(KeyCode::BracketLeft) {
         state.selected_tile = state.selected_tile.saturating_sub(1);
+        state.stamp = None;
     }
     if keys.just_pressed(KeyCode::BracketRight) {
         state.selected_tile = (state.selected_tile + 1).min(tile_count - 1);
+        state.stamp = None;
     }
 
     // 清空地图（做成可 Undo 的命令）。
     if keys.just_pressed(KeyCode::KeyR) {
         if let Some(mut map) = map {
-            let mut changes: Vec<CellChange> = Vec::new();
-            let layer_len = map.layer_len();
-            let map_width = map.width as usize;
-            for (idx, cell) in map.tiles.iter_mut().enumerate() {
-                let before = cell.clone();
-                if before.is_some() {
-                    *cell = None;
-                    let layer = (idx / layer_len) as u32;
-                    let local = idx % layer_len;
-                    let x = (local % map_width) as u32;
-                    let y = (local / map_width) as u32;
-                    apply_tile_change(&mut render, &config, layer, x, y, &before, &None);
-                    changes.push(CellChange {
-                        idx,
-                        before,
-                        after: None,
-                    });
-                }
+            let cells: Vec<(u32, i32, i32, TileCell)> = map.iter_cells().collect();
+            let mut changes: Vec<CellChange> = Vec::with_capacity(cells.len());
+            for (layer, x, y, before) in cells {
+                map.set_cell(layer, x, y, TileCell::EMPTY);
+                apply_tile_change(&mut render, &config, &map, layer, x, y, before, TileCell::EMPTY);
+                changes.push(CellChange {
+                    layer,
+                    x,
+                    y,
+                    before,
+                    after: TileCell::EMPTY,
+                });
             }
-            undo.push(EditCommand { changes });
+            undo.push(EditCommand { changes, ..Default::default() });
         }
     }
 }
 
-/// 工具快捷键：1/2/3/4/5/6 切换（笔刷/矩形/填充/选择/粘贴/橡皮）。
+/// 工具快捷键：1/2/3/4/5/6/7/8 切换（笔刷/矩形/填充/选择/粘贴/橡皮/直线/魔棒；直线下再按 7 切换折线）。
 pub fn tool_shortcuts(
     keys: Res<ButtonInput<KeyCode>>,
     input: Res<MapSizeInput>,
     palette_input: Res<PaletteSearchInput>,
     layer_name_input: Res<LayerNameInput>,
+    grid_dialog: Res<TilesetGridDialogState>,
     mut tools: ResMut<ToolState>,
     mut brush: ResMut<BrushSettings>,
 ) {
     // 正在输入地图尺寸时，数字键留给输入框。
-    if input.focus != MapSizeFocus::None || palette_input.focused || layer_name_input.focused {
+    if input.focus != MapSizeFocus::None
+        || palette_input.focused
+        || layer_name_input.focused
+        || grid_dialog.captures_keys()
+    {
         return;
     }
 
@@ -90,14 +91,18 @@ pub fn tool_shortcuts(
     if keys.just_pressed(KeyCode::Digit1) || keys.just_pressed(KeyCode::Numpad1) {
         tools.tool = ToolKind::Pencil;
     } else if keys.just_pressed(KeyCode::Digit2) || keys.just_pressed(KeyCode::Numpad2) {
-        tools.tool = ToolKind::Rect;
+        tools.pick(ToolKind::Rect);
     } else if keys.just_pressed(KeyCode::Digit3) || keys.just_pressed(KeyCode::Numpad3) {
-        tools.tool = ToolKind::Fill;
+        tools.pick(ToolKind::Fill);
     } else if keys.just_pressed(KeyCode::Digit4) || keys.just_pressed(KeyCode::Numpad4) {
         tools.tool = ToolKind::Select;
     } else if keys.just_pressed(KeyCode::Digit5) || keys.just_pressed(KeyCode::Numpad5) {
         tools.tool = ToolKind::Paste;
     } else if keys.just_pressed(KeyCode::Digit6) || keys.just_pressed(KeyCode::Numpad6) {
         tools.tool = ToolKind::Eraser;
+    } else if keys.just_pressed(KeyCode::Digit7) || keys.just_pressed(KeyCode::Numpad7) {
+        tools.pick(ToolKind::Line);
+    } else if keys.just_pressed(KeyCode::Digit8) || keys.just_pressed(KeyCode::Numpad8) {
+        tools.pick(ToolKind::MagicWand);
     }
 }
@@ -0,0 +1,35 @@
+//! tile 动画播放：推进时钟，按帧序列改写地图 tile 的 `TileTextureIndex`。
+
+use bevy::prelude::*;
+use bevy_ecs_tilemap::prelude::TileTextureIndex;
+
+use crate::editor::types::{AnimatedTile, EditorConfig, TileAnimationClock, TilesetRuntime};
+
+/// View → 动画播放开关关闭时时钟停止，画布与 palette 停在当前帧。
+pub fn advance_tile_animation_clock(
+    time: Res<Time>,
+    config: Res<EditorConfig>,
+    mut clock: ResMut<TileAnimationClock>,
+) {
+    if !config.play_animations {
+        return;
+    }
+    clock.elapsed_ms = clock.elapsed_ms.wrapping_add(time.delta().as_millis() as u64);
+}
+
+/// 把带动画的 tile 切到当前帧（只在帧变化时写入，避免每帧触发 tilemap 重新提交）。
+pub fn animate_map_tiles(
+    clock: Res<TileAnimationClock>,
+    runtime: Res<TilesetRuntime>,
+    mut tile_q: Query<(&AnimatedTile, &mut TileTextureIndex)>,
+) {
+    for (animated, mut texture_index) in tile_q.iter_mut() {
+        let Some(rt) = runtime.by_id.get(&animated.tileset_id) else {
+            continue;
+        };
+        let frame = rt.frame_at(animated.base_index, clock.elapsed_ms);
+        if texture_index.0 != frame {
+            texture_index.0 = frame;
+        }
+    }
+}
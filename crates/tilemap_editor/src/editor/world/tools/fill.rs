@@ -1,20 +1,27 @@
 use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
 
-use std::collections::VecDeque;
+use tilemap_core::{flood_fill_meta, ops, paint_autotile, paint_variations, paint_wang};
 
 use crate::editor::types::{
-    CellChange, ContextMenuState, EditCommand, EditorConfig, EditorState, LayerState, TileMapData,
-    TileRef, TilesetLibrary, ToolKind, ToolState, UndoStack,
-    WorldCamera,
+    AutotileBrushState, ContextMenuState, EditCommand, EditorConfig, EditorState, LayerState, MetaBrushState, PasteState,
+    TileMapData, TileRef, TilesetLibrary, ToolKind, ToolState, UndoStack, VariationBrushState, WangBrushState, WorldCamera,
 };
 
-use super::super::{apply_tile_change, cursor_tile_pos, TilemapRenderParams};
+use super::super::{cursor_tile_pos, render_cell_changes, TilemapRenderParams};
 
-/// 油漆桶（Flood Fill）：点击格子后，按 4 邻接填充“同类 tile”的连通区域。
+/// 油漆桶（Flood Fill）：点击格子后，填充与其“同类 tile”的格子。
+///
+/// 选取方式见 `ToolState::fill_mode`（再次选择油漆桶循环）：4 邻接连通、8 邻接连通、整层同类；
+/// 比较哪些图层见 `ToolState::fill_sample`。
 ///
 /// - 左键：填充为当前选择的 tile
 /// - 右键：保留给右键菜单
+/// - 元数据模式：按当前字段的值比较（不看取样图层），同样按 `fill_mode` 选取（Shift 写 0）
+/// - 有印章时：区域按印章图案平铺（对齐区域外接框左上角）
+/// - 自动图块模式：填充为当前自动图块组，并更新区域边缘外的同组邻居
+/// - Wang 地形模式：把区域涂成当前颜色（Shift 仍按普通方式擦除）
+/// - 随机变体模式：区域内逐格按权重随机挑选当前变体集的 tile
 pub fn fill_with_mouse(
     buttons: Res<ButtonInput<MouseButton>>,
     keys: Res<ButtonInput<KeyCode>>,
@@ -24,8 +31,10 @@ pub fn fill_with_mouse(
     windows: Query<&Window, With<PrimaryWindow>>,
     camera_q: Query<(&Camera, &GlobalTransform), With<WorldCamera>>,
     config: Res<EditorConfig>,
-    state: Res<EditorState>,
+    (state, paste): (Res<EditorState>, Res<PasteState>),
     lib: Res<TilesetLibrary>,
+    meta_brush: Res<MetaBrushState>,
+    (autotile, mut wang, mut variation): (Res<AutotileBrushState>, ResMut<WangBrushState>, ResMut<VariationBrushState>),
     map: Option<ResMut<TileMapData>>,
     mut render: TilemapRenderParams,
     mut undo: ResMut<UndoStack>,
@@ -57,7 +66,7 @@ pub fn fill_with_mouse(
         .get(layer as usize)
         .map(|d| d.locked)
         .unwrap_or(false);
-    if layer_locked {
+    if layer_locked && !meta_brush.enabled {
         return;
     }
 
@@ -78,13 +87,58 @@ pub fn fill_with_mouse(
         camera,
         camera_transform,
         &config,
-        map.width,
-        map.height,
+        map.fixed_size(),
     ) else {
         return;
     };
 
     let erase = keys.pressed(KeyCode::ShiftLeft) || keys.pressed(KeyCode::ShiftRight);
+    if meta_brush.enabled {
+        let value = if erase { 0 } else { meta_brush.value() };
+        let meta = flood_fill_meta(&mut map, pos.x, pos.y, meta_brush.field, value, tools.fill_mode);
+        undo.push(EditCommand { meta, ..Default::default() });
+        return;
+    }
+
+    // “同类”按取样图层判断（可以是别的图层或可见图层合成），写入仍是当前层。
+    let sample = tools.fill_sample.layers(&map, layer);
+    let cells = ops::fill_region(&map, &sample, pos.x, pos.y, tools.fill_mode);
+    if !erase && let Some(stamp) = state.stamp.as_ref() {
+        let changes = ops::fill_pattern(&mut map, layer, &cells, stamp, paste.orientation);
+        if !changes.is_empty() {
+            render_cell_changes(&mut render, &config, &map, &changes);
+            undo.push(EditCommand { changes, ..Default::default() });
+        }
+        return;
+    }
+
+    if let Some((tileset_id, def)) = autotile.active(&lib, state.selected_tile) {
+        let changes = paint_autotile(&mut map, layer, &tileset_id, &def, &cells, erase);
+        if !changes.is_empty() {
+            render_cell_changes(&mut render, &config, &map, &changes);
+            undo.push(EditCommand { changes, ..Default::default() });
+        }
+        return;
+    }
+
+    if !erase && let Some((tileset_id, set, color)) = wang.active(&lib) {
+        let changes = paint_wang(&mut map, layer, &tileset_id, &set, color, &cells, &mut wang.rng);
+        if !changes.is_empty() {
+            render_cell_changes(&mut render, &config, &map, &changes);
+            undo.push(EditCommand { changes, ..Default::default() });
+        }
+        return;
+    }
+
+    if !erase && let Some((tileset_id, set)) = variation.active(&lib) {
+        let changes = paint_variations(&mut map, layer, &tileset_id, &set, &cells, &mut variation.rng);
+        if !changes.is_empty() {
+            render_cell_changes(&mut render, &config, &map, &changes);
+            undo.push(EditCommand { changes, ..Default::default() });
+        }
+        return;
+    }
+
     let desired: Option<TileRef> = if erase {
         None
     } else {
@@ -100,68 +154,14 @@ pub fn fill_with_mouse(
         })
     };
 
-    let start_idx = map.idx_layer(layer, pos.x, pos.y);
-    let target = map.tiles[start_idx].clone();
-    if target == desired {
+    let desired = map.pack(desired.as_ref());
+    let changes = ops::fill_cells(&mut map, layer, &cells, desired);
+    if changes.is_empty() {
         return;
     }
 
-    let w = map.width;
-    let h = map.height;
-    let mut visited = vec![false; (w * h) as usize];
-    let mut q = VecDeque::new();
-    let start_local = (pos.y * w + pos.x) as usize;
-    visited[start_local] = true;
-    q.push_back((pos.x, pos.y));
-
-    let mut cmd = EditCommand::default();
-
-    while let Some((x, y)) = q.pop_front() {
-        let idx = map.idx_layer(layer, x, y);
-        if map.tiles[idx] != target {
-            continue;
-        }
-
-        let before = map.tiles[idx].clone();
-        map.tiles[idx] = desired.clone();
-        cmd.changes.push(CellChange {
-            idx,
-            before,
-            after: desired.clone(),
-        });
-
-        let push =
-            |nx: i32, ny: i32, visited: &mut [bool], q: &mut VecDeque<(u32, u32)>, w: u32, h: u32| {
-                if nx < 0 || ny < 0 {
-                    return;
-                }
-                let (nx, ny) = (nx as u32, ny as u32);
-                if nx >= w || ny >= h {
-                    return;
-                }
-                let nidx = (ny * w + nx) as usize;
-                if visited[nidx] {
-                    return;
-                }
-                visited[nidx] = true;
-                q.push_back((nx, ny));
-            };
-
-        push(x as i32 - 1, y as i32, &mut visited, &mut q, w, h);
-        push(x as i32 + 1, y as i32, &mut visited, &mut q, w, h);
-        push(x as i32, y as i32 - 1, &mut visited, &mut q, w, h);
-        push(x as i32, y as i32 + 1, &mut visited, &mut q, w, h);
-    }
-
     // 局部刷新渲染（只刷改动格子）
-    let layer_len = map.layer_len();
-    let layer_offset = (layer as usize) * layer_len;
-    for ch in &cmd.changes {
-        let local = ch.idx.saturating_sub(layer_offset);
-        let x = (local % map.width as usize) as u32;
-        let y = (local / map.width as usize) as u32;
-        apply_tile_change(&mut render, &config, layer, x, y, &ch.before, &ch.after);
-    }
+    render_cell_changes(&mut render, &config, &map, &changes);
 
-    undo.push(cmd);
+    undo.push(EditCommand { changes, ..Default::default() });
 }
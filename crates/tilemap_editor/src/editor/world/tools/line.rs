@@ -0,0 +1,129 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use tilemap_core::{polyline_cells, snap_line_45};
+
+use crate::editor::types::{
+    BrushSettings, ContextMenuState, EditorConfig, LayerState, LineToolState, ShapePreview, TileMapData, ToolKind,
+    ToolState, WorldCamera,
+};
+
+use super::super::cursor_tile_pos;
+use super::shape::{brush_footprint, ShapePaintParams};
+
+#[derive(SystemParam)]
+pub(crate) struct LineWithMouseParams<'w, 's> {
+    buttons: Res<'w, ButtonInput<MouseButton>>,
+    keys: Res<'w, ButtonInput<KeyCode>>,
+    tools: Res<'w, ToolState>,
+    brush: Res<'w, BrushSettings>,
+    layer_state: Res<'w, LayerState>,
+    menu: Res<'w, ContextMenuState>,
+    windows: Query<'w, 's, &'static Window, With<PrimaryWindow>>,
+    camera_q: Query<'w, 's, (&'static Camera, &'static GlobalTransform), With<WorldCamera>>,
+    config: Res<'w, EditorConfig>,
+    map: Option<ResMut<'w, TileMapData>>,
+    paint: ShapePaintParams<'w, 's>,
+}
+
+/// 直线工具：两点模式按下到松开画一条 Bresenham 直线；折线模式每次点击加顶点，Enter 提交、Esc 取消。
+///
+/// Shift 把当前线段吸附到 45° 方向；线宽为笔刷尺寸。
+pub fn line_with_mouse(
+    params: LineWithMouseParams,
+    mut line: ResMut<LineToolState>,
+    mut preview: ResMut<ShapePreview>,
+) {
+    let LineWithMouseParams {
+        buttons,
+        keys,
+        tools,
+        brush,
+        layer_state,
+        menu,
+        windows,
+        camera_q,
+        config,
+        map,
+        mut paint,
+    } = params;
+
+    if tools.tool != ToolKind::Line || line.polyline != tools.line_polyline {
+        if !line.vertices.is_empty() {
+            line.vertices.clear();
+            preview.cells.clear();
+        }
+        line.polyline = tools.line_polyline;
+    }
+    if tools.tool != ToolKind::Line {
+        return;
+    }
+    if menu.open || menu.consume_left_click {
+        return;
+    }
+    // Alt 留给框选，Space 留给平移。
+    if keys.any_pressed([KeyCode::AltLeft, KeyCode::AltRight, KeyCode::Space]) {
+        return;
+    }
+
+    let Some(mut map) = map else {
+        return;
+    };
+    let layer = layer_state.active.min(map.layers.saturating_sub(1));
+    let layer_locked = map.layer_data.get(layer as usize).is_some_and(|d| d.locked);
+    if (layer_locked && !paint.meta_mode()) || keys.just_pressed(KeyCode::Escape) {
+        line.vertices.clear();
+        preview.cells.clear();
+        return;
+    }
+
+    let Ok(window) = windows.single() else {
+        return;
+    };
+    let Ok((camera, camera_transform)) = camera_q.single() else {
+        return;
+    };
+    let pos = cursor_tile_pos(window, camera, camera_transform, &config, map.fixed_size());
+
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let left_start = buttons.just_pressed(MouseButton::Left);
+    let left_down = buttons.pressed(MouseButton::Left);
+    // 光标处的下一个顶点（Shift 时相对上一个顶点吸附）。
+    let anchor = if line.polyline { line.vertices.last() } else { line.vertices.first() };
+    let next = pos.map(|p| match anchor {
+        Some(&a) if shift => IVec2::from(snap_line_45(a.into(), p.into())),
+        _ => p,
+    });
+
+    let mut commit = false;
+    if line.polyline {
+        if left_start && let Some(next) = next {
+            line.vertices.push(next);
+        }
+        commit = keys.any_just_pressed([KeyCode::Enter, KeyCode::NumpadEnter]) && !line.vertices.is_empty();
+    } else if line.vertices.is_empty() {
+        if left_start && let Some(p) = pos {
+            line.vertices = vec![p, p];
+        }
+    } else {
+        if left_down && let Some(next) = next {
+            line.vertices[1] = next;
+        }
+        commit = !left_down;
+    }
+
+    let mut points: Vec<(i32, i32)> = line.vertices.iter().map(|&v| v.into()).collect();
+    if line.polyline && !commit && let Some(next) = next {
+        points.push(next.into());
+    }
+    let cells = brush_footprint(&map, &polyline_cells(&points), brush.size);
+
+    if commit {
+        paint.commit(&mut map, layer, &cells, false);
+        line.vertices.clear();
+        preview.cells.clear();
+        return;
+    }
+    preview.cells = cells.into_iter().map(IVec2::from).collect();
+}
@@ -1,7 +1,10 @@
 mod fill;
+mod line;
 mod paint;
 mod rect;
+mod shape;
 
 pub use fill::fill_with_mouse;
+pub use line::line_with_mouse;
 pub use paint::paint_with_mouse;
 pub use rect::rect_with_mouse;
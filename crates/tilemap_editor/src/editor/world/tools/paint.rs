@@ -4,10 +4,12 @@ use bevy::ecs::system::SystemParam;
 
 use std::collections::HashMap;
 
+use tilemap_core::{ops, paint_autotile, paint_variations, paint_wang, MetaRecorder};
+
 use crate::editor::types::{
-    CellChange, ContextMenuState, EditCommand, EditorConfig, EditorState, LayerState, TileMapData,
-    TileRef, TilesetLibrary, ToolKind, ToolState, UndoStack,
-    BrushSettings, WorldCamera, 
+    AutotileBrushState, CellChange, ContextMenuState, EditCommand, EditorConfig, EditorState, LayerState, MetaBrushState,
+    PasteState, TileMapData, TileCell, TileRef, TilesetLibrary, ToolKind, ToolState, UndoStack,
+    BrushSettings, VariationBrushState, WangBrushState, WorldCamera, 
 };
 
 use super::super::{apply_tile_change, cursor_tile_pos, TilemapRenderParams};
@@ -18,12 +20,17 @@ pub struct PaintWithMouseParams<'w, 's> {
     pub keys: Res<'w, ButtonInput<KeyCode>>,
     pub tools: Res<'w, ToolState>,
     pub brush: Res<'w, BrushSettings>,
+    pub meta_brush: Res<'w, MetaBrushState>,
+    pub autotile: Res<'w, AutotileBrushState>,
+    pub wang: ResMut<'w, WangBrushState>,
+    pub variation: ResMut<'w, VariationBrushState>,
     pub layer_state: Res<'w, LayerState>,
     pub menu: Res<'w, ContextMenuState>,
     pub windows: Query<'w, 's, &'static Window, With<PrimaryWindow>>,
     pub camera_q: Query<'w, 's, (&'static Camera, &'static GlobalTransform), With<WorldCamera>>,
     pub config: Res<'w, EditorConfig>,
     pub state: Res<'w, EditorState>,
+    pub paste: Res<'w, PasteState>,
     pub lib: Res<'w, TilesetLibrary>,
     pub map: Option<ResMut<'w, TileMapData>>,
     pub render: TilemapRenderParams<'w, 's>,
@@ -52,7 +59,11 @@ pub fn paint_with_mouse(
         return;
     }
 
-    let active_id = if p.tools.tool == ToolKind::Pencil {
+    // 元数据模式不需要 tileset，也不受图层锁定影响（元数据与图层无关）。
+    let meta_mode = p.meta_brush.enabled;
+    let active_id = if meta_mode {
+        None
+    } else if p.tools.tool == ToolKind::Pencil {
         let Some(active_id) = p.lib.active_id.clone() else {
             return;
         };
@@ -70,10 +81,11 @@ pub fn paint_with_mouse(
         .get(layer as usize)
         .map(|d| d.locked)
         .unwrap_or(false);
-    if layer_locked {
+    if layer_locked && !meta_mode {
         // 若当前正在 stroke 中，直接终止（不提交）。
         stroke.active = false;
         stroke.changes.clear();
+        stroke.meta = MetaRecorder::default();
         return;
     }
 
@@ -103,23 +115,116 @@ pub fn paint_with_mouse(
         camera,
         camera_transform,
         &p.config,
-        map.width,
-        map.height,
+        map.fixed_size(),
     );
 
     // 开始一次 stroke：必须在画布区域内按下
-    if !stroke.active {
-        if pos.is_some() {
-            if left_start {
-                stroke.begin(MouseButton::Left);
-            }
-        }
+    if !stroke.active
+        && left_start
+        && let Some(start) = pos
+    {
+        stroke.begin(MouseButton::Left);
+        stroke.origin = start;
     }
 
     let Some(pos) = pos else {
         return;
     };
 
+    // 没有在绘制中
+    if !left_down {
+        return;
+    }
+
+    let size = p.brush.size.clamp(1, 3) as i32;
+    if meta_mode {
+        let field = p.meta_brush.field;
+        let value = if p.tools.tool == ToolKind::Eraser { 0 } else { p.meta_brush.value() };
+        for dy in 0..size {
+            for dx in 0..size {
+                let (x, y) = (pos.x + dx, pos.y + dy);
+                // 先只读比较，值没变时不触发地图的变更检测（叠加层据此重建）。
+                let current = map.meta.get(x, y);
+                if current.with(field, value) != current {
+                    stroke.meta.set(&mut map, x, y, field, value);
+                }
+            }
+        }
+        return;
+    }
+
+    // 印章：以 stroke 起点为原点按印章（变换后）尺寸平铺，光标落在哪一块就盖哪一块；忽略笔刷尺寸。
+    if p.tools.tool == ToolKind::Pencil
+        && let Some(stamp) = p.state.stamp.as_ref()
+    {
+        let (w, h) = p.paste.orientation.dims(stamp.width, stamp.height);
+        let step = IVec2::new(w.max(1) as i32, h.max(1) as i32);
+        let block = stroke.origin + (pos - stroke.origin).div_euclid(step) * step;
+        if stroke.last_block == Some(block) {
+            return;
+        }
+        stroke.last_block = Some(block);
+        for ch in ops::stamp_tiles(&mut map, layer, stamp, block.x, block.y, p.paste.orientation) {
+            stroke.record_change(ch.layer, ch.x, ch.y, ch.before, ch.after);
+            apply_tile_change(&mut p.render, &p.config, &map, ch.layer, ch.x, ch.y, ch.before, ch.after);
+        }
+        return;
+    }
+
+    if let Some((tileset_id, def)) = p.autotile.active(&p.lib, p.state.selected_tile) {
+        let erase = p.tools.tool == ToolKind::Eraser;
+        let mut cells = Vec::new();
+        for dy in 0..size {
+            for dx in 0..size {
+                let (x, y) = (pos.x + dx, pos.y + dy);
+                // 已是同组 tile（或擦除时已为空）的格子跳过，拖动经过时不反复重算邻居。
+                let done = match map.view(map.cell(layer, x, y)) {
+                    Some(v) => !erase && v.tileset_id == &tileset_id && def.contains(v.index),
+                    None => erase,
+                };
+                if map.contains(x, y) && !done {
+                    cells.push((x, y));
+                }
+            }
+        }
+        if cells.is_empty() {
+            return;
+        }
+        for ch in paint_autotile(&mut map, layer, &tileset_id, &def, &cells, erase) {
+            stroke.record_change(ch.layer, ch.x, ch.y, ch.before, ch.after);
+            apply_tile_change(&mut p.render, &p.config, &map, ch.layer, ch.x, ch.y, ch.before, ch.after);
+        }
+        return;
+    }
+
+    // Wang 地形只接管铅笔；橡皮仍按普通方式擦除。
+    if p.tools.tool == ToolKind::Pencil
+        && let Some((tileset_id, set, color)) = p.wang.active(&p.lib)
+    {
+        let cells: Vec<(i32, i32)> = (0..size)
+            .flat_map(|dy| (0..size).map(move |dx| (pos.x + dx, pos.y + dy)))
+            .collect();
+        for ch in paint_wang(&mut map, layer, &tileset_id, &set, color, &cells, &mut p.wang.rng) {
+            stroke.record_change(ch.layer, ch.x, ch.y, ch.before, ch.after);
+            apply_tile_change(&mut p.render, &p.config, &map, ch.layer, ch.x, ch.y, ch.before, ch.after);
+        }
+        return;
+    }
+
+    // 随机变体同样只接管铅笔；已是本组 tile 的格子不会重抽。
+    if p.tools.tool == ToolKind::Pencil
+        && let Some((tileset_id, set)) = p.variation.active(&p.lib)
+    {
+        let cells: Vec<(i32, i32)> = (0..size)
+            .flat_map(|dy| (0..size).map(move |dx| (pos.x + dx, pos.y + dy)))
+            .collect();
+        for ch in paint_variations(&mut map, layer, &tileset_id, &set, &cells, &mut p.variation.rng) {
+            stroke.record_change(ch.layer, ch.x, ch.y, ch.before, ch.after);
+            apply_tile_change(&mut p.render, &p.config, &map, ch.layer, ch.x, ch.y, ch.before, ch.after);
+        }
+        return;
+    }
+
     let desired: Option<TileRef> = if p.tools.tool == ToolKind::Eraser {
         None
     } else {
@@ -132,31 +237,21 @@ pub fn paint_with_mouse(
         })
     };
 
-    // 没有在绘制中
-    if !left_down {
-        return;
-    }
-
-    let size = p.brush.size.clamp(1, 3);
+    let desired = map.pack(desired.as_ref());
     for dy in 0..size {
         for dx in 0..size {
             let x = pos.x + dx;
             let y = pos.y + dy;
-            if x >= map.width || y >= map.height {
+            if !map.contains(x, y) {
                 continue;
             }
-            let idx = map.idx_layer(layer, x, y);
-            if idx >= map.tiles.len() {
+            if map.cell(layer, x, y) == desired {
                 continue;
             }
-            if map.tiles[idx] == desired {
-                continue;
-            }
-            let before = map.tiles[idx].clone();
-            map.tiles[idx] = desired.clone();
-            stroke.record_change(idx, before.clone(), desired.clone());
+            let before = map.set_cell(layer, x, y, desired);
+            stroke.record_change(layer, x, y, before, desired);
 
-            apply_tile_change(&mut p.render, &p.config, layer, x, y, &before, &desired);
+            apply_tile_change(&mut p.render, &p.config, &map, layer, x, y, before, desired);
         }
     }
 }
@@ -164,7 +259,11 @@ pub fn paint_with_mouse(
 pub struct StrokeState {
     pub active: bool,
     pub button: MouseButton,
-    changes: HashMap<usize, CellChange>,
+    /// stroke 起点：印章按此对齐平铺。
+    pub origin: IVec2,
+    last_block: Option<IVec2>,
+    changes: HashMap<(u32, i32, i32), CellChange>,
+    meta: MetaRecorder,
 }
 
 impl Default for StrokeState {
@@ -172,7 +271,10 @@ impl Default for StrokeState {
         Self {
             active: false,
             button: MouseButton::Left,
+            origin: IVec2::ZERO,
+            last_block: None,
             changes: HashMap::new(),
+            meta: MetaRecorder::default(),
         }
     }
 }
@@ -181,14 +283,23 @@ impl StrokeState {
     pub fn begin(&mut self, button: MouseButton) {
         self.active = true;
         self.button = button;
+        self.last_block = None;
         self.changes.clear();
+        self.meta = MetaRecorder::default();
     }
 
-    pub fn record_change(&mut self, idx: usize, before: Option<TileRef>, after: Option<TileRef>) {
+    pub fn record_change(
+        &mut self,
+        layer: u32,
+        x: i32,
+        y: i32,
+        before: TileCell,
+        after: TileCell,
+    ) {
         self.changes
-            .entry(idx)
-            .and_modify(|c| c.after = after.clone())
-            .or_insert(CellChange { idx, before, after });
+            .entry((layer, x, y))
+            .and_modify(|c| c.after = after)
+            .or_insert(CellChange { layer, x, y, before, after });
     }
 
     pub fn take_command(&mut self) -> EditCommand {
@@ -198,7 +309,10 @@ impl StrokeState {
             .map(|(_, v)| v)
             .filter(|c| c.before != c.after)
             .collect();
-        changes.sort_by_key(|c| c.idx);
-        EditCommand { changes }
+        changes.sort_by_key(|c| (c.layer, c.y, c.x));
+        EditCommand {
+            changes,
+            meta: std::mem::take(&mut self.meta).finish(),
+        }
     }
 }
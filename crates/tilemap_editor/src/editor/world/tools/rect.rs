@@ -2,19 +2,26 @@ use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
 
+use tilemap_core::{drag_bounds, ellipse_cells, rect_outline_cells};
+
 use crate::editor::types::{
-    CellChange, ContextMenuState, EditCommand, EditorConfig, EditorState, LayerState, TileMapData,
-    TileRef, TilesetLibrary, ToolKind, ToolState, UndoStack,
+    ContextMenuState, EditorConfig, LayerState, ShapeMode, ShapePreview, TileBounds, TileMapData, ToolKind, ToolState,
     WorldCamera,
 };
 
-use super::super::{apply_tile_change, cursor_tile_pos, TilemapRenderParams};
+use super::super::cursor_tile_pos;
+use super::shape::ShapePaintParams;
+
+/// 拖拽中超过这个格子数时只画外框，不生成幽灵预览。
+const MAX_PREVIEW_CELLS: u32 = 4096;
 
 pub struct RectDragState {
     pub active: bool,
     pub button: MouseButton,
-    pub start: UVec2,
-    pub current: UVec2,
+    pub start: IVec2,
+    pub current: IVec2,
+    /// 按下时按住了 Shift：这次拖拽擦除。
+    pub erase: bool,
 }
 
 #[derive(SystemParam)]
@@ -27,11 +34,9 @@ pub(crate) struct RectWithMouseParams<'w, 's> {
     windows: Query<'w, 's, &'static Window, With<PrimaryWindow>>,
     camera_q: Query<'w, 's, (&'static Camera, &'static GlobalTransform), With<WorldCamera>>,
     config: Res<'w, EditorConfig>,
-    state: Res<'w, EditorState>,
-    lib: Res<'w, TilesetLibrary>,
     map: Option<ResMut<'w, TileMapData>>,
-    render: TilemapRenderParams<'w, 's>,
-    undo: ResMut<'w, UndoStack>,
+    paint: ShapePaintParams<'w, 's>,
+    preview: ResMut<'w, ShapePreview>,
 }
 
 impl Default for RectDragState {
@@ -39,14 +44,18 @@ impl Default for RectDragState {
         Self {
             active: false,
             button: MouseButton::Left,
-            start: UVec2::ZERO,
-            current: UVec2::ZERO,
+            start: IVec2::ZERO,
+            current: IVec2::ZERO,
+            erase: false,
         }
     }
 }
 
-/// 矩形工具：拖拽框选并一次性填充/擦除。
-pub fn rect_with_mouse(mut gizmos: Gizmos, params: RectWithMouseParams, mut drag: Local<RectDragState>) {
+/// 矩形工具：拖拽画实心 / 边框矩形或椭圆（`ToolState.shape`），松开时一次性提交。
+///
+/// 按住 Shift 按下左键时擦除（与油漆桶一致）；拖拽开始后再按 Shift 约束为正方形 / 圆。
+/// Ctrl 以按下点为中心（Alt 留给框选）。
+pub fn rect_with_mouse(params: RectWithMouseParams, mut drag: Local<RectDragState>) {
     let RectWithMouseParams {
         buttons,
         keys,
@@ -56,17 +65,20 @@ pub fn rect_with_mouse(mut gizmos: Gizmos, params: RectWithMouseParams, mut drag
         windows,
         camera_q,
         config,
-        state,
-        lib,
         map,
-        mut render,
-        mut undo,
+        mut paint,
+        mut preview,
     } = params;
 
     if tools.tool != ToolKind::Rect {
         drag.active = false;
         return;
     }
+    // 非拖拽状态不显示预览（也清掉直线工具留下的格子）。
+    if !drag.active && (!preview.cells.is_empty() || preview.frame.is_some()) {
+        preview.cells.clear();
+        preview.frame = None;
+    }
     if menu.open || menu.consume_left_click {
         drag.active = false;
         return;
@@ -103,8 +115,7 @@ pub fn rect_with_mouse(mut gizmos: Gizmos, params: RectWithMouseParams, mut drag
         camera,
         camera_transform,
         &config,
-        map.width,
-        map.height,
+        map.fixed_size(),
     );
 
     let layer = layer_state.active.min(map.layers.saturating_sub(1));
@@ -113,7 +124,7 @@ pub fn rect_with_mouse(mut gizmos: Gizmos, params: RectWithMouseParams, mut drag
         .get(layer as usize)
         .map(|d| d.locked)
         .unwrap_or(false);
-    if layer_locked {
+    if layer_locked && !paint.meta_mode() {
         drag.active = false;
         return;
     }
@@ -128,6 +139,7 @@ pub fn rect_with_mouse(mut gizmos: Gizmos, params: RectWithMouseParams, mut drag
             drag.button = MouseButton::Left;
             drag.start = pos;
             drag.current = pos;
+            drag.erase = keys.pressed(KeyCode::ShiftLeft) || keys.pressed(KeyCode::ShiftRight);
         }
     }
 
@@ -142,71 +154,39 @@ pub fn rect_with_mouse(mut gizmos: Gizmos, params: RectWithMouseParams, mut drag
         }
     }
 
-    let min_x = drag.start.x.min(drag.current.x);
-    let max_x = drag.start.x.max(drag.current.x);
-    let min_y = drag.start.y.min(drag.current.y);
-    let max_y = drag.start.y.max(drag.current.y);
-
-    // 绘制预览框
-    let tile_w = config.tile_size.x as f32;
-    let tile_h = config.tile_size.y as f32;
-    let x0 = min_x as f32 * tile_w;
-    let y0 = min_y as f32 * tile_h;
-    let x1 = (max_x as f32 + 1.0) * tile_w;
-    let y1 = (max_y as f32 + 1.0) * tile_h;
-    let preview_color = Color::srgba(0.25, 0.45, 0.95, 0.95);
-    gizmos.line_2d(Vec2::new(x0, y0), Vec2::new(x1, y0), preview_color);
-    gizmos.line_2d(Vec2::new(x1, y0), Vec2::new(x1, y1), preview_color);
-    gizmos.line_2d(Vec2::new(x1, y1), Vec2::new(x0, y1), preview_color);
-    gizmos.line_2d(Vec2::new(x0, y1), Vec2::new(x0, y0), preview_color);
+    // 擦除拖拽的 Shift 是按下时就按住的，不当作约束。
+    let square = !drag.erase && (keys.pressed(KeyCode::ShiftLeft) || keys.pressed(KeyCode::ShiftRight));
+    let centered = keys.pressed(KeyCode::ControlLeft) || keys.pressed(KeyCode::ControlRight);
+    let bounds = drag_bounds(drag.start.into(), drag.current.into(), square, centered);
 
     // 结束拖拽：提交命令
     let ended = left_end || !left_down;
     if !ended {
-        return;
-    }
-
-    let erase = keys.pressed(KeyCode::ShiftLeft) || keys.pressed(KeyCode::ShiftRight);
-    let desired: Option<TileRef> = if erase {
-        None
-    } else {
-        let Some(active_id) = lib.active_id.clone() else {
-            drag.active = false;
-            return;
+        preview.frame = Some(bounds);
+        preview.cells = if bounds.width().saturating_mul(bounds.height()) <= MAX_PREVIEW_CELLS {
+            shape_cells(&map, tools.shape, bounds).into_iter().map(IVec2::from).collect()
+        } else {
+            Vec::new()
         };
-        Some(TileRef {
-            tileset_id: active_id,
-            index: state.selected_tile,
-            rot: 0,
-            flip_x: false,
-            flip_y: false,
-        })
-    };
-
-    let mut changes: Vec<CellChange> = Vec::new();
-    for y in min_y..=max_y {
-        for x in min_x..=max_x {
-            let idx = map.idx_layer(layer, x, y);
-            if idx >= map.tiles.len() {
-                continue;
-            }
-            if map.tiles[idx] == desired {
-                continue;
-            }
-
-            let before = map.tiles[idx].clone();
-            map.tiles[idx] = desired.clone();
-            changes.push(CellChange {
-                idx,
-                before: before.clone(),
-                after: desired.clone(),
-            });
-
-            // 局部刷新渲染
-            apply_tile_change(&mut render, &config, layer, x, y, &before, &desired);
-        }
+        return;
     }
 
-    undo.push(EditCommand { changes });
+    let cells = shape_cells(&map, tools.shape, bounds);
+    paint.commit(&mut map, layer, &cells, drag.erase);
+    preview.cells.clear();
+    preview.frame = None;
     drag.active = false;
 }
+
+/// 外接框内当前形状覆盖的格子（裁掉图外格子）。
+fn shape_cells(map: &TileMapData, shape: ShapeMode, bounds: TileBounds) -> Vec<(i32, i32)> {
+    let cells = match shape {
+        ShapeMode::Rect => (bounds.min_y..=bounds.max_y)
+            .flat_map(|y| (bounds.min_x..=bounds.max_x).map(move |x| (x, y)))
+            .collect(),
+        ShapeMode::RectOutline => rect_outline_cells(bounds),
+        ShapeMode::Ellipse => ellipse_cells(bounds, true),
+        ShapeMode::EllipseOutline => ellipse_cells(bounds, false),
+    };
+    cells.into_iter().filter(|&(x, y)| map.contains(x, y)).collect()
+}
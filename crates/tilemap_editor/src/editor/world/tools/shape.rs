@@ -0,0 +1,94 @@
+use std::collections::HashSet;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+use tilemap_core::{ops, paint_autotile, paint_variations, paint_wang, MetaRecorder};
+
+use crate::editor::types::{
+    AutotileBrushState, EditCommand, EditorConfig, EditorState, MetaBrushState, PasteState, TileMapData, TileRef,
+    TilesetLibrary, UndoStack, VariationBrushState, WangBrushState,
+};
+
+use super::super::{render_cell_changes, TilemapRenderParams};
+
+/// 形状工具提交一组格子时用到的资源（元数据 / 印章图案 / 自动图块 / Wang 地形 / 随机变体 / 普通 tile 按此优先级）。
+#[derive(SystemParam)]
+pub(crate) struct ShapePaintParams<'w, 's> {
+    config: Res<'w, EditorConfig>,
+    state: Res<'w, EditorState>,
+    paste: Res<'w, PasteState>,
+    lib: Res<'w, TilesetLibrary>,
+    meta_brush: Res<'w, MetaBrushState>,
+    autotile: Res<'w, AutotileBrushState>,
+    wang: ResMut<'w, WangBrushState>,
+    variation: ResMut<'w, VariationBrushState>,
+    render: TilemapRenderParams<'w, 's>,
+    undo: ResMut<'w, UndoStack>,
+}
+
+impl ShapePaintParams<'_, '_> {
+    pub fn meta_mode(&self) -> bool {
+        self.meta_brush.enabled
+    }
+
+    /// 把 `cells` 画成当前笔刷（`erase` 时擦除），整体作为一个撤销步骤。
+    pub fn commit(&mut self, map: &mut TileMapData, layer: u32, cells: &[(i32, i32)], erase: bool) {
+        if self.meta_brush.enabled {
+            let (field, value) = (self.meta_brush.field, if erase { 0 } else { self.meta_brush.value() });
+            let mut rec = MetaRecorder::default();
+            for &(x, y) in cells {
+                rec.set(map, x, y, field, value);
+            }
+            self.undo.push(EditCommand { meta: rec.finish(), ..Default::default() });
+            return;
+        }
+
+        let changes = if !erase && let Some(stamp) = self.state.stamp.as_ref() {
+            ops::fill_pattern(map, layer, cells, stamp, self.paste.orientation)
+        } else if let Some((tileset_id, def)) = self.autotile.active(&self.lib, self.state.selected_tile) {
+            paint_autotile(map, layer, &tileset_id, &def, cells, erase)
+        } else if !erase && let Some((tileset_id, set, color)) = self.wang.active(&self.lib) {
+            paint_wang(map, layer, &tileset_id, &set, color, cells, &mut self.wang.rng)
+        } else if !erase && let Some((tileset_id, set)) = self.variation.active(&self.lib) {
+            paint_variations(map, layer, &tileset_id, &set, cells, &mut self.variation.rng)
+        } else {
+            let desired = if erase {
+                None
+            } else {
+                let Some(tileset_id) = self.lib.active_id.clone() else {
+                    return;
+                };
+                Some(TileRef {
+                    tileset_id,
+                    index: self.state.selected_tile,
+                    rot: 0,
+                    flip_x: false,
+                    flip_y: false,
+                })
+            };
+            let desired = map.pack(desired.as_ref());
+            ops::fill_cells(map, layer, cells, desired)
+        };
+        render_cell_changes(&mut self.render, &self.config, map, &changes);
+        self.undo.push(EditCommand { changes, ..Default::default() });
+    }
+}
+
+/// 按笔刷尺寸把每个格子扩成 `size x size` 方块（与铅笔一致向右上扩），去重并裁掉图外格子。
+pub(crate) fn brush_footprint(map: &TileMapData, cells: &[(i32, i32)], size: u32) -> Vec<(i32, i32)> {
+    let size = size.clamp(1, 3) as i32;
+    let mut seen = HashSet::new();
+    let mut out = Vec::new();
+    for &(x, y) in cells {
+        for dy in 0..size {
+            for dx in 0..size {
+                let c = (x + dx, y + dy);
+                if map.contains(c.0, c.1) && seen.insert(c) {
+                    out.push(c);
+                }
+            }
+        }
+    }
+    out
+}
@@ -1,8 +1,46 @@
 use bevy::prelude::*;
 
+use tilemap_core::{ops, reapply_meta, revert_meta};
+
 use crate::editor::types::{EditorConfig, TileMapData, UndoStack};
 
-use super::{apply_tile_change, TilemapRenderParams};
+use super::{apply_tile_change, render_cell_changes, TilemapRenderParams};
+
+/// 撤销一步：把 undo 栈顶命令的 before 写回地图并刷新渲染。
+pub fn undo_once(
+    map: &mut TileMapData,
+    render: &mut TilemapRenderParams,
+    config: &EditorConfig,
+    undo: &mut UndoStack,
+) -> bool {
+    let Some(cmd) = undo.undo.pop() else {
+        return false;
+    };
+    ops::revert(map, &cmd.changes);
+    revert_meta(map, &cmd.meta);
+    for ch in cmd.changes.iter().rev() {
+        apply_tile_change(render, config, map, ch.layer, ch.x, ch.y, ch.after, ch.before);
+    }
+    undo.redo.push(cmd);
+    true
+}
+
+/// 重做一步：把 redo 栈顶命令的 after 写回地图并刷新渲染。
+pub fn redo_once(
+    map: &mut TileMapData,
+    render: &mut TilemapRenderParams,
+    config: &EditorConfig,
+    undo: &mut UndoStack,
+) -> bool {
+    let Some(cmd) = undo.redo.pop() else {
+        return false;
+    };
+    ops::reapply(map, &cmd.changes);
+    reapply_meta(map, &cmd.meta);
+    render_cell_changes(render, config, map, &cmd.changes);
+    undo.undo.push(cmd);
+    true
+}
 
 pub fn undo_redo_shortcuts(
     keys: Res<ButtonInput<KeyCode>>,
@@ -26,40 +64,11 @@ pub fn undo_redo_shortcuts(
             && (keys.pressed(KeyCode::ShiftLeft) || keys.pressed(KeyCode::ShiftRight)));
 
     if want_undo {
-        let Some(cmd) = undo.undo.pop() else {
-            return;
-        };
-        let layer_len = map.layer_len();
-        for ch in &cmd.changes {
-            if ch.idx < map.tiles.len() {
-                map.tiles[ch.idx] = ch.before.clone();
-                let layer = (ch.idx / layer_len) as u32;
-                let local = ch.idx % layer_len;
-                let x = (local % map.width as usize) as u32;
-                let y = (local / map.width as usize) as u32;
-                apply_tile_change(&mut render, &config, layer, x, y, &ch.after, &ch.before);
-            }
-        }
-        undo.redo.push(cmd);
+        undo_once(&mut map, &mut render, &config, &mut undo);
         return;
     }
 
     if want_redo {
-        let Some(cmd) = undo.redo.pop() else {
-            return;
-        };
-        let layer_len = map.layer_len();
-        for ch in &cmd.changes {
-            if ch.idx < map.tiles.len() {
-                map.tiles[ch.idx] = ch.after.clone();
-                let layer = (ch.idx / layer_len) as u32;
-                let local = ch.idx % layer_len;
-                let x = (local % map.width as usize) as u32;
-                let y = (local / map.width as usize) as u32;
-                apply_tile_change(&mut render, &config, layer, x, y, &ch.before, &ch.after);
-            }
-        }
-        undo.undo.push(cmd);
-        return;
+        redo_once(&mut map, &mut render, &config, &mut undo);
     }
 }
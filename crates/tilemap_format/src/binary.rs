@@ -0,0 +1,567 @@
+//! 紧凑二进制格式（小端），供构建脚本快速读写；内容与 RON V4 一一对应。
+//!
+//! 布局：
+//! - magic `TMB\0` + u16 版本
+//! - u8 flags（bit0 = infinite），u32 width / height / layers
+//! - 每层：str name，u8 visible，u8 locked，f32 opacity（版本 2 起）
+//! - u32 tileset 数；每个：str id / name / category / asset_path，
+//!   u32 tile_width / tile_height / margin / spacing（版本 3 起），
+//!   u32 tile 附加数据条数；每条：u32 index，u32 帧数，每帧 u32 index / duration_ms（版本 4 起），
+//!   u32 碰撞形状数，每个：u8 类型（0 整格 / 1 多边形），多边形再跟 u32 顶点数 + 每点 f32 x / y（版本 5 起），
+//!   u32 自动图块组数；每组：str name，u32 first（版本 7 起），
+//!   u32 Wang 集数；每个：str name，u8 类型（0 corner / 1 edge / 2 mixed），u32 tile，
+//!   u32 颜色数，每个 str name / color，u32 tile，f32 probability，
+//!   u32 wang tile 数，每个 u32 index + 8 个 u8 槽位（版本 8 起；tile 为 u32::MAX 表示未设置），
+//!   u32 变体集数；每个：str name，u8 flags（bit0 随机旋转 / bit1 随机翻转），
+//!   u32 tile 数，每个 u32 index + f32 weight（版本 9 起）
+//! - u32 元数据格子数；每个：i32 x / y，u8 region / passage / terrain（版本 6 起）
+//! - u32 格子数；每个：u32 layer，i32 x / y，u32 tileset，u32 index，u8 朝向（Tiled H/V/D 三位）
+//!
+//! str = u32 字节长度 + UTF-8。
+
+use std::collections::HashMap;
+
+use tilemap_core::{
+    AutotileDef, CellMeta, CollisionShape, LayerData, MetaField, Orientation, TileMapData, TileRef, Variation,
+    VariationSet, WangColor, WangKind, WangSet, WangTile,
+};
+
+use crate::{new_map, AnimationFrame, TileMeta, TilesetEntry};
+
+pub const BINARY_MAGIC: [u8; 4] = *b"TMB\0";
+const VERSION: u16 = 9;
+/// 版本 1 没有图层不透明度，仍可读取。
+const VERSION_NO_OPACITY: u16 = 1;
+/// 版本 2 没有 tileset 切分参数（按地图网格切分），仍可读取。
+const VERSION_NO_GRID: u16 = 2;
+/// 版本 3 没有 tile 附加数据（动画），仍可读取。
+const VERSION_NO_TILE_META: u16 = 3;
+/// 版本 4 没有 tile 碰撞形状，仍可读取。
+const VERSION_NO_COLLISION: u16 = 4;
+/// 版本 5 没有地图元数据（区域 / 通行 / 地形），仍可读取。
+const VERSION_NO_CELL_META: u16 = 5;
+/// 版本 6 没有自动图块组，仍可读取。
+const VERSION_NO_AUTOTILES: u16 = 6;
+/// 版本 7 没有 Wang 集，仍可读取。
+const VERSION_NO_WANGSETS: u16 = 7;
+/// 版本 8 没有变体集，仍可读取。
+const VERSION_NO_VARIATIONS: u16 = 8;
+const SHAPE_FULL: u8 = 0;
+const SHAPE_POLYGON: u8 = 1;
+const FLAG_INFINITE: u8 = 1;
+const VARIATION_ROTATE: u8 = 1;
+const VARIATION_FLIP: u8 = 2;
+const WANG_KINDS: [WangKind; 3] = [WangKind::Corner, WangKind::Edge, WangKind::Mixed];
+const NO_TILE: u32 = u32::MAX;
+
+/// 单个格子记录的字节数（用于校验格子数是否与剩余长度相符）。
+const TILE_RECORD_LEN: usize = 4 * 5 + 1;
+const META_RECORD_LEN: usize = 4 * 2 + 3;
+
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn u8(&mut self, v: u8) {
+        self.0.push(v);
+    }
+
+    fn u32(&mut self, v: u32) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn i32(&mut self, v: i32) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn f32(&mut self, v: f32) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn str(&mut self, v: &str) {
+        self.u32(v.len() as u32);
+        self.0.extend_from_slice(v.as_bytes());
+    }
+}
+
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], String> {
+        let end = self.pos.checked_add(n).filter(|&e| e <= self.buf.len());
+        let Some(end) = end else {
+            return Err(format!("文件被截断（偏移 {}，还需 {n} 字节）", self.pos));
+        };
+        let out = &self.buf[self.pos..end];
+        self.pos = end;
+        Ok(out)
+    }
+
+    fn remaining(&self) -> usize {
+        self.buf.len() - self.pos
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        let b = self.take(2)?;
+        Ok(u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let b = self.take(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn i32(&mut self) -> Result<i32, String> {
+        let b = self.take(4)?;
+        Ok(i32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn f32(&mut self) -> Result<f32, String> {
+        let b = self.take(4)?;
+        Ok(f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn str(&mut self) -> Result<String, String> {
+        let at = self.pos;
+        let len = self.u32()? as usize;
+        let bytes = self.take(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| format!("偏移 {at} 处的字符串不是合法 UTF-8"))
+    }
+}
+
+/// 编码为二进制。`tilesets` 必须覆盖地图中用到的全部 tileset_id。
+pub fn encode_map_binary(map: &TileMapData, tilesets: &[TilesetEntry]) -> Result<Vec<u8>, String> {
+    let id_to_index: HashMap<&str, u32> = tilesets
+        .iter()
+        .enumerate()
+        .map(|(i, t)| (t.id.as_str(), i as u32))
+        .collect();
+
+    let mut w = Writer(Vec::new());
+    w.0.extend_from_slice(&BINARY_MAGIC);
+    w.0.extend_from_slice(&VERSION.to_le_bytes());
+    w.u8(if map.infinite { FLAG_INFINITE } else { 0 });
+    w.u32(map.width);
+    w.u32(map.height);
+    let layers = map.layers.max(1);
+    w.u32(layers);
+    for layer in 0..layers {
+        let meta = map.layer_data.get(layer as usize).cloned().unwrap_or_default();
+        w.str(&meta.name);
+        w.u8(meta.visible as u8);
+        w.u8(meta.locked as u8);
+        w.f32(meta.opacity);
+    }
+
+    w.u32(tilesets.len() as u32);
+    for t in tilesets {
+        w.str(&t.id);
+        w.str(&t.name);
+        w.str(&t.category);
+        w.str(&t.asset_path);
+        w.u32(t.tile_width);
+        w.u32(t.tile_height);
+        w.u32(t.margin);
+        w.u32(t.spacing);
+        w.u32(t.tiles.len() as u32);
+        for meta in &t.tiles {
+            w.u32(meta.index);
+            w.u32(meta.animation.len() as u32);
+            for frame in &meta.animation {
+                w.u32(frame.index);
+                w.u32(frame.duration_ms);
+            }
+            w.u32(meta.collision.len() as u32);
+            for shape in &meta.collision {
+                match shape {
+                    CollisionShape::Full => w.u8(SHAPE_FULL),
+                    CollisionShape::Polygon(points) => {
+                        w.u8(SHAPE_POLYGON);
+                        w.u32(points.len() as u32);
+                        for &[x, y] in points {
+                            w.f32(x);
+                            w.f32(y);
+                        }
+                    }
+                }
+            }
+        }
+        w.u32(t.autotiles.len() as u32);
+        for def in &t.autotiles {
+            w.str(&def.name);
+            w.u32(def.first);
+        }
+        w.u32(t.wangsets.len() as u32);
+        for set in &t.wangsets {
+            w.str(&set.name);
+            w.u8(WANG_KINDS.iter().position(|k| *k == set.kind).unwrap_or(0) as u8);
+            w.u32(set.tile.unwrap_or(NO_TILE));
+            w.u32(set.colors.len() as u32);
+            for color in &set.colors {
+                w.str(&color.name);
+                w.str(&color.color);
+                w.u32(color.tile.unwrap_or(NO_TILE));
+                w.f32(color.probability);
+            }
+            w.u32(set.tiles.len() as u32);
+            for tile in &set.tiles {
+                w.u32(tile.index);
+                w.0.extend_from_slice(&tile.wang_id);
+            }
+        }
+        w.u32(t.variations.len() as u32);
+        for set in &t.variations {
+            w.str(&set.name);
+            let mut flags = 0;
+            if set.random_rotation {
+                flags |= VARIATION_ROTATE;
+            }
+            if set.random_flip {
+                flags |= VARIATION_FLIP;
+            }
+            w.u8(flags);
+            w.u32(set.tiles.len() as u32);
+            for v in &set.tiles {
+                w.u32(v.index);
+                w.f32(v.weight);
+            }
+        }
+    }
+
+    w.u32(map.meta.len() as u32);
+    for (x, y, meta) in map.meta.iter() {
+        w.i32(x);
+        w.i32(y);
+        w.u8(meta.region);
+        w.u8(meta.passage);
+        w.u8(meta.terrain);
+    }
+
+    let mut tiles = Vec::new();
+    for (layer, x, y, tile) in map.iter_tiles() {
+        let Some(&tileset) = id_to_index.get(tile.tileset_id.as_str()) else {
+            return Err(format!("tileset_id 不在 tileset 列表中: {}", tile.tileset_id));
+        };
+        tiles.push((layer, x, y, tileset, tile.index, tile.orientation()));
+    }
+    w.u32(tiles.len() as u32);
+    for (layer, x, y, tileset, index, orientation) in tiles {
+        w.u32(layer);
+        w.i32(x);
+        w.i32(y);
+        w.u32(tileset);
+        w.u32(index);
+        w.u8((orientation.tiled_flags() >> 29) as u8);
+    }
+    Ok(w.0)
+}
+
+fn read_tile_meta(r: &mut Reader, version: u16) -> Result<Vec<TileMeta>, String> {
+    let count = r.u32()?;
+    if count as usize > r.remaining() / 8 {
+        return Err(format!("tile 附加数据条数 {count} 与文件长度不符"));
+    }
+    let mut tiles = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let index = r.u32()?;
+        let frames = r.u32()?;
+        if frames as usize > r.remaining() / 8 {
+            return Err(format!("tile {index} 的动画帧数 {frames} 与文件长度不符"));
+        }
+        let mut animation = Vec::with_capacity(frames as usize);
+        for _ in 0..frames {
+            animation.push(AnimationFrame {
+                index: r.u32()?,
+                duration_ms: r.u32()?,
+            });
+        }
+        let collision = if version > VERSION_NO_COLLISION {
+            read_collision(r, index)?
+        } else {
+            Vec::new()
+        };
+        tiles.push(TileMeta { index, animation, collision });
+    }
+    Ok(tiles)
+}
+
+fn read_autotiles(r: &mut Reader) -> Result<Vec<AutotileDef>, String> {
+    let count = r.u32()?;
+    if count as usize > r.remaining() / 8 {
+        return Err(format!("自动图块组数 {count} 与文件长度不符"));
+    }
+    let mut defs = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        defs.push(AutotileDef {
+            name: r.str()?,
+            first: r.u32()?,
+        });
+    }
+    Ok(defs)
+}
+
+fn read_wangsets(r: &mut Reader) -> Result<Vec<WangSet>, String> {
+    let tile = |v: u32| (v != NO_TILE).then_some(v);
+    let count = r.u32()?;
+    if count as usize > r.remaining() / 17 {
+        return Err(format!("Wang 集数 {count} 与文件长度不符"));
+    }
+    let mut sets = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let name = r.str()?;
+        let kind = r.u8()?;
+        let Some(&kind) = WANG_KINDS.get(kind as usize) else {
+            return Err(format!("Wang 集 {name} 的类型未知: {kind}"));
+        };
+        let mut set = WangSet {
+            name,
+            kind,
+            tile: tile(r.u32()?),
+            ..Default::default()
+        };
+        let colors = r.u32()?;
+        if colors as usize > r.remaining() / 16 {
+            return Err(format!("Wang 集 {} 的颜色数 {colors} 与文件长度不符", set.name));
+        }
+        for _ in 0..colors {
+            set.colors.push(WangColor {
+                name: r.str()?,
+                color: r.str()?,
+                tile: tile(r.u32()?),
+                probability: r.f32()?,
+            });
+        }
+        let tiles = r.u32()?;
+        if tiles as usize > r.remaining() / 12 {
+            return Err(format!("Wang 集 {} 的 tile 数 {tiles} 与文件长度不符", set.name));
+        }
+        for _ in 0..tiles {
+            let index = r.u32()?;
+            let mut wang_id = [0; 8];
+            wang_id.copy_from_slice(r.take(8)?);
+            set.tiles.push(WangTile { index, wang_id });
+        }
+        sets.push(set);
+    }
+    Ok(sets)
+}
+
+fn read_variations(r: &mut Reader) -> Result<Vec<VariationSet>, String> {
+    let count = r.u32()?;
+    if count as usize > r.remaining() / 9 {
+        return Err(format!("变体集数 {count} 与文件长度不符"));
+    }
+    let mut sets = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let name = r.str()?;
+        let flags = r.u8()?;
+        let tiles = r.u32()?;
+        if tiles as usize > r.remaining() / 8 {
+            return Err(format!("变体集 {name} 的 tile 数 {tiles} 与文件长度不符"));
+        }
+        let mut set = VariationSet {
+            name,
+            tiles: Vec::with_capacity(tiles as usize),
+            random_rotation: flags & VARIATION_ROTATE != 0,
+            random_flip: flags & VARIATION_FLIP != 0,
+        };
+        for _ in 0..tiles {
+            set.tiles.push(Variation {
+                index: r.u32()?,
+                weight: r.f32()?,
+            });
+        }
+        sets.push(set);
+    }
+    Ok(sets)
+}
+
+fn read_collision(r: &mut Reader, index: u32) -> Result<Vec<CollisionShape>, String> {
+    let count = r.u32()?;
+    if count as usize > r.remaining() {
+        return Err(format!("tile {index} 的碰撞形状数 {count} 与文件长度不符"));
+    }
+    let mut shapes = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        match r.u8()? {
+            SHAPE_FULL => shapes.push(CollisionShape::Full),
+            SHAPE_POLYGON => {
+                let n = r.u32()?;
+                if n as usize > r.remaining() / 8 {
+                    return Err(format!("tile {index} 的多边形顶点数 {n} 与文件长度不符"));
+                }
+                let mut points = Vec::with_capacity(n as usize);
+                for _ in 0..n {
+                    points.push([r.f32()?, r.f32()?]);
+                }
+                shapes.push(CollisionShape::Polygon(points));
+            }
+            other => return Err(format!("tile {index} 的碰撞形状类型未知: {other}")),
+        }
+    }
+    Ok(shapes)
+}
+
+fn read_cell_meta(r: &mut Reader, map: &mut TileMapData) -> Result<(), String> {
+    let count = r.u32()?;
+    if count as usize > r.remaining() / META_RECORD_LEN {
+        return Err(format!("元数据格子数 {count} 与文件长度不符"));
+    }
+    for _ in 0..count {
+        let x = r.i32()?;
+        let y = r.i32()?;
+        let meta = CellMeta::default()
+            .with(MetaField::Region, r.u8()?)
+            .with(MetaField::Passage, r.u8()?)
+            .with(MetaField::Terrain, r.u8()?);
+        if !map.contains(x, y) {
+            return Err(format!("元数据格子 ({x}, {y}) 超出地图范围 {}x{}", map.width, map.height));
+        }
+        map.meta.set(x, y, meta);
+    }
+    Ok(())
+}
+
+/// 解码二进制，返回地图与 tileset 列表（与 `decode_map_ron` 一致）。
+pub fn decode_map_binary(bytes: &[u8]) -> Result<(TileMapData, Vec<TilesetEntry>), String> {
+    let mut r = Reader { buf: bytes, pos: 0 };
+    if r.take(4).ok() != Some(&BINARY_MAGIC[..]) {
+        return Err("不是 tilemap 二进制文件（magic 不匹配）".to_string());
+    }
+    let version = r.u16()?;
+    if !(VERSION_NO_OPACITY..=VERSION).contains(&version) {
+        return Err(format!("不支持的二进制版本: {version}（当前支持 {VERSION}）"));
+    }
+    let flags = r.u8()?;
+    let width = r.u32()?;
+    let height = r.u32()?;
+    let layers = r.u32()?;
+    // 每层至少占 6 字节（空名字 + 两个标志），先校验再分配。
+    if layers as usize > r.remaining() / 6 {
+        return Err(format!("图层数 {layers} 与文件长度不符"));
+    }
+    let mut map = new_map(width, height, layers, flags & FLAG_INFINITE != 0)?;
+    for layer in 0..layers as usize {
+        let name = r.str()?;
+        let visible = r.u8()? != 0;
+        let locked = r.u8()? != 0;
+        let opacity = if version >= VERSION_NO_GRID { r.f32()? } else { 1.0 };
+        let opacity = if opacity.is_nan() { 1.0 } else { opacity.clamp(0.0, 1.0) };
+        map.layer_data[layer] = LayerData {
+            name,
+            visible,
+            locked,
+            opacity,
+        };
+    }
+
+    let tileset_count = r.u32()?;
+    if tileset_count as usize > r.remaining() / 16 {
+        return Err(format!("tileset 数 {tileset_count} 与文件长度不符"));
+    }
+    let mut tilesets = Vec::with_capacity(tileset_count as usize);
+    for _ in 0..tileset_count {
+        let mut entry = TilesetEntry {
+            id: r.str()?,
+            name: r.str()?,
+            category: r.str()?,
+            asset_path: r.str()?,
+            ..Default::default()
+        };
+        if version > VERSION_NO_GRID {
+            entry.tile_width = r.u32()?;
+            entry.tile_height = r.u32()?;
+            entry.margin = r.u32()?;
+            entry.spacing = r.u32()?;
+        }
+        if version > VERSION_NO_TILE_META {
+            entry.tiles = read_tile_meta(&mut r, version)?;
+        }
+        if version > VERSION_NO_AUTOTILES {
+            entry.autotiles = read_autotiles(&mut r)?;
+        }
+        if version > VERSION_NO_WANGSETS {
+            entry.wangsets = read_wangsets(&mut r)?;
+        }
+        if version > VERSION_NO_VARIATIONS {
+            entry.variations = read_variations(&mut r)?;
+        }
+        tilesets.push(entry);
+    }
+
+    if version > VERSION_NO_CELL_META {
+        read_cell_meta(&mut r, &mut map)?;
+    }
+
+    let tile_count = r.u32()? as usize;
+    if tile_count.checked_mul(TILE_RECORD_LEN) != Some(r.remaining()) {
+        return Err(format!(
+            "格子数 {tile_count} 与剩余长度 {} 字节不符（文件被截断或有多余数据）",
+            r.remaining()
+        ));
+    }
+    for _ in 0..tile_count {
+        let layer = r.u32()?;
+        let x = r.i32()?;
+        let y = r.i32()?;
+        let tileset = r.u32()?;
+        let index = r.u32()?;
+        let orientation = Orientation::from_tiled_gid((r.u8()? as u32 & 0b111) << 29);
+        if layer >= map.layers {
+            return Err(format!("格子 ({x}, {y}) 的图层 {layer} 超出图层数 {}", map.layers));
+        }
+        if !map.contains(x, y) {
+            return Err(format!("格子 ({x}, {y}) 超出地图范围 {width}x{height}"));
+        }
+        let Some(entry) = tilesets.get(tileset as usize) else {
+            return Err(format!("格子 ({x}, {y}) 引用了不存在的 tileset #{tileset}"));
+        };
+        let mut tile = TileRef {
+            tileset_id: entry.id.clone(),
+            index,
+            rot: 0,
+            flip_x: false,
+            flip_y: false,
+        };
+        tile.set_orientation(orientation);
+        map.set(layer, x, y, Some(tile));
+    }
+
+    Ok((map, tilesets))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{assert_same, entries, sample};
+
+    #[test]
+    fn binary_round_trip() {
+        let tilesets = entries();
+        for infinite in [false, true] {
+            let map = sample(infinite);
+            let bytes = encode_map_binary(&map, &tilesets).unwrap();
+            let (back, back_tilesets) = decode_map_binary(&bytes).unwrap();
+            assert_same(&map, &back);
+            assert_eq!(back_tilesets, tilesets);
+
+            assert!(decode_map_binary(&bytes[..bytes.len() - 1]).is_err());
+            assert!(decode_map_binary(b"nope").is_err());
+        }
+    }
+
+    #[test]
+    fn binary_rejects_overflowing_dimensions() {
+        // width * height * layers 超出 u64：应当视为尺寸过大，而不是溢出后通过检查。
+        let mut bytes = encode_map_binary(&TileMapData::new_with_layers(1, 1, 2), &[]).unwrap();
+        bytes[7..11].copy_from_slice(&u32::MAX.to_le_bytes());
+        bytes[11..15].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(decode_map_binary(&bytes).unwrap_err().contains("尺寸过大"));
+    }
+}
@@ -0,0 +1,119 @@
+//! 预计算碰撞体：按 tileset 的 tile 碰撞形状合并地图几何，并以 JSON 随关卡一起发布。
+//!
+//! JSON 结构：`{ "version": 1, "tile_width", "tile_height", "mode", "colliders": MapColliders }`，
+//! 坐标单位为格子（y 向上），乘以 tile 像素尺寸即为世界坐标。
+
+use serde::{Deserialize, Serialize};
+
+use tilemap_core::{build_colliders, ColliderMode, MapColliders, TileMapData};
+
+use crate::TilesetEntry;
+
+const COLLIDERS_VERSION: u32 = 1;
+
+/// 预计算碰撞体文件的内容。
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CollidersFile {
+    pub version: u32,
+    pub tile_width: u32,
+    pub tile_height: u32,
+    pub mode: ColliderMode,
+    pub colliders: MapColliders,
+}
+
+/// 用 `tilesets` 里记录的碰撞形状合并 `layers` 的碰撞几何（运行时与离线导出共用）。
+pub fn map_colliders(
+    map: &TileMapData,
+    tilesets: &[TilesetEntry],
+    layers: &[u32],
+    mode: ColliderMode,
+) -> MapColliders {
+    build_colliders(map, layers, mode, |id, index| {
+        tilesets
+            .iter()
+            .find(|t| &t.id == id)
+            .map(|t| t.collision(index))
+            .unwrap_or(&[])
+    })
+}
+
+pub fn encode_colliders_json(
+    colliders: &MapColliders,
+    mode: ColliderMode,
+    tile_size: (u32, u32),
+) -> Result<String, String> {
+    let file = CollidersFile {
+        version: COLLIDERS_VERSION,
+        tile_width: tile_size.0,
+        tile_height: tile_size.1,
+        mode,
+        colliders: colliders.clone(),
+    };
+    serde_json::to_string_pretty(&file).map_err(|e| e.to_string())
+}
+
+pub fn decode_colliders_json(text: &str) -> Result<CollidersFile, String> {
+    let file: CollidersFile = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    if file.version != COLLIDERS_VERSION {
+        return Err(format!("不支持的碰撞体文件版本: {}", file.version));
+    }
+    Ok(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tilemap_core::{CollisionShape, Orientation, TileRef};
+
+    #[test]
+    fn colliders_use_tileset_shapes_and_round_trip() {
+        let mut ground = TilesetEntry {
+            id: "ground".to_string(),
+            ..Default::default()
+        };
+        ground.set_collision(0, vec![CollisionShape::Full]);
+        ground.set_collision(1, vec![CollisionShape::polygon(vec![[0.0, 0.0], [1.0, 0.0], [1.0, 0.5]])]);
+
+        let mut map = TileMapData::new_with_layers(4, 2, 1);
+        let tile = |index: u32, orientation: Orientation| {
+            let mut t = TileRef {
+                tileset_id: "ground".to_string(),
+                index,
+                rot: 0,
+                flip_x: false,
+                flip_y: false,
+            };
+            t.set_orientation(orientation);
+            Some(t)
+        };
+        for x in 0..3 {
+            map.set(0, x, 0, tile(0, Orientation::IDENTITY));
+        }
+        map.set(0, 3, 0, tile(1, Orientation::FLIP_X));
+        // 没有碰撞定义的 tile / tileset 不产生几何。
+        map.set(0, 0, 1, tile(5, Orientation::IDENTITY));
+        map.set(
+            0,
+            1,
+            1,
+            Some(TileRef {
+                tileset_id: "deco".to_string(),
+                index: 0,
+                rot: 0,
+                flip_x: false,
+                flip_y: false,
+            }),
+        );
+
+        let colliders = map_colliders(&map, &[ground], &[0], ColliderMode::Rects);
+        assert_eq!(colliders.rects.len(), 1);
+        assert_eq!((colliders.rects[0].width, colliders.rects[0].height), (3, 1));
+        assert_eq!(colliders.polygons.len(), 1);
+
+        let text = encode_colliders_json(&colliders, ColliderMode::Rects, (16, 16)).unwrap();
+        let back = decode_colliders_json(&text).unwrap();
+        assert_eq!(back.colliders, colliders);
+        assert_eq!((back.tile_width, back.tile_height, back.mode), (16, 16, ColliderMode::Rects));
+        assert!(decode_colliders_json(&text.replace("\"version\": 1", "\"version\": 9")).is_err());
+    }
+}
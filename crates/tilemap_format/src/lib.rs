@@ -2,12 +2,149 @@
 
 use serde::{Deserialize, Serialize};
 
-use tilemap_core::{LayerData, TileMapData, TileRef};
+use tilemap_core::{
+    AutotileDef, CollisionShape, LayerData, MetaGrid, TileGrid, TileMapData, TileRef, TilesetId, VariationSet, WangSet,
+};
+
+mod binary;
+mod colliders;
+mod tile_meta;
+mod tiled_json;
+mod validate;
+
+pub use binary::{decode_map_binary, encode_map_binary, BINARY_MAGIC};
+pub use colliders::{decode_colliders_json, encode_colliders_json, map_colliders, CollidersFile};
+pub use tile_meta::{animation_frame_at, AnimationFrame, TileMeta};
+pub use tiled_json::{decode_map_tiled_json, decode_tiled_tileset_wangsets, encode_map_tiled_json, TiledTilesetInfo};
+pub use validate::{is_content_hash_id, validate_map, AssetProbe, CellPos, MapProblem};
+
+/// 地图文件里记录的 tileset（编辑器的 tileset 库条目也用同一结构）。
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct TilesetEntry {
+    pub id: TilesetId,
+    pub name: String,
+    pub category: String,
+    /// 相对 assets 目录的路径，例如：`tilesets/foo.png`；为空表示占位（缺少源文件）。
+    pub asset_path: String,
+    /// 图集中单个 tile 的像素尺寸；0 表示沿用地图网格尺寸（旧数据没有这两个字段）。
+    #[serde(default)]
+    pub tile_width: u32,
+    #[serde(default)]
+    pub tile_height: u32,
+    /// 图片四周的留白（像素）。
+    #[serde(default)]
+    pub margin: u32,
+    /// 相邻 tile 之间的间隔（像素）。
+    #[serde(default)]
+    pub spacing: u32,
+    /// 单个 tile 的附加数据（动画、碰撞），按 index 升序。
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tiles: Vec<TileMeta>,
+    /// 自动图块组（47 块 blob），按 `first` 升序。
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub autotiles: Vec<AutotileDef>,
+    /// Wang 集（地形过渡，与 Tiled wangsets 对应）。
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub wangsets: Vec<WangSet>,
+    /// 随机变体集（随机笔刷按权重挑选的一组 tile）。
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub variations: Vec<VariationSet>,
+}
+
+impl TilesetEntry {
+    /// 图集切分规则；`grid_tile` 为地图网格尺寸，tile 尺寸未设置时使用。
+    pub fn grid(&self, grid_tile: (u32, u32)) -> TileGrid {
+        let or_grid = |v: u32, d: u32| if v == 0 { d } else { v };
+        TileGrid {
+            tile_width: or_grid(self.tile_width, grid_tile.0),
+            tile_height: or_grid(self.tile_height, grid_tile.1),
+            margin: self.margin,
+            spacing: self.spacing,
+        }
+    }
+
+    /// 写入切分规则；tile 尺寸与地图网格相同时记为 0（跟随网格）。
+    pub fn set_grid(&mut self, grid: TileGrid, grid_tile: (u32, u32)) {
+        let or_zero = |v: u32, d: u32| if v == d { 0 } else { v };
+        self.tile_width = or_zero(grid.tile_width, grid_tile.0);
+        self.tile_height = or_zero(grid.tile_height, grid_tile.1);
+        self.margin = grid.margin;
+        self.spacing = grid.spacing;
+    }
+
+    pub fn tile_meta(&self, index: u32) -> Option<&TileMeta> {
+        self.tiles.iter().find(|t| t.index == index)
+    }
+
+    /// 第 `index` 个 tile 的动画帧；静态 tile 返回空切片。
+    pub fn animation(&self, index: u32) -> &[AnimationFrame] {
+        self.tile_meta(index).map(|t| t.animation.as_slice()).unwrap_or(&[])
+    }
+
+    /// 设置（`frames` 为空时删除）第 `index` 个 tile 的动画。
+    pub fn set_animation(&mut self, index: u32, frames: Vec<AnimationFrame>) {
+        self.tile_meta_mut(index).animation = frames;
+        self.tiles.retain(|t| !t.is_empty());
+    }
+
+    /// 第 `index` 个 tile 的碰撞形状；无碰撞返回空切片。
+    pub fn collision(&self, index: u32) -> &[CollisionShape] {
+        self.tile_meta(index).map(|t| t.collision.as_slice()).unwrap_or(&[])
+    }
+
+    /// 设置（`shapes` 为空时删除）第 `index` 个 tile 的碰撞形状。
+    pub fn set_collision(&mut self, index: u32, shapes: Vec<CollisionShape>) {
+        self.tile_meta_mut(index).collision = shapes;
+        self.tiles.retain(|t| !t.is_empty());
+    }
+
+    /// 包含第 `index` 个 tile 的自动图块组。
+    pub fn autotile_at(&self, index: u32) -> Option<&AutotileDef> {
+        self.autotiles.iter().find(|d| d.contains(index))
+    }
+
+    /// 新增（已有同一 `first` 时改名）自动图块组。
+    pub fn set_autotile(&mut self, def: AutotileDef) {
+        match self.autotiles.binary_search_by_key(&def.first, |d| d.first) {
+            Ok(i) => self.autotiles[i] = def,
+            Err(i) => self.autotiles.insert(i, def),
+        }
+    }
+
+    /// 取（不存在时按 index 顺序插入）第 `index` 个 tile 的附加数据。
+    fn tile_meta_mut(&mut self, index: u32) -> &mut TileMeta {
+        let i = match self.tiles.binary_search_by_key(&index, |t| t.index) {
+            Ok(i) => i,
+            Err(i) => {
+                self.tiles.insert(i, TileMeta { index, ..Default::default() });
+                i
+            }
+        };
+        &mut self.tiles[i]
+    }
+}
+
+/// 固定尺寸地图的格子总数上限（layers * width * height），防止损坏的文件头触发超大分配。
+const MAX_DENSE_CELLS: u64 = 1 << 28;
+
+/// 按文件头创建空地图；尺寸离谱时报错而不是直接分配。
+fn new_map(width: u32, height: u32, layers: u32, infinite: bool) -> Result<TileMapData, String> {
+    let layers = layers.max(1);
+    if infinite {
+        return Ok(TileMapData::new_infinite(layers));
+    }
+    let cells = (width as u64).checked_mul(height as u64).and_then(|n| n.checked_mul(layers as u64));
+    if cells.is_none_or(|n| n > MAX_DENSE_CELLS) {
+        return Err(format!("地图尺寸过大：{width}x{height}，{layers} 层"));
+    }
+    Ok(TileMapData::new_with_layers(width, height, layers))
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 struct TileEntry {
-    x: u32,
-    y: u32,
+    /// 无限地图允许负坐标。
+    x: i32,
+    y: i32,
     layer: u32,
     /// tileset 在 tileset_ids 中的索引
     tileset: u32,
@@ -28,12 +165,18 @@ struct MapFileV4<TTileset> {
     layers: u32,
     #[serde(default)]
     layer_data: Vec<LayerData>,
+    /// 无限地图：width/height 无意义，tiles 坐标可为负。
+    #[serde(default)]
+    infinite: bool,
     /// 地图所需 tileset 列表（用于跨机器拷贝后自动回显渲染）
     tilesets: Vec<TTileset>,
     /// tileset 的 id 列表，与 tilesets 同序
     tileset_ids: Vec<String>,
     /// 稀疏存储，仅保存已绘制的 tile
     tiles: Vec<TileEntry>,
+    /// 区域 / 通行 / 地形标记（旧文件没有此字段）。
+    #[serde(default, skip_serializing_if = "MetaGrid::is_empty")]
+    meta: MetaGrid,
 }
 
 pub fn encode_map_ron_v3<TTileset: Serialize>(
@@ -50,19 +193,11 @@ pub fn encode_map_ron_v3<TTileset: Serialize>(
         id_to_index.insert(id.as_str(), i as u32);
     }
 
-    let layer_len = map.layer_len();
     let mut tiles = Vec::new();
-    for (i, tile_opt) in map.tiles.iter().enumerate() {
-        let Some(tile) = tile_opt.as_ref() else {
-            continue;
-        };
+    for (layer, x, y, tile) in map.iter_tiles() {
         let Some(tileset_index) = id_to_index.get(tile.tileset_id.as_str()) else {
             return Err(format!("tileset_id 不在 tileset_ids 列表中: {}", tile.tileset_id));
         };
-        let layer = (i / layer_len) as u32;
-        let rem = (i % layer_len) as u32;
-        let x = rem % map.width;
-        let y = rem / map.width;
         tiles.push(TileEntry {
             x,
             y,
@@ -80,9 +215,11 @@ pub fn encode_map_ron_v3<TTileset: Serialize>(
         height: map.height,
         layers: map.layers.max(1),
         layer_data: map.layer_data.clone(),
+        infinite: map.infinite,
         tilesets,
         tileset_ids,
         tiles,
+        meta: map.meta.clone(),
     };
 
     ron::ser::to_string_pretty(&v4, ron::ser::PrettyConfig::default()).map_err(|e| e.to_string())
@@ -93,7 +230,7 @@ where
     for<'de> TTileset: Deserialize<'de>,
 {
     let v4 = ron::from_str::<MapFileV4<TTileset>>(text).map_err(|e| e.to_string())?;
-    let mut map = TileMapData::new_with_layers(v4.width, v4.height, v4.layers.max(1));
+    let mut map = new_map(v4.width, v4.height, v4.layers, v4.infinite)?;
 
     if !v4.layer_data.is_empty() {
         map.layer_data = v4.layer_data;
@@ -103,27 +240,171 @@ where
                     name: format!("Layer {}", i + 1),
                     visible: true,
                     locked: false,
+                    opacity: 1.0,
                 });
             }
         }
     }
 
     for tile in v4.tiles {
-        if tile.layer >= map.layers || tile.x >= map.width || tile.y >= map.height {
+        if tile.layer >= map.layers || !map.contains(tile.x, tile.y) {
             continue;
         }
         let Some(tileset_id) = v4.tileset_ids.get(tile.tileset as usize) else {
             continue;
         };
-        let idx = map.idx_layer(tile.layer, tile.x, tile.y);
-        map.tiles[idx] = Some(TileRef {
-            tileset_id: tileset_id.clone(),
-            index: tile.index,
-            rot: tile.rot,
-            flip_x: tile.flip_x,
-            flip_y: tile.flip_y,
-        });
+        map.set(
+            tile.layer,
+            tile.x,
+            tile.y,
+            Some(TileRef {
+                tileset_id: tileset_id.clone(),
+                index: tile.index,
+                rot: tile.rot,
+                flip_x: tile.flip_x,
+                flip_y: tile.flip_y,
+            }),
+        );
+    }
+
+    for (x, y, meta) in v4.meta.iter() {
+        if map.contains(x, y) {
+            map.meta.set(x, y, meta);
+        }
     }
 
     Ok((map, v4.tilesets))
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use tilemap_core::{
+        CellMeta, Orientation, Variation, WangColor, WangKind, WangTile, PASS_BLOCK_LEFT, PASS_BLOCK_UP,
+    };
+
+    pub(crate) fn entry(id: &str) -> TilesetEntry {
+        TilesetEntry {
+            id: id.to_string(),
+            name: format!("{id} name"),
+            category: "terrain".to_string(),
+            asset_path: format!("tilesets/{id}.png"),
+            ..Default::default()
+        }
+    }
+
+    /// "b" 为 16px、带 margin / spacing、1 号 tile 有动画和碰撞、带自动图块组、Wang 集与变体集的图集，验证 tileset 数据往返无损。
+    pub(crate) fn entries() -> Vec<TilesetEntry> {
+        let mut b = entry("b");
+        b.tile_width = 16;
+        b.tile_height = 16;
+        b.margin = 1;
+        b.spacing = 2;
+        b.set_animation(
+            1,
+            vec![
+                AnimationFrame { index: 1, duration_ms: 120 },
+                AnimationFrame { index: 3, duration_ms: 80 },
+            ],
+        );
+        b.set_collision(
+            1,
+            vec![
+                CollisionShape::Full,
+                CollisionShape::polygon(vec![[0.0, 0.0], [1.0, 0.0], [1.0, 0.5]]),
+            ],
+        );
+        b.set_collision(2, vec![CollisionShape::polygon(vec![[0.0, 0.0], [0.25, 0.0], [0.25, 0.75]])]);
+        b.set_autotile(AutotileDef { name: "草地; 边缘".to_string(), first: 48 });
+        b.wangsets.push(WangSet {
+            name: "地面".to_string(),
+            kind: WangKind::Mixed,
+            tile: Some(4),
+            colors: vec![
+                WangColor { name: "草".to_string(), color: "#00ff00".to_string(), tile: None, probability: 0.5 },
+                WangColor { name: "沙".to_string(), color: "#ffcc00".to_string(), tile: Some(5), probability: 1.0 },
+            ],
+            tiles: vec![
+                WangTile { index: 4, wang_id: [1; 8] },
+                WangTile { index: 5, wang_id: [2, 2, 1, 1, 1, 2, 2, 2] },
+            ],
+        });
+        b.variations.push(VariationSet {
+            name: "草丛".to_string(),
+            tiles: vec![Variation { index: 6, weight: 3.0 }, Variation { index: 7, weight: 0.5 }],
+            random_rotation: true,
+            random_flip: false,
+        });
+        vec![entry("a"), b]
+    }
+
+    pub(crate) fn tile(id: &str, index: u32, orientation: Orientation) -> TileRef {
+        let mut t = TileRef {
+            tileset_id: id.to_string(),
+            index,
+            rot: 0,
+            flip_x: false,
+            flip_y: false,
+        };
+        t.set_orientation(orientation);
+        t
+    }
+
+    pub(crate) fn sample(infinite: bool) -> TileMapData {
+        let mut map = if infinite {
+            TileMapData::new_infinite(2)
+        } else {
+            TileMapData::new_with_layers(5, 3, 2)
+        };
+        map.layer_data[1].name = "top".to_string();
+        map.layer_data[1].locked = true;
+        map.layer_data[1].opacity = 0.5;
+        map.set(0, 0, 0, Some(tile("a", 0, Orientation::IDENTITY)));
+        map.set(0, 4, 2, Some(tile("a", 5, Orientation::ROT_CW)));
+        map.set(1, 2, 1, Some(tile("b", 1, Orientation::ANTI_TRANSPOSE)));
+        if infinite {
+            map.set(1, -17, -40, Some(tile("b", 2, Orientation::FLIP_Y)));
+        }
+        map.meta.set(1, 2, CellMeta { region: 3, passage: PASS_BLOCK_UP | PASS_BLOCK_LEFT, terrain: 0 });
+        map.meta.set(4, 0, CellMeta { region: 0, passage: 0, terrain: 255 });
+        map
+    }
+
+    pub(crate) fn cells(map: &TileMapData) -> Vec<(u32, i32, i32, Option<TileRef>)> {
+        let mut v: Vec<_> = map.iter_cells().map(|(l, x, y, c)| (l, x, y, map.unpack(c))).collect();
+        v.sort_by_key(|(l, x, y, _)| (*l, *x, *y));
+        v
+    }
+
+    pub(crate) fn assert_same(a: &TileMapData, b: &TileMapData) {
+        assert_eq!(a.fixed_size(), b.fixed_size());
+        assert_eq!(a.layers, b.layers);
+        assert_eq!(b.layer_data[1].name, "top");
+        assert!(b.layer_data[1].locked);
+        assert_eq!(b.layer_data[1].opacity, 0.5);
+        assert_eq!(b.layer_data[0].opacity, 1.0);
+        assert_eq!(cells(a), cells(b));
+        assert_eq!(a.meta, b.meta);
+        assert_eq!(b.meta.len(), 2);
+    }
+
+    #[test]
+    fn ron_round_trip_keeps_cell_meta() {
+        for infinite in [false, true] {
+            let map = sample(infinite);
+            let ids = entries().into_iter().map(|t| t.id).collect();
+            let text = encode_map_ron_v3(&map, entries(), ids).unwrap();
+            let (back, _) = decode_map_ron::<TilesetEntry>(&text).unwrap();
+            assert_same(&map, &back);
+        }
+    }
+
+    #[test]
+    fn ron_rejects_oversized_dense_map() {
+        let text = "(width: 100000, height: 100000, layers: 4, tilesets: [], tileset_ids: [], tiles: [])";
+        assert!(decode_map_ron::<TilesetEntry>(text).unwrap_err().contains("地图尺寸过大"));
+        // 无限地图不受尺寸上限影响。
+        let text = "(width: 100000, height: 100000, layers: 4, infinite: true, tilesets: [], tileset_ids: [], tiles: [])";
+        assert!(decode_map_ron::<TilesetEntry>(text).unwrap().0.fixed_size().is_none());
+    }
+}
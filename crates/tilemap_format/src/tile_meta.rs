@@ -0,0 +1,66 @@
+//! tileset 中单个 tile 的附加数据（随 TilesetEntry 存进 tileset 库与地图文件）。
+
+use serde::{Deserialize, Serialize};
+use tilemap_core::CollisionShape;
+
+/// 动画的一帧：显示同一 tileset 中第 `index` 个 tile，持续 `duration_ms` 毫秒。
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AnimationFrame {
+    pub index: u32,
+    pub duration_ms: u32,
+}
+
+/// 按 tile index 记录的附加数据（动画、碰撞）；全部为空的条目不保存。
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct TileMeta {
+    pub index: u32,
+    /// 动画帧序列（与 Tiled 的 `animation` 一致）；为空表示静态 tile。
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub animation: Vec<AnimationFrame>,
+    /// 碰撞形状（tile 局部坐标，见 `CollisionShape`）；为空表示无碰撞。
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub collision: Vec<CollisionShape>,
+}
+
+impl TileMeta {
+    pub fn is_empty(&self) -> bool {
+        self.animation.is_empty() && self.collision.is_empty()
+    }
+}
+
+/// 动画播放到 `elapsed_ms` 时应显示的 tile index（循环播放）；没有有效帧时返回 None。
+pub fn animation_frame_at(frames: &[AnimationFrame], elapsed_ms: u64) -> Option<u32> {
+    let total: u64 = frames.iter().map(|f| f.duration_ms as u64).sum();
+    if total == 0 {
+        return frames.first().map(|f| f.index);
+    }
+    let mut t = elapsed_ms % total;
+    for frame in frames {
+        let d = frame.duration_ms as u64;
+        if t < d {
+            return Some(frame.index);
+        }
+        t -= d;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_loop_by_duration() {
+        let frames = [
+            AnimationFrame { index: 4, duration_ms: 100 },
+            AnimationFrame { index: 5, duration_ms: 0 },
+            AnimationFrame { index: 6, duration_ms: 50 },
+        ];
+        assert_eq!(animation_frame_at(&frames, 0), Some(4));
+        assert_eq!(animation_frame_at(&frames, 99), Some(4));
+        assert_eq!(animation_frame_at(&frames, 100), Some(6));
+        assert_eq!(animation_frame_at(&frames, 149), Some(6));
+        assert_eq!(animation_frame_at(&frames, 150), Some(4));
+        assert_eq!(animation_frame_at(&[], 10), None);
+    }
+}
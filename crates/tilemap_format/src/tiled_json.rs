@@ -0,0 +1,971 @@
+//! Tiled JSON（.tmj）子集：orthogonal 地图 + tilelayer + 内嵌/外部 tileset。
+//!
+//! 约定：
+//! - 地图格子 y 向上、Tiled 行号向下：固定尺寸地图 `row = height - 1 - y`，无限地图 `ty = -1 - y`。
+//! - 图块朝向直接对应 GID 高位（`Orientation::tiled_flags` / `Orientation::from_tiled_gid`）。
+//! - tileset 的 id / category / asset_path 写在 tileset 自定义属性里，读回时无损还原；
+//!   tile 尺寸 / margin / spacing 直接对应 Tiled 的同名字段，tile 动画对应 `tiles[].animation`。
+//! - tile 碰撞对应 `tiles[].objectgroup`：整格导出为覆盖整个 tile 的矩形，多边形导出为 polygon 对象
+//!   （像素坐标、y 向下）；导入时 ellipse / point 等其它对象忽略。
+//! - Wang 集直接对应 tileset 的 `wangsets`（corner / edge / mixed），外部 .tsj 里的 wangsets 可单独读取。
+//! - 自动图块组、随机变体集没有 Tiled 对应物，以 JSON 文本写在 tileset 属性 `autotiles` / `variations` 里。
+//! - 地图元数据（区域 / 通行 / 地形）写在地图属性 `cell_meta` 里：`x,y,region,passage,terrain` 以 `;` 分隔，
+//!   坐标沿用本工具的 y 向上坐标；Tiled 只原样保留，不做显示。
+//! - 导入只处理 tilelayer（object/image/group 层忽略），图层数据只支持 JSON 数组（CSV）编码。
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use tilemap_core::{
+    CellMeta, CollisionShape, LayerData, MetaField, MetaGrid, Orientation, TileBounds, TileGrid, TileMapData, TileRef,
+    WangColor, WangKind, WangSet, WangTile,
+};
+
+use crate::{new_map, AnimationFrame, TilesetEntry};
+
+/// 无限地图导出的 chunk 边长（与 Tiled 默认一致）。
+const CHUNK: i32 = 16;
+
+/// GID 中除 tile 编号外的标志位（含六边形地图专用的 0x1000_0000）。
+const GID_FLAGS: u32 = 0xF000_0000;
+
+const PROP_ID: &str = "tileset_id";
+const PROP_CATEGORY: &str = "category";
+const PROP_ASSET_PATH: &str = "asset_path";
+const PROP_CELL_META: &str = "cell_meta";
+/// 自动图块组（JSON 数组，Tiled 中只作为自定义属性保留）。
+const PROP_AUTOTILES: &str = "autotiles";
+/// 随机变体集（JSON 数组，同样只作为自定义属性保留）。
+const PROP_VARIATIONS: &str = "variations";
+
+/// 导出 tileset 所需的图片信息（地图本身不记录 tile 尺寸与图集大小）。
+#[derive(Clone, Debug)]
+pub struct TiledTilesetInfo {
+    pub entry: TilesetEntry,
+    /// 写入 JSON 的图片路径（Tiled 相对地图文件解析）。
+    pub image: String,
+    /// 图片像素尺寸；未知时按地图中用到的最大 index 推一个单行图集。
+    pub image_size: Option<(u32, u32)>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TiledMap {
+    #[serde(rename = "type", default)]
+    kind: String,
+    #[serde(default)]
+    version: String,
+    #[serde(default = "default_orientation")]
+    orientation: String,
+    #[serde(default)]
+    renderorder: String,
+    width: u32,
+    height: u32,
+    tilewidth: u32,
+    tileheight: u32,
+    #[serde(default)]
+    infinite: bool,
+    #[serde(default)]
+    nextlayerid: u32,
+    #[serde(default)]
+    nextobjectid: u32,
+    layers: Vec<TiledLayer>,
+    #[serde(default)]
+    tilesets: Vec<TiledTileset>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    properties: Vec<TiledProperty>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TiledLayer {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    id: u32,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    x: i32,
+    #[serde(default)]
+    y: i32,
+    #[serde(default)]
+    width: u32,
+    #[serde(default)]
+    height: u32,
+    #[serde(default = "default_opacity")]
+    opacity: f32,
+    #[serde(default = "default_visible")]
+    visible: bool,
+    #[serde(default)]
+    locked: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    encoding: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    data: Option<TiledData>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    chunks: Option<Vec<TiledChunk>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    startx: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    starty: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TiledChunk {
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    data: TiledData,
+}
+
+/// 图层数据：数组（CSV）或 base64 字符串（后者不支持导入）。
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum TiledData {
+    Gids(Vec<u32>),
+    Encoded(String),
+}
+
+#[derive(Serialize, Deserialize)]
+struct TiledTileset {
+    firstgid: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    source: Option<String>,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    image: String,
+    #[serde(default)]
+    imagewidth: u32,
+    #[serde(default)]
+    imageheight: u32,
+    #[serde(default)]
+    tilewidth: u32,
+    #[serde(default)]
+    tileheight: u32,
+    #[serde(default)]
+    columns: u32,
+    #[serde(default)]
+    tilecount: u32,
+    #[serde(default)]
+    margin: u32,
+    #[serde(default)]
+    spacing: u32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    properties: Vec<TiledProperty>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tiles: Vec<TiledTile>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    wangsets: Vec<TiledWangSet>,
+}
+
+/// 外部 tileset 文件（.tsj）中本工具关心的部分。
+#[derive(Deserialize)]
+struct TiledTilesetFile {
+    #[serde(default)]
+    wangsets: Vec<TiledWangSet>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TiledWangSet {
+    name: String,
+    #[serde(rename = "type", default = "default_wang_kind")]
+    kind: String,
+    #[serde(default = "no_tile")]
+    tile: i64,
+    #[serde(default)]
+    colors: Vec<TiledWangColor>,
+    #[serde(default)]
+    wangtiles: Vec<TiledWangTile>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TiledWangColor {
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    color: String,
+    #[serde(default = "no_tile")]
+    tile: i64,
+    #[serde(default = "default_probability")]
+    probability: f32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TiledWangTile {
+    tileid: u32,
+    wangid: Vec<u8>,
+}
+
+/// tileset 中单个 tile 的数据（只处理 animation / objectgroup，其它字段忽略）。
+#[derive(Serialize, Deserialize)]
+struct TiledTile {
+    id: u32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    animation: Vec<TiledFrame>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    objectgroup: Option<TiledObjectGroup>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TiledObjectGroup {
+    #[serde(rename = "type", default = "objectgroup_kind")]
+    kind: String,
+    #[serde(default = "default_draworder")]
+    draworder: String,
+    #[serde(default)]
+    objects: Vec<TiledObject>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TiledObject {
+    #[serde(default)]
+    id: u32,
+    #[serde(default)]
+    x: f32,
+    #[serde(default)]
+    y: f32,
+    #[serde(default)]
+    width: f32,
+    #[serde(default)]
+    height: f32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    polygon: Option<Vec<TiledPoint>>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    ellipse: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    point: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    polyline: Option<Value>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TiledPoint {
+    x: f32,
+    y: f32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TiledFrame {
+    tileid: u32,
+    duration: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TiledProperty {
+    name: String,
+    #[serde(rename = "type", default)]
+    kind: String,
+    value: Value,
+}
+
+fn default_wang_kind() -> String {
+    WangKind::Corner.tiled_name().to_string()
+}
+
+/// Tiled 用 -1 表示“未指定 tile”。
+fn no_tile() -> i64 {
+    -1
+}
+
+fn default_probability() -> f32 {
+    1.0
+}
+
+fn default_orientation() -> String {
+    "orthogonal".to_string()
+}
+
+fn objectgroup_kind() -> String {
+    "objectgroup".to_string()
+}
+
+fn default_draworder() -> String {
+    "index".to_string()
+}
+
+/// 碰撞形状 -> Tiled 对象（tile 内像素坐标，y 向下）。
+fn collision_to_tiled(shapes: &[CollisionShape], tw: u32, th: u32) -> Option<TiledObjectGroup> {
+    if shapes.is_empty() {
+        return None;
+    }
+    let (tw, th) = (tw as f32, th as f32);
+    let objects = shapes
+        .iter()
+        .enumerate()
+        .map(|(i, shape)| {
+            let mut object = TiledObject {
+                id: i as u32 + 1,
+                x: 0.0,
+                y: 0.0,
+                width: 0.0,
+                height: 0.0,
+                polygon: None,
+                ellipse: false,
+                point: false,
+                polyline: None,
+            };
+            match shape {
+                CollisionShape::Full => {
+                    object.width = tw;
+                    object.height = th;
+                }
+                CollisionShape::Polygon(points) => {
+                    object.polygon = Some(
+                        points
+                            .iter()
+                            .map(|&[x, y]| TiledPoint { x: x * tw, y: (1.0 - y) * th })
+                            .collect(),
+                    );
+                }
+            }
+            object
+        })
+        .collect();
+    Some(TiledObjectGroup {
+        kind: objectgroup_kind(),
+        draworder: default_draworder(),
+        objects,
+    })
+}
+
+/// Tiled 对象 -> 碰撞形状；覆盖整个 tile 的矩形还原为 `Full`。
+fn collision_from_tiled(group: &TiledObjectGroup, tw: u32, th: u32) -> Vec<CollisionShape> {
+    if tw == 0 || th == 0 {
+        return Vec::new();
+    }
+    let (tw, th) = (tw as f32, th as f32);
+    let norm = |x: f32, y: f32| [x / tw, 1.0 - y / th];
+    let mut shapes = Vec::new();
+    for o in &group.objects {
+        if o.ellipse || o.point || o.polyline.is_some() {
+            continue;
+        }
+        let shape = match &o.polygon {
+            Some(points) => {
+                CollisionShape::polygon(points.iter().map(|p| norm(o.x + p.x, o.y + p.y)).collect())
+            }
+            None if o.x == 0.0 && o.y == 0.0 && o.width == tw && o.height == th => CollisionShape::Full,
+            None => CollisionShape::polygon(vec![
+                norm(o.x, o.y + o.height),
+                norm(o.x + o.width, o.y + o.height),
+                norm(o.x + o.width, o.y),
+                norm(o.x, o.y),
+            ]),
+        };
+        if !shape.is_degenerate() {
+            shapes.push(shape);
+        }
+    }
+    shapes
+}
+
+fn wangsets_to_tiled(sets: &[WangSet]) -> Vec<TiledWangSet> {
+    let tile = |t: Option<u32>| t.map_or(-1, i64::from);
+    sets.iter()
+        .map(|set| TiledWangSet {
+            name: set.name.clone(),
+            kind: set.kind.tiled_name().to_string(),
+            tile: tile(set.tile),
+            colors: set
+                .colors
+                .iter()
+                .map(|c| TiledWangColor {
+                    name: c.name.clone(),
+                    color: c.color.clone(),
+                    tile: tile(c.tile),
+                    probability: c.probability,
+                })
+                .collect(),
+            wangtiles: set
+                .tiles
+                .iter()
+                .map(|t| TiledWangTile {
+                    tileid: t.index,
+                    wangid: t.wang_id.to_vec(),
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+fn wangsets_from_tiled(sets: &[TiledWangSet]) -> Result<Vec<WangSet>, String> {
+    let tile = |t: i64| u32::try_from(t).ok();
+    sets.iter()
+        .map(|set| {
+            let Some(kind) = WangKind::from_tiled_name(&set.kind) else {
+                return Err(format!("Wang 集 {} 的类型未知: {}", set.name, set.kind));
+            };
+            let tiles = set
+                .wangtiles
+                .iter()
+                .map(|t| {
+                    let wang_id: [u8; 8] = t.wangid.as_slice().try_into().map_err(|_| {
+                        format!("Wang 集 {} 中 tile {} 的 wangid 应有 8 个值", set.name, t.tileid)
+                    })?;
+                    Ok(WangTile { index: t.tileid, wang_id })
+                })
+                .collect::<Result<_, String>>()?;
+            Ok(WangSet {
+                name: set.name.clone(),
+                kind,
+                tile: tile(set.tile),
+                colors: set
+                    .colors
+                    .iter()
+                    .map(|c| WangColor {
+                        name: c.name.clone(),
+                        color: c.color.clone(),
+                        tile: tile(c.tile),
+                        probability: c.probability,
+                    })
+                    .collect(),
+                tiles,
+            })
+        })
+        .collect()
+}
+
+/// 读取 Tiled tileset 文件（.tsj / .json）中的 Wang 集。
+pub fn decode_tiled_tileset_wangsets(text: &str) -> Result<Vec<WangSet>, String> {
+    let file: TiledTilesetFile = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    wangsets_from_tiled(&file.wangsets)
+}
+
+fn default_opacity() -> f32 {
+    1.0
+}
+
+fn default_visible() -> bool {
+    true
+}
+
+fn string_prop(name: &str, value: &str) -> TiledProperty {
+    TiledProperty {
+        name: name.to_string(),
+        kind: "string".to_string(),
+        value: Value::String(value.to_string()),
+    }
+}
+
+fn tileset_props(entry: &TilesetEntry) -> Result<Vec<TiledProperty>, String> {
+    let mut props = vec![
+        string_prop(PROP_ID, &entry.id),
+        string_prop(PROP_CATEGORY, &entry.category),
+        string_prop(PROP_ASSET_PATH, &entry.asset_path),
+    ];
+    if !entry.autotiles.is_empty() {
+        let text = serde_json::to_string(&entry.autotiles).map_err(|e| e.to_string())?;
+        props.push(string_prop(PROP_AUTOTILES, &text));
+    }
+    if !entry.variations.is_empty() {
+        let text = serde_json::to_string(&entry.variations).map_err(|e| e.to_string())?;
+        props.push(string_prop(PROP_VARIATIONS, &text));
+    }
+    Ok(props)
+}
+
+fn find_prop<'a>(props: &'a [TiledProperty], name: &str) -> Option<&'a str> {
+    props
+        .iter()
+        .find(|p| p.name == name)
+        .and_then(|p| p.value.as_str())
+        .filter(|v| !v.is_empty())
+}
+
+/// 导出为 Tiled JSON。`tilesets` 的顺序决定 firstgid 的分配顺序。
+pub fn encode_map_tiled_json(
+    map: &TileMapData,
+    tile_size: (u32, u32),
+    tilesets: &[TiledTilesetInfo],
+) -> Result<String, String> {
+    let (tw, th) = tile_size;
+    if tw == 0 || th == 0 {
+        return Err("tile 尺寸不能为 0".to_string());
+    }
+
+    let mut max_index: HashMap<&str, u32> = HashMap::new();
+    for (_, _, _, tile) in map.iter_tiles() {
+        let m = max_index.entry(tile.tileset_id.as_str()).or_insert(0);
+        *m = (*m).max(tile.index);
+    }
+
+    let mut out_tilesets = Vec::with_capacity(tilesets.len());
+    // tileset_id -> (firstgid, tilecount)
+    let mut gid_base: HashMap<&str, (u32, u32)> = HashMap::new();
+    let mut firstgid = 1u32;
+    for info in tilesets {
+        let grid = info.entry.grid(tile_size);
+        if grid.tile_width == 0 || grid.tile_height == 0 {
+            return Err(format!("tileset {} 的 tile 尺寸不能为 0", info.entry.id));
+        }
+        let (columns, tilecount, (iw, ih)) = match info.image_size {
+            Some((iw, ih)) => (grid.columns(iw), grid.capacity(iw, ih), (iw, ih)),
+            None => {
+                let count = max_index.get(info.entry.id.as_str()).map_or(1, |m| m + 1);
+                let iw = 2 * grid.margin + count * grid.tile_width + (count - 1) * grid.spacing;
+                (count, count, (iw, 2 * grid.margin + grid.tile_height))
+            }
+        };
+        gid_base.insert(info.entry.id.as_str(), (firstgid, tilecount));
+        out_tilesets.push(TiledTileset {
+            firstgid,
+            source: None,
+            name: info.entry.name.clone(),
+            image: info.image.clone(),
+            imagewidth: iw,
+            imageheight: ih,
+            tilewidth: grid.tile_width,
+            tileheight: grid.tile_height,
+            columns,
+            tilecount,
+            margin: grid.margin,
+            spacing: grid.spacing,
+            properties: tileset_props(&info.entry)?,
+            tiles: info
+                .entry
+                .tiles
+                .iter()
+                .filter(|t| !t.is_empty())
+                .map(|t| TiledTile {
+                    id: t.index,
+                    animation: t
+                        .animation
+                        .iter()
+                        .map(|f| TiledFrame {
+                            tileid: f.index,
+                            duration: f.duration_ms,
+                        })
+                        .collect(),
+                    objectgroup: collision_to_tiled(&t.collision, grid.tile_width, grid.tile_height),
+                })
+                .collect(),
+            wangsets: wangsets_to_tiled(&info.entry.wangsets),
+        });
+        firstgid = firstgid
+            .checked_add(tilecount.max(1))
+            .ok_or_else(|| "tileset 总 tile 数超出 GID 范围".to_string())?;
+    }
+
+    let gid_of = |layer: u32, x: i32, y: i32| -> Result<u32, String> {
+        let Some(tile) = map.view(map.cell(layer, x, y)) else {
+            return Ok(0);
+        };
+        let Some(&(first, count)) = gid_base.get(tile.tileset_id.as_str()) else {
+            return Err(format!("tileset_id 不在 tileset 列表中: {}", tile.tileset_id));
+        };
+        if tile.index >= count {
+            return Err(format!(
+                "({x}, {y}) 层 {layer} 的 tile index {} 超出 tileset {} 的范围（共 {count} 个）",
+                tile.index, tile.tileset_id
+            ));
+        }
+        Ok((first + tile.index) | tile.orientation().tiled_flags())
+    };
+
+    let layer_count = map.layers.max(1);
+    let mut layers = Vec::with_capacity(layer_count as usize);
+    let (map_w, map_h) = match map.fixed_size() {
+        Some(size) => size,
+        None => map
+            .used_bounds()
+            .map_or((0, 0), |b| (b.width(), b.height())),
+    };
+    for layer in 0..layer_count {
+        let meta = map.layer_data.get(layer as usize).cloned().unwrap_or_default();
+        let mut tl = TiledLayer {
+            kind: "tilelayer".to_string(),
+            id: layer + 1,
+            name: meta.name,
+            x: 0,
+            y: 0,
+            width: map_w,
+            height: map_h,
+            opacity: meta.opacity,
+            visible: meta.visible,
+            locked: meta.locked,
+            encoding: None,
+            data: None,
+            chunks: None,
+            startx: None,
+            starty: None,
+        };
+        if map.infinite {
+            let (chunks, bounds) = encode_chunks(map, layer, &gid_of)?;
+            if let Some(b) = bounds {
+                tl.startx = Some(b.min_x);
+                tl.starty = Some(b.min_y);
+                tl.width = b.width();
+                tl.height = b.height();
+            }
+            tl.chunks = Some(chunks);
+        } else {
+            let mut data = Vec::with_capacity((map_w * map_h) as usize);
+            for row in 0..map_h as i32 {
+                let y = map_h as i32 - 1 - row;
+                for x in 0..map_w as i32 {
+                    data.push(gid_of(layer, x, y)?);
+                }
+            }
+            tl.data = Some(TiledData::Gids(data));
+        }
+        layers.push(tl);
+    }
+
+    let doc = TiledMap {
+        kind: "map".to_string(),
+        version: "1.10".to_string(),
+        orientation: default_orientation(),
+        renderorder: "right-down".to_string(),
+        width: map_w,
+        height: map_h,
+        tilewidth: tw,
+        tileheight: th,
+        infinite: map.infinite,
+        nextlayerid: layer_count + 1,
+        nextobjectid: 1,
+        layers,
+        tilesets: out_tilesets,
+        properties: if map.meta.is_empty() {
+            Vec::new()
+        } else {
+            vec![string_prop(PROP_CELL_META, &encode_cell_meta(&map.meta))]
+        },
+    };
+    serde_json::to_string_pretty(&doc).map_err(|e| e.to_string())
+}
+
+/// 无限地图的一层按 Tiled 坐标切成 16x16 chunk；返回 chunk 列表与（chunk 对齐的）Tiled 坐标包围盒。
+fn encode_chunks(
+    map: &TileMapData,
+    layer: u32,
+    gid_of: &dyn Fn(u32, i32, i32) -> Result<u32, String>,
+) -> Result<(Vec<TiledChunk>, Option<TileBounds>), String> {
+    let mut keys: Vec<(i32, i32)> = map
+        .iter_cells()
+        .filter(|&(l, _, _, _)| l == layer)
+        .map(|(_, x, y, _)| (x.div_euclid(CHUNK), (-1 - y).div_euclid(CHUNK)))
+        .collect();
+    keys.sort_unstable_by_key(|&(cx, cy)| (cy, cx));
+    keys.dedup();
+
+    let mut bounds: Option<TileBounds> = None;
+    let mut chunks = Vec::with_capacity(keys.len());
+    for (cx, cy) in keys {
+        let (ox, oy) = (cx * CHUNK, cy * CHUNK);
+        let mut data = Vec::with_capacity((CHUNK * CHUNK) as usize);
+        for ty in oy..oy + CHUNK {
+            for tx in ox..ox + CHUNK {
+                data.push(gid_of(layer, tx, -1 - ty)?);
+            }
+        }
+        let (ex, ey) = (ox + CHUNK - 1, oy + CHUNK - 1);
+        match bounds.as_mut() {
+            Some(b) => {
+                b.include(ox, oy);
+                b.include(ex, ey);
+            }
+            None => bounds = Some(TileBounds::from_corners(ox, oy, ex, ey)),
+        }
+        chunks.push(TiledChunk {
+            x: ox,
+            y: oy,
+            width: CHUNK as u32,
+            height: CHUNK as u32,
+            data: TiledData::Gids(data),
+        });
+    }
+    Ok((chunks, bounds))
+}
+
+/// 图层 / chunk 原点加上数据内的 (列, 行)；坐标超出 i32 时返回 None（损坏的文件）。
+fn offset_cell(x: i32, y: i32, col: usize, row: usize) -> Option<(i32, i32)> {
+    let col = i32::try_from(col).ok()?;
+    let row = i32::try_from(row).ok()?;
+    Some((x.checked_add(col)?, y.checked_add(row)?))
+}
+
+fn gids<'a>(data: &'a TiledData, what: &str) -> Result<&'a [u32], String> {
+    match data {
+        TiledData::Gids(v) => Ok(v),
+        TiledData::Encoded(_) => Err(format!(
+            "{what} 使用了 base64/压缩编码，暂不支持；请在 Tiled 中把图层格式改为 CSV"
+        )),
+    }
+}
+
+/// 从 Tiled JSON 导入，返回地图与 tileset 列表（与 `decode_map_ron` 一致）。
+pub fn decode_map_tiled_json(text: &str) -> Result<(TileMapData, Vec<TilesetEntry>), String> {
+    let doc: TiledMap = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    if doc.orientation != "orthogonal" {
+        return Err(format!("只支持 orthogonal 地图，实际为 {}", doc.orientation));
+    }
+
+    let mut tilesets: Vec<&TiledTileset> = doc.tilesets.iter().collect();
+    tilesets.sort_by_key(|t| t.firstgid);
+    let entries: Vec<TilesetEntry> = tilesets
+        .iter()
+        .map(|t| -> Result<TilesetEntry, String> {
+            let fallback = t.source.as_deref().unwrap_or(t.image.as_str());
+            let name = if t.name.is_empty() { fallback } else { t.name.as_str() };
+            let mut entry = TilesetEntry {
+                id: find_prop(&t.properties, PROP_ID).unwrap_or(name).to_string(),
+                name: name.to_string(),
+                category: find_prop(&t.properties, PROP_CATEGORY)
+                    .unwrap_or("default")
+                    .to_string(),
+                asset_path: find_prop(&t.properties, PROP_ASSET_PATH)
+                    .unwrap_or(fallback)
+                    .to_string(),
+                ..Default::default()
+            };
+            let grid = TileGrid {
+                tile_width: t.tilewidth,
+                tile_height: t.tileheight,
+                margin: t.margin,
+                spacing: t.spacing,
+            };
+            entry.set_grid(grid, (doc.tilewidth, doc.tileheight));
+            for tile in &t.tiles {
+                let animation: Vec<AnimationFrame> = tile
+                    .animation
+                    .iter()
+                    .map(|f| AnimationFrame {
+                        index: f.tileid,
+                        duration_ms: f.duration,
+                    })
+                    .collect();
+                entry.set_animation(tile.id, animation);
+                if let Some(group) = &tile.objectgroup {
+                    entry.set_collision(tile.id, collision_from_tiled(group, grid.tile_width, grid.tile_height));
+                }
+            }
+            if let Some(text) = find_prop(&t.properties, PROP_AUTOTILES) {
+                entry.autotiles = serde_json::from_str(text)
+                    .map_err(|e| format!("tileset {} 的属性 {PROP_AUTOTILES} 无法解析: {e}", entry.name))?;
+            }
+            if let Some(text) = find_prop(&t.properties, PROP_VARIATIONS) {
+                entry.variations = serde_json::from_str(text)
+                    .map_err(|e| format!("tileset {} 的属性 {PROP_VARIATIONS} 无法解析: {e}", entry.name))?;
+            }
+            entry.wangsets = wangsets_from_tiled(&t.wangsets)?;
+            Ok(entry)
+        })
+        .collect::<Result<_, _>>()?;
+
+    let tile_of = |gid: u32| -> Result<Option<TileRef>, String> {
+        let raw = gid & !GID_FLAGS;
+        if raw == 0 {
+            return Ok(None);
+        }
+        let Some(i) = tilesets.iter().rposition(|t| t.firstgid <= raw) else {
+            return Err(format!("GID {raw} 不属于任何 tileset"));
+        };
+        let mut tile = TileRef {
+            tileset_id: entries[i].id.clone(),
+            index: raw - tilesets[i].firstgid,
+            rot: 0,
+            flip_x: false,
+            flip_y: false,
+        };
+        tile.set_orientation(Orientation::from_tiled_gid(gid));
+        Ok(Some(tile))
+    };
+
+    let tile_layers: Vec<&TiledLayer> = doc.layers.iter().filter(|l| l.kind == "tilelayer").collect();
+    let layer_count = (tile_layers.len() as u32).max(1);
+    let mut map = new_map(doc.width, doc.height, layer_count, doc.infinite)?;
+    for (layer, tl) in tile_layers.iter().enumerate() {
+        let layer = layer as u32;
+        map.layer_data[layer as usize] = LayerData {
+            name: tl.name.clone(),
+            visible: tl.visible,
+            locked: tl.locked,
+            opacity: tl.opacity.clamp(0.0, 1.0),
+        };
+        if let Some(chunks) = &tl.chunks {
+            for chunk in chunks {
+                let data = gids(&chunk.data, &format!("图层 {} 的 chunk", tl.name))?;
+                if data.len() as u64 != chunk.width as u64 * chunk.height as u64 {
+                    return Err(format!(
+                        "图层 {} 的 chunk ({}, {}) 数据长度 {} 与尺寸 {}x{} 不符",
+                        tl.name,
+                        chunk.x,
+                        chunk.y,
+                        data.len(),
+                        chunk.width,
+                        chunk.height
+                    ));
+                }
+                let cw = chunk.width.max(1) as usize;
+                for (i, &gid) in data.iter().enumerate() {
+                    let Some(tile) = tile_of(gid)? else {
+                        continue;
+                    };
+                    let (tx, ty) = offset_cell(chunk.x, chunk.y, i % cw, i / cw)
+                        .ok_or_else(|| format!("图层 {} 的 chunk ({}, {}) 坐标越界", tl.name, chunk.x, chunk.y))?;
+                    map.set(layer, tx, -1 - ty, Some(tile));
+                }
+            }
+            continue;
+        }
+        if let Some(enc) = &tl.encoding
+            && enc != "csv"
+        {
+            return Err(format!("图层 {} 使用了 {enc} 编码，暂不支持；请改为 CSV", tl.name));
+        }
+        let Some(data) = &tl.data else {
+            continue;
+        };
+        let data = gids(data, &format!("图层 {}", tl.name))?;
+        let lw = if tl.width == 0 { doc.width } else { tl.width };
+        let lh = if tl.height == 0 { doc.height } else { tl.height };
+        if data.len() != (lw as usize) * (lh as usize) {
+            return Err(format!(
+                "图层 {} 的数据长度 {} 与尺寸 {lw}x{lh} 不符",
+                tl.name,
+                data.len()
+            ));
+        }
+        let lw = lw.max(1) as usize;
+        for (i, &gid) in data.iter().enumerate() {
+            let Some(tile) = tile_of(gid)? else {
+                continue;
+            };
+            let (col, row) = offset_cell(tl.x, tl.y, i % lw, i / lw)
+                .ok_or_else(|| format!("图层 {} 的偏移 ({}, {}) 坐标越界", tl.name, tl.x, tl.y))?;
+            let top = if doc.infinite { -1 } else { (doc.height as i32).saturating_sub(1) };
+            let Some(y) = top.checked_sub(row) else {
+                return Err(format!("图层 {} 的偏移 ({}, {}) 坐标越界", tl.name, tl.x, tl.y));
+            };
+            map.set(layer, col, y, Some(tile));
+        }
+    }
+    if let Some(text) = find_prop(&doc.properties, PROP_CELL_META) {
+        decode_cell_meta(text, &mut map)?;
+    }
+
+    Ok((map, entries))
+}
+
+fn encode_cell_meta(grid: &MetaGrid) -> String {
+    grid.iter()
+        .map(|(x, y, m)| format!("{x},{y},{},{},{}", m.region, m.passage, m.terrain))
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+fn decode_cell_meta(text: &str, map: &mut TileMapData) -> Result<(), String> {
+    for item in text.split(';').map(str::trim).filter(|s| !s.is_empty()) {
+        let bad = || format!("地图属性 {PROP_CELL_META} 中的记录无法解析: {item}");
+        let parts: Vec<&str> = item.split(',').map(str::trim).collect();
+        let [x, y, region, passage, terrain] = parts[..] else {
+            return Err(bad());
+        };
+        let x: i32 = x.parse().map_err(|_| bad())?;
+        let y: i32 = y.parse().map_err(|_| bad())?;
+        let meta = CellMeta::default()
+            .with(MetaField::Region, region.parse().map_err(|_| bad())?)
+            .with(MetaField::Passage, passage.parse().map_err(|_| bad())?)
+            .with(MetaField::Terrain, terrain.parse().map_err(|_| bad())?);
+        if map.contains(x, y) {
+            map.meta.set(x, y, meta);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tilemap_core::TILED_FLIP_H;
+
+    use crate::tests::{assert_same, entries, entry, sample, tile};
+    use crate::{decode_map_tiled_json, decode_tiled_tileset_wangsets, encode_map_tiled_json};
+
+    #[test]
+    fn tiled_json_round_trip() {
+        let tilesets: Vec<TiledTilesetInfo> = entries()
+            .into_iter()
+            .map(|entry| TiledTilesetInfo {
+                image: format!("../tilesets/{}.png", entry.id),
+                entry,
+                image_size: Some((128, 64)),
+            })
+            .collect();
+        for infinite in [false, true] {
+            let map = sample(infinite);
+            let text = encode_map_tiled_json(&map, (32, 32), &tilesets).unwrap();
+            let (back, back_tilesets) = decode_map_tiled_json(&text).unwrap();
+            assert_same(&map, &back);
+            assert_eq!(back_tilesets, entries());
+        }
+
+        // index 超出图集范围（128x64 / 32 = 8 个）时拒绝导出，避免 GID 串到下一个 tileset。
+        let mut map = TileMapData::new_with_layers(1, 1, 1);
+        map.set(0, 0, 0, Some(tile("a", 8, Orientation::IDENTITY)));
+        assert!(encode_map_tiled_json(&map, (32, 32), &tilesets).is_err());
+    }
+
+    #[test]
+    fn tiled_json_rows_are_top_down() {
+        let mut map = TileMapData::new_with_layers(2, 2, 1);
+        map.set(0, 1, 0, Some(tile("a", 0, Orientation::FLIP_X)));
+        let info = TiledTilesetInfo {
+            entry: entry("a"),
+            image: "a.png".to_string(),
+            image_size: None,
+        };
+        let text = encode_map_tiled_json(&map, (16, 16), &[info]).unwrap();
+        let doc: serde_json::Value = serde_json::from_str(&text).unwrap();
+        let data = doc["layers"][0]["data"].as_array().unwrap();
+        let data: Vec<u64> = data.iter().map(|v| v.as_u64().unwrap()).collect();
+        assert_eq!(data, vec![0, 0, 0, (1 | TILED_FLIP_H) as u64]);
+    }
+
+    #[test]
+    fn tiled_json_rejects_broken_chunks() {
+        let info = TiledTilesetInfo {
+            entry: entry("a"),
+            image: "a.png".to_string(),
+            image_size: None,
+        };
+        let mut map = TileMapData::new_infinite(1);
+        map.set(0, 1, -1, Some(tile("a", 0, Orientation::IDENTITY)));
+        let text = encode_map_tiled_json(&map, (16, 16), &[info]).unwrap();
+        let doc: serde_json::Value = serde_json::from_str(&text).unwrap();
+
+        // chunk 原点贴近 i32::MAX 时，列偏移会溢出：应报错而不是 panic。
+        let mut far = doc.clone();
+        far["layers"][0]["chunks"][0]["x"] = i32::MAX.into();
+        assert!(decode_map_tiled_json(&far.to_string()).is_err());
+
+        // 数据长度与 chunk 尺寸不符。
+        let mut short = doc;
+        short["layers"][0]["chunks"][0]["data"] = vec![1].into();
+        assert!(decode_map_tiled_json(&short.to_string()).is_err());
+    }
+
+    #[test]
+    fn tiled_tileset_file_wangsets_decode() {
+        let text = r##"{
+            "name": "terrain", "tilewidth": 16, "tileheight": 16,
+            "wangsets": [{
+                "name": "水岸", "type": "corner", "tile": -1,
+                "colors": [{ "name": "水", "color": "#0000ff", "tile": 3, "probability": 0.25 }],
+                "wangtiles": [{ "tileid": 3, "wangid": [0, 1, 0, 1, 0, 1, 0, 1] }]
+            }]
+        }"##;
+        let sets = decode_tiled_tileset_wangsets(text).unwrap();
+        assert_eq!(sets.len(), 1);
+        assert_eq!(sets[0].kind, WangKind::Corner);
+        assert_eq!(sets[0].tile, None);
+        assert_eq!(sets[0].colors[0].tile, Some(3));
+        assert_eq!(sets[0].wang_id(3), Some([0, 1, 0, 1, 0, 1, 0, 1]));
+
+        assert!(decode_tiled_tileset_wangsets(&text.replace("\"corner\"", "\"hex\"")).is_err());
+        assert!(decode_tiled_tileset_wangsets(&text.replace("0, 1, 0, 1, 0, 1, 0, 1", "0, 1")).is_err());
+    }
+}
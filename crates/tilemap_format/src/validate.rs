@@ -0,0 +1,287 @@
+//! 地图完整性检查：图层元数据、tileset 条目、图片状态、tile index 范围、内容 hash。
+//!
+//! 本模块不做 IO：图片状态由调用方通过 `probe` 提供（CLI 直接读文件，编辑器用已加载的纹理）。
+
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+
+use tilemap_core::{TileMapData, TilesetId};
+
+use crate::TilesetEntry;
+
+/// 调用方对单个 tileset 图片的探测结果。
+#[derive(Clone, Debug, PartialEq)]
+pub enum AssetProbe {
+    /// 图片可用：网格容量（columns * rows）；`hash` 为文件内容的 blake3（hex），未计算时为 None。
+    Ready { capacity: u32, hash: Option<String> },
+    /// 仍在加载（编辑器异步加载中），本次跳过容量检查。
+    Pending,
+    Missing { path: String },
+    Unreadable { path: String, error: String },
+}
+
+/// 问题所在格子（用于定位）。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CellPos {
+    pub layer: u32,
+    pub x: i32,
+    pub y: i32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum MapProblem {
+    LayerDataMismatch { layer_data: usize, layers: u32 },
+    DuplicateTileset { id: TilesetId },
+    /// 地图引用了 tileset 列表里没有的 id。
+    UnlistedTileset { id: TilesetId, cells: usize, first: CellPos },
+    /// 占位条目（asset_path 为空）；`first` 为 None 表示没有格子使用。
+    PlaceholderTileset { id: TilesetId, cells: usize, first: Option<CellPos> },
+    MissingAsset { id: TilesetId, path: String, cells: usize, first: Option<CellPos> },
+    UnreadableAsset { id: TilesetId, path: String, error: String, cells: usize, first: Option<CellPos> },
+    IndexOutOfRange { id: TilesetId, cells: usize, capacity: u32, first: CellPos, first_index: u32 },
+    /// 图片内容与 id（内容 hash）不一致：图片被替换过，或条目指向了别的文件。
+    HashMismatch { id: TilesetId, path: String, actual: String },
+}
+
+impl MapProblem {
+    pub fn tileset_id(&self) -> Option<&str> {
+        match self {
+            MapProblem::LayerDataMismatch { .. } => None,
+            MapProblem::DuplicateTileset { id }
+            | MapProblem::UnlistedTileset { id, .. }
+            | MapProblem::PlaceholderTileset { id, .. }
+            | MapProblem::MissingAsset { id, .. }
+            | MapProblem::UnreadableAsset { id, .. }
+            | MapProblem::IndexOutOfRange { id, .. }
+            | MapProblem::HashMismatch { id, .. } => Some(id),
+        }
+    }
+
+    /// 第一个受影响的格子（没有格子受影响时为 None）。
+    pub fn location(&self) -> Option<CellPos> {
+        match self {
+            MapProblem::UnlistedTileset { first, .. } | MapProblem::IndexOutOfRange { first, .. } => {
+                Some(*first)
+            }
+            MapProblem::PlaceholderTileset { first, .. }
+            | MapProblem::MissingAsset { first, .. }
+            | MapProblem::UnreadableAsset { first, .. } => *first,
+            _ => None,
+        }
+    }
+}
+
+fn affected(f: &mut fmt::Formatter<'_>, cells: usize) -> fmt::Result {
+    if cells == 0 {
+        write!(f, "（未被使用）")
+    } else {
+        write!(f, "（{cells} 个格子受影响）")
+    }
+}
+
+impl fmt::Display for MapProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapProblem::LayerDataMismatch { layer_data, layers } => {
+                write!(f, "layer_data 有 {layer_data} 项，但 layers = {layers}")
+            }
+            MapProblem::DuplicateTileset { id } => write!(f, "tileset {id} 在列表中出现多次"),
+            MapProblem::UnlistedTileset { id, cells, .. } => {
+                write!(f, "地图引用的 tileset {id} 不在 tileset 列表中")?;
+                affected(f, *cells)
+            }
+            MapProblem::PlaceholderTileset { id, cells, .. } => {
+                write!(f, "tileset {id} 没有 asset_path（占位条目）")?;
+                affected(f, *cells)
+            }
+            MapProblem::MissingAsset { id, path, cells, .. } => {
+                write!(f, "tileset {id} 的图片不存在: {path}")?;
+                affected(f, *cells)
+            }
+            MapProblem::UnreadableAsset { id, path, error, cells, .. } => {
+                write!(f, "tileset {id} 的图片 {path} 无法读取: {error}")?;
+                affected(f, *cells)
+            }
+            MapProblem::IndexOutOfRange { id, cells, capacity, first, first_index } => write!(
+                f,
+                "tileset {id} 有 {cells} 个格子的 tile index 超出图集范围 {capacity}（例如 层 {} ({}, {}) index {first_index}）",
+                first.layer, first.x, first.y
+            ),
+            MapProblem::HashMismatch { id, path, actual } => {
+                write!(f, "tileset {id} 的图片 {path} 内容已变化（实际 hash {actual}）")
+            }
+        }
+    }
+}
+
+/// tileset id 是否为内容 hash（编辑器导入时用 blake3 hex 作为 id）；旧数据的手写 id 不做 hash 校验。
+pub fn is_content_hash_id(id: &str) -> bool {
+    id.len() == 64 && id.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+#[derive(Default)]
+struct Usage {
+    cells: usize,
+    first: Option<CellPos>,
+    bad: usize,
+    first_bad: Option<(CellPos, u32)>,
+}
+
+/// 返回发现的问题（空 = 通过）。每个 tileset 的图片只探测一次。
+pub fn validate_map(
+    map: &TileMapData,
+    tilesets: &[TilesetEntry],
+    mut probe: impl FnMut(&TilesetEntry) -> AssetProbe,
+) -> Vec<MapProblem> {
+    let mut problems = Vec::new();
+
+    if map.layer_data.len() != map.layers as usize {
+        problems.push(MapProblem::LayerDataMismatch {
+            layer_data: map.layer_data.len(),
+            layers: map.layers,
+        });
+    }
+
+    let mut seen = HashSet::new();
+    for t in tilesets {
+        if !seen.insert(t.id.as_str()) {
+            problems.push(MapProblem::DuplicateTileset { id: t.id.clone() });
+        }
+    }
+
+    let probes: BTreeMap<&str, AssetProbe> = tilesets
+        .iter()
+        .filter(|t| !t.asset_path.is_empty())
+        .map(|t| (t.id.as_str(), probe(t)))
+        .collect();
+
+    let mut usage: BTreeMap<&str, Usage> = BTreeMap::new();
+    for (layer, x, y, tile) in map.iter_tiles() {
+        let pos = CellPos { layer, x, y };
+        let u = usage.entry(tile.tileset_id.as_str()).or_default();
+        u.cells += 1;
+        u.first.get_or_insert(pos);
+        if let Some(AssetProbe::Ready { capacity, .. }) = probes.get(tile.tileset_id.as_str())
+            && tile.index >= *capacity
+        {
+            u.bad += 1;
+            u.first_bad.get_or_insert((pos, tile.index));
+        }
+    }
+
+    for (&id, u) in &usage {
+        if !seen.contains(id) {
+            problems.push(MapProblem::UnlistedTileset {
+                id: id.to_string(),
+                cells: u.cells,
+                first: u.first.unwrap_or(CellPos { layer: 0, x: 0, y: 0 }),
+            });
+        }
+    }
+
+    // 列表里有、但没有格子使用的 tileset 同样检查图片，方便提前发现拷贝遗漏。
+    let mut reported = HashSet::new();
+    for t in tilesets {
+        if !reported.insert(t.id.as_str()) {
+            continue;
+        }
+        let (cells, first) = usage
+            .get(t.id.as_str())
+            .map(|u| (u.cells, u.first))
+            .unwrap_or((0, None));
+        let id = t.id.clone();
+        let path = t.asset_path.clone();
+        match probes.get(t.id.as_str()) {
+            None => problems.push(MapProblem::PlaceholderTileset { id, cells, first }),
+            Some(AssetProbe::Pending) => {}
+            Some(AssetProbe::Missing { path }) => problems.push(MapProblem::MissingAsset {
+                id,
+                path: path.clone(),
+                cells,
+                first,
+            }),
+            Some(AssetProbe::Unreadable { path, error }) => problems.push(MapProblem::UnreadableAsset {
+                id,
+                path: path.clone(),
+                error: error.clone(),
+                cells,
+                first,
+            }),
+            Some(AssetProbe::Ready { capacity, hash }) => {
+                if let Some(hash) = hash
+                    && is_content_hash_id(&t.id)
+                    && *hash != t.id
+                {
+                    problems.push(MapProblem::HashMismatch {
+                        id: id.clone(),
+                        path,
+                        actual: hash.clone(),
+                    });
+                }
+                if let Some(Usage { bad, first_bad: Some((first, first_index)), .. }) =
+                    usage.get(t.id.as_str())
+                {
+                    problems.push(MapProblem::IndexOutOfRange {
+                        id,
+                        cells: *bad,
+                        capacity: *capacity,
+                        first: *first,
+                        first_index: *first_index,
+                    });
+                }
+            }
+        }
+    }
+
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tilemap_core::Orientation;
+
+    use crate::tests::{entry, tile};
+
+    #[test]
+    fn validate_reports_assets_indices_and_hashes() {
+        let hash_a = "a".repeat(64);
+        let mut tilesets = vec![entry(&hash_a), entry("b"), entry("c")];
+        tilesets[2].asset_path.clear();
+        let mut map = TileMapData::new_with_layers(4, 4, 2);
+        map.set(0, 1, 1, Some(tile(&hash_a, 3, Orientation::IDENTITY)));
+        map.set(1, 2, 3, Some(tile(&hash_a, 9, Orientation::IDENTITY)));
+        map.set(0, 0, 0, Some(tile("b", 0, Orientation::IDENTITY)));
+        map.set(0, 3, 3, Some(tile("gone", 0, Orientation::IDENTITY)));
+        map.layer_data.pop();
+
+        let problems = validate_map(&map, &tilesets, |t| match t.id.as_str() {
+            "b" => AssetProbe::Missing { path: t.asset_path.clone() },
+            _ => AssetProbe::Ready { capacity: 4, hash: Some("f".repeat(64)) },
+        });
+        assert!(problems.contains(&MapProblem::LayerDataMismatch { layer_data: 1, layers: 2 }));
+        assert!(problems.contains(&MapProblem::IndexOutOfRange {
+            id: hash_a.clone(),
+            cells: 1,
+            capacity: 4,
+            first: CellPos { layer: 1, x: 2, y: 3 },
+            first_index: 9,
+        }));
+        let kinds: Vec<(Option<&str>, Option<CellPos>)> =
+            problems.iter().map(|p| (p.tileset_id(), p.location())).collect();
+        assert!(kinds.contains(&(Some("gone"), Some(CellPos { layer: 0, x: 3, y: 3 }))));
+        assert!(kinds.contains(&(Some("b"), Some(CellPos { layer: 0, x: 0, y: 0 }))));
+        assert!(kinds.contains(&(Some("c"), None)));
+        assert!(problems.iter().any(|p| matches!(p, MapProblem::HashMismatch { id, .. } if *id == hash_a)));
+        assert_eq!(problems.len(), 6);
+
+        // 图片正常、hash 一致时通过；加载中的 tileset 不报越界。
+        let problems = validate_map(&map, &tilesets[..1], |_| AssetProbe::Ready {
+            capacity: 16,
+            hash: Some(hash_a.clone()),
+        });
+        assert_eq!(problems.len(), 3, "{problems:?}");
+        let problems = validate_map(&map, &tilesets[..1], |_| AssetProbe::Pending);
+        assert!(!problems.iter().any(|p| matches!(p, MapProblem::IndexOutOfRange { .. })));
+    }
+}
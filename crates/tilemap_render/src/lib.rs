@@ -0,0 +1,439 @@
+//! CPU 合成：把 `TileMapData` 的可见图层按 tileset 图集逐格贴到一张 RGBA 图上。
+//!
+//! 地图格子 y 向上，输出图片 y 向下；图块朝向在图像坐标系内直接按 `Orientation` 变换像素。
+//! 每个 tileset 按自己的 `TileGrid` 切分，tile 与网格尺寸不同时像 Tiled 一样左下角对齐。
+//! 图集由调用方加载（编辑器、CLI、测试各自决定从哪里读图）。
+
+#![forbid(unsafe_code)]
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+
+use image::imageops::{self, FilterType};
+use image::{Rgba, RgbaImage};
+use tilemap_core::{
+    blob_mask, rm_a2_quarters, TileBounds, TileGrid, TileMapData, TilesetId, AUTOTILE_SHEET_COLUMNS, AUTOTILE_VARIANTS,
+};
+
+/// 输出图片的像素上限，避免超大地图一次性分配过多内存。
+const MAX_PIXELS: u64 = 1 << 28;
+
+/// 一张 tileset 图集及其切分规则。
+#[derive(Clone, Debug)]
+pub struct Atlas {
+    pub image: RgbaImage,
+    pub grid: TileGrid,
+}
+
+#[derive(Clone, Debug)]
+pub struct RenderOptions {
+    /// 地图网格尺寸（像素）；tile 尺寸不同的 tileset 按左下角对齐到格子。
+    pub tile_size: (u32, u32),
+    /// 输出缩放倍数（> 0）；放大用最近邻，缩小用线性插值。
+    pub scale: f32,
+    /// 网格线颜色；`None` 表示不画网格。线宽固定 1 像素，在缩放之后绘制。
+    pub grid: Option<Rgba<u8>>,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            tile_size: (32, 32),
+            scale: 1.0,
+            grid: None,
+        }
+    }
+}
+
+/// 渲染结果与过程中的警告（缺图集、index 越界等，不中断渲染）。
+pub struct Rendered {
+    pub image: RgbaImage,
+    pub warnings: Vec<String>,
+}
+
+/// 要渲染的格子范围：固定地图为整张图，无限地图为已用范围。
+pub fn render_bounds(map: &TileMapData) -> Result<TileBounds, String> {
+    match map.fixed_size() {
+        Some((w, h)) if w > 0 && h > 0 => {
+            Ok(TileBounds::from_corners(0, 0, w as i32 - 1, h as i32 - 1))
+        }
+        Some(_) => Err("地图尺寸为 0，无法渲染".to_string()),
+        None => map
+            .used_bounds()
+            .ok_or_else(|| "无限地图为空，无法渲染".to_string()),
+    }
+}
+
+/// 合成地图；`atlases` 中缺少的 tileset 只产生警告，对应格子留空。
+pub fn render_map(
+    map: &TileMapData,
+    atlases: &HashMap<TilesetId, Atlas>,
+    opts: &RenderOptions,
+) -> Result<Rendered, String> {
+    let (tw, th) = opts.tile_size;
+    if tw == 0 || th == 0 {
+        return Err("tile 尺寸不能为 0".to_string());
+    }
+    if !(opts.scale.is_finite() && opts.scale > 0.0) {
+        return Err(format!("缩放倍数必须大于 0，实际为 {}", opts.scale));
+    }
+    let bounds = render_bounds(map)?;
+    let (px_w, px_h) = (bounds.width() as u64 * tw as u64, bounds.height() as u64 * th as u64);
+    let (out_w, out_h) = (scaled(px_w, opts.scale), scaled(px_h, opts.scale));
+    if px_w * px_h > MAX_PIXELS || out_w * out_h > MAX_PIXELS {
+        return Err(format!("输出图片过大：{}x{} 像素", px_w.max(out_w), px_h.max(out_h)));
+    }
+
+    let mut image = RgbaImage::new(px_w as u32, px_h as u32);
+    let mut missing: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut out_of_range = 0usize;
+
+    for layer in 0..map.layers {
+        let meta = map.layer_data.get(layer as usize);
+        let visible = meta.map(|d| d.visible).unwrap_or(true);
+        let opacity = meta.map(|d| d.opacity.clamp(0.0, 1.0)).unwrap_or(1.0);
+        if !visible || opacity <= 0.0 {
+            continue;
+        }
+        // 与 Tiled 的 right-down 顺序一致：尺寸大于网格的 tile 会盖住上一行。
+        let mut tiles = map.tiles_in_rect(layer, bounds);
+        tiles.sort_by_key(|&(x, y, _)| (Reverse(y), x));
+        for (x, y, tile) in tiles {
+            let Some(atlas) = atlases.get(tile.tileset_id) else {
+                *missing.entry(tile.tileset_id.as_str()).or_default() += 1;
+                continue;
+            };
+            let grid = atlas.grid;
+            let columns = grid.columns(atlas.image.width());
+            if tile.index >= grid.capacity(atlas.image.width(), atlas.image.height()) {
+                out_of_range += 1;
+                continue;
+            }
+            let (src_x, src_y) = grid.tile_origin(tile.index, columns);
+            let (gw, gh) = grid.tile_size();
+            let orientation = tile.orientation();
+            // 左下角对齐格子左下角：比网格大的 tile 向上、向右伸出。
+            let (_, out_h) = orientation.dims(gw, gh);
+            let dst_x = (x - bounds.min_x) as i64 * tw as i64;
+            let dst_y = (bounds.max_y - y + 1) as i64 * th as i64 - out_h as i64;
+            for py in 0..gh {
+                for px in 0..gw {
+                    let Some((ox, oy)) = orientation.transform_cell(px, py, gw, gh) else {
+                        continue;
+                    };
+                    let (ix, iy) = (dst_x + ox as i64, dst_y + oy as i64);
+                    if ix < 0 || iy < 0 || ix >= image.width() as i64 || iy >= image.height() as i64 {
+                        continue;
+                    }
+                    let mut src = *atlas.image.get_pixel(src_x + px, src_y + py);
+                    if opacity < 1.0 {
+                        src[3] = (src[3] as f32 * opacity).round() as u8;
+                    }
+                    blend_over(image.get_pixel_mut(ix as u32, iy as u32), src);
+                }
+            }
+        }
+    }
+
+    if (out_w, out_h) != (px_w, px_h) {
+        // 放大保持像素风格；缩小（缩略图）需要插值，否则细节会闪烁丢失。
+        let filter = if opts.scale >= 1.0 { FilterType::Nearest } else { FilterType::Triangle };
+        image = imageops::resize(&image, out_w as u32, out_h as u32, filter);
+    }
+    if let Some(color) = opts.grid {
+        draw_grid(&mut image, bounds, opts, color);
+    }
+
+    let mut warnings = Vec::new();
+    for (id, count) in missing {
+        warnings.push(format!("tileset {id} 没有可用的图集，跳过 {count} 个格子"));
+    }
+    if out_of_range > 0 {
+        warnings.push(format!("{out_of_range} 个格子的 tile index 超出图集范围，已跳过"));
+    }
+    Ok(Rendered { image, warnings })
+}
+
+/// 把 RPG Maker A2 的 2x3 自动图块块（左上角为图集第 `block` 个 tile）烘焙成 47 块 blob 图集：
+/// 8 列、按变体顺序排列、无留白，配合 `AutotileDef { first: 0, .. }` 使用。
+pub fn bake_rm_a2_autotile(atlas: &Atlas, block: u32) -> Result<RgbaImage, String> {
+    let grid = atlas.grid;
+    let (tw, th) = grid.tile_size();
+    if tw == 0 || th == 0 || tw % 2 != 0 || th % 2 != 0 {
+        return Err(format!("A2 自动图块要求 tile 尺寸为偶数，实际为 {tw}x{th}"));
+    }
+    let (w, h) = atlas.image.dimensions();
+    let columns = grid.columns(w);
+    let (col, row) = (block % columns.max(1), block / columns.max(1));
+    if columns == 0 || col + 2 > columns || row + 3 > grid.rows(h) {
+        return Err(format!("第 {block} 个 tile 起的 2x3 区域超出图集范围"));
+    }
+
+    let (hw, hh) = (tw / 2, th / 2);
+    // 块内第 (qc, qr) 个半 tile 的左上角；按所在 tile 取原点，兼容 margin / spacing。
+    let half_origin = |qc: u32, qr: u32| {
+        let index = block + (qr / 2) * columns + qc / 2;
+        let (x, y) = grid.tile_origin(index, columns);
+        (x + (qc % 2) * hw, y + (qr % 2) * hh)
+    };
+    let rows = AUTOTILE_VARIANTS.div_ceil(AUTOTILE_SHEET_COLUMNS);
+    let mut out = RgbaImage::new(AUTOTILE_SHEET_COLUMNS * tw, rows * th);
+    for variant in 0..AUTOTILE_VARIANTS {
+        let Some(mask) = blob_mask(variant) else {
+            continue;
+        };
+        let dst_x = (variant % AUTOTILE_SHEET_COLUMNS) * tw;
+        let dst_y = (variant / AUTOTILE_SHEET_COLUMNS) * th;
+        for (i, [qc, qr]) in rm_a2_quarters(mask).into_iter().enumerate() {
+            let (sx, sy) = half_origin(qc, qr);
+            let (qx, qy) = (i as u32 % 2, i as u32 / 2);
+            let part = imageops::crop_imm(&atlas.image, sx, sy, hw, hh).to_image();
+            imageops::replace(&mut out, &part, (dst_x + qx * hw) as i64, (dst_y + qy * hh) as i64);
+        }
+    }
+    Ok(out)
+}
+
+fn scaled(px: u64, scale: f32) -> u64 {
+    ((px as f64 * scale as f64).round() as u64).max(1)
+}
+
+/// 在每个格子边界画 1 像素线（含外框），位置按缩放后的格子尺寸取整。
+fn draw_grid(image: &mut RgbaImage, bounds: TileBounds, opts: &RenderOptions, color: Rgba<u8>) {
+    let (w, h) = image.dimensions();
+    let line = |i: u32, tile: u32, limit: u32| -> u32 {
+        let at = (i as f64 * tile as f64 * opts.scale as f64).round() as u32;
+        at.min(limit - 1)
+    };
+    let mut xs: Vec<u32> = (0..=bounds.width()).map(|i| line(i, opts.tile_size.0, w)).collect();
+    let mut ys: Vec<u32> = (0..=bounds.height()).map(|i| line(i, opts.tile_size.1, h)).collect();
+    xs.dedup();
+    ys.dedup();
+    for &x in &xs {
+        for y in 0..h {
+            blend_over(image.get_pixel_mut(x, y), color);
+        }
+    }
+    for &y in &ys {
+        for x in 0..w {
+            // 交点已被竖线画过，避免半透明网格在交点处叠加两次。
+            if xs.binary_search(&x).is_ok() {
+                continue;
+            }
+            blend_over(image.get_pixel_mut(x, y), color);
+        }
+    }
+}
+
+/// 非预乘 alpha 的 source-over 混合。
+fn blend_over(dst: &mut Rgba<u8>, src: Rgba<u8>) {
+    let sa = src[3] as u32;
+    if sa == 0 {
+        return;
+    }
+    if sa == 255 {
+        *dst = src;
+        return;
+    }
+    let da = dst[3] as u32;
+    // out_a = sa + da * (1 - sa)，按 255 定点计算。
+    let out_a = sa * 255 + da * (255 - sa);
+    if out_a == 0 {
+        *dst = Rgba([0, 0, 0, 0]);
+        return;
+    }
+    for c in 0..3 {
+        let v = (src[c] as u32 * sa * 255 + dst[c] as u32 * da * (255 - sa)) / out_a;
+        dst[c] = v as u8;
+    }
+    dst[3] = (out_a / 255) as u8;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tilemap_core::{blob_variant, Orientation, TileRef, AUTOTILE_N, AUTOTILE_S};
+
+    const RED: Rgba<u8> = Rgba([255, 0, 0, 255]);
+    const BLUE: Rgba<u8> = Rgba([0, 0, 255, 255]);
+
+    /// 2x1 个 2x2 的 tile：index 0 左上角红、其余透明；index 1 全蓝。
+    fn atlases() -> HashMap<TilesetId, Atlas> {
+        let mut image = RgbaImage::new(4, 2);
+        image.put_pixel(0, 0, RED);
+        for y in 0..2 {
+            for x in 2..4 {
+                image.put_pixel(x, y, BLUE);
+            }
+        }
+        let atlas = Atlas {
+            image,
+            grid: TileGrid::new(2, 2),
+        };
+        HashMap::from([("t".to_string(), atlas)])
+    }
+
+    fn tile(index: u32, orientation: Orientation) -> Option<TileRef> {
+        tile_in("t", index, orientation)
+    }
+
+    fn tile_in(tileset: &str, index: u32, orientation: Orientation) -> Option<TileRef> {
+        let mut t = TileRef {
+            tileset_id: tileset.to_string(),
+            index,
+            rot: 0,
+            flip_x: false,
+            flip_y: false,
+        };
+        t.set_orientation(orientation);
+        Some(t)
+    }
+
+    fn opts() -> RenderOptions {
+        RenderOptions {
+            tile_size: (2, 2),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn rows_are_top_down_and_orientation_applies() {
+        let mut map = TileMapData::new_with_layers(2, 2, 1);
+        map.set(0, 0, 1, tile(0, Orientation::IDENTITY));
+        map.set(0, 1, 0, tile(0, Orientation::FLIP_X));
+        let out = render_map(&map, &atlases(), &opts()).unwrap();
+        assert!(out.warnings.is_empty());
+        assert_eq!(out.image.dimensions(), (4, 4));
+        // (0, 1) 在图片上方一行；(1, 0) 水平翻转后红点落到右上角。
+        assert_eq!(*out.image.get_pixel(0, 0), RED);
+        assert_eq!(*out.image.get_pixel(3, 2), RED);
+        assert_eq!(out.image.get_pixel(2, 2)[3], 0);
+    }
+
+    #[test]
+    fn hidden_layers_skip_and_opacity_blends() {
+        let mut map = TileMapData::new_with_layers(1, 1, 3);
+        map.set(0, 0, 0, tile(0, Orientation::IDENTITY));
+        map.set(1, 0, 0, tile(1, Orientation::IDENTITY));
+        map.layer_data[1].opacity = 0.5;
+        map.set(2, 0, 0, tile(1, Orientation::IDENTITY));
+        map.layer_data[2].visible = false;
+        let out = render_map(&map, &atlases(), &opts()).unwrap();
+        let p = *out.image.get_pixel(0, 0);
+        assert_eq!(p[3], 255);
+        assert!((126..=129).contains(&p[0]) && (126..=129).contains(&p[2]), "{p:?}");
+        assert_eq!(*out.image.get_pixel(1, 1), Rgba([0, 0, 255, 128]));
+    }
+
+    #[test]
+    fn scale_and_grid() {
+        let mut map = TileMapData::new_with_layers(2, 1, 1);
+        map.set(0, 1, 0, tile(1, Orientation::IDENTITY));
+        let green = Rgba([0, 255, 0, 255]);
+        let out = render_map(
+            &map,
+            &atlases(),
+            &RenderOptions {
+                scale: 2.0,
+                grid: Some(green),
+                ..opts()
+            },
+        )
+        .unwrap();
+        assert_eq!(out.image.dimensions(), (8, 4));
+        assert_eq!(*out.image.get_pixel(5, 1), BLUE);
+        for (x, y) in [(0, 1), (4, 1), (7, 1), (2, 0), (2, 3)] {
+            assert_eq!(*out.image.get_pixel(x, y), green, "({x}, {y})");
+        }
+
+        let thumb = render_map(&map, &atlases(), &RenderOptions { scale: 0.5, ..opts() }).unwrap();
+        assert_eq!(thumb.image.dimensions(), (2, 1));
+        assert!(render_map(&map, &atlases(), &RenderOptions { scale: 0.0, ..opts() }).is_err());
+    }
+
+    #[test]
+    fn tile_sizes_differing_from_grid_anchor_bottom_left() {
+        // "big"：1px margin 内的一个 4x4 蓝色 tile；"dot"：1px spacing 分隔的两个 1x1 tile，第二个为红色。
+        let mut big = RgbaImage::from_pixel(6, 6, RED);
+        for y in 1..5 {
+            for x in 1..5 {
+                big.put_pixel(x, y, BLUE);
+            }
+        }
+        let mut dot = RgbaImage::new(3, 1);
+        dot.put_pixel(2, 0, RED);
+        let mut atlases = atlases();
+        atlases.insert(
+            "big".to_string(),
+            Atlas {
+                image: big,
+                grid: TileGrid { tile_width: 4, tile_height: 4, margin: 1, spacing: 0 },
+            },
+        );
+        atlases.insert(
+            "dot".to_string(),
+            Atlas {
+                image: dot,
+                grid: TileGrid { tile_width: 1, tile_height: 1, margin: 0, spacing: 1 },
+            },
+        );
+
+        let mut map = TileMapData::new_with_layers(2, 2, 2);
+        map.set(0, 0, 0, tile_in("big", 0, Orientation::IDENTITY));
+        map.set(1, 1, 1, tile_in("dot", 1, Orientation::IDENTITY));
+        let out = render_map(&map, &atlases, &opts()).unwrap();
+        assert!(out.warnings.is_empty(), "{:?}", out.warnings);
+        // 大 tile 从左下格子向上、向右铺满 4x4；小 tile 落在右上格子的左下像素。
+        assert_eq!(*out.image.get_pixel(0, 0), BLUE);
+        assert_eq!(*out.image.get_pixel(3, 3), BLUE);
+        assert_eq!(*out.image.get_pixel(2, 1), RED);
+        assert_eq!(*out.image.get_pixel(3, 0), BLUE);
+    }
+
+    #[test]
+    fn missing_atlas_and_bad_index_warn() {
+        let mut map = TileMapData::new_with_layers(2, 1, 1);
+        map.set(0, 0, 0, tile(7, Orientation::IDENTITY));
+        map.set(
+            0,
+            1,
+            0,
+            Some(TileRef {
+                tileset_id: "gone".to_string(),
+                index: 0,
+                rot: 0,
+                flip_x: false,
+                flip_y: false,
+            }),
+        );
+        let out = render_map(&map, &atlases(), &opts()).unwrap();
+        assert_eq!(out.warnings.len(), 2);
+        assert!(out.warnings[0].contains("gone"));
+    }
+
+    #[test]
+    fn rm_a2_block_bakes_into_blob_sheet() {
+        // 2x2 的 tile：每个半 tile（1 像素）的红色通道记录它在块内的 列 + 行 * 4。
+        let mut image = RgbaImage::new(6, 6);
+        for qr in 0..6 {
+            for qc in 0..4 {
+                image.put_pixel(qc + 2, qr, Rgba([(qc + qr * 4) as u8, 0, 0, 255]));
+            }
+        }
+        let atlas = Atlas { image, grid: TileGrid::new(2, 2) };
+        assert!(bake_rm_a2_autotile(&atlas, 2).is_err());
+        let sheet = bake_rm_a2_autotile(&atlas, 1).unwrap();
+        assert_eq!(sheet.dimensions(), (16, 12));
+
+        let quarter = |variant: u32, i: u32| {
+            let (x, y) = ((variant % 8) * 2 + i % 2, (variant / 8) * 2 + i / 2);
+            sheet.get_pixel(x, y)[0] as u32
+        };
+        // 孤立块 = 预览 tile；竖直通道两侧是左右边。
+        let isolated = blob_variant(0);
+        assert_eq!([0, 1, 2, 3].map(|i| quarter(isolated, i)), [0, 1, 4, 5]);
+        let column = blob_variant(AUTOTILE_N | AUTOTILE_S);
+        assert_eq!([0, 1, 2, 3].map(|i| quarter(column, i)), [16, 19, 12, 15]);
+        assert_eq!(sheet.get_pixel(15, 11)[3], 0);
+    }
+}